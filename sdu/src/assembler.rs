@@ -99,6 +99,10 @@ pub fn assembler_command(command: AssemblerCommand) -> Result<()> {
             let source_errors =
                 hir::lower::lower_program::accumulated::<SourceDiagnosticAccumulator>(db, program);
 
+            // warnings are printed too, but don't fail the compilation
+            let has_errors = source_errors.iter().any(|e| e.is_error())
+                || hir_errors.iter().any(|e| e.is_error());
+
             let mut ariadne_errors = Vec::new();
             ariadne_errors.extend(source_errors.into_iter().map(|e| e.into_ariadne(db)));
             ariadne_errors.extend(hir_errors.into_iter().map(|e| e.into_ariadne(db)));
@@ -109,6 +113,8 @@ pub fn assembler_command(command: AssemblerCommand) -> Result<()> {
                 for error in ariadne_errors {
                     error.eprint(&mut cache).context("Failed to print error")?;
                 }
+            }
+            if has_errors {
                 return Err(anyhow::anyhow!("Compilation failed"));
             }
 
@@ -4,7 +4,10 @@ use anyhow::{Context, Result};
 use bytes::Bytes;
 use itertools::Itertools;
 use shin_core::{
-    format::scenario::instruction_elements::CodeAddress,
+    format::scenario::{
+        Scenario,
+        scenes::{group_scenes, write_scenes},
+    },
     vm::command::{CommandResult, RuntimeCommand},
 };
 
@@ -50,6 +53,13 @@ pub enum ScenarioCommand {
         scenario_path: PathBuf,
         output_filename: Option<PathBuf>,
     },
+    /// Disassemble a scenario, grouping the instructions by the scenes and messages they belong to
+    ///
+    /// NOTE: the format of the output is not stable yet
+    Scenes {
+        scenario_path: PathBuf,
+        output_filename: Option<PathBuf>,
+    },
 }
 
 fn make_output(output_filename: Option<PathBuf>) -> Result<Box<dyn std::io::Write>> {
@@ -263,29 +273,26 @@ fn dump_info(path: PathBuf, output_filename: Option<PathBuf>) -> Result<()> {
 fn disassemble(path: PathBuf, output_filename: Option<PathBuf>) -> Result<()> {
     let scenario = std::fs::read(path)?;
     let scenario = Bytes::from(scenario);
-    let scenario = shin_core::format::scenario::Scenario::new(scenario)?;
+    let scenario = Scenario::new(scenario)?;
 
     let mut output = make_output(output_filename)?;
 
-    let entry = scenario.entrypoint_address();
-    let mut reader = scenario.instruction_reader(entry);
-
-    let mut end_position = scenario.raw().len();
-    // scenario file is aligned to 0x10 bytes, so there are some zeros at the end
-    // trim them
-    while end_position > 0 && scenario.raw()[end_position - 1] == 0 {
-        end_position -= 1;
+    for (position, instruction) in scenario.instructions()? {
+        writeln!(output, "{:08x?} {:?}", position.0, instruction)?;
     }
-    let end_position = CodeAddress(end_position as u32);
 
-    while reader.position() < end_position {
-        let position = reader.position();
+    Ok(())
+}
+
+fn scenes(path: PathBuf, output_filename: Option<PathBuf>) -> Result<()> {
+    let scenario = std::fs::read(path)?;
+    let scenario = Bytes::from(scenario);
+    let scenario = Scenario::new(scenario)?;
 
-        let instruction = reader
-            .read()
-            .with_context(|| format!("Reading instruction at {}", position))?;
-        writeln!(output, "{:08x?} {:?}", position.0, instruction)?;
-    }
+    let mut output = make_output(output_filename)?;
+
+    let items = group_scenes(scenario.instructions()?);
+    write_scenes(&mut output, scenario.info_tables(), &items)?;
 
     Ok(())
 }
@@ -314,5 +321,9 @@ pub fn scenario_command(command: ScenarioCommand) -> Result<()> {
             scenario_path,
             output_filename,
         } => disassemble(scenario_path, output_filename),
+        ScenarioCommand::Scenes {
+            scenario_path,
+            output_filename,
+        } => scenes(scenario_path, output_filename),
     }
 }
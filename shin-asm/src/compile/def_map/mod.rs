@@ -279,7 +279,7 @@ def $b = $a
 
         expect![[r#"
             building def map produced errors:
-            source-level: [Diagnostic { message: "Encountered a loop while resolving register $b", location: Span(WithFile { value: 10..12, file: File(Id { value: 1 }) }), additional_labels: [] }]
+            source-level: [Diagnostic { message: "Encountered a loop while resolving register $b", location: Span(WithFile { value: 10..12, file: File(Id { value: 1 }) }), additional_labels: [], severity: Error }]
             hir-level: []"#]]
             .assert_eq(errors.as_deref().unwrap());
 
@@ -305,7 +305,7 @@ def A = 65536 * 65536
 
         expect![[r#"
             building def map produced errors:
-            source-level: [Diagnostic { message: "Overflow in constant expression", location: Span(WithFile { value: 9..22, file: File(Id { value: 1 }) }), additional_labels: [] }]
+            source-level: [Diagnostic { message: "Overflow in constant expression", location: Span(WithFile { value: 9..22, file: File(Id { value: 1 }) }), additional_labels: [], severity: Error }]
             hir-level: []"#]]
         .assert_eq(errors.as_deref().unwrap());
 
@@ -98,11 +98,19 @@ impl DiagnosticLocation for HirLocation {
     }
 }
 
+/// How bad a diagnostic is. Only errors prevent the program from being compiled
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
 #[derive(Debug, Clone)]
 pub struct Diagnostic<L> {
     pub message: String,
     pub location: L,
     pub additional_labels: Vec<(String, L)>,
+    pub severity: Severity,
 }
 
 impl<L> Diagnostic<L> {
@@ -111,9 +119,21 @@ impl<L> Diagnostic<L> {
             message,
             location,
             additional_labels: Vec::new(),
+            severity: Severity::Error,
+        }
+    }
+
+    pub fn warning(message: String, location: L) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::new(message, location)
         }
     }
 
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
     pub fn with_additional_label(mut self, message: String, location: L) -> Self {
         self.additional_labels.push((message, location));
         self
@@ -128,6 +148,7 @@ impl<L> Diagnostic<L> {
                 .into_iter()
                 .map(|(m, l)| (m, f(l)))
                 .collect(),
+            severity: self.severity,
         }
     }
 }
@@ -188,7 +209,12 @@ fn lower_diagnostic_into_ariadne<L: DiagnosticLocation>(
 ) -> ariadne::Report<'static, CharSpan> {
     let span = diagnostic.location.span(db).to_char_span(db);
 
-    ariadne::Report::build(ariadne::ReportKind::Error, *span.source(), span.start())
+    let kind = match diagnostic.severity {
+        Severity::Error => ariadne::ReportKind::Error,
+        Severity::Warning => ariadne::ReportKind::Warning,
+    };
+
+    ariadne::Report::build(kind, *span.source(), span.start())
         .with_message(diagnostic.message)
         .with_label(ariadne::Label::new(span))
         .with_labels(
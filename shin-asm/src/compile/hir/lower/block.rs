@@ -395,4 +395,30 @@ mod tests {
             "#]],
         );
     }
+
+    #[test]
+    pub fn check_out_of_range_volume() {
+        // only the typed parameters are checked, `abs` takes a plain number
+        check_from_hir(
+            indoc! {r#"
+                BGMPLAY 0, 0, 0, 2.0
+                abs $v1, 2000
+            "#},
+            expect![[r#"
+                Diagnostics:
+                Warning: Expected a volume in range 0..=1, but got 2. It will be clamped
+                   ╭─[test.sal:1:18]
+                   │
+                 1 │ BGMPLAY 0, 0, 0, 2.0
+                   │                  ───  
+                   │                        
+                ───╯
+
+                instructions:
+                  Command(BGMPLAY(BGMPLAY { bgm_data_id: 0, fade_in_time: 0, no_repeat: 0, volume: 2000 }))
+                  uo(UnaryOperation { ty: Abs, destination: $v1, source: 2000 })
+                code addresses:
+            "#]],
+        );
+    }
 }
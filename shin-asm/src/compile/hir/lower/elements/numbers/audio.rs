@@ -0,0 +1,113 @@
+use std::ops::RangeInclusive;
+
+use shin_core::{
+    format::scenario::instruction_elements::{NumberSpec, UntypedNumberSpec},
+    vm::command::types::{MaskParam, Pan, Volume},
+};
+
+use super::{super::prelude::*, try_number_spec};
+use crate::compile::hir::lower::LowerResult;
+
+/// Lowers a fixed-point audio parameter, warning if a constant is outside of `range`
+///
+/// The engine divides these by 1000, so a `0.5` rational literal lowers to `500`. Out of range values are silently clamped at runtime, which is most likely not what the script author wanted.
+fn audio_number_spec<T>(
+    collectors: &mut FromHirCollectors,
+    ctx: &FromHirBlockCtx,
+    expr: ExprId,
+    expected: &str,
+    range: RangeInclusive<i32>,
+) -> LowerResult<NumberSpec<T>> {
+    let Some(number) = try_number_spec::<T>(collectors, ctx, expr)? else {
+        return collectors.emit_unexpected_type(ctx, expected, expr);
+    };
+
+    if let UntypedNumberSpec::Constant(value) = number.into_untyped() {
+        if !range.contains(&value) {
+            collectors.emit_warning(
+                expr.into(),
+                format!(
+                    "Expected {} in range {}..={}, but got {}. It will be clamped",
+                    expected,
+                    *range.start() as f32 / 1000.0,
+                    *range.end() as f32 / 1000.0,
+                    value as f32 / 1000.0
+                ),
+            );
+        }
+    }
+
+    Ok(number)
+}
+
+impl FromHirExpr for NumberSpec<Volume> {
+    fn from_hir_expr(
+        collectors: &mut FromHirCollectors,
+        ctx: &FromHirBlockCtx,
+        expr: ExprId,
+    ) -> LowerResult<Self> {
+        audio_number_spec(collectors, ctx, expr, "a volume", 0..=1000)
+    }
+}
+
+impl FromHirExpr for NumberSpec<Pan> {
+    fn from_hir_expr(
+        collectors: &mut FromHirCollectors,
+        ctx: &FromHirBlockCtx,
+        expr: ExprId,
+    ) -> LowerResult<Self> {
+        audio_number_spec(collectors, ctx, expr, "a pan", -1000..=1000)
+    }
+}
+
+impl FromHirExpr for NumberSpec<MaskParam> {
+    fn from_hir_expr(
+        collectors: &mut FromHirCollectors,
+        ctx: &FromHirBlockCtx,
+        expr: ExprId,
+    ) -> LowerResult<Self> {
+        // 0 is a special value meaning 1.0
+        audio_number_spec(collectors, ctx, expr, "a mask parameter", 0..=1000)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shin_core::{
+        format::scenario::instruction_elements::{FromNumber, NumberSpec, UntypedNumberSpec},
+        vm::command::types::{Pan, Volume},
+    };
+
+    use super::super::super::check_from_hir_ok;
+
+    fn constant<T>(value: i32) -> NumberSpec<T> {
+        NumberSpec::new(UntypedNumberSpec::Constant(value))
+    }
+
+    #[test]
+    fn volume_from_hir() {
+        check_from_hir_ok::<NumberSpec<Volume>>(
+            "HELLO 0.5, 1.0, 0, $v0",
+            &[
+                constant(500),
+                constant(1000),
+                constant(0),
+                NumberSpec::new(UntypedNumberSpec::Register("$v0".parse().unwrap())),
+            ],
+        );
+
+        // the lowered constant resolves to the volume that was written
+        assert_eq!(Volume::from_number(500), Volume(0.5));
+    }
+
+    #[test]
+    fn pan_from_hir() {
+        check_from_hir_ok::<NumberSpec<Pan>>(
+            "HELLO -1.0, 0.25, 1.0",
+            &[constant(-1000), constant(250), constant(1000)],
+        );
+
+        assert_eq!(Pan::from_number(-1000), Pan(-1.0));
+        assert_eq!(Pan::from_number(250), Pan(0.25));
+    }
+}
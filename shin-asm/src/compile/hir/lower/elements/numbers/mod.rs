@@ -1,4 +1,6 @@
+mod audio;
 mod messagebox_style;
+mod plain;
 mod ticks;
 
 use shin_core::format::scenario::instruction_elements::{NumberSpec, UntypedNumberSpec};
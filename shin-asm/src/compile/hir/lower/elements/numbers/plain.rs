@@ -0,0 +1,31 @@
+use shin_core::format::scenario::{info::BgmId, instruction_elements::NumberSpec};
+
+use super::{super::prelude::*, try_number_spec};
+use crate::compile::hir::lower::LowerResult;
+
+/// Implements lowering for the typed numbers that don't have any symbolic names (yet), accepting a number or a register
+macro_rules! plain_number_spec {
+    ($($ty:ty => $expected:literal),* $(,)?) => {
+        $(
+            impl FromHirExpr for NumberSpec<$ty> {
+                fn from_hir_expr(
+                    collectors: &mut FromHirCollectors,
+                    ctx: &FromHirBlockCtx,
+                    expr: ExprId,
+                ) -> LowerResult<Self> {
+                    if let Some(number) = try_number_spec(collectors, ctx, expr)? {
+                        Ok(number)
+                    } else {
+                        collectors.emit_unexpected_type(ctx, $expected, expr)
+                    }
+                }
+            }
+        )*
+    };
+}
+
+// TODO: BGM ids should be referenced by name, taken from the scenario info tables
+plain_number_spec!(
+    bool => "a boolean",
+    BgmId => "a BGM id",
+);
@@ -16,6 +16,16 @@ impl<L: StringLengthDesc, F: StringFixup + 'static> FromHirExpr for SJisString<L
             return collectors.emit_unexpected_type(ctx, "a string", expr);
         };
 
+        if let Some(c) = F::find_unencodable_char(s) {
+            return collectors.emit_diagnostic(
+                expr.into(),
+                format!(
+                    "The string contains a char that can't be encoded in it: `{}` (U+{:04X})",
+                    c, c as u32
+                ),
+            );
+        }
+
         Ok(SJisString::new(s.to_string()))
     }
 }
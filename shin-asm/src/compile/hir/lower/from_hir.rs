@@ -30,6 +30,11 @@ impl HirDiagnosticCollector {
         self.diagnostics.push(HirDiagnostic::new(message, location));
     }
 
+    pub fn emit_warning(&mut self, location: HirLocation, message: String) {
+        self.diagnostics
+            .push(HirDiagnostic::warning(message, location));
+    }
+
     pub fn is_empty(&self) -> bool {
         self.diagnostics.is_empty()
     }
@@ -63,6 +68,11 @@ impl<'a> HirDiagnosticCollectorWithFile<'a> {
     pub fn emit(&mut self, location: HirIdWithBlock, message: String) {
         self.diagnostics.emit(location.in_file(self.file), message);
     }
+
+    pub fn emit_warning(&mut self, location: HirIdWithBlock, message: String) {
+        self.diagnostics
+            .emit_warning(location.in_file(self.file), message);
+    }
 }
 
 pub struct HirDiagnosticCollectorWithBlock<'a> {
@@ -82,6 +92,11 @@ impl<'a> HirDiagnosticCollectorWithBlock<'a> {
         self.diagnostics
             .emit(HirIdWithBlock::new(location, self.block), message);
     }
+
+    pub fn emit_warning(&mut self, location: HirId, message: String) {
+        self.diagnostics
+            .emit_warning(HirIdWithBlock::new(location, self.block), message);
+    }
 }
 
 pub struct CodeAddressCollector {
@@ -121,6 +136,12 @@ impl FromHirCollectors<'_, '_, '_> {
         Err(LowerError)
     }
 
+    /// Emit a warning about a suspicious, but valid, element. Unlike [`Self::emit_diagnostic`] this doesn't fail the lowering
+    #[inline]
+    pub fn emit_warning(&mut self, location: HirId, message: String) {
+        self.diagnostics.emit_warning(location, message);
+    }
+
     #[inline]
     pub fn emit_unexpected_type<T>(
         &mut self,
@@ -2,13 +2,16 @@
 
 use shin_core::{
     format::{
-        scenario::instruction_elements::{MessageId, NumberSpec, U8Bool},
+        scenario::{
+            info::BgmId,
+            instruction_elements::{MessageId, NumberSpec, U8Bool},
+        },
         text::U16FixupString,
     },
     time::Ticks,
     vm::command::{
-        compiletime::{EXIT, MSGINIT, MSGSET, WAIT},
-        types::MessageboxStyle,
+        compiletime::{BGMPLAY, EXIT, MSGINIT, MSGSET, WAIT},
+        types::{MessageboxStyle, Volume},
         CompiletimeCommand,
     },
 };
@@ -46,10 +49,27 @@ fn MSGSET((text,): (U16FixupString,)) -> CompiletimeCommand {
     })
 }
 
+fn BGMPLAY(
+    (bgm_data_id, fade_in_time, no_repeat, volume): (
+        NumberSpec<BgmId>,
+        NumberSpec<Ticks>,
+        NumberSpec<bool>,
+        NumberSpec<Volume>,
+    ),
+) -> CompiletimeCommand {
+    CompiletimeCommand::BGMPLAY(BGMPLAY {
+        bgm_data_id,
+        fade_in_time,
+        no_repeat,
+        volume,
+    })
+}
+
 pub fn commands(builder: RouterBuilder<impl Router>) -> RouterBuilder<impl Router> {
     builder
         .add("EXIT", EXIT)
         .add("WAIT", WAIT)
         .add("MSGINIT", MSGINIT)
         .add("MSGSET", MSGSET)
+        .add("BGMPLAY", BGMPLAY)
 }
@@ -23,7 +23,9 @@ impl AudioHandle {
         )
     }
 
-    #[allow(unused)] // TODO: use it for lip-sync
+    /// The loudness of the sound as it is heard (with the volume applied), for lip sync.
+    ///
+    /// This is the peak level of the recent output, between 0.0 and 1.0 for the sounds that don't clip.
     pub fn get_amplitude(&self) -> f32 {
         f32::from_bits(
             self.shared
@@ -57,6 +59,24 @@ impl AudioHandle {
             .map_err(|_| anyhow!("Command queue full"))
     }
 
+    /// Pauses or resumes the playback.
+    ///
+    /// While paused, the sound is silent and its position does not advance.
+    pub fn set_paused(&mut self, paused: bool) -> anyhow::Result<()> {
+        self.command_producer
+            .try_push(Command::SetPaused(paused))
+            .map_err(|_| anyhow!("Command queue full"))
+    }
+
+    /// Sets how fast the sound is played, 1.0 being the normal speed.
+    ///
+    /// The pitch changes along with the speed.
+    pub fn set_playback_rate(&mut self, rate: f32) -> anyhow::Result<()> {
+        self.command_producer
+            .try_push(Command::SetPlaybackRate(rate))
+            .map_err(|_| anyhow!("Command queue full"))
+    }
+
     /// Returns the current playback position of the sound.
     pub fn position(&self) -> Ticks {
         Ticks::from_millis(
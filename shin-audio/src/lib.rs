@@ -3,6 +3,7 @@
 mod data;
 mod handle;
 mod manager;
+mod offline;
 mod resampler;
 mod sound;
 
@@ -10,6 +11,7 @@ pub use data::AudioData;
 pub use handle::AudioHandle;
 use kira::track::TrackId;
 pub use manager::AudioManager;
+pub use offline::{OfflineAudioClock, TestClock, frames_to_pcm_s16};
 pub use shin_core::format::audio::AudioFile;
 use shin_core::{
     time::Tween,
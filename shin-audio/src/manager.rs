@@ -1,10 +1,36 @@
-use kira::{manager::AudioManagerSettings, sound::SoundData};
+use anyhow::{Result, bail};
+use kira::{
+    Frame,
+    manager::{
+        AudioManagerSettings,
+        backend::{
+            cpal::CpalBackend,
+            mock::{MockBackend, MockBackendSettings},
+        },
+    },
+    sound::SoundData,
+    track::{TrackBuilder, TrackHandle},
+};
 use parking_lot::Mutex;
 
-type Backend = kira::manager::backend::cpal::CpalBackend;
+enum Manager {
+    /// Plays the audio on a real audio device
+    Device(kira::manager::AudioManager<CpalBackend>),
+    /// Doesn't touch any audio devices, the audio is rendered on demand with [`AudioManager::render_offline`]
+    Offline(kira::manager::AudioManager<MockBackend>),
+}
+
+macro_rules! dispatch {
+    ($manager:expr, $m:ident => $body:expr) => {
+        match $manager {
+            Manager::Device($m) => $body,
+            Manager::Offline($m) => $body,
+        }
+    };
+}
 
 pub struct AudioManager {
-    manager: Mutex<kira::manager::AudioManager<Backend>>,
+    manager: Mutex<Manager>,
 }
 
 impl AudioManager {
@@ -14,7 +40,23 @@ impl AudioManager {
             .expect("Failed to create kira audio manager");
 
         Self {
-            manager: Mutex::new(manager),
+            manager: Mutex::new(Manager::Device(manager)),
+        }
+    }
+
+    /// Creates an audio manager that doesn't output to an audio device
+    ///
+    /// Instead, the audio has to be rendered explicitly with [`AudioManager::render_offline`], which is fully deterministic.
+    /// This is useful for exporting the gameplay as a video.
+    pub fn new_offline(sample_rate: u32) -> Self {
+        let manager = kira::manager::AudioManager::<MockBackend>::new(AudioManagerSettings {
+            backend_settings: MockBackendSettings { sample_rate },
+            ..Default::default()
+        })
+        .expect("Failed to create kira audio manager");
+
+        Self {
+            manager: Mutex::new(Manager::Offline(manager)),
         }
     }
 
@@ -24,10 +66,37 @@ impl AudioManager {
     {
         let mut manager = self.manager.lock();
 
-        manager.play(data).expect("Failed to start playing audio")
+        dispatch!(&mut *manager, manager => {
+            manager.play(data).expect("Failed to start playing audio")
+        })
     }
 
-    pub fn kira_manager(&self) -> &Mutex<kira::manager::AudioManager<Backend>> {
-        &self.manager
+    pub fn add_sub_track(&self, builder: TrackBuilder) -> TrackHandle {
+        let mut manager = self.manager.lock();
+
+        dispatch!(&mut *manager, manager => {
+            manager.add_sub_track(builder).expect("Failed to create a track")
+        })
+    }
+
+    pub fn is_offline(&self) -> bool {
+        matches!(*self.manager.lock(), Manager::Offline(_))
+    }
+
+    /// Renders the next `frame_count` audio frames (stereo samples) of the offline audio manager
+    ///
+    /// Commands issued to the sounds (starting, stopping, volume changes, etc.) are applied at the start of the rendered chunk.
+    ///
+    /// Fails if the manager is outputting to an audio device.
+    pub fn render_offline(&self, frame_count: usize) -> Result<Vec<Frame>> {
+        let mut manager = self.manager.lock();
+        let Manager::Offline(manager) = &mut *manager else {
+            bail!("Offline rendering requested from an audio manager outputting to a device");
+        };
+
+        let backend = manager.backend_mut();
+        backend.on_start_processing();
+
+        Ok((0..frame_count).map(|_| backend.process()).collect())
     }
 }
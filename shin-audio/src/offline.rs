@@ -0,0 +1,240 @@
+//! Deterministic audio rendering, synchronized to video frames.
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use kira::Frame;
+
+use crate::AudioManager;
+
+/// Paces the offline audio rendering to the video frames
+///
+/// The number of audio frames to render is computed from the total number of video frames rendered so far,
+/// so the rounding errors don't accumulate: after `n` video frames, exactly `floor(n * sample_rate / fps)` audio frames are rendered.
+#[derive(Debug, Clone)]
+pub struct OfflineAudioClock {
+    sample_rate: u32,
+    fps: u32,
+    video_frame: u64,
+}
+
+impl OfflineAudioClock {
+    pub fn new(sample_rate: u32, fps: u32) -> Self {
+        assert!(fps > 0, "fps must be positive");
+
+        Self {
+            sample_rate,
+            fps,
+            video_frame: 0,
+        }
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Number of video frames rendered so far
+    pub fn video_frame(&self) -> u64 {
+        self.video_frame
+    }
+
+    /// Total number of audio frames corresponding to the first `video_frames` video frames
+    pub fn audio_frames_until(&self, video_frames: u64) -> u64 {
+        video_frames * self.sample_rate as u64 / self.fps as u64
+    }
+
+    /// Advances the clock by a single video frame, returning the number of audio frames to render for it
+    pub fn advance(&mut self) -> usize {
+        let start = self.audio_frames_until(self.video_frame);
+        self.video_frame += 1;
+        let end = self.audio_frames_until(self.video_frame);
+
+        (end - start) as usize
+    }
+
+    /// Renders the audio for the next video frame
+    pub fn render_video_frame(&mut self, audio_manager: &AudioManager) -> Result<Vec<Frame>> {
+        let frame_count = self.advance();
+        audio_manager.render_offline(frame_count)
+    }
+}
+
+/// Drives an offline [`AudioManager`] in the tests, so that the audio progresses in lockstep with the simulated frames
+///
+/// Running a frame renders (and discards) exactly one 60 fps video frame worth of audio, independent of the wall clock.
+pub struct TestClock {
+    audio_manager: Arc<AudioManager>,
+    clock: OfflineAudioClock,
+}
+
+impl TestClock {
+    pub const FPS: u32 = 60;
+
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            audio_manager: Arc::new(AudioManager::new_offline(sample_rate)),
+            clock: OfflineAudioClock::new(sample_rate, Self::FPS),
+        }
+    }
+
+    pub fn audio_manager(&self) -> &Arc<AudioManager> {
+        &self.audio_manager
+    }
+
+    /// Number of frames run so far
+    pub fn frame(&self) -> u64 {
+        self.clock.video_frame()
+    }
+
+    /// Renders the audio of the next `count` frames, returning it
+    pub fn run_frames(&mut self, count: u32) -> Result<Vec<Frame>> {
+        let mut frames = Vec::new();
+        for _ in 0..count {
+            frames.extend(self.clock.render_video_frame(&self.audio_manager)?);
+        }
+        Ok(frames)
+    }
+}
+
+/// Converts the rendered audio frames to interleaved signed 16-bit stereo PCM, suitable for muxing
+pub fn frames_to_pcm_s16(frames: &[Frame]) -> Vec<i16> {
+    frames
+        .iter()
+        .flat_map(|frame| [frame.left, frame.right])
+        .map(|sample| (sample.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use kira::track::TrackId;
+    use shin_core::{
+        format::audio::{AudioBuffer, AudioFrameSource},
+        time::Tween,
+        vm::command::types::{Pan, Volume},
+    };
+
+    use super::{OfflineAudioClock, TestClock, frames_to_pcm_s16};
+    use crate::{AudioData, AudioManager, AudioSettings};
+
+    const SAMPLE_RATE: u32 = 44100;
+
+    /// A constant stereo signal, split into fixed-size frames
+    struct ConstantSource {
+        value: (f32, f32),
+        length: u32,
+        position: u32,
+    }
+
+    impl ConstantSource {
+        const FRAME_SIZE: u32 = 1024;
+    }
+
+    impl AudioFrameSource for ConstantSource {
+        fn max_frame_size(&self) -> usize {
+            Self::FRAME_SIZE as usize
+        }
+
+        fn sample_rate(&self) -> u32 {
+            SAMPLE_RATE
+        }
+
+        fn pre_skip(&self) -> u32 {
+            0
+        }
+
+        fn pre_roll(&self) -> u32 {
+            0
+        }
+
+        fn read_frame(&mut self, destination: &mut AudioBuffer) -> bool {
+            destination.clear();
+            let count = Self::FRAME_SIZE.min(self.length - self.position);
+            if count == 0 {
+                return false;
+            }
+            destination.extend((0..count).map(|_| self.value));
+            self.position += count;
+            true
+        }
+
+        fn samples_seek(&mut self, sample_position: u32) -> anyhow::Result<u32> {
+            let frame_start = sample_position / Self::FRAME_SIZE * Self::FRAME_SIZE;
+            self.position = frame_start;
+            Ok(sample_position - frame_start)
+        }
+
+        fn current_sample_position(&self) -> u32 {
+            self.position
+        }
+    }
+
+    #[test]
+    fn video_frames_match_duration() {
+        // 44100 / 24 is not an integer, so the chunks have different sizes
+        let mut clock = OfflineAudioClock::new(SAMPLE_RATE, 24);
+        let chunks = (0..24).map(|_| clock.advance()).collect::<Vec<_>>();
+
+        assert!(chunks.iter().all(|&c| c == 1837 || c == 1838));
+        assert_eq!(chunks.iter().sum::<usize>(), SAMPLE_RATE as usize);
+        assert_eq!(clock.video_frame(), 24);
+    }
+
+    #[test]
+    fn render_one_second() {
+        let mut clock = TestClock::new(SAMPLE_RATE);
+        clock.audio_manager().play(AudioData {
+            source: ConstantSource {
+                value: (0.5, -0.5),
+                length: SAMPLE_RATE * 2,
+                position: 0,
+            },
+            settings: AudioSettings {
+                track: TrackId::Main,
+                fade_in: Tween::IMMEDIATE,
+                loop_start: None,
+                volume: Volume::default(),
+                pan: Pan::default(),
+            },
+        });
+
+        let frames = clock.run_frames(TestClock::FPS).unwrap();
+
+        assert_eq!(clock.frame(), TestClock::FPS as u64);
+        assert_eq!(frames.len(), SAMPLE_RATE as usize);
+        // the sound is actually rendered, not just silence
+        assert!(frames.iter().any(|frame| frame.left > 0.1));
+        assert!(frames.iter().any(|frame| frame.right < -0.1));
+
+        let pcm = frames_to_pcm_s16(&frames);
+        assert_eq!(pcm.len(), SAMPLE_RATE as usize * 2);
+    }
+
+    #[test]
+    fn amplitude_follows_the_output() {
+        let mut clock = TestClock::new(SAMPLE_RATE);
+        let handle = clock.audio_manager().play(AudioData {
+            source: ConstantSource {
+                value: (0.5, -0.5),
+                length: SAMPLE_RATE / 2,
+                position: 0,
+            },
+            settings: AudioSettings {
+                track: TrackId::Main,
+                fade_in: Tween::IMMEDIATE,
+                loop_start: None,
+                volume: Volume(0.5),
+                pan: Pan::default(),
+            },
+        });
+        assert_eq!(handle.get_amplitude(), 0.0);
+
+        // the volume is applied
+        clock.run_frames(10).unwrap();
+        assert!((handle.get_amplitude() - 0.25).abs() < 0.01);
+
+        // and the sound going quiet closes the mouths
+        clock.run_frames(TestClock::FPS).unwrap();
+        assert_eq!(handle.get_amplitude(), 0.0);
+    }
+}
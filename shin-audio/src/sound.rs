@@ -22,11 +22,16 @@ use crate::{resampler::Resampler, AudioData};
 
 pub const COMMAND_BUFFER_CAPACITY: usize = 8;
 
+/// Time for the amplitude to fall off to ~37% after the sound gets quiet
+const AMPLITUDE_RELEASE_SECONDS: f32 = 0.1;
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Command {
     SetVolume(Volume, Tween),
     SetPanning(Pan, Tween),
     Stop(Tween),
+    SetPaused(bool),
+    SetPlaybackRate(f32),
 }
 
 pub(crate) struct Shared {
@@ -34,7 +39,7 @@ pub(crate) struct Shared {
     // TODO: use it to implement BGMSYNC (I don't know which unit it uses)
     // in ms, relative to the start of the sound
     pub position: AtomicU32,
-    // used for lip sync
+    // used for lip sync, see `AudioSound::amplitude`
     pub amplitude: AtomicU32,
 }
 
@@ -117,6 +122,12 @@ pub struct AudioSound<S: AudioFrameSource + Send> {
     command_consumer: HeapCons<Command>,
     shared: Arc<Shared>,
     state: PlaybackState,
+    /// A paused sound outputs silence, keeping its position and tweens where they were
+    paused: bool,
+    /// How fast the sound is played, changing its pitch along with the speed
+    playback_rate: f32,
+    /// Peak level of the output, following the rises immediately and falling off over [`AMPLITUDE_RELEASE_SECONDS`]
+    amplitude: f32,
     volume: Tweener,
     panning: Tweener,
     volume_fade: Tweener,
@@ -137,6 +148,9 @@ impl<S: AudioFrameSource + Send> AudioSound<S> {
             command_consumer,
             shared,
             state: PlaybackState::Playing,
+            paused: false,
+            playback_rate: 1.0,
+            amplitude: 0.0,
             volume: Tweener::new(data.settings.volume.0),
             panning: Tweener::new(data.settings.pan.0),
             volume_fade,
@@ -198,6 +212,8 @@ impl<S: AudioFrameSource + Send> Sound for AudioSound<S> {
                 Command::SetVolume(volume, tween) => self.volume.enqueue_now(volume.0, tween),
                 Command::SetPanning(panning, tween) => self.panning.enqueue_now(panning.0, tween),
                 Command::Stop(tween) => self.stop(tween),
+                Command::SetPaused(paused) => self.paused = paused,
+                Command::SetPlaybackRate(rate) => self.playback_rate = rate,
             }
         }
 
@@ -205,7 +221,10 @@ impl<S: AudioFrameSource + Send> Sound for AudioSound<S> {
             self.wait_status().bits(),
             std::sync::atomic::Ordering::SeqCst,
         );
-        // TODO: compute the amplitude
+        self.shared.amplitude.store(
+            self.amplitude.to_bits(),
+            std::sync::atomic::Ordering::SeqCst,
+        );
         let position = self.sample_provider.source.current_samples_position() as u64 * 1000
             / self.sample_provider.source.sample_rate() as u64;
         self.shared.position.store(
@@ -220,6 +239,11 @@ impl<S: AudioFrameSource + Send> Sound for AudioSound<S> {
         _clock_info_provider: &ClockInfoProvider,
         _modulator_value_provider: &ModulatorValueProvider,
     ) -> Frame {
+        if self.paused {
+            self.amplitude = 0.0;
+            return Frame::ZERO;
+        }
+
         let dt_ticks = Ticks::from_seconds(dt as f32);
 
         // update tweeners
@@ -231,7 +255,7 @@ impl<S: AudioFrameSource + Send> Sound for AudioSound<S> {
             self.state = PlaybackState::Stopped
         }
 
-        let mut f = self.sample_provider.next(dt);
+        let mut f = self.sample_provider.next(dt * self.playback_rate as f64);
 
         if self.sample_provider.reached_eof && self.sample_provider.resampler.outputting_silence() {
             self.state = PlaybackState::Stopped;
@@ -245,6 +269,13 @@ impl<S: AudioFrameSource + Send> Sound for AudioSound<S> {
             f = Frame::new(f.left * (1.0 - pan).sqrt(), f.right * pan.sqrt()) * SQRT_2
         }
 
+        let release = (-dt as f32 / AMPLITUDE_RELEASE_SECONDS).exp();
+        self.amplitude = f
+            .left
+            .abs()
+            .max(f.right.abs())
+            .max(self.amplitude * release);
+
         f
     }
 
@@ -261,6 +292,10 @@ impl<S: AudioFrameSource + Send> Sound for AudioSound<S> {
                 self.wait_status().bits(),
                 std::sync::atomic::Ordering::SeqCst,
             );
+            // and a stopped voice doesn't leave the mouths open
+            self.shared
+                .amplitude
+                .store(0.0f32.to_bits(), std::sync::atomic::Ordering::SeqCst);
         }
 
         result
@@ -0,0 +1,237 @@
+//! Support for sprite animations, played by the animation layer.
+//!
+//! Like the [sprite sheets](super::sprite_sheet), this is our own format, not the one of the original game.
+//! The file consists of a header with the list of the frames, followed by the sprite sheet the frames are taken from.
+//!
+//! All the numbers are little-endian:
+//!
+//! | Size     | Field                                                      |
+//! |----------|------------------------------------------------------------|
+//! | 4        | magic, `ANIM`                                              |
+//! | 4        | version, always 1                                          |
+//! | 4        | number of frames, at least 1                               |
+//! | variable | the frames                                                 |
+//! | variable | the sprite sheet, see [`read_sprite_sheet`]                |
+//!
+//! Each frame is:
+//!
+//! | Size     | Field                                                      |
+//! |----------|------------------------------------------------------------|
+//! | variable | name of the sprite in the sheet, zero-terminated Shift-JIS |
+//! | 4        | duration in ticks, not 0                                   |
+//! | 2        | x offset of the sprite center from the layer origin        |
+//! | 2        | y offset                                                   |
+
+use std::io;
+
+use anyhow::{Result, bail};
+use binrw::{BinRead, BinWrite};
+
+use crate::format::{
+    sprite_sheet::{SpriteSheet, read_sprite_sheet, write_sprite_sheet},
+    text::ZeroString,
+};
+
+#[derive(BinRead, BinWrite, Debug)]
+#[brw(little, magic = b"ANIM")]
+struct AnimationHeader {
+    #[br(assert(version == 1))]
+    #[bw(assert(*version == 1))]
+    version: u32,
+    count: u32,
+
+    #[br(count = count)]
+    frames: Vec<AnimationFrameEntry>,
+}
+
+#[derive(BinRead, BinWrite, Debug)]
+struct AnimationFrameEntry {
+    sprite: ZeroString,
+    duration: u32,
+    offset_x: i16,
+    offset_y: i16,
+}
+
+/// A single keyframe of an animation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnimationFrame {
+    /// Name of the sprite shown, in the sheet of the animation
+    pub sprite: String,
+    /// How long the frame is shown, in ticks
+    pub duration: u32,
+    /// Offset of the sprite center from the layer origin, in pixels
+    pub offset_x: i16,
+    pub offset_y: i16,
+}
+
+pub struct Animation {
+    pub frames: Vec<AnimationFrame>,
+    pub sheet: SpriteSheet,
+}
+
+pub fn read_animation(source: &[u8]) -> Result<Animation> {
+    let mut source = io::Cursor::new(source);
+    let header = AnimationHeader::read(&mut source)?;
+    let sheet = read_sprite_sheet(&source.get_ref()[source.position() as usize..])?;
+
+    if header.frames.is_empty() {
+        bail!("Animation has no frames");
+    }
+
+    let frames = header
+        .frames
+        .into_iter()
+        .map(
+            |AnimationFrameEntry {
+                 sprite,
+                 duration,
+                 offset_x,
+                 offset_y,
+             }| {
+                if duration == 0 {
+                    bail!("Animation frame {:?} has zero duration", sprite.0);
+                }
+                if sheet.get_sprite(&sprite.0).is_none() {
+                    bail!("Animation frame {:?} is not in the sprite sheet", sprite.0);
+                }
+
+                Ok(AnimationFrame {
+                    sprite: sprite.0,
+                    duration,
+                    offset_x,
+                    offset_y,
+                })
+            },
+        )
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(Animation { frames, sheet })
+}
+
+pub fn write_animation<W: io::Write + io::Seek>(animation: &Animation, dest: &mut W) -> Result<()> {
+    let frames = animation
+        .frames
+        .iter()
+        .map(|frame| AnimationFrameEntry {
+            sprite: ZeroString::new(frame.sprite.clone()),
+            duration: frame.duration,
+            offset_x: frame.offset_x,
+            offset_y: frame.offset_y,
+        })
+        .collect::<Vec<_>>();
+
+    let header = AnimationHeader {
+        version: 1,
+        count: frames.len().try_into()?,
+        frames,
+    };
+
+    header.write(dest)?;
+    write_sprite_sheet(&animation.sheet, dest)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, io::Cursor};
+
+    use image::RgbaImage;
+
+    use super::{Animation, AnimationFrame, read_animation, write_animation};
+    use crate::format::sprite_sheet::{SpriteRect, SpriteSheet, write_sprite_sheet};
+
+    fn frame(sprite: &str, duration: u32) -> AnimationFrame {
+        AnimationFrame {
+            sprite: sprite.to_string(),
+            duration,
+            offset_x: -3,
+            offset_y: 7,
+        }
+    }
+
+    fn animation(frames: Vec<AnimationFrame>) -> Animation {
+        let sprites = ["a", "b"]
+            .into_iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let rect = SpriteRect {
+                    x: i as u16 * 2,
+                    y: 0,
+                    width: 2,
+                    height: 2,
+                };
+                (name.to_string(), rect)
+            })
+            .collect::<HashMap<_, _>>();
+
+        Animation {
+            frames,
+            sheet: SpriteSheet {
+                texture: RgbaImage::new(4, 2),
+                sprites,
+            },
+        }
+    }
+
+    fn encode_sheet(sheet: &SpriteSheet) -> Vec<u8> {
+        let mut encoded = Cursor::new(Vec::new());
+        write_sprite_sheet(sheet, &mut encoded).unwrap();
+        encoded.into_inner()
+    }
+
+    fn encode(animation: &Animation) -> Vec<u8> {
+        let mut encoded = Cursor::new(Vec::new());
+        write_animation(animation, &mut encoded).unwrap();
+        encoded.into_inner()
+    }
+
+    #[test]
+    fn round_trip() {
+        let animation = animation(vec![frame("a", 4), frame("b", 2), frame("a", 1)]);
+        let decoded = read_animation(&encode(&animation)).unwrap();
+
+        assert_eq!(decoded.frames, animation.frames);
+        assert_eq!(decoded.sheet.sprites, animation.sheet.sprites);
+    }
+
+    #[test]
+    fn layout() {
+        let mut data = b"ANIM".to_vec();
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(&2u32.to_le_bytes());
+        for (name, duration, offset_x, offset_y) in [(b"b\0", 5u32, 1i16, -2i16), (b"a\0", 3, 0, 0)]
+        {
+            data.extend_from_slice(name);
+            data.extend_from_slice(&duration.to_le_bytes());
+            data.extend_from_slice(&offset_x.to_le_bytes());
+            data.extend_from_slice(&offset_y.to_le_bytes());
+        }
+        data.extend_from_slice(&encode_sheet(&animation(vec![]).sheet));
+
+        let decoded = read_animation(&data).unwrap();
+        assert_eq!(decoded.frames, [
+            AnimationFrame {
+                sprite: "b".to_string(),
+                duration: 5,
+                offset_x: 1,
+                offset_y: -2,
+            },
+            AnimationFrame {
+                sprite: "a".to_string(),
+                duration: 3,
+                offset_x: 0,
+                offset_y: 0,
+            },
+        ]);
+        // and it's also what gets written
+        assert_eq!(encode(&decoded), data);
+    }
+
+    #[test]
+    fn rejects_invalid_frames() {
+        assert!(read_animation(&encode(&animation(vec![]))).is_err());
+        assert!(read_animation(&encode(&animation(vec![frame("c", 4)]))).is_err());
+        assert!(read_animation(&encode(&animation(vec![frame("a", 0)]))).is_err());
+    }
+}
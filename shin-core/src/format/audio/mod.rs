@@ -137,7 +137,7 @@ impl<F: AsRef<AudioFile>> AudioDecoder<F> {
             match info.channel_count {
                 1 => Channels::Mono,
                 2 => Channels::Stereo,
-                _ => panic!("Unsupported channel count"),
+                channels => bail!("Unsupported channel count: {}", channels),
             },
         )?;
         let buffer =
@@ -71,6 +71,10 @@ pub struct GlyphInfo {
 }
 
 impl GlyphInfo {
+    pub fn advance_width_f32(&self) -> f32 {
+        self.advance_width as f32
+    }
+
     pub fn actual_size(&self) -> (u32, u32) {
         (self.actual_width as u32, self.actual_height as u32)
     }
@@ -136,6 +140,13 @@ pub enum GlyphMipLevel {
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct GlyphId(pub u32);
 
+impl GlyphId {
+    /// The glyph of the `U+0000` character
+    ///
+    /// The fonts map all the unused characters to the same placeholder glyph, and `U+0000` is the first of them, so it gets the first id.
+    pub const NOTDEF: GlyphId = GlyphId(0);
+}
+
 enum GlyphData {
     Raw(Vec<u8>),
     Compressed(Vec<u8>),
@@ -268,8 +279,44 @@ impl BinRead for Glyph {
     }
 }
 
+/// Bounding box of the glyph bitmap (w/o padding), relative to the pen position on the baseline
+///
+/// The Y axis points down, like in the engine
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct GlyphBoundingBox {
+    pub left: i32,
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+}
+
+/// The metrics accessors only read the glyph header, so they never decompress the glyph bitmap
 pub trait GlyphTrait: for<'a> BinRead<Args<'a> = ()> {
     fn get_info(&self) -> GlyphInfo;
+
+    /// Amount of horizontal pen movements after drawing the glyph
+    fn advance_width(&self) -> u8 {
+        self.get_info().advance_width
+    }
+
+    /// Distance between the pen position and the top left of the glyph bitmap, as in the font metrics (Y pointing up)
+    fn bearing(&self) -> (i8, i8) {
+        let info = self.get_info();
+        (info.bearing_x, info.bearing_y)
+    }
+
+    fn bounding_box(&self) -> GlyphBoundingBox {
+        let info = self.get_info();
+        let left = info.bearing_x as i32;
+        let top = -(info.bearing_y as i32);
+
+        GlyphBoundingBox {
+            left,
+            top,
+            right: left + info.actual_width as i32,
+            bottom: top + info.actual_height as i32,
+        }
+    }
 }
 impl GlyphTrait for Glyph {
     fn get_info(&self) -> GlyphInfo {
@@ -333,6 +380,18 @@ impl<G: GlyphTrait> Font<G> {
         self.glyphs.get(&self.characters[character as usize])
     }
 
+    /// Get the glyph id for a codepoint, returning `None` for the codepoints beyond the character table
+    pub fn try_get_glyph_id(&self, codepoint: u32) -> Option<GlyphId> {
+        self.characters.get(codepoint as usize).copied()
+    }
+
+    /// Get the glyph id for a codepoint, falling back to [`GlyphId::NOTDEF`] for the codepoints beyond the character table
+    ///
+    /// Returns `Err` with the fallback glyph id in that case, so that the caller can report the missing codepoint.
+    pub fn get_glyph_id_or_notdef(&self, codepoint: u32) -> Result<GlyphId, GlyphId> {
+        self.try_get_glyph_id(codepoint).ok_or(GlyphId::NOTDEF)
+    }
+
     pub fn get_glyph(&self, glyph_id: GlyphId) -> Option<&G> {
         self.glyphs.get(&glyph_id)
     }
@@ -444,3 +503,114 @@ pub fn read_lazy_font<R: Read + Seek>(reader: &mut R) -> BinResult<FontLazy> {
 pub fn read_font_metrics<R: Read + Seek>(reader: &mut R) -> BinResult<FontInfo> {
     Font::read_le(reader)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use binrw::BinWrite;
+
+    use super::{FontHeader, GlyphBoundingBox, GlyphHeader, GlyphId, GlyphTrait, read_lazy_font};
+
+    /// Encodes a font with an empty glyph for all the characters, except for `A`
+    fn encode_font() -> Vec<u8> {
+        const TABLE_END: u32 = 16 + 0x10000 * 4;
+
+        let mut glyphs = Cursor::new(Vec::new());
+        let mut write_glyph = |header: GlyphHeader| {
+            let offset = TABLE_END + glyphs.get_ref().len() as u32;
+            let data_size = header.texture_width as usize * header.texture_height as usize;
+            header.write(&mut glyphs).unwrap();
+            glyphs.get_mut().extend(vec![0; data_size]);
+            glyphs.set_position(glyphs.get_ref().len() as u64);
+            offset
+        };
+
+        let empty_glyph = write_glyph(GlyphHeader {
+            bearing_x: 0,
+            bearing_y: 0,
+            actual_width: 0,
+            actual_height: 0,
+            advance_width: 0,
+            unused: 0,
+            texture_width: 0,
+            texture_height: 0,
+            compressed_size: 0,
+        });
+        let a_glyph = write_glyph(GlyphHeader {
+            bearing_x: 1,
+            bearing_y: 10,
+            actual_width: 6,
+            actual_height: 9,
+            advance_width: 8,
+            unused: 0,
+            texture_width: 8,
+            texture_height: 16,
+            compressed_size: 0,
+        });
+        let glyphs = glyphs.into_inner();
+
+        let mut font = Cursor::new(Vec::new());
+        FontHeader {
+            version: 1,
+            size: TABLE_END + glyphs.len() as u32,
+            ascent: 12,
+            descent: 4,
+        }
+        .write(&mut font)
+        .unwrap();
+        for character in 0..0x10000u32 {
+            let offset = if character == 'A' as u32 {
+                a_glyph
+            } else {
+                empty_glyph
+            };
+            offset.write_le(&mut font).unwrap();
+        }
+
+        let mut font = font.into_inner();
+        font.extend(glyphs);
+        font
+    }
+
+    #[test]
+    fn glyph_metrics() {
+        let font = read_lazy_font(&mut Cursor::new(encode_font())).unwrap();
+
+        let glyph = font.get_glyph_for_character('A' as u16);
+        assert_eq!(glyph.advance_width(), 8);
+        assert_eq!(glyph.bearing(), (1, 10));
+        assert_eq!(
+            glyph.bounding_box(),
+            GlyphBoundingBox {
+                left: 1,
+                top: -10,
+                right: 7,
+                bottom: -1,
+            }
+        );
+    }
+
+    #[test]
+    fn codepoints_beyond_the_table() {
+        let font = read_lazy_font(&mut Cursor::new(encode_font())).unwrap();
+
+        assert_eq!(font.try_get_glyph_id('A' as u32), Some(GlyphId(1)));
+        assert_eq!(font.try_get_glyph_id(0xffff), Some(GlyphId(0)));
+        assert_eq!(font.try_get_glyph_id('😀' as u32), None);
+    }
+
+    #[test]
+    fn notdef_past_the_table_end() {
+        let font = read_lazy_font(&mut Cursor::new(encode_font())).unwrap();
+
+        assert_eq!(font.get_glyph_id_or_notdef('A' as u32), Ok(GlyphId(1)));
+        assert_eq!(font.get_glyph_id_or_notdef(0x10000), Err(GlyphId::NOTDEF));
+        // the tofu is the same glyph the unused characters are mapped to
+        assert_eq!(
+            font.get_glyph_id_or_notdef('😀' as u32),
+            Err(font.get_character_mapping()[0xffff])
+        );
+        assert!(font.get_glyph(GlyphId::NOTDEF).is_some());
+    }
+}
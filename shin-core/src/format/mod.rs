@@ -5,6 +5,7 @@ pub mod text;
 
 pub mod rom;
 
+pub mod animation;
 pub mod audio;
 pub mod bustup;
 pub mod font;
@@ -12,6 +13,7 @@ pub mod mask;
 pub mod picture;
 pub mod save;
 pub mod scenario;
+pub mod sprite_sheet;
 pub mod sysse;
 pub mod texture_archive;
 
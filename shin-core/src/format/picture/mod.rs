@@ -250,6 +250,73 @@ impl PictureBuilder for SimpleMergedPicture {
     }
 }
 
+/// Merges the blocks overlapping a region of the picture into an image of the region size
+///
+/// Use with [`read_picture_region`] to avoid decoding the blocks outside of the region.
+pub struct MergedPictureRegion {
+    pub image: RgbaImage,
+    pub region: PictureRegion,
+    pub origin_x: i32,
+    pub origin_y: i32,
+    pub picture_id: u32,
+}
+
+impl PictureBuilder for MergedPictureRegion {
+    type Args = PictureRegion;
+    type Output = MergedPictureRegion;
+
+    fn new(
+        region: PictureRegion,
+        _effective_width: u32,
+        _effective_height: u32,
+        origin_x: i32,
+        origin_y: i32,
+        picture_id: u32,
+    ) -> Self {
+        MergedPictureRegion {
+            image: RgbaImage::new(region.width, region.height),
+            region,
+            origin_x,
+            origin_y,
+            picture_id,
+        }
+    }
+
+    fn add_block(
+        &mut self,
+        _data_offset: u32,
+        positions: Vec<(u32, u32)>,
+        block: PicBlock,
+    ) -> Result<()> {
+        // same as in SimpleMergedPicture
+        assert_eq!(block.offset_x, 0);
+        assert_eq!(block.offset_y, 0);
+
+        let block_image = block.data;
+        for &(x, y) in &positions {
+            image::imageops::replace(
+                &mut self.image,
+                &block_image,
+                x as i64 - self.region.x as i64,
+                y as i64 - self.region.y as i64,
+            );
+        }
+
+        Ok(())
+    }
+
+    fn build(self) -> Result<Self::Output> {
+        Ok(self)
+    }
+}
+
+/// Decodes the part of a picture inside the `region`, decompressing only the blocks overlapping it.
+///
+/// NOTE: this will spawn rayon tasks and block waiting for them. If you don't want blocking wrap it with [`shin_tasks::compute::spawn`].
+pub fn decode_picture_region(source: &[u8], region: PictureRegion) -> Result<RgbaImage> {
+    read_picture_region::<MergedPictureRegion>(source, region, region).map(|picture| picture.image)
+}
+
 pub struct SimplePicture {
     pub blocks: Vec<(Vec<(u32, u32)>, PicBlock)>,
     pub effective_width: u32,
@@ -332,6 +399,30 @@ fn decode_dict(
     }
 }
 
+/// Each pixel is stored as a per-channel (wrapping) difference: from the pixel to the left in the first row, and from the pixel above in the others
+fn decode_differential(image: &mut RgbaImage, encoded_data: &[u8], stride: usize) {
+    let width = image.width() as usize;
+    let mut previous_row = None::<Vec<[u8; 4]>>;
+
+    for (row, dest_row) in encoded_data.chunks(stride).zip_eq(image.rows_mut()) {
+        let mut current_row = Vec::with_capacity(width);
+        for ((x, delta), dest_pixel) in row[..width * 4]
+            .chunks_exact(4)
+            .enumerate()
+            .zip_eq(dest_row)
+        {
+            let base = match &previous_row {
+                Some(previous_row) => previous_row[x],
+                None => current_row.last().copied().unwrap_or_default(),
+            };
+            let pixel: [u8; 4] = std::array::from_fn(|i| base[i].wrapping_add(delta[i]));
+            current_row.push(pixel);
+            *dest_pixel = image::Rgba(pixel);
+        }
+        previous_row = Some(current_row);
+    }
+}
+
 pub fn read_texture(
     data: &[u8],
     compressed_size: usize,
@@ -400,7 +491,8 @@ pub fn read_texture(
             stride,
         )
     } else {
-        todo!("decode differential")
+        let stride = differential_stride;
+        decode_differential(target_image, &data[..stride * height as usize], stride)
     }
 }
 
@@ -493,10 +585,73 @@ pub fn read_picture_header(source: &[u8]) -> Result<PictureHeaderInfo> {
     })
 }
 
+/// A rectangle in the picture space, in pixels
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PictureRegion {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl PictureRegion {
+    /// The region covering the whole picture
+    pub fn whole(header: &PictureHeaderInfo) -> Self {
+        Self {
+            x: 0,
+            y: 0,
+            width: header.effective_width as u32,
+            height: header.effective_height as u32,
+        }
+    }
+
+    fn overlaps(&self, x: u32, y: u32, width: u32, height: u32) -> bool {
+        x < self.x.saturating_add(self.width)
+            && self.x < x.saturating_add(width)
+            && y < self.y.saturating_add(self.height)
+            && self.y < y.saturating_add(height)
+    }
+}
+
+/// Read the extent of a picture block, without decoding its data
+///
+/// Returns `None` for the empty blocks, as they don't have any.
+fn read_picture_block_extent(block_data: &[u8]) -> Result<Option<PictureRegion>> {
+    if block_data.is_empty() {
+        return Ok(None);
+    }
+
+    let header: PicBlockHeader = io::Cursor::new(block_data)
+        .read_le()
+        .context("Reading block header")?;
+
+    Ok(Some(PictureRegion {
+        x: header.offset_x as u32,
+        y: header.offset_y as u32,
+        width: header.width as u32,
+        height: header.height as u32,
+    }))
+}
+
 /// Reads and decodes a picture.
 ///
 /// NOTE: this will spawn rayon tasks and block waiting for them. If you don't want blocking wrap it with [`shin_tasks::compute::spawn`].
 pub fn read_picture<B: PictureBuilder>(source: &[u8], builder_args: B::Args) -> Result<B::Output> {
+    let header = read_picture_header(source)?;
+
+    read_picture_region::<B>(source, PictureRegion::whole(&header), builder_args)
+}
+
+/// Reads and decodes the part of a picture overlapping the `region`.
+///
+/// Only the blocks overlapping the region are decompressed, so the memory usage stays low even for the large pictures. The builder is only given the positions of the blocks that overlap the region; it is up to it to crop them.
+///
+/// NOTE: this will spawn rayon tasks and block waiting for them. If you don't want blocking wrap it with [`shin_tasks::compute::spawn`].
+pub fn read_picture_region<B: PictureBuilder>(
+    source: &[u8],
+    region: PictureRegion,
+    builder_args: B::Args,
+) -> Result<B::Output> {
     let mut source = io::Cursor::new(source);
     let header: PicHeader = BinRead::read(&mut source)?;
 
@@ -527,6 +682,22 @@ pub fn read_picture<B: PictureBuilder>(source: &[u8], builder_args: B::Args) ->
         positions.push((block_desc.x as u32, block_desc.y as u32));
     }
 
+    let mut extents = BTreeMap::new();
+    for (&data_offset, (_, data)) in &blocks {
+        extents.insert(data_offset, read_picture_block_extent(data)?);
+    }
+    blocks.retain(|data_offset, (positions, _)| {
+        // empty blocks cost nothing to decode, leave them to the builder
+        let Some(extent) = extents[data_offset] else {
+            return true;
+        };
+
+        positions.retain(|&(x, y)| {
+            region.overlaps(x + extent.x, y + extent.y, extent.width, extent.height)
+        });
+        !positions.is_empty()
+    });
+
     let builder = B::new(
         builder_args,
         header.effective_width as u32,
@@ -550,3 +721,134 @@ pub fn read_picture<B: PictureBuilder>(source: &[u8], builder_args: B::Args) ->
 
     builder.build()
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use binrw::BinWrite;
+    use image::GenericImageView;
+
+    use super::{
+        CompressionFlags, PicBlockDesc, PicBlockHeader, PicHeader, PictureRegion, Rgba8,
+        SimpleMergedPicture, SimplePicture, decode_picture_region, read_picture,
+        read_picture_region,
+    };
+
+    const BLOCK_SIZE: u16 = 4;
+
+    /// Encodes an 8x8 picture made of four dictionary-encoded blocks, with every pixel being different
+    fn encode_picture() -> Vec<u8> {
+        let positions = [(0, 0), (4, 0), (0, 4), (4, 4)];
+
+        let dictionary = (0..=255)
+            .map(|i| Rgba8 {
+                r: i,
+                g: 255 - i,
+                b: 0,
+                a: 255,
+            })
+            .collect::<Vec<_>>();
+
+        let blocks = (0..positions.len())
+            .map(|block| {
+                let mut data = Cursor::new(Vec::new());
+                PicBlockHeader {
+                    compression_flags: CompressionFlags::USE_DICT_ENCODING
+                        | CompressionFlags::USE_INLINE_ALPHA,
+                    opaque_rect_count: 0,
+                    transparent_rect_count: 0,
+                    padding_before_data: 0,
+                    offset_x: 0,
+                    offset_y: 0,
+                    width: BLOCK_SIZE,
+                    height: BLOCK_SIZE,
+                    compressed_size: 0,
+                }
+                .write_le(&mut data)
+                .unwrap();
+                let mut data = data.into_inner();
+                data.extend_from_slice(bytemuck::cast_slice(&dictionary));
+                data.extend((0..BLOCK_SIZE * BLOCK_SIZE).map(|i| (block * 16) as u8 + i as u8));
+                data
+            })
+            .collect::<Vec<_>>();
+
+        let header_size = 36 + 12 * positions.len() as u32;
+        let data_size = blocks.iter().map(|b| b.len() as u32).sum::<u32>();
+
+        let mut picture = Cursor::new(Vec::new());
+        PicHeader {
+            version: 3,
+            file_size: header_size + data_size,
+            origin_x: 0,
+            origin_y: 0,
+            effective_width: 8,
+            effective_height: 8,
+            field_20: 0,
+            block_count: positions.len() as u32,
+            picture_id: 0,
+            scale: 4096,
+        }
+        .write(&mut picture)
+        .unwrap();
+
+        let mut offset = header_size;
+        for (&(x, y), block) in positions.iter().zip(&blocks) {
+            PicBlockDesc {
+                x,
+                y,
+                offset,
+                size: block.len() as u32,
+            }
+            .write_le(&mut picture)
+            .unwrap();
+            offset += block.len() as u32;
+        }
+
+        let mut picture = picture.into_inner();
+        for block in blocks {
+            picture.extend(block);
+        }
+        picture
+    }
+
+    #[test]
+    fn region_matches_the_full_picture() {
+        let picture = encode_picture();
+        let full = read_picture::<SimpleMergedPicture>(&picture, ())
+            .unwrap()
+            .image;
+
+        for (x, y, width, height) in [(2, 2, 4, 4), (5, 1, 3, 6), (0, 0, 8, 8)] {
+            let region = decode_picture_region(
+                &picture,
+                PictureRegion {
+                    x,
+                    y,
+                    width,
+                    height,
+                },
+            )
+            .unwrap();
+
+            assert_eq!(region, full.view(x, y, width, height).to_image());
+        }
+    }
+
+    #[test]
+    fn only_overlapping_blocks_are_decoded() {
+        let picture = encode_picture();
+        let region = PictureRegion {
+            x: 5,
+            y: 1,
+            width: 2,
+            height: 2,
+        };
+
+        let decoded = read_picture_region::<SimplePicture>(&picture, region, ()).unwrap();
+
+        assert_eq!(decoded.blocks.len(), 1);
+        assert_eq!(decoded.blocks[0].0, [(4, 0)]);
+    }
+}
@@ -10,6 +10,9 @@ use serde_big_array::BigArray;
 use crate::crc32;
 
 mod obfuscation;
+mod unlocks;
+
+pub use unlocks::{UnlockBits, UnlockState, UnlockType};
 
 type Endian = bitbuffer::BigEndian;
 const ENDIAN: Endian = bitbuffer::BigEndian;
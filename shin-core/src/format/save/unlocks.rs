@@ -0,0 +1,221 @@
+use serde::{Deserialize, Serialize};
+
+/// A growable set of non-negative indices, stored as a bitmask like the unlock vectors in the save file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnlockBits(Vec<u32>);
+
+impl UnlockBits {
+    /// The save file stores the unlock vectors with a 16-bit length in words, so this is the largest index it can hold.
+    ///
+    /// It also keeps a bogus index in the scenario from allocating a huge bitmask.
+    pub const MAX_INDEX: i32 = u16::MAX as i32 * 32 - 1;
+
+    pub fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    pub fn contains(&self, index: i32) -> bool {
+        if index < 0 {
+            return false;
+        }
+        let index = index as usize;
+        self.0
+            .get(index / 32)
+            .is_some_and(|word| word & (1 << (index % 32)) != 0)
+    }
+
+    /// Returns `true` if the index wasn't in the set before.
+    ///
+    /// Negative indices and the ones above [`Self::MAX_INDEX`] are ignored.
+    pub fn insert(&mut self, index: i32) -> bool {
+        if !(0..=Self::MAX_INDEX).contains(&index) {
+            return false;
+        }
+        let index = index as usize;
+        if self.0.len() <= index / 32 {
+            self.0.resize(index / 32 + 1, 0);
+        }
+
+        let word = &mut self.0[index / 32];
+        let bit = 1 << (index % 32);
+        let newly_set = *word & bit == 0;
+        *word |= bit;
+        newly_set
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = i32> + '_ {
+        self.0.iter().enumerate().flat_map(|(word_index, &word)| {
+            (0..32)
+                .filter(move |bit| word & (1 << bit) != 0)
+                .map(move |bit| (word_index * 32 + bit) as i32)
+        })
+    }
+}
+
+/// What kind of extra content is unlocked by the `UNLOCK` command
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum UnlockType {
+    Cg,
+    Bgm,
+    Movie,
+}
+
+impl UnlockType {
+    pub fn from_raw(unlock_type: u8) -> Option<Self> {
+        match unlock_type {
+            0 => Some(Self::Cg),
+            1 => Some(Self::Bgm),
+            2 => Some(Self::Movie),
+            _ => None,
+        }
+    }
+}
+
+/// Tracks the extra content unlocked by the scenario (gallery entries, tips, characters and trophies).
+///
+/// This is global progression, independent of the save slots: loading a save doesn't lock anything again.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UnlockState {
+    pub cgs: UnlockBits,
+    pub bgms: UnlockBits,
+    pub movies: UnlockBits,
+    pub tips: UnlockBits,
+    pub characters: UnlockBits,
+    pub trophies: UnlockBits,
+    /// Notification types that were raised with `NOTIFYSET`
+    pub notifications: UnlockBits,
+}
+
+impl UnlockState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn bits_mut(&mut self, unlock_type: UnlockType) -> &mut UnlockBits {
+        match unlock_type {
+            UnlockType::Cg => &mut self.cgs,
+            UnlockType::Bgm => &mut self.bgms,
+            UnlockType::Movie => &mut self.movies,
+        }
+    }
+
+    /// Unlocks the entries with the `indices`, returning the number of the newly unlocked ones.
+    pub fn unlock(&mut self, unlock_type: UnlockType, indices: &[i32]) -> usize {
+        let bits = self.bits_mut(unlock_type);
+        indices.iter().filter(|&&index| bits.insert(index)).count()
+    }
+
+    pub fn is_unlocked(&self, unlock_type: UnlockType, index: i32) -> bool {
+        match unlock_type {
+            UnlockType::Cg => self.cgs.contains(index),
+            UnlockType::Bgm => self.bgms.contains(index),
+            UnlockType::Movie => self.movies.contains(index),
+        }
+    }
+
+    pub fn unlock_tips(&mut self, tip_ids: &[i32]) -> usize {
+        tip_ids.iter().filter(|&&id| self.tips.insert(id)).count()
+    }
+
+    pub fn is_tip_unlocked(&self, tip_id: i32) -> bool {
+        self.tips.contains(tip_id)
+    }
+
+    pub fn unlock_character(&mut self, character_id: i32) -> bool {
+        self.characters.insert(character_id)
+    }
+
+    pub fn is_character_unlocked(&self, character_id: i32) -> bool {
+        self.characters.contains(character_id)
+    }
+
+    pub fn award_trophy(&mut self, trophy_id: i32) -> bool {
+        self.trophies.insert(trophy_id)
+    }
+
+    pub fn has_trophy(&self, trophy_id: i32) -> bool {
+        self.trophies.contains(trophy_id)
+    }
+
+    pub fn set_notification(&mut self, notification_type: i32) -> bool {
+        self.notifications.insert(notification_type)
+    }
+
+    pub fn is_notification_set(&self, notification_type: i32) -> bool {
+        self.notifications.contains(notification_type)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{UnlockBits, UnlockState, UnlockType};
+    use crate::{
+        format::scenario::{instruction_elements::NumberSpec, types::U8SmallNumberList},
+        vm::{IntoRuntimeForm, VmCtx},
+    };
+
+    fn unlock_indices(indices: &[i32]) -> U8SmallNumberList {
+        U8SmallNumberList::from_contents(indices.iter().map(|&i| NumberSpec::constant(i)))
+    }
+
+    #[test]
+    fn unlock_sets_bits() {
+        let ctx = VmCtx::new(0, 0);
+        let mut state = UnlockState::new();
+
+        let indices = unlock_indices(&[0, 5, 31, 32, 100]).into_runtime_form(&ctx);
+        assert_eq!(state.unlock(UnlockType::Cg, &indices), 5);
+
+        for index in [0, 5, 31, 32, 100] {
+            assert!(state.is_unlocked(UnlockType::Cg, index));
+        }
+        for index in [1, 30, 33, 99, 101, 1000] {
+            assert!(!state.is_unlocked(UnlockType::Cg, index));
+        }
+        assert_eq!(state.cgs.iter().collect::<Vec<_>>(), [0, 5, 31, 32, 100]);
+
+        // other kinds of content are unaffected
+        assert!(!state.is_unlocked(UnlockType::Bgm, 5));
+        assert!(!state.is_unlocked(UnlockType::Movie, 5));
+    }
+
+    #[test]
+    fn unlock_is_idempotent() {
+        let ctx = VmCtx::new(0, 0);
+        let mut state = UnlockState::new();
+
+        let indices = unlock_indices(&[3, 7]).into_runtime_form(&ctx);
+        assert_eq!(state.unlock(UnlockType::Bgm, &indices), 2);
+        let unlocked = state.bgms.clone();
+
+        assert_eq!(state.unlock(UnlockType::Bgm, &indices), 0);
+        assert_eq!(state.bgms, unlocked);
+
+        // only the new index counts
+        let indices = unlock_indices(&[7, 8]).into_runtime_form(&ctx);
+        assert_eq!(state.unlock(UnlockType::Bgm, &indices), 1);
+        assert_eq!(state.bgms.iter().collect::<Vec<_>>(), [3, 7, 8]);
+    }
+
+    #[test]
+    fn negative_indices_are_ignored() {
+        let mut state = UnlockState::new();
+
+        assert_eq!(state.unlock_tips(&[-1, 2]), 1);
+        assert!(!state.is_tip_unlocked(-1));
+        assert!(state.is_tip_unlocked(2));
+    }
+
+    #[test]
+    fn out_of_range_indices_are_ignored() {
+        let mut state = UnlockState::new();
+
+        assert!(!state.award_trophy(i32::MAX));
+        assert!(!state.has_trophy(i32::MAX));
+        assert!(state.trophies.iter().next().is_none());
+
+        assert!(state.award_trophy(UnlockBits::MAX_INDEX));
+        assert!(state.has_trophy(UnlockBits::MAX_INDEX));
+        assert!(!state.award_trophy(UnlockBits::MAX_INDEX + 1));
+    }
+}
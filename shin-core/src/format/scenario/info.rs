@@ -8,7 +8,7 @@
 //!
 //! Apart from the asset tables, there are also a few other data blocks for various game-specific features, such as the Picture Box (`cgmode`) and Music Box (`bgmmode`), or Umineko's character relationship grid (`chars`). These may be somewhat more freeform in structure than the simple tables listed above, and their corresponding entry structs often also contain IDs linking to other data tables, as explained above.
 
-use std::io::{Read, Seek, Write};
+use std::io::{Read, Seek, SeekFrom, Write};
 
 use anyhow::anyhow;
 use binrw::{BinRead, BinResult, BinWrite, Endian, FilePtr32, file_ptr::FilePtrArgs};
@@ -186,6 +186,23 @@ pub struct VoiceMappingInfoItem {
 }
 pub type VoiceMappingInfo = Vec<VoiceMappingInfoItem>;
 
+impl VoiceMappingInfoItem {
+    /// Whether the voice (named like in `VOICEPLAY`, e.g. `00/awase0001`) matches the [`name_pattern`](Self::name_pattern), ignoring the case
+    pub fn matches(&self, voice_name: &str) -> bool {
+        fn matches(pattern: &[u8], name: &[u8]) -> bool {
+            match pattern.split_first() {
+                None => name.is_empty(),
+                Some((b'*', rest)) => (0..=name.len()).any(|skip| matches(rest, &name[skip..])),
+                Some((c, rest)) => name
+                    .split_first()
+                    .is_some_and(|(n, name)| c.eq_ignore_ascii_case(n) && matches(rest, name)),
+            }
+        }
+
+        matches(self.name_pattern.as_str().as_bytes(), voice_name.as_bytes())
+    }
+}
+
 /// An entry in the Picture Box (`cgmode`).
 #[derive(Debug, PartialEq, Eq, Hash, BinRead, BinWrite)]
 pub struct PictureBoxInfoItem {
@@ -517,11 +534,24 @@ fn parse_terminated_segment_list<R: Read + Seek, T: for<'a> BinRead<Args<'a> = (
     }
 }
 
+/// Older scenario versions lack some of the tables, leaving a zero offset in place of the pointer. Those are read as empty
+fn is_null_ptr<R: Read + Seek>(reader: &mut R, endian: Endian) -> BinResult<bool> {
+    let pos = reader.stream_position()?;
+    if u32::read_options(reader, endian, ())? == 0 {
+        return Ok(true);
+    }
+    reader.seek(SeekFrom::Start(pos))?;
+    Ok(false)
+}
+
 fn parse_simple_section_ptr<R: Read + Seek, T: for<'a> BinRead<Args<'a> = ()> + 'static>(
     reader: &mut R,
     endian: Endian,
     args: FilePtrArgs<()>,
 ) -> BinResult<Vec<T>> {
+    if is_null_ptr(reader, endian)? {
+        return Ok(Vec::new());
+    }
     FilePtr32::<SimpleTable<T>>::parse(reader, endian, args).map(|x| x.elements)
 }
 
@@ -530,6 +560,9 @@ fn parse_sized_section_ptr<R: Read + Seek, T: for<'a> BinRead<Args<'a> = ()> + '
     endian: Endian,
     args: FilePtrArgs<()>,
 ) -> BinResult<Vec<T>> {
+    if is_null_ptr(reader, endian)? {
+        return Ok(Vec::new());
+    }
     // maybe check that the size matches for our own sanity?
     FilePtr32::<SizedTable<T>>::parse(reader, endian, args).map(|x| x.elements)
 }
@@ -539,6 +572,9 @@ fn parse_sized_segment_list_ptr<R: Read + Seek, T: for<'a> BinRead<Args<'a> = ()
     endian: Endian,
     args: FilePtrArgs<()>,
 ) -> BinResult<Vec<T>> {
+    if is_null_ptr(reader, endian)? {
+        return Ok(Vec::new());
+    }
     FilePtr32::<SizedSegmentList<T>>::parse(reader, endian, args).map(|x| x.segments)
 }
 
@@ -733,4 +769,129 @@ impl ScenarioInfoTables {
     pub fn movie_info(&self, movie_id: MovieId) -> &MovieInfoItem {
         &self.movie_info[movie_id.0 as usize]
     }
+
+    /// The lipsync character ids of the characters speaking in the voice, taken from the first [`VoiceMappingInfoItem`] matching it
+    pub fn lipsync_character_ids(&self, voice_name: &str) -> &[u8] {
+        self.voice_mapping_info
+            .iter()
+            .find(|item| item.matches(voice_name))
+            .map_or(&[], |item| &item.lipsync_character_ids.0)
+    }
+
+    // the entries are listed along with their ids, for tools that browse the assets
+
+    pub fn mask_entries(&self) -> impl Iterator<Item = (MaskId, &MaskInfoItem)> {
+        entries(&self.mask_info, MaskId)
+    }
+    pub fn picture_entries(&self) -> impl Iterator<Item = (PictureId, &PictureInfoItem)> {
+        entries(&self.picture_info, PictureId)
+    }
+    pub fn bustup_entries(&self) -> impl Iterator<Item = (BustupId, &BustupInfoItem)> {
+        entries(&self.bustup_info, BustupId)
+    }
+    pub fn bgm_entries(&self) -> impl Iterator<Item = (BgmId, &BgmInfoItem)> {
+        entries(&self.bgm_info, BgmId)
+    }
+    pub fn se_entries(&self) -> impl Iterator<Item = (SeId, &SeInfoItem)> {
+        entries(&self.se_info, SeId)
+    }
+    pub fn movie_entries(&self) -> impl Iterator<Item = (MovieId, &MovieInfoItem)> {
+        entries(&self.movie_info, MovieId)
+    }
+}
+
+fn entries<Id, T>(table: &[T], id: fn(u16) -> Id) -> impl Iterator<Item = (Id, &T)> {
+    table
+        .iter()
+        .enumerate()
+        .map(move |(index, item)| (id(index as u16), item))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomData;
+
+    use bytes::Bytes;
+    use smallvec::smallvec;
+
+    use super::{BgmId, MaskId, PictureId, VoiceMappingInfoItem};
+    use crate::format::{scenario::Scenario, text::U16String};
+
+    /// A scenario with a mask, a picture and a BGM, the other tables are missing like in the older versions
+    fn scenario() -> Scenario {
+        const CODE_OFFSET: u32 = 147;
+
+        let mut data = b"SNR ".to_vec();
+        for value in [CODE_OFFSET + 1, 0, 6, 19, 0, 0, 0, CODE_OFFSET] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        // masks, pictures, bustups, BGMs, then all the other tables
+        for offset in [88u32, 104, 0, 121].into_iter().chain([0; 9]) {
+            data.extend_from_slice(&offset.to_le_bytes());
+        }
+        assert_eq!(data.len(), 88);
+
+        // sized tables: byte size, element count, elements
+        data.extend_from_slice(b"\x10\x00\x00\x00\x01\x00\x00\x00\x06\x00MSK01\x00");
+        data.extend_from_slice(b"\x11\x00\x00\x00\x01\x00\x00\x00\x05\x00BG01\x00\xff\xff");
+        data.extend_from_slice(
+            b"\x1a\x00\x00\x00\x01\x00\x00\x00\x09\x00UMIB_003\x00\x03\x00Hi\x00\xff\xff",
+        );
+        assert_eq!(data.len(), CODE_OFFSET as usize);
+        // EXIT
+        data.push(0x00);
+
+        Scenario::new(Bytes::from(data)).unwrap()
+    }
+
+    #[test]
+    fn entries() {
+        let scenario = scenario();
+        let info = scenario.info_tables();
+
+        let masks = info
+            .mask_entries()
+            .map(|(id, mask)| (id, mask.path()))
+            .collect::<Vec<_>>();
+        assert_eq!(masks, [(MaskId(0), "/mask/msk01.msk".to_string())]);
+
+        let (picture_id, picture) = info.picture_entries().next().unwrap();
+        assert_eq!(picture_id, PictureId(0));
+        assert_eq!(picture.path(), "/picture/bg01.pic");
+        assert_eq!(picture.linked_cg_id.repr(), None);
+
+        let (bgm_id, bgm) = info.bgm_entries().next().unwrap();
+        assert_eq!(bgm_id, BgmId(0));
+        assert_eq!(info.bgm_info(bgm_id).path(), "/bgm/umib_003.nxa");
+        assert_eq!(bgm.display_name.as_str(), "Hi");
+    }
+
+    #[test]
+    fn missing_tables_are_empty() {
+        let scenario = scenario();
+        let info = scenario.info_tables();
+
+        assert_eq!(info.bustup_entries().count(), 0);
+        assert_eq!(info.se_entries().count(), 0);
+        assert_eq!(info.movie_entries().count(), 0);
+        assert!(info.character_box_info.is_empty());
+        assert!(info.music_box_info.is_empty());
+        assert!(info.tips_info.is_empty());
+    }
+
+    #[test]
+    fn voice_mapping_patterns() {
+        let mapping = |pattern: &str| VoiceMappingInfoItem {
+            name_pattern: U16String::new(pattern),
+            lipsync_character_ids: super::U8SmallList(smallvec![3, 7], PhantomData),
+        };
+
+        assert!(mapping("00/awase0001").matches("00/AWASE0001"));
+        assert!(!mapping("00/awase0001").matches("00/awase00012"));
+        assert!(mapping("00/awase*").matches("00/awase0001"));
+        assert!(mapping("00/awase*").matches("00/awase"));
+        assert!(mapping("*/awase*1").matches("12/awase0001"));
+        assert!(!mapping("*/awase*1").matches("12/awase0002"));
+        assert!(!mapping("01/*").matches("00/awase0001"));
+    }
 }
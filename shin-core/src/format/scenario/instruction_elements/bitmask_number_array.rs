@@ -49,6 +49,8 @@ pub fn lower_number_array<
 /// Represents 8 numbers, each of which may or may not be present.
 ///
 /// If the number is not present, it is treated as `NumberSpec::Constant(0)`.
+///
+/// Each number is typed separately and converted to its runtime form through its own [`FromNumber`] impl.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct BitmaskNumberArray<
     T1 = i32,
@@ -70,6 +72,22 @@ pub struct BitmaskNumberArray<
     NumberSpec<T8>,
 );
 
+impl<T1, T2, T3, T4, T5, T6, T7, T8> BitmaskNumberArray<T1, T2, T3, T4, T5, T6, T7, T8> {
+    /// Build the array from 8 numbers, the ones equal to `Constant(0)` being omitted when encoded
+    pub fn new(numbers: [UntypedNumberSpec; 8]) -> Self {
+        Self(
+            NumberSpec::new(numbers[0]),
+            NumberSpec::new(numbers[1]),
+            NumberSpec::new(numbers[2]),
+            NumberSpec::new(numbers[3]),
+            NumberSpec::new(numbers[4]),
+            NumberSpec::new(numbers[5]),
+            NumberSpec::new(numbers[6]),
+            NumberSpec::new(numbers[7]),
+        )
+    }
+}
+
 impl<T1, T2, T3, T4, T5, T6, T7, T8> BinRead
     for BitmaskNumberArray<T1, T2, T3, T4, T5, T6, T7, T8>
 {
@@ -90,16 +108,7 @@ impl<T1, T2, T3, T4, T5, T6, T7, T8> BinRead
             mask >>= 1;
         }
 
-        Ok(Self(
-            NumberSpec::new(untyped[0]),
-            NumberSpec::new(untyped[1]),
-            NumberSpec::new(untyped[2]),
-            NumberSpec::new(untyped[3]),
-            NumberSpec::new(untyped[4]),
-            NumberSpec::new(untyped[5]),
-            NumberSpec::new(untyped[6]),
-            NumberSpec::new(untyped[7]),
-        ))
+        Ok(Self::new(untyped))
     }
 }
 
@@ -173,11 +182,44 @@ impl<
 
 #[cfg(test)]
 mod tests {
+    use std::io::Cursor;
+
+    use binrw::BinRead;
+
     use super::BitmaskNumberArray;
-    use crate::format::{
-        scenario::instruction_elements::NumberSpec, test_util::assert_enc_dec_pair,
+    use crate::{
+        format::{
+            scenario::instruction_elements::{NumberSpec, Register},
+            test_util::assert_enc_dec_pair,
+        },
+        time::Ticks,
+        vm::{IntoRuntimeForm, VmCtx, command::types::LayerCtrlFlags},
     };
 
+    type LayerCtrlParams = BitmaskNumberArray<i32, Ticks, LayerCtrlFlags, i32>;
+
+    fn decode(encoded: &str, ctx: &VmCtx) -> (i32, Ticks, LayerCtrlFlags, i32) {
+        let encoded = hex::decode(encoded).unwrap();
+        let params = LayerCtrlParams::read_le(&mut Cursor::new(&encoded)).unwrap();
+        let (target_value, duration, flags, easing_param, ..) = params.into_runtime_form(ctx);
+        (target_value, duration, flags, easing_param)
+    }
+
+    #[test]
+    fn layerctrl_params() {
+        let mut ctx = VmCtx::new(0, 0);
+        ctx.write_register(Register::from_regular_register(3), 30);
+
+        // target value 100, duration from $3, flags 5, easing param absent
+        assert_eq!(
+            decode("078064b305", &ctx),
+            (100, Ticks::from_u32(30), LayerCtrlFlags(5), 0)
+        );
+        // only the flags are present, the rest are defaulted to zero
+        assert_eq!(decode("0405", &ctx), (0, Ticks::ZERO, LayerCtrlFlags(5), 0));
+        assert_eq!(decode("00", &ctx), (0, Ticks::ZERO, LayerCtrlFlags(0), 0));
+    }
+
     #[test]
     fn enc_dec() {
         const ZERO: NumberSpec = NumberSpec::constant(0);
@@ -12,7 +12,7 @@ use crate::{
 ///
 /// It can be a constant or be referencing a register.
 ///
-/// [FromVmCtx](crate::vm::FromVmCtx) trait is used to convert it to runtime representation in command definitions (see [crate::vm::command])
+/// [IntoRuntimeForm](crate::vm::IntoRuntimeForm) trait is used to convert it to runtime representation in command definitions (see [crate::vm::command])
 #[derive(Copy, Clone, PartialEq, Eq)]
 pub enum UntypedNumberSpec {
     Constant(i32),
@@ -188,6 +188,24 @@ impl<T> NumberSpec<T> {
     pub fn into_untyped(self) -> UntypedNumberSpec {
         self.0
     }
+
+    /// The value, if it's a constant known without running the VM
+    #[inline]
+    pub fn as_constant(&self) -> Option<i32> {
+        match self.0 {
+            UntypedNumberSpec::Constant(value) => Some(value),
+            UntypedNumberSpec::Register(_) => None,
+        }
+    }
+
+    /// The register the value is read from, if it's not a constant
+    #[inline]
+    pub fn referenced_register(&self) -> Option<Register> {
+        match self.0 {
+            UntypedNumberSpec::Constant(_) => None,
+            UntypedNumberSpec::Register(register) => Some(register),
+        }
+    }
 }
 
 impl NumberSpec {
@@ -241,7 +259,8 @@ mod tests {
 
     use super::UntypedNumberSpec::*;
     use crate::format::{
-        scenario::instruction_elements::UntypedNumberSpec, test_util::assert_enc_dec_pair,
+        scenario::instruction_elements::{NumberSpec, UntypedNumberSpec},
+        test_util::assert_enc_dec_pair,
     };
 
     #[test]
@@ -353,4 +372,16 @@ mod tests {
             v => panic!("unexpected error: {:?}", v),
         };
     }
+
+    #[test]
+    fn introspection() {
+        let constant = NumberSpec::constant(-42);
+        assert_eq!(constant.as_constant(), Some(-42));
+        assert_eq!(constant.referenced_register(), None);
+
+        let register = "$a1".parse().unwrap();
+        let from_register = NumberSpec::register(register);
+        assert_eq!(from_register.as_constant(), None);
+        assert_eq!(from_register.referenced_register(), Some(register));
+    }
 }
@@ -141,3 +141,17 @@ pub enum Instruction {
     /// Send command to the game engine
     Command(CompiletimeCommand),
 }
+
+impl Instruction {
+    /// The addresses the instruction can transfer the control to, besides the next instruction
+    pub fn jump_targets(&self) -> &[CodeAddress] {
+        match self {
+            Instruction::jc { target, .. }
+            | Instruction::j { target }
+            | Instruction::gosub { target }
+            | Instruction::call { target, .. } => std::slice::from_ref(target),
+            Instruction::jt { table, .. } => &table.0,
+            _ => &[],
+        }
+    }
+}
@@ -5,11 +5,12 @@
 pub mod info;
 pub mod instruction_elements;
 pub mod instructions;
+pub mod scenes;
 pub mod types;
 
-use std::io::Cursor;
+use std::{collections::BTreeSet, io::Cursor};
 
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
 use binrw::{BinRead, BinWrite};
 use bytes::Bytes;
 use instruction_elements::CodeAddress;
@@ -91,6 +92,95 @@ impl Scenario {
     pub fn instruction_reader(&self, offset: CodeAddress) -> InstructionReader {
         InstructionReader::new(self.raw_data.clone(), offset)
     }
+
+    /// Decode all the instructions, from the entrypoint to the end of the file
+    pub fn instructions(&self) -> Result<Vec<(CodeAddress, Instruction)>> {
+        self.read_instructions().map(|(instructions, _)| instructions)
+    }
+
+    /// Like [`Self::instructions`], along with where the last one ends
+    fn read_instructions(&self) -> Result<(Vec<(CodeAddress, Instruction)>, CodeAddress)> {
+        let mut reader = self.instruction_reader(self.entrypoint_address);
+
+        let mut end_position = self.raw_data.len();
+        // scenario file is aligned to 0x10 bytes, so there are some zeros at the end
+        // trim them
+        while end_position > 0 && self.raw_data[end_position - 1] == 0 {
+            end_position -= 1;
+        }
+        let end_position = CodeAddress(end_position as u32);
+
+        let mut instructions = Vec::new();
+        while reader.position() < end_position {
+            let position = reader.position();
+
+            let instruction = reader
+                .read()
+                .with_context(|| format!("Reading instruction at {}", position))?;
+            instructions.push((position, instruction));
+        }
+
+        // the last instruction can end with zeros, past the trimmed end
+        Ok((instructions, reader.position()))
+    }
+
+    /// Find where all the instructions start and which of them can be jumped to
+    pub fn instruction_index(&self) -> Result<InstructionIndex> {
+        let (instructions, end) = self.read_instructions()?;
+
+        Ok(InstructionIndex {
+            end,
+            jump_targets: instructions
+                .iter()
+                .flat_map(|(_, instruction)| instruction.jump_targets())
+                .copied()
+                .collect(),
+            offsets: instructions
+                .into_iter()
+                .map(|(position, _)| position)
+                .collect(),
+        })
+    }
+}
+
+/// Instruction boundaries in the scenario code, for mapping the code addresses back to instructions
+#[derive(Debug)]
+pub struct InstructionIndex {
+    /// Sorted, as the instructions are decoded sequentially
+    offsets: Vec<CodeAddress>,
+    /// Where the last instruction ends
+    end: CodeAddress,
+    jump_targets: BTreeSet<CodeAddress>,
+}
+
+impl InstructionIndex {
+    /// The start offsets of all the instructions, in order
+    pub fn offsets(&self) -> &[CodeAddress] {
+        &self.offsets
+    }
+
+    /// Whether an instruction starts at the address (as opposed to the address pointing into the middle of one)
+    pub fn is_instruction_start(&self, address: CodeAddress) -> bool {
+        self.offsets.binary_search(&address).is_ok()
+    }
+
+    /// The start of the instruction the address points into, `None` if it's outside the code
+    pub fn instruction_containing(&self, address: CodeAddress) -> Option<CodeAddress> {
+        if address >= self.end {
+            return None;
+        }
+
+        match self.offsets.binary_search(&address) {
+            Ok(index) => Some(self.offsets[index]),
+            Err(0) => None,
+            Err(index) => Some(self.offsets[index - 1]),
+        }
+    }
+
+    /// All the addresses jumped to or called by the instructions
+    pub fn jump_targets(&self) -> &BTreeSet<CodeAddress> {
+        &self.jump_targets
+    }
 }
 
 pub struct InstructionReader {
@@ -120,3 +210,62 @@ impl InstructionReader {
         self.cur.set_position(offset.0 as u64);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use super::{Scenario, instruction_elements::CodeAddress};
+
+    /// A scenario with no info tables and the code calling a subroutine jumping back into the middle of the caller
+    fn scenario() -> Scenario {
+        const CODE_OFFSET: u32 = 88;
+        let code = [
+            // 88: gosub 94
+            &[0x48, 94, 0, 0, 0][..],
+            // 93: return
+            &[0x50],
+            // 94: j 93
+            &[0x47, 93, 0, 0, 0],
+        ]
+        .concat();
+
+        let size = CODE_OFFSET + code.len() as u32;
+        let mut data = b"SNR ".to_vec();
+        for value in [size, 0, 6, 19, 0, 0, 0, CODE_OFFSET] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        // all the info table pointers are null
+        data.resize(CODE_OFFSET as usize, 0);
+        data.extend_from_slice(&code);
+
+        Scenario::new(Bytes::from(data)).unwrap()
+    }
+
+    #[test]
+    fn instruction_index() {
+        let index = scenario().instruction_index().unwrap();
+
+        assert_eq!(index.offsets(), [88, 93, 94].map(CodeAddress));
+        assert!(index.is_instruction_start(CodeAddress(93)));
+        // the address of the jump target is not an instruction
+        assert!(!index.is_instruction_start(CodeAddress(89)));
+        assert_eq!(
+            index.instruction_containing(CodeAddress(89)),
+            Some(CodeAddress(88))
+        );
+        assert_eq!(
+            index.instruction_containing(CodeAddress(98)),
+            Some(CodeAddress(94))
+        );
+        assert_eq!(index.instruction_containing(CodeAddress(10)), None);
+        // the code ends at 99
+        assert_eq!(index.instruction_containing(CodeAddress(99)), None);
+        assert_eq!(index.instruction_containing(CodeAddress(200)), None);
+
+        assert_eq!(
+            index.jump_targets().iter().copied().collect::<Vec<_>>(),
+            [93, 94].map(CodeAddress)
+        );
+    }
+}
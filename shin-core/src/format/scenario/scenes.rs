@@ -0,0 +1,389 @@
+//! Groups a linear instruction stream into scenes and messages, for readable scenario dumps.
+//!
+//! A scene spans from a `MSGINIT` to a `MSGCLOSE`, and each `MSGSET` in it starts a message, collecting the instructions up to the next one. The grouping follows the instruction order, not the control flow, so it's only a reading aid.
+
+use std::{fmt, io};
+
+use crate::{
+    format::scenario::{
+        info::{BgmId, MaskIdOpt, ScenarioInfoTables, SeId},
+        instruction_elements::{CodeAddress, FromNumber, NumberSpec, UntypedNumberSpec},
+        instructions::Instruction,
+    },
+    vm::command::{CompiletimeCommand, spec::ParamValue},
+};
+
+pub type AddressedInstruction = (CodeAddress, Instruction);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScenarioItem {
+    Instruction(AddressedInstruction),
+    Scene(Scene),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Scene {
+    /// The `MSGINIT` starting the scene, `None` if a message was shown without one
+    pub init: Option<AddressedInstruction>,
+    /// Instructions before the first message
+    pub preamble: Vec<AddressedInstruction>,
+    pub messages: Vec<Message>,
+    /// The `MSGCLOSE` ending the scene, `None` if the scene was cut short by the next `MSGINIT` or the end of the stream
+    pub close: Option<AddressedInstruction>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    /// The `MSGSET` showing the message
+    pub set: AddressedInstruction,
+    /// Instructions executed after showing the message, until the next one
+    pub instructions: Vec<AddressedInstruction>,
+}
+
+fn command(instruction: &Instruction) -> Option<&CompiletimeCommand> {
+    match instruction {
+        Instruction::Command(command) => Some(command),
+        _ => None,
+    }
+}
+
+/// Groups the instructions into scenes
+///
+/// The unbalanced boundaries are handled gracefully: a `MSGSET` outside of a scene starts an implicit one, a `MSGINIT` inside a scene ends it, and a stray `MSGCLOSE` is kept as a plain instruction.
+/// Waits (like `MSGWAIT` or `WAIT`) don't affect the grouping, they are kept with the message that precedes them, even if it's not the one they are waiting for.
+pub fn group_scenes(
+    instructions: impl IntoIterator<Item = AddressedInstruction>,
+) -> Vec<ScenarioItem> {
+    let mut items = Vec::new();
+    let mut scene: Option<Scene> = None;
+
+    for (address, instruction) in instructions {
+        match command(&instruction) {
+            Some(CompiletimeCommand::MSGINIT(_)) => {
+                if let Some(scene) = scene.take() {
+                    items.push(ScenarioItem::Scene(scene));
+                }
+                scene = Some(Scene {
+                    init: Some((address, instruction)),
+                    ..Scene::default()
+                });
+            }
+            Some(CompiletimeCommand::MSGSET(_)) => {
+                scene
+                    .get_or_insert_with(Scene::default)
+                    .messages
+                    .push(Message {
+                        set: (address, instruction),
+                        instructions: Vec::new(),
+                    });
+            }
+            Some(CompiletimeCommand::MSGCLOSE(_)) if scene.is_some() => {
+                let mut scene = scene.take().unwrap();
+                scene.close = Some((address, instruction));
+                items.push(ScenarioItem::Scene(scene));
+            }
+            _ => match &mut scene {
+                Some(scene) => match scene.messages.last_mut() {
+                    Some(message) => message.instructions.push((address, instruction)),
+                    None => scene.preamble.push((address, instruction)),
+                },
+                None => items.push(ScenarioItem::Instruction((address, instruction))),
+            },
+        }
+    }
+
+    if let Some(scene) = scene {
+        items.push(ScenarioItem::Scene(scene));
+    }
+
+    items
+}
+
+/// Resolves the ids used by the commands to names
+pub trait IdNames {
+    fn bgm_name(&self, id: BgmId) -> Option<&str>;
+    fn se_name(&self, id: SeId) -> Option<&str>;
+    fn mask_name(&self, id: MaskIdOpt) -> Option<&str>;
+}
+
+impl IdNames for ScenarioInfoTables {
+    fn bgm_name(&self, id: BgmId) -> Option<&str> {
+        let index = id.into_i32() as usize;
+        self.bgm_info.get(index).map(|bgm| bgm.name.as_str())
+    }
+
+    fn se_name(&self, id: SeId) -> Option<&str> {
+        let index = id.into_i32() as usize;
+        self.se_info.get(index).map(|se| se.name.as_str())
+    }
+
+    fn mask_name(&self, id: MaskIdOpt) -> Option<&str> {
+        let index = id.repr()?.into_i32() as usize;
+        self.mask_info.get(index).map(|mask| mask.name.as_str())
+    }
+}
+
+/// Doesn't resolve any names, for when the info tables are not available
+impl IdNames for () {
+    fn bgm_name(&self, _: BgmId) -> Option<&str> {
+        None
+    }
+
+    fn se_name(&self, _: SeId) -> Option<&str> {
+        None
+    }
+
+    fn mask_name(&self, _: MaskIdOpt) -> Option<&str> {
+        None
+    }
+}
+
+fn constant<T: 'static>(value: &dyn ParamValue) -> Option<i32> {
+    match value
+        .as_any()
+        .downcast_ref::<NumberSpec<T>>()?
+        .into_untyped()
+    {
+        // the ids are stored as i16, the larger values can't refer to anything
+        UntypedNumberSpec::Constant(value) if i16::try_from(value).is_ok() => Some(value),
+        _ => None,
+    }
+}
+
+fn resolve_name<'a>(names: &'a dyn IdNames, value: &dyn ParamValue) -> Option<&'a str> {
+    if let Some(id) = constant::<BgmId>(value).filter(|&id| id >= 0) {
+        names.bgm_name(BgmId::from_number(id))
+    } else if let Some(id) = constant::<SeId>(value).filter(|&id| id >= 0) {
+        names.se_name(SeId::from_number(id))
+    } else if let Some(id) = constant::<MaskIdOpt>(value) {
+        names.mask_name(MaskIdOpt::from_number(id))
+    } else {
+        None
+    }
+}
+
+/// Formats an instruction, naming the command parameters and resolving the ids
+struct InstructionDisplay<'a> {
+    instruction: &'a Instruction,
+    names: &'a dyn IdNames,
+}
+
+impl fmt::Display for InstructionDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(command) = command(self.instruction) else {
+            return write!(f, "{:?}", self.instruction);
+        };
+
+        let spec = command.spec();
+        write!(f, "{}", spec.name)?;
+        for (i, (param, value)) in spec.params.iter().zip(command.param_values()).enumerate() {
+            let separator = if i == 0 { " " } else { ", " };
+            write!(f, "{}{}={:?}", separator, param.name, value)?;
+            if let Some(name) = resolve_name(self.names, value) {
+                write!(f, " ({})", name)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn write_instruction<W: io::Write>(
+    output: &mut W,
+    names: &dyn IdNames,
+    depth: usize,
+    (address, instruction): &AddressedInstruction,
+) -> io::Result<()> {
+    writeln!(
+        output,
+        "{:08x} {:indent$}{}",
+        address.0,
+        "",
+        InstructionDisplay { instruction, names },
+        indent = depth * 2
+    )
+}
+
+/// Writes the grouped scenario, indenting the instructions by the scene and the message they belong to
+pub fn write_scenes<W: io::Write>(
+    output: &mut W,
+    names: &dyn IdNames,
+    items: &[ScenarioItem],
+) -> io::Result<()> {
+    for item in items {
+        let scene = match item {
+            ScenarioItem::Instruction(instruction) => {
+                write_instruction(output, names, 0, instruction)?;
+                continue;
+            }
+            ScenarioItem::Scene(scene) => scene,
+        };
+
+        match &scene.init {
+            Some(init) => write_instruction(output, names, 0, init)?,
+            None => writeln!(output, "{:8} (scene without MSGINIT)", "")?,
+        }
+        for instruction in &scene.preamble {
+            write_instruction(output, names, 1, instruction)?;
+        }
+        for message in &scene.messages {
+            write_instruction(output, names, 1, &message.set)?;
+            for instruction in &message.instructions {
+                write_instruction(output, names, 2, instruction)?;
+            }
+        }
+        match &scene.close {
+            Some(close) => write_instruction(output, names, 0, close)?,
+            None => writeln!(output, "{:8} (scene without MSGCLOSE)", "")?,
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IdNames, ScenarioItem, group_scenes, write_scenes};
+    use crate::format::{
+        scenario::{
+            info::{BgmId, MaskIdOpt, SeId},
+            instruction_elements::{CodeAddress, MessageId, NumberSpec, U8Bool, UntypedNumberSpec},
+            instructions::Instruction,
+        },
+        text::U16FixupString,
+    };
+    use crate::vm::command::{
+        CompiletimeCommand,
+        compiletime::{BGMPLAY, MSGCLOSE, MSGINIT, MSGSET, MSGWAIT, WAIT},
+    };
+
+    struct Names;
+
+    impl IdNames for Names {
+        fn bgm_name(&self, id: BgmId) -> Option<&str> {
+            (id.into_i32() == 3).then_some("umib_003")
+        }
+
+        fn se_name(&self, _: SeId) -> Option<&str> {
+            None
+        }
+
+        fn mask_name(&self, _: MaskIdOpt) -> Option<&str> {
+            None
+        }
+    }
+
+    fn constant<T>(value: i32) -> NumberSpec<T> {
+        NumberSpec::new(UntypedNumberSpec::Constant(value))
+    }
+
+    fn msgset(text: &str, auto_wait: bool) -> CompiletimeCommand {
+        CompiletimeCommand::MSGSET(MSGSET {
+            msg_id: MessageId(0),
+            auto_wait: U8Bool(auto_wait),
+            text: U16FixupString::new(text),
+        })
+    }
+
+    fn scenario() -> Vec<(CodeAddress, Instruction)> {
+        let msginit = CompiletimeCommand::MSGINIT(MSGINIT {
+            messagebox_style: constant(0),
+        });
+        let msgclose = CompiletimeCommand::MSGCLOSE(MSGCLOSE {
+            wait_for_close: U8Bool(true),
+        });
+        let bgmplay = CompiletimeCommand::BGMPLAY(BGMPLAY {
+            bgm_data_id: constant(3),
+            fade_in_time: constant(0),
+            no_repeat: constant(0),
+            volume: constant(1000),
+        });
+        let wait = CompiletimeCommand::WAIT(WAIT {
+            allow_interrupt: U8Bool(false),
+            wait_amount: constant(60),
+        });
+        let msgwait = CompiletimeCommand::MSGWAIT(MSGWAIT {
+            signal_num: NumberSpec::constant(-1),
+        });
+
+        [
+            bgmplay.clone(),
+            msginit.clone(),
+            wait.clone(),
+            msgset("first", true),
+            // the first message is not waited for, and its wait comes after the third one is shown
+            msgset("second", false),
+            wait.clone(),
+            msgset("third", true),
+            msgwait,
+            msgclose.clone(),
+            // a stray close
+            msgclose,
+            // a message without MSGINIT, cut short by the next scene
+            msgset("fourth", true),
+            msginit,
+            bgmplay,
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(i, command)| (CodeAddress(i as u32 * 0x10), Instruction::Command(command)))
+        .collect()
+    }
+
+    #[test]
+    fn scene_grouping() {
+        let items = group_scenes(scenario());
+
+        let shape = items
+            .iter()
+            .map(|item| match item {
+                ScenarioItem::Instruction(_) => "instruction".to_string(),
+                ScenarioItem::Scene(scene) => format!(
+                    "scene init={} preamble={} messages={:?} close={}",
+                    scene.init.is_some(),
+                    scene.preamble.len(),
+                    scene
+                        .messages
+                        .iter()
+                        .map(|message| message.instructions.len())
+                        .collect::<Vec<_>>(),
+                    scene.close.is_some(),
+                ),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            shape,
+            [
+                "instruction",
+                "scene init=true preamble=1 messages=[0, 1, 1] close=true",
+                "instruction",
+                "scene init=false preamble=0 messages=[0] close=false",
+                "scene init=true preamble=1 messages=[] close=false",
+            ]
+        );
+    }
+
+    #[test]
+    fn scenes_formatting() {
+        let mut output = Vec::new();
+        write_scenes(&mut output, &Names, &group_scenes(scenario())[..2]).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            [
+                "00000000 BGMPLAY bgm_data_id=3 (umib_003), fade_in_time=0, no_repeat=0, volume=1000",
+                "00000010 MSGINIT messagebox_style=0",
+                "00000020   WAIT allow_interrupt=false, wait_amount=60",
+                "00000030   MSGSET msg_id=0, auto_wait=true, text=\"first\"",
+                "00000040   MSGSET msg_id=0, auto_wait=false, text=\"second\"",
+                "00000050     WAIT allow_interrupt=false, wait_amount=60",
+                "00000060   MSGSET msg_id=0, auto_wait=true, text=\"third\"",
+                "00000070     MSGWAIT signal_num=-1",
+                "00000080 MSGCLOSE wait_for_close=true",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+}
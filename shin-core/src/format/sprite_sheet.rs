@@ -0,0 +1,218 @@
+//! Support for sprite sheets, packing many small sprites into a single texture.
+//!
+//! This is not a format used by the original game, it's our own, only stored inside of the [animations](super::animation).
+//! It consists of a header with the sprite index, followed by the uncompressed RGBA8 texels.
+
+use std::{collections::HashMap, io};
+
+use anyhow::{Result, bail};
+use binrw::{BinRead, BinWrite};
+use image::RgbaImage;
+use itertools::Itertools;
+
+use crate::format::text::ZeroString;
+
+#[derive(BinRead, BinWrite, Debug)]
+#[brw(little, magic = b"SPSH")]
+struct SpriteSheetHeader {
+    #[br(assert(version == 1))]
+    #[bw(assert(*version == 1))]
+    version: u32,
+    width: u16,
+    height: u16,
+    count: u32,
+
+    #[br(count = count)]
+    index: Vec<SpriteIndexEntry>,
+}
+
+#[derive(BinRead, BinWrite, Debug)]
+struct SpriteIndexEntry {
+    name: ZeroString,
+    rect: SpriteRect,
+}
+
+/// Position of a sprite in the sheet texture, in texels
+#[derive(BinRead, BinWrite, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SpriteRect {
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl SpriteRect {
+    fn fits_in(&self, width: u32, height: u32) -> bool {
+        self.x as u32 + self.width as u32 <= width && self.y as u32 + self.height as u32 <= height
+    }
+}
+
+pub struct SpriteSheet {
+    pub texture: RgbaImage,
+    pub sprites: HashMap<String, SpriteRect>,
+}
+
+impl SpriteSheet {
+    pub fn get_sprite(&self, name: &str) -> Option<SpriteRect> {
+        self.sprites.get(name).copied()
+    }
+
+    /// Multiply the color channels of the texture by its alpha, which is how the layers expect the textures to be
+    pub fn premultiply_alpha(&mut self) {
+        for pixel in self.texture.pixels_mut() {
+            let alpha = pixel[3] as u32;
+            for channel in &mut pixel.0[..3] {
+                *channel = ((*channel as u32 * alpha + 127) / 255) as u8;
+            }
+        }
+    }
+}
+
+pub fn read_sprite_sheet(source: &[u8]) -> Result<SpriteSheet> {
+    let mut source = io::Cursor::new(source);
+    let header = SpriteSheetHeader::read(&mut source)?;
+
+    let (width, height) = (header.width as u32, header.height as u32);
+    let texels = &source.get_ref()[source.position() as usize..];
+    let Some(texture) = RgbaImage::from_raw(width, height, texels.to_vec()) else {
+        bail!(
+            "Sprite sheet texels are truncated: expected {}x{} RGBA8 texels, got {} bytes",
+            width,
+            height,
+            texels.len()
+        );
+    };
+
+    let mut sprites = HashMap::with_capacity(header.index.len());
+    for SpriteIndexEntry { name, rect } in header.index {
+        if !rect.fits_in(width, height) {
+            bail!(
+                "Sprite {:?} ({:?}) is outside of the {}x{} sheet",
+                name.0,
+                rect,
+                width,
+                height
+            );
+        }
+        if sprites.insert(name.0, rect).is_some() {
+            bail!("Duplicate sprite name in the sheet");
+        }
+    }
+
+    Ok(SpriteSheet { texture, sprites })
+}
+
+pub fn write_sprite_sheet<W: io::Write + io::Seek>(
+    sheet: &SpriteSheet,
+    dest: &mut W,
+) -> Result<()> {
+    let (width, height) = sheet.texture.dimensions();
+
+    let index = sheet
+        .sprites
+        .iter()
+        // keep the output deterministic
+        .sorted_by(|(left, _), (right, _)| left.cmp(right))
+        .map(|(name, &rect)| SpriteIndexEntry {
+            name: ZeroString::new(name.clone()),
+            rect,
+        })
+        .collect::<Vec<_>>();
+
+    let header = SpriteSheetHeader {
+        version: 1,
+        width: width.try_into()?,
+        height: height.try_into()?,
+        count: index.len().try_into()?,
+        index,
+    };
+
+    header.write(dest)?;
+    dest.write_all(sheet.texture.as_raw())?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, io::Cursor};
+
+    use image::{Rgba, RgbaImage};
+
+    use super::{SpriteRect, SpriteSheet, read_sprite_sheet, write_sprite_sheet};
+
+    fn sheet() -> SpriteSheet {
+        let texture = RgbaImage::from_fn(8, 4, |x, _| {
+            if x < 4 {
+                Rgba([255, 0, 0, 255])
+            } else {
+                Rgba([0, 255, 0, 128])
+            }
+        });
+        let sprites = HashMap::from([
+            ("red".to_string(), SpriteRect {
+                x: 0,
+                y: 0,
+                width: 4,
+                height: 4,
+            }),
+            ("green".to_string(), SpriteRect {
+                x: 4,
+                y: 1,
+                width: 4,
+                height: 3,
+            }),
+        ]);
+
+        SpriteSheet { texture, sprites }
+    }
+
+    fn encode(sheet: &SpriteSheet) -> Vec<u8> {
+        let mut encoded = Cursor::new(Vec::new());
+        write_sprite_sheet(sheet, &mut encoded).unwrap();
+        encoded.into_inner()
+    }
+
+    #[test]
+    fn round_trip() {
+        let sheet = sheet();
+        let decoded = read_sprite_sheet(&encode(&sheet)).unwrap();
+
+        assert_eq!(decoded.texture, sheet.texture);
+        assert_eq!(decoded.sprites, sheet.sprites);
+        assert_eq!(
+            decoded.get_sprite("green"),
+            Some(SpriteRect {
+                x: 4,
+                y: 1,
+                width: 4,
+                height: 3,
+            })
+        );
+        assert_eq!(decoded.get_sprite("blue"), None);
+    }
+
+    #[test]
+    fn premultiply_alpha() {
+        let mut sheet = sheet();
+        sheet.premultiply_alpha();
+
+        assert_eq!(sheet.texture.get_pixel(0, 0), &Rgba([255, 0, 0, 255]));
+        assert_eq!(sheet.texture.get_pixel(7, 3), &Rgba([0, 128, 0, 128]));
+    }
+
+    #[test]
+    fn rejects_sprite_outside_of_sheet() {
+        let mut sheet = sheet();
+        sheet.sprites.get_mut("green").unwrap().width = 5;
+
+        assert!(read_sprite_sheet(&encode(&sheet)).is_err());
+    }
+
+    #[test]
+    fn rejects_truncated_texels() {
+        let encoded = encode(&sheet());
+
+        assert!(read_sprite_sheet(&encoded[..encoded.len() - 1]).is_err());
+    }
+}
@@ -221,8 +221,16 @@ pub fn encode_string_fixup(s: &str) -> String {
         .collect()
 }
 
-/// Apply transformations that the game does to some strings
-/// This basically involves replacing  
+/// Find a char that doesn't survive [`encode_string_fixup`] followed by [`decode_string_fixup`]
+///
+/// Those are the chars that the fixup produces (mostly half-width katakana), as they are turned into hiragana when decoding.
+pub fn find_unencodable_fixup_char(s: &str) -> Option<char> {
+    s.chars().find(|c| FIXUP_DECODE_TABLE.contains_key(c))
+}
+
+/// Undo the transformations that the game does to some strings (see [`encode_string_fixup`])
+///
+/// The inline message commands are made of ASCII chars, which are never transformed, so they are kept intact
 pub fn decode_string_fixup(s: &str) -> String {
     s.chars()
         .map(|c| FIXUP_DECODE_TABLE.get(&c).copied().unwrap_or(c))
@@ -244,7 +252,57 @@ mod tests {
         assert_eq!(encoded, b"\x82\xa0\x82\xa2\x82\xa4\x82\xa6\x82\xa8");
     }
 
-    // TODO: cover the fix-ups with tests
+    #[test]
+    fn fixup_pairs() {
+        let pairs = [
+            ("", ""),
+            ("HELLO", "HELLO"),
+            ("かわいい", "ｶﾜｲｲ"),
+            ("「うみねこのなく頃に」", "｢ｳﾐﾈｺﾉﾅｸ頃ﾆ｣"),
+            ("ええっ、？！…　。", "ｴｴｯ､ﾟﾞ･\u{f8f0}｡"),
+            // katakana and the hiragana with dakuten are left alone
+            ("ミクだ", "ミクだ"),
+        ];
+
+        for (decoded, encoded) in pairs {
+            assert_eq!(encode_string_fixup(decoded), encoded);
+            assert_eq!(decode_string_fixup(encoded), decoded);
+        }
+    }
+
+    #[test]
+    fn fixup_keeps_inline_commands() {
+        use crate::layout::{MessageTextParser, ParsedCommand};
+
+        let pairs = [
+            (
+                "@v00/10100001.「きひひひひ！」@k@r",
+                "@v00/10100001.｢ｷﾋﾋﾋﾋﾞ｣@k@r",
+            ),
+            ("@bちかん.@<痴漢@>だ", "@bﾁｶﾝ.@<痴漢@>だ"),
+            ("@c$f00.あ@c.@w500.い@|", "@c$f00.ｱ@c.@w500.ｲ@|"),
+        ];
+
+        for (decoded, encoded) in pairs {
+            assert_eq!(encode_string_fixup(decoded), encoded);
+            assert_eq!(decode_string_fixup(encoded), decoded);
+        }
+
+        // the decoded commands parse the same way as the ones written by hand
+        let message = decode_string_fixup("@v00/1.@bﾁｶﾝ.@<痴漢@>");
+        let commands = MessageTextParser::new(&message).collect::<Vec<_>>();
+        assert_eq!(
+            commands,
+            vec![
+                ParsedCommand::Voice("00/1".to_string()),
+                ParsedCommand::RubiContent("ちかん".to_string()),
+                ParsedCommand::RubiBaseStart,
+                ParsedCommand::Char('痴'),
+                ParsedCommand::Char('漢'),
+                ParsedCommand::RubiBaseEnd,
+            ]
+        );
+    }
 
     // these files were auto-generated by a script
     // they check that the Shift_JIS decoder works the same way the original engine does it
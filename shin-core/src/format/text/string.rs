@@ -11,6 +11,8 @@ use crate::{
 pub trait StringFixup {
     fn encode(string: String) -> String;
     fn decode(string: String) -> String;
+    /// Find a char that would be decoded differently after being encoded
+    fn find_unencodable_char(string: &str) -> Option<char>;
 }
 
 #[derive(Debug)]
@@ -22,6 +24,9 @@ impl StringFixup for NoFixup {
     fn decode(string: String) -> String {
         string
     }
+    fn find_unencodable_char(_: &str) -> Option<char> {
+        None
+    }
 }
 
 #[derive(Debug)]
@@ -34,6 +39,10 @@ impl StringFixup for WithFixup {
     fn decode(string: String) -> String {
         text::decode_string_fixup(&string)
     }
+
+    fn find_unencodable_char(string: &str) -> Option<char> {
+        text::find_unencodable_fixup_char(string)
+    }
 }
 
 pub trait StringLengthDesc:
@@ -156,6 +165,17 @@ impl<L: StringLengthDesc, F: StringFixup> BinWrite for SJisString<L, F> {
     ) -> BinResult<()> {
         let pos = writer.stream_position()?;
 
+        // otherwise the string would silently change when read back
+        if let Some(c) = F::find_unencodable_char(&self.0) {
+            return Err(binrw::Error::AssertFail {
+                pos,
+                message: format!(
+                    "The string contains a char that can't be encoded with the fixup: {} (U+{:04X})",
+                    c, c as u32
+                ),
+            });
+        }
+
         // TODO: extra allocation ALWAYS
         let fixed_up = F::encode(self.0.clone());
 
@@ -208,7 +228,10 @@ impl<L: StringLengthDesc, F: StringFixup> IntoRuntimeForm for SJisString<L, F> {
 
 #[cfg(test)]
 mod tests {
-    use binrw::{io::NoSeek, BinWrite};
+    use std::io::Cursor;
+
+    use binrw::{io::NoSeek, BinRead, BinWrite};
+    use rand::{Rng, SeedableRng};
 
     use super::{U16FixupString, U16String, U8FixupString, U8String, ZeroString};
     use crate::format::test_util::assert_enc_dec_pair;
@@ -258,5 +281,59 @@ mod tests {
         assert_enc_dec_pair(&U16String::new("かわいい"), "090082a982ed82a282a200");
         assert_enc_dec_pair(&U16FixupString::new("かわいい"), "0500b6dcb2b200");
         assert_enc_dec_pair(&U16FixupString::new("日本"), "050093fa967b00");
+        // the inline message commands are not affected by the fixup
+        assert_enc_dec_pair(
+            &U16FixupString::new("@r@kかわいい"),
+            "09004072406bb6dcb2b200",
+        );
+        assert_enc_dec_pair(
+            &U16FixupString::new("@bかわ.@<川@>"),
+            "0c004062b6dc2e403c90ec403e00",
+        );
+    }
+
+    fn round_trip<T: for<'a> BinRead<Args<'a> = ()> + for<'a> BinWrite<Args<'a> = ()>>(value: &T) -> T {
+        let mut encoded = NoSeek::new(Vec::new());
+        value.write_le(&mut encoded).unwrap();
+        T::read_le(&mut Cursor::new(encoded.into_inner())).unwrap()
+    }
+
+    #[test]
+    fn fixup_round_trip() {
+        // the chars affected by the fixup, mixed with the inline commands and the ones left alone
+        let alphabet = "「」ぁぃぅぇぉゃゅょあいうえおかきくけこさしすせそたちつてとなにぬねのはひふへほまみむめもやゆよらりるれろわをんーっ、？！…　。だミク日本@rkbw<>|.$/0123456789ABCabc "
+            .chars()
+            .collect::<Vec<_>>();
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0x42);
+
+        for _ in 0..1000 {
+            let len = rng.random_range(0..100);
+            let string = (0..len)
+                .map(|_| alphabet[rng.random_range(0..alphabet.len())])
+                .collect::<String>();
+
+            let u8_string = U8FixupString::new(&string);
+            assert_eq!(round_trip(&u8_string), u8_string);
+            let u16_string = U16FixupString::new(&string);
+            assert_eq!(round_trip(&u16_string), u16_string);
+        }
+    }
+
+    #[test]
+    fn enc_fixup_unencodable() {
+        // half-width katakana would be read back as hiragana
+        let err = U16FixupString::new("ｶわいい")
+            .write_le(&mut NoSeek::new(Vec::new()))
+            .unwrap_err();
+        assert_eq!(
+            format!("{:?}", err),
+            "The string contains a char that can't be encoded with the fixup: ｶ (U+FF76) at 0x0"
+        );
+
+        // but it's fine without the fixup
+        assert_eq!(
+            round_trip(&U16String::new("ｶわいい")),
+            U16String::new("ｶわいい")
+        );
     }
 }
@@ -1,8 +1,8 @@
-//! Support for decoding TXA texture archives.
+//! Support for decoding and encoding TXA texture archives.
 
-use std::collections::HashMap;
+use std::{collections::HashMap, io};
 
-use anyhow::Result;
+use anyhow::{Result, bail};
 use binrw::{BinRead, BinWrite};
 use image::RgbaImage;
 use rayon::prelude::*;
@@ -39,6 +39,7 @@ struct TxaIndexEntry {
     name: ZeroString,
 }
 
+#[derive(Default)]
 pub struct TextureArchive {
     pub textures: Vec<RgbaImage>,
     pub name_to_index: HashMap<String, usize>,
@@ -55,6 +56,26 @@ impl TextureArchive {
             .get(&vindex)
             .map(|&i| &self.textures[i])
     }
+
+    /// Add a texture to the end of the archive. Its virtual index is its position in the archive
+    pub fn add_texture(&mut self, name: impl Into<String>, texture: RgbaImage) -> Result<()> {
+        let name = name.into();
+        let index = self.textures.len();
+        let vindex = u16::try_from(index)?;
+
+        if self.name_to_index.contains_key(&name) {
+            bail!("Duplicate texture name: {:?}", name);
+        }
+        if self.vindex_to_index.contains_key(&vindex) {
+            bail!("Virtual index {} is already used", vindex);
+        }
+
+        self.textures.push(texture);
+        self.name_to_index.insert(name, index);
+        self.vindex_to_index.insert(vindex, index);
+
+        Ok(())
+    }
 }
 
 fn decode_texture(
@@ -74,6 +95,147 @@ fn decode_texture(
     Ok(image)
 }
 
+/// Encodes a texture with a dictionary and inline alpha, without compression
+///
+/// Returns `None` when the texture has more than 256 colors, which don't fit in the dictionary.
+fn encode_dict_texture(texture: &RgbaImage) -> Option<Vec<u8>> {
+    let (width, height) = texture.dimensions();
+    let stride = ((width + 3) & 0xfffffffc) as usize;
+
+    let mut dictionary = Vec::<[u8; 4]>::new();
+    let mut dictionary_index = HashMap::new();
+    let mut indices = vec![0u8; stride * height as usize];
+
+    for (x, y, pixel) in texture.enumerate_pixels() {
+        let index = *dictionary_index.entry(pixel.0).or_insert_with(|| {
+            dictionary.push(pixel.0);
+            dictionary.len() - 1
+        });
+        indices[y as usize * stride + x as usize] = u8::try_from(index).ok()?;
+    }
+
+    let mut data = vec![0u8; 0x400];
+    data[..dictionary.len() * 4].copy_from_slice(dictionary.as_flattened());
+    data.extend(indices);
+
+    Some(data)
+}
+
+/// Encodes a texture as the differences between the neighbouring pixels, without compression
+fn encode_differential_texture(texture: &RgbaImage) -> Vec<u8> {
+    let (width, height) = texture.dimensions();
+    let stride = ((width * 4 + 0xf) & 0xfffffff0) as usize;
+
+    let mut data = vec![0u8; stride * height as usize];
+    for (x, y, pixel) in texture.enumerate_pixels() {
+        let base = match (x, y) {
+            (0, 0) => [0; 4],
+            (x, 0) => texture.get_pixel(x - 1, 0).0,
+            (x, y) => texture.get_pixel(x, y - 1).0,
+        };
+        let offset = y as usize * stride + x as usize * 4;
+        for (i, (&channel, &base)) in pixel.0.iter().zip(&base).enumerate() {
+            data[offset + i] = channel.wrapping_sub(base);
+        }
+    }
+
+    data
+}
+
+/// Encodes and writes a texture archive.
+///
+/// The textures are stored uncompressed. They are dictionary-encoded, unless one of them has more than 256 colors, in which case the whole archive is differential-encoded.
+pub fn write_texture_archive<W: io::Write>(archive: &TextureArchive, dest: &mut W) -> Result<()> {
+    const DATA_ALIGNMENT: u32 = 0x10;
+
+    let mut names = vec![None; archive.textures.len()];
+    for (name, &index) in &archive.name_to_index {
+        names[index] = Some(name.as_str());
+    }
+    let mut vindices = vec![None; archive.textures.len()];
+    for (&vindex, &index) in &archive.vindex_to_index {
+        vindices[index] = Some(vindex);
+    }
+
+    // the encoding is chosen for the whole archive
+    let dict_encoded = archive
+        .textures
+        .iter()
+        .map(encode_dict_texture)
+        .collect::<Option<Vec<_>>>();
+    let use_dict_encoding = dict_encoded.is_some();
+    let data = dict_encoded.unwrap_or_else(|| {
+        archive
+            .textures
+            .iter()
+            .map(encode_differential_texture)
+            .collect()
+    });
+
+    let mut index = Vec::with_capacity(archive.textures.len());
+    for (i, (texture, encoded)) in archive.textures.iter().zip(&data).enumerate() {
+        let (Some(name), Some(virtual_index)) = (names[i], vindices[i]) else {
+            bail!("Texture {} has no name or virtual index", i);
+        };
+
+        index.push(TxaIndexEntry {
+            entry_length: 0,
+            virtual_index,
+            width: texture.width().try_into()?,
+            height: texture.height().try_into()?,
+            data_offset: 0,
+            data_compressed_size: 0,
+            data_decompressed_size: encoded.len().try_into()?,
+            name: ZeroString::new(name),
+        });
+    }
+
+    for entry in &mut index {
+        // the entries are aligned to 4 bytes, count the padding in
+        // TODO: not sure whether the game does the same
+        let mut encoded = io::Cursor::new(Vec::new());
+        entry.write_le(&mut encoded)?;
+        entry.entry_length = encoded.get_ref().len().next_multiple_of(4).try_into()?;
+    }
+
+    let mut header = TxaHeader {
+        version: 2,
+        file_size: 0,
+        use_dict_encoding: use_dict_encoding.into(),
+        count: index.len().try_into()?,
+        max_decompressed_size: index
+            .iter()
+            .map(|entry| entry.data_decompressed_size)
+            .max()
+            .unwrap_or(0),
+        index_size: index.iter().map(|entry| entry.entry_length as u32).sum(),
+        index,
+    };
+
+    // the header size doesn't depend on the offsets, so measure it first
+    let mut encoded_header = io::Cursor::new(Vec::new());
+    header.write(&mut encoded_header)?;
+    let mut offset = (encoded_header.get_ref().len() as u32).next_multiple_of(DATA_ALIGNMENT);
+    for (entry, data) in header.index.iter_mut().zip(&data) {
+        entry.data_offset = offset;
+        offset = (offset + data.len() as u32).next_multiple_of(DATA_ALIGNMENT);
+    }
+    header.file_size = offset;
+
+    let mut output = io::Cursor::new(Vec::new());
+    header.write(&mut output)?;
+    let mut output = output.into_inner();
+    for (entry, data) in header.index.iter().zip(&data) {
+        output.resize(entry.data_offset as usize, 0);
+        output.extend_from_slice(data);
+    }
+    output.resize(header.file_size as usize, 0);
+
+    dest.write_all(&output)?;
+
+    Ok(())
+}
+
 /// Reads and decodes a texture archive.
 ///
 /// NOTE: this will spawn rayon tasks and block waiting for them. If you don't want blocking wrap it with [`shin_tasks::compute::spawn`].
@@ -121,3 +283,76 @@ pub fn read_texture_archive(source: &[u8]) -> Result<TextureArchive> {
         vindex_to_index,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use image::{Rgba, RgbaImage};
+
+    use super::{TextureArchive, read_texture_archive, write_texture_archive};
+
+    fn archive() -> TextureArchive {
+        let mut archive = TextureArchive::default();
+        archive
+            .add_texture(
+                "button",
+                RgbaImage::from_fn(6, 3, |x, y| Rgba([x as u8 * 40, y as u8 * 80, 255, 255])),
+            )
+            .unwrap();
+        // the width is not a multiple of 4, so the rows are padded
+        archive
+            .add_texture(
+                "cursor",
+                RgbaImage::from_fn(5, 2, |x, _| Rgba([255, 0, 0, x as u8 * 60])),
+            )
+            .unwrap();
+        archive
+    }
+
+    fn encode(archive: &TextureArchive) -> Vec<u8> {
+        let mut encoded = Vec::new();
+        write_texture_archive(archive, &mut encoded).unwrap();
+        encoded
+    }
+
+    #[test]
+    fn round_trip() {
+        let archive = archive();
+        let encoded = encode(&archive);
+        let decoded = read_texture_archive(&encoded).unwrap();
+
+        assert_eq!(decoded.textures, archive.textures);
+        assert_eq!(decoded.name_to_index, archive.name_to_index);
+        assert_eq!(decoded.vindex_to_index, archive.vindex_to_index);
+
+        // rebuilding the archive that was read gives the same bytes
+        assert_eq!(encode(&decoded), encoded);
+    }
+
+    #[test]
+    fn many_colors_round_trip() {
+        let mut archive = archive();
+        // 400 colors, too many for the dictionary
+        archive
+            .add_texture(
+                "background",
+                RgbaImage::from_fn(20, 20, |x, y| Rgba([x as u8 * 12, y as u8 * 12, 7, 200])),
+            )
+            .unwrap();
+
+        let encoded = encode(&archive);
+        // the whole archive falls back to the differential encoding
+        assert_eq!(encoded[12..16], 0u32.to_le_bytes());
+
+        let decoded = read_texture_archive(&encoded).unwrap();
+        assert_eq!(decoded.textures, archive.textures);
+        assert_eq!(encode(&decoded), encoded);
+    }
+
+    #[test]
+    fn duplicate_names_are_rejected() {
+        let mut archive = archive();
+
+        assert!(archive.add_texture("cursor", RgbaImage::new(1, 1)).is_err());
+        assert_eq!(archive.textures.len(), 2);
+    }
+}
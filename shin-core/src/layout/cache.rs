@@ -0,0 +1,150 @@
+use std::hash::{Hash, Hasher};
+
+use indexmap::{Equivalent, IndexMap};
+
+/// Borrowed form of the cache key, allowing lookups without allocating a `String`
+struct KeyRef<'a, S>(&'a str, &'a S);
+
+impl<S: Hash> Hash for KeyRef<'_, S> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // NB: must match the `Hash` impl of `(String, S)`
+        self.0.hash(state);
+        self.1.hash(state);
+    }
+}
+
+impl<S: Eq> Equivalent<(String, S)> for KeyRef<'_, S> {
+    fn equivalent(&self, key: &(String, S)) -> bool {
+        self.0 == key.0 && *self.1 == key.1
+    }
+}
+
+/// Keeps the layouts of recently drawn text, so that text not changing between frames (like UI labels) is laid out only once.
+///
+/// The entries are keyed by the text and its style (anything that affects the layout: fonts, [`LayoutParams`](super::LayoutParams), etc).
+/// Changing either of them leads to the text being laid out again.
+///
+/// The cache holds at most `capacity` entries, evicting the least recently used one when full.
+pub struct LayoutCache<S, T> {
+    capacity: usize,
+    /// Ordered from the least to the most recently used
+    entries: IndexMap<(String, S), T>,
+    layout_count: u64,
+}
+
+impl<S: Hash + Eq + Clone, T> LayoutCache<S, T> {
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "LayoutCache capacity must be non-zero");
+
+        Self {
+            capacity,
+            entries: IndexMap::with_capacity(capacity),
+            layout_count: 0,
+        }
+    }
+
+    /// Returns the cached layout of `text` in `style`, calling `layout` to produce it if there is none.
+    pub fn get_or_layout(&mut self, text: &str, style: &S, layout: impl FnOnce() -> T) -> &T {
+        let index = match self.entries.get_index_of(&KeyRef(text, style)) {
+            Some(index) => {
+                let last = self.entries.len() - 1;
+                self.entries.move_index(index, last);
+                last
+            }
+            None => {
+                if self.entries.len() == self.capacity {
+                    self.entries.shift_remove_index(0);
+                }
+                self.layout_count += 1;
+
+                let (index, _) = self
+                    .entries
+                    .insert_full((text.to_string(), style.clone()), layout());
+                index
+            }
+        };
+
+        &self.entries[index]
+    }
+
+    /// Drops the cached layout of `text` in `style`, if any.
+    pub fn invalidate(&mut self, text: &str, style: &S) {
+        self.entries.shift_remove(&KeyRef(text, style));
+    }
+
+    /// Drops all the cached layouts, e.g. after the fonts have changed.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// How many times the text had to be laid out, useful for debugging
+    pub fn layout_count(&self) -> u64 {
+        self.layout_count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LayoutCache;
+    use crate::vm::command::types::MessageTextLayout;
+
+    fn frame(
+        cache: &mut LayoutCache<MessageTextLayout, String>,
+        text: &str,
+        style: MessageTextLayout,
+    ) -> String {
+        cache.get_or_layout(text, &style, || format!("{}:{:?}", text, style)).clone()
+    }
+
+    #[test]
+    fn same_label_every_frame() {
+        let mut cache = LayoutCache::new(4);
+
+        for _ in 0..100 {
+            assert_eq!(frame(&mut cache, "Start", MessageTextLayout::Center), "Start:Center");
+        }
+        // the layout is skipped after the first frame
+        assert_eq!(cache.layout_count(), 1);
+    }
+
+    #[test]
+    fn changes_invalidate() {
+        let mut cache = LayoutCache::new(4);
+
+        frame(&mut cache, "Start", MessageTextLayout::Center);
+        assert_eq!(frame(&mut cache, "Load", MessageTextLayout::Center), "Load:Center");
+        assert_eq!(frame(&mut cache, "Load", MessageTextLayout::Left), "Load:Left");
+        assert_eq!(cache.layout_count(), 3);
+
+        cache.invalidate("Start", &MessageTextLayout::Center);
+        frame(&mut cache, "Start", MessageTextLayout::Center);
+        assert_eq!(cache.layout_count(), 4);
+    }
+
+    #[test]
+    fn bounded() {
+        let mut cache = LayoutCache::new(2);
+
+        frame(&mut cache, "Start", MessageTextLayout::Center);
+        frame(&mut cache, "Load", MessageTextLayout::Center);
+        // "Start" becomes the most recently used one
+        frame(&mut cache, "Start", MessageTextLayout::Center);
+        frame(&mut cache, "Exit", MessageTextLayout::Center);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.layout_count(), 3);
+
+        // "Load" got evicted, but "Start" didn't
+        frame(&mut cache, "Start", MessageTextLayout::Center);
+        assert_eq!(cache.layout_count(), 3);
+        frame(&mut cache, "Load", MessageTextLayout::Center);
+        assert_eq!(cache.layout_count(), 4);
+    }
+}
@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use crate::format::font::{Font, GlyphInfo, GlyphTrait};
 
@@ -8,6 +8,14 @@ pub trait FontMetrics {
     fn get_descent(&self) -> u32;
 
     fn get_glyph_info(&self, codepoint: char) -> Option<GlyphInfo>;
+
+    /// Adjustment of the advance between the two characters, in font units (negative values bring them closer)
+    ///
+    /// Returns `None` if the font has no kerning data for the pair, in which case the characters are spaced by their advance widths alone.
+    fn get_kerning(&self, left: char, right: char) -> Option<i8> {
+        let _ = (left, right);
+        None
+    }
 }
 
 impl<T: FontMetrics> FontMetrics for Arc<T> {
@@ -22,6 +30,10 @@ impl<T: FontMetrics> FontMetrics for Arc<T> {
     fn get_glyph_info(&self, codepoint: char) -> Option<GlyphInfo> {
         (**self).get_glyph_info(codepoint)
     }
+
+    fn get_kerning(&self, left: char, right: char) -> Option<i8> {
+        (**self).get_kerning(left, right)
+    }
 }
 
 impl<T: FontMetrics> FontMetrics for &T {
@@ -36,6 +48,10 @@ impl<T: FontMetrics> FontMetrics for &T {
     fn get_glyph_info(&self, codepoint: char) -> Option<GlyphInfo> {
         (**self).get_glyph_info(codepoint)
     }
+
+    fn get_kerning(&self, left: char, right: char) -> Option<i8> {
+        (**self).get_kerning(left, right)
+    }
 }
 
 impl<G: GlyphTrait> FontMetrics for Font<G> {
@@ -48,6 +64,61 @@ impl<G: GlyphTrait> FontMetrics for Font<G> {
     }
 
     fn get_glyph_info(&self, codepoint: char) -> Option<GlyphInfo> {
-        Font::try_get_glyph_for_character(self, codepoint.try_into().unwrap()).map(|v| v.get_info())
+        let glyph_id = Font::get_glyph_id_or_notdef(self, codepoint as u32).unwrap_or_else(|id| id);
+        Font::get_glyph(self, glyph_id).map(|v| v.get_info())
+    }
+}
+
+/// Kerning adjustments for pairs of characters, in font units.
+#[derive(Debug, Default, Clone)]
+pub struct KerningTable {
+    pairs: HashMap<(char, char), i8>,
+}
+
+impl KerningTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, left: char, right: char, adjustment: i8) {
+        self.pairs.insert((left, right), adjustment);
+    }
+
+    pub fn get(&self, left: char, right: char) -> Option<i8> {
+        self.pairs.get(&(left, right)).copied()
+    }
+}
+
+/// Font metrics supplemented with a kerning table.
+///
+/// The FNT format doesn't store any kerning, so it has to come from elsewhere.
+pub struct KernedFont<F> {
+    font: F,
+    kerning: KerningTable,
+}
+
+impl<F: FontMetrics> KernedFont<F> {
+    pub fn new(font: F, kerning: KerningTable) -> Self {
+        Self { font, kerning }
+    }
+}
+
+impl<F: FontMetrics> FontMetrics for KernedFont<F> {
+    fn get_ascent(&self) -> u32 {
+        self.font.get_ascent()
+    }
+
+    fn get_descent(&self) -> u32 {
+        self.font.get_descent()
+    }
+
+    fn get_glyph_info(&self, codepoint: char) -> Option<GlyphInfo> {
+        self.font.get_glyph_info(codepoint)
+    }
+
+    fn get_kerning(&self, left: char, right: char) -> Option<i8> {
+        self.kerning
+            .get(left, right)
+            .or_else(|| self.font.get_kerning(left, right))
     }
 }
@@ -0,0 +1,91 @@
+use std::ops::Range;
+
+use glam::{vec2, Vec2};
+
+use crate::layout::{commands::Command, LineInfo};
+
+/// A part of a link span that is on a single line.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct LinkArea {
+    pub link_id: u32,
+    pub line_index: usize,
+    /// Top-left corner of the area
+    pub min: Vec2,
+    /// Bottom-right corner of the area
+    pub max: Vec2,
+}
+
+impl LinkArea {
+    pub fn contains(&self, position: Vec2) -> bool {
+        position.cmpge(self.min).all() && position.cmplt(self.max).all()
+    }
+}
+
+/// Areas occupied by the `@l<id>.` ... `@l.` spans of a laid out message.
+///
+/// All coordinates are in the space of the laid out text, with the origin at its top-left corner.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct LinkMap {
+    areas: Vec<LinkArea>,
+}
+
+impl LinkMap {
+    /// Builds the link areas from the command indices of the link spans.
+    ///
+    /// Must be called after all the lines were finalized, but before the commands are sorted by time.
+    pub(super) fn build(
+        spans: &[(u32, Range<usize>)],
+        commands: &[Command],
+        lines: &[LineInfo],
+    ) -> Self {
+        let mut areas: Vec<LinkArea> = Vec::new();
+
+        for (link_id, range) in spans {
+            for cmd in &commands[range.clone()] {
+                let Command::Char(char) = cmd else {
+                    continue;
+                };
+                let line = &lines[char.line_index];
+
+                // a link broken by a soft line break gets an area on each of the lines
+                match areas.last_mut() {
+                    Some(area)
+                        if area.link_id == *link_id && area.line_index == char.line_index =>
+                    {
+                        area.min.x = area.min.x.min(char.position.x);
+                        area.max.x = area.max.x.max(char.right_border());
+                    }
+                    _ => areas.push(LinkArea {
+                        link_id: *link_id,
+                        line_index: char.line_index,
+                        min: vec2(char.position.x, line.y_position),
+                        max: vec2(char.right_border(), line.y_position + line.line_height),
+                    }),
+                }
+            }
+        }
+
+        Self { areas }
+    }
+
+    /// Maps the areas into another coordinate space, keeping them axis-aligned.
+    pub(super) fn transform(&mut self, f: impl Fn(Vec2) -> Vec2) {
+        for area in &mut self.areas {
+            let (a, b) = (f(area.min), f(area.max));
+            area.min = a.min(b);
+            area.max = a.max(b);
+        }
+    }
+
+    pub fn areas(&self) -> &[LinkArea] {
+        &self.areas
+    }
+
+    /// Finds the link under `position`, if any.
+    pub fn hit_test(&self, position: Vec2) -> Option<u32> {
+        self.areas
+            .iter()
+            .find(|area| area.contains(position))
+            .map(|area| area.link_id)
+    }
+}
@@ -1,12 +1,16 @@
 pub mod commands;
 pub mod font;
+pub mod links;
 pub mod mixins;
 
+use std::{collections::HashMap, ops::Range};
+
 use commands::{Char, Command, Section, Voice, VoiceSync, VoiceWait, Wait};
 use float_ord::FloatOrd;
 use font::FontMetrics;
 use glam::{vec2, Vec2};
 use itertools::Itertools;
+use links::LinkMap;
 use shin_primitives::{char_set::CharSet, color::UnormColor};
 
 use crate::{
@@ -29,9 +33,23 @@ pub struct LineInfo {
     pub rubi_height: f32,
 }
 
+/// Direction in which the characters advance
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum TextDirection {
+    /// Left to right, with the lines going downwards
+    #[default]
+    Horizontal,
+    /// Top to bottom, with the columns going leftwards
+    ///
+    /// The text is laid out horizontally first and then turned into columns, so the line breaking rules apply to the columns as is.
+    /// `layout_width` limits the height of the columns, while the [`LineInfo`]s keep describing them in the horizontal frame.
+    Vertical,
+}
+
 pub struct LayoutParams {
     pub layout_width: f32,
     pub text_alignment: MessageTextLayout,
+    pub text_direction: TextDirection,
 
     /// Space before the line
     pub line_padding_above: f32,
@@ -42,10 +60,17 @@ pub struct LayoutParams {
 
     pub rubi_size: f32,
     pub text_size: f32,
+    /// Overrides the line height, which is normally the height of the tallest character in the line.
+    ///
+    /// Useful to keep the lines on a common grid when mixing fonts and font sizes.
+    pub line_height: Option<f32>,
+    /// Overrides the distance from the top of the line (below the rubi text) to the baseline, which is normally derived from the line height & font metrics
+    pub baseline_ascent: Option<f32>,
     pub base_font_horizontal_scale: f32,
     pub follow_kinsoku_shori_rules: bool,
     pub always_leave_space_for_rubi: bool,
     pub perform_soft_breaks: bool,
+    pub reveal_delays: RevealDelays,
 }
 
 impl Default for LayoutParams {
@@ -53,19 +78,87 @@ impl Default for LayoutParams {
         Self {
             layout_width: 640.0,
             text_alignment: MessageTextLayout::Justify,
+            text_direction: TextDirection::Horizontal,
             line_padding_above: 0.0,
             line_padding_below: 0.0,
             line_padding_between: 0.0,
             rubi_size: 0.0,
             text_size: 20.0,
+            line_height: None,
+            baseline_ascent: None,
             base_font_horizontal_scale: 1.0,
             follow_kinsoku_shori_rules: true,
             always_leave_space_for_rubi: false,
             perform_soft_breaks: true,
+            reveal_delays: RevealDelays::default(),
+        }
+    }
+}
+
+impl LayoutParams {
+    /// Layout params used by the message layer, with the text alignment set by `MSGINIT`
+    pub fn message_layer(text_alignment: MessageTextLayout) -> Self {
+        Self {
+            layout_width: 1500.0,
+            text_alignment,
+            text_direction: TextDirection::Horizontal,
+            line_padding_above: 0.0,
+            line_padding_below: 0.0,
+            line_padding_between: 4.0,
+            rubi_size: 20.0,
+            text_size: 50.0,
+            line_height: None,
+            baseline_ascent: None,
+            base_font_horizontal_scale: 0.9697,
+            follow_kinsoku_shori_rules: true,
+            always_leave_space_for_rubi: true, // < I am not sure if this should be true
+            perform_soft_breaks: true,
+            reveal_delays: RevealDelays::default(),
         }
     }
 }
 
+/// Extra time to pause for after revealing some characters, so that the punctuation paces the text without explicit waits.
+///
+/// The delays are in multiples of the text size and get scaled by the draw speed, like the time to reveal the characters themselves.
+/// This way they are skipped along with the rest of the reveal in the instant text and at the maximum draw speed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RevealDelays {
+    delays: HashMap<char, f32>,
+}
+
+impl RevealDelays {
+    /// No extra delays at all
+    pub fn none() -> Self {
+        Self {
+            delays: HashMap::new(),
+        }
+    }
+
+    /// Set the delay after each of the characters in `class`
+    pub fn with_class(mut self, class: &str, delay: f32) -> Self {
+        for codepoint in class.chars() {
+            self.delays.insert(codepoint, delay);
+        }
+        self
+    }
+
+    pub fn get(&self, codepoint: char) -> f32 {
+        self.delays.get(&codepoint).copied().unwrap_or(0.0)
+    }
+}
+
+impl Default for RevealDelays {
+    /// The delays used by the original engine
+    fn default() -> Self {
+        Self::none()
+            // U+3002 IDEOGRAPHIC FULL STOP
+            .with_class("。", 4.0)
+            // U+3001 IDEOGRAPHIC COMMA
+            .with_class("、", 2.0)
+    }
+}
+
 pub struct MessageTextLayouterDefaults {
     // NOTE: unparsed values are stored here
     pub color: i32,
@@ -73,6 +166,100 @@ pub struct MessageTextLayouterDefaults {
     pub fade: i32,
 }
 
+/// Whether the character is a space, which doesn't count towards the line width when aligning it
+fn is_space(codepoint: char) -> bool {
+    // U+3000 IDEOGRAPHIC SPACE
+    codepoint == ' ' || codepoint == '　'
+}
+
+/// Whether the line is a single word, which can't be justified.
+///
+/// Japanese text doesn't separate words with spaces, so it can be stretched between any two characters.
+/// In alphabetic scripts that would pull the letters of the word apart, so lines without any spaces are left as they are.
+fn is_single_word(commands: &[Command]) -> bool {
+    // U+2000 is where General Punctuation starts, everything below are the alphabetic scripts
+    commands.iter().all(|cmd| match cmd {
+        Command::Char(char) if !char.is_rubi => {
+            !is_space(char.codepoint) && char.codepoint < '\u{2000}'
+        }
+        _ => true,
+    })
+}
+
+/// Whether the line is in an alphabetic script, which is justified by stretching the spaces between the words.
+///
+/// Other lines (like the Japanese ones) are stretched between all the characters.
+fn is_alphabetic_line(commands: &[Command]) -> bool {
+    commands.iter().all(|cmd| match cmd {
+        Command::Char(char) if !char.is_rubi => {
+            is_space(char.codepoint) || char.codepoint < '\u{2000}'
+        }
+        _ => true,
+    })
+}
+
+/// Justify the line by distributing the extra space between the words, returning `false` if there are no spaces to stretch.
+///
+/// The trailing spaces (starting at `content_width`) are not counted, so that the last word ends at the line end.
+fn justify_by_words(commands: &mut [Command], content_width: f32, layout_width: f32) -> bool {
+    let gaps = commands
+        .iter()
+        .filter_map(|cmd| match cmd {
+            Command::Char(char)
+                if !char.is_rubi && is_space(char.codepoint) && char.position.x < content_width =>
+            {
+                Some(char.position.x)
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    if gaps.is_empty() {
+        return false;
+    }
+
+    let gap_extra = (layout_width - content_width) / gaps.len() as f32;
+    for cmd in commands.iter_mut() {
+        if let Command::Char(char) = cmd {
+            let preceding_gaps = gaps.iter().filter(|&&x| x < char.position.x).count();
+            char.position.x += gap_extra * preceding_gaps as f32;
+        }
+    }
+
+    true
+}
+
+/// The presentation form of the punctuation that has a different shape in vertical text
+fn vertical_form(codepoint: char) -> Option<char> {
+    Some(match codepoint {
+        '，' => '︐',
+        '、' => '︑',
+        '。' => '︒',
+        '：' => '︓',
+        '；' => '︔',
+        '！' => '︕',
+        '？' => '︖',
+        '…' => '︙',
+        '‥' => '︰',
+        '（' => '︵',
+        '）' => '︶',
+        '｛' => '︷',
+        '｝' => '︸',
+        '〔' => '︹',
+        '〕' => '︺',
+        '【' => '︻',
+        '】' => '︼',
+        '《' => '︽',
+        '》' => '︾',
+        '〈' => '︿',
+        '〉' => '﹀',
+        '「' => '﹁',
+        '」' => '﹂',
+        '『' => '﹃',
+        '』' => '﹄',
+        _ => return None,
+    })
+}
+
 fn parse_color(color: i32) -> UnormColor {
     UnormColor::from_decimal_rgb(color)
 }
@@ -132,9 +319,16 @@ pub struct MessageTextLayouterImpl<Font> {
     pub rubi_start_time: f32,
 
     pub is_bold: bool,
+    /// The previous character on the line, to look up the kerning for the next one
+    pub previous_char: Option<(char, CharFontType)>,
     pub section_counter: u32,
     pub sync_counter: u32,
 
+    /// Link id and the command index at which the currently open link span starts
+    pub link_start: Option<(u32, usize)>,
+    pub link_spans: Vec<(u32, Range<usize>)>,
+    pub links: LinkMap,
+
     pub size: Vec2,
 }
 
@@ -181,8 +375,12 @@ impl<Font> MessageTextLayouterImpl<Font> {
             rubi_start_x: 0.0,
             rubi_start_time: 0.0,
             is_bold: false,
+            previous_char: None,
             section_counter: 0,
             sync_counter: 0,
+            link_start: None,
+            link_spans: vec![],
+            links: Default::default(),
             size: Default::default(),
         }
     }
@@ -218,14 +416,19 @@ impl<Font: FontMetrics> MessageTextLayouterImpl<Font> {
         self.rubi_start_x = 0.0;
         self.rubi_start_time = 0.0;
         self.is_bold = false;
+        self.previous_char = None;
 
         self.section_counter = 1; // sic! unlike sync counter, section counter is initialized to 1
         self.sync_counter = 0;
+        self.link_start = None;
+        self.link_spans.clear();
+        self.links = LinkMap::default();
         self.size = Vec2::ZERO;
     }
 
     pub fn on_message_end<M: MessageTextLayouterMixin<Font>>(&mut self, mixin: &mut M) {
         self.on_rubi_base_end();
+        self.on_set_link(-1);
 
         let cmd = Wait {
             time: self.get_block_end_time(),
@@ -242,9 +445,54 @@ impl<Font: FontMetrics> MessageTextLayouterImpl<Font> {
 
         mixin.on_newline(self);
 
+        // the link spans are stored as command indices, so this has to be done before sorting
+        self.links = LinkMap::build(&self.link_spans, &self.commands, &self.lines);
+
+        if self.params.text_direction == TextDirection::Vertical {
+            self.make_vertical();
+        }
+
         self.commands.sort_by_key(|cmd| FloatOrd(cmd.time()));
     }
 
+    /// Turns the laid out lines into columns, the first line becoming the rightmost column
+    fn make_vertical(&mut self) {
+        let ascent = self.font_normal.get_ascent() as f32;
+        let descent = self.font_normal.get_descent() as f32;
+        let ascent_ratio = ascent / (ascent + descent);
+
+        let total_width = self.size.y;
+
+        for cmd in &mut self.commands {
+            let Command::Char(char) = cmd else {
+                continue;
+            };
+
+            // the glyphs are kept upright: their em box is centered in the column (where it was across the line)
+            // and starts where the horizontal advance did
+            let em_top = char.position.y - char.height * ascent_ratio;
+            let column_center = total_width - (em_top + char.height / 2.0);
+            char.position = vec2(
+                column_center - char.width / 2.0,
+                char.position.x + char.height * ascent_ratio,
+            );
+
+            let font = match char.font {
+                CharFontType::Regular => &self.font_normal,
+                CharFontType::Bold => &self.font_bold,
+            };
+            if let Some(form) = vertical_form(char.codepoint) {
+                // not every font has the vertical forms, the horizontal ones are better than nothing
+                if font.get_glyph_info(form).is_some() {
+                    char.codepoint = form;
+                }
+            }
+        }
+
+        self.links.transform(|position| vec2(total_width - position.y, position.x));
+        self.size = vec2(self.size.y, self.size.x);
+    }
+
     pub fn on_char(&mut self, codepoint: char) {
         const SHOULD_NOT_START_A_LINE: CharSet<56> = CharSet::new(")>]―’”‥…─♪、。々〉》」』】〕〟ぁぃぅぇぉっゃゅょゎんゝゞァィゥェォッャュョヮヵヶ・ーヽヾ！）：；？｝～");
         const SHOULD_NOT_END_A_LINE: CharSet<14> = CharSet::new("(<[‘“〈《「『【〔〝（｛");
@@ -270,9 +518,19 @@ impl<Font: FontMetrics> MessageTextLayouterImpl<Font> {
         let scale = self.params.text_size / (font.get_ascent() + font.get_descent()) as f32
             * self.font_scale;
         let horizontal_scale = scale * self.params.base_font_horizontal_scale;
-        let width = horizontal_scale * glyph_info.advance_width as f32;
+        let width = horizontal_scale * glyph_info.advance_width_f32();
         let height = self.params.text_size * self.font_scale;
 
+        // NB: the original engine doesn't do any kerning, but then its fonts don't have any kerning data either
+        if let Some((previous, previous_font_type)) = self.previous_char {
+            if previous_font_type == font_type {
+                if let Some(kerning) = font.get_kerning(previous, codepoint) {
+                    self.position.x += horizontal_scale * kerning as f32;
+                }
+            }
+        }
+        self.previous_char = Some((codepoint, font_type));
+
         if self.rubi_open {
             // if we have moved on from the start of the rubi base text - do not allow a line break
             cant_be_at_line_start = self.rubi_start_x != self.position.x;
@@ -301,15 +559,7 @@ impl<Font: FontMetrics> MessageTextLayouterImpl<Font> {
         } else {
             self.current_time += cmd.width * self.draw_speed;
 
-            let punct_delay = if codepoint == '。' {
-                // U+3002 IDEOGRAPHIC FULL STOP
-                4.0
-            } else if codepoint == '、' {
-                // U+3001 IDEOGRAPHIC COMMA
-                2.0
-            } else {
-                0.0
-            };
+            let punct_delay = self.params.reveal_delays.get(codepoint);
 
             // NB: IEEE floats are a bitch. Previously I have written (cmd.width + self.params.text_size * punct_delay) * self.draw_speed, but this is not the same thing
             self.current_time += (self.params.text_size * punct_delay) * self.draw_speed;
@@ -419,6 +669,7 @@ impl<Font: FontMetrics> MessageTextLayouterImpl<Font> {
 
         mixin.finalize_up_to(self, self.commands.len(), true);
         self.position.x = 0.0;
+        self.previous_char = None;
     }
 
     pub fn on_click_wait(&mut self) {
@@ -624,6 +875,8 @@ impl<Font: FontMetrics> MessageTextLayouterImpl<Font> {
         }
 
         self.rubi_open = true;
+        // don't pull the rubi base text into the preceding character
+        self.previous_char = None;
         self.rubi_start_cmd_index = self.commands.len();
         self.rubi_start_x = self.position.x;
         self.rubi_start_time = self.current_time;
@@ -661,7 +914,7 @@ impl<Font: FontMetrics> MessageTextLayouterImpl<Font> {
         for codepoint in self.rubi_text.chars() {
             let glyph_info = font.get_glyph_info(codepoint).unwrap();
 
-            let width = horizontal_scale * glyph_info.advance_width as f32;
+            let width = horizontal_scale * glyph_info.advance_width_f32();
 
             let cmd = Char {
                 time: self.rubi_start_time + rubi_time,
@@ -744,6 +997,8 @@ impl<Font: FontMetrics> MessageTextLayouterImpl<Font> {
             .extend(rubi_commands.into_iter().map(Command::Char));
 
         self.rubi_open = false;
+        // the cursor might have been moved to fit the rubi, kerning against the base text makes no sense
+        self.previous_char = None;
     }
 
     pub fn on_bold_start(&mut self) {
@@ -754,6 +1009,15 @@ impl<Font: FontMetrics> MessageTextLayouterImpl<Font> {
         self.is_bold = false;
     }
 
+    pub fn on_set_link(&mut self, link: i32) {
+        if let Some((link_id, start)) = self.link_start.take() {
+            self.link_spans.push((link_id, start..self.commands.len()));
+        }
+        if link >= 0 {
+            self.link_start = Some((link as u32, self.commands.len()));
+        }
+    }
+
     pub fn finalize_up_to(&mut self, finalize_index: usize, is_hard_break: bool) {
         let new_commands = &mut self.commands[self.finalized_command_count..finalize_index];
 
@@ -767,6 +1031,8 @@ impl<Font: FontMetrics> MessageTextLayouterImpl<Font> {
         // );
 
         let mut max_width = 0.0f32;
+        // like `max_width`, but without the trailing spaces
+        let mut content_width = 0.0f32;
         let mut line_height = 0.0f32;
         let mut rubi_height = 0.0f32;
         let mut char_count = 0;
@@ -776,9 +1042,13 @@ impl<Font: FontMetrics> MessageTextLayouterImpl<Font> {
                     if char.is_rubi {
                         rubi_height = self.params.rubi_size;
                         max_width = max_width.max(char.position.x + char.width);
+                        content_width = content_width.max(char.position.x + char.width);
                     } else {
                         line_height = line_height.max(char.height);
                         max_width = max_width.max(char.position.x + char.width);
+                        if !is_space(char.codepoint) {
+                            content_width = content_width.max(char.position.x + char.width);
+                        }
 
                         if self.params.always_leave_space_for_rubi {
                             rubi_height = self.params.rubi_size;
@@ -798,11 +1068,16 @@ impl<Font: FontMetrics> MessageTextLayouterImpl<Font> {
             }
         }
 
+        if let Some(line_height_override) = self.params.line_height {
+            line_height = line_height_override;
+        }
+
         // These variables are no longer going to change
         let layout_width = self.params.layout_width;
 
         let max_width = max_width;
         let mut line_width = max_width; // can change due to overflow or justification
+        let mut aligned_width = content_width; // the width used to center or right-align the line
         let line_height = line_height;
         let rubi_height = rubi_height;
         let char_count = char_count;
@@ -810,14 +1085,15 @@ impl<Font: FontMetrics> MessageTextLayouterImpl<Font> {
         let ascent = self.font_normal.get_ascent() as f32;
         let descent = self.font_normal.get_descent() as f32;
 
-        let ascent_scaled = if char_count == 0 {
+        let ascent_scaled = if let Some(baseline_ascent) = self.params.baseline_ascent {
+            baseline_ascent
+        } else if char_count == 0 {
             // baseline calculation seems wrong if there are no characters
             // weird...
-            self.params.text_size
+            self.params.text_size / (ascent + descent) * ascent
         } else {
-            line_height
-        } / (ascent + descent)
-            * ascent;
+            line_height / (ascent + descent) * ascent
+        };
         let rubi_ascent_scaled = rubi_height / (ascent + descent) * ascent;
 
         if char_count > 0 {
@@ -839,27 +1115,37 @@ impl<Font: FontMetrics> MessageTextLayouterImpl<Font> {
 
                 // we've used the full line, override the line width
                 line_width = layout_width;
+                aligned_width = layout_width;
             } else if !is_hard_break
                 && self.params.text_alignment == MessageTextLayout::Justify
                 && layout_width - max_width < layout_width * 0.05
+                && !is_single_word(new_commands)
             {
                 // eprintln!("Justifying line to fit: {} -> {}", max_width, layout_width);
                 // justify the non-last line characters if requested
-                for cmd in new_commands.iter_mut() {
-                    if let Command::Char(char) = cmd {
-                        let x_pos = char.position.x;
-                        char.position.x = (self.params.layout_width - char.width)
-                            * (x_pos / (x_pos + (max_width - (x_pos + char.width))));
+                let justified = if is_alphabetic_line(new_commands) {
+                    justify_by_words(new_commands, content_width, layout_width)
+                } else {
+                    for cmd in new_commands.iter_mut() {
+                        if let Command::Char(char) = cmd {
+                            let x_pos = char.position.x;
+                            char.position.x = (self.params.layout_width - char.width)
+                                * (x_pos / (x_pos + (max_width - (x_pos + char.width))));
+                        }
                     }
-                }
+                    true
+                };
 
-                // we've used the full line, override the line width
-                line_width = layout_width;
+                if justified {
+                    // we've used the full line, override the line width
+                    line_width = layout_width;
+                    aligned_width = layout_width;
+                }
             }
 
             let x_offset = match self.params.text_alignment {
-                MessageTextLayout::Center => (layout_width - line_width) / 2.0,
-                MessageTextLayout::Right => layout_width - line_width,
+                MessageTextLayout::Center => (layout_width - aligned_width) / 2.0,
+                MessageTextLayout::Right => layout_width - aligned_width,
                 _ => 0.0,
             };
 
@@ -953,10 +1239,26 @@ impl<Font: FontMetrics, M: MessageTextLayouterMixin<Font>> MessageTextLayouterWi
     }
 
     pub fn finish(self) -> (Vec<Command>, Vec<LineInfo>, Vec2) {
+        let (commands, lines, size, _) = self.finish_with_links();
+        (commands, lines, size)
+    }
+
+    /// Like [`Self::parse`], but also returns the areas of the link spans for hit-testing.
+    pub fn parse_with_links(
+        mut self,
+        message: &str,
+    ) -> (Vec<Command>, Vec<LineInfo>, Vec2, LinkMap) {
+        MessageTextParser::new(message).parse_into(&mut self);
+
+        self.finish_with_links()
+    }
+
+    pub fn finish_with_links(self) -> (Vec<Command>, Vec<LineInfo>, Vec2, LinkMap) {
         (
             self.layouter.commands,
             self.layouter.lines,
             self.layouter.size,
+            self.layouter.links,
         )
     }
 }
@@ -1071,6 +1373,10 @@ impl<Font: FontMetrics, M: MessageTextLayouterMixin<Font>> TextLayouter
     fn on_bold_end(&mut self) {
         self.layouter.on_bold_end()
     }
+
+    fn on_set_link(&mut self, link: i32) {
+        self.layouter.on_set_link(link)
+    }
 }
 
 pub type MessageTextLayouter<Font> = MessageTextLayouterWithMixin<Font, mixins::NoMixin>;
@@ -0,0 +1,158 @@
+//! Tests for the horizontal alignment of the lines
+
+use super::{assert_approx_eq, layout_lines, make_layouter, overflowing_line, LAYOUT_WIDTH};
+use crate::{
+    format::scenario::instruction_elements::FromNumber,
+    layout::commands::{Char, Command},
+    vm::command::types::{MessageTextLayout, MessageboxStyle, MessageboxType},
+};
+
+fn x_positions(line: &[Char]) -> Vec<f32> {
+    line.iter().map(|char| char.position.x).collect()
+}
+
+const TWO_LINES: &str = "@rあいうえお@rかきく";
+
+#[test]
+fn left() {
+    for line in layout_lines(MessageTextLayout::Left, TWO_LINES) {
+        assert_eq!(line[0].position.x, 0.0);
+    }
+}
+
+#[test]
+fn center() {
+    let left = layout_lines(MessageTextLayout::Left, TWO_LINES);
+    let center = layout_lines(MessageTextLayout::Center, TWO_LINES);
+    assert_eq!(center.len(), 2);
+
+    for (left, center) in left.iter().zip(&center) {
+        let first = &center[0];
+        let last = center.last().unwrap();
+        assert_approx_eq((first.position.x + last.right_border()) / 2.0, LAYOUT_WIDTH / 2.0);
+
+        // the characters are shifted as a whole
+        let offset = first.position.x - left[0].position.x;
+        for (left, center) in x_positions(left).into_iter().zip(x_positions(center)) {
+            assert_approx_eq(center, left + offset);
+        }
+    }
+}
+
+#[test]
+fn right() {
+    let right = layout_lines(MessageTextLayout::Right, TWO_LINES);
+    assert_eq!(right.len(), 2);
+
+    for line in right {
+        assert_approx_eq(line.last().unwrap().right_border(), LAYOUT_WIDTH);
+    }
+}
+
+#[test]
+fn trailing_spaces_ignored() {
+    for text_alignment in [MessageTextLayout::Center, MessageTextLayout::Right] {
+        let lines = layout_lines(text_alignment, "@rかきく@rかきく　　@rかきく  ");
+        assert_eq!(lines.len(), 3);
+
+        // the trailing spaces are still there, but the visible text is placed the same way
+        for line in &lines[1..] {
+            assert_eq!(x_positions(&line[..3]), x_positions(&lines[0]));
+        }
+    }
+}
+
+#[test]
+fn justify() {
+    // "。" can't start a line, so "あ" gets carried over with it, leaving a gap to be filled by justification
+    let text = format!("{}@rかきく", overflowing_line('あ', '。'));
+
+    let left = layout_lines(MessageTextLayout::Left, &text);
+    let justify = layout_lines(MessageTextLayout::Justify, &text);
+    assert_eq!(justify.len(), 3);
+
+    let first_line = &justify[0];
+    assert!(left[0].last().unwrap().right_border() < LAYOUT_WIDTH);
+    assert_eq!(first_line[0].position.x, 0.0);
+    assert_approx_eq(first_line.last().unwrap().right_border(), LAYOUT_WIDTH);
+
+    // the last lines of the paragraphs are not stretched
+    for (left, justify) in left[1..].iter().zip(&justify[1..]) {
+        assert_eq!(x_positions(justify), x_positions(left));
+    }
+}
+
+#[test]
+fn justify_words() {
+    let sample = &layout_lines(MessageTextLayout::Left, "@ra a")[0];
+    let letter_width = sample[1].position.x - sample[0].position.x;
+    let word_width = sample[2].position.x - sample[0].position.x;
+
+    // enough words for the ")" to not fit, so it gets carried over with the last word, leaving a gap to be filled by justification
+    let word_count = ((LAYOUT_WIDTH - letter_width) / word_width).ceil() as usize;
+    let text = format!("@r{}a)", "a ".repeat(word_count));
+
+    let left = layout_lines(MessageTextLayout::Left, &text);
+    let justify = layout_lines(MessageTextLayout::Justify, &text);
+    assert_eq!(justify.len(), 2);
+
+    let first_line = &justify[0];
+    let last_letter = first_line
+        .iter()
+        .rfind(|char| char.codepoint != ' ')
+        .unwrap();
+    assert_eq!(first_line[0].position.x, 0.0);
+    assert_approx_eq(last_letter.right_border(), LAYOUT_WIDTH);
+
+    // only the spaces are stretched, the spaces stay right after the letters
+    for (left, justify) in left[0].windows(2).zip(first_line.windows(2)) {
+        if justify[1].codepoint == ' ' {
+            assert_approx_eq(
+                justify[1].position.x - justify[0].position.x,
+                left[1].position.x - left[0].position.x,
+            );
+        } else {
+            assert!(
+                justify[1].position.x - justify[0].position.x
+                    > left[1].position.x - left[0].position.x
+            );
+        }
+    }
+}
+
+#[test]
+fn justify_single_word() {
+    // same as in the `justify` test, but a word in an alphabetic script has nowhere to put the extra space
+    let text = overflowing_line('a', ')');
+
+    let left = layout_lines(MessageTextLayout::Left, &text);
+    let justify = layout_lines(MessageTextLayout::Justify, &text);
+    assert_eq!(justify.len(), 2);
+
+    assert!(justify[0].last().unwrap().right_border() < LAYOUT_WIDTH);
+    assert_eq!(x_positions(&justify[0]), x_positions(&left[0]));
+}
+
+#[test]
+fn msginit_style() {
+    // MSGINIT with the novel messagebox and the centered text layout
+    let style = MessageboxStyle::from_number(0x24);
+    assert_eq!(style.messagebox_type, MessageboxType::Novel);
+    assert_eq!(style.text_layout, MessageTextLayout::Center);
+
+    // the next MSGSET lays out the text with the style
+    let (commands, _, _) =
+        make_layouter(style.text_layout, style.messagebox_type).parse("@rかきく");
+    let chars = commands
+        .into_iter()
+        .filter_map(|command| match command {
+            Command::Char(char) => Some(char),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    let first = &chars[0];
+    let last = chars.last().unwrap();
+    assert!(first.position.x > 0.0);
+    assert_approx_eq((first.position.x + last.right_border()) / 2.0, LAYOUT_WIDTH / 2.0);
+}
@@ -0,0 +1,84 @@
+//! Tests for the pair kerning
+
+use super::{assert_approx_eq, message_layer_params, FONTS};
+use crate::{
+    format::font::FontInfo,
+    layout::{
+        commands::{Char, Command},
+        font::{KernedFont, KerningTable},
+        MessageLayerLayouter, MessageTextLayouterDefaults,
+    },
+    vm::command::types::{MessageTextLayout, MessageboxType},
+};
+
+/// Lays out `text` in novel mode with the given kerning table, returning the base text characters
+fn layout(kerning: KerningTable, text: &str) -> Vec<Char> {
+    let font = |font: &'static FontInfo| KernedFont::new(font, kerning.clone());
+    let defaults = MessageTextLayouterDefaults {
+        color: 999,
+        draw_speed: 80,
+        fade: 200,
+    };
+
+    let layouter = MessageLayerLayouter::new(
+        font(&FONTS.normal_font),
+        font(&FONTS.bold_font),
+        MessageboxType::Novel,
+        message_layer_params(MessageTextLayout::Left),
+        defaults,
+    );
+    let (commands, _, _) = layouter.parse(text);
+
+    commands
+        .into_iter()
+        .filter_map(|command| match command {
+            Command::Char(char) if !char.is_rubi => Some(char),
+            _ => None,
+        })
+        .collect()
+}
+
+fn run_width(chars: &[Char]) -> f32 {
+    let first = chars.first().unwrap();
+    let last = chars.last().unwrap();
+    last.position.x + last.width - first.position.x
+}
+
+fn kerning(pairs: &[(char, char, i8)]) -> KerningTable {
+    let mut table = KerningTable::new();
+    for &(left, right, adjustment) in pairs {
+        table.insert(left, right, adjustment);
+    }
+    table
+}
+
+#[test]
+fn kerned_pair_is_tighter() {
+    let unkerned = layout(KerningTable::new(), "@rAV");
+    let kerned = layout(kerning(&[('A', 'V', -8)]), "@rAV");
+
+    assert!(run_width(&kerned) < run_width(&unkerned));
+    // the glyphs themselves are unchanged, only the second one is moved
+    assert_eq!(kerned[0].position, unkerned[0].position);
+    assert_approx_eq(
+        unkerned[1].position.x - kerned[1].position.x,
+        8.0 * kerned[1].horizontal_scale,
+    );
+}
+
+#[test]
+fn no_kerning_data() {
+    // pairs missing from the table are laid out as before
+    let unkerned = layout(KerningTable::new(), "@rAVA");
+    let kerned = layout(kerning(&[('V', 'V', -8)]), "@rAVA");
+
+    assert_eq!(kerned, unkerned);
+}
+
+#[test]
+fn not_across_lines() {
+    let unkerned = layout(KerningTable::new(), "@rA@rV");
+    let kerned = layout(kerning(&[('A', 'V', -8)]), "@rA@rV");
+
+    assert_eq!(kerned, unkerned);
+}
@@ -0,0 +1,64 @@
+//! Tests for the line height & baseline overrides
+
+use super::{make_layouter_with_params, message_layer_params};
+use crate::{
+    layout::{commands::Command, LayoutParams},
+    vm::command::types::{MessageTextLayout, MessageboxType},
+};
+
+// regular, bold and enlarged text on separate lines (the first line is the character name, which is skipped in novel mode)
+const MIXED_TEXT: &str = "@rあいう@r@{あいう@}@r@z150.あいう@z.@rあいう";
+
+/// Lays out [`MIXED_TEXT`], returning the distances between the baselines of consecutive lines
+fn baseline_spacing(params: LayoutParams) -> Vec<f32> {
+    let (commands, lines, _) =
+        make_layouter_with_params(MessageboxType::Novel, params).parse(MIXED_TEXT);
+
+    let baselines = lines
+        .iter()
+        .map(|line| line.y_position + line.baseline_ascent)
+        .collect::<Vec<_>>();
+
+    // the characters are placed right on the baseline
+    for command in &commands {
+        if let Command::Char(char) = command {
+            assert_eq!(char.position.y, baselines[char.line_index]);
+        }
+    }
+
+    baselines.windows(2).map(|w| w[1] - w[0]).collect()
+}
+
+#[test]
+fn fixed_line_height() {
+    let spacing = baseline_spacing(LayoutParams {
+        line_height: Some(80.0),
+        baseline_ascent: Some(60.0),
+        ..message_layer_params(MessageTextLayout::Left)
+    });
+
+    // rubi height + line height + padding between the lines
+    assert_eq!(spacing, vec![104.0; 3]);
+}
+
+#[test]
+fn fixed_line_height_derived_baseline() {
+    let spacing = baseline_spacing(LayoutParams {
+        line_height: Some(80.0),
+        ..message_layer_params(MessageTextLayout::Left)
+    });
+
+    assert_eq!(spacing.len(), 3);
+    for spacing in spacing {
+        assert!((spacing - 104.0).abs() < 1e-3, "{}", spacing);
+    }
+}
+
+#[test]
+fn default_line_height() {
+    // without the override the enlarged line pushes its neighbours apart
+    let spacing = baseline_spacing(message_layer_params(MessageTextLayout::Left));
+
+    assert!(spacing[1] > spacing[0]);
+    assert!(spacing[2] > spacing[0]);
+}
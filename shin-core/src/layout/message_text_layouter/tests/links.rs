@@ -0,0 +1,65 @@
+//! Tests for hit-testing the link spans
+
+use glam::{vec2, Vec2};
+
+use super::make_layouter;
+use crate::{
+    layout::{
+        commands::{Char, Command},
+        LineInfo,
+    },
+    vm::command::types::{MessageTextLayout, MessageboxType},
+};
+
+fn char_center(char: &Char, lines: &[LineInfo]) -> Vec2 {
+    let line = &lines[char.line_index];
+    vec2(
+        char.position.x + char.width / 2.0,
+        line.y_position + line.line_height / 2.0,
+    )
+}
+
+#[test]
+fn link_wrapped_across_lines() {
+    let layouter = make_layouter(MessageTextLayout::Justify, MessageboxType::Ushiromiya);
+    // the link starts at the end of the first text line and gets wrapped to the next one
+    let text = format!("名前@r{}@l7.{}@l.。", "あ".repeat(25), "い".repeat(20));
+    let (commands, lines, _, links) = layouter.parse_with_links(&text);
+
+    let link_lines = links
+        .areas()
+        .iter()
+        .map(|area| {
+            assert_eq!(area.link_id, 7);
+            area.line_index
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(link_lines, vec![1, 2]);
+
+    for command in &commands {
+        let Command::Char(char) = command else {
+            continue;
+        };
+
+        let expected = (char.codepoint == 'い').then_some(7);
+        assert_eq!(links.hit_test(char_center(char, &lines)), expected, "{:?}", char.codepoint);
+    }
+
+    // past the end of the last line
+    let last_line = lines.last().unwrap();
+    assert_eq!(links.hit_test(vec2(1490.0, last_line.y_position + 10.0)), None);
+}
+
+#[test]
+fn no_links() {
+    let layouter = make_layouter(MessageTextLayout::Justify, MessageboxType::Novel);
+    let (commands, lines, _, links) = layouter.parse_with_links("ああ@l1.@l.ああ");
+
+    // empty spans don't produce any areas
+    assert!(links.areas().is_empty());
+    for command in &commands {
+        if let Command::Char(char) = command {
+            assert_eq!(links.hit_test(char_center(char, &lines)), None);
+        }
+    }
+}
@@ -1,5 +1,12 @@
+mod alignment;
 mod dumps;
+mod line_metrics;
+mod kerning;
+mod links;
+mod reveal_delays;
+mod rubi;
 mod snapshots;
+mod vertical;
 
 use std::{fs::File, io::BufReader, sync::LazyLock};
 
@@ -9,7 +16,10 @@ use crate::{
     format::font::FontInfo,
     layout::{
         message_text_layouter::{
-            commands::Command, LayoutParams, LineInfo, MessageLayerLayouter,
+            commands::{Char, Command},
+            LayoutParams,
+            LineInfo,
+            MessageLayerLayouter,
             MessageTextLayouterDefaults,
         },
         MessageTextParser,
@@ -39,41 +49,87 @@ pub fn read_fonts() -> TestFonts {
     }
 }
 
-pub fn make_snapshot(
-    text_alignment: MessageTextLayout,
-    messagebox_type: MessageboxType,
-    text: &str,
-) -> (Vec<Command>, Vec<LineInfo>, Vec2) {
-    // share fonts between invocations in the same process
-    static FONTS: LazyLock<TestFonts> = LazyLock::new(|| read_fonts());
+/// Layout params used by the MessageLayer
+pub fn message_layer_params(text_alignment: MessageTextLayout) -> LayoutParams {
+    LayoutParams::message_layer(text_alignment)
+}
+
+// share fonts between invocations in the same process
+static FONTS: LazyLock<TestFonts> = LazyLock::new(|| read_fonts());
 
+pub fn make_layouter_with_params(
+    messagebox_type: MessageboxType,
+    layout_params: LayoutParams,
+) -> MessageLayerLayouter<&'static FontInfo> {
     let normal = &FONTS.normal_font;
     let bold = &FONTS.bold_font;
 
-    // layout params used by the MessageLayer
-    let layout_params = LayoutParams {
-        layout_width: 1500.0,
-        text_alignment,
-        line_padding_above: 0.0,
-        line_padding_below: 0.0,
-        line_padding_between: 4.0,
-        rubi_size: 20.0,
-        text_size: 50.0,
-        base_font_horizontal_scale: 0.9697,
-        follow_kinsoku_shori_rules: true,
-        always_leave_space_for_rubi: true,
-        perform_soft_breaks: true,
-    };
     let defaults = MessageTextLayouterDefaults {
         color: 999,
         draw_speed: 80,
         fade: 200,
     };
 
-    let mut layouter =
-        MessageLayerLayouter::new(normal, bold, messagebox_type, layout_params, defaults);
+    MessageLayerLayouter::new(normal, bold, messagebox_type, layout_params, defaults)
+}
+
+pub fn make_layouter(
+    text_alignment: MessageTextLayout,
+    messagebox_type: MessageboxType,
+) -> MessageLayerLayouter<&'static FontInfo> {
+    make_layouter_with_params(messagebox_type, message_layer_params(text_alignment))
+}
+
+pub fn make_snapshot(
+    text_alignment: MessageTextLayout,
+    messagebox_type: MessageboxType,
+    text: &str,
+) -> (Vec<Command>, Vec<LineInfo>, Vec2) {
+    let mut layouter = make_layouter(text_alignment, messagebox_type);
     let parser = MessageTextParser::new(text);
     parser.parse_into(&mut layouter);
 
     layouter.finish()
 }
+
+pub const LAYOUT_WIDTH: f32 = 1500.0;
+
+/// Lays out `text` in novel mode (no character name), returning the base text characters grouped by line
+pub fn layout_lines(text_alignment: MessageTextLayout, text: &str) -> Vec<Vec<Char>> {
+    let (commands, lines, _) = make_layouter(text_alignment, MessageboxType::Novel).parse(text);
+
+    let mut result = lines.iter().map(|_| Vec::new()).collect::<Vec<_>>();
+    for command in commands {
+        if let Command::Char(char) = command {
+            if !char.is_rubi {
+                result[char.line_index].push(char);
+            }
+        }
+    }
+    result.retain(|line| !line.is_empty());
+    result
+}
+
+pub fn assert_approx_eq(actual: f32, expected: f32) {
+    assert!((actual - expected).abs() < 1e-3, "{} != {}", actual, expected);
+}
+
+/// How many `codepoint`s fit into a line of the layout width
+pub fn fitting_char_count(codepoint: char) -> usize {
+    let width = layout_lines(MessageTextLayout::Left, &format!("@r{}", codepoint))[0][0].width;
+
+    let mut count = 0;
+    let mut x = 0.0;
+    while x < LAYOUT_WIDTH {
+        x += width;
+        count += 1;
+    }
+    count
+}
+
+/// Builds a line of `codepoint`s just long enough for `terminator` to be the first character not fitting into the layout width
+pub fn overflowing_line(codepoint: char, terminator: char) -> String {
+    let count = fitting_char_count(codepoint);
+
+    format!("@r{}{}", codepoint.to_string().repeat(count), terminator)
+}
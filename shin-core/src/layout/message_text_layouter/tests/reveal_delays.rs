@@ -0,0 +1,75 @@
+//! Tests for the extra reveal delays after the punctuation
+
+use super::{assert_approx_eq, make_layouter_with_params, message_layer_params};
+use crate::{
+    layout::{
+        LayoutParams, RevealDelays,
+        commands::{Char, Command},
+    },
+    vm::command::types::{MessageTextLayout, MessageboxType},
+};
+
+const TEXT_SIZE: f32 = 50.0;
+
+fn layout_chars(reveal_delays: RevealDelays, text: &str) -> Vec<Char> {
+    let params = LayoutParams {
+        reveal_delays,
+        ..message_layer_params(MessageTextLayout::Left)
+    };
+    let (commands, _, _) = make_layouter_with_params(MessageboxType::Novel, params).parse(text);
+
+    commands
+        .into_iter()
+        .filter_map(|command| match command {
+            Command::Char(char) => Some(char),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Time it takes to reveal a unit of width, derived from the first two characters (which must not have any delay)
+fn draw_speed(chars: &[Char]) -> f32 {
+    (chars[1].time - chars[0].time) / chars[0].width
+}
+
+/// The extra delay after the character at `index`, in multiples of the text size
+fn extra_delay(chars: &[Char], index: usize) -> f32 {
+    let char = &chars[index];
+    let draw_speed = draw_speed(chars);
+    (chars[index + 1].time - char.time - char.width * draw_speed) / (TEXT_SIZE * draw_speed)
+}
+
+#[test]
+fn default_delays() {
+    let chars = layout_chars(RevealDelays::default(), "@rあい。う、え");
+
+    assert_approx_eq(extra_delay(&chars, 1), 0.0);
+    assert_approx_eq(extra_delay(&chars, 2), 4.0);
+    assert_approx_eq(extra_delay(&chars, 4), 2.0);
+}
+
+#[test]
+fn overridden_delays() {
+    let delays = RevealDelays::default()
+        .with_class("！？", 3.0)
+        .with_class("。", 0.0);
+    let chars = layout_chars(delays, "@rあい！う？え。お");
+
+    assert_approx_eq(extra_delay(&chars, 2), 3.0);
+    assert_approx_eq(extra_delay(&chars, 4), 3.0);
+    assert_approx_eq(extra_delay(&chars, 6), 0.0);
+
+    let chars = layout_chars(RevealDelays::none(), "@rあい。う、え");
+    assert_approx_eq(extra_delay(&chars, 2), 0.0);
+    assert_approx_eq(extra_delay(&chars, 4), 0.0);
+}
+
+#[test]
+fn no_delays_in_instant_text() {
+    let chars = layout_chars(RevealDelays::default(), "@r@[あ。い、@]う");
+
+    // the whole instant text is shown at once, the punctuation doesn't hold up the following text either
+    for char in &chars {
+        assert_eq!(char.time, chars[0].time);
+    }
+}
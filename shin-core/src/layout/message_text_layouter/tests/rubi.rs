@@ -0,0 +1,98 @@
+//! Tests for the placement of the rubi (furigana) text
+
+use super::{assert_approx_eq, fitting_char_count, make_layouter_with_params, message_layer_params};
+use crate::{
+    layout::{
+        commands::{Char, Command},
+        LayoutParams, LineInfo,
+    },
+    vm::command::types::{MessageTextLayout, MessageboxType},
+};
+
+/// Lays out `text` in novel mode, returning the base text characters, the rubi characters and the lines
+fn layout_rubi(text: &str) -> (Vec<Char>, Vec<Char>, Vec<LineInfo>) {
+    let params = LayoutParams {
+        // the line height has to account for the rubi by itself
+        always_leave_space_for_rubi: false,
+        ..message_layer_params(MessageTextLayout::Left)
+    };
+    let (commands, lines, _) = make_layouter_with_params(MessageboxType::Novel, params).parse(text);
+
+    let (rubi, base) = commands
+        .into_iter()
+        .filter_map(|command| match command {
+            Command::Char(char) => Some(char),
+            _ => None,
+        })
+        .partition::<Vec<_>, _>(|char| char.is_rubi);
+
+    (base, rubi, lines)
+}
+
+/// Horizontal center of the run of characters
+fn center(chars: &[Char]) -> f32 {
+    let left = chars.iter().map(|char| char.position.x).fold(f32::MAX, f32::min);
+    let right = chars.iter().map(|char| char.right_border()).fold(f32::MIN, f32::max);
+    (left + right) / 2.0
+}
+
+#[test]
+fn centered_over_two_kanji() {
+    let (base, rubi, lines) = layout_rubi("@r@bかんじ.@<漢字@>");
+    assert_eq!(base.len(), 2);
+    assert_eq!(rubi.len(), 3);
+
+    assert_approx_eq(center(&rubi), center(&base));
+
+    // the rubi is narrower than the base, so the base is laid out as usual
+    assert_eq!(base[0].position.x, 0.0);
+    assert_approx_eq(base[1].position.x, base[0].width);
+
+    // the rubi is placed above the base text, inside the space reserved for it in the line
+    let line = &lines[base[0].line_index];
+    assert!(line.rubi_height > 0.0);
+    for char in &rubi {
+        assert!(char.position.y > line.y_position);
+        assert!(char.position.y <= line.y_position + line.rubi_height);
+        assert!(char.position.y < base[0].position.y);
+    }
+}
+
+#[test]
+fn no_space_reserved_without_rubi() {
+    let (_, _, lines) = layout_rubi("@r漢字@r@bかんじ.@<漢字@>");
+    assert_eq!(lines[0].rubi_height, 0.0);
+    assert!(lines[1].rubi_height > 0.0);
+    assert!(lines[1].line_height > lines[0].line_height);
+}
+
+#[test]
+fn wider_than_base() {
+    let (base, rubi, _) = layout_rubi("@r@bかんじかんじ.@<字@>の");
+    let (kanji, following) = base.split_at(1);
+
+    // the base is spread out to match the rubi, and the following text comes after it
+    assert_approx_eq(center(kanji), center(&rubi));
+    assert!(kanji[0].position.x > 0.0);
+    assert_approx_eq(following[0].position.x, rubi.last().unwrap().right_border());
+}
+
+#[test]
+fn moves_with_base_at_line_break() {
+    // the first kanji of the base still fits into the line, but the second one doesn't
+    let count = fitting_char_count('あ');
+    let text = format!("@r{}@bかんじ.@<漢字@>", "あ".repeat(count - 1));
+    let (base, rubi, _) = layout_rubi(&text);
+
+    // the base can't be split, so it moves to the next line, and the rubi follows it
+    let kanji = &base[count - 1..];
+    assert_eq!(kanji.len(), 2);
+    assert_eq!(kanji[0].line_index, base[0].line_index + 1);
+    assert_eq!(kanji[1].line_index, kanji[0].line_index);
+    for char in &rubi {
+        assert_eq!(char.line_index, kanji[0].line_index);
+    }
+
+    assert_eq!(kanji[0].position.x, 0.0);
+    assert_approx_eq(center(&rubi), center(kanji));
+}
@@ -0,0 +1,66 @@
+//! Tests for the vertical text direction
+
+use super::{assert_approx_eq, make_layouter_with_params, message_layer_params};
+use crate::{
+    layout::{
+        commands::{Char, Command},
+        LayoutParams, LineInfo, TextDirection,
+    },
+    vm::command::types::{MessageTextLayout, MessageboxType},
+};
+
+const TWO_LINES: &str = "@rあいうえお@rかきく";
+
+/// Lays out `text` in novel mode, returning the characters grouped by line (column) and the lines
+fn layout(text_direction: TextDirection, text: &str) -> (Vec<Vec<Char>>, Vec<LineInfo>) {
+    let params = LayoutParams {
+        text_direction,
+        ..message_layer_params(MessageTextLayout::Left)
+    };
+    let (commands, lines, _) = make_layouter_with_params(MessageboxType::Novel, params).parse(text);
+
+    let mut result = lines.iter().map(|_| Vec::new()).collect::<Vec<_>>();
+    for command in commands {
+        if let Command::Char(char) = command {
+            result[char.line_index].push(char);
+        }
+    }
+    (result, lines)
+}
+
+fn column_center(char: &Char) -> f32 {
+    char.position.x + char.width / 2.0
+}
+
+#[test]
+fn glyph_advance() {
+    let (columns, _) = layout(TextDirection::Vertical, TWO_LINES);
+    let (lines, _) = layout(TextDirection::Horizontal, TWO_LINES);
+    assert_eq!(columns.len(), 2);
+
+    for (column, line) in columns.iter().zip(&lines) {
+        for (chars, horizontal) in column.windows(2).zip(line.windows(2)) {
+            // the glyphs advance downwards by the same amount they do to the right in horizontal text
+            assert_approx_eq(chars[1].position.y - chars[0].position.y, chars[0].width);
+            assert_approx_eq(
+                chars[1].position.y - chars[0].position.y,
+                horizontal[1].position.x - horizontal[0].position.x,
+            );
+            // and stay centered in their column
+            assert_approx_eq(column_center(&chars[1]), column_center(&chars[0]));
+        }
+    }
+}
+
+#[test]
+fn column_step() {
+    let (columns, lines) = layout(TextDirection::Vertical, TWO_LINES);
+
+    // the columns go to the left, spaced like the horizontal lines
+    let line_step = lines[1].y_position - lines[0].y_position;
+    assert!(line_step > 0.0);
+    assert_approx_eq(column_center(&columns[1][0]) - column_center(&columns[0][0]), -line_step);
+
+    // both columns start at the top
+    assert_eq!(columns[0][0].position.y, columns[1][0].position.y);
+}
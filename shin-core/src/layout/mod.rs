@@ -1,10 +1,14 @@
+mod cache;
 mod message_text_layouter;
 mod parser;
+mod plain_text;
 mod text_layouter;
 
+pub use cache::LayoutCache;
 pub use message_text_layouter::{
-    commands, font, LayoutParams, LineInfo, MessageLayerLayouter, MessageTextLayouter,
-    MessageTextLayouterDefaults,
+    commands, font, links, LayoutParams, LineInfo, MessageLayerLayouter, MessageTextLayouter,
+    MessageTextLayouterDefaults, RevealDelays, TextDirection,
 };
 pub use parser::{MessageTextParser, ParsedCommand};
+pub use plain_text::PlainTextMessage;
 pub use text_layouter::TextLayouter;
@@ -24,6 +24,10 @@ pub enum ParsedCommand {
     NoFinalClickWait,
     /// @k
     ClickWait,
+    /// @l
+    ///
+    /// Not used by the original engine, marks clickable text (e.g. glossary links). `@l.` closes the span.
+    SetLink(i32),
     /// @o
     VoiceVolume(i32),
     /// @r
@@ -78,6 +82,7 @@ impl<'a> MessageTextParser<'a> {
                 ParsedCommand::SetColor(color) => layouter.on_set_color(color),
                 ParsedCommand::NoFinalClickWait => layouter.on_auto_click(),
                 ParsedCommand::ClickWait => layouter.on_click_wait(),
+                ParsedCommand::SetLink(link) => layouter.on_set_link(link),
                 ParsedCommand::VoiceVolume(volume) => layouter.on_set_voice_volume(volume),
                 ParsedCommand::Newline => layouter.on_newline(),
                 ParsedCommand::TextSpeed(speed) => layouter.on_set_draw_speed(speed),
@@ -169,6 +174,7 @@ impl Iterator for MessageTextParser<'_> {
             'c' => ParsedCommand::SetColor(self.read_int_argument()),
             'e' => ParsedCommand::NoFinalClickWait,
             'k' => ParsedCommand::ClickWait,
+            'l' => ParsedCommand::SetLink(self.read_int_argument()),
             'o' => ParsedCommand::VoiceVolume(self.read_int_argument()),
             'r' => ParsedCommand::Newline,
             's' => ParsedCommand::TextSpeed(self.read_int_argument()),
@@ -257,6 +263,27 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_link() {
+        let message = "See @l3.Hi@l.!";
+        let commands = parse(message);
+
+        assert_eq!(
+            commands,
+            vec![
+                ParsedCommand::Char('S'),
+                ParsedCommand::Char('e'),
+                ParsedCommand::Char('e'),
+                ParsedCommand::Char(' '),
+                ParsedCommand::SetLink(3),
+                ParsedCommand::Char('H'),
+                ParsedCommand::Char('i'),
+                ParsedCommand::SetLink(-1),
+                ParsedCommand::Char('!'),
+            ]
+        );
+    }
+
     #[test]
     fn test_wait() {
         let message = "Hello@w400.@rWorld";
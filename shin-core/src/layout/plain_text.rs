@@ -0,0 +1,96 @@
+use crate::layout::parser::{MessageTextParser, ParsedCommand};
+
+/// A message with all the layouter commands stripped, suitable for logs and transcripts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlainTextMessage {
+    /// The character name from the first line of the message, if it's not empty
+    pub character_name: Option<String>,
+    /// The rest of the message text. Lines are separated with `\n`
+    pub text: String,
+}
+
+impl PlainTextMessage {
+    /// Converts a message (as passed to MSGSET) to plain text.
+    ///
+    /// Just like in the message layer, the first line is treated as the character name.
+    ///
+    /// Rubi (furigana) is rendered inline after its base text, like `base(rubi)`.
+    pub fn parse(message: &str) -> Self {
+        let mut character_name = String::new();
+        let mut text = String::new();
+        let mut is_character_name = true;
+        let mut rubi = None;
+
+        for command in MessageTextParser::new(message) {
+            let output = if is_character_name {
+                &mut character_name
+            } else {
+                &mut text
+            };
+
+            match command {
+                ParsedCommand::Char(c) => output.push(c),
+                ParsedCommand::Newline => {
+                    if is_character_name {
+                        is_character_name = false;
+                    } else {
+                        output.push('\n');
+                    }
+                }
+                ParsedCommand::RubiContent(content) => rubi = Some(content),
+                ParsedCommand::RubiBaseEnd => {
+                    if let Some(rubi) = rubi.take().filter(|r| !r.is_empty()) {
+                        output.push('(');
+                        output.push_str(&rubi);
+                        output.push(')');
+                    }
+                }
+                // everything else only affects the presentation
+                _ => {}
+            }
+        }
+
+        Self {
+            character_name: (!character_name.is_empty()).then_some(character_name),
+            text,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PlainTextMessage;
+
+    #[test]
+    fn narration() {
+        assert_eq!(
+            PlainTextMessage::parse("@rSome narration.@k@rAnd more of it."),
+            PlainTextMessage {
+                character_name: None,
+                text: "Some narration.\nAnd more of it.".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn character_name_and_commands() {
+        assert_eq!(
+            PlainTextMessage::parse("@v00/awase0001.@[Battler@]@r@c940.「Hello@w400.@y, world」@c."),
+            PlainTextMessage {
+                character_name: Some("Battler".to_string()),
+                text: "「Hello, world」".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn rubi() {
+        assert_eq!(
+            PlainTextMessage::parse("@r@bかな.@<漢字@>です"),
+            PlainTextMessage {
+                character_name: None,
+                text: "漢字(かな)です".to_string(),
+            }
+        );
+    }
+}
@@ -26,4 +26,5 @@ pub trait TextLayouter {
     fn on_rubi_base_end(&mut self);
     fn on_bold_start(&mut self);
     fn on_bold_end(&mut self);
+    fn on_set_link(&mut self, link: i32);
 }
@@ -0,0 +1,123 @@
+use std::time::Duration;
+
+use crate::time::TICKS_PER_SECOND;
+
+const NANOS_PER_SECOND: u64 = 1_000_000_000;
+
+/// Turns the wall-clock time between the frames into whole ticks, to run the VM at a fixed tick rate regardless of the frame rate.
+///
+/// The part of a tick left over from a frame is carried over to the next one.
+/// The frame time is clamped to `max_frame_time`, so that after a stall the game slows down for a moment instead of trying to catch up in one go, which would make the next frame slow too.
+#[derive(Debug, Clone)]
+pub struct TickAccumulator {
+    max_frame_time: Duration,
+    /// The time left over, in nanoseconds multiplied by the tick rate, so that the ticks are counted exactly
+    remainder: u64,
+}
+
+impl TickAccumulator {
+    pub const DEFAULT_MAX_FRAME_TIME: Duration = Duration::from_millis(250);
+
+    pub fn new(max_frame_time: Duration) -> Self {
+        Self {
+            max_frame_time,
+            remainder: 0,
+        }
+    }
+
+    /// Add the time a frame took, returning how many ticks to run
+    pub fn push(&mut self, frame_time: Duration) -> u32 {
+        let frame_time = frame_time.min(self.max_frame_time);
+
+        self.remainder += frame_time.as_nanos() as u64 * TICKS_PER_SECOND as u64;
+        let ticks = self.remainder / NANOS_PER_SECOND;
+        self.remainder %= NANOS_PER_SECOND;
+
+        ticks as u32
+    }
+
+    /// The part of the next tick that has already passed, in the range [0.0, 1.0)
+    pub fn fraction(&self) -> f32 {
+        self.remainder as f32 / NANOS_PER_SECOND as f32
+    }
+}
+
+impl Default for TickAccumulator {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_MAX_FRAME_TIME)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::TickAccumulator;
+    use crate::time::Ticks;
+
+    #[test]
+    fn conversion_round_trip() {
+        for ticks in [0.0, 1.0, 2.5, 60.0, 3600.0] {
+            let ticks = Ticks::from_f32(ticks);
+
+            let seconds = Ticks::from_seconds(ticks.as_seconds());
+            assert!(
+                (seconds.as_f32() - ticks.as_f32()).abs() < 1e-3,
+                "{:?}",
+                ticks
+            );
+            let duration = Ticks::from_duration(ticks.as_duration());
+            assert!(
+                (duration.as_f32() - ticks.as_f32()).abs() < 1e-3,
+                "{:?}",
+                ticks
+            );
+        }
+
+        assert_eq!(
+            Ticks::from_u32(90).as_duration(),
+            Duration::from_millis(1500)
+        );
+    }
+
+    #[test]
+    fn carries_the_remainder() {
+        let mut accumulator = TickAccumulator::default();
+
+        // 40 fps is a tick and a half per frame
+        let ticks = (0..4)
+            .map(|_| accumulator.push(Duration::from_millis(25)))
+            .collect::<Vec<_>>();
+        assert_eq!(ticks, [1, 2, 1, 2]);
+        assert_eq!(accumulator.fraction(), 0.0);
+
+        // frames shorter than a tick add up
+        assert_eq!(accumulator.push(Duration::from_millis(10)), 0);
+        assert!((accumulator.fraction() - 0.6).abs() < 1e-6);
+        assert_eq!(accumulator.push(Duration::from_millis(10)), 1);
+    }
+
+    #[test]
+    fn exact_tick_rate() {
+        let mut accumulator = TickAccumulator::default();
+
+        // a second worth of frames at 144 fps, rounded to whole nanoseconds
+        let frame_time = Duration::from_nanos(1_000_000_000 / 144);
+        let ticks = (0..144 * 10)
+            .map(|_| accumulator.push(frame_time))
+            .sum::<u32>();
+        // the rounding loses less than a tick over ten seconds
+        assert_eq!(ticks, 599);
+    }
+
+    #[test]
+    fn clamps_stalls() {
+        let mut accumulator = TickAccumulator::default();
+
+        assert_eq!(accumulator.push(Duration::from_secs(10)), 15);
+        assert_eq!(accumulator.fraction(), 0.0);
+
+        let mut accumulator = TickAccumulator::new(Duration::from_secs(1));
+        assert_eq!(accumulator.push(Duration::from_secs(10)), 60);
+    }
+}
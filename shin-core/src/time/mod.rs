@@ -1,3 +1,4 @@
+mod accumulator;
 mod tween;
 mod tweener;
 
@@ -7,6 +8,7 @@ use std::{
     time::Duration,
 };
 
+pub use accumulator::TickAccumulator;
 use derive_more::{Add, AddAssign, Sub, SubAssign};
 use float_ord::FloatOrd;
 use tracing::warn;
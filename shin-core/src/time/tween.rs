@@ -26,8 +26,24 @@ pub enum Easing {
 const HALF_PI: f32 = PI / 2.0;
 
 impl Easing {
-    fn apply(&self, x: f32) -> f32 {
-        match *self {
+    /// Get the easing selected by the index in the [`LayerCtrlFlags`](crate::vm::command::types::LayerCtrlFlags), returning `None` for the unknown ones
+    ///
+    /// The [`Easing::Power`] curve takes its power from a separate `LAYERCTRL` parameter, which is passed as `power`.
+    pub fn from_index(index: i32, power: i32) -> Option<Easing> {
+        Some(match index {
+            0 => Easing::Linear,
+            1 => Easing::SineIn,
+            2 => Easing::SineOut,
+            3 => Easing::SineInOut,
+            4 => Easing::Jump,
+            5 => Easing::Power(power),
+            _ => return None,
+        })
+    }
+
+    /// Sample the curve at `x`, going from 0 at `x = 0` to 1 at `x = 1`
+    pub fn apply(self, x: f32) -> f32 {
+        match self {
             Easing::Linear => x,
             Easing::SineIn => 1.0 - (x * HALF_PI).cos(),
             Easing::SineOut => (x * HALF_PI).sin(),
@@ -85,3 +101,45 @@ impl Tween {
         self.easing.apply(x)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Easing;
+
+    fn all_easings() -> Vec<Easing> {
+        let mut easings = (0..5)
+            .map(|index| Easing::from_index(index, 0).unwrap())
+            .collect::<Vec<_>>();
+        easings.extend([-3, -2, -1, 0, 1, 2, 3].map(|power| Easing::from_index(5, power).unwrap()));
+        easings
+    }
+
+    #[test]
+    fn from_index() {
+        assert_eq!(Easing::from_index(0, 0), Some(Easing::Linear));
+        assert_eq!(Easing::from_index(3, 0), Some(Easing::SineInOut));
+        assert_eq!(Easing::from_index(5, -2), Some(Easing::Power(-2)));
+        assert_eq!(Easing::from_index(6, 0), None);
+        assert_eq!(Easing::from_index(-1, 0), None);
+    }
+
+    #[test]
+    fn boundaries() {
+        for easing in all_easings() {
+            assert!(easing.apply(0.0).abs() < 1e-6, "{:?}", easing);
+            assert!((easing.apply(1.0) - 1.0).abs() < 1e-6, "{:?}", easing);
+        }
+    }
+
+    #[test]
+    fn monotonic() {
+        for easing in all_easings() {
+            let samples = (0..=100)
+                .map(|i| easing.apply(i as f32 / 100.0))
+                .collect::<Vec<_>>();
+            for pair in samples.windows(2) {
+                assert!(pair[0] <= pair[1], "{:?}: {:?}", easing, samples);
+            }
+        }
+    }
+}
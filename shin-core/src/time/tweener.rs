@@ -48,10 +48,35 @@ impl Tweener {
         }
     }
 
+    /// The value the tweener will have once all the enqueued tweens finish playing.
+    pub fn final_value(&self) -> Value {
+        match self.tween_queue.back() {
+            Some(&(value, _)) => value,
+            None => match self.state {
+                State::Idle => self.value,
+                State::Tweening {
+                    values: (_, value), ..
+                } => value,
+            },
+        }
+    }
+
     pub fn is_idle(&self) -> bool {
         matches!(self.state, State::Idle)
     }
 
+    /// Time left until all the enqueued tweens finish playing.
+    pub fn remaining_duration(&self) -> Ticks {
+        let current = match self.state {
+            State::Idle => Ticks::ZERO,
+            State::Tweening { time, tween, .. } => tween.duration - time,
+        };
+
+        self.tween_queue
+            .iter()
+            .fold(current, |acc, &(_, tween)| acc + tween.duration)
+    }
+
     /// Enqueues a new value to tween to.
     pub fn enqueue(&mut self, value: Value, tween: Tween) {
         match self.state {
@@ -77,11 +102,11 @@ impl Tweener {
         a + (b - a) * amount
     }
 
-    fn next(&mut self, time: Ticks) {
+    fn next(&mut self) {
         if let Some((value, tween)) = self.tween_queue.pop_front() {
             self.state = State::Tweening {
                 values: (self.value, value),
-                time,
+                time: Ticks::ZERO,
                 tween,
             };
         } else {
@@ -89,27 +114,32 @@ impl Tweener {
         }
     }
 
+    /// Advances the playback, moving on to the next enqueued tweens as the current ones finish.
+    ///
+    /// The time left over after a tween finishes is spent on the next ones, so a long update can go through several short tweens at once.
     pub fn update(&mut self, delta_time: Ticks) {
-        if let State::Tweening {
+        let mut delta_time = delta_time;
+        while let State::Tweening {
             values,
             time,
             tween,
         } = &mut self.state
         {
             *time += delta_time;
-            if *time >= tween.duration {
-                self.value = values.1;
-                let remaining_time = *time - tween.duration;
-                self.next(remaining_time);
-            } else {
+            if *time < tween.duration {
                 self.value = Self::lerp(values.0, values.1, tween.value(*time));
+                return;
             }
+
+            self.value = values.1;
+            delta_time = *time - tween.duration;
+            self.next();
         }
     }
 
     /// Fast-forwards the tweener to the last enqueue value.
     pub fn fast_forward(&mut self) {
-        let last_queue_value = self.tween_queue.pop_front();
+        let last_queue_value = self.tween_queue.pop_back();
         self.tween_queue.clear();
 
         let value = match last_queue_value {
@@ -137,3 +167,66 @@ impl Tweener {
         self.enqueue(value, tween);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Tweener;
+    use crate::time::{Ticks, Tween};
+
+    fn linear(ticks: u32) -> Tween {
+        Tween::linear(Ticks::from_u32(ticks))
+    }
+
+    #[test]
+    fn single_tween_does_not_allocate() {
+        let mut tweener = Tweener::new(0.0);
+        tweener.enqueue(10.0, linear(10));
+        tweener.enqueue_now(20.0, linear(10));
+
+        assert_eq!(tweener.tween_queue.capacity(), 0);
+        assert_eq!(tweener.final_value(), 20.0);
+    }
+
+    #[test]
+    fn chained_tweens() {
+        let mut tweener = Tweener::new(0.0);
+        tweener.enqueue(10.0, linear(10));
+        tweener.enqueue(0.0, linear(10));
+        assert_eq!(tweener.target_value(), 10.0);
+        assert_eq!(tweener.final_value(), 0.0);
+
+        let mut path = Vec::new();
+        for _ in 0..4 {
+            tweener.update(Ticks::from_u32(5));
+            path.push(tweener.value());
+            // idle only after the whole queue is played
+            assert_eq!(tweener.is_idle(), path.len() == 4);
+        }
+        assert_eq!(path, [5.0, 10.0, 5.0, 0.0]);
+    }
+
+    #[test]
+    fn update_spanning_several_tweens() {
+        let mut tweener = Tweener::new(0.0);
+        tweener.enqueue(10.0, linear(10));
+        tweener.enqueue(20.0, linear(2));
+        tweener.enqueue(0.0, linear(10));
+
+        // the left over time is carried over to the following tweens
+        tweener.update(Ticks::from_u32(17));
+        assert_eq!(tweener.value(), 10.0);
+        assert_eq!(tweener.remaining_duration(), Ticks::from_u32(5));
+    }
+
+    #[test]
+    fn fast_forward_to_the_last_value() {
+        let mut tweener = Tweener::new(0.0);
+        tweener.enqueue(10.0, linear(10));
+        tweener.enqueue(20.0, linear(10));
+        tweener.enqueue(30.0, linear(10));
+
+        tweener.fast_forward();
+        assert_eq!(tweener.value(), 30.0);
+        assert!(tweener.is_idle());
+    }
+}
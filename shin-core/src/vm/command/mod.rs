@@ -1,5 +1,6 @@
 //! Defines the commands that can be produced by the VM and executed by the engine.
 
+pub mod spec;
 pub mod types;
 
 use shin_derive::Command;
@@ -18,7 +19,7 @@ use crate::{
         text::{StringArray, U16FixupString, U16String},
     },
     time::Ticks,
-    vm::command::types::{PlaneId, WipeFlags, WiperType},
+    vm::command::types::{WipeFlags, WiperType},
 };
 
 #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
@@ -82,6 +83,7 @@ pub enum Command {
         ///
         /// If the message is not waited, [MSGWAIT](Command::MSGWAIT) can be called to synchronize with parts the message
         auto_wait: U8Bool,
+        /// The fixup is undone when the string is decoded, so the runtime form is the plain message text
         text: U16FixupString,
     },
     /// Waits for message to reach the specified section
@@ -247,6 +249,7 @@ pub enum Command {
     RESUMESET {},
     #[cmd(opcode = 0xa5u8)]
     RESUME {},
+    /// Request an engine-specific service, see [`SyscallHandler`](crate::vm::syscall::SyscallHandler)
     #[cmd(opcode = 0xa6u8)]
     SYSCALL {
         call_id: NumberSpec,
@@ -294,8 +297,14 @@ pub enum Command {
         layer_id: NumberSpec<VLayerId>,
         wait_properties: U8SmallNumberList<LayerProperty>,
     },
+    /// Exchange the contents of two layer slots on the current plane, along with their properties
+    ///
+    /// Swapping with an unloaded slot moves the layer there.
     #[cmd(opcode = 0xc5u8)]
-    LAYERSWAP { arg1: NumberSpec, arg2: NumberSpec },
+    LAYERSWAP {
+        layer_id_1: NumberSpec,
+        layer_id_2: NumberSpec,
+    },
     /// Select a subset of layers to perform batch operations
     ///
     /// These can then be used as [VLayerIdRepr::Selected] in commands accepting a [VLayerId].
@@ -322,8 +331,11 @@ pub enum Command {
     TRANSWAIT { arg: NumberSpec },
     #[cmd(opcode = 0xcbu8)]
     PAGEBACK {},
+    /// Select the plane the layer commands operate on
+    ///
+    /// The id is not a `NumberSpec<PlaneId>`, as an out-of-range id is a script error that should not bring the whole VM down
     #[cmd(opcode = 0xccu8)]
-    PLANESELECT { plane_id: NumberSpec<PlaneId> },
+    PLANESELECT { plane_id: NumberSpec },
     #[cmd(opcode = 0xcdu8)]
     PLANECLEAR {},
     #[cmd(opcode = 0xceu8)]
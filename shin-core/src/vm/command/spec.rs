@@ -0,0 +1,209 @@
+//! Describes the parameters of the commands, so that tools (like the assembler lints or the disassembler) can inspect them at runtime.
+//!
+//! The specs are generated by the `Command` derive macro from the [`Command`](super::Command) enum, so they can't go out of sync with it.
+
+use std::{any::Any, fmt::Debug};
+
+use super::CompiletimeCommand;
+use crate::format::{
+    scenario::{
+        instruction_elements::{BitmaskNumberArray, MessageId, NumberSpec, Register, U8Bool},
+        types::SmallList,
+    },
+    text::{
+        StringArray,
+        string::{NoFixup, SJisString, StringLengthDesc, WithFixup},
+    },
+};
+
+/// Describes a command, available as `compiletime::NAME::SPEC` or through [`CompiletimeCommand::spec`](super::CompiletimeCommand::spec) and [`RuntimeCommand::spec`](super::RuntimeCommand::spec)
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CommandSpec {
+    pub name: &'static str,
+    pub opcode: u8,
+    /// The parameters, in the order they are encoded in
+    pub params: &'static [ParamSpec],
+}
+
+impl CommandSpec {
+    pub fn by_name(name: &str) -> Option<&'static CommandSpec> {
+        CompiletimeCommand::SPECS
+            .iter()
+            .find(|spec| spec.name == name)
+    }
+
+    pub fn by_opcode(opcode: u8) -> Option<&'static CommandSpec> {
+        CompiletimeCommand::spec_by_opcode(opcode)
+    }
+
+    pub fn param(&self, name: &str) -> Option<&'static ParamSpec> {
+        self.params.iter().find(|param| param.name == name)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ParamSpec {
+    pub name: &'static str,
+    /// The type of the parameter, as written in the command definition (like `NumberSpec<Volume>`)
+    pub ty: &'static str,
+    pub kind: ParamKind,
+    /// Whether the command writes its result to this register
+    pub dest: bool,
+}
+
+/// How a command parameter is encoded
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ParamKind {
+    /// A number embedded directly in the instruction
+    Immediate,
+    /// A [`NumberSpec`], either a constant or a register
+    Number,
+    /// A [`U8Bool`]
+    Bool,
+    Register,
+    MessageId,
+    String {
+        /// Whether the string has the fixup applied (see [`WithFixup`])
+        fixup: bool,
+    },
+    StringArray,
+    /// A list of [`NumberSpec`]s
+    NumberList,
+}
+
+/// A value of a command parameter, which can be inspected without knowing the command type
+pub trait ParamValue: Debug + Any {
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: Debug + Any> ParamValue for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Implemented for the types that can be used as command parameters
+pub trait CommandParam {
+    const KIND: ParamKind;
+}
+
+macro_rules! command_param {
+    ($($ty:ty => $kind:expr),* $(,)?) => {
+        $(
+            impl CommandParam for $ty {
+                const KIND: ParamKind = $kind;
+            }
+        )*
+    };
+}
+
+command_param!(
+    u8 => ParamKind::Immediate,
+    u16 => ParamKind::Immediate,
+    U8Bool => ParamKind::Bool,
+    Register => ParamKind::Register,
+    MessageId => ParamKind::MessageId,
+    StringArray => ParamKind::StringArray,
+);
+
+impl<T> CommandParam for NumberSpec<T> {
+    const KIND: ParamKind = ParamKind::Number;
+}
+
+impl<L: StringLengthDesc> CommandParam for SJisString<L, NoFixup> {
+    const KIND: ParamKind = ParamKind::String { fixup: false };
+}
+
+impl<L: StringLengthDesc> CommandParam for SJisString<L, WithFixup> {
+    const KIND: ParamKind = ParamKind::String { fixup: true };
+}
+
+impl<L, T, const N: usize> CommandParam for SmallList<L, NumberSpec<T>, N>
+where
+    L: Into<usize> + TryFrom<usize> + 'static,
+{
+    const KIND: ParamKind = ParamKind::NumberList;
+}
+
+impl<T1, T2, T3, T4, T5, T6, T7, T8> CommandParam
+    for BitmaskNumberArray<T1, T2, T3, T4, T5, T6, T7, T8>
+{
+    const KIND: ParamKind = ParamKind::NumberList;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CommandSpec, ParamKind};
+    use crate::{
+        format::{
+            scenario::instruction_elements::{MessageId, U8Bool},
+            text::U16FixupString,
+        },
+        vm::command::{
+            CompiletimeCommand,
+            compiletime::{BGMPLAY, MSGSET, SGET},
+        },
+    };
+
+    #[test]
+    fn msgset_spec() {
+        let spec = MSGSET::SPEC;
+
+        assert_eq!(spec.name, "MSGSET");
+        assert_eq!(spec.opcode, 0x86);
+        assert_eq!(
+            spec.params
+                .iter()
+                .map(|param| param.name)
+                .collect::<Vec<_>>(),
+            ["msg_id", "auto_wait", "text"]
+        );
+
+        let text = spec.param("text").unwrap();
+        assert_eq!(text.kind, ParamKind::String { fixup: true });
+        assert_eq!(text.ty, "U16FixupString");
+        assert_eq!(spec.param("auto_wait").unwrap().kind, ParamKind::Bool);
+
+        let command = CompiletimeCommand::MSGSET(MSGSET {
+            msg_id: MessageId(0),
+            auto_wait: U8Bool(true),
+            text: U16FixupString::new("hello"),
+        });
+        assert_eq!(command.spec(), &spec);
+    }
+
+    #[test]
+    fn typed_and_dest_params() {
+        let volume = BGMPLAY::SPEC.param("volume").unwrap();
+        assert_eq!(volume.kind, ParamKind::Number);
+        assert_eq!(volume.ty, "NumberSpec<Volume>");
+
+        assert!(SGET::SPEC.param("dest").unwrap().dest);
+        assert!(!SGET::SPEC.param("slot_number").unwrap().dest);
+    }
+
+    #[test]
+    fn specs_by_name() {
+        assert_eq!(CommandSpec::by_name("BGMPLAY"), Some(&BGMPLAY::SPEC));
+        assert_eq!(CommandSpec::by_name("NOPE"), None);
+
+        let opcodes = CompiletimeCommand::SPECS
+            .iter()
+            .map(|spec| spec.opcode)
+            .collect::<Vec<_>>();
+        assert!(opcodes.is_sorted());
+    }
+
+    #[test]
+    fn specs_by_opcode() {
+        assert_eq!(CommandSpec::by_opcode(0x86), Some(&MSGSET::SPEC));
+        assert_eq!(CommandSpec::by_opcode(0x00).unwrap().name, "EXIT");
+        assert_eq!(CommandSpec::by_opcode(0x84), None);
+
+        // every opcode maps back to its own command, so there are no duplicates
+        for spec in CompiletimeCommand::SPECS {
+            assert_eq!(CommandSpec::by_opcode(spec.opcode), Some(spec));
+            assert_eq!(CommandSpec::by_name(spec.name), Some(spec));
+        }
+    }
+}
@@ -105,3 +105,37 @@ impl FromNumber for WipeFlags {
         WipeFlags::from_bits(number).expect("Invalid WipeFlags")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::MaskFlags;
+    use crate::{
+        format::scenario::instruction_elements::FromNumber, vm::command::types::MaskParam,
+    };
+
+    #[test]
+    fn mask_flags() {
+        assert_eq!(MaskFlags::from_number(0), MaskFlags::empty());
+        assert_eq!(
+            MaskFlags::from_number(0x13),
+            MaskFlags::FLIP_X | MaskFlags::FLIP_Y | MaskFlags::SCALE
+        );
+        assert_eq!(MaskFlags::from_number(0x4), MaskFlags::INVERT);
+    }
+
+    #[test]
+    #[should_panic]
+    fn unknown_mask_flags() {
+        MaskFlags::from_number(0x8);
+    }
+
+    #[test]
+    fn mask_param() {
+        // zero means the softest edge
+        assert_eq!(MaskParam::from_number(0), MaskParam(1.0));
+        assert_eq!(MaskParam::from_number(250), MaskParam(0.25));
+        // the value is clamped, so that there is no division by zero
+        assert_eq!(MaskParam::from_number(-5), MaskParam(0.001));
+        assert_eq!(MaskParam::from_number(5000), MaskParam(1.0));
+    }
+}
@@ -1,4 +1,4 @@
-//! Defines `FromVmCtx` and `FromVmCtxDefault` traits, that are used to convert from compile-time (e.g. `NumberSpec`) to runtime (e.g. `i32`) representations of command parameters
+//! Defines the `IntoRuntimeForm` trait, that is used to convert from compile-time (e.g. `NumberSpec`) to runtime (e.g. `i32`) representations of command parameters
 //!
 //! Also contains implementation for std types & stuff defined in `shin_core::format`, like `U8String` -> `String` stuff
 
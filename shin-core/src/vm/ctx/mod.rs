@@ -1,19 +1,64 @@
 mod into_runtime_form;
+mod register_names;
+
+use std::sync::Arc;
 
 pub use into_runtime_form::*;
+pub use register_names::*;
 use smallvec::SmallVec;
 use tracing::warn;
 
-use crate::format::scenario::{
-    instruction_elements::{
-        CodeAddress, FromNumber, NumberSpec, Register, RegisterRepr, UntypedNumberSpec,
+use crate::{
+    format::scenario::{
+        instruction_elements::{
+            CodeAddress, FromNumber, NumberSpec, Register, RegisterRepr, UntypedNumberSpec,
+        },
+        instructions::{BinaryOperationType, Expression, ExpressionTerm, JumpCond, JumpCondType},
     },
-    instructions::{BinaryOperationType, Expression, ExpressionTerm, JumpCond, JumpCondType},
+    vm::command::RuntimeCommand,
 };
 
+/// Call stack depth used by [`VmCtx::new`], way more than any real scenario needs
+pub const DEFAULT_MAX_CALL_STACK_DEPTH: usize = 1024;
+
+/// An error in the execution of a scenario, which is most likely malformed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VmError {
+    /// A value was pushed to the call stack already holding the maximum number of values, usually because of runaway recursion
+    CallStackOverflow { depth: usize },
+}
+
+impl std::fmt::Display for VmError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VmError::CallStackOverflow { depth } => {
+                write!(f, "call stack overflow (depth {})", depth)
+            }
+        }
+    }
+}
+
+impl std::error::Error for VmError {}
+
+/// Callback invoked with the address and the decoded form of every command the VM issues
+pub type TraceHookFn = dyn FnMut(CodeAddress, &RuntimeCommand) + Send;
+
+/// Holder of the optional [`TraceHookFn`]
+///
+/// The hook is a debugging aid and not a part of the VM state, so it's not carried over to the clones (and snapshots).
+#[derive(Default)]
+struct TraceHook(Option<Box<TraceHookFn>>);
+
+impl Clone for TraceHook {
+    fn clone(&self) -> Self {
+        Self(None)
+    }
+}
+
 /// Contains the full VM state
 ///
 /// It consists of a memory, two stacks (call and data)
+#[derive(Clone)]
 pub struct VmCtx {
     /// Memory (aka registers I guess)
     regular_registers: [i32; 0x1000],
@@ -23,6 +68,8 @@ pub struct VmCtx {
     ///
     /// Also [push](super::Instruction::push) uses this stack for some reason
     call_stack: Vec<CodeAddress>,
+    /// Maximum number of values the call stack can hold
+    max_call_stack_depth: usize,
     /// Data stack
     ///
     /// Stores the arguments for each call instruction
@@ -33,6 +80,9 @@ pub struct VmCtx {
     arguments_stack: Vec<SmallVec<i32, 6>>,
     /// PRNG state, updated on each instruction executed
     prng_state: u32,
+    trace_hook: TraceHook,
+    /// Names to format the registers with in the traces, shared between the clones
+    register_names: Arc<RegisterNames>,
 }
 
 #[inline]
@@ -77,15 +127,74 @@ impl VmCtx {
         Self {
             regular_registers: memory,
             call_stack: Vec::new(),
+            max_call_stack_depth: DEFAULT_MAX_CALL_STACK_DEPTH,
             arguments_stack: Vec::new(),
             prng_state: random_seed,
+            trace_hook: TraceHook::default(),
+            register_names: Arc::new(RegisterNames::new()),
+        }
+    }
+
+    /// Install a hook that is called right before each command is handed off to the engine, replacing the previous one
+    ///
+    /// Meant for tracing the execution, e.g. to compare it with the original engine.
+    pub fn set_trace_hook(
+        &mut self,
+        hook: impl FnMut(CodeAddress, &RuntimeCommand) + Send + 'static,
+    ) {
+        self.trace_hook = TraceHook(Some(Box::new(hook)));
+    }
+
+    pub fn clear_trace_hook(&mut self) {
+        self.trace_hook = TraceHook(None);
+    }
+
+    pub(super) fn take_trace_hook(&mut self) -> Option<Box<TraceHookFn>> {
+        self.trace_hook.0.take()
+    }
+
+    pub(super) fn restore_trace_hook(&mut self, hook: Option<Box<TraceHookFn>>) {
+        self.trace_hook = TraceHook(hook);
+    }
+
+    /// Pass the command to the trace hook, if there is one
+    #[inline]
+    pub fn trace_command(&mut self, address: CodeAddress, command: &RuntimeCommand) {
+        if let Some(hook) = &mut self.trace_hook.0 {
+            hook(address, command);
         }
     }
 
+    /// Set the names the registers are formatted with in the traces, replacing the previous ones
+    pub fn set_register_names(&mut self, names: RegisterNames) {
+        self.register_names = Arc::new(names);
+    }
+
+    pub fn register_names(&self) -> &RegisterNames {
+        &self.register_names
+    }
+
+    pub(super) fn shared_register_names(&self) -> Arc<RegisterNames> {
+        self.register_names.clone()
+    }
+
+    pub(super) fn set_shared_register_names(&mut self, names: Arc<RegisterNames>) {
+        self.register_names = names;
+    }
+
+    /// Set the maximum number of values the call stack can hold, see [`VmError::CallStackOverflow`]
+    pub fn set_max_call_stack_depth(&mut self, depth: usize) {
+        self.max_call_stack_depth = depth;
+    }
+
     pub(super) fn get_prng_state(&self) -> u32 {
         self.prng_state
     }
 
+    pub(super) fn set_prng_state(&mut self, state: u32) {
+        self.prng_state = state;
+    }
+
     /// Get the value from memory
     ///
     /// The address can be a stack offset (mem3) or main memory address (mem1)
@@ -150,8 +259,14 @@ impl VmCtx {
         }
     }
 
-    pub fn push_code_stack(&mut self, addr: CodeAddress) {
+    pub fn push_code_stack(&mut self, addr: CodeAddress) -> Result<(), VmError> {
+        if self.call_stack.len() >= self.max_call_stack_depth {
+            return Err(VmError::CallStackOverflow {
+                depth: self.call_stack.len(),
+            });
+        }
         self.call_stack.push(addr);
+        Ok(())
     }
 
     pub fn pop_code_stack(&mut self) -> CodeAddress {
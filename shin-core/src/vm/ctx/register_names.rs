@@ -0,0 +1,104 @@
+//! Defines `RegisterNames`, human-readable names of the registers used when tracing the VM execution
+
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Display},
+};
+
+use crate::format::scenario::instruction_elements::Register;
+
+/// Maps the registers to names, like the register aliases of the assembler sources
+///
+/// The names are written without the `$` prefix, it's added when formatting.
+#[derive(Debug, Default, Clone)]
+pub struct RegisterNames(HashMap<Register, String>);
+
+impl RegisterNames {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, register: Register, name: impl Into<String>) {
+        self.0.insert(register, name.into());
+    }
+
+    pub fn get(&self, register: Register) -> Option<&str> {
+        self.0.get(&register).map(String::as_str)
+    }
+
+    /// Format the register by its name, falling back to the numeric form (like `$v42`) for the unnamed ones
+    pub fn display(&self, register: Register) -> NamedRegister<'_> {
+        NamedRegister {
+            names: self,
+            register,
+        }
+    }
+}
+
+impl<S: Into<String>> FromIterator<(Register, S)> for RegisterNames {
+    fn from_iter<I: IntoIterator<Item = (Register, S)>>(iter: I) -> Self {
+        Self(
+            iter.into_iter()
+                .map(|(register, name)| (register, name.into()))
+                .collect(),
+        )
+    }
+}
+
+/// A register formatted with its name, see [`RegisterNames::display`]
+#[derive(Copy, Clone)]
+pub struct NamedRegister<'a> {
+    names: &'a RegisterNames,
+    register: Register,
+}
+
+impl Display for NamedRegister<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.names.get(self.register) {
+            Some(name) => write!(f, "${}", name),
+            None => Display::fmt(&self.register, f),
+        }
+    }
+}
+
+impl Debug for NamedRegister<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RegisterNames;
+    use crate::{format::scenario::instruction_elements::Register, vm::VmCtx};
+
+    fn register(s: &str) -> Register {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn display_aliases() {
+        let names = [
+            (register("$v10"), "EPISODE"),
+            (register("$a0"), "arg_count"),
+        ]
+        .into_iter()
+        .collect::<RegisterNames>();
+
+        let mut ctx = VmCtx::new(0, 0);
+        assert_eq!(
+            format!("{}", ctx.register_names().display(register("$v10"))),
+            "$v10"
+        );
+
+        ctx.set_register_names(names);
+        let names = ctx.register_names();
+        assert_eq!(format!("{}", names.display(register("$v10"))), "$EPISODE");
+        assert_eq!(
+            format!("{:?}", names.display(register("$a0"))),
+            "$arg_count"
+        );
+        // the unnamed ones keep the numeric form
+        assert_eq!(format!("{}", names.display(register("$v11"))), "$v11");
+    }
+}
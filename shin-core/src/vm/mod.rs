@@ -14,6 +14,8 @@
 //!
 //! Most commands do not have any feedback to the VM, except for [SGET](command::runtime::SGET), [SELECT](command::runtime::SELECT) and [QUIZ](command::runtime::QUIZ).
 //!
+//! The [SYSCALL](command::runtime::SYSCALL) command is the exception: it's handled by the VM itself, with a [`SyscallHandler`](syscall::SyscallHandler).
+//!
 //! # Usage
 //!
 //! The [`Scripter`] struct is the main entry point for the VM. It reads a scenario, executes the instructions and returns commands for engine to execute.
@@ -22,6 +24,7 @@
 pub mod breakpoint;
 pub mod command;
 mod ctx;
+pub mod syscall;
 
 use anyhow::Result;
 pub use ctx::*;
@@ -31,12 +34,13 @@ use tracing::{instrument, trace};
 use crate::{
     format::scenario::{
         InstructionReader, Scenario,
-        instruction_elements::CodeAddress,
+        instruction_elements::{CodeAddress, Register},
         instructions::{BinaryOperation, Instruction, UnaryOperation, UnaryOperationType},
     },
     vm::{
         breakpoint::{BreakpointHandle, CodeBreakpointSet},
         command::{CommandResult, RuntimeCommand},
+        syscall::{DefaultSyscallHandler, SyscallHandler},
     },
 };
 
@@ -75,7 +79,33 @@ pub struct Scripter {
     ctx: VmCtx,
     instruction_reader: InstructionReader,
     position: CodeAddress,
+    /// PRNG state before the instruction at `position` was executed
+    position_prng_state: u32,
     breakpoints: CodeBreakpointSet,
+    syscall_handler: Box<dyn SyscallHandler>,
+}
+
+/// A copy of the [`Scripter`] execution state, used to implement saving and loading
+///
+/// Breakpoints are not part of the snapshot.
+#[derive(Clone)]
+pub struct ScripterSnapshot {
+    ctx: VmCtx,
+    position: CodeAddress,
+}
+
+impl ScripterSnapshot {
+    /// Get the position the VM will resume execution from after restoring this snapshot
+    pub fn position(&self) -> CodeAddress {
+        self.position
+    }
+
+    /// Get the PRNG state captured in this snapshot
+    ///
+    /// This is what the save files store as [`GameDataEntry::random_seed`](crate::format::save::GameDataEntry::random_seed): passing it as the `random_seed` of [`Scripter::new`] continues the same sequence of random numbers.
+    pub fn prng_state(&self) -> u32 {
+        self.ctx.get_prng_state()
+    }
 }
 
 impl Scripter {
@@ -91,7 +121,9 @@ impl Scripter {
             ctx: VmCtx::new(init_val, random_seed),
             instruction_reader: scenario.instruction_reader(scenario.entrypoint_address()),
             position: scenario.entrypoint_address(),
+            position_prng_state: random_seed,
             breakpoints: CodeBreakpointSet::new(),
+            syscall_handler: Box::new(DefaultSyscallHandler),
         }
     }
 
@@ -103,7 +135,8 @@ impl Scripter {
         &mut self,
         instruction: Instruction,
         pc: CodeAddress,
-    ) -> Option<RuntimeCommand> {
+    ) -> Result<Option<RuntimeCommand>, VmError> {
+        self.position_prng_state = self.ctx.get_prng_state();
         self.ctx.update_prng();
         self.position = pc;
 
@@ -120,7 +153,7 @@ impl Scripter {
                     _ => todo!(),
                 };
 
-                trace!(?pc, ?ty, ?destination, ?source, ?result, "uo");
+                trace!(?pc, ?ty, destination = ?self.ctx.register_names().display(destination), ?source, ?result, "uo");
 
                 self.ctx.write_register(destination, result);
             }
@@ -134,14 +167,14 @@ impl Scripter {
                 let right = self.ctx.get_number(right);
                 let result = self.ctx.evaluate_binary_operation(ty, left, right);
 
-                trace!(?pc, ?ty, ?destination, ?left, ?right, ?result, "bo");
+                trace!(?pc, ?ty, destination = ?self.ctx.register_names().display(destination), ?left, ?right, ?result, "bo");
 
                 self.ctx.write_register(destination, result);
             }
 
             Instruction::exp { dest, expr } => {
                 let result = self.ctx.evaluate_expression(&expr);
-                trace!(?pc, ?dest, ?result, ?expr, "exp");
+                trace!(?pc, dest = ?self.ctx.register_names().display(dest), ?result, ?expr, "exp");
                 self.ctx.write_register(dest, result);
             }
             Instruction::gt { dest, index, table } => {
@@ -152,7 +185,7 @@ impl Scripter {
                 } else {
                     0
                 };
-                trace!(?pc, ?index, ?result, ?dest, table_len = ?table.0.len(), "gt");
+                trace!(?pc, ?index, ?result, dest = ?self.ctx.register_names().display(dest), table_len = ?table.0.len(), "gt");
                 self.ctx.write_register(dest, result);
             }
             Instruction::jc {
@@ -176,7 +209,8 @@ impl Scripter {
             }
             Instruction::gosub { target } => {
                 trace!(?pc, ?target, "gosub");
-                self.ctx.push_code_stack(self.instruction_reader.position());
+                self.ctx
+                    .push_code_stack(self.instruction_reader.position())?;
                 self.instruction_reader.set_position(target);
             }
             Instruction::retsub {} => {
@@ -199,7 +233,7 @@ impl Scripter {
                 let min = self.ctx.get_number(min);
                 let max = self.ctx.get_number(max);
                 let result = self.ctx.run_prng(min, max);
-                trace!(?pc, ?dest, ?min, ?max, ?result, prng_state = ?self.ctx.get_prng_state(), "rnd");
+                trace!(?pc, dest = ?self.ctx.register_names().display(dest), ?min, ?max, ?result, prng_state = ?self.ctx.get_prng_state(), "rnd");
                 self.ctx.write_register(dest, result);
             }
             Instruction::call { target, args } => {
@@ -210,7 +244,8 @@ impl Scripter {
                     .collect::<SmallVec<i32, 6>>();
                 trace!(?pc, ?target, ?args, "call");
 
-                self.ctx.push_code_stack(self.instruction_reader.position());
+                self.ctx
+                    .push_code_stack(self.instruction_reader.position())?;
                 self.ctx.push_data_stack_frame(&args);
                 self.instruction_reader.set_position(target);
             }
@@ -226,7 +261,7 @@ impl Scripter {
                 trace!(?pc, ?values, "push");
 
                 for value in values {
-                    self.ctx.push_code_stack(value)
+                    self.ctx.push_code_stack(value)?;
                 }
             }
             Instruction::pop { dest } => {
@@ -248,11 +283,12 @@ impl Scripter {
             Instruction::Command(command) => {
                 let command = command.into_runtime_form(&self.ctx);
                 trace!(?pc, ?command, "command");
-                return Some(command);
+                self.ctx.trace_command(pc, &command);
+                return Ok(Some(command));
             }
         }
 
-        None
+        Ok(None)
     }
 
     /// Get the current position of the VM
@@ -263,38 +299,360 @@ impl Scripter {
         self.position
     }
 
+    /// Read the current value of a register, like the results the engine returned to the scenario
+    pub fn read_register(&self, register: Register) -> i32 {
+        self.ctx.read_register(register)
+    }
+
     /// Sets the position of the VM
     ///
     /// This might have unpredictable results because the script is not supposed to be ran from arbitrary positions
     pub fn unsafe_set_position(&mut self, address: CodeAddress) {
         self.position = address;
+        self.position_prng_state = self.ctx.get_prng_state();
         self.instruction_reader.set_position(address);
     }
 
+    /// Take a snapshot of the VM state
+    ///
+    /// The snapshot points to the instruction returned by the last [`Scripter::run`] call, so restoring it will issue the same command again.
+    /// The PRNG state is rewound to match, otherwise issuing the command again would advance it one extra time.
+    pub fn snapshot(&self) -> ScripterSnapshot {
+        let mut ctx = self.ctx.clone();
+        ctx.set_prng_state(self.position_prng_state);
+
+        ScripterSnapshot {
+            ctx,
+            position: self.position,
+        }
+    }
+
+    /// Restore the VM state from a snapshot taken by [`Scripter::snapshot`]
+    ///
+    /// The snapshot must have been taken from a scripter running the same scenario.
+    pub fn restore(&mut self, snapshot: &ScripterSnapshot) {
+        // the trace hook and the register names stay installed
+        let trace_hook = self.ctx.take_trace_hook();
+        let register_names = self.ctx.shared_register_names();
+        self.ctx = snapshot.ctx.clone();
+        self.ctx.restore_trace_hook(trace_hook);
+        self.ctx.set_shared_register_names(register_names);
+        self.unsafe_set_position(snapshot.position);
+    }
+
+    /// Continue running from the same position in another version of the scenario, keeping the VM state
+    ///
+    /// Meant for hot-reloading the scenario while developing. Like with [`Scripter::unsafe_set_position`], it only makes sense if the code was not moved around.
+    /// Pair it with [`Scripter::restore`] to issue the current command again from the new scenario.
+    pub fn set_scenario(&mut self, scenario: &Scenario) {
+        self.instruction_reader = scenario.instruction_reader(self.position);
+    }
+
     /// Run the VM until a command is encountered
     ///
     /// You should pass the result of the previous command to this function (use `CommandResult::None` if the VM is just starting)
     #[inline]
     pub fn run(&mut self, prev_command_result: CommandResult) -> Result<RuntimeCommand> {
-        match prev_command_result {
-            CommandResult::None => {}
-            CommandResult::WriteMemory(addr, value) => {
-                self.ctx.write_register(addr, value);
-            }
-        }
+        self.apply_command_result(prev_command_result);
 
         loop {
             let pc = self.instruction_reader.position();
             let instruction = self.instruction_reader.read()?;
             self.breakpoints.visit_address(pc);
-            if let Some(command) = self.run_instruction(instruction, pc) {
-                return Ok(command);
+            match self.run_instruction(instruction, pc)? {
+                None => {}
+                // syscalls are handled by the VM itself, not making it to the engine
+                Some(RuntimeCommand::SYSCALL(syscall)) => {
+                    let result = syscall.dispatch(self.syscall_handler.as_mut());
+                    self.apply_command_result(result);
+                }
+                Some(command) => return Ok(command),
+            }
+        }
+    }
+
+    #[inline]
+    fn apply_command_result(&mut self, result: CommandResult) {
+        match result {
+            CommandResult::None => {}
+            CommandResult::WriteMemory(addr, value) => {
+                self.ctx.write_register(addr, value);
             }
         }
     }
 
+    /// Set the handler for the `SYSCALL` commands, replacing the [`DefaultSyscallHandler`]
+    pub fn set_syscall_handler(&mut self, handler: impl SyscallHandler + 'static) {
+        self.syscall_handler = Box::new(handler);
+    }
+
+    /// Set the maximum depth of the call stack, after which [`Scripter::run`] fails with [`VmError::CallStackOverflow`]
+    ///
+    /// Defaults to [`DEFAULT_MAX_CALL_STACK_DEPTH`].
+    pub fn set_max_call_stack_depth(&mut self, depth: usize) {
+        self.ctx.set_max_call_stack_depth(depth);
+    }
+
+    /// Install a hook called with the address of every command right before it's returned from [`Scripter::run`], see [`VmCtx::set_trace_hook`]
+    pub fn set_trace_hook(
+        &mut self,
+        hook: impl FnMut(CodeAddress, &RuntimeCommand) + Send + 'static,
+    ) {
+        self.ctx.set_trace_hook(hook);
+    }
+
+    pub fn clear_trace_hook(&mut self) {
+        self.ctx.clear_trace_hook();
+    }
+
+    /// Set the names the registers are formatted with in the traces, see [`RegisterNames`]
+    pub fn set_register_names(&mut self, names: RegisterNames) {
+        self.ctx.set_register_names(names);
+    }
+
     /// Install a breakpoint at the given code address
     pub fn add_breakpoint(&mut self, address: CodeAddress) -> BreakpointHandle {
         self.breakpoints.add_breakpoint(address)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::{
+        format::scenario::{Scenario, instruction_elements::CodeAddress},
+        vm::{
+            DEFAULT_MAX_CALL_STACK_DEPTH, Scripter, VmCtx, VmError,
+            command::{CommandResult, RuntimeCommand},
+            syscall::{SyscallHandler, syscall_result_register},
+        },
+    };
+
+    const MIN_SCENARIO: &[u8] = b"SNR \xd8\x00\x00\x00\x00\x00\x00\x00\x06\x00\x00\x00\x13\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\xbc\x00\x00\x00X\x00\x00\x00`\x00\x00\x00h\x00\x00\x00p\x00\x00\x00x\x00\x00\x00\x80\x00\x00\x00\x88\x00\x00\x00\x90\x00\x00\x00\x94\x00\x00\x00\x98\x00\x00\x00\x9c\x00\x00\x00\xa4\x00\x00\x00\xa8\x00\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00F\x02\xb0\x00\xc4\x00\x00\x00\xff\r\x00Hello world!\x00\x00\x00\x00\x00";
+
+    #[test]
+    fn snapshot_restore() {
+        let scenario = Scenario::new(bytes::Bytes::from_static(MIN_SCENARIO)).unwrap();
+        let mut scripter = Scripter::new(&scenario, 0, 42);
+
+        let command = scripter.run(CommandResult::None).unwrap();
+        let snapshot = scripter.snapshot();
+
+        // restore into a fresh scripter, as if loading in a new session
+        let mut restored_scripter = Scripter::new(&scenario, 0, 42);
+        restored_scripter.restore(&snapshot);
+        assert_eq!(restored_scripter.position(), snapshot.position());
+
+        // the command the snapshot was taken at is issued again
+        let restored_command = restored_scripter.run(CommandResult::None).unwrap();
+        assert_eq!(format!("{:?}", restored_command), format!("{:?}", command));
+        assert_eq!(restored_scripter.position(), scripter.position());
+    }
+
+    #[test]
+    fn changed_scenario() {
+        let scenario = Scenario::new(bytes::Bytes::from_static(MIN_SCENARIO)).unwrap();
+        let mut scripter = Scripter::new(&scenario, 0, 42);
+        let command = scripter.run(CommandResult::None).unwrap();
+        assert!(format!("{:?}", command).contains("Hello world!"));
+        let snapshot = scripter.snapshot();
+
+        // the message edited in place, keeping its length
+        let mut data = MIN_SCENARIO.to_vec();
+        let text_start = data.windows(5).position(|w| w == b"Hello").unwrap();
+        data[text_start..][..5].copy_from_slice(b"Hallo");
+        let changed = Scenario::new(bytes::Bytes::from(data)).unwrap();
+
+        scripter.set_scenario(&changed);
+        scripter.restore(&snapshot);
+        let command = scripter.run(CommandResult::None).unwrap();
+        assert!(format!("{:?}", command).contains("Hallo world!"));
+    }
+
+    /// [`MIN_SCENARIO`] with the start of the code overwritten by the bytes returned by `code`, which is passed the code offset
+    fn scenario_with_code(code: impl FnOnce(u32) -> Vec<u8>) -> Scenario {
+        let mut data = MIN_SCENARIO.to_vec();
+        let code_offset = u32::from_le_bytes(data[0x20..0x24].try_into().unwrap());
+        let code = code(code_offset);
+        data[code_offset as usize..][..code.len()].copy_from_slice(&code);
+
+        Scenario::new(bytes::Bytes::from(data)).unwrap()
+    }
+
+    /// [`MIN_SCENARIO`] with the code replaced by a subroutine endlessly calling itself
+    fn self_calling_scenario() -> Scenario {
+        scenario_with_code(|code_offset| {
+            // gosub code_offset
+            let mut code = vec![0x48];
+            code.extend_from_slice(&code_offset.to_le_bytes());
+            code
+        })
+    }
+
+    fn run_until_overflow(scripter: &mut Scripter) -> VmError {
+        let error = scripter
+            .run(CommandResult::None)
+            .expect_err("the scenario should never issue a command");
+        error.downcast::<VmError>().unwrap()
+    }
+
+    #[test]
+    fn call_stack_overflow() {
+        let scenario = self_calling_scenario();
+
+        let mut scripter = Scripter::new(&scenario, 0, 42);
+        assert_eq!(
+            run_until_overflow(&mut scripter),
+            VmError::CallStackOverflow {
+                depth: DEFAULT_MAX_CALL_STACK_DEPTH
+            }
+        );
+
+        let mut scripter = Scripter::new(&scenario, 0, 42);
+        scripter.set_max_call_stack_depth(16);
+        assert_eq!(
+            run_until_overflow(&mut scripter),
+            VmError::CallStackOverflow { depth: 16 }
+        );
+    }
+
+    #[test]
+    fn deep_call_stack_within_limit() {
+        let mut ctx = VmCtx::new(0, 42);
+        ctx.set_max_call_stack_depth(100);
+
+        for depth in 0..100 {
+            ctx.push_code_stack(CodeAddress(depth)).unwrap();
+        }
+        assert_eq!(
+            ctx.push_code_stack(CodeAddress(100)),
+            Err(VmError::CallStackOverflow { depth: 100 })
+        );
+
+        // returning frees up the space again
+        assert_eq!(ctx.pop_code_stack(), CodeAddress(99));
+        ctx.push_code_stack(CodeAddress(99)).unwrap();
+    }
+
+    #[test]
+    fn trace_hook() {
+        let scenario = Scenario::new(bytes::Bytes::from_static(MIN_SCENARIO)).unwrap();
+        let mut scripter = Scripter::new(&scenario, 0, 42);
+
+        let trace = Arc::new(Mutex::new(Vec::new()));
+        scripter.set_trace_hook({
+            let trace = trace.clone();
+            move |address, command| {
+                trace
+                    .lock()
+                    .unwrap()
+                    .push((address, format!("{:?}", command)))
+            }
+        });
+
+        // run the scenario to the end, recording the commands as the engine sees them
+        let mut issued = Vec::new();
+        let mut prev_command_result = CommandResult::None;
+        loop {
+            let command = scripter.run(prev_command_result).unwrap();
+            issued.push((scripter.position(), format!("{:?}", command)));
+            match command.execute_dummy() {
+                Some(result) => prev_command_result = result,
+                None => break,
+            }
+        }
+
+        // DEBUGOUT & EXIT
+        assert_eq!(issued.len(), 2);
+        assert_eq!(*trace.lock().unwrap(), issued);
+
+        // the hook survives restoring a snapshot, and is gone after clearing it
+        let mut scripter = Scripter::new(&scenario, 0, 42);
+        let snapshot = scripter.snapshot();
+        let trace = Arc::new(Mutex::new(0));
+        scripter.set_trace_hook({
+            let trace = trace.clone();
+            move |_, _| *trace.lock().unwrap() += 1
+        });
+        scripter.restore(&snapshot);
+        scripter.run(CommandResult::None).unwrap();
+        assert_eq!(*trace.lock().unwrap(), 1);
+
+        scripter.clear_trace_hook();
+        scripter.restore(&snapshot);
+        scripter.run(CommandResult::None).unwrap();
+        assert_eq!(*trace.lock().unwrap(), 1);
+    }
+
+    #[test]
+    fn random_sequence_replays_after_restore() {
+        // loop: rnd $v0, 0, 1000; DEBUGOUT "", $v0; j loop
+        let scenario = scenario_with_code(|code_offset| {
+            let mut code = vec![0x4c, 0x00, 0x00, 0x00, 0x83, 0xe8];
+            code.extend_from_slice(&[0xff, 0x01, 0x00, 0x00, 0x01, 0xb0]);
+            code.push(0x47);
+            code.extend_from_slice(&code_offset.to_le_bytes());
+            code
+        });
+        let draw = |scripter: &mut Scripter, count: usize| {
+            (0..count)
+                .map(|_| {
+                    let command = scripter.run(CommandResult::None).unwrap();
+                    assert!(matches!(command, RuntimeCommand::DEBUGOUT(_)));
+                    format!("{:?}", command)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let mut scripter = Scripter::new(&scenario, 0, 0x1234);
+        let before = draw(&mut scripter, 4);
+        let snapshot = scripter.snapshot();
+        let continued = draw(&mut scripter, 8);
+        assert!(continued.windows(2).any(|pair| pair[0] != pair[1]));
+
+        // the snapshot points at the last DEBUGOUT, so it's issued again after restoring, followed by the same values
+        scripter.restore(&snapshot);
+        assert_eq!(scripter.snapshot().prng_state(), snapshot.prng_state());
+        let replayed = draw(&mut scripter, 9);
+        assert_eq!(replayed[0], before[3]);
+        assert_eq!(replayed[1..], continued);
+
+        // a different seed gives a different sequence
+        let mut other_scripter = Scripter::new(&scenario, 0, 0x4321);
+        assert_ne!(draw(&mut other_scripter, 4), before);
+    }
+
+    /// Returns `argument + 100` for the syscall 5
+    struct TestSyscallHandler;
+
+    impl SyscallHandler for TestSyscallHandler {
+        fn dispatch(&mut self, call_id: i32, argument: i32) -> Option<i32> {
+            (call_id == 5).then_some(argument + 100)
+        }
+    }
+
+    #[test]
+    fn syscall_result() {
+        let run_syscall = |call_id: u8, handler: Option<TestSyscallHandler>| {
+            // SYSCALL call_id, 7; EXIT 0, 0
+            let scenario = scenario_with_code(|_| vec![0xa6, call_id, 0x07, 0x00, 0x00, 0x00]);
+            let mut scripter = Scripter::new(&scenario, 0, 42);
+            scripter.ctx.write_register(syscall_result_register(), -1);
+            if let Some(handler) = handler {
+                scripter.set_syscall_handler(handler);
+            }
+
+            // the syscall doesn't make it to the engine
+            let command = scripter.run(CommandResult::None).unwrap();
+            assert!(matches!(command, RuntimeCommand::EXIT(_)), "{:?}", command);
+
+            scripter.ctx.read_register(syscall_result_register())
+        };
+
+        assert_eq!(run_syscall(5, Some(TestSyscallHandler)), 107);
+        // the syscalls without a result (and the unknown ones) leave the register alone
+        assert_eq!(run_syscall(6, Some(TestSyscallHandler)), -1);
+        assert_eq!(run_syscall(5, None), -1);
+    }
+}
@@ -0,0 +1,44 @@
+//! Engine-specific services requested by the scenario with the [SYSCALL](super::command::runtime::SYSCALL) command
+
+use tracing::warn;
+
+use crate::{
+    format::scenario::instruction_elements::Register,
+    vm::command::{CommandResult, runtime::SYSCALL},
+};
+
+/// Index of the regular register the syscall results are written to
+///
+/// The `SYSCALL` command has no destination operand, so the results of the syscalls returning a value go to a fixed register.
+/// This is a convention of this engine: the last regular register is used, hoping that the scenarios don't use it for anything else.
+pub const SYSCALL_RESULT_REGISTER_INDEX: u16 = 0xfff;
+
+pub fn syscall_result_register() -> Register {
+    Register::from_regular_register(SYSCALL_RESULT_REGISTER_INDEX)
+}
+
+/// Handles the syscalls issued by the scenario, see [`Scripter::set_syscall_handler`](super::Scripter::set_syscall_handler)
+pub trait SyscallHandler: Send {
+    /// Returns the value to be written to the [result register](syscall_result_register), if the syscall has one
+    fn dispatch(&mut self, call_id: i32, argument: i32) -> Option<i32>;
+}
+
+/// The handler used by default, ignoring all the syscalls
+#[derive(Debug, Default)]
+pub struct DefaultSyscallHandler;
+
+impl SyscallHandler for DefaultSyscallHandler {
+    fn dispatch(&mut self, call_id: i32, argument: i32) -> Option<i32> {
+        warn!(call_id, argument, "Unknown syscall");
+        None
+    }
+}
+
+impl SYSCALL {
+    pub fn dispatch(self, handler: &mut dyn SyscallHandler) -> CommandResult {
+        match handler.dispatch(self.call_id, self.argument) {
+            Some(value) => CommandResult::WriteMemory(syscall_result_register(), value),
+            None => self.token.finish(),
+        }
+    }
+}
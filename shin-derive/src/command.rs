@@ -5,7 +5,10 @@ use quote::quote;
 use synstructure::{Structure, VariantInfo};
 
 use crate::{
-    sanitization::{BIN_READ, BIN_WRITE, COMMAND_RESULT, INTO_RUNTIME_FORM, REGISTER, VM_CTX},
+    sanitization::{
+        BIN_READ, BIN_WRITE, COMMAND_PARAM, COMMAND_RESULT, COMMAND_SPEC, INTO_RUNTIME_FORM,
+        PARAM_SPEC, PARAM_VALUE, REGISTER, VM_CTX,
+    },
     util::{parse_attribute, parse_opt_attribute},
 };
 
@@ -206,6 +209,30 @@ fn codegen_command_compiletime_type(input: &CommandVariant) -> TokenStream {
         .map(|a| quote!(#a))
         .unwrap_or_else(|| quote!());
 
+    let name_str = name.to_string();
+    let param_specs = input.fields.iter().map(|f| {
+        let ident = f.field.ident.as_ref().unwrap().to_string();
+        let ty = &f.field.ty;
+        // the tokens are printed with spaces around the generics, which is not how the types are usually written
+        let ty_str = quote!(#ty).to_string().replace(' ', "");
+        let dest = f.meta.dest;
+        quote! {
+            #PARAM_SPEC {
+                name: #ident,
+                ty: #ty_str,
+                kind: <#ty as #COMMAND_PARAM>::KIND,
+                dest: #dest,
+            }
+        }
+    });
+
+    let param_values = input.fields.iter().map(|f| {
+        let ident = f.field.ident.as_ref().unwrap();
+        quote! {
+            &self.#ident as &dyn #PARAM_VALUE
+        }
+    });
+
     quote! {
         #[derive(#BIN_READ, #BIN_WRITE, PartialEq, Eq, Clone, Debug)]
         #doc
@@ -213,6 +240,19 @@ fn codegen_command_compiletime_type(input: &CommandVariant) -> TokenStream {
         pub struct #name {
             #(#fields),*
         }
+
+        impl #name {
+            pub const SPEC: #COMMAND_SPEC = #COMMAND_SPEC {
+                name: #name_str,
+                opcode: #magic,
+                params: &[#(#param_specs),*],
+            };
+
+            /// Values of the parameters, in the same order as in the [`Self::SPEC`]
+            pub fn param_values(&self) -> Vec<&dyn #PARAM_VALUE> {
+                vec![#(#param_values),*]
+            }
+        }
     }
 }
 
@@ -252,6 +292,36 @@ fn codegen_command_token_type(input: &CommandVariant) -> TokenStream {
     }
 }
 
+/// Check that no two commands share an opcode, as they would be impossible to tell apart when decoding
+fn check_unique_opcodes(variants: &[CommandVariant]) -> syn::Result<()> {
+    let errors = variants
+        .iter()
+        .into_group_map_by(|v| v.meta.opcode)
+        .into_iter()
+        .filter(|(_, group)| group.len() > 1)
+        .sorted_by_key(|&(opcode, _)| opcode)
+        .flat_map(|(opcode, group)| {
+            let names = group.iter().map(|v| v.name.to_string()).join(", ");
+            // point at every command but the first one, which is the one that was "there first"
+            group[1..]
+                .iter()
+                .map(|v| {
+                    syn::Error::new(
+                        v.name.span(),
+                        format!("Duplicate opcode 0x{:02x}, used by {}", opcode, names),
+                    )
+                })
+                .collect::<Vec<_>>()
+        });
+
+    errors
+        .reduce(|mut acc, e| {
+            acc.combine(e);
+            acc
+        })
+        .map_or(Ok(()), Err)
+}
+
 pub fn impl_command(input: Structure) -> TokenStream {
     let variants = input
         .variants()
@@ -259,6 +329,10 @@ pub fn impl_command(input: Structure) -> TokenStream {
         .map(parse_command_variant)
         .collect::<Vec<_>>();
 
+    if let Err(e) = check_unique_opcodes(&variants) {
+        return e.to_compile_error();
+    }
+
     let runtime_types: TokenStream = variants.iter().map(codegen_command_runtime_type).collect();
 
     let compiletime_types: TokenStream = variants
@@ -276,8 +350,11 @@ pub fn impl_command(input: Structure) -> TokenStream {
         })
         .collect();
 
+    let opcodes: Vec<u8> = variants.iter().map(|v| v.meta.opcode).collect();
+
     // this is for some reason necessary... Otherwise a strange error in the quote! machinery pops out
     let into_runtime_form = &INTO_RUNTIME_FORM;
+    let param_value = &PARAM_VALUE;
 
     quote! {
         /// This module contains compile-time representation of commands.
@@ -317,6 +394,40 @@ pub fn impl_command(input: Structure) -> TokenStream {
             #(#variant_names(runtime::#variant_names)),*
         }
 
+        impl CompiletimeCommand {
+            /// Specs of all the commands, in the order of their definition
+            pub const SPECS: &'static [#COMMAND_SPEC] = &[#(compiletime::#variant_names::SPEC),*];
+
+            pub fn spec(&self) -> &'static #COMMAND_SPEC {
+                match self {
+                    #(CompiletimeCommand::#variant_names(_) => &compiletime::#variant_names::SPEC),*
+                }
+            }
+
+            /// Values of the parameters, in the same order as in the [`Self::spec`]
+            pub fn param_values(&self) -> Vec<&dyn #param_value> {
+                match self {
+                    #(CompiletimeCommand::#variant_names(v) => v.param_values()),*
+                }
+            }
+
+            /// Find the spec of the command encoded with the given opcode
+            pub fn spec_by_opcode(opcode: u8) -> Option<&'static #COMMAND_SPEC> {
+                match opcode {
+                    #(#opcodes => Some(&compiletime::#variant_names::SPEC),)*
+                    _ => None,
+                }
+            }
+        }
+
+        impl RuntimeCommand {
+            pub fn spec(&self) -> &'static #COMMAND_SPEC {
+                match self {
+                    #(RuntimeCommand::#variant_names(_) => &compiletime::#variant_names::SPEC),*
+                }
+            }
+        }
+
         impl #into_runtime_form for CompiletimeCommand {
             type Output = RuntimeCommand;
 
@@ -337,3 +448,31 @@ pub fn impl_command(input: Structure) -> TokenStream {
         }
     }
 }
+
+#[cfg(test)]
+#[test]
+fn test_duplicate_opcodes() {
+    use syn::parse_quote;
+
+    let input: syn::DeriveInput = parse_quote! {
+        enum Command {
+            #[cmd(opcode = 0x00u8)]
+            EXIT {},
+            #[cmd(opcode = 0x81u8)]
+            SGET {},
+            #[cmd(opcode = 0x81u8)]
+            SSET {},
+            #[cmd(opcode = 0x81u8)]
+            WAIT {},
+        }
+    };
+    let output = impl_command(Structure::try_new(&input).unwrap()).to_string();
+
+    assert!(output.contains("compile_error"));
+    assert_eq!(
+        output
+            .matches("Duplicate opcode 0x81, used by SGET, SSET, WAIT")
+            .count(),
+        2
+    );
+}
@@ -87,6 +87,10 @@ ident_str! {
     pub INTO_RUNTIME_FORM = from_shin_core!(vm::IntoRuntimeForm);
     pub REGISTER = from_shin_core!(format::scenario::instruction_elements::Register);
     pub COMMAND_RESULT = from_shin_core!(vm::command::CommandResult);
+    pub COMMAND_SPEC = from_shin_core!(vm::command::spec::CommandSpec);
+    pub PARAM_SPEC = from_shin_core!(vm::command::spec::ParamSpec);
+    pub COMMAND_PARAM = from_shin_core!(vm::command::spec::CommandParam);
+    pub PARAM_VALUE = from_shin_core!(vm::command::spec::ParamValue);
     pub RATIONAL = from_shin_core!(rational::Rational);
 
     pub TEXTURE_ARCHIVE = from_shin!(asset::texture_archive::TextureArchive);
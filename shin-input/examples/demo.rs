@@ -1,9 +1,9 @@
 use std::{collections::VecDeque, time::Duration};
 
-use enum_map::{Enum, EnumMap, enum_map};
+use enum_map::{Enum, EnumMap};
 use glam::{Mat4, Vec3};
 use shin_input::{
-    Action, ActionState, RawInputState,
+    Action, ActionState, Binding, Bindings,
     inputs::{GamepadButton, VirtualGamepadButton},
 };
 use shin_primitives::color::UnormColor;
@@ -29,23 +29,67 @@ enum HelloAction {
 }
 
 impl Action for HelloAction {
-    fn lower(
-        RawInputState {
-            mouse: _,
-            keyboard,
-            gamepads,
-        }: &RawInputState,
-    ) -> EnumMap<Self, bool> {
-        enum_map! {
-            HelloAction::Ok => keyboard.contains(&KeyCode::Enter) || keyboard.contains(&KeyCode::Space) || gamepads.is_held(GamepadButton::A),
-            HelloAction::Back => keyboard.contains(&KeyCode::KeyQ) || keyboard.contains(&KeyCode::Escape) || gamepads.is_held(GamepadButton::B),
-            HelloAction::Up => keyboard.contains(&KeyCode::ArrowUp) || gamepads.is_held(GamepadButton::Up) || gamepads.is_vheld(VirtualGamepadButton::StickLUp) || gamepads.is_vheld(VirtualGamepadButton::StickRUp),
-            HelloAction::Down => keyboard.contains(&KeyCode::ArrowDown) || gamepads.is_held(GamepadButton::Down) || gamepads.is_vheld(VirtualGamepadButton::StickLDown) || gamepads.is_vheld(VirtualGamepadButton::StickRDown),
-            HelloAction::Left => keyboard.contains(&KeyCode::ArrowLeft) || gamepads.is_held(GamepadButton::Left) || gamepads.is_vheld(VirtualGamepadButton::StickLLeft) || gamepads.is_vheld(VirtualGamepadButton::StickRLeft),
-            HelloAction::Right => keyboard.contains(&KeyCode::ArrowRight) || gamepads.is_held(GamepadButton::Right) || gamepads.is_vheld(VirtualGamepadButton::StickLRight) || gamepads.is_vheld(VirtualGamepadButton::StickRRight),
-            HelloAction::ToggleFullscreen => keyboard.contains(&KeyCode::F11),
-            HelloAction::SwitchScene => keyboard.contains(&KeyCode::Tab) || gamepads.is_held(GamepadButton::X),
-        }
+    fn default_bindings() -> Bindings<Self> {
+        use Binding::{Gamepad, Key, VirtualGamepad};
+
+        Bindings::new()
+            .with(
+                HelloAction::Ok,
+                [
+                    Key(KeyCode::Enter),
+                    Key(KeyCode::Space),
+                    Gamepad(GamepadButton::A),
+                ],
+            )
+            .with(
+                HelloAction::Back,
+                [
+                    Key(KeyCode::KeyQ),
+                    Key(KeyCode::Escape),
+                    Gamepad(GamepadButton::B),
+                ],
+            )
+            .with(
+                HelloAction::Up,
+                [
+                    Key(KeyCode::ArrowUp),
+                    Gamepad(GamepadButton::Up),
+                    VirtualGamepad(VirtualGamepadButton::StickLUp),
+                    VirtualGamepad(VirtualGamepadButton::StickRUp),
+                ],
+            )
+            .with(
+                HelloAction::Down,
+                [
+                    Key(KeyCode::ArrowDown),
+                    Gamepad(GamepadButton::Down),
+                    VirtualGamepad(VirtualGamepadButton::StickLDown),
+                    VirtualGamepad(VirtualGamepadButton::StickRDown),
+                ],
+            )
+            .with(
+                HelloAction::Left,
+                [
+                    Key(KeyCode::ArrowLeft),
+                    Gamepad(GamepadButton::Left),
+                    VirtualGamepad(VirtualGamepadButton::StickLLeft),
+                    VirtualGamepad(VirtualGamepadButton::StickRLeft),
+                ],
+            )
+            .with(
+                HelloAction::Right,
+                [
+                    Key(KeyCode::ArrowRight),
+                    Gamepad(GamepadButton::Right),
+                    VirtualGamepad(VirtualGamepadButton::StickLRight),
+                    VirtualGamepad(VirtualGamepadButton::StickRRight),
+                ],
+            )
+            .with(HelloAction::ToggleFullscreen, [Key(KeyCode::F11)])
+            .with(
+                HelloAction::SwitchScene,
+                [Key(KeyCode::Tab), Gamepad(GamepadButton::X)],
+            )
     }
 }
 
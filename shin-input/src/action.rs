@@ -3,7 +3,7 @@ use std::time::Duration;
 use enum_map::{enum_map, Enum, EnumMap};
 use tracing::warn;
 
-use crate::RawInputState;
+use crate::{Bindings, RawInputState};
 
 #[derive(Default, Copy, Clone)]
 struct ActionData60Fps {
@@ -19,11 +19,44 @@ enum ActionDataDynamic {
         duration: Duration,
     },
     Repeating {
+        held_for: Duration,
         duration_since_rapid_repeat: Duration,
         rapid_repeats: u32,
     },
 }
 
+/// Timings for synthesizing repeats of a held action
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RepeatTiming {
+    /// How long the action has to be held before it starts repeating
+    pub delay: Duration,
+    /// Time between the repeats
+    pub interval: Duration,
+    /// Number of repeats after which the rapid repeats start, firing twice as often
+    pub rapid_after_repeats: u32,
+}
+
+impl RepeatTiming {
+    // 24 ticks @ 60 tps
+    const DEFAULT_DELAY: Duration = Duration::from_nanos(24 * 1000000000 / 60);
+    const DEFAULT_INTERVAL: Duration = Duration::from_nanos(4 * 1000000000 / 60);
+
+    fn rapid_repeat_period(&self) -> Duration {
+        self.interval / 2
+    }
+}
+
+impl Default for RepeatTiming {
+    /// The timings used by the game
+    fn default() -> Self {
+        Self {
+            delay: Self::DEFAULT_DELAY,
+            interval: Self::DEFAULT_INTERVAL,
+            rapid_after_repeats: 10,
+        }
+    }
+}
+
 pub enum ActionSignal {
     Held,
     Clicked,
@@ -37,8 +70,16 @@ pub struct ActionState {
     pub is_held: bool,
     /// The button was started being held down this frame
     pub is_clicked: bool,
+    /// The button is being held down long enough to fire a repeat this frame (see [`RepeatTiming`])
+    pub is_repeat: bool,
     pub is_clicked_or_repeated: bool,
     pub is_clicked_or_rapid_repeated: bool,
+    /// For how long the button has been held down, zero on the frame it was clicked
+    pub held_for: Duration,
+    /// How far the action is actuated, from 0 to 1
+    ///
+    /// Digital inputs are either 0 or 1, only the analog axes (see [`Binding::Axis`](crate::Binding::Axis)) have values in between
+    pub value: f32,
 }
 
 impl ActionState {
@@ -46,40 +87,55 @@ impl ActionState {
         Self {
             is_held: false,
             is_clicked: false,
+            is_repeat: false,
             is_clicked_or_repeated: false,
             is_clicked_or_rapid_repeated: false,
+            held_for: Duration::ZERO,
+            value: 0.0,
         }
     }
     fn clicked() -> Self {
         Self {
             is_held: true,
             is_clicked: true,
+            is_repeat: false,
             is_clicked_or_repeated: true,
             is_clicked_or_rapid_repeated: true,
+            held_for: Duration::ZERO,
+            value: 1.0,
         }
     }
-    fn held() -> Self {
+    fn held(held_for: Duration) -> Self {
         Self {
             is_held: true,
             is_clicked: false,
+            is_repeat: false,
             is_clicked_or_repeated: false,
             is_clicked_or_rapid_repeated: false,
+            held_for,
+            value: 1.0,
         }
     }
-    fn repeated() -> Self {
+    fn repeated(held_for: Duration) -> Self {
         Self {
             is_held: true,
             is_clicked: false,
+            is_repeat: true,
             is_clicked_or_repeated: true,
             is_clicked_or_rapid_repeated: true,
+            held_for,
+            value: 1.0,
         }
     }
-    fn rapid_repeated() -> Self {
+    fn rapid_repeated(held_for: Duration) -> Self {
         Self {
             is_held: true,
             is_clicked: false,
+            is_repeat: false,
             is_clicked_or_repeated: false,
             is_clicked_or_rapid_repeated: true,
+            held_for,
+            value: 1.0,
         }
     }
 }
@@ -122,76 +178,90 @@ impl ActionData60Fps {
         ActionState {
             is_held,
             is_clicked,
+            is_repeat: is_clicked_or_repeated && !is_clicked,
             is_clicked_or_repeated,
             is_clicked_or_rapid_repeated,
+            held_for: Duration::ZERO,
+            value: if is_held { 1.0 } else { 0.0 },
         }
     }
 }
 
 impl ActionDataDynamic {
-    // 24 ticks @ 60 tps
-    const CLICKED_TO_REPEATING: Duration = Duration::from_nanos(24 * 1000000000 / 60);
-    const RAPID_REPEAT_PERIOD: Duration = Duration::from_nanos(2 * 1000000000 / 60);
-    const RAPID_REPEAT_START: u32 = 20;
-
     // TODO: duration's precision is probably excessive
-    pub fn update(&mut self, current_state: bool, elapsed: Duration) -> ActionState {
-        if !current_state {
+    /// `was_pressed` is set when the button got a press event since the last update, even if it was released again
+    pub fn update(
+        &mut self,
+        current_state: bool,
+        was_pressed: bool,
+        elapsed: Duration,
+        timing: &RepeatTiming,
+    ) -> ActionState {
+        if was_pressed {
+            // the button was released and pressed again between the updates, it should register as a fresh click
+            // (it also catches the presses that got released before the update and would be lost otherwise)
             *self = ActionDataDynamic::Released;
-            ActionState::released()
-        } else {
-            match self {
-                ActionDataDynamic::Released => {
-                    *self = ActionDataDynamic::Clicked {
-                        duration: Duration::from_secs(0),
+        } else if !current_state {
+            *self = ActionDataDynamic::Released;
+            return ActionState::released();
+        }
+
+        match self {
+            ActionDataDynamic::Released => {
+                *self = ActionDataDynamic::Clicked {
+                    duration: Duration::from_secs(0),
+                };
+                ActionState::clicked()
+            }
+            ActionDataDynamic::Clicked { duration } => {
+                *duration += elapsed;
+
+                if *duration >= timing.delay {
+                    let held_for = *duration;
+                    *self = ActionDataDynamic::Repeating {
+                        held_for,
+                        duration_since_rapid_repeat: Duration::from_secs(0),
+                        rapid_repeats: 0,
                     };
-                    ActionState::clicked()
-                }
-                ActionDataDynamic::Clicked { duration } => {
-                    *duration += elapsed;
 
-                    if *duration >= Self::CLICKED_TO_REPEATING {
-                        *self = ActionDataDynamic::Repeating {
-                            duration_since_rapid_repeat: Duration::from_secs(0),
-                            rapid_repeats: 0,
-                        };
+                    ActionState::repeated(held_for)
+                } else {
+                    ActionState::held(*duration)
+                }
+            }
+            ActionDataDynamic::Repeating {
+                held_for,
+                duration_since_rapid_repeat,
+                rapid_repeats,
+            } => {
+                *held_for += elapsed;
+                *duration_since_rapid_repeat += elapsed;
 
-                        ActionState::repeated()
-                    } else {
-                        ActionState::held()
+                let rapid_repeat_period = timing.rapid_repeat_period();
+                if *duration_since_rapid_repeat >= rapid_repeat_period {
+                    // skip repeats if we are running too slow
+                    let mut new_repeats = 0;
+                    while *duration_since_rapid_repeat >= rapid_repeat_period {
+                        *duration_since_rapid_repeat -= rapid_repeat_period;
+                        new_repeats += 1;
                     }
-                }
-                ActionDataDynamic::Repeating {
-                    duration_since_rapid_repeat,
-                    rapid_repeats,
-                } => {
-                    *duration_since_rapid_repeat += elapsed;
-
-                    if *duration_since_rapid_repeat >= Self::RAPID_REPEAT_PERIOD {
-                        // skip repeats if we are running too slow
-                        let mut new_repeats = 0;
-                        while *duration_since_rapid_repeat >= Self::RAPID_REPEAT_PERIOD {
-                            *duration_since_rapid_repeat -= Self::RAPID_REPEAT_PERIOD;
-                            new_repeats += 1;
-                        }
-                        if new_repeats > 1 {
-                            warn!(
-                                "Running too slow, skipped {} rapid repeats",
-                                new_repeats - 1
-                            );
-                        }
-                        *rapid_repeats += 1;
-
-                        if *rapid_repeats % 2 == 0 {
-                            ActionState::repeated()
-                        } else if *rapid_repeats >= Self::RAPID_REPEAT_START {
-                            ActionState::rapid_repeated()
-                        } else {
-                            ActionState::held()
-                        }
+                    if new_repeats > 1 {
+                        warn!(
+                            "Running too slow, skipped {} rapid repeats",
+                            new_repeats - 1
+                        );
+                    }
+                    *rapid_repeats += 1;
+
+                    if *rapid_repeats % 2 == 0 {
+                        ActionState::repeated(*held_for)
+                    } else if *rapid_repeats >= timing.rapid_after_repeats * 2 {
+                        ActionState::rapid_repeated(*held_for)
                     } else {
-                        ActionState::held()
+                        ActionState::held(*held_for)
                     }
+                } else {
+                    ActionState::held(*held_for)
                 }
             }
         }
@@ -199,40 +269,248 @@ impl ActionDataDynamic {
 }
 
 pub struct ActionsState<A: Enum> {
+    bindings: Bindings<A>,
+    repeat_timing: RepeatTiming,
     actions_data: EnumMap<A, ActionDataDynamic>,
 }
 
-impl<A: Enum> ActionsState<A> {
+impl<A: Action> ActionsState<A> {
     pub fn new() -> Self {
+        Self::with_bindings(A::default_bindings())
+    }
+}
+
+impl<A: Enum> ActionsState<A> {
+    pub fn with_bindings(bindings: Bindings<A>) -> Self {
         Self {
+            bindings,
+            repeat_timing: RepeatTiming::default(),
             actions_data: enum_map! { _ => ActionDataDynamic::default() },
         }
     }
 
+    pub fn bindings(&self) -> &Bindings<A> {
+        &self.bindings
+    }
+
+    /// Change the bindings, taking effect on the next [`ActionsState::update`]
+    pub fn bindings_mut(&mut self) -> &mut Bindings<A> {
+        &mut self.bindings
+    }
+
+    pub fn repeat_timing(&self) -> &RepeatTiming {
+        &self.repeat_timing
+    }
+
+    pub fn set_repeat_timing(&mut self, repeat_timing: RepeatTiming) {
+        self.repeat_timing = repeat_timing;
+    }
+
     pub fn update(
         &mut self,
-        current_state: EnumMap<A, bool>,
+        raw_input_state: &RawInputState,
         elapsed: Duration,
     ) -> EnumMap<A, ActionState> {
+        let current_state = self.bindings.lower(raw_input_state);
+        let was_pressed = self.bindings.lower_pressed(raw_input_state);
+        let values = self.bindings.lower_values(raw_input_state);
+
         self.actions_data
             .iter_mut()
-            .zip(current_state.iter())
-            .map(|((action, action_data), (_, current_state))| {
-                (action, action_data.update(*current_state, elapsed))
-            })
+            .zip(current_state.iter().zip(was_pressed.iter()))
+            .zip(values.iter())
+            .map(
+                |(((action, action_data), ((_, current_state), (_, was_pressed))), (_, value))| {
+                    let mut state = action_data.update(
+                        *current_state,
+                        *was_pressed,
+                        elapsed,
+                        &self.repeat_timing,
+                    );
+                    state.value = *value;
+                    (action, state)
+                },
+            )
             .collect()
     }
 }
 
 pub trait Action: Enum {
-    fn lower(raw_input_state: &RawInputState) -> EnumMap<Self, bool>;
+    /// The bindings used until the user changes them
+    fn default_bindings() -> Bindings<Self>;
 }
 
 #[derive(Debug, Copy, Clone, Enum)]
 pub enum DummyAction {}
 
 impl Action for DummyAction {
-    fn lower(_raw_input_state: &RawInputState) -> EnumMap<Self, bool> {
-        enum_map! {}
+    fn default_bindings() -> Bindings<Self> {
+        Bindings::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use enum_map::Enum;
+
+    use super::{Action, ActionsState, RepeatTiming};
+    use crate::{
+        AxisDirection, Binding, Bindings, RawInputState,
+        inputs::{GamepadAxis, KeyCode},
+    };
+
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Enum)]
+    enum TestAction {
+        Advance,
+        Skip,
+        CursorRight,
+    }
+
+    impl Action for TestAction {
+        fn default_bindings() -> Bindings<Self> {
+            Bindings::new()
+                .with(TestAction::Advance, [Binding::Key(KeyCode::Space)])
+                .with(TestAction::Skip, [Binding::Key(KeyCode::ControlLeft)])
+                .with(
+                    TestAction::CursorRight,
+                    [
+                        Binding::Key(KeyCode::ArrowRight),
+                        Binding::Axis(GamepadAxis::LeftStickX, AxisDirection::Positive),
+                    ],
+                )
+        }
+    }
+
+    const FRAME: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+    fn holding(keys: &[KeyCode]) -> RawInputState {
+        let mut state = RawInputState::new();
+        for &key in keys {
+            state.keyboard.insert(key);
+        }
+        state
+    }
+
+    #[test]
+    fn rebind() {
+        let mut actions = ActionsState::<TestAction>::new();
+
+        let state = actions.update(&holding(&[KeyCode::Space]), FRAME);
+        assert!(state[TestAction::Advance].is_clicked);
+        actions.update(&holding(&[]), FRAME);
+
+        actions
+            .bindings_mut()
+            .rebind(TestAction::Advance, [Binding::Key(KeyCode::Enter)]);
+
+        let state = actions.update(&holding(&[KeyCode::Space]), FRAME);
+        assert!(!state[TestAction::Advance].is_held);
+        actions.update(&holding(&[]), FRAME);
+
+        let state = actions.update(&holding(&[KeyCode::Enter]), FRAME);
+        assert!(state[TestAction::Advance].is_clicked);
+        assert!(!state[TestAction::Skip].is_held);
+    }
+
+    #[test]
+    fn conflicting_bindings() {
+        let mut actions = ActionsState::<TestAction>::new();
+        actions
+            .bindings_mut()
+            .bind(TestAction::Skip, KeyCode::Space);
+
+        assert_eq!(
+            actions.bindings().actions_bound_to(KeyCode::Space),
+            vec![TestAction::Advance, TestAction::Skip]
+        );
+
+        let state = actions.update(&holding(&[KeyCode::Space]), FRAME);
+        assert!(state[TestAction::Advance].is_clicked);
+        assert!(state[TestAction::Skip].is_clicked);
+    }
+
+    #[test]
+    fn repeat_cadence() {
+        let mut actions = ActionsState::<TestAction>::new();
+        actions.set_repeat_timing(RepeatTiming {
+            delay: Duration::from_millis(100),
+            interval: Duration::from_millis(20),
+            rapid_after_repeats: 100,
+        });
+
+        let frame = Duration::from_millis(10);
+        let mut repeats = Vec::new();
+        for i in 0..20 {
+            let state = actions.update(&holding(&[KeyCode::Space]), frame)[TestAction::Advance];
+            assert!(state.is_held);
+            assert_eq!(state.is_clicked, i == 0);
+            assert_eq!(state.held_for, frame * i);
+            if state.is_repeat {
+                repeats.push(i);
+            }
+        }
+        assert_eq!(repeats, vec![10, 12, 14, 16, 18]);
+
+        let state = actions.update(&holding(&[]), frame)[TestAction::Advance];
+        assert!(!state.is_held);
+        assert_eq!(state.held_for, Duration::ZERO);
+    }
+
+    #[test]
+    fn repress_within_a_frame() {
+        let mut actions = ActionsState::<TestAction>::new();
+
+        for _ in 0..30 {
+            actions.update(&holding(&[KeyCode::Space]), FRAME);
+        }
+
+        // the key got released and pressed again between the updates, so it looks held in both of them
+        let mut state = holding(&[KeyCode::Space]);
+        state.keyboard_pressed.insert(KeyCode::Space);
+        let action = actions.update(&state, FRAME)[TestAction::Advance];
+        assert!(action.is_clicked);
+        assert_eq!(action.held_for, Duration::ZERO);
+
+        // pressed and released again between the updates
+        actions.update(&holding(&[]), FRAME);
+        let mut state = holding(&[]);
+        state.keyboard_pressed.insert(KeyCode::Space);
+        assert!(actions.update(&state, FRAME)[TestAction::Advance].is_clicked);
+        assert!(!actions.update(&holding(&[]), FRAME)[TestAction::Advance].is_held);
+    }
+
+    #[test]
+    fn stick_deflection() {
+        let mut actions = ActionsState::<TestAction>::new();
+        let deadzone = actions.bindings().axis_settings().deadzone;
+
+        let mut state = holding(&[]);
+        state.gamepads.unified.axes[GamepadAxis::LeftStickX] = 0.8;
+        assert_eq!(state.gamepads.axis(GamepadAxis::LeftStickX), 0.8);
+
+        let action = actions.update(&state, FRAME)[TestAction::CursorRight];
+        assert!(action.is_clicked);
+        assert_eq!(action.value, deadzone.apply(0.8));
+        assert!(action.value > 0.0 && action.value < 1.0);
+
+        // within the deadzone
+        state.gamepads.unified.axes[GamepadAxis::LeftStickX] = deadzone.inner() / 2.0;
+        let action = actions.update(&state, FRAME)[TestAction::CursorRight];
+        assert!(!action.is_held);
+        assert_eq!(action.value, 0.0);
+
+        // deflected in the other direction
+        state.gamepads.unified.axes[GamepadAxis::LeftStickX] = -1.0;
+        let action = actions.update(&state, FRAME)[TestAction::CursorRight];
+        assert!(!action.is_held);
+        assert_eq!(action.value, 0.0);
+
+        // the keyboard binding is at full value
+        let action =
+            actions.update(&holding(&[KeyCode::ArrowRight]), FRAME)[TestAction::CursorRight];
+        assert!(action.is_clicked);
+        assert_eq!(action.value, 1.0);
     }
 }
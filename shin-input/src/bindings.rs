@@ -0,0 +1,314 @@
+use anyhow::{ensure, Result};
+use enum_map::{Enum, EnumMap};
+
+use crate::{
+    RawInputState,
+    inputs::{GamepadAxis, GamepadButton, KeyCode, MouseButton, VirtualGamepadButton},
+};
+
+/// A single input that can trigger an action
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButton),
+    VirtualGamepad(VirtualGamepadButton),
+    /// Deflection of a gamepad axis in the given direction, see [`AxisSettings`]
+    Axis(GamepadAxis, AxisDirection),
+}
+
+/// Direction of an axis deflection. Positive is right for the X axes and up for the Y axes
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum AxisDirection {
+    Positive,
+    Negative,
+}
+
+/// Rescales an axis value, ignoring the small deflections of a stick at rest and saturating before the physical limit
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Deadzone {
+    inner: f32,
+    outer: f32,
+}
+
+impl Deadzone {
+    /// Deflections up to the `inner` magnitude are treated as zero, and starting from the `outer` one as full.
+    ///
+    /// Fails unless `0 <= inner < outer <= 1`, as there would be no range to rescale.
+    pub fn new(inner: f32, outer: f32) -> Result<Self> {
+        ensure!(
+            (0.0..outer).contains(&inner) && outer <= 1.0,
+            "Invalid deadzone: inner {} and outer {}",
+            inner,
+            outer
+        );
+        Ok(Self { inner, outer })
+    }
+
+    pub fn inner(&self) -> f32 {
+        self.inner
+    }
+
+    pub fn outer(&self) -> f32 {
+        self.outer
+    }
+
+    pub fn apply(&self, value: f32) -> f32 {
+        let magnitude = value.abs();
+        if magnitude <= self.inner {
+            return 0.0;
+        }
+
+        let scaled = ((magnitude - self.inner) / (self.outer - self.inner)).min(1.0);
+        scaled.copysign(value)
+    }
+}
+
+impl Default for Deadzone {
+    fn default() -> Self {
+        Self {
+            inner: 0.15,
+            outer: 0.95,
+        }
+    }
+}
+
+/// How the [`Binding::Axis`] bindings are converted to action states
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AxisSettings {
+    pub deadzone: Deadzone,
+    /// The value (after the deadzone) at which the action is considered held
+    pub threshold: f32,
+}
+
+impl Default for AxisSettings {
+    fn default() -> Self {
+        Self {
+            deadzone: Deadzone::default(),
+            threshold: 0.5,
+        }
+    }
+}
+
+impl Binding {
+    pub fn is_held(&self, raw_input_state: &RawInputState, axis_settings: &AxisSettings) -> bool {
+        match *self {
+            Binding::Key(key) => raw_input_state.keyboard.contains(&key),
+            Binding::Mouse(button) => raw_input_state.mouse.buttons[button],
+            Binding::Gamepad(button) => raw_input_state.gamepads.is_held(button),
+            Binding::VirtualGamepad(button) => raw_input_state.gamepads.is_vheld(button),
+            Binding::Axis(..) => {
+                self.value(raw_input_state, axis_settings) >= axis_settings.threshold
+            }
+        }
+    }
+
+    /// How far the input is actuated, from 0 to 1. Only the axes have values in between
+    pub fn value(&self, raw_input_state: &RawInputState, axis_settings: &AxisSettings) -> f32 {
+        match *self {
+            Binding::Axis(axis, direction) => {
+                let value = axis_settings
+                    .deadzone
+                    .apply(raw_input_state.gamepads.axis(axis));
+                match direction {
+                    AxisDirection::Positive => value.max(0.0),
+                    AxisDirection::Negative => (-value).max(0.0),
+                }
+            }
+            _ => {
+                if self.is_held(raw_input_state, axis_settings) {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+
+    /// Whether the input got pressed since the last frame, even if it was released before the end of the frame
+    pub fn was_pressed(&self, raw_input_state: &RawInputState) -> bool {
+        match *self {
+            Binding::Key(key) => raw_input_state.keyboard_pressed.contains(&key),
+            Binding::Mouse(button) => raw_input_state.mouse.pressed[button],
+            Binding::Gamepad(button) => raw_input_state.gamepads.was_pressed(button),
+            // virtual buttons and axes are derived from the stick positions sampled once per frame, so there are no presses to miss
+            Binding::VirtualGamepad(_) | Binding::Axis(..) => false,
+        }
+    }
+}
+
+impl From<KeyCode> for Binding {
+    fn from(key: KeyCode) -> Self {
+        Binding::Key(key)
+    }
+}
+
+impl From<MouseButton> for Binding {
+    fn from(button: MouseButton) -> Self {
+        Binding::Mouse(button)
+    }
+}
+
+impl From<GamepadButton> for Binding {
+    fn from(button: GamepadButton) -> Self {
+        Binding::Gamepad(button)
+    }
+}
+
+impl From<VirtualGamepadButton> for Binding {
+    fn from(button: VirtualGamepadButton) -> Self {
+        Binding::VirtualGamepad(button)
+    }
+}
+
+/// Maps each action to the inputs that trigger it, so that they can be changed at runtime (like from an options menu)
+///
+/// An action is held while any of its inputs is held.
+///
+/// The same input can be bound to several actions, in which case it triggers all of them at once.
+/// This is intentionally allowed, as some actions are meant to overlap (like confirming a choice and advancing the text with the same key).
+/// When the user rebinds an input, use [`Bindings::actions_bound_to`] to find the conflicts and warn about them.
+pub struct Bindings<A: Enum> {
+    bindings: EnumMap<A, Vec<Binding>>,
+    axis_settings: AxisSettings,
+}
+
+impl<A: Enum> Bindings<A> {
+    /// Create bindings with no inputs bound to any action
+    pub fn new() -> Self {
+        Self {
+            bindings: EnumMap::from_fn(|_| Vec::new()),
+            axis_settings: AxisSettings::default(),
+        }
+    }
+
+    /// Bind the inputs to the action, for building the default bindings
+    pub fn with(mut self, action: A, bindings: impl IntoIterator<Item = Binding>) -> Self {
+        let action_bindings = &mut self.bindings[action];
+        for binding in bindings {
+            push_unique(action_bindings, binding);
+        }
+        self
+    }
+
+    pub fn get(&self, action: A) -> &[Binding] {
+        &self.bindings[action]
+    }
+
+    /// Add an input triggering the action, keeping the existing ones
+    pub fn bind(&mut self, action: A, binding: impl Into<Binding>) {
+        push_unique(&mut self.bindings[action], binding.into());
+    }
+
+    pub fn unbind(&mut self, action: A, binding: impl Into<Binding>) {
+        let binding = binding.into();
+        self.bindings[action].retain(|&b| b != binding);
+    }
+
+    /// Replace all the inputs triggering the action
+    pub fn rebind(&mut self, action: A, bindings: impl IntoIterator<Item = Binding>) {
+        let action_bindings = &mut self.bindings[action];
+        action_bindings.clear();
+        for binding in bindings {
+            push_unique(action_bindings, binding);
+        }
+    }
+
+    pub fn axis_settings(&self) -> &AxisSettings {
+        &self.axis_settings
+    }
+
+    pub fn set_axis_settings(&mut self, axis_settings: AxisSettings) {
+        self.axis_settings = axis_settings;
+    }
+
+    /// List the actions the input is bound to
+    pub fn actions_bound_to(&self, binding: impl Into<Binding>) -> Vec<A> {
+        let binding = binding.into();
+        self.bindings
+            .iter()
+            .filter(|(_, bindings)| bindings.contains(&binding))
+            .map(|(action, _)| action)
+            .collect()
+    }
+
+    /// Get the actions held in the given input state
+    pub fn lower(&self, raw_input_state: &RawInputState) -> EnumMap<A, bool> {
+        self.bindings
+            .iter()
+            .map(|(action, bindings)| {
+                let is_held = bindings
+                    .iter()
+                    .any(|binding| binding.is_held(raw_input_state, &self.axis_settings));
+                (action, is_held)
+            })
+            .collect()
+    }
+
+    /// Get how far the actions are actuated in the given input state, taking the largest value of the bound inputs
+    pub fn lower_values(&self, raw_input_state: &RawInputState) -> EnumMap<A, f32> {
+        self.bindings
+            .iter()
+            .map(|(action, bindings)| {
+                let value = bindings
+                    .iter()
+                    .map(|binding| binding.value(raw_input_state, &self.axis_settings))
+                    .fold(0.0, f32::max);
+                (action, value)
+            })
+            .collect()
+    }
+
+    /// Get the actions that got pressed since the last frame in the given input state
+    pub fn lower_pressed(&self, raw_input_state: &RawInputState) -> EnumMap<A, bool> {
+        self.bindings
+            .iter()
+            .map(|(action, bindings)| {
+                let was_pressed = bindings
+                    .iter()
+                    .any(|binding| binding.was_pressed(raw_input_state));
+                (action, was_pressed)
+            })
+            .collect()
+    }
+}
+
+fn push_unique(bindings: &mut Vec<Binding>, binding: Binding) {
+    if !bindings.contains(&binding) {
+        bindings.push(binding);
+    }
+}
+
+impl<A: Enum> Default for Bindings<A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Deadzone;
+
+    #[test]
+    fn deadzone() {
+        let deadzone = Deadzone::new(0.2, 0.8).unwrap();
+
+        assert_eq!(deadzone.apply(0.0), 0.0);
+        assert_eq!(deadzone.apply(0.2), 0.0);
+        assert_eq!(deadzone.apply(-0.1), 0.0);
+        assert!((deadzone.apply(0.5) - 0.5).abs() < 1e-6);
+        assert!((deadzone.apply(-0.5) + 0.5).abs() < 1e-6);
+        assert_eq!(deadzone.apply(0.9), 1.0);
+        assert_eq!(deadzone.apply(-1.0), -1.0);
+    }
+
+    #[test]
+    fn invalid_deadzone() {
+        assert!(Deadzone::new(0.5, 0.5).is_err());
+        assert!(Deadzone::new(0.8, 0.2).is_err());
+        assert!(Deadzone::new(-0.1, 0.8).is_err());
+        assert!(Deadzone::new(0.2, 1.5).is_err());
+        assert!(Deadzone::new(f32::NAN, 0.8).is_err());
+        assert!(Deadzone::new(0.0, 1.0).is_ok());
+    }
+}
@@ -26,6 +26,16 @@ impl GamepadAxis {
             _ => return None,
         })
     }
+
+    /// Most gamepads report the analog triggers as buttons with a value instead of as axes
+    pub fn from_gilrs_button(button: gilrs::Button) -> Option<Self> {
+        use gilrs::Button::*;
+        Some(match button {
+            LeftTrigger2 => GamepadAxis::LeftTrigger,
+            RightTrigger2 => GamepadAxis::RightTrigger,
+            _ => return None,
+        })
+    }
 }
 
 #[derive(Debug, Hash, Ord, PartialOrd, PartialEq, Eq, Clone, Copy, Enum)]
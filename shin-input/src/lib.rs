@@ -1,7 +1,9 @@
 pub mod inputs;
 
 mod action;
+mod bindings;
 mod raw_input_state;
 
-pub use action::{Action, ActionSignal, ActionState, ActionsState, DummyAction};
+pub use action::{Action, ActionSignal, ActionState, ActionsState, DummyAction, RepeatTiming};
+pub use bindings::{AxisDirection, AxisSettings, Binding, Bindings, Deadzone};
 pub use raw_input_state::{RawInputAccumulator, RawInputState};
@@ -15,6 +15,8 @@ use crate::inputs::{GamepadAxis, GamepadButton, MouseButton, VirtualGamepadButto
 pub struct MouseState {
     /// Mouse buttons state, simple state of each button
     pub buttons: EnumMap<MouseButton, bool>,
+    /// Mouse buttons that got pressed during this frame
+    pub pressed: EnumMap<MouseButton, bool>,
     pub position: Vec2,
     pub scroll_amount: f32,
 }
@@ -23,6 +25,7 @@ impl MouseState {
     pub fn new() -> Self {
         Self {
             buttons: enum_map! { _ => false },
+            pressed: enum_map! { _ => false },
             position: vec2(0.0, 0.0),
             scroll_amount: 0.0,
         }
@@ -32,6 +35,8 @@ impl MouseState {
 #[derive(Clone, Default)]
 pub struct UnifiedGamepadState {
     pub buttons: EnumMap<GamepadButton, bool>,
+    /// Buttons that got pressed during this frame
+    pub pressed: EnumMap<GamepadButton, bool>,
     pub axes: EnumMap<GamepadAxis, f32>,
     pub virtual_keys: EnumMap<VirtualGamepadButton, bool>,
 }
@@ -40,6 +45,7 @@ impl UnifiedGamepadState {
     pub fn new() -> Self {
         Self {
             buttons: enum_map! { _ => false },
+            pressed: enum_map! { _ => false },
             axes: enum_map! { _ => 0.0 },
             virtual_keys: enum_map! { _ => false },
         }
@@ -92,6 +98,17 @@ impl GamepadsState {
         self.unified.buttons[button]
     }
 
+    /// Get the raw value of the axis, from -1 to 1 (or from 0 to 1 for the triggers)
+    ///
+    /// No deadzone is applied, see [`Deadzone`](crate::Deadzone) for that
+    pub fn axis(&self, axis: GamepadAxis) -> f32 {
+        self.unified.axes[axis]
+    }
+
+    pub fn was_pressed(&self, button: GamepadButton) -> bool {
+        self.unified.pressed[button]
+    }
+
     pub fn is_vheld(&self, axis: VirtualGamepadButton) -> bool {
         self.unified.virtual_keys[axis]
     }
@@ -137,6 +154,10 @@ impl RawInputAccumulator {
                     match event.state {
                         ElementState::Pressed => {
                             state.keyboard.insert(keycode);
+                            // OS key repeats are not presses, our input system synthesizes its own repeats
+                            if !event.repeat {
+                                state.keyboard_pressed.insert(keycode);
+                            }
                         }
                         ElementState::Released => {
                             state.keyboard.remove(&keycode);
@@ -158,18 +179,25 @@ impl RawInputAccumulator {
                     winit::event::MouseScrollDelta::PixelDelta(p) => (p.y / 120.0) as f32, /* this value is windows-specific */
                 };
 
-                if amount > 0.0 {
-                    state.mouse.buttons[MouseButton::WheelUp] = true;
+                let button = if amount > 0.0 {
+                    MouseButton::WheelUp
                 } else {
-                    state.mouse.buttons[MouseButton::WheelDown] = true;
-                }
+                    MouseButton::WheelDown
+                };
+                state.mouse.buttons[button] = true;
+                state.mouse.pressed[button] = true;
                 state.mouse.scroll_amount = amount;
             }
             &WindowEvent::MouseInput { button, state, .. } => {
                 if let Some(button) = convert_winit_mouse_button(button) {
-                    self.state.mouse.buttons[button] = match state {
-                        ElementState::Pressed => true,
-                        ElementState::Released => false,
+                    match state {
+                        ElementState::Pressed => {
+                            self.state.mouse.buttons[button] = true;
+                            self.state.mouse.pressed[button] = true;
+                        }
+                        ElementState::Released => {
+                            self.state.mouse.buttons[button] = false;
+                        }
                     }
                 }
             }
@@ -188,6 +216,7 @@ impl RawInputAccumulator {
                     EventType::ButtonPressed(button, _) => {
                         if let Some(button) = GamepadButton::from_gilrs(button) {
                             gamepad.buttons[button] = true;
+                            gamepad.pressed[button] = true;
                         }
                     }
                     EventType::ButtonRepeated(_button, _) => {
@@ -198,8 +227,11 @@ impl RawInputAccumulator {
                             gamepad.buttons[button] = false;
                         }
                     }
-                    EventType::ButtonChanged(_button, _value, _) => {
-                        // we do not support analog buttons
+                    EventType::ButtonChanged(button, value, _) => {
+                        // the other analog buttons are handled as digital ones
+                        if let Some(axis) = GamepadAxis::from_gilrs_button(button) {
+                            gamepad.axes[axis] = value;
+                        }
                     }
                     EventType::AxisChanged(axis, value, _) => {
                         if let Some(axis) = GamepadAxis::from_gilrs(axis) {
@@ -233,7 +265,18 @@ impl RawInputAccumulator {
                             buttons: acc
                                 .buttons
                                 .map(|button, value| gamepad.buttons[button] | value),
-                            axes: acc.axes.map(|axis, value| gamepad.axes[axis].max(value)),
+                            pressed: acc
+                                .pressed
+                                .map(|button, value| gamepad.pressed[button] | value),
+                            // take the largest deflection, whichever direction it is in
+                            axes: acc.axes.map(|axis, value| {
+                                let other = gamepad.axes[axis];
+                                if other.abs() > value.abs() {
+                                    other
+                                } else {
+                                    value
+                                }
+                            }),
                             virtual_keys: acc
                                 .virtual_keys
                                 .map(|key, value| gamepad.virtual_keys[key] | value),
@@ -249,6 +292,13 @@ impl RawInputAccumulator {
         self.state.mouse.scroll_amount = 0.0;
         self.state.mouse.buttons[MouseButton::WheelUp] = false;
         self.state.mouse.buttons[MouseButton::WheelDown] = false;
+
+        self.state.keyboard_pressed.clear();
+        self.state.mouse.pressed = enum_map! { _ => false };
+        for (_, gamepad) in &mut self.state.gamepads.gamepads {
+            gamepad.pressed = enum_map! { _ => false };
+        }
+        self.state.gamepads.unified.pressed = enum_map! { _ => false };
     }
 }
 
@@ -256,6 +306,8 @@ impl RawInputAccumulator {
 pub struct RawInputState {
     /// Keyboard state, set of pressed keys
     pub keyboard: PetitSet<KeyCode, 16>,
+    /// Keys that got pressed during this frame, even if they were released before its end
+    pub keyboard_pressed: PetitSet<KeyCode, 16>,
     pub mouse: MouseState,
     pub gamepads: GamepadsState,
     // TODO: touchscreen
@@ -266,6 +318,7 @@ impl RawInputState {
     pub fn new() -> Self {
         Self {
             keyboard: PetitSet::new(),
+            keyboard_pressed: PetitSet::new(),
             mouse: MouseState::new(),
             gamepads: GamepadsState::new(),
         }
@@ -9,8 +9,25 @@ use crate::{
     vertices::VertexType,
 };
 
+/// Selects which sub-allocator of a [`DynamicBufferBackend`] is used for the data
+///
+/// Uniform data has much stricter alignment requirements than vertex and index data,
+/// so mixing them in the same buffer wastes a lot of space on padding.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum DynamicBufferPool {
+    /// Uniform data, aligned to [`MIN_UNIFORM_BUFFER_ALIGNMENT`](super::types::MIN_UNIFORM_BUFFER_ALIGNMENT)
+    Uniform,
+    /// Vertex and index data
+    Geometry,
+}
+
 pub trait DynamicBufferBackend {
-    fn get_with_raw_data(&mut self, alignment: BytesAddress, data: &[u8]) -> BufferRef<RawMarker>;
+    fn get_with_raw_data(
+        &mut self,
+        pool: DynamicBufferPool,
+        alignment: BytesAddress,
+        data: &[u8],
+    ) -> BufferRef<RawMarker>;
 
     fn get_with_struct_data<T: StructBufferType>(&mut self, data: &T::Value) -> BufferRef<T> {
         // can't use a statically-sized array here because of `<T::Value as encase::ShaderSize>::SHADER_SIZE.get() as usize`
@@ -20,14 +37,15 @@ pub trait DynamicBufferBackend {
         buffer.write(data).unwrap();
         let buffer = buffer.into_inner();
 
-        self.get_with_raw_data(T::OFFSET_ALIGNMENT, buffer)
+        self.get_with_raw_data(T::DYNAMIC_POOL, T::OFFSET_ALIGNMENT, buffer)
             .downcast()
     }
 
     fn get_with_slice_data<T: ArrayBufferType>(&mut self, data: &[T::Element]) -> BufferRef<T> {
         let data: &[u8] = bytemuck::cast_slice(data);
 
-        self.get_with_raw_data(T::OFFSET_ALIGNMENT, data).downcast()
+        self.get_with_raw_data(T::DYNAMIC_POOL, T::OFFSET_ALIGNMENT, data)
+            .downcast()
     }
 
     fn get_uniform_with_data<T: encase::ShaderSize + encase::internal::WriteInto>(
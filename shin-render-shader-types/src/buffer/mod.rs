@@ -9,7 +9,10 @@ use ownership::{AnyOwnership, BufferOwnership, Owned, Shared};
 use types::BufferType;
 use wgpu::util::DeviceExt as _;
 
-pub use self::{bytes_address::BytesAddress, dynamic_buffer::DynamicBufferBackend};
+pub use self::{
+    bytes_address::BytesAddress,
+    dynamic_buffer::{DynamicBufferBackend, DynamicBufferPool},
+};
 use crate::{
     RenderClone, RenderCloneCtx,
     buffer::types::{ArrayBufferType, IndexMarker, RawMarker, VertexMarker},
@@ -23,13 +26,15 @@ pub enum BufferUsage {
     StagingWrite,
     /// MAP_READ | COPY_DST
     StagingRead,
-    // TODO: maybe create specialized dynamic buffers for index/vertex/uniform?
-    // uniform buffers have very different layout requirements, so it will reduce wasted space somewhat
-    /// COPY_DST | INDEX | VERTEX | UNIFORM
-    Dynamic,
-    // `Dynamic` for GPUs with MAPPABLE_PRIMARY_BUFFERS feature (integrated GPUs normally)
-    /// MAP_WRITE | INDEX | VERTEX | UNIFORM
-    DynamicMappable,
+    /// COPY_DST | UNIFORM
+    DynamicUniform,
+    /// COPY_DST | INDEX | VERTEX
+    DynamicGeometry,
+    // `Dynamic*` for GPUs with MAPPABLE_PRIMARY_BUFFERS feature (integrated GPUs normally)
+    /// MAP_WRITE | UNIFORM
+    DynamicUniformMappable,
+    /// MAP_WRITE | INDEX | VERTEX
+    DynamicGeometryMappable,
     /// VERTEX
     Vertex,
     /// INDEX
@@ -43,17 +48,21 @@ impl From<BufferUsage> for wgpu::BufferUsages {
                 wgpu::BufferUsages::MAP_WRITE | wgpu::BufferUsages::COPY_SRC
             }
             BufferUsage::StagingRead => wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
-            BufferUsage::Dynamic => {
+            BufferUsage::DynamicUniform => {
+                wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::UNIFORM
+            }
+            BufferUsage::DynamicGeometry => {
                 wgpu::BufferUsages::COPY_DST
                     | wgpu::BufferUsages::INDEX
                     | wgpu::BufferUsages::VERTEX
-                    | wgpu::BufferUsages::UNIFORM
             }
-            BufferUsage::DynamicMappable => {
+            BufferUsage::DynamicUniformMappable => {
+                wgpu::BufferUsages::MAP_WRITE | wgpu::BufferUsages::UNIFORM
+            }
+            BufferUsage::DynamicGeometryMappable => {
                 wgpu::BufferUsages::MAP_WRITE
                     | wgpu::BufferUsages::INDEX
                     | wgpu::BufferUsages::VERTEX
-                    | wgpu::BufferUsages::UNIFORM
             }
             BufferUsage::Vertex => wgpu::BufferUsages::VERTEX,
             BufferUsage::Index => wgpu::BufferUsages::INDEX,
@@ -294,6 +303,8 @@ pub type AnyBuffer<T> = Buffer<AnyOwnership, T>;
 pub type OwnedVertexBuffer<T> = OwnedBuffer<VertexMarker<T>>;
 pub type OwnedIndexBuffer = OwnedBuffer<IndexMarker>;
 
+pub type SharedVertexBuffer<T> = SharedBuffer<VertexMarker<T>>;
+
 pub type AnyVertexBuffer<T> = AnyBuffer<VertexMarker<T>>;
 pub type AnyIndexBuffer = AnyBuffer<IndexMarker>;
 
@@ -341,7 +352,7 @@ impl<T: BufferType> OwnedBuffer<T> {
     }
 }
 
-impl<T: ArrayBufferType> OwnedBuffer<T> {
+impl<O: BufferOwnership, T: ArrayBufferType> Buffer<O, T> {
     pub fn allocate_with_array_contents(
         device: &wgpu::Device,
         data: &[T::Element],
@@ -354,18 +365,30 @@ impl<T: ArrayBufferType> OwnedBuffer<T> {
     }
 }
 
-impl<T: VertexType> OwnedBuffer<VertexMarker<T>> {
+impl<O: BufferOwnership, T: VertexType> Buffer<O, VertexMarker<T>> {
     pub fn allocate_vertex(device: &wgpu::Device, data: &[T], label: Option<&str>) -> Self {
         Self::allocate_with_array_contents(device, data, BufferUsage::Vertex, label)
     }
 }
 
-impl OwnedBuffer<IndexMarker> {
+impl<O: BufferOwnership> Buffer<O, IndexMarker> {
     pub fn allocate_index(device: &wgpu::Device, data: &[u16], label: Option<&str>) -> Self {
         Self::allocate_with_array_contents(device, data, BufferUsage::Index, label)
     }
 }
 
+/// Clones the handle, not the data: both buffers will refer to the same GPU memory
+impl<T: BufferType> Clone for SharedBuffer<T> {
+    fn clone(&self) -> Self {
+        Self {
+            ownership: self.ownership.clone(),
+            offset: self.offset,
+            logical_size: self.logical_size,
+            phantom: PhantomData,
+        }
+    }
+}
+
 impl<T: BufferType> From<OwnedBuffer<T>> for AnyBuffer<T> {
     fn from(value: OwnedBuffer<T>) -> Self {
         AnyBuffer {
@@ -2,7 +2,10 @@ use std::marker::PhantomData;
 
 use tracing::error;
 
-use crate::{buffer::BytesAddress, vertices::VertexType};
+use crate::{
+    buffer::{BytesAddress, DynamicBufferPool},
+    vertices::VertexType,
+};
 
 // TODO: this is very conservative, maybe we can find a way to relax this at runtime somehow
 pub const MIN_UNIFORM_BUFFER_ALIGNMENT: BytesAddress = BytesAddress::new(256);
@@ -11,6 +14,8 @@ pub trait BufferType {
     const OFFSET_ALIGNMENT: BytesAddress;
     const LOGICAL_SIZE_STRIDE: BytesAddress;
     const IS_ARRAY_TYPE: bool;
+    /// Which pool to use when allocating this type in a dynamic buffer
+    const DYNAMIC_POOL: DynamicBufferPool;
 
     fn is_valid_offset(offset: BytesAddress) -> bool {
         offset.is_aligned_to(Self::OFFSET_ALIGNMENT)
@@ -69,6 +74,7 @@ impl BufferType for RawMarker {
     const OFFSET_ALIGNMENT: BytesAddress = BytesAddress::new(4);
     const LOGICAL_SIZE_STRIDE: BytesAddress = BytesAddress::new(1);
     const IS_ARRAY_TYPE: bool = true;
+    const DYNAMIC_POOL: DynamicBufferPool = DynamicBufferPool::Geometry;
 }
 impl ArrayBufferType for RawMarker {
     type Element = u8;
@@ -82,6 +88,7 @@ impl<T: VertexType> BufferType for VertexMarker<T> {
     };
     const LOGICAL_SIZE_STRIDE: BytesAddress = BytesAddress::from_usize(std::mem::size_of::<T>());
     const IS_ARRAY_TYPE: bool = true;
+    const DYNAMIC_POOL: DynamicBufferPool = DynamicBufferPool::Geometry;
 }
 impl<T: VertexType> ArrayBufferType for VertexMarker<T> {
     type Element = T;
@@ -91,6 +98,7 @@ impl BufferType for IndexMarker {
     const OFFSET_ALIGNMENT: BytesAddress = BytesAddress::new(4);
     const LOGICAL_SIZE_STRIDE: BytesAddress = BytesAddress::new(2);
     const IS_ARRAY_TYPE: bool = true;
+    const DYNAMIC_POOL: DynamicBufferPool = DynamicBufferPool::Geometry;
 }
 impl ArrayBufferType for IndexMarker {
     type Element = u16;
@@ -100,6 +108,7 @@ impl<T: encase::ShaderType + encase::ShaderSize> BufferType for UniformMarker<T>
     const OFFSET_ALIGNMENT: BytesAddress = MIN_UNIFORM_BUFFER_ALIGNMENT;
     const LOGICAL_SIZE_STRIDE: BytesAddress = BytesAddress::new(T::SHADER_SIZE.get());
     const IS_ARRAY_TYPE: bool = true;
+    const DYNAMIC_POOL: DynamicBufferPool = DynamicBufferPool::Uniform;
 }
 impl<T: encase::ShaderSize + encase::internal::WriteInto> StructBufferType for UniformMarker<T> {
     type Value = T;
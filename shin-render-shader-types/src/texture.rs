@@ -1,4 +1,5 @@
 use enum_iterator::Sequence;
+use glam::UVec2;
 
 #[derive(Debug)]
 pub struct TextureSamplerStore {
@@ -46,6 +47,8 @@ pub enum TextureTargetKind {
 pub struct TextureTarget<'a> {
     pub kind: TextureTargetKind,
     pub view: &'a wgpu::TextureView,
+    /// Size of the texture, in pixels
+    pub size: UVec2,
 }
 
 #[derive(Debug, Copy, Clone)]
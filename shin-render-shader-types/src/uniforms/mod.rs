@@ -163,6 +163,9 @@ pub struct LayerUniformParams {
     pub transform: Mat4,
     pub color: FloatColor4,
     pub fragment_param: Vec4,
+    // xy - distance between the taps of the small blur, in texture coordinates (zero for no blur)
+    // zw - unused
+    pub blur: Vec4,
     // in reality those are enums, but wgsl doesn't natively support them
     // we can probably be a bit smarter and generate constants for those but that's a paaaaaain
     pub output_type: u32,
@@ -190,15 +193,20 @@ impl UniformType for LayerUniformParams {
                 ty: &<Vec4 as UniformType>::SCHEMA,
                 offset: LayerUniformParams::METADATA.extra.offsets[2] as u32,
             },
+            FieldSchema {
+                name: "blur",
+                ty: &<Vec4 as UniformType>::SCHEMA,
+                offset: LayerUniformParams::METADATA.extra.offsets[3] as u32,
+            },
             FieldSchema {
                 name: "output_type",
                 ty: &<u32 as UniformType>::SCHEMA,
-                offset: LayerUniformParams::METADATA.extra.offsets[3] as u32,
+                offset: LayerUniformParams::METADATA.extra.offsets[4] as u32,
             },
             FieldSchema {
                 name: "fragment_operation",
                 ty: &<u32 as UniformType>::SCHEMA,
-                offset: LayerUniformParams::METADATA.extra.offsets[4] as u32,
+                offset: LayerUniformParams::METADATA.extra.offsets[5] as u32,
             },
         ],
     });
@@ -332,3 +340,248 @@ impl UniformType for WiperMaskUniformParams {
         ],
     });
 }
+
+#[derive(ShaderType)]
+pub struct RasterUniformParams {
+    pub transform: Mat4,
+    // (amplitude, angular frequency, phase, unused), all in texture coordinates
+    pub horizontal: Vec4,
+    pub vertical: Vec4,
+}
+
+impl UniformType for RasterUniformParams {
+    const SCHEMA: TypeSchema = TypeSchema::Struct(StructSchema {
+        name: "RasterUniformParams",
+        size: RasterUniformParams::METADATA.min_size.get() as u32,
+        alignment: RasterUniformParams::METADATA.alignment.get() as u32,
+        fields: &[
+            FieldSchema {
+                name: "transform",
+                ty: &<Mat4 as UniformType>::SCHEMA,
+                offset: RasterUniformParams::METADATA.extra.offsets[0] as u32,
+            },
+            FieldSchema {
+                name: "horizontal",
+                ty: &<Vec4 as UniformType>::SCHEMA,
+                offset: RasterUniformParams::METADATA.extra.offsets[1] as u32,
+            },
+            FieldSchema {
+                name: "vertical",
+                ty: &<Vec4 as UniformType>::SCHEMA,
+                offset: RasterUniformParams::METADATA.extra.offsets[2] as u32,
+            },
+        ],
+    });
+}
+
+#[derive(ShaderType)]
+pub struct RippleUniformParams {
+    pub transform: Mat4,
+    // xy - ripple center in texture coordinates, zw - texture size in pixels
+    pub center: Vec4,
+    // (amplitude, angular frequency, phase, unused), in pixels
+    pub wave: Vec4,
+}
+
+impl UniformType for RippleUniformParams {
+    const SCHEMA: TypeSchema = TypeSchema::Struct(StructSchema {
+        name: "RippleUniformParams",
+        size: RippleUniformParams::METADATA.min_size.get() as u32,
+        alignment: RippleUniformParams::METADATA.alignment.get() as u32,
+        fields: &[
+            FieldSchema {
+                name: "transform",
+                ty: &<Mat4 as UniformType>::SCHEMA,
+                offset: RippleUniformParams::METADATA.extra.offsets[0] as u32,
+            },
+            FieldSchema {
+                name: "center",
+                ty: &<Vec4 as UniformType>::SCHEMA,
+                offset: RippleUniformParams::METADATA.extra.offsets[1] as u32,
+            },
+            FieldSchema {
+                name: "wave",
+                ty: &<Vec4 as UniformType>::SCHEMA,
+                offset: RippleUniformParams::METADATA.extra.offsets[2] as u32,
+            },
+        ],
+    });
+}
+
+#[derive(ShaderType)]
+pub struct BlurUniformParams {
+    pub transform: Mat4,
+    // xy - distance between the taps, in texture coordinates
+    // z - number of taps on each side of the center
+    // w - unused
+    pub blur: Vec4,
+}
+
+impl UniformType for BlurUniformParams {
+    const SCHEMA: TypeSchema = TypeSchema::Struct(StructSchema {
+        name: "BlurUniformParams",
+        size: BlurUniformParams::METADATA.min_size.get() as u32,
+        alignment: BlurUniformParams::METADATA.alignment.get() as u32,
+        fields: &[
+            FieldSchema {
+                name: "transform",
+                ty: &<Mat4 as UniformType>::SCHEMA,
+                offset: BlurUniformParams::METADATA.extra.offsets[0] as u32,
+            },
+            FieldSchema {
+                name: "blur",
+                ty: &<Vec4 as UniformType>::SCHEMA,
+                offset: BlurUniformParams::METADATA.extra.offsets[1] as u32,
+            },
+        ],
+    });
+}
+
+#[derive(ShaderType)]
+pub struct DissolveUniformParams {
+    pub transform: Mat4,
+    // x - dissolve threshold (0..1), y - unused, zw - texture size in pixels
+    pub dissolve: Vec4,
+}
+
+impl UniformType for DissolveUniformParams {
+    const SCHEMA: TypeSchema = TypeSchema::Struct(StructSchema {
+        name: "DissolveUniformParams",
+        size: DissolveUniformParams::METADATA.min_size.get() as u32,
+        alignment: DissolveUniformParams::METADATA.alignment.get() as u32,
+        fields: &[
+            FieldSchema {
+                name: "transform",
+                ty: &<Mat4 as UniformType>::SCHEMA,
+                offset: DissolveUniformParams::METADATA.extra.offsets[0] as u32,
+            },
+            FieldSchema {
+                name: "dissolve",
+                ty: &<Vec4 as UniformType>::SCHEMA,
+                offset: DissolveUniformParams::METADATA.extra.offsets[1] as u32,
+            },
+        ],
+    });
+}
+
+#[derive(ShaderType)]
+pub struct PostProcessUniformParams {
+    pub transform: Mat4,
+    // rows of the 3x3 matrix applied to the linear colors
+    pub color_matrix: [Vec4; 3],
+    // x - gamma exponent (inverse of the gamma setting), y - brightness multiplier, zw - unused
+    pub color_adjust: Vec4,
+    // x - radius, y - softness, z - strength, w - unused
+    pub vignette: Vec4,
+    // linear color the edges fade to, w - unused
+    pub vignette_color: Vec4,
+}
+
+impl UniformType for PostProcessUniformParams {
+    const SCHEMA: TypeSchema = TypeSchema::Struct(StructSchema {
+        name: "PostProcessUniformParams",
+        size: PostProcessUniformParams::METADATA.min_size.get() as u32,
+        alignment: PostProcessUniformParams::METADATA.alignment.get() as u32,
+        fields: &[
+            FieldSchema {
+                name: "transform",
+                ty: &<Mat4 as UniformType>::SCHEMA,
+                offset: PostProcessUniformParams::METADATA.extra.offsets[0] as u32,
+            },
+            FieldSchema {
+                name: "color_matrix",
+                ty: &<[Vec4; 3] as UniformType>::SCHEMA,
+                offset: PostProcessUniformParams::METADATA.extra.offsets[1] as u32,
+            },
+            FieldSchema {
+                name: "color_adjust",
+                ty: &<Vec4 as UniformType>::SCHEMA,
+                offset: PostProcessUniformParams::METADATA.extra.offsets[2] as u32,
+            },
+            FieldSchema {
+                name: "vignette",
+                ty: &<Vec4 as UniformType>::SCHEMA,
+                offset: PostProcessUniformParams::METADATA.extra.offsets[3] as u32,
+            },
+            FieldSchema {
+                name: "vignette_color",
+                ty: &<Vec4 as UniformType>::SCHEMA,
+                offset: PostProcessUniformParams::METADATA.extra.offsets[4] as u32,
+            },
+        ],
+    });
+}
+
+#[derive(ShaderType)]
+pub struct FxaaUniformParams {
+    pub transform: Mat4,
+}
+
+impl UniformType for FxaaUniformParams {
+    const SCHEMA: TypeSchema = TypeSchema::Struct(StructSchema {
+        name: "FxaaUniformParams",
+        size: FxaaUniformParams::METADATA.min_size.get() as u32,
+        alignment: FxaaUniformParams::METADATA.alignment.get() as u32,
+        fields: &[FieldSchema {
+            name: "transform",
+            ty: &<Mat4 as UniformType>::SCHEMA,
+            offset: FxaaUniformParams::METADATA.extra.offsets[0] as u32,
+        }],
+    });
+}
+
+#[derive(ShaderType)]
+pub struct GradientUniformParams {
+    pub transform: Mat4,
+    // premultiplied colors of the stops, in linear space
+    pub colors: [Vec4; 8],
+    // can't pass [f32; 8] due to alignment requirements,
+    // so the stop offsets are packed by four
+    pub offsets: [Vec4; 2],
+    // linear: start.xy, end.xy
+    // radial: center.xy, radius, unused
+    pub geometry: Vec4,
+    // 0 - linear, 1 - radial
+    pub kind: u32,
+    pub stop_count: u32,
+}
+
+impl UniformType for GradientUniformParams {
+    const SCHEMA: TypeSchema = TypeSchema::Struct(StructSchema {
+        name: "GradientUniformParams",
+        size: GradientUniformParams::METADATA.min_size.get() as u32,
+        alignment: GradientUniformParams::METADATA.alignment.get() as u32,
+        fields: &[
+            FieldSchema {
+                name: "transform",
+                ty: &<Mat4 as UniformType>::SCHEMA,
+                offset: GradientUniformParams::METADATA.extra.offsets[0] as u32,
+            },
+            FieldSchema {
+                name: "colors",
+                ty: &<[Vec4; 8] as UniformType>::SCHEMA,
+                offset: GradientUniformParams::METADATA.extra.offsets[1] as u32,
+            },
+            FieldSchema {
+                name: "offsets",
+                ty: &<[Vec4; 2] as UniformType>::SCHEMA,
+                offset: GradientUniformParams::METADATA.extra.offsets[2] as u32,
+            },
+            FieldSchema {
+                name: "geometry",
+                ty: &<Vec4 as UniformType>::SCHEMA,
+                offset: GradientUniformParams::METADATA.extra.offsets[3] as u32,
+            },
+            FieldSchema {
+                name: "kind",
+                ty: &<u32 as UniformType>::SCHEMA,
+                offset: GradientUniformParams::METADATA.extra.offsets[4] as u32,
+            },
+            FieldSchema {
+                name: "stop_count",
+                ty: &<u32 as UniformType>::SCHEMA,
+                offset: GradientUniformParams::METADATA.extra.offsets[5] as u32,
+            },
+        ],
+    });
+}
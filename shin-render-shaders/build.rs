@@ -17,9 +17,11 @@ use naga::{
 use quote::{TokenStreamExt, quote};
 use shin_render_shader_types::{
     uniforms::{
-        ClearUniformParams, FillUniformParams, FontBorderUniformParams, FontUniformParams,
-        LayerUniformParams, MaskUniformParams, MovieUniformParams, SpriteUniformParams,
-        UniformType, WiperDefaultUniformParams, WiperMaskUniformParams,
+        BlurUniformParams, ClearUniformParams, DissolveUniformParams, FillUniformParams,
+        FontBorderUniformParams, FontUniformParams, FxaaUniformParams, GradientUniformParams,
+        LayerUniformParams, MaskUniformParams, MovieUniformParams, PostProcessUniformParams,
+        RasterUniformParams, RippleUniformParams, SpriteUniformParams, UniformType,
+        WiperDefaultUniformParams, WiperMaskUniformParams,
         metadata::{ArraySchema, PrimitiveType, StructSchema, TypeSchema},
     },
     vertices::{
@@ -293,6 +295,13 @@ fn generate_wgsl_types() -> WgslSchema {
     ctx.gen_uniform::<MovieUniformParams>();
     ctx.gen_uniform::<WiperDefaultUniformParams>();
     ctx.gen_uniform::<WiperMaskUniformParams>();
+    ctx.gen_uniform::<RasterUniformParams>();
+    ctx.gen_uniform::<RippleUniformParams>();
+    ctx.gen_uniform::<DissolveUniformParams>();
+    ctx.gen_uniform::<BlurUniformParams>();
+    ctx.gen_uniform::<PostProcessUniformParams>();
+    ctx.gen_uniform::<FxaaUniformParams>();
+    ctx.gen_uniform::<GradientUniformParams>();
 
     let vertex_rust_names = ctx.known_vertices;
     let struct_rust_names = ctx
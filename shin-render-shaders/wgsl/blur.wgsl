@@ -0,0 +1,42 @@
+#import types::{PosTexVertex, BlurUniformParams}
+
+@group(0) @binding(0)
+var<uniform> params: BlurUniformParams;
+
+@group(0) @binding(1)
+var texture_texture: texture_2d<f32>;
+@group(0) @binding(2)
+var texture_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) texture_position: vec2<f32>,
+}
+
+@vertex
+fn vertex_main(input: PosTexVertex) -> VertexOutput {
+    var output: VertexOutput;
+
+    output.clip_position = params.transform * vec4<f32>(input.position, 0.0, 1.0);
+    output.texture_position = input.texture_position;
+
+    return output;
+}
+
+fn sample(offset: vec2<f32>, position: vec2<f32>) -> vec4<f32> {
+    return textureSampleLevel(texture_texture, texture_sampler, position + offset * params.blur.xy, 0.0);
+}
+
+// one direction of a separable box blur, averaging (premultiplied) colors of equally weighted taps
+// the small radii are blurred by the layer shader instead, see `layer.wgsl`
+@fragment
+fn fragment_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    let taps = i32(params.blur.z);
+
+    var sum = vec4<f32>(0.0);
+    for (var i = -taps; i <= taps; i++) {
+        sum += sample(vec2<f32>(f32(i)), input.texture_position);
+    }
+
+    return sum / f32(2 * taps + 1);
+}
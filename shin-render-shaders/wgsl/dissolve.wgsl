@@ -0,0 +1,55 @@
+#import types::{PosTexVertex, DissolveUniformParams}
+
+@group(0) @binding(0)
+var<uniform> params: DissolveUniformParams;
+
+@group(0) @binding(1)
+var texture_texture: texture_2d<f32>;
+@group(0) @binding(2)
+var texture_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) texture_position: vec2<f32>,
+}
+
+// size of the noise tile, in pixels
+const NOISE_TILE_SIZE: u32 = 64u;
+
+// PCG hash, see https://www.reedbeta.com/blog/hash-functions-for-gpu-rendering/
+fn pcg_hash(value: u32) -> u32 {
+    let state = value * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+// tileable white noise in [0; 1)
+fn noise(pixel: vec2<u32>) -> f32 {
+    let cell = pixel % NOISE_TILE_SIZE;
+    let hash = pcg_hash(cell.x + pcg_hash(cell.y));
+    // keep only 24 bits, so that the conversion to f32 is exact and never rounds up to 1.0
+    return f32(hash >> 8u) / 16777216.0;
+}
+
+@vertex
+fn vertex_main(input: PosTexVertex) -> VertexOutput {
+    var output: VertexOutput;
+
+    output.clip_position = params.transform * vec4<f32>(input.position, 0.0, 1.0);
+    output.texture_position = input.texture_position;
+
+    return output;
+}
+
+@fragment
+fn fragment_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    let sampled = textureSample(texture_texture, texture_sampler, input.texture_position);
+
+    let pixel = vec2<u32>(floor(input.texture_position * params.dissolve.zw));
+    if noise(pixel) < params.dissolve.x {
+        // fully transparent fragments are discarded when the layer is drawn with LayerDiscard
+        return vec4<f32>(0.0, 0.0, 0.0, 0.0);
+    }
+
+    return sampled;
+}
@@ -0,0 +1,78 @@
+#import types::{PosTexVertex, FxaaUniformParams}
+
+@group(0) @binding(0)
+var<uniform> params: FxaaUniformParams;
+
+@group(0) @binding(1)
+var texture_texture: texture_2d<f32>;
+@group(0) @binding(2)
+var texture_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) texture_position: vec2<f32>,
+}
+
+@vertex
+fn vertex_main(input: PosTexVertex) -> VertexOutput {
+    var output: VertexOutput;
+
+    output.clip_position = params.transform * vec4<f32>(input.position, 0.0, 1.0);
+    output.texture_position = input.texture_position;
+
+    return output;
+}
+
+const REDUCE_MIN: f32 = 1.0 / 128.0;
+const REDUCE_MUL: f32 = 1.0 / 8.0;
+const SPAN_MAX: f32 = 8.0;
+
+fn luma(color: vec3<f32>) -> f32 {
+    return dot(color, vec3<f32>(0.299, 0.587, 0.114));
+}
+
+// the simple (console) variant of FXAA: the blur direction is estimated from the luma of the diagonal neighbours
+@fragment
+fn fragment_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    // the texture may be of a different size than the virtual canvas, so the texel size is taken from the texture itself
+    let texel = 1.0 / vec2<f32>(textureDimensions(texture_texture));
+    let uv = input.texture_position;
+
+    let center = textureSample(texture_texture, texture_sampler, uv);
+    let luma_nw = luma(textureSample(texture_texture, texture_sampler, uv + vec2<f32>(-1.0, -1.0) * texel).rgb);
+    let luma_ne = luma(textureSample(texture_texture, texture_sampler, uv + vec2<f32>(1.0, -1.0) * texel).rgb);
+    let luma_sw = luma(textureSample(texture_texture, texture_sampler, uv + vec2<f32>(-1.0, 1.0) * texel).rgb);
+    let luma_se = luma(textureSample(texture_texture, texture_sampler, uv + vec2<f32>(1.0, 1.0) * texel).rgb);
+    let luma_m = luma(center.rgb);
+
+    let luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    let luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+
+    var direction = vec2<f32>(
+        -((luma_nw + luma_ne) - (luma_sw + luma_se)),
+        (luma_nw + luma_sw) - (luma_ne + luma_se),
+    );
+    let direction_reduce = max(
+        (luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * REDUCE_MUL,
+        REDUCE_MIN,
+    );
+    let direction_scale = 1.0 / (min(abs(direction.x), abs(direction.y)) + direction_reduce);
+    direction = clamp(direction * direction_scale, vec2<f32>(-SPAN_MAX), vec2<f32>(SPAN_MAX)) * texel;
+
+    let color_a = 0.5 * (
+        textureSample(texture_texture, texture_sampler, uv + direction * (1.0 / 3.0 - 0.5)).rgb
+        + textureSample(texture_texture, texture_sampler, uv + direction * (2.0 / 3.0 - 0.5)).rgb
+    );
+    let color_b = color_a * 0.5 + 0.25 * (
+        textureSample(texture_texture, texture_sampler, uv + direction * -0.5).rgb
+        + textureSample(texture_texture, texture_sampler, uv + direction * 0.5).rgb
+    );
+
+    // the wider sample might have reached past the edge, in which case the narrower one is used
+    let luma_b = luma(color_b);
+    if luma_b < luma_min || luma_b > luma_max {
+        return vec4<f32>(color_a, center.a);
+    } else {
+        return vec4<f32>(color_b, center.a);
+    }
+}
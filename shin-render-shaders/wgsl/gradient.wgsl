@@ -0,0 +1,72 @@
+#import types::{PosVertex, GradientUniformParams}
+
+@group(0) @binding(0)
+var<uniform> params: GradientUniformParams;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) position: vec2<f32>,
+}
+
+@vertex
+fn vertex_main(input: PosVertex) -> VertexOutput {
+    var output: VertexOutput;
+    output.clip_position = params.transform * vec4<f32>(input.position, 1.0);
+    // the gradient geometry is specified in the same space as the vertices
+    output.position = input.position.xy;
+    return output;
+}
+
+fn linear_to_srgb(color: vec3<f32>) -> vec3<f32> {
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
+fn stop_offset(index: u32) -> f32 {
+    return params.offsets[index / 4u][index % 4u];
+}
+
+// position along the gradient, 0 being the first stop and 1 being the last one
+// a degenerate gradient (with no length or no radius) is filled with the last stop instead of dividing by zero
+fn gradient_position(position: vec2<f32>) -> f32 {
+    if (params.kind == 0u) {
+        let start = params.geometry.xy;
+        let axis = params.geometry.zw - start;
+        let length_squared = dot(axis, axis);
+        if (length_squared <= 0.0) {
+            return 1.0;
+        }
+        return dot(position - start, axis) / length_squared;
+    } else {
+        let radius = params.geometry.z;
+        if (radius <= 0.0) {
+            return 1.0;
+        }
+        return distance(position, params.geometry.xy) / radius;
+    }
+}
+
+@fragment
+fn fragment_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    let t = clamp(gradient_position(input.position), 0.0, 1.0);
+
+    // the colors before the first and after the last stop are extended
+    var color = params.colors[0];
+    for (var i = 1u; i < params.stop_count; i++) {
+        let from_offset = stop_offset(i - 1u);
+        let to_offset = stop_offset(i);
+        if (t >= to_offset) {
+            color = params.colors[i];
+        } else if (t > from_offset) {
+            color = mix(params.colors[i - 1u], params.colors[i], (t - from_offset) / (to_offset - from_offset));
+        }
+    }
+
+    // the stops are interpolated premultiplied and in linear space, so the output has to be encoded back
+    if (color.a <= 0.0) {
+        return vec4<f32>(0.0);
+    }
+    let encoded = linear_to_srgb(clamp(color.rgb / color.a, vec3<f32>(0.0), vec3<f32>(1.0)));
+    return vec4<f32>(encoded * color.a, color.a);
+}
@@ -24,9 +24,31 @@ fn vertex_main(input: PosTexVertex) -> VertexOutput {
     return output;
 }
 
+// taps on each side of the center of the small blur, making for a 5x5 kernel
+const SMALL_BLUR_TAPS: i32 = 2;
+
+fn sample_texture(position: vec2<f32>) -> vec4<f32> {
+    // the branch is on a uniform, so the sampling stays in uniform control flow
+    if all(params.blur.xy == vec2<f32>(0.0)) {
+        return textureSample(texture_texture, texture_sampler, position);
+    }
+
+    // a box blur for the small radii, cheaper than the separate passes of `blur.wgsl`
+    // only used with premultiplied textures, so the colors can be averaged as they are
+    var sum = vec4<f32>(0.0);
+    for (var y = -SMALL_BLUR_TAPS; y <= SMALL_BLUR_TAPS; y++) {
+        for (var x = -SMALL_BLUR_TAPS; x <= SMALL_BLUR_TAPS; x++) {
+            sum += textureSample(texture_texture, texture_sampler, position + vec2<f32>(f32(x), f32(y)) * params.blur.xy);
+        }
+    }
+    let side = f32(2 * SMALL_BLUR_TAPS + 1);
+
+    return sum / (side * side);
+}
+
 @fragment
 fn fragment_main(input: VertexOutput) -> @location(0) vec4<f32> {
-    let sampled = textureSample(texture_texture, texture_sampler, input.texture_position);
+    let sampled = sample_texture(input.texture_position);
 
     if params.output_type == 2 {
         // discard
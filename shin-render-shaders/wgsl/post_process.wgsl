@@ -0,0 +1,65 @@
+#import types::{PosTexVertex, PostProcessUniformParams}
+
+@group(0) @binding(0)
+var<uniform> params: PostProcessUniformParams;
+
+@group(0) @binding(1)
+var texture_texture: texture_2d<f32>;
+@group(0) @binding(2)
+var texture_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) texture_position: vec2<f32>,
+}
+
+@vertex
+fn vertex_main(input: PosTexVertex) -> VertexOutput {
+    var output: VertexOutput;
+
+    output.clip_position = params.transform * vec4<f32>(input.position, 0.0, 1.0);
+    output.texture_position = input.texture_position;
+
+    return output;
+}
+
+fn srgb_to_linear(color: vec3<f32>) -> vec3<f32> {
+    let low = color / 12.92;
+    let high = pow((color + 0.055) / 1.055, vec3<f32>(2.4));
+    return select(high, low, color <= vec3<f32>(0.04045));
+}
+
+fn linear_to_srgb(color: vec3<f32>) -> vec3<f32> {
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
+@fragment
+fn fragment_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    let sampled = textureSample(texture_texture, texture_sampler, input.texture_position);
+
+    // like the original game, we are not sRGB-correct: the colors stay gamma-encoded all the way
+    // to the screen, so they have to be decoded for the color matrix to operate on light intensities
+    let linear = srgb_to_linear(sampled.rgb);
+    let transformed = vec3<f32>(
+        dot(linear, params.color_matrix[0].xyz),
+        dot(linear, params.color_matrix[1].xyz),
+        dot(linear, params.color_matrix[2].xyz),
+    );
+
+    // the vignette is faded in in linear space too, so that it darkens the image evenly
+    // the distance from the center is normalized to be 1 at the corners
+    let size = vec2<f32>(textureDimensions(texture_texture));
+    let distance = length((input.texture_position - 0.5) * size) / length(size * 0.5);
+    let vignette = params.vignette.z
+        * smoothstep(params.vignette.x, params.vignette.x + params.vignette.y, distance);
+    let vignetted = mix(transformed, params.vignette_color.rgb, vignette);
+
+    let encoded = linear_to_srgb(clamp(vignetted, vec3<f32>(0.0), vec3<f32>(1.0)));
+
+    // while the gamma curve is applied to the encoded values directly
+    let adjusted = pow(encoded, vec3<f32>(params.color_adjust.x)) * params.color_adjust.y;
+
+    return vec4<f32>(clamp(adjusted, vec3<f32>(0.0), vec3<f32>(1.0)), sampled.a);
+}
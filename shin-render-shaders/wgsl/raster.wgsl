@@ -0,0 +1,37 @@
+#import types::{PosTexVertex, RasterUniformParams}
+
+@group(0) @binding(0)
+var<uniform> params: RasterUniformParams;
+
+@group(0) @binding(1)
+var texture_texture: texture_2d<f32>;
+@group(0) @binding(2)
+var texture_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) texture_position: vec2<f32>,
+}
+
+@vertex
+fn vertex_main(input: PosTexVertex) -> VertexOutput {
+    var output: VertexOutput;
+
+    output.clip_position = params.transform * vec4<f32>(input.position, 0.0, 1.0);
+    output.texture_position = input.texture_position;
+
+    return output;
+}
+
+@fragment
+fn fragment_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    let position = input.texture_position;
+
+    // each row is shifted horizontally, each column is shifted vertically
+    let offset = vec2<f32>(
+        params.horizontal.x * sin(position.y * params.horizontal.y + params.horizontal.z),
+        params.vertical.x * sin(position.x * params.vertical.y + params.vertical.z),
+    );
+
+    return textureSample(texture_texture, texture_sampler, position + offset);
+}
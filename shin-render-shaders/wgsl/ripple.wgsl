@@ -0,0 +1,46 @@
+#import types::{PosTexVertex, RippleUniformParams}
+
+@group(0) @binding(0)
+var<uniform> params: RippleUniformParams;
+
+@group(0) @binding(1)
+var texture_texture: texture_2d<f32>;
+@group(0) @binding(2)
+var texture_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) texture_position: vec2<f32>,
+}
+
+@vertex
+fn vertex_main(input: PosTexVertex) -> VertexOutput {
+    var output: VertexOutput;
+
+    output.clip_position = params.transform * vec4<f32>(input.position, 0.0, 1.0);
+    output.texture_position = input.texture_position;
+
+    return output;
+}
+
+@fragment
+fn fragment_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    let texture_size = params.center.zw;
+    let position = input.texture_position * texture_size;
+    let delta = position - params.center.xy * texture_size;
+    let distance = length(delta);
+
+    let amplitude = params.wave.x;
+    let frequency = params.wave.y;
+    let phase = params.wave.z;
+
+    var offset = vec2<f32>(0.0, 0.0);
+    if distance > 0.0001 {
+        // fade the waves out towards the center over a single wavelength, so the center itself doesn't move
+        let wavelength = 6.28318530718 / frequency;
+        let falloff = min(distance / wavelength, 1.0);
+        offset = delta / distance * amplitude * falloff * sin(distance * frequency - phase);
+    }
+
+    return textureSample(texture_texture, texture_sampler, (position + offset) / texture_size);
+}
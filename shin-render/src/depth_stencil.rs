@@ -35,6 +35,11 @@ impl DepthStencil {
         }
     }
 
+    /// Match a render target that has the same scale set, see [`RenderTexture::set_scale`](crate::render_texture::RenderTexture::set_scale)
+    pub fn set_scale(&mut self, scale: f32) {
+        self.inner_texture.set_scale(scale);
+    }
+
     pub fn get_target_view(&mut self) -> DepthStencilTarget {
         DepthStencilTarget {
             view: self.inner_texture.resize_and_get_view(),
@@ -3,7 +3,7 @@ use std::sync::mpsc;
 
 use shin_primitives::exclusive::Exclusive;
 use shin_render_shader_types::buffer::{
-    BufferRef, BufferUsage, BytesAddress, OwnedBuffer, types::RawMarker,
+    BufferRef, BufferUsage, BytesAddress, DynamicBufferPool, OwnedBuffer, types::RawMarker,
 };
 use tracing::info;
 
@@ -20,8 +20,11 @@ use tracing::info;
 /// 3. Submit all command encoders that were used in step 2.
 /// 4. Call [`StagingBelt::recall()`].
 ///
+/// Each belt serves a single [`DynamicBufferPool`], which determines the usage of the allocated buffers.
+///
 /// [`Queue::write_buffer_with()`]: wgpu::Queue::write_buffer_with
 pub struct StagingBelt {
+    pool: DynamicBufferPool,
     chunk_size: BytesAddress,
     alloc_buffer_counter: u32,
     /// Chunks into which we are accumulating data to be transferred.
@@ -49,9 +52,10 @@ impl StagingBelt {
     /// * 1-4 times less than the total amount of data uploaded per submission
     ///   (per [`StagingBelt::finish()`]); and
     /// * bigger is better, within these bounds.
-    pub fn new(chunk_size: BytesAddress) -> Self {
+    pub fn new(pool: DynamicBufferPool, chunk_size: BytesAddress) -> Self {
         let (sender, receiver) = mpsc::channel();
         StagingBelt {
+            pool,
             chunk_size,
             alloc_buffer_counter: 0,
             active_chunks: Vec::new(),
@@ -82,13 +86,15 @@ impl StagingBelt {
     /// which may be used to meet alignment requirements for the operation you wish to perform
     /// with the slice. This does not necessarily affect the alignment of the [`BufferViewMut`].
     ///
+    /// The third returned value is the number of bytes skipped in the chunk to satisfy the alignment.
+    ///
     /// NOTE: staging buffer slice can be larger than requested to satisfy `wgpu::MAP_ALIGNMENT`.
     pub fn allocate(
         &mut self,
         size: BytesAddress,
         alignment: BytesAddress,
         device: &wgpu::Device,
-    ) -> (BufferRef<RawMarker>, BufferRef<RawMarker>) {
+    ) -> (BufferRef<RawMarker>, BufferRef<RawMarker>, BytesAddress) {
         assert!(
             alignment.get().is_power_of_two(),
             "alignment must be a power of two, not {alignment}"
@@ -99,7 +105,7 @@ impl StagingBelt {
         let mut chunk = if let Some(index) = self
             .active_chunks
             .iter()
-            .position(|chunk| chunk.can_allocate(size, alignment))
+            .position(|chunk| chunk.allocator.can_allocate(size, alignment))
         {
             self.active_chunks.swap_remove(index)
         } else {
@@ -108,7 +114,7 @@ impl StagingBelt {
             if let Some(index) = self
                 .free_chunks
                 .iter()
-                .position(|chunk| chunk.can_allocate(size, alignment))
+                .position(|chunk| chunk.allocator.can_allocate(size, alignment))
             {
                 self.free_chunks.swap_remove(index)
             } else {
@@ -117,6 +123,7 @@ impl StagingBelt {
                 let size = self.chunk_size.max(size);
                 let index = self.alloc_buffer_counter;
                 self.alloc_buffer_counter += 1;
+                let pool = self.pool;
 
                 if device
                     .features()
@@ -127,12 +134,15 @@ impl StagingBelt {
                         staging: OwnedBuffer::allocate_raw(
                             device,
                             size,
-                            BufferUsage::DynamicMappable,
+                            match pool {
+                                DynamicBufferPool::Uniform => BufferUsage::DynamicUniformMappable,
+                                DynamicBufferPool::Geometry => BufferUsage::DynamicGeometryMappable,
+                            },
                             true,
-                            Some(&format!("StagingBelt/buffer #{index}")),
+                            Some(&format!("StagingBelt/{pool:?}/buffer #{index}")),
                         ),
                         actual: None,
-                        offset: BytesAddress::ZERO,
+                        allocator: SubAllocator::new(size),
                     }
                 } else {
                     Chunk {
@@ -141,22 +151,28 @@ impl StagingBelt {
                             size,
                             BufferUsage::StagingWrite,
                             true,
-                            Some(&format!("StagingBelt/staging #{index}")),
+                            Some(&format!("StagingBelt/{pool:?}/staging #{index}")),
                         ),
                         actual: Some(OwnedBuffer::allocate_raw(
                             device,
                             size,
-                            BufferUsage::Dynamic,
+                            match pool {
+                                DynamicBufferPool::Uniform => BufferUsage::DynamicUniform,
+                                DynamicBufferPool::Geometry => BufferUsage::DynamicGeometry,
+                            },
                             false,
-                            Some(&format!("StagingBelt/actual #{index}")),
+                            Some(&format!("StagingBelt/{pool:?}/actual #{index}")),
                         )),
-                        offset: BytesAddress::ZERO,
+                        allocator: SubAllocator::new(size),
                     }
                 }
             }
         };
 
-        let allocation_offset = chunk.allocate(size, alignment);
+        let SubAllocation {
+            offset: allocation_offset,
+            padding,
+        } = chunk.allocator.allocate(size, alignment);
 
         self.active_chunks.push(chunk);
         let chunk = self.active_chunks.last().unwrap();
@@ -169,6 +185,7 @@ impl StagingBelt {
                 size.align_to(BytesAddress::new(wgpu::MAP_ALIGNMENT)),
             ),
             actual.slice_bytes(allocation_offset, size),
+            padding,
         )
     }
 
@@ -192,7 +209,8 @@ impl StagingBelt {
                 assert_eq!(actual_offset, BytesAddress::ZERO);
 
                 let size = chunk
-                    .offset
+                    .allocator
+                    .offset()
                     .align_to(BytesAddress::new(wgpu::COPY_BUFFER_ALIGNMENT));
 
                 encoder.copy_buffer_to_buffer(staging, 0, actual, 0, size.get());
@@ -212,7 +230,7 @@ impl StagingBelt {
         for Chunk {
             staging,
             actual,
-            offset,
+            allocator,
         } in self.closed_chunks.drain(..)
         {
             let sender = self.sender.get_mut().clone();
@@ -221,7 +239,7 @@ impl StagingBelt {
                 let _ = sender.send(Chunk {
                     staging,
                     actual,
-                    offset,
+                    allocator,
                 });
             });
         }
@@ -231,7 +249,7 @@ impl StagingBelt {
     /// from `self.receiver` to `self.free_chunks`.
     fn receive_chunks(&mut self) {
         while let Ok(mut chunk) = self.receiver.get_mut().try_recv() {
-            chunk.offset = BytesAddress::ZERO;
+            chunk.allocator.reset();
             self.free_chunks.push(chunk);
         }
     }
@@ -240,6 +258,7 @@ impl StagingBelt {
 impl fmt::Debug for StagingBelt {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("StagingBelt")
+            .field("pool", &self.pool)
             .field("chunk_size", &self.chunk_size)
             .field("active_chunks", &self.active_chunks.len())
             .field("closed_chunks", &self.closed_chunks.len())
@@ -251,27 +270,130 @@ impl fmt::Debug for StagingBelt {
 struct Chunk {
     staging: OwnedBuffer<RawMarker>,
     actual: Option<OwnedBuffer<RawMarker>>,
-    offset: BytesAddress,
+    allocator: SubAllocator,
 }
 
 impl Chunk {
     pub fn get_buffers(&self) -> (&OwnedBuffer<RawMarker>, &OwnedBuffer<RawMarker>) {
         (&self.staging, self.actual.as_ref().unwrap_or(&self.staging))
     }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct SubAllocation {
+    offset: BytesAddress,
+    /// Bytes skipped before `offset` to satisfy the alignment
+    padding: BytesAddress,
+}
+
+/// Linear sub-allocator of a single chunk
+#[derive(Debug, Clone)]
+struct SubAllocator {
+    offset: BytesAddress,
+    capacity: BytesAddress,
+}
+
+impl SubAllocator {
+    fn new(capacity: BytesAddress) -> Self {
+        Self {
+            offset: BytesAddress::ZERO,
+            capacity,
+        }
+    }
+
+    fn offset(&self) -> BytesAddress {
+        self.offset
+    }
+
+    fn reset(&mut self) {
+        self.offset = BytesAddress::ZERO;
+    }
 
     fn can_allocate(&self, size: BytesAddress, alignment: BytesAddress) -> bool {
         let alloc_start = self.offset.align_to(alignment);
         let alloc_end = alloc_start + size;
 
-        alloc_end <= self.staging.raw_bytes_size()
+        alloc_end <= self.capacity
     }
 
-    fn allocate(&mut self, size: BytesAddress, alignment: BytesAddress) -> BytesAddress {
+    fn allocate(&mut self, size: BytesAddress, alignment: BytesAddress) -> SubAllocation {
         let alloc_start = self.offset.align_to(alignment);
         let alloc_end = alloc_start + size;
 
-        assert!(alloc_end <= self.staging.raw_bytes_size());
+        assert!(alloc_end <= self.capacity);
+        let padding = alloc_start - self.offset;
         self.offset = alloc_end;
-        alloc_start
+
+        SubAllocation {
+            offset: alloc_start,
+            padding,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shin_render_shader_types::buffer::{
+        BytesAddress, DynamicBufferPool,
+        types::{BufferType, IndexMarker, RawMarker, UniformMarker},
+    };
+
+    use super::SubAllocator;
+
+    fn allocate(
+        allocator: &mut SubAllocator,
+        size: u64,
+        alignment: BytesAddress,
+    ) -> BytesAddress {
+        // the belt always aligns to at least `wgpu::MAP_ALIGNMENT`
+        let alignment = alignment.max(BytesAddress::new(wgpu::MAP_ALIGNMENT));
+        allocator.allocate(BytesAddress::new(size), alignment).padding
+    }
+
+    #[test]
+    fn split_pools_reduce_padding() {
+        let capacity = BytesAddress::new(1024 * 1024);
+
+        // a typical sprite draw: a uniform block, a quad worth of vertices and its indices
+        let workload = [
+            (
+                UniformMarker::<glam::Mat4>::DYNAMIC_POOL,
+                144,
+                UniformMarker::<glam::Mat4>::OFFSET_ALIGNMENT,
+            ),
+            (RawMarker::DYNAMIC_POOL, 128, BytesAddress::new(4)),
+            (IndexMarker::DYNAMIC_POOL, 12, IndexMarker::OFFSET_ALIGNMENT),
+        ];
+
+        let mut shared = SubAllocator::new(capacity);
+        let mut uniform = SubAllocator::new(capacity);
+        let mut geometry = SubAllocator::new(capacity);
+
+        let mut shared_padding = BytesAddress::ZERO;
+        let mut split_padding = BytesAddress::ZERO;
+        for _ in 0..100 {
+            for &(pool, size, alignment) in &workload {
+                shared_padding += allocate(&mut shared, size, alignment);
+                split_padding += allocate(
+                    match pool {
+                        DynamicBufferPool::Uniform => &mut uniform,
+                        DynamicBufferPool::Geometry => &mut geometry,
+                    },
+                    size,
+                    alignment,
+                );
+            }
+        }
+
+        // in a shared buffer, vertex and index data push every uniform block to the next 256-byte boundary:
+        // 144 + 128 + 12 = 284 bytes are used out of 512 per draw
+        assert_eq!(shared_padding, BytesAddress::new(99 * 228));
+        // separately, only the uniform blocks themselves are padded to 256 bytes,
+        // and the geometry only to `wgpu::MAP_ALIGNMENT`
+        assert_eq!(split_padding, BytesAddress::new(99 * (112 + 4)));
+        assert_eq!(
+            uniform.offset() + geometry.offset(),
+            BytesAddress::new(100 * (144 + 128 + 12)) + split_padding
+        );
     }
 }
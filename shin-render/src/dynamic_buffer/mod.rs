@@ -3,12 +3,12 @@ mod belt;
 use std::fmt::Debug;
 
 use shin_render_shader_types::buffer::{
-    BufferRef, BytesAddress, DynamicBufferBackend,
+    BufferRef, BytesAddress, DynamicBufferBackend, DynamicBufferPool,
     types::{BufferType, RawMarker},
 };
 use sketches_ddsketch::DDSketch;
 
-use crate::dynamic_buffer::belt::StagingBelt;
+use crate::{dynamic_buffer::belt::StagingBelt, geometry_cache::GeometryCache};
 
 pub struct DynamicBufferStats {
     pub start_time: std::time::Instant,
@@ -71,40 +71,64 @@ impl Debug for DynamicBufferStats {
 }
 
 /// Dynamically allocates space in a gpu buffer, mostly used for submitting uniform data
+///
+/// Uniform and vertex/index data are sub-allocated from separate buffers (see [`DynamicBufferPool`]),
+/// so that small vertex and index allocations don't get padded to the uniform alignment.
 pub struct DynamicBuffer {
     device: wgpu::Device,
-    belt: StagingBelt,
+    uniform_belt: StagingBelt,
+    geometry_belt: StagingBelt,
+    geometry_cache: GeometryCache,
     stats: DynamicBufferStats,
 }
 
 impl DynamicBuffer {
     pub fn new(device: wgpu::Device, chunk_size: BytesAddress) -> Self {
         Self {
+            geometry_cache: GeometryCache::new(&device),
             device,
-            belt: StagingBelt::new(chunk_size),
+            uniform_belt: StagingBelt::new(DynamicBufferPool::Uniform, chunk_size),
+            geometry_belt: StagingBelt::new(DynamicBufferPool::Geometry, chunk_size),
             stats: DynamicBufferStats::new(),
         }
     }
 
+    /// The static geometry is kept alongside the dynamic one, so that it's available wherever a render pass is
+    pub fn geometry_cache(&self) -> &GeometryCache {
+        &self.geometry_cache
+    }
+
     pub fn finish(&mut self, encoder: &mut wgpu::CommandEncoder) {
-        self.belt.finish(encoder)
+        self.uniform_belt.finish(encoder);
+        self.geometry_belt.finish(encoder);
     }
 
     pub fn recall(&mut self) {
-        self.belt.recall()
+        self.uniform_belt.recall();
+        self.geometry_belt.recall();
     }
 }
 
 impl DynamicBufferBackend for DynamicBuffer {
     #[tracing::instrument(skip_all)]
-    fn get_with_raw_data(&mut self, alignment: BytesAddress, data: &[u8]) -> BufferRef<RawMarker> {
+    fn get_with_raw_data(
+        &mut self,
+        pool: DynamicBufferPool,
+        alignment: BytesAddress,
+        data: &[u8],
+    ) -> BufferRef<RawMarker> {
         let logical_size = BytesAddress::new(data.len() as _);
 
-        let (staging, actual) = self.belt.allocate(logical_size, alignment, &self.device);
+        let belt = match pool {
+            DynamicBufferPool::Uniform => &mut self.uniform_belt,
+            DynamicBufferPool::Geometry => &mut self.geometry_belt,
+        };
+        let (staging, actual, padding) = belt.allocate(logical_size, alignment, &self.device);
 
         assert!(RawMarker::is_valid_logical_size(logical_size));
 
         self.stats.allocated += logical_size.get();
+        self.stats.wasted += padding.get();
         self.stats.alignment_histogram.add(alignment.get() as f64);
         self.stats.size_histogram.add(logical_size.get() as f64);
 
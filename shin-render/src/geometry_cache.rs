@@ -0,0 +1,57 @@
+//! Vertex buffers for the shapes drawn over and over again, uploaded once instead of every frame.
+
+use shin_render_shader_types::{buffer::SharedVertexBuffer, vertices::PosTexVertex};
+
+use crate::quad_vertices::build_quad_vertices;
+
+pub struct GeometryCache {
+    unit_quad: SharedVertexBuffer<PosTexVertex>,
+}
+
+impl GeometryCache {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let unit_quad = build_quad_vertices(|t| PosTexVertex {
+            position: t,
+            texture_position: t,
+        });
+
+        Self {
+            unit_quad: SharedVertexBuffer::allocate_vertex(
+                device,
+                &unit_quad,
+                Some("GeometryCache/unit_quad"),
+            ),
+        }
+    }
+
+    /// A quad spanning `[0; 1]` in both the position and the texture coordinates, to be drawn as a triangle strip
+    ///
+    /// Combined with a projection matrix mapping `[0; 1]` to the whole target, it draws a texture over all of it.
+    pub fn unit_quad(&self) -> SharedVertexBuffer<PosTexVertex> {
+        self.unit_quad.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GeometryCache;
+    use crate::test_utils::request_device;
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn quads_are_reused() {
+        let (device, _queue) = request_device();
+        let cache = GeometryCache::new(&device);
+
+        let first = cache.unit_quad();
+        let second = cache.unit_quad();
+        let (first_buffer, first_offset, first_size) = first.as_buffer_ref().into_parts();
+        let (second_buffer, second_offset, second_size) = second.as_buffer_ref().into_parts();
+
+        // the same wgpu buffer, not a copy of the vertices
+        assert!(first_buffer == second_buffer);
+        assert_eq!(first_offset, second_offset);
+        assert_eq!(first_size, second_size);
+        assert_eq!(first.as_buffer_ref().count(), 4);
+    }
+}
@@ -0,0 +1,257 @@
+//! Gradient fills, for drawing backgrounds and UI elements without a texture.
+
+use anyhow::{Result, bail};
+use glam::{Mat4, Vec2, Vec4, vec3, vec4};
+use shin_primitives::color::FloatColor4;
+use shin_render_shader_types::{
+    buffer::VertexSource, uniforms::GradientUniformParams, vertices::PosVertex,
+};
+
+use crate::{
+    DrawPrimitive, RenderProgramWithArguments, RenderRequestBuilder, render_pass::RenderPass,
+};
+
+/// The maximum number of stops the gradient shader supports
+pub const MAX_GRADIENT_STOPS: usize = 8;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum GradientKind {
+    /// The color changes along the line from `start` to `end`, staying the same across it.
+    ///
+    /// If `start` and `end` are the same point, the whole fill is the color of the last stop.
+    Linear { start: Vec2, end: Vec2 },
+    /// The color changes with the distance from `center`, reaching the last stop at `radius`.
+    ///
+    /// If the `radius` is not positive, the whole fill is the color of the last stop.
+    Radial { center: Vec2, radius: f32 },
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct GradientStop {
+    /// Position of the stop along the gradient, in `[0; 1]`
+    pub offset: f32,
+    /// The color is not premultiplied
+    pub color: FloatColor4,
+}
+
+/// A gradient fill, drawn by the [`RenderProgramWithArguments::Gradient`] program.
+///
+/// The geometry is specified in the same space as the vertices, before the transform is applied.
+/// The stops are interpolated in linear space and with premultiplied alpha, and the output is premultiplied.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gradient {
+    kind: GradientKind,
+    stops: Vec<GradientStop>,
+}
+
+fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+impl Gradient {
+    /// The stops are sorted by their offsets. There has to be at least one and at most [`MAX_GRADIENT_STOPS`] of them.
+    pub fn new(kind: GradientKind, stops: impl IntoIterator<Item = GradientStop>) -> Result<Self> {
+        let mut stops = stops
+            .into_iter()
+            .map(|stop| GradientStop {
+                offset: stop.offset.clamp(0.0, 1.0),
+                ..stop
+            })
+            .collect::<Vec<_>>();
+        stops.sort_by(|left, right| left.offset.total_cmp(&right.offset));
+
+        if !(1..=MAX_GRADIENT_STOPS).contains(&stops.len()) {
+            bail!(
+                "A gradient must have between 1 and {} stops, got {}",
+                MAX_GRADIENT_STOPS,
+                stops.len()
+            );
+        }
+
+        Ok(Self { kind, stops })
+    }
+
+    /// A linear gradient between two colors
+    pub fn linear(start: Vec2, end: Vec2, from: FloatColor4, to: FloatColor4) -> Self {
+        Self {
+            kind: GradientKind::Linear { start, end },
+            stops: vec![
+                GradientStop {
+                    offset: 0.0,
+                    color: from,
+                },
+                GradientStop {
+                    offset: 1.0,
+                    color: to,
+                },
+            ],
+        }
+    }
+
+    pub fn kind(&self) -> GradientKind {
+        self.kind
+    }
+
+    pub fn stops(&self) -> &[GradientStop] {
+        &self.stops
+    }
+
+    /// Apply `f` to the colors of all the stops, for example to tint the gradient with the layer color
+    pub fn map_colors(&self, f: impl Fn(FloatColor4) -> FloatColor4) -> Self {
+        Self {
+            kind: self.kind,
+            stops: self
+                .stops
+                .iter()
+                .map(|stop| GradientStop {
+                    color: f(stop.color),
+                    ..*stop
+                })
+                .collect(),
+        }
+    }
+
+    /// Whether all the stops are opaque, so the gradient can be drawn in the opaque pass
+    pub fn is_opaque(&self) -> bool {
+        self.stops.iter().all(|stop| stop.color.a >= 1.0)
+    }
+
+    /// The premultiplied linear color of a stop, which is what gets interpolated
+    fn stop_color(stop: &GradientStop) -> Vec4 {
+        let FloatColor4 { r, g, b, a } = stop.color;
+        let linear = vec3(srgb_to_linear(r), srgb_to_linear(g), srgb_to_linear(b));
+        (linear * a).extend(a)
+    }
+
+    pub fn uniform_params(&self, transform: Mat4) -> GradientUniformParams {
+        let mut colors = [Vec4::ZERO; MAX_GRADIENT_STOPS];
+        let mut offsets = [0.0; MAX_GRADIENT_STOPS];
+        for (i, stop) in self.stops.iter().enumerate() {
+            colors[i] = Self::stop_color(stop);
+            offsets[i] = stop.offset;
+        }
+
+        let (kind, geometry) = match self.kind {
+            GradientKind::Linear { start, end } => (0, vec4(start.x, start.y, end.x, end.y)),
+            GradientKind::Radial { center, radius } => (1, vec4(center.x, center.y, radius, 0.0)),
+        };
+
+        GradientUniformParams {
+            transform,
+            colors,
+            offsets: [
+                Vec4::from_slice(&offsets[0..4]),
+                Vec4::from_slice(&offsets[4..8]),
+            ],
+            geometry,
+            kind,
+            stop_count: self.stops.len() as u32,
+        }
+    }
+
+    /// Fill `rect` (x, y, width, height, in the space of `transform`) with the gradient.
+    ///
+    /// The output is premultiplied, so `builder` should be blending accordingly.
+    pub fn fill_rect(
+        &self,
+        pass: &mut RenderPass,
+        builder: RenderRequestBuilder,
+        rect: Vec4,
+        transform: Mat4,
+    ) {
+        let left = rect.x;
+        let right = rect.x + rect.z;
+        let top = rect.y;
+        let bottom = rect.y + rect.w;
+
+        let vertices = &[
+            PosVertex {
+                position: vec3(left, top, 0.0),
+            },
+            PosVertex {
+                position: vec3(right, top, 0.0),
+            },
+            PosVertex {
+                position: vec3(left, bottom, 0.0),
+            },
+            PosVertex {
+                position: vec3(right, bottom, 0.0),
+            },
+        ];
+
+        pass.run(builder.build(
+            RenderProgramWithArguments::Gradient {
+                vertices: VertexSource::VertexData { vertices },
+                gradient: self,
+                transform,
+            },
+            DrawPrimitive::TrianglesStrip,
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{Mat4, Vec2, vec2};
+    use shin_primitives::color::FloatColor4;
+
+    use super::{Gradient, GradientKind, GradientStop, MAX_GRADIENT_STOPS};
+
+    #[test]
+    fn stop_count_is_checked() {
+        let stops = |count: usize| {
+            (0..count).map(|i| GradientStop {
+                offset: i as f32 / count as f32,
+                color: FloatColor4::WHITE,
+            })
+        };
+        let kind = GradientKind::Radial {
+            center: Vec2::ZERO,
+            radius: 1.0,
+        };
+
+        assert!(Gradient::new(kind, stops(0)).is_err());
+        assert!(Gradient::new(kind, stops(MAX_GRADIENT_STOPS)).is_ok());
+        assert!(Gradient::new(kind, stops(MAX_GRADIENT_STOPS + 1)).is_err());
+    }
+
+    #[test]
+    fn stops_are_sorted() {
+        let gradient = Gradient::new(
+            GradientKind::Radial {
+                center: Vec2::ZERO,
+                radius: 10.0,
+            },
+            [1.0, 0.0, 0.5].map(|offset| GradientStop {
+                offset,
+                color: FloatColor4::WHITE,
+            }),
+        )
+        .unwrap();
+
+        let offsets = gradient.stops().iter().map(|stop| stop.offset);
+        assert_eq!(offsets.collect::<Vec<_>>(), [0.0, 0.5, 1.0]);
+    }
+
+    #[test]
+    fn uniform_params_pack_the_stops() {
+        let gradient = Gradient::linear(
+            Vec2::ZERO,
+            vec2(1.0, 0.0),
+            FloatColor4::WHITE,
+            FloatColor4::from_rgba(0.5, 0.5, 0.5, 0.5),
+        );
+        let params = gradient.uniform_params(Mat4::IDENTITY);
+
+        assert_eq!(params.stop_count, 2);
+        assert_eq!(params.kind, 0);
+        assert_eq!(params.offsets[0].to_array(), [0.0, 1.0, 0.0, 0.0]);
+        // the colors are converted to linear space and premultiplied
+        assert!((params.colors[1].x - 0.2140 * 0.5).abs() < 1e-3);
+        assert_eq!(params.colors[1].w, 0.5);
+    }
+}
@@ -5,16 +5,21 @@
 // here we create an abstraction over wgpu which makes it look more like shin's render abstraction over nvn.
 // an important departure is not using global variables, but making all the arguments explicit (helped by a builder pattern with typestates (maybe))
 
-mod depth_stencil;
+pub mod depth_stencil;
 pub mod dynamic_buffer;
+pub mod geometry_cache;
 pub mod gpu_texture;
+pub mod gradient;
 pub mod init;
 pub mod pipelines;
 pub mod quad_vertices;
+pub mod readback;
 pub mod render_pass;
 pub mod render_texture;
 pub mod resize;
 pub mod resizeable_texture;
+#[cfg(test)]
+mod test_utils;
 
 use enum_iterator::Sequence;
 use glam::{Mat4, Vec2, Vec3, Vec4, vec3, vec4};
@@ -27,6 +32,8 @@ use shin_render_shader_types::{
 pub use shin_render_shaders as shaders;
 use shin_render_shaders::ShaderName;
 
+use crate::gradient::Gradient;
+
 pub const TEXTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
 pub const DEPTH_STENCIL_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth24PlusStencil8;
 
@@ -181,6 +188,9 @@ pub enum RenderProgramWithArguments<'a> {
         transform: Mat4,
         color_multiplier: FloatColor4,
         fragment_shader_param: Vec4,
+        // distance between the taps of the small box blur done while sampling, in texture coordinates
+        // zero to sample the texture just once
+        blur_step: Vec2,
     },
     Mask {
         fragment_shader: LayerFragmentShader,
@@ -193,7 +203,16 @@ pub enum RenderProgramWithArguments<'a> {
         minmax: Vec2,
     },
 
-    Dissolve {},
+    Dissolve {
+        vertices: VertexSource<'a, PosTexVertex>,
+        texture: TextureSource<'a>,
+        transform: Mat4,
+        // texture size, in pixels
+        texture_size: Vec2,
+        // fragments with noise values below the threshold are made fully transparent
+        // 0 leaves the texture as-is, 1 dissolves it completely
+        threshold: f32,
+    },
     TapEffect {},
     Movie {
         vertices: VertexSource<'a, PosTexVertex>,
@@ -234,10 +253,36 @@ pub enum RenderProgramWithArguments<'a> {
     },
 
     Mosaic {},
-    Blur {},
+    Blur {
+        vertices: VertexSource<'a, PosTexVertex>,
+        texture: TextureSource<'a>,
+        transform: Mat4,
+        // distance between the taps, in texture coordinates
+        step: Vec2,
+        // number of taps on each side of the center
+        taps: u32,
+    },
     ZoomBlur {},
-    Raster {},
-    Ripple {},
+    Raster {
+        vertices: VertexSource<'a, PosTexVertex>,
+        texture: TextureSource<'a>,
+        transform: Mat4,
+        // (amplitude, angular frequency, phase) of the row displacement, in texture coordinates
+        horizontal: Vec3,
+        // (amplitude, angular frequency, phase) of the column displacement, in texture coordinates
+        vertical: Vec3,
+    },
+    Ripple {
+        vertices: VertexSource<'a, PosTexVertex>,
+        texture: TextureSource<'a>,
+        transform: Mat4,
+        // ripple center, in texture coordinates
+        center: Vec2,
+        // texture size, in pixels
+        texture_size: Vec2,
+        // (amplitude, angular frequency, phase) of the radial displacement, in pixels
+        wave: Vec3,
+    },
     Breakup {},
 
     Charicon0 {},
@@ -245,6 +290,36 @@ pub enum RenderProgramWithArguments<'a> {
     Charicon2 {},
     Charicon3 {},
     Test {},
+
+    // not present in the original engine
+    PostProcess {
+        vertices: VertexSource<'a, PosTexVertex>,
+        texture: TextureSource<'a>,
+        transform: Mat4,
+        // rows of the matrix applied to the colors in linear space, before the gamma adjustment
+        color_matrix: [Vec4; 3],
+        // the colors are raised to the power of 1 / gamma, the values not above 0 are clamped to 0.01
+        gamma: f32,
+        // the colors are multiplied by the brightness after the gamma adjustment
+        brightness: f32,
+        // the edges are faded to the vignette color in linear space, starting at the radius (1 being the distance to the corners)
+        vignette_radius: f32,
+        // distance over which the vignette ramps up to the full strength
+        vignette_softness: f32,
+        vignette_strength: f32,
+        // linear color
+        vignette_color: Vec3,
+    },
+    Fxaa {
+        vertices: VertexSource<'a, PosTexVertex>,
+        texture: TextureSource<'a>,
+        transform: Mat4,
+    },
+    Gradient {
+        vertices: VertexSource<'a, PosVertex>,
+        gradient: &'a Gradient,
+        transform: Mat4,
+    },
 }
 
 impl RenderProgramWithArguments<'_> {
@@ -260,6 +335,13 @@ impl RenderProgramWithArguments<'_> {
             RenderProgramWithArguments::Movie { .. } => ShaderName::Movie,
             RenderProgramWithArguments::WiperDefault { .. } => ShaderName::WiperDefault,
             RenderProgramWithArguments::WiperMask { .. } => ShaderName::WiperMask,
+            RenderProgramWithArguments::Raster { .. } => ShaderName::Raster,
+            RenderProgramWithArguments::Ripple { .. } => ShaderName::Ripple,
+            RenderProgramWithArguments::Dissolve { .. } => ShaderName::Dissolve,
+            RenderProgramWithArguments::Blur { .. } => ShaderName::Blur,
+            RenderProgramWithArguments::PostProcess { .. } => ShaderName::PostProcess,
+            RenderProgramWithArguments::Fxaa { .. } => ShaderName::Fxaa,
+            RenderProgramWithArguments::Gradient { .. } => ShaderName::Gradient,
 
             ref program => todo!("Implement shader for {:?}", program),
         }
@@ -395,6 +477,21 @@ impl DepthStencilState {
         Self { depth, stencil }
     }
 
+    /// Increments the stencil value of every pixel drawn to, counting the overdraw, see [`RenderPass::set_overdraw_counting`](render_pass::RenderPass::set_overdraw_counting)
+    pub fn overdraw_counting() -> Self {
+        Self {
+            depth: DepthState::default(),
+            stencil: StencilState {
+                pipeline: StencilPipelineState {
+                    function: StencilFunction::Always,
+                    pass_operation: StencilOperation::Increment,
+                    ..Default::default()
+                },
+                stencil_reference: 0,
+            },
+        }
+    }
+
     pub fn into_pipeline_parts(self) -> (DepthStencilPipelineState, u8) {
         (
             DepthStencilPipelineState {
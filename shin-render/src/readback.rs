@@ -0,0 +1,210 @@
+//! Copying textures back from the GPU, for screenshots and tests.
+
+use anyhow::{Context, bail};
+use image::RgbaImage;
+
+/// Bytes in a row of the copy buffer, which has to be padded to [`wgpu::COPY_BYTES_PER_ROW_ALIGNMENT`]
+fn padded_bytes_per_row(width: u32) -> u32 {
+    let unpadded = width * 4;
+    unpadded.div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT) * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT
+}
+
+/// Convert the padded rows of `format` texels to an RGBA8 image
+///
+/// The sRGB formats are not decoded: the bytes stored are already sRGB-encoded, which is what the image expects.
+/// The same goes for the [`TEXTURE_FORMAT`](crate::TEXTURE_FORMAT), where the engine keeps the colors gamma-encoded all the way.
+fn texels_to_rgba(
+    format: wgpu::TextureFormat,
+    data: &[u8],
+    width: u32,
+    height: u32,
+    padded_bytes_per_row: u32,
+) -> anyhow::Result<RgbaImage> {
+    let swap_red_blue = match format {
+        wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => false,
+        wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => true,
+        format => bail!("Reading back {:?} textures is not supported", format),
+    };
+
+    let row_bytes = width as usize * 4;
+    let mut pixels = Vec::with_capacity(row_bytes * height as usize);
+    for row in data
+        .chunks(padded_bytes_per_row as usize)
+        .take(height as usize)
+    {
+        pixels.extend_from_slice(&row[..row_bytes]);
+    }
+
+    if swap_red_blue {
+        for texel in pixels.chunks_exact_mut(4) {
+            texel.swap(0, 2);
+        }
+    }
+
+    RgbaImage::from_raw(width, height, pixels).context("Texture data is too short")
+}
+
+/// Copy the contents of `texture` to the CPU
+///
+/// The copy is submitted right away, but the returned future only resolves after the GPU is done with it, which is noticed when the device is polled.
+/// This happens on the following submits, or can be forced with [`wgpu::Device::poll`].
+pub fn read_texture_to_cpu(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+) -> impl Future<Output = anyhow::Result<RgbaImage>> + use<> {
+    let format = texture.format();
+    let width = texture.width();
+    let height = texture.height();
+    let padded_bytes_per_row = padded_bytes_per_row(width);
+
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("readback_buffer"),
+        size: padded_bytes_per_row as u64 * height as u64,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("readback_encoder"),
+    });
+    encoder.copy_texture_to_buffer(
+        wgpu::TexelCopyTextureInfo {
+            texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        wgpu::TexelCopyBufferInfo {
+            buffer: &buffer,
+            layout: wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_bytes_per_row),
+                rows_per_image: Some(height),
+            },
+        },
+        wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+    );
+    queue.submit([encoder.finish()]);
+
+    let (sender, receiver) = oneshot::channel();
+    buffer
+        .slice(..)
+        .map_async(wgpu::MapMode::Read, move |result| {
+            // the receiver might have been dropped if nobody is interested in the result anymore
+            let _ = sender.send(result);
+        });
+
+    async move {
+        receiver
+            .await
+            .context("Texture readback was cancelled")?
+            .context("Mapping the readback buffer")?;
+
+        let image = texels_to_rgba(
+            format,
+            &buffer.slice(..).get_mapped_range(),
+            width,
+            height,
+            padded_bytes_per_row,
+        );
+        buffer.unmap();
+
+        image
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dpi::PhysicalSize;
+
+    use super::{padded_bytes_per_row, texels_to_rgba};
+    use crate::{
+        render_texture::RenderTexture,
+        resize::{SurfaceResizeSource, ViewportParams},
+        test_utils::{now_or_never, request_device},
+    };
+
+    #[test]
+    fn padding_is_removed() {
+        assert_eq!(padded_bytes_per_row(1), 256);
+        assert_eq!(padded_bytes_per_row(64), 256);
+        assert_eq!(padded_bytes_per_row(65), 512);
+
+        let mut data = vec![0xee; 256 * 2];
+        data[0..8].copy_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8]);
+        data[256..264].copy_from_slice(&[9, 10, 11, 12, 13, 14, 15, 16]);
+
+        let image = texels_to_rgba(wgpu::TextureFormat::Rgba8Unorm, &data, 2, 2, 256).unwrap();
+        assert_eq!(image.as_raw(), &(1..=16).collect::<Vec<u8>>());
+    }
+
+    #[test]
+    fn formats_are_converted() {
+        let data = [10, 20, 30, 40];
+
+        for (format, expected) in [
+            (wgpu::TextureFormat::Rgba8Unorm, [10, 20, 30, 40]),
+            // the stored sRGB bytes are what the image wants
+            (wgpu::TextureFormat::Rgba8UnormSrgb, [10, 20, 30, 40]),
+            (wgpu::TextureFormat::Bgra8Unorm, [30, 20, 10, 40]),
+            (wgpu::TextureFormat::Bgra8UnormSrgb, [30, 20, 10, 40]),
+        ] {
+            let image = texels_to_rgba(format, &data, 1, 1, 4).unwrap();
+            assert_eq!(image.as_raw(), &expected, "{:?}", format);
+        }
+
+        assert!(texels_to_rgba(wgpu::TextureFormat::R8Unorm, &data, 1, 1, 4).is_err());
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn render_texture_read_back() {
+        let (device, queue) = request_device();
+
+        // a narrow texture, so the rows have to be padded
+        let resize_source = SurfaceResizeSource::new(ViewportParams::both(PhysicalSize::new(5, 3)));
+        let mut texture = RenderTexture::new(
+            device.clone(),
+            resize_source.canvas_handle(),
+            "readback_test".to_string(),
+        );
+
+        let mut encoder = device.create_command_encoder(&Default::default());
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: None,
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: texture.as_texture_target().view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color {
+                        r: 1.0,
+                        g: 0.0,
+                        b: 0.2,
+                        a: 0.6,
+                    }),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        queue.submit([encoder.finish()]);
+
+        let readback = texture.read_to_cpu(&device, &queue);
+        device.poll(wgpu::Maintain::Wait);
+        let image = now_or_never(readback)
+            .expect("The readback is not done after waiting for the device")
+            .unwrap();
+
+        assert_eq!(image.dimensions(), (5, 3));
+        for pixel in image.pixels() {
+            assert_eq!(pixel.0, [255, 0, 51, 153]);
+        }
+    }
+}
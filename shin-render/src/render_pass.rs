@@ -1,19 +1,23 @@
-use glam::{vec3, vec4};
+use glam::{Vec2, vec2, vec3, vec4};
 use shin_primitives::color::{FloatColor4, UnormColor};
 use shin_render_shader_types::{
     buffer::VertexSource,
     texture::{DepthStencilTarget, TextureSamplerStore, TextureTarget, TextureTargetKind},
     uniforms::{
-        ClearUniformParams, FillUniformParams, FontBorderUniformParams, FontUniformParams,
-        LayerUniformParams, MaskUniformParams, MovieUniformParams, SpriteUniformParams,
-        WiperDefaultUniformParams, WiperMaskUniformParams,
+        BlurUniformParams, ClearUniformParams, DissolveUniformParams, FillUniformParams,
+        FontBorderUniformParams, FontUniformParams, FxaaUniformParams, LayerUniformParams,
+        MaskUniformParams, MovieUniformParams, PostProcessUniformParams, RasterUniformParams,
+        RippleUniformParams, SpriteUniformParams, WiperDefaultUniformParams,
+        WiperMaskUniformParams,
     },
     vertices::PosVertex,
 };
 use shin_render_shaders::{
-    Clear, ClearBindings, Fill, FillBindings, Font, FontBindings, FontBorder, FontBorderBindings,
-    Layer, LayerBindings, Mask, MaskBindings, Movie, MovieBindings, Shader, Sprite, SpriteBindings,
-    WiperDefault, WiperDefaultBindings, WiperMask, WiperMaskBindings,
+    Blur, BlurBindings, Clear, ClearBindings, Dissolve, DissolveBindings, Fill, FillBindings, Font,
+    FontBindings, FontBorder, FontBorderBindings, Fxaa, FxaaBindings, Gradient, GradientBindings,
+    Layer, LayerBindings, Mask, MaskBindings, Movie, MovieBindings, PostProcess,
+    PostProcessBindings, Raster, RasterBindings, Ripple, RippleBindings, Shader, Sprite,
+    SpriteBindings, WiperDefault, WiperDefaultBindings, WiperMask, WiperMaskBindings,
 };
 
 use crate::{
@@ -21,6 +25,7 @@ use crate::{
     RenderProgramWithArguments, RenderRequest, RenderRequestBuilder, StencilFunction,
     StencilOperation, StencilPipelineState, StencilState,
     dynamic_buffer::DynamicBuffer,
+    geometry_cache::GeometryCache,
     pipelines::{PipelineStorage, PipelineStorageKey},
 };
 
@@ -29,7 +34,10 @@ pub struct RenderPass<'pipelines, 'dynbuffer, 'sampler, 'device, 'encoder> {
     dynamic_buffer: &'dynbuffer mut DynamicBuffer,
     sampler_store: &'sampler TextureSamplerStore,
     target_kind: TextureTargetKind,
+    /// Size of the area the clip space is mapped to, in pixels
+    pixel_size: Vec2,
     has_depth_stencil: bool,
+    overdraw_counting: bool,
     device: &'device wgpu::Device,
     pass: wgpu::RenderPass<'encoder>,
 }
@@ -80,21 +88,31 @@ impl<'pipelines, 'dynbuffer, 'sampler, 'device, 'encoder>
             occlusion_query_set: None,
         });
 
-        if let Some((x, y, width, height)) = viewport {
-            pass.set_viewport(x, y, width, height, 0.0, 1.0);
-        }
+        let pixel_size = match viewport {
+            Some((x, y, width, height)) => {
+                pass.set_viewport(x, y, width, height, 0.0, 1.0);
+                vec2(width, height)
+            }
+            None => target_color.size.as_vec2(),
+        };
 
         Self {
             pipeline_storage,
             dynamic_buffer,
             sampler_store,
             target_kind: target_color.kind,
+            pixel_size,
             has_depth_stencil: target_depth_stencil.is_some(),
+            overdraw_counting: false,
             device,
             pass,
         }
     }
 
+    pub fn geometry_cache(&self) -> &GeometryCache {
+        self.dynamic_buffer.geometry_cache()
+    }
+
     pub fn push_debug(&mut self, label: &str) {
         self.pass.push_debug_group(label)
     }
@@ -103,6 +121,19 @@ impl<'pipelines, 'dynbuffer, 'sampler, 'device, 'encoder>
         self.pass.pop_debug_group()
     }
 
+    /// Size of the area the clip space is mapped to (the viewport or the whole target), in pixels
+    pub fn pixel_size(&self) -> Vec2 {
+        self.pixel_size
+    }
+
+    /// Make the following draws count how many times each pixel is drawn to in the stencil buffer, instead of writing the colors
+    ///
+    /// This is a debug view, it breaks the masking done with the stencil buffer. Does nothing without a depth stencil target.
+    /// The [`clear`](Self::clear)s are skipped while counting.
+    pub fn set_overdraw_counting(&mut self, enabled: bool) {
+        self.overdraw_counting = enabled;
+    }
+
     fn run_impl<S: Shader>(
         &mut self,
         key: PipelineStorageKey,
@@ -131,6 +162,15 @@ impl<'pipelines, 'dynbuffer, 'sampler, 'device, 'encoder>
             program,
         } = request;
 
+        let (depth_stencil, color_blend_type) = if self.overdraw_counting {
+            (
+                DepthStencilState::overdraw_counting(),
+                ColorBlendType::NoColor,
+            )
+        } else {
+            (depth_stencil, color_blend_type)
+        };
+
         let (depth_stencil, stencil_reference) = depth_stencil.into_pipeline_parts();
 
         let key = PipelineStorageKey {
@@ -218,6 +258,7 @@ impl<'pipelines, 'dynbuffer, 'sampler, 'device, 'encoder>
                 transform,
                 color_multiplier,
                 fragment_shader_param,
+                blur_step,
             } => self.run_impl::<Layer>(
                 key,
                 LayerBindings {
@@ -225,6 +266,7 @@ impl<'pipelines, 'dynbuffer, 'sampler, 'device, 'encoder>
                         transform,
                         color: color_multiplier,
                         fragment_param: fragment_shader_param,
+                        blur: vec4(blur_step.x, blur_step.y, 0.0, 0.0),
                         output_type: output_kind as u32,
                         fragment_operation: fragment_shader as u32,
                     },
@@ -316,12 +358,144 @@ impl<'pipelines, 'dynbuffer, 'sampler, 'device, 'encoder>
                 },
                 vertices,
             ),
+
+            RenderProgramWithArguments::Raster {
+                vertices,
+                texture,
+                transform,
+                horizontal,
+                vertical,
+            } => self.run_impl::<Raster>(
+                key,
+                RasterBindings {
+                    params: &RasterUniformParams {
+                        transform,
+                        horizontal: horizontal.extend(0.0),
+                        vertical: vertical.extend(0.0),
+                    },
+                    texture,
+                },
+                vertices,
+            ),
+            RenderProgramWithArguments::Ripple {
+                vertices,
+                texture,
+                transform,
+                center,
+                texture_size,
+                wave,
+            } => self.run_impl::<Ripple>(
+                key,
+                RippleBindings {
+                    params: &RippleUniformParams {
+                        transform,
+                        center: center.extend(texture_size.x).extend(texture_size.y),
+                        wave: wave.extend(0.0),
+                    },
+                    texture,
+                },
+                vertices,
+            ),
+            RenderProgramWithArguments::Dissolve {
+                vertices,
+                texture,
+                transform,
+                texture_size,
+                threshold,
+            } => self.run_impl::<Dissolve>(
+                key,
+                DissolveBindings {
+                    params: &DissolveUniformParams {
+                        transform,
+                        dissolve: vec4(threshold, 0.0, texture_size.x, texture_size.y),
+                    },
+                    texture,
+                },
+                vertices,
+            ),
+            RenderProgramWithArguments::Blur {
+                vertices,
+                texture,
+                transform,
+                step,
+                taps,
+            } => self.run_impl::<Blur>(
+                key,
+                BlurBindings {
+                    params: &BlurUniformParams {
+                        transform,
+                        blur: vec4(step.x, step.y, taps as f32, 0.0),
+                    },
+                    texture,
+                },
+                vertices,
+            ),
+            RenderProgramWithArguments::PostProcess {
+                vertices,
+                texture,
+                transform,
+                color_matrix,
+                gamma,
+                brightness,
+                vignette_radius,
+                vignette_softness,
+                vignette_strength,
+                vignette_color,
+            } => self.run_impl::<PostProcess>(
+                key,
+                PostProcessBindings {
+                    params: &PostProcessUniformParams {
+                        transform,
+                        color_matrix,
+                        // a non-positive gamma would blow the exponent up to infinity, turning the whites into NaNs
+                        color_adjust: vec4(gamma.max(0.01).recip(), brightness, 0.0, 0.0),
+                        vignette: vec4(
+                            vignette_radius,
+                            // smoothstep is undefined for equal edges
+                            vignette_softness.max(1e-4),
+                            vignette_strength,
+                            0.0,
+                        ),
+                        vignette_color: vignette_color.extend(0.0),
+                    },
+                    texture,
+                },
+                vertices,
+            ),
+            RenderProgramWithArguments::Fxaa {
+                vertices,
+                texture,
+                transform,
+            } => self.run_impl::<Fxaa>(
+                key,
+                FxaaBindings {
+                    params: &FxaaUniformParams { transform },
+                    texture,
+                },
+                vertices,
+            ),
+            RenderProgramWithArguments::Gradient {
+                vertices,
+                gradient,
+                transform,
+            } => self.run_impl::<Gradient>(
+                key,
+                GradientBindings {
+                    params: &gradient.uniform_params(transform),
+                },
+                vertices,
+            ),
             _ => todo!(),
         }
     }
 
     #[tracing::instrument(skip_all)]
     pub fn clear(&mut self, color: Option<UnormColor>, stencil: Option<u8>, depth: Option<f32>) {
+        // not a draw of the scene, and clearing the stencil would throw away the counts
+        if self.overdraw_counting {
+            return;
+        }
+
         let z = match depth {
             Some(z) => z + z - 1.0,
             None => 1.0,
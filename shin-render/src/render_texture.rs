@@ -1,3 +1,6 @@
+use dpi::PhysicalSize;
+use glam::uvec2;
+use image::RgbaImage;
 use shin_render_shader_types::{
     RenderClone, RenderCloneCtx,
     texture::{TextureSampler, TextureSource, TextureTarget, TextureTargetKind},
@@ -5,6 +8,7 @@ use shin_render_shader_types::{
 
 use crate::{
     TEXTURE_FORMAT,
+    readback::read_texture_to_cpu,
     resize::{CanvasSize, ResizeHandle},
     resizeable_texture::ResizeableTexture,
 };
@@ -55,6 +59,11 @@ impl RenderTexture {
         }
     }
 
+    /// Render at `scale` times the canvas resolution, the texture is resized the next time it is used as a target
+    pub fn set_scale(&mut self, scale: f32) {
+        self.inner_texture.set_scale(scale);
+    }
+
     pub fn as_texture_source(&self) -> TextureSource {
         TextureSource {
             view: self.inner_texture.get_view(),
@@ -63,18 +72,37 @@ impl RenderTexture {
     }
 
     pub fn as_texture_target(&mut self) -> TextureTarget {
+        self.inner_texture.resize_and_get_view();
+        let texture = self.inner_texture.get_texture();
+
         TextureTarget {
             kind: TextureTargetKind::RenderTexture,
-            view: self.inner_texture.resize_and_get_view(),
+            view: self.inner_texture.get_view(),
+            size: uvec2(texture.width(), texture.height()),
         }
     }
+
+    /// Copy the current contents of the texture to the CPU, see [`read_texture_to_cpu`]
+    pub fn read_to_cpu(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> impl Future<Output = anyhow::Result<RgbaImage>> + use<> {
+        read_texture_to_cpu(device, queue, self.inner_texture.get_texture())
+    }
 }
 
+/// Allocates a new texture of the same size and copies the contents into it with the `ctx` encoder
+///
+/// The clone is independent from the original: rendering into either of them doesn't affect the other.
+/// It still follows the same viewport (and keeps the scale), so both get resized (and cleared) on the same canvas resize.
 impl RenderClone for RenderTexture {
     fn render_clone(&self, ctx: &mut RenderCloneCtx) -> Self {
         let resize_handle = self.inner_texture.get_resize_handle();
 
-        let size = resize_handle.get_without_update().into();
+        // the size of the texture itself, the handle might have been rescaled since it was allocated
+        let texture = self.inner_texture.get_texture();
+        let size = PhysicalSize::new(texture.width(), texture.height());
 
         let new_texture = ResizeableTexture::new_with_size(
             ctx.device.clone(),
@@ -113,3 +141,78 @@ impl RenderClone for RenderTexture {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use dpi::PhysicalSize;
+    use shin_render_shader_types::{RenderClone, RenderCloneCtx};
+
+    use super::RenderTexture;
+    use crate::{
+        resize::{SurfaceResizeSource, ViewportParams},
+        test_utils::{now_or_never, request_device},
+    };
+
+    fn fill(device: &wgpu::Device, queue: &wgpu::Queue, texture: &mut RenderTexture, r: f64) {
+        let mut encoder = device.create_command_encoder(&Default::default());
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: None,
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: texture.as_texture_target().view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color {
+                        r,
+                        g: 0.0,
+                        b: 0.0,
+                        a: 1.0,
+                    }),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        queue.submit([encoder.finish()]);
+    }
+
+    fn red_channel(device: &wgpu::Device, queue: &wgpu::Queue, texture: &RenderTexture) -> u8 {
+        let readback = texture.read_to_cpu(device, queue);
+        device.poll(wgpu::Maintain::Wait);
+        let image = now_or_never(readback)
+            .expect("The readback is not done after waiting for the device")
+            .unwrap();
+
+        image.get_pixel(0, 0).0[0]
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn clones_are_independent() {
+        let (device, queue) = request_device();
+
+        let resize_source = SurfaceResizeSource::new(ViewportParams::both(PhysicalSize::new(4, 4)));
+        let mut original = RenderTexture::new(
+            device.clone(),
+            resize_source.canvas_handle(),
+            "clone_test".to_string(),
+        );
+        fill(&device, &queue, &mut original, 1.0);
+
+        let mut encoder = device.create_command_encoder(&Default::default());
+        let mut clone = original.render_clone(&mut RenderCloneCtx {
+            device: &device,
+            encoder: &mut encoder,
+        });
+        queue.submit([encoder.finish()]);
+
+        // the contents are copied
+        assert_eq!(red_channel(&device, &queue, &clone), 255);
+
+        // but not shared
+        fill(&device, &queue, &mut clone, 0.0);
+        assert_eq!(red_channel(&device, &queue, &clone), 0);
+        assert_eq!(red_channel(&device, &queue, &original), 255);
+    }
+}
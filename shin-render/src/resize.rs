@@ -125,6 +125,7 @@ impl SurfaceResizeSource {
     pub fn handle<Aspect: SizeAspect + Default>(&self) -> ResizeHandle<Aspect> {
         ResizeHandle {
             inner: self.inner.clone(),
+            scale: 1.0,
             last_known_size: Default::default(),
         }
     }
@@ -138,25 +139,47 @@ impl SurfaceResizeSource {
     }
 }
 
+/// Scales both dimensions of the `size`, keeping them at least 1 pixel
+fn scale_size(size: PhysicalSize<u32>, scale: f32) -> PhysicalSize<u32> {
+    if scale == 1.0 {
+        return size;
+    }
+
+    let scale = |v: u32| ((v as f32 * scale).round() as u32).max(1);
+    PhysicalSize::new(scale(size.width), scale(size.height))
+}
+
 #[derive(Debug, Clone)]
 pub struct ResizeHandle<Aspect> {
     inner: Arc<RwLock<ViewportParams>>,
+    /// Factor applied to the viewport sizes, for targets rendered at a different resolution
+    scale: f32,
     last_known_size: Aspect,
 }
 
 impl<Aspect: SizeAspect> ResizeHandle<Aspect> {
+    /// Set the factor applied to the viewport sizes. The change is picked up by the next [`Self::get`] or [`Self::update`].
+    pub fn set_scale(&mut self, scale: f32) {
+        self.scale = scale;
+    }
+
     pub fn get_without_update(&self) -> Aspect {
         self.last_known_size
     }
 
-    pub fn get(&mut self) -> Aspect {
+    fn get_scaled(&self) -> Aspect {
         let size = self.inner.read().unwrap().get::<Aspect>();
+        scale_size(size.into(), self.scale).into()
+    }
+
+    pub fn get(&mut self) -> Aspect {
+        let size = self.get_scaled();
         self.last_known_size = size;
         size
     }
 
     pub fn update(&mut self) -> Option<Aspect> {
-        let size = self.inner.read().unwrap().get::<Aspect>();
+        let size = self.get_scaled();
         if size != self.last_known_size {
             self.last_known_size = size;
             Some(size)
@@ -181,3 +204,60 @@ impl ResizeHandle<SurfaceSize> {
         (x, y, width, height)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use dpi::PhysicalSize;
+
+    use super::{CanvasSize, SurfaceResizeSource, SurfaceSize, ViewportParams};
+
+    #[test]
+    fn scaled_handle() {
+        let source = SurfaceResizeSource::new(ViewportParams::both(PhysicalSize::new(1920, 1080)));
+
+        let mut scene = source.canvas_handle();
+        scene.set_scale(0.5);
+        let mut output = source.surface_handle();
+
+        assert_eq!(
+            scene.get(),
+            CanvasSize {
+                width: 960,
+                height: 540
+            }
+        );
+        assert_eq!(
+            output.get(),
+            SurfaceSize {
+                width: 1920,
+                height: 1080
+            }
+        );
+
+        // changing the scale is picked up like a resize
+        scene.set_scale(1.0);
+        assert_eq!(
+            scene.update(),
+            Some(CanvasSize {
+                width: 1920,
+                height: 1080
+            })
+        );
+        assert_eq!(scene.update(), None);
+    }
+
+    #[test]
+    fn scaled_size_is_never_empty() {
+        let source = SurfaceResizeSource::new(ViewportParams::both(PhysicalSize::new(3, 1)));
+
+        let mut handle = source.canvas_handle();
+        handle.set_scale(0.1);
+        assert_eq!(
+            handle.get(),
+            CanvasSize {
+                width: 1,
+                height: 1
+            }
+        );
+    }
+}
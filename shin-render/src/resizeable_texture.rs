@@ -97,6 +97,11 @@ impl<Aspect: SizeAspect> ResizeableTexture<Aspect> {
         &self.texture.1
     }
 
+    /// Render the texture at `scale` times the size of the viewport. It gets resized on the next [`Self::resize_and_get_view`].
+    pub fn set_scale(&mut self, scale: f32) {
+        self.resize_handle.set_scale(scale);
+    }
+
     pub fn get_resize_handle(&self) -> ResizeHandle<Aspect> {
         self.resize_handle.clone()
     }
@@ -0,0 +1,26 @@
+//! Helpers for the tests that need a GPU.
+
+use std::{
+    pin::pin,
+    task::{Context, Poll, Waker},
+};
+
+/// wgpu resolves its futures on native without any waiting, as long as the device was polled
+pub fn now_or_never<F: Future>(future: F) -> Option<F::Output> {
+    match pin!(future).poll(&mut Context::from_waker(Waker::noop())) {
+        Poll::Ready(output) => Some(output),
+        Poll::Pending => None,
+    }
+}
+
+/// Panics when there is no GPU adapter, the tests calling it are `#[ignore]`d so that they're only run with `cargo test -- --ignored`
+pub fn request_device() -> (wgpu::Device, wgpu::Queue) {
+    let instance = wgpu::Instance::default();
+    let adapter = now_or_never(instance.request_adapter(&Default::default()))
+        .flatten()
+        .expect("No GPU adapter available");
+
+    now_or_never(adapter.request_device(&Default::default(), None))
+        .expect("Requesting a device is not immediate")
+        .unwrap()
+}
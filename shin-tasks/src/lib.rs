@@ -13,6 +13,13 @@ impl<T> AsyncTask<T> {
         self.task.detach()
     }
 
+    /// Cancel the task, so that its future is not polled anymore and gets dropped, along with everything it holds
+    ///
+    /// If the task is being polled at the moment, the future is dropped as soon as the poll returns. Cancelling a finished task drops its output.
+    pub fn cancel(self) {
+        drop(self.task)
+    }
+
     pub fn poll_naive(&mut self) -> Option<T> {
         // this is slightly inefficient as it will end up registering the waker, even though we don't need it
         // however, we would need to write our own task primitive if we want different behavior
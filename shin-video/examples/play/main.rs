@@ -1,11 +1,11 @@
 use std::{fs::File, time::Duration};
 
-use enum_map::{Enum, EnumMap, enum_map};
+use enum_map::{Enum, EnumMap};
 use glam::Mat4;
 use shin_audio::AudioManager;
 use shin_core::{primitives::update::FrameId, time::Ticks};
 use shin_input::{
-    Action, ActionState, RawInputState,
+    Action, ActionState, Binding, Bindings,
     inputs::{GamepadButton, KeyCode},
 };
 use shin_render::{RenderRequestBuilder, render_pass::RenderPass};
@@ -16,20 +16,27 @@ use shin_window::{AppContext, RenderContext, ShinApp};
 enum PlayAction {
     Exit,
     ToggleFullscreen,
+    TogglePause,
 }
 
 impl Action for PlayAction {
-    fn lower(
-        RawInputState {
-            mouse: _,
-            keyboard,
-            gamepads,
-        }: &RawInputState,
-    ) -> EnumMap<Self, bool> {
-        enum_map! {
-            PlayAction::Exit => keyboard.contains(&KeyCode::KeyQ) || keyboard.contains(&KeyCode::Escape) || gamepads.is_held(GamepadButton::Plus),
-            PlayAction::ToggleFullscreen => keyboard.contains(&KeyCode::F11),
-        }
+    fn default_bindings() -> Bindings<Self> {
+        use Binding::{Gamepad, Key};
+
+        Bindings::new()
+            .with(
+                PlayAction::Exit,
+                [
+                    Key(KeyCode::KeyQ),
+                    Key(KeyCode::Escape),
+                    Gamepad(GamepadButton::Plus),
+                ],
+            )
+            .with(PlayAction::ToggleFullscreen, [Key(KeyCode::F11)])
+            .with(
+                PlayAction::TogglePause,
+                [Key(KeyCode::Space), Gamepad(GamepadButton::A)],
+            )
     }
 }
 
@@ -52,7 +59,7 @@ impl ShinApp for PlayerExample {
         let file = File::open("op1.mp4").unwrap();
         let mp4 = Mp4::new(file).unwrap();
         let video_player =
-            VideoPlayerHandle::new(&context.wgpu.device, &audio_manager, mp4).unwrap();
+            VideoPlayerHandle::new(&context.wgpu.device, &audio_manager, mp4, None).unwrap();
 
         Ok(Self {
             audio_manager,
@@ -76,6 +83,9 @@ impl ShinApp for PlayerExample {
         if input[PlayAction::ToggleFullscreen].is_clicked {
             context.winit.toggle_fullscreen();
         }
+        if input[PlayAction::TogglePause].is_clicked {
+            self.video_player.set_paused(!self.video_player.is_paused());
+        }
 
         self.video_player.update(
             self.frame,
@@ -4,6 +4,8 @@ mod audio;
 mod h264_decoder;
 pub mod mp4;
 mod mp4_bitstream_converter;
+#[cfg(test)]
+mod test_utils;
 mod texture;
 mod timer;
 mod video_player;
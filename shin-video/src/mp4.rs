@@ -1,11 +1,14 @@
 use std::{
+    collections::HashMap,
     io::{Read, Seek, SeekFrom},
     sync::Arc,
 };
 
-use anyhow::{Context, Result, anyhow};
+use anyhow::{Context, Result, anyhow, bail};
 use mp4::{Mp4Sample, Mp4Track};
 use parking_lot::Mutex;
+use shin_core::time::Ticks;
+use tracing::warn;
 
 pub type Mp4ReadStream = std::fs::File;
 pub type Mp4Reader<S> = Arc<Mutex<mp4::Mp4Reader<S>>>;
@@ -64,6 +67,79 @@ impl<S: Read + Seek> Mp4TrackReader<S> {
 
         Ok(Some(sample))
     }
+
+    /// Continue reading from the given sample (numbered from 1, like in the mp4 file)
+    pub fn seek_to_sample(&mut self, sample_id: u32) {
+        self.samples_position = sample_id.clamp(1, self.samples_count + 1);
+    }
+
+    /// The sample presented at `time` (in the track timescale), or `None` if the track is over by then
+    pub fn sample_at_time(&self, time: u64) -> Option<u32> {
+        self.get_mp4_track_info(|track| {
+            sample_at_time(
+                track
+                    .trak
+                    .mdia
+                    .minf
+                    .stbl
+                    .stts
+                    .entries
+                    .iter()
+                    .map(|entry| (entry.sample_count, entry.sample_delta)),
+                time,
+            )
+        })
+    }
+
+    /// The closest sync sample (keyframe) at or before `sample_id`, where the decoding can start from
+    pub fn sync_sample_before(&self, sample_id: u32) -> u32 {
+        self.get_mp4_track_info(|track| {
+            sync_sample_before(
+                track
+                    .trak
+                    .mdia
+                    .minf
+                    .stbl
+                    .stss
+                    .as_ref()
+                    .map(|stss| stss.entries.as_slice()),
+                sample_id,
+            )
+        })
+    }
+}
+
+/// Finds the sample presented at `time`, given the `(sample_count, sample_delta)` runs of the time-to-sample table
+fn sample_at_time(durations: impl IntoIterator<Item = (u32, u32)>, time: u64) -> Option<u32> {
+    let mut run_start_sample = 1;
+    let mut run_start_time = 0;
+
+    for (sample_count, sample_delta) in durations {
+        let run_duration = sample_count as u64 * sample_delta as u64;
+        if time < run_start_time + run_duration {
+            let offset = (time - run_start_time) / sample_delta as u64;
+            return Some(run_start_sample + offset as u32);
+        }
+
+        run_start_sample += sample_count;
+        run_start_time += run_duration;
+    }
+
+    None
+}
+
+/// Without the sync sample table, every sample is a sync sample
+fn sync_sample_before(sync_samples: Option<&[u32]>, sample_id: u32) -> u32 {
+    let Some(sync_samples) = sync_samples else {
+        return sample_id;
+    };
+
+    sync_samples
+        .iter()
+        .copied()
+        .take_while(|&sync_sample| sync_sample <= sample_id)
+        .last()
+        .unwrap_or(1)
 }
 
 impl<S: Read + Seek> Clone for Mp4TrackReader<S> {
@@ -90,19 +166,166 @@ fn stream_len(stream: &mut impl Seek) -> Result<u64> {
     Ok(len)
 }
 
+/// Iterates over the boxes in `data`, giving their type and contents
+fn child_boxes(mut data: &[u8]) -> impl Iterator<Item = ([u8; 4], &[u8])> {
+    std::iter::from_fn(move || {
+        let header = data.get(..8)?;
+        let size = u32::from_be_bytes(header[..4].try_into().unwrap()) as u64;
+        let box_type: [u8; 4] = header[4..8].try_into().unwrap();
+
+        let (header_size, size) = match size {
+            // the box extends to the end
+            0 => (8, data.len() as u64),
+            1 => (16, u64::from_be_bytes(data.get(8..16)?.try_into().unwrap())),
+            size => (8, size),
+        };
+        if size < header_size || size > data.len() as u64 {
+            return None;
+        }
+
+        let (current, rest) = data.split_at(size as usize);
+        data = rest;
+        Some((box_type, &current[header_size as usize..]))
+    })
+}
+
+fn find_child_box<'a>(data: &'a [u8], box_type: &[u8; 4]) -> Option<&'a [u8]> {
+    child_boxes(data).find_map(|(ty, contents)| (&ty == box_type).then_some(contents))
+}
+
+/// Reads the extended language tags (from the `elng` boxes) of the tracks in the `moov` box contents, by track id
+fn extended_languages(moov: &[u8]) -> HashMap<u32, String> {
+    child_boxes(moov)
+        .filter(|(ty, _)| ty == b"trak")
+        .filter_map(|(_, trak)| {
+            let tkhd = find_child_box(trak, b"tkhd")?;
+            // the track id follows the creation and modification times, which are 64 bit in the version 1
+            let track_id_offset = if *tkhd.first()? == 1 { 20 } else { 12 };
+            let track_id = u32::from_be_bytes(
+                tkhd.get(track_id_offset..track_id_offset + 4)?
+                    .try_into()
+                    .unwrap(),
+            );
+
+            let elng = find_child_box(find_child_box(trak, b"mdia")?, b"elng")?;
+            // skip the version and the flags, the tag is null-terminated
+            let tag = elng.get(4..)?.split(|&b| b == 0).next()?;
+
+            Some((track_id, String::from_utf8_lossy(tag).into_owned()))
+        })
+        .collect()
+}
+
+/// Finds the `moov` box in the stream and reads the extended language tags in it, leaving the stream at its start
+///
+/// The `mp4` crate skips the `elng` boxes, so they are read separately.
+fn read_extended_languages(
+    reader: &mut (impl Read + Seek),
+    size: u64,
+) -> Result<HashMap<u32, String>> {
+    let mut position = 0;
+    let mut result = HashMap::new();
+
+    while position + 8 <= size {
+        reader.seek(SeekFrom::Start(position))?;
+        let mut header = [0; 8];
+        reader.read_exact(&mut header)?;
+        let (header_size, box_size) = match u32::from_be_bytes(header[..4].try_into().unwrap()) {
+            0 => (8, size - position),
+            1 => {
+                let mut large_size = [0; 8];
+                reader.read_exact(&mut large_size)?;
+                (16, u64::from_be_bytes(large_size))
+            }
+            box_size => (8, box_size as u64),
+        };
+        // the size is read from the file, a broken one would have us allocate way more than the file has
+        if box_size < header_size || box_size > size - position {
+            bail!("Invalid size of a top-level box at {}", position);
+        }
+
+        if &header[4..] == b"moov" {
+            let mut moov = vec![0; (box_size - header_size) as usize];
+            reader.read_exact(&mut moov)?;
+            result = extended_languages(&moov);
+            break;
+        }
+
+        position += box_size;
+    }
+
+    reader.seek(SeekFrom::Start(0))?;
+    Ok(result)
+}
+
+/// An audio track of a movie, see [`Mp4::audio_tracks`]
+#[derive(Debug, Clone)]
+pub struct AudioTrackInfo {
+    /// The position among the audio tracks, to select it with [`Mp4::select_audio_track`]
+    pub index: usize,
+    pub track_id: u32,
+    /// `None` if the codec is not supported by the `mp4` crate
+    pub codec: Option<mp4::MediaType>,
+    pub channel_count: u16,
+    /// The language tag from the `elng` box, or the ISO 639-2 code from the `mdhd` box if there is none
+    pub language: String,
+}
+
+/// A subtitle from the timed text (`tx3g`) track of a movie, see [`Mp4::subtitle_cues`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubtitleCue {
+    pub start: Ticks,
+    pub end: Ticks,
+    pub text: String,
+}
+
+/// Reads the text of a `tx3g` sample: a 16 bit length followed by the UTF-8 text, the style boxes after it are ignored
+fn tx3g_text(sample: &[u8]) -> Option<String> {
+    let length = u16::from_be_bytes(sample.get(..2)?.try_into().unwrap()) as usize;
+    let text = sample.get(2..2 + length)?;
+    Some(String::from_utf8_lossy(text).into_owned())
+}
+
+fn read_subtitle_cues<S: Read + Seek>(mut track: Mp4TrackReader<S>) -> Result<Vec<SubtitleCue>> {
+    let time_base = track.get_mp4_track_info(|track| track.timescale());
+    let to_ticks = |time: u64| Ticks::from_seconds((time as f64 / time_base as f64) as f32);
+
+    let mut cues = Vec::new();
+    while let Some(sample) = track.next_sample()? {
+        // the gaps between the cues are filled with empty samples
+        let Some(text) = tx3g_text(&sample.bytes).filter(|text| !text.is_empty()) else {
+            continue;
+        };
+
+        cues.push(SubtitleCue {
+            start: to_ticks(sample.start_time),
+            end: to_ticks(sample.start_time + sample.duration as u64),
+            text,
+        });
+    }
+
+    Ok(cues)
+}
+
 pub struct Mp4<S: Read + Seek> {
     pub reader: Mp4Reader<S>,
     pub video_track: Mp4TrackReader<S>,
+    /// The selected audio track, the first one by default
     pub audio_track: Option<Mp4TrackReader<S>>,
+    audio_track_ids: Vec<u32>,
+    extended_languages: HashMap<u32, String>,
+    subtitle_cues: Arc<[SubtitleCue]>,
 }
 
 impl<S: Read + Seek> Mp4<S> {
     pub fn new(mut reader: S) -> Result<Self> {
         let size = stream_len(&mut reader).context("Getting the length of a stream")?;
+        let extended_languages =
+            read_extended_languages(&mut reader, size).context("Reading the extended languages")?;
         let mp4 =
             mp4::Mp4Reader::read_header(reader, size).context("Reading the MP4 file headers")?;
 
-        let tracks = mp4
+        let mut tracks = mp4
             .tracks()
             .iter()
             .map(|(_, track)| -> Result<_> {
@@ -110,6 +333,8 @@ impl<S: Read + Seek> Mp4<S> {
                 Ok((track.track_id(), ty))
             })
             .collect::<Result<Vec<_>>>()?;
+        // the tracks are kept in a hash map, sort them to pick them in the file order
+        tracks.sort_by_key(|&(id, _)| id);
 
         let video_track_id = tracks
             .iter()
@@ -117,28 +342,100 @@ impl<S: Read + Seek> Mp4<S> {
             .map(|(id, _)| *id)
             .ok_or_else(|| anyhow::anyhow!("No video track found"))?;
 
-        let audio_track_id = tracks
+        let audio_track_ids = tracks
             .iter()
-            .find(|(_, ty)| *ty == mp4::TrackType::Audio)
-            .map(|(id, _)| *id);
+            .filter(|(_, ty)| *ty == mp4::TrackType::Audio)
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
 
         let reader = Arc::new(Mutex::new(mp4));
 
         let video_track = Mp4TrackReader::new(reader.clone(), video_track_id)
             .context("Opening mp4 video track")?;
-        let audio_track = audio_track_id
-            .map(|audio_track_id| {
+        let audio_track = audio_track_ids
+            .first()
+            .map(|&audio_track_id| {
                 Mp4TrackReader::new(reader.clone(), audio_track_id)
-                    .context("Opening mp4 video track")
+                    .context("Opening mp4 audio track")
             })
             .transpose()?;
 
+        // the movie still plays without its subtitles
+        let subtitle_cues = tracks
+            .iter()
+            .find(|(_, ty)| *ty == mp4::TrackType::Subtitle)
+            .and_then(|&(subtitle_track_id, _)| {
+                Mp4TrackReader::new(reader.clone(), subtitle_track_id)
+                    .and_then(read_subtitle_cues)
+                    .inspect_err(|e| warn!("Could not read the mp4 subtitle track: {:?}", e))
+                    .ok()
+            })
+            .unwrap_or_default();
+
         Ok(Self {
             reader,
             video_track,
             audio_track,
+            audio_track_ids,
+            extended_languages,
+            subtitle_cues: subtitle_cues.into(),
         })
     }
+
+    /// Lists the audio tracks, in the order of the file
+    pub fn audio_tracks(&self) -> Vec<AudioTrackInfo> {
+        let mp4 = self.reader.lock();
+
+        self.audio_track_ids
+            .iter()
+            .enumerate()
+            .map(|(index, &track_id)| {
+                let track = &mp4.tracks()[&track_id];
+
+                AudioTrackInfo {
+                    index,
+                    track_id,
+                    codec: track.media_type().ok(),
+                    channel_count: track
+                        .trak
+                        .mdia
+                        .minf
+                        .stbl
+                        .stsd
+                        .mp4a
+                        .as_ref()
+                        .map_or(0, |mp4a| mp4a.channelcount),
+                    language: self
+                        .extended_languages
+                        .get(&track_id)
+                        .cloned()
+                        .unwrap_or_else(|| track.language().to_string()),
+                }
+            })
+            .collect()
+    }
+
+    /// The subtitles of the first timed text track, in the order they are shown, or none if the movie has no such track
+    pub fn subtitle_cues(&self) -> &[SubtitleCue] {
+        &self.subtitle_cues
+    }
+
+    /// Plays the audio track at `index` among the [`Self::audio_tracks`] instead of the first one
+    pub fn select_audio_track(&mut self, index: usize) -> Result<()> {
+        let Some(&track_id) = self.audio_track_ids.get(index) else {
+            bail!(
+                "Audio track {} requested, but the movie has {} audio tracks",
+                index,
+                self.audio_track_ids.len()
+            );
+        };
+
+        self.audio_track = Some(
+            Mp4TrackReader::new(self.reader.clone(), track_id)
+                .context("Opening mp4 audio track")?,
+        );
+        Ok(())
+    }
 }
 
 impl<S: Read + Seek> Clone for Mp4<S> {
@@ -147,6 +444,184 @@ impl<S: Read + Seek> Clone for Mp4<S> {
             reader: self.reader.clone(),
             video_track: self.video_track.clone(),
             audio_track: self.audio_track.clone(),
+            audio_track_ids: self.audio_track_ids.clone(),
+            extended_languages: self.extended_languages.clone(),
+            subtitle_cues: self.subtitle_cues.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, fs::File, io::Cursor};
+
+    use shin_core::format::audio::AudioSource;
+
+    use super::{
+        Mp4, extended_languages, read_extended_languages, sample_at_time, sync_sample_before,
+    };
+    use crate::{audio::AacFrameSource, test_utils::encode_clip};
+
+    #[test]
+    fn samples_are_found_by_time() {
+        // 3 samples of 10 units, then 2 of 5
+        let durations = [(3, 10), (2, 5)];
+
+        assert_eq!(sample_at_time(durations, 0), Some(1));
+        assert_eq!(sample_at_time(durations, 9), Some(1));
+        assert_eq!(sample_at_time(durations, 10), Some(2));
+        assert_eq!(sample_at_time(durations, 29), Some(3));
+        assert_eq!(sample_at_time(durations, 30), Some(4));
+        assert_eq!(sample_at_time(durations, 39), Some(5));
+        // past the end
+        assert_eq!(sample_at_time(durations, 40), None);
+    }
+
+    #[test]
+    fn decoding_starts_from_a_keyframe() {
+        let sync_samples = [1, 10, 20];
+
+        assert_eq!(sync_sample_before(Some(&sync_samples), 1), 1);
+        assert_eq!(sync_sample_before(Some(&sync_samples), 9), 1);
+        assert_eq!(sync_sample_before(Some(&sync_samples), 10), 10);
+        assert_eq!(sync_sample_before(Some(&sync_samples), 25), 20);
+        assert_eq!(sync_sample_before(None, 25), 25);
+    }
+
+    fn mp4_box(box_type: &[u8; 4], contents: &[u8]) -> Vec<u8> {
+        let mut result = ((contents.len() + 8) as u32).to_be_bytes().to_vec();
+        result.extend(box_type);
+        result.extend(contents);
+        result
+    }
+
+    fn trak(track_id: u32, language: Option<&str>) -> Vec<u8> {
+        // version 0 tkhd: version and flags, creation and modification times, then the track id
+        let mut tkhd = vec![0; 12];
+        tkhd.extend(track_id.to_be_bytes());
+        tkhd.extend([0; 8]);
+
+        let mut mdia = mp4_box(b"mdhd", &[0; 24]);
+        if let Some(language) = language {
+            let mut elng = vec![0; 4];
+            elng.extend(language.as_bytes());
+            elng.push(0);
+            mdia.extend(mp4_box(b"elng", &elng));
         }
+
+        let mut contents = mp4_box(b"tkhd", &tkhd);
+        contents.extend(mp4_box(b"mdia", &mdia));
+        mp4_box(b"trak", &contents)
+    }
+
+    #[test]
+    fn extended_languages_are_read() {
+        let mut moov = mp4_box(b"mvhd", &[0; 100]);
+        moov.extend(trak(1, Some("en-US")));
+        moov.extend(trak(2, None));
+        moov.extend(trak(3, Some("ja")));
+
+        assert_eq!(
+            extended_languages(&moov),
+            HashMap::from([(1, "en-US".to_string()), (3, "ja".to_string())])
+        );
+    }
+
+    #[test]
+    fn truncated_moov_is_rejected() {
+        let mut file = mp4_box(b"ftyp", &[0; 8]);
+        file.extend(mp4_box(b"moov", &trak(1, Some("en-US"))));
+        let size = file.len() as u64;
+        let read = |file: &[u8]| read_extended_languages(&mut Cursor::new(file), size);
+
+        assert_eq!(
+            read(&file).unwrap(),
+            HashMap::from([(1, "en-US".to_string())])
+        );
+
+        // a moov claiming to be 4 GiB large, in a file much smaller than that
+        // right after the 16 bytes of the ftyp
+        file[16..20].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(read(&file).is_err());
+    }
+
+    /// Counts the sign changes of the left channel over the first half a second of the selected audio track
+    fn zero_crossings(mp4: &Mp4<File>) -> usize {
+        let track = mp4.audio_track.clone().unwrap();
+        let mut source = AudioSource::new(AacFrameSource::new(track).unwrap());
+
+        let samples = std::iter::from_fn(|| source.read_sample())
+            .take(22050)
+            .map(|(left, _)| left)
+            .collect::<Vec<_>>();
+        samples
+            .windows(2)
+            .filter(|pair| (pair[0] < 0.0) != (pair[1] < 0.0))
+            .count()
+    }
+
+    #[test]
+    fn audio_tracks_can_be_selected() {
+        let Some(path) = encode_clip("audio-tracks", &[
+            "-f",
+            "lavfi",
+            "-i",
+            "testsrc=duration=1:size=64x64:rate=30",
+            "-f",
+            "lavfi",
+            "-i",
+            "sine=frequency=440:duration=1",
+            "-f",
+            "lavfi",
+            "-i",
+            "sine=frequency=1760:duration=1",
+            "-map",
+            "0:v",
+            "-map",
+            "1:a",
+            "-map",
+            "2:a",
+            "-c:v",
+            "libx264",
+            "-pix_fmt",
+            "yuv420p",
+            "-c:a",
+            "aac",
+            "-ac",
+            "2",
+            "-metadata:s:a:0",
+            "language=jpn",
+            "-metadata:s:a:1",
+            "language=eng",
+        ]) else {
+            eprintln!("Could not encode a test clip with ffmpeg, skipping the test");
+            return;
+        };
+
+        let mut mp4 = Mp4::new(File::open(&path).unwrap()).unwrap();
+
+        let tracks = mp4.audio_tracks();
+        assert_eq!(
+            tracks
+                .iter()
+                .map(|track| (track.index, track.channel_count, track.language.as_str()))
+                .collect::<Vec<_>>(),
+            [(0, 2, "jpn"), (1, 2, "eng")]
+        );
+        assert!(
+            tracks
+                .iter()
+                .all(|track| matches!(track.codec, Some(mp4::MediaType::AAC)))
+        );
+
+        // the first track is played by default, the second one has a higher tone
+        let first = zero_crossings(&mp4);
+        mp4.select_audio_track(1).unwrap();
+        let second = zero_crossings(&mp4);
+        assert!(second > first * 3, "{} vs {}", first, second);
+
+        assert!(mp4.select_audio_track(2).is_err());
+
+        std::fs::remove_file(path).unwrap();
     }
 }
@@ -0,0 +1,25 @@
+//! Helpers for the tests that need a movie to play.
+
+use std::{path::PathBuf, process::Command, sync::Once};
+
+/// Encodes a clip with ffmpeg to a temporary file, or returns `None` if ffmpeg can't do it
+///
+/// The `args` specify the inputs and the codecs, the `name` keeps the files of the tests running in parallel apart.
+pub fn encode_clip(name: &str, args: &[&str]) -> Option<PathBuf> {
+    let path = std::env::temp_dir().join(format!("shin-video-{}-{}.mp4", name, std::process::id()));
+
+    let status = Command::new(which::which("ffmpeg").ok()?)
+        .args(["-loglevel", "error", "-y"])
+        .args(args)
+        .arg(&path)
+        .status()
+        .ok()?;
+
+    status.success().then_some(path)
+}
+
+/// The decoders run on the task pools, which can only be created once per process
+pub fn init_task_pools() {
+    static TASK_POOLS: Once = Once::new();
+    TASK_POOLS.call_once(shin_tasks::create_task_pools);
+}
@@ -1,3 +1,4 @@
+use anyhow::{Result, ensure};
 use shin_audio::AudioHandle;
 use shin_core::{
     time::{Ticks, Tween},
@@ -10,15 +11,29 @@ pub struct IndependentTimer {
     time_base: u32,
     /// How many time units have passed since the start of the timer
     time: u64,
+    /// A paused timer ignores the updates
+    paused: bool,
+    /// How much faster than the updates the time goes
+    speed: f32,
 }
 
 impl IndependentTimer {
     pub fn new(time_base: u32) -> IndependentTimer {
-        IndependentTimer { time_base, time: 0 }
+        IndependentTimer {
+            time_base,
+            time: 0,
+            paused: false,
+            speed: 1.0,
+        }
     }
 
     pub fn update(&mut self, delta_time: Ticks) -> u64 {
-        self.time += (delta_time.as_seconds() as f64 * self.time_base as f64) as u64;
+        if self.paused {
+            return self.time;
+        }
+
+        self.time +=
+            (delta_time.as_seconds() as f64 * self.speed as f64 * self.time_base as f64) as u64;
 
         self.time
     }
@@ -31,6 +46,9 @@ impl IndependentTimer {
 pub struct AudioTiedTimer {
     timer: IndependentTimer,
     audio_handle: AudioHandle,
+    /// The time the audio started playing at, as it is restarted when seeking
+    audio_start_time: u64,
+    volume: Volume,
 }
 
 impl AudioTiedTimer {
@@ -40,13 +58,21 @@ impl AudioTiedTimer {
         AudioTiedTimer {
             timer: IndependentTimer::new(time_base),
             audio_handle,
+            audio_start_time: 0,
+            volume: Volume::default(),
         }
     }
 
     pub fn update(&mut self, delta_time: Ticks) -> u64 {
+        // the audio is paused as well, so its position can't tell how far we are
+        if self.timer.paused || self.timer.speed == 0.0 {
+            return self.timer.time;
+        }
+
         self.timer.update(delta_time);
 
-        let audio_secs = self.audio_handle.position().as_seconds() as f64;
+        let audio_secs = self.audio_handle.position().as_seconds() as f64
+            + self.audio_start_time as f64 / self.timer.time_base as f64;
         let timer_secs = self.timer.time() as f64 / self.timer.time_base as f64;
 
         if (audio_secs - timer_secs).abs() > Self::MAX_DRIFT {
@@ -63,6 +89,36 @@ impl AudioTiedTimer {
     pub fn time(&self) -> u64 {
         self.timer.time()
     }
+
+    fn set_volume(&mut self, volume: Volume) {
+        self.volume = volume;
+        self.audio_handle
+            .set_volume(volume, Tween::IMMEDIATE)
+            .unwrap()
+    }
+
+    fn set_paused(&mut self, paused: bool) {
+        self.timer.paused = paused;
+        self.update_audio_pause();
+    }
+
+    fn set_speed(&mut self, speed: f32) {
+        self.timer.speed = speed;
+        // a zero playback rate would hold the last sample, so the audio is paused instead
+        if speed > 0.0 {
+            if let Err(e) = self.audio_handle.set_playback_rate(speed) {
+                warn!("Could not change the audio speed: {}", e);
+            }
+        }
+        self.update_audio_pause();
+    }
+
+    fn update_audio_pause(&mut self) {
+        let paused = self.timer.paused || self.timer.speed == 0.0;
+        if let Err(e) = self.audio_handle.set_paused(paused) {
+            warn!("Could not pause or resume the audio: {}", e);
+        }
+    }
 }
 
 pub enum Timer {
@@ -93,6 +149,90 @@ impl Timer {
         }
     }
 
+    pub fn is_paused(&self) -> bool {
+        match self {
+            Timer::Independent(timer) => timer.paused,
+            Timer::AudioTiedTimer(timer) => timer.timer.paused,
+        }
+    }
+
+    /// Freeze the time along with the audio, or let them go on from where they were
+    pub fn set_paused(&mut self, paused: bool) {
+        match self {
+            Timer::Independent(timer) => timer.paused = paused,
+            Timer::AudioTiedTimer(timer) => timer.set_paused(paused),
+        }
+    }
+
+    pub fn speed(&self) -> f32 {
+        match self {
+            Timer::Independent(timer) => timer.speed,
+            Timer::AudioTiedTimer(timer) => timer.timer.speed,
+        }
+    }
+
+    /// Make the time go `speed` times faster than the updates, speeding up the audio as well
+    ///
+    /// A zero speed freezes the time like a pause, negative speeds are rejected.
+    pub fn set_speed(&mut self, speed: f32) -> Result<()> {
+        ensure!(
+            speed >= 0.0 && speed.is_finite(),
+            "Invalid playback speed: {}",
+            speed
+        );
+
+        match self {
+            Timer::Independent(timer) => timer.speed = speed,
+            Timer::AudioTiedTimer(timer) => timer.set_speed(speed),
+        }
+        Ok(())
+    }
+
+    /// Jump to `time`, following the `audio_handle` playing from there
+    ///
+    /// The audio playing before is stopped, its volume, speed and pause are carried over to the new one.
+    pub fn seek(&mut self, time: u64, audio_handle: Option<AudioHandle>) {
+        let (time_base, volume) = match self {
+            Timer::Independent(timer) => (timer.time_base, Volume::default()),
+            Timer::AudioTiedTimer(timer) => {
+                if let Err(e) = timer.audio_handle.stop(Tween::IMMEDIATE) {
+                    warn!("Could not stop the audio before seeking: {}", e);
+                }
+                (timer.timer.time_base, timer.volume)
+            }
+        };
+        let paused = self.is_paused();
+        let speed = self.speed();
+
+        let timer = IndependentTimer {
+            time_base,
+            time,
+            paused: false,
+            speed: 1.0,
+        };
+
+        *self = match audio_handle {
+            Some(audio_handle) => {
+                let mut timer = AudioTiedTimer {
+                    timer,
+                    audio_handle,
+                    audio_start_time: time,
+                    volume: Volume::default(),
+                };
+                timer.set_volume(volume);
+                Timer::AudioTiedTimer(timer)
+            }
+            None => Timer::Independent(timer),
+        };
+        if paused {
+            self.set_paused(true);
+        }
+        if speed != 1.0 {
+            self.set_speed(speed)
+                .expect("The speed was validated when it was set");
+        }
+    }
+
     // Why is this a function on a timer?
     // because timer keeps the audio handle :/
     pub fn set_audio_volume(&mut self, volume: Volume) {
@@ -100,9 +240,59 @@ impl Timer {
             return;
         };
 
-        timer
-            .audio_handle
-            .set_volume(volume, Tween::IMMEDIATE)
-            .unwrap()
+        timer.set_volume(volume);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shin_core::time::Ticks;
+
+    use super::Timer;
+
+    #[test]
+    fn pause_freezes_the_time() {
+        let mut timer = Timer::new_independent(1000);
+
+        timer.update(Ticks::from_seconds(1.0));
+        timer.set_paused(true);
+        assert!(timer.is_paused());
+        for _ in 0..60 {
+            timer.update(Ticks::from_u32(1));
+        }
+        assert_eq!(timer.time(), 1000);
+
+        timer.set_paused(false);
+        timer.update(Ticks::from_seconds(0.5));
+        // only the unpaused time has passed
+        assert_eq!(timer.time(), 1500);
+    }
+
+    #[test]
+    fn speed_scales_the_time() {
+        let mut timer = Timer::new_independent(1000);
+
+        timer.set_speed(2.0).unwrap();
+        timer.update(Ticks::from_seconds(0.5));
+        assert_eq!(timer.time(), 1000);
+
+        // zero speed behaves like a pause
+        timer.set_speed(0.0).unwrap();
+        timer.update(Ticks::from_seconds(0.5));
+        assert_eq!(timer.time(), 1000);
+
+        assert!(timer.set_speed(-1.0).is_err());
+        assert!(timer.set_speed(f32::NAN).is_err());
+        assert_eq!(timer.speed(), 0.0);
+    }
+
+    #[test]
+    fn seeking_keeps_the_pause() {
+        let mut timer = Timer::new_independent(1000);
+
+        timer.set_paused(true);
+        timer.seek(2000, None);
+        timer.update(Ticks::from_seconds(1.0));
+        assert_eq!(timer.time(), 2000);
     }
 }
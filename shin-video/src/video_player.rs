@@ -4,7 +4,7 @@ use anyhow::{Context, Result};
 use glam::{Mat4, Vec4};
 use kira::track::TrackId;
 use parking_lot::{Mutex, RwLock, RwLockReadGuard, RwLockUpgradableReadGuard};
-use shin_audio::{AudioData, AudioManager, AudioSettings};
+use shin_audio::{AudioData, AudioHandle, AudioManager, AudioSettings};
 use shin_core::{
     primitives::{
         exclusive::Exclusive,
@@ -25,36 +25,181 @@ use crate::{
     VideoFrameTexture,
     audio::AacFrameSource,
     h264_decoder::{FrameTiming, H264Decoder, H264DecoderTrait, Nv12Frame},
-    mp4::Mp4,
+    mp4::{Mp4, SubtitleCue},
     timer::Timer,
 };
 
 struct VideoPlayerInner {
     update_tracker: UpdateTracker,
+    tracks: Box<dyn MovieTracks>,
     // we could use [`Exclusive`] instead of [`Mutex`] here, but `set_volume` will then have to take a lock that is much larger than necessary
     timer: Mutex<Timer>,
     video_decoder: Exclusive<H264Decoder>,
     video_texture: VideoFrameTexture,
     pending_frame: Option<(FrameTiming, Nv12Frame)>,
+    /// Number of the frame currently in `video_texture`, `None` until the first update presents one
+    displayed_frame: Option<u32>,
+    subtitle_cues: Vec<SubtitleCue>,
+    active_subtitle: Option<usize>,
 }
 
 pub struct VideoPlayerHandle {
     inner: RwLock<VideoPlayerInner>,
 }
 
+/// Opens the tracks of a movie at an arbitrary position, so that the player can seek
+trait MovieTracks: Send + Sync {
+    /// How many video time units are there in one second
+    fn time_base(&self) -> u32;
+
+    /// Start decoding the video from the keyframe before `time`, or `None` if the video is over by then
+    fn open_video(&self, time: u64) -> Result<Option<H264Decoder>>;
+
+    /// Start playing the audio from `time`, if the movie has an audio track that is not over by then
+    fn play_audio(&self, audio_manager: &AudioManager, time: Ticks) -> Result<Option<AudioHandle>>;
+}
+
+impl<S: Read + Seek + Send + 'static> MovieTracks for Mp4<S> {
+    fn time_base(&self) -> u32 {
+        self.video_track
+            .get_mp4_track_info(|track| track.timescale())
+    }
+
+    fn open_video(&self, time: u64) -> Result<Option<H264Decoder>> {
+        let Some(sample) = self.video_track.sample_at_time(time) else {
+            return Ok(None);
+        };
+
+        let mut track = self.video_track.clone();
+        track.seek_to_sample(track.sync_sample_before(sample));
+
+        H264Decoder::new(track)
+            .context("Initializing H264Decoder")
+            .map(Some)
+    }
+
+    fn play_audio(&self, audio_manager: &AudioManager, time: Ticks) -> Result<Option<AudioHandle>> {
+        let Some(track) = &self.audio_track else {
+            return Ok(None);
+        };
+
+        let time_base = track.get_mp4_track_info(|track| track.timescale());
+        let Some(sample) = track.sample_at_time(ticks_to_time(time, time_base)) else {
+            return Ok(None);
+        };
+
+        let mut track = track.clone();
+        track.seek_to_sample(sample);
+
+        let frame_source = AacFrameSource::new(track).context("Initializing AacFrameSource")?;
+        Ok(Some(audio_manager.play(AudioData {
+            source: frame_source,
+            settings: AudioSettings {
+                track: TrackId::Main,
+                fade_in: Tween::MS_15,
+                loop_start: None,
+                volume: Volume::default(),
+                pan: Pan::default(),
+            },
+        })))
+    }
+}
+
+fn ticks_to_time(ticks: Ticks, time_base: u32) -> u64 {
+    (ticks.as_seconds() as f64 * time_base as f64) as u64
+}
+
+fn time_to_ticks(time: u64, time_base: u32) -> Ticks {
+    Ticks::from_seconds((time as f64 / time_base as f64) as f32)
+}
+
+/// Read the frames that are due at `current_time`, returning the latest of them for display
+///
+/// The frames before it are dropped, so that the playback keeps up when the time goes faster than the frames can be shown.
+fn take_due_frame(
+    video_decoder: &mut H264Decoder,
+    pending_frame: &mut Option<(FrameTiming, Nv12Frame)>,
+    current_time: u64,
+) -> Option<(FrameTiming, Nv12Frame)> {
+    let mut due_frame = None;
+    let mut skipped_frames = 0;
+
+    while let Some((timing, _)) = pending_frame {
+        // if it's not time to display the frame yet - stop the loop
+        if timing.start_time > current_time {
+            break;
+        }
+
+        if due_frame.is_some() {
+            skipped_frames += 1;
+        }
+        due_frame = pending_frame.take();
+
+        // look at the frame after the due one
+        *pending_frame = video_decoder.read_frame().unwrap_or_else(|err| {
+            error!("Error reading frame: {}. Stopping playback", err);
+            None
+        });
+
+        if pending_frame.is_none() {
+            info!("No more frames, stopping playback");
+        }
+    }
+
+    if skipped_frames > 0 {
+        warn!("Skipped {} frames", skipped_frames);
+    }
+
+    due_frame
+}
+
+/// Finds the subtitle shown at `time`, preferring the one that started later if they overlap
+fn active_cue(cues: &[SubtitleCue], time: Ticks) -> Option<usize> {
+    cues.iter()
+        .rposition(|cue| cue.start <= time && time < cue.end)
+}
+
+/// Start decoding the video from the keyframe before `time`, skipping forward to the first frame at or after it
+///
+/// Returns `None` if there are no frames left by then.
+fn seek_video(
+    tracks: &dyn MovieTracks,
+    time: u64,
+) -> Result<Option<(H264Decoder, (FrameTiming, Nv12Frame))>> {
+    let Some(mut decoder) = tracks.open_video(time)? else {
+        return Ok(None);
+    };
+
+    while let Some((timing, frame)) = decoder.read_frame().context("Reading frame")? {
+        if timing.start_time >= time {
+            return Ok(Some((decoder, (timing, frame))));
+        }
+    }
+
+    Ok(None)
+}
+
 impl VideoPlayerHandle {
+    /// Start playing the movie, with the audio track at `audio_track` among the [`Mp4::audio_tracks`], or the first one if `None`
     pub fn new<S: Read + Seek + Send + 'static>(
         device: &wgpu::Device,
         audio_manager: &AudioManager,
-        mp4: Mp4<S>,
+        mut mp4: Mp4<S>,
+        audio_track: Option<usize>,
     ) -> Result<VideoPlayerHandle> {
-        let time_base = mp4
-            .video_track
-            .get_mp4_track_info(|track| track.timescale());
+        if let Some(audio_track) = audio_track {
+            mp4.select_audio_track(audio_track)
+                .context("Selecting the audio track")?;
+        }
+
+        let subtitle_cues = mp4.subtitle_cues().to_vec();
+        let tracks: Box<dyn MovieTracks> = Box::new(mp4);
+        let time_base = tracks.time_base();
 
         let start = std::time::Instant::now();
-        let mut video_decoder =
-            H264Decoder::new(mp4.video_track).context("Initializing H264Decoder")?;
+        let mut video_decoder = tracks
+            .open_video(0)?
+            .context("The video track has no samples")?;
         let pending_frame = video_decoder.read_frame().context("Reading first frame")?;
         let duration = start.elapsed();
 
@@ -67,23 +212,8 @@ impl VideoPlayerHandle {
                 .context("Getting H264 frame size")?,
         );
 
-        // TODO: use the audio track
         // if we are using audio the timer should be tracking the audio playback
-        let audio_handle = if let Some(track) = mp4.audio_track {
-            let frame_source = AacFrameSource::new(track).context("Initializing AacFrameSource")?;
-            Some(audio_manager.play(AudioData {
-                source: frame_source,
-                settings: AudioSettings {
-                    track: TrackId::Main,
-                    fade_in: Tween::MS_15,
-                    loop_start: None,
-                    volume: Volume::default(),
-                    pan: Pan::default(),
-                },
-            }))
-        } else {
-            None
-        };
+        let audio_handle = tracks.play_audio(audio_manager, Ticks::ZERO)?;
 
         let timer = match audio_handle {
             Some(handle) => Timer::new_audio_tied(time_base, handle),
@@ -92,10 +222,14 @@ impl VideoPlayerHandle {
 
         let inner = VideoPlayerInner {
             update_tracker: UpdateTracker::new(),
+            tracks,
             timer: Mutex::new(timer),
             video_decoder: Exclusive::new(video_decoder),
             video_texture,
             pending_frame,
+            displayed_frame: None,
+            subtitle_cues,
+            active_subtitle: None,
         };
 
         Ok(VideoPlayerHandle {
@@ -103,6 +237,41 @@ impl VideoPlayerHandle {
         })
     }
 
+    /// Jump to `target` from the start of the movie
+    ///
+    /// The decoding restarts from the keyframe before the target, going forward to the first frame at or after it, which is presented on the next update.
+    /// The audio is restarted from the same point.
+    ///
+    /// Seeking past the end of the video finishes the playback.
+    pub fn seek(&self, audio_manager: &AudioManager, target: Ticks) -> Result<()> {
+        let mut write_guard = self.inner.write();
+        let this = &mut *write_guard;
+
+        let time_base = this.tracks.time_base();
+        let target_time = ticks_to_time(target, time_base);
+
+        match seek_video(this.tracks.as_ref(), target_time).context("Seeking the video")? {
+            Some((video_decoder, (timing, frame))) => {
+                let audio_handle = this
+                    .tracks
+                    .play_audio(audio_manager, time_to_ticks(timing.start_time, time_base))
+                    .context("Seeking the audio")?;
+
+                this.timer.get_mut().seek(timing.start_time, audio_handle);
+                this.video_decoder = Exclusive::new(video_decoder);
+                this.pending_frame = Some((timing, frame));
+            }
+            None => {
+                info!("Seeking past the end of the video, stopping playback");
+
+                this.timer.get_mut().seek(target_time, None);
+                this.pending_frame = None;
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn set_volume(&self, volume: Volume) {
         let read_guard = self.inner.read();
         let mut timer = read_guard.timer.lock();
@@ -112,9 +281,30 @@ impl VideoPlayerHandle {
         timer.set_audio_volume(volume);
     }
 
+    /// Freeze the video and the audio, keeping the last presented frame on screen, or resume them from where they were
+    pub fn set_paused(&self, paused: bool) {
+        let read_guard = self.inner.read();
+        read_guard.timer.lock().set_paused(paused);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        let read_guard = self.inner.read();
+        read_guard.timer.lock().is_paused()
+    }
+
+    /// Play the movie `speed` times faster, speeding up (and pitching up) the audio as well
+    ///
+    /// The frames that can't be shown in time are dropped. A zero speed freezes the playback like [`Self::set_paused`], negative speeds are rejected.
+    pub fn set_speed(&self, speed: f32) -> Result<()> {
+        let read_guard = self.inner.read();
+        read_guard.timer.lock().set_speed(speed)
+    }
+
     pub fn update(&self, game_frame_id: FrameId, delta_time: Ticks, queue: &wgpu::Queue) {
         let read_guard = self.inner.upgradable_read();
-        if !read_guard.update_tracker.needs_update(game_frame_id) {
+        if !read_guard.update_tracker.needs_update(game_frame_id)
+            || read_guard.timer.lock().is_paused()
+        {
             return;
         }
 
@@ -129,61 +319,48 @@ impl VideoPlayerHandle {
                 timer.time()
             };
 
-            let mut skipped_frames = 0;
-            // find the latest frame that is ready for display
-            // this might be the currently pending frame, or any of the frames after it (shouldn't happen often I think)
-            while let Some((timing, ref frame)) = this.pending_frame {
-                // if it's not time to display the frame yet - stop the loop
-                if timing.start_time > current_time {
-                    // very noisy
-                    // trace!(
-                    //     "Not time to display frame #{}, time: {}",
-                    //     timing.frame_number,
-                    //     timing.start_time
-                    // );
-                    break;
-                }
-
-                // look at the frame after the pending one
-                let next_frame = this
-                    .video_decoder
-                    .get_mut()
-                    .read_frame()
-                    .unwrap_or_else(|err| {
-                        error!("Error reading frame: {}. Stopping playback", err);
-                        None
-                    });
-
-                // if the next frame is not ready for display yet...
-                if next_frame
-                    .as_ref()
-                    .map_or(true, |(timing, _)| timing.start_time > current_time)
-                {
-                    if skipped_frames > 0 {
-                        warn!("Skipped {} frames", skipped_frames);
-                    }
-
-                    // then update the texture with the pending frame
-                    trace!(
-                        "Displaying frame #{}, time: {}",
-                        timing.frame_number, timing.start_time
-                    );
-                    this.video_texture.write_data_nv12(queue, frame);
-                    // the loop will not enter again, so the pending frame will now be displayed
-                } else {
-                    skipped_frames += 1;
-                    // if the next frame is also ready for display, then we should skip the pending frame
-                }
-
-                if next_frame.is_none() {
-                    info!("No more frames, stopping playback");
-                }
-
-                this.pending_frame = next_frame;
+            this.active_subtitle = active_cue(
+                &this.subtitle_cues,
+                time_to_ticks(current_time, this.tracks.time_base()),
+            );
+
+            if let Some((timing, frame)) = take_due_frame(
+                this.video_decoder.get_mut(),
+                &mut this.pending_frame,
+                current_time,
+            ) {
+                trace!(
+                    "Displaying frame #{}, time: {}",
+                    timing.frame_number, timing.start_time
+                );
+                this.video_texture.write_data_nv12(queue, &frame);
+                this.displayed_frame = Some(timing.frame_number);
             }
         }
     }
 
+    /// The text of the subtitle shown at the time of the last update, for the caller to render over the movie
+    ///
+    /// Always `None` if the movie has no timed text track.
+    pub fn active_subtitle(&self) -> Option<String> {
+        let read_guard = self.inner.read();
+        read_guard
+            .active_subtitle
+            .map(|index| read_guard.subtitle_cues[index].text.clone())
+    }
+
+    /// The playback time as of the last update, from the start of the movie
+    pub fn position(&self) -> Ticks {
+        let read_guard = self.inner.read();
+        let time = read_guard.timer.lock().time();
+        time_to_ticks(time, read_guard.tracks.time_base())
+    }
+
+    /// The number of the frame shown by [`Self::get_frame`], counting from the start of the movie
+    pub fn displayed_frame_number(&self) -> Option<u32> {
+        self.inner.read().displayed_frame
+    }
+
     pub fn is_finished(&self) -> bool {
         let read_guard = self.inner.read();
         read_guard.pending_frame.is_none()
@@ -239,3 +416,178 @@ impl<'a> VideoPlayerFrameHandle<'a> {
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{fs::File, path::PathBuf};
+
+    use shin_core::time::Ticks;
+
+    use super::{MovieTracks, active_cue, seek_video, take_due_frame, ticks_to_time};
+    use crate::{
+        h264_decoder::H264DecoderTrait as _,
+        mp4::Mp4,
+        test_utils::{encode_clip, init_task_pools},
+        timer::Timer,
+    };
+
+    /// Encodes a two seconds long 30 fps clip with a keyframe every half a second, or returns `None` if ffmpeg can't do it
+    fn test_clip(name: &str) -> Option<PathBuf> {
+        encode_clip(
+            name,
+            &[
+                "-f",
+                "lavfi",
+                "-i",
+                "testsrc=duration=2:size=64x64:rate=30",
+                "-c:v",
+                "libx264",
+                "-g",
+                "15",
+                "-pix_fmt",
+                "yuv420p",
+            ],
+        )
+    }
+
+    /// Plays the clip for half a second of 30 fps updates at the given speed, returning how many frames were displayed and the time of the last one
+    fn play_for_half_a_second(mp4: &Mp4<File>, speed: f32) -> (usize, f32) {
+        let time_base = mp4.time_base();
+        let mut decoder = mp4.open_video(0).unwrap().unwrap();
+        let mut pending_frame = decoder.read_frame().unwrap();
+        let mut timer = Timer::new_independent(time_base);
+        timer.set_speed(speed).unwrap();
+
+        let mut displayed = Vec::new();
+        for _ in 0..15 {
+            let current_time = timer.update(Ticks::from_u32(2));
+            if let Some((timing, _)) =
+                take_due_frame(&mut decoder, &mut pending_frame, current_time)
+            {
+                displayed.push(timing.start_time);
+            }
+        }
+
+        let last_time = *displayed.last().unwrap() as f32 / time_base as f32;
+        (displayed.len(), last_time)
+    }
+
+    #[test]
+    fn double_speed_drops_frames() {
+        let Some(path) = test_clip("speed") else {
+            eprintln!("Could not encode a test clip with ffmpeg, skipping the test");
+            return;
+        };
+        init_task_pools();
+
+        let mp4 = Mp4::new(File::open(&path).unwrap()).unwrap();
+
+        let (_, normal_time) = play_for_half_a_second(&mp4, 1.0);
+        let (displayed, double_time) = play_for_half_a_second(&mp4, 2.0);
+
+        // the clip went twice as far, give or take a frame
+        assert!((normal_time - 0.5).abs() <= 1.0 / 30.0, "{}", normal_time);
+        assert!((double_time - 1.0).abs() <= 1.0 / 30.0, "{}", double_time);
+        // but with an update per two frames, every other frame was dropped
+        assert!(displayed <= 15, "{}", displayed);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn active_subtitle_follows_the_cues() {
+        let subtitles_path =
+            std::env::temp_dir().join(format!("shin-video-subtitles-{}.srt", std::process::id()));
+        std::fs::write(
+            &subtitles_path,
+            "1\n00:00:00,200 --> 00:00:00,800\nHello\n\n2\n00:00:01,000 --> 00:00:01,500\nWorld\n",
+        )
+        .unwrap();
+
+        let path = encode_clip(
+            "subtitles",
+            &[
+                "-f",
+                "lavfi",
+                "-i",
+                "testsrc=duration=2:size=64x64:rate=30",
+                "-i",
+                subtitles_path.to_str().unwrap(),
+                "-c:v",
+                "libx264",
+                "-pix_fmt",
+                "yuv420p",
+                "-c:s",
+                "mov_text",
+            ],
+        );
+        std::fs::remove_file(&subtitles_path).unwrap();
+        let Some(path) = path else {
+            eprintln!("Could not encode a test clip with ffmpeg, skipping the test");
+            return;
+        };
+
+        let mp4 = Mp4::new(File::open(&path).unwrap()).unwrap();
+        let cues = mp4.subtitle_cues();
+        assert_eq!(
+            cues.iter().map(|cue| cue.text.as_str()).collect::<Vec<_>>(),
+            ["Hello", "World"]
+        );
+
+        let text_at = |seconds: f32| {
+            active_cue(cues, Ticks::from_seconds(seconds)).map(|index| cues[index].text.as_str())
+        };
+        assert_eq!(text_at(0.0), None);
+        assert_eq!(text_at(0.3), Some("Hello"));
+        assert_eq!(text_at(0.7), Some("Hello"));
+        assert_eq!(text_at(0.9), None);
+        assert_eq!(text_at(1.2), Some("World"));
+        assert_eq!(text_at(1.8), None);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn movies_without_subtitles_have_no_cues() {
+        let Some(path) = test_clip("no-subtitles") else {
+            eprintln!("Could not encode a test clip with ffmpeg, skipping the test");
+            return;
+        };
+
+        let mp4 = Mp4::new(File::open(&path).unwrap()).unwrap();
+        assert!(mp4.subtitle_cues().is_empty());
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn seek_presents_the_target_frame() {
+        let Some(path) = test_clip("seek") else {
+            eprintln!("Could not encode a test clip with ffmpeg, skipping the test");
+            return;
+        };
+        init_task_pools();
+
+        let mp4 = Mp4::new(File::open(&path).unwrap()).unwrap();
+        let time_base = mp4.time_base();
+
+        // between the frames, a few frames after a keyframe
+        let target = ticks_to_time(Ticks::from_seconds(1.05), time_base);
+        let (_, (timing, _)) = seek_video(&mp4, target)
+            .unwrap()
+            .expect("The target is within the clip");
+        assert!(timing.start_time >= target);
+        assert!(
+            timing.start_time < target + timing.duration as u64,
+            "{} is more than a frame after {}",
+            timing.start_time,
+            target
+        );
+
+        // past the end
+        let target = ticks_to_time(Ticks::from_seconds(3.0), time_base);
+        assert!(seek_video(&mp4, target).unwrap().is_none());
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
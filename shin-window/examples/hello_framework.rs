@@ -1,8 +1,8 @@
 use std::time::Duration;
 
-use enum_map::{Enum, EnumMap, enum_map};
+use enum_map::{Enum, EnumMap};
 use glam::{Mat4, Vec3};
-use shin_input::{Action, ActionState, RawInputState, inputs::GamepadButton};
+use shin_input::{Action, ActionState, Binding, Bindings, inputs::GamepadButton};
 use shin_primitives::color::{FloatColor4, UnormColor};
 use shin_render::{
     DrawPrimitive, RenderProgramWithArguments, RenderRequestBuilder,
@@ -22,17 +22,26 @@ enum HelloAction {
 }
 
 impl Action for HelloAction {
-    fn lower(
-        RawInputState {
-            mouse: _,
-            keyboard,
-            gamepads,
-        }: &RawInputState,
-    ) -> EnumMap<Self, bool> {
-        enum_map! {
-            HelloAction::Ok => keyboard.contains(&KeyCode::Enter) || keyboard.contains(&KeyCode::Space) || gamepads.is_held(GamepadButton::A),
-            HelloAction::Back => keyboard.contains(&KeyCode::KeyQ) || keyboard.contains(&KeyCode::Escape) || gamepads.is_held(GamepadButton::B),
-        }
+    fn default_bindings() -> Bindings<Self> {
+        use Binding::{Gamepad, Key};
+
+        Bindings::new()
+            .with(
+                HelloAction::Ok,
+                [
+                    Key(KeyCode::Enter),
+                    Key(KeyCode::Space),
+                    Gamepad(GamepadButton::A),
+                ],
+            )
+            .with(
+                HelloAction::Back,
+                [
+                    Key(KeyCode::KeyQ),
+                    Key(KeyCode::Escape),
+                    Gamepad(GamepadButton::B),
+                ],
+            )
     }
 }
 
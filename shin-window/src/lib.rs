@@ -7,7 +7,8 @@ use std::{
 use cfg_if::cfg_if;
 use derive_where::derive_where;
 use enum_map::EnumMap;
-use shin_input::{Action, ActionState, ActionsState, RawInputAccumulator};
+use glam::uvec2;
+use shin_input::{Action, ActionState, ActionsState, Bindings, RawInputAccumulator};
 use shin_render::{
     init::{RenderResources, WgpuInitResult, WgpuResources},
     render_pass::RenderPass,
@@ -31,6 +32,8 @@ pub struct AppContext<'a, A: ShinApp> {
     pub winit: &'a mut WindowState,
     pub wgpu: &'a WgpuResources,
     pub render: &'a mut RenderResources,
+    /// The input bindings, can be changed to rebind the actions at runtime
+    pub bindings: &'a mut Bindings<A::ActionType>,
 }
 
 pub struct RenderContext<'a> {
@@ -250,12 +253,15 @@ fn finish_wgpu_init<A: ShinApp>(
         proxy: proxy.clone(),
     };
 
+    let mut input_state = ActionsState::new();
+
     let context = AppContext {
         event_loop,
         event_loop_proxy: &shin_proxy,
         winit: &mut winit,
         wgpu: &wgpu,
         render: &mut render,
+        bindings: input_state.bindings_mut(),
     };
 
     let app = A::init(context, params)
@@ -273,7 +279,7 @@ fn finish_wgpu_init<A: ShinApp>(
         proxy,
         last_update: Instant::now(),
         raw_input_state,
-        input_state: ActionsState::new(),
+        input_state,
         context,
         app,
     }
@@ -453,7 +459,7 @@ impl<A: ShinApp> ApplicationHandler<ShinAppEventImpl<A::EventType>> for WinitApp
                     proxy: _,
                     last_update: _,
                     raw_input_state: _,
-                    input_state: _,
+                    input_state,
                     context:
                         AppContextOwned {
                             event_loop_proxy,
@@ -475,6 +481,7 @@ impl<A: ShinApp> ApplicationHandler<ShinAppEventImpl<A::EventType>> for WinitApp
                         wgpu,
                         winit,
                         render,
+                        bindings: input_state.bindings_mut(),
                     },
                     e,
                 );
@@ -557,11 +564,9 @@ impl<A: ShinApp> ApplicationHandler<ShinAppEventImpl<A::EventType>> for WinitApp
                 *last_update = now_update;
 
                 let raw_instantaneous_input_state = raw_input_state.start_frame();
-                let instantaneous_input_state =
-                    A::ActionType::lower(&raw_instantaneous_input_state);
                 // NOTE: this interface does not expose analog stick positions, as well as mouse wheel (and buttons...)
                 // these should probably be passed directly or some other abstraction should be devised
-                let input_state = input_state.update(instantaneous_input_state, elapsed);
+                let action_states = input_state.update(&raw_instantaneous_input_state, elapsed);
 
                 let mut update_encoder =
                     wgpu.device
@@ -577,8 +582,9 @@ impl<A: ShinApp> ApplicationHandler<ShinAppEventImpl<A::EventType>> for WinitApp
                             winit,
                             wgpu,
                             render,
+                            bindings: input_state.bindings_mut(),
                         },
-                        input_state,
+                        action_states,
                         elapsed,
                         &mut update_encoder,
                     );
@@ -603,6 +609,10 @@ impl<A: ShinApp> ApplicationHandler<ShinAppEventImpl<A::EventType>> for WinitApp
                         TextureTarget {
                             kind: TextureTargetKind::Screen,
                             view: &surface_texture.view,
+                            size: uvec2(
+                                surface_texture.texture.texture.width(),
+                                surface_texture.texture.texture.height(),
+                            ),
                         },
                         Some(DepthStencilTarget {
                             view: render.surface_depth_stencil_buffer.resize_and_get_view(),
@@ -0,0 +1,172 @@
+use std::{fmt::Write as _, path::Path, time::Duration};
+
+use shin_core::layout::{MessageTextParser, ParsedCommand, PlainTextMessage};
+
+/// A message that has been shown to the player
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BacklogEntry {
+    pub character_name: Option<String>,
+    pub text: String,
+    /// Name of the voice the message starts with (as passed to VOICEPLAY), for replaying it
+    pub voice: Option<String>,
+    /// Time since the start of the session at which the message was shown
+    pub timestamp: Duration,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum TranscriptFormat {
+    Text,
+    #[default]
+    Markdown,
+}
+
+impl TranscriptFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            TranscriptFormat::Text => "txt",
+            TranscriptFormat::Markdown => "md",
+        }
+    }
+}
+
+/// All messages shown during the session, in order
+#[derive(Debug, Default, Clone)]
+pub struct Backlog {
+    entries: Vec<BacklogEntry>,
+}
+
+impl Backlog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, message: &str, timestamp: Duration) {
+        let PlainTextMessage {
+            character_name,
+            text,
+        } = PlainTextMessage::parse(message);
+        let voice = MessageTextParser::new(message).find_map(|command| match command {
+            ParsedCommand::Voice(voice) => Some(voice),
+            _ => None,
+        });
+
+        self.entries.push(BacklogEntry {
+            character_name,
+            text,
+            voice,
+            timestamp,
+        });
+    }
+
+    pub fn entries(&self) -> &[BacklogEntry] {
+        &self.entries
+    }
+
+    /// Forget all but the first `len` entries, like when going back to an earlier save
+    pub fn truncate(&mut self, len: usize) {
+        self.entries.truncate(len);
+    }
+
+    pub fn export_transcript(&self, format: TranscriptFormat) -> String {
+        let mut result = String::new();
+
+        if format == TranscriptFormat::Markdown {
+            result.push_str("# Transcript\n\n");
+        }
+
+        for entry in &self.entries {
+            let timestamp = format_timestamp(entry.timestamp);
+
+            match format {
+                TranscriptFormat::Text => {
+                    write!(result, "[{}] ", timestamp).unwrap();
+                    if let Some(name) = &entry.character_name {
+                        write!(result, "{}: ", name).unwrap();
+                    }
+                    result.push_str(&entry.text);
+                    result.push_str("\n\n");
+                }
+                TranscriptFormat::Markdown => {
+                    write!(result, "`{}` ", timestamp).unwrap();
+                    if let Some(name) = &entry.character_name {
+                        write!(result, "**{}**: ", escape_markdown(name)).unwrap();
+                    }
+                    // trailing double space is a markdown hard line break
+                    result.push_str(&escape_markdown(&entry.text).replace('\n', "  \n"));
+                    result.push_str("\n\n");
+                }
+            }
+        }
+
+        result
+    }
+
+    pub fn write_transcript(&self, path: &Path, format: TranscriptFormat) -> std::io::Result<()> {
+        std::fs::write(path, self.export_transcript(format))
+    }
+}
+
+fn format_timestamp(timestamp: Duration) -> String {
+    let seconds = timestamp.as_secs();
+    format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+fn escape_markdown(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '*' | '_' | '`' | '[' | ']' | '<' | '>' | '#') {
+            result.push('\\');
+        }
+        result.push(c);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{Backlog, TranscriptFormat};
+
+    fn backlog() -> Backlog {
+        let mut backlog = Backlog::new();
+        backlog.push("@rIt was a @bかな.@<dark@> and stormy night.", Duration::from_secs(5));
+        backlog.push(
+            "@v00/awase0001.Battler@r@c940.「What a *mess*@w400.@k,@rreally」@c.",
+            Duration::from_secs(3725),
+        );
+        backlog
+    }
+
+    #[test]
+    fn voices_are_kept() {
+        let backlog = backlog();
+
+        assert_eq!(backlog.entries()[0].voice, None);
+        assert_eq!(backlog.entries()[1].voice.as_deref(), Some("00/awase0001"));
+    }
+
+    #[test]
+    fn text_transcript() {
+        assert_eq!(
+            backlog().export_transcript(TranscriptFormat::Text),
+            "[00:00:05] It was a dark(かな) and stormy night.\n\n\
+             [01:02:05] Battler: 「What a *mess*,\nreally」\n\n"
+        );
+    }
+
+    #[test]
+    fn markdown_transcript() {
+        assert_eq!(
+            backlog().export_transcript(TranscriptFormat::Markdown),
+            "# Transcript\n\n\
+             `00:00:05` It was a dark(かな) and stormy night.\n\n\
+             `01:02:05` **Battler**: 「What a \\*mess\\*,  \nreally」\n\n"
+        );
+    }
+}
@@ -0,0 +1,242 @@
+use std::sync::Arc;
+
+use glam::vec2;
+use shin_core::primitives::color::FloatColor4;
+use shin_render::render_pass::RenderPass;
+
+use crate::{
+    asset::font::GpuFontLazy,
+    render::{PreRenderContext, VIRTUAL_CANVAS_SIZE_VEC, label::Label},
+};
+
+/// Distance between the lines of the menu, in virtual canvas pixels
+const LINE_SPACING: f32 = 60.0;
+const HIGHLIGHT_COLOR: FloatColor4 = FloatColor4::from_rgba(1.0, 0.8, 0.2, 1.0);
+
+/// A variant of the choice menu that is shown to the player
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChoiceVariant {
+    /// Index of the variant in the `SELECT` command, which is what gets returned to the scenario
+    pub index: usize,
+    pub text: String,
+}
+
+/// State of the choice menu shown by the `SELECT` command
+#[derive(Debug, Clone)]
+pub struct ChoiceMenu {
+    title: String,
+    variants: Vec<ChoiceVariant>,
+    /// Position of the highlighted variant in the visible variants
+    cursor: usize,
+    selected: Option<usize>,
+}
+
+impl ChoiceMenu {
+    /// Bit `i` of the `visibility_mask` being set means the `i`-th variant is shown.
+    pub fn new<I>(title: String, variants: I, visibility_mask: i32) -> Self
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let variants = variants
+            .into_iter()
+            .enumerate()
+            .filter(|&(index, _)| index < 32 && visibility_mask & (1 << index) != 0)
+            .map(|(index, text)| ChoiceVariant { index, text })
+            .collect();
+
+        Self {
+            title,
+            variants,
+            cursor: 0,
+            selected: None,
+        }
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn visible_variants(&self) -> &[ChoiceVariant] {
+        &self.variants
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Moves the highlight by `delta` visible variants, wrapping around.
+    pub fn move_cursor(&mut self, delta: isize) {
+        if self.variants.is_empty() {
+            return;
+        }
+        let count = self.variants.len() as isize;
+        self.cursor = (self.cursor as isize + delta).rem_euclid(count) as usize;
+    }
+
+    /// Selects the variant at the `position` in the visible variants.
+    pub fn select(&mut self, position: usize) {
+        if let Some(variant) = self.variants.get(position) {
+            self.selected = Some(variant.index);
+        }
+    }
+
+    pub fn select_highlighted(&mut self) {
+        self.select(self.cursor);
+    }
+
+    /// The original index of the selected variant, if the player has made the choice
+    pub fn selected(&self) -> Option<usize> {
+        self.selected
+    }
+}
+
+/// The choice menu laid out for display, the title with the variants below it, centered on the screen
+// TODO: this is a stand-in, the original engine draws the variants over the `select` messagebox textures
+pub struct ChoiceMenuLabels {
+    title: Label,
+    variants: Vec<Label>,
+}
+
+impl ChoiceMenuLabels {
+    pub fn new(context: &PreRenderContext, font: &Arc<GpuFontLazy>, menu: &ChoiceMenu) -> Self {
+        Self {
+            title: Label::new(context, font, menu.title()),
+            variants: menu
+                .visible_variants()
+                .iter()
+                .map(|variant| Label::new(context, font, &variant.text))
+                .collect(),
+        }
+    }
+
+    /// Whether the labels show the texts of the `menu`
+    pub fn is_for(&self, menu: &ChoiceMenu) -> bool {
+        self.title.text() == menu.title()
+            && self
+                .variants
+                .iter()
+                .map(Label::text)
+                .eq(menu.visible_variants().iter().map(|v| v.text.as_str()))
+    }
+
+    /// Draw the menu, with the variant at the `cursor` highlighted
+    pub fn render(&self, pass: &mut RenderPass, cursor: usize) {
+        let line_count = 1 + self.variants.len();
+        let top = (VIRTUAL_CANVAS_SIZE_VEC.y - line_count as f32 * LINE_SPACING) / 2.0;
+        let line_position = |label: &Label, line: usize| {
+            let x = (VIRTUAL_CANVAS_SIZE_VEC.x - label.size().x) / 2.0;
+            vec2(x, top + line as f32 * LINE_SPACING)
+        };
+
+        self.title
+            .render(pass, line_position(&self.title, 0), FloatColor4::WHITE);
+        for (position, variant) in self.variants.iter().enumerate() {
+            let color = if position == cursor {
+                HIGHLIGHT_COLOR
+            } else {
+                FloatColor4::WHITE
+            };
+            variant.render(pass, line_position(variant, position + 1), color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winit::dpi::PhysicalSize;
+
+    use super::{ChoiceMenu, ChoiceMenuLabels};
+    use crate::{
+        adv::test_utils::font,
+        render::test_utils::{TestRenderer, create_task_pools},
+    };
+
+    fn menu(visibility_mask: i32) -> ChoiceMenu {
+        ChoiceMenu::new(
+            "What to do?".to_string(),
+            ["Run", "Hide", "Fight", "Pray"].map(String::from),
+            visibility_mask,
+        )
+    }
+
+    #[test]
+    fn all_visible() {
+        let mut menu = menu(0b1111);
+        assert_eq!(menu.visible_variants().len(), 4);
+        assert_eq!(menu.selected(), None);
+
+        menu.select(2);
+        assert_eq!(menu.selected(), Some(2));
+    }
+
+    #[test]
+    fn masked_variants_keep_their_index() {
+        // "Hide" & "Pray" are hidden
+        let mut menu = menu(0b0101);
+        let texts = menu
+            .visible_variants()
+            .iter()
+            .map(|v| v.text.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(texts, ["Run", "Fight"]);
+
+        // the second visible variant is the third one in the command
+        menu.select(1);
+        assert_eq!(menu.selected(), Some(2));
+    }
+
+    #[test]
+    fn cursor_wraps_around_visible_variants() {
+        let mut menu = menu(0b1010);
+
+        menu.move_cursor(-1);
+        assert_eq!(menu.cursor(), 1);
+        menu.select_highlighted();
+        assert_eq!(menu.selected(), Some(3));
+
+        menu.move_cursor(1);
+        menu.select_highlighted();
+        assert_eq!(menu.selected(), Some(1));
+    }
+
+    #[test]
+    fn out_of_range_selection_is_ignored() {
+        let mut menu = menu(0b0001);
+        menu.select(1);
+        assert_eq!(menu.selected(), None);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn labels_highlight_the_cursor() {
+        // 2 virtual pixels per pixel
+        let mut renderer = TestRenderer::new(PhysicalSize::new(960, 540));
+        // the glyphs are loaded in the background
+        create_task_pools();
+        let mut target = renderer.new_render_texture();
+
+        let other_menu = menu(0b0101);
+        // "Hide" & "Pray", with the cursor on "Pray"
+        let mut menu = menu(0b1010);
+        menu.move_cursor(1);
+        renderer.pre_render(|context| {
+            let labels = ChoiceMenuLabels::new(context, &font(), &menu);
+            assert!(labels.is_for(&menu));
+            assert!(!labels.is_for(&other_menu));
+
+            let mut pass = context.begin_pass(target.as_texture_target(), None, "choice_menu");
+            labels.render(&mut pass, menu.cursor());
+        });
+        let image = renderer.read(&target);
+
+        // the 3 lines are 60 pixels apart, centered vertically (starting at 450), with the glyphs 4 to 12 pixels below the top
+        // all the lines are centered horizontally, with a character (not a space) in the middle of the screen
+        let pixel = |line: u32| image.get_pixel(960 / 2, (450 + line * 60 + 8) / 2).0;
+        let is_white = |line| pixel(line).iter().all(|&channel| channel > 250);
+        assert!(is_white(0));
+        assert!(is_white(1));
+        // the highlight has hardly any blue
+        let [r, _, b, _] = pixel(2);
+        assert!(r > 250 && b < 60, "not highlighted: {:?}", pixel(2));
+    }
+}
@@ -2,9 +2,7 @@ use super::prelude::*;
 
 impl StartableCommand for command::runtime::CHARS {
     type StateInfo = ();
-    fn apply_state(&self, _state: &mut VmState) {
-        warn!("TODO: CHARS state: {:?}", self);
-    }
+    fn apply_state(&self, _state: &mut VmState) {}
 
     fn start(
         self,
@@ -12,9 +10,10 @@ impl StartableCommand for command::runtime::CHARS {
         _scenario: &Arc<Scenario>,
         _vm_state: &VmState,
         _state_info: (),
-        _adv_state: &mut AdvState,
+        adv_state: &mut AdvState,
     ) -> CommandStartResult {
-        warn!("TODO: CHARS: {:?}", self);
+        // TODO: the meaning of arg2 is unknown
+        adv_state.unlocks.unlock_character(self.arg1);
         self.token.finish().into()
     }
 }
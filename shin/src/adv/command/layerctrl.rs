@@ -1,4 +1,4 @@
-use shin_core::time::{Easing, Tween};
+use shin_core::time::Easing;
 use shin_render::shaders::types::RenderCloneCtx;
 
 use super::prelude::*;
@@ -27,13 +27,7 @@ impl StartableCommand for command::runtime::LAYERCTRL {
                     .properties
                     .get_property(self.property_id);
             }
-            VLayerIdRepr::Selected => {
-                affected_layers = layers.layerbank_allocator.layers_in_range(
-                    layers.current_plane,
-                    layers.layer_selection.from,
-                    layers.layer_selection.to,
-                )
-            }
+            VLayerIdRepr::Selected => affected_layers = layers.selected_layers(),
             VLayerIdRepr::Layer(layer_id) => {
                 affected_layers = layers.layerbank_allocator.layers_in_range(
                     layers.current_plane,
@@ -85,15 +79,8 @@ impl StartableCommand for command::runtime::LAYERCTRL {
             warn!("LAYERCTRL: ignore_wait is set, but not supported");
         }
 
-        let easing = match flags.easing() {
-            0 => Easing::Linear,
-            1 => Easing::SineIn,
-            2 => Easing::SineOut,
-            3 => Easing::SineInOut,
-            4 => Easing::Jump,
-            5 => Easing::Power(easing_param),
-            _ => panic!("LAYERCTRL: unknown easing function: {}", flags.easing()),
-        };
+        let easing = Easing::from_index(flags.easing(), easing_param)
+            .unwrap_or_else(|| panic!("LAYERCTRL: unknown easing function: {}", flags.easing()));
 
         let mut changed = false;
         let mut apply_to_properties = |properties: &mut LayerProperties| {
@@ -127,7 +114,8 @@ impl StartableCommand for command::runtime::LAYERCTRL {
                 tweener.fast_forward();
             }
 
-            tweener.enqueue(target_value as f32, Tween { duration, easing })
+            // plays after the tweens already queued for the property
+            properties.animate(self.property_id, to_value, duration, easing);
         };
 
         match self.layer_id.repr() {
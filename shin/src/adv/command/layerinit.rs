@@ -26,13 +26,7 @@ impl StartableCommand for command::runtime::LAYERINIT {
                     .properties
                     .init();
             }
-            VLayerIdRepr::Selected => {
-                affected_layers = layers.layerbank_allocator.layers_in_range(
-                    layers.current_plane,
-                    layers.layer_selection.from,
-                    layers.layer_selection.to,
-                )
-            }
+            VLayerIdRepr::Selected => affected_layers = layers.selected_layers(),
             VLayerIdRepr::Layer(layer_id) => {
                 affected_layers = layers.layerbank_allocator.layers_in_range(
                     layers.current_plane,
@@ -9,14 +9,14 @@ use tracing::error;
 
 use super::prelude::*;
 use crate::{
-    adv::vm_state::layers::LayerOperationTarget,
+    adv::vm_state::layers::{LayerOperationTarget, LayerSelection},
     layer::{LayerProperties, user::UserLayer},
 };
 
 pub struct LAYERLOAD {
     token: Option<command::token::LAYERLOAD>,
     state_info: LayerLoadStateInfo,
-    load_task: AsyncTask<UserLayer>,
+    load_task: AsyncTask<anyhow::Result<UserLayer>>,
 }
 
 struct LayerInfo {
@@ -81,7 +81,7 @@ impl StartableCommand for command::runtime::LAYERLOAD {
                 return state_info;
             }
             VLayerIdRepr::Selected => {
-                for layer in layers.layer_selection.iter() {
+                for layer in layers.layer_selection.iter().flat_map(LayerSelection::iter) {
                     add_layer(layer);
                 }
             }
@@ -186,6 +186,15 @@ impl UpdatableCommand for LAYERLOAD {
         let Some(layer) = self.load_task.poll_naive() else {
             return None;
         };
+        let layer = match layer {
+            Ok(layer) => layer,
+            Err(e) => {
+                // the scenario can go on without the layer, it just won't be shown
+                error!("Failed to load the layer: {:?}", e);
+                adv_state.allow_running_animations = true;
+                return Some(self.token.take().unwrap().finish());
+            }
+        };
 
         // NB: here the game also loads a wiper, but we don't support `LayerGroup`-level wiping
 
@@ -196,23 +205,41 @@ impl UpdatableCommand for LAYERLOAD {
         for info in &self.state_info.affected_layers {
             // NOTE: the original game does not remove the previous layer here, but we can't to this because we don't Arc everything
             // this should not be a problem because we replace the layer with this layerbank id at the end of the for loop iteration
-            let mut previous_layer =
+            let previous_layer =
                 plane_layer_group.remove_layer(info.operation_target.layerbank, Ticks::ZERO);
 
-            let mut previous_props = previous_layer
+            let previous_props = previous_layer
                 .as_ref()
                 .map(|layer| layer.properties().clone());
 
-            let mut layer = if info.already_the_same {
-                previous_layer.unwrap()
-            } else {
-                layer.render_clone(&mut context.pre_render.render_clone_ctx())
+            let mut layer = match (previous_layer, &layer) {
+                (Some(previous_layer), _) if info.already_the_same => previous_layer,
+                // another expression of the same bustup keeps the layer, so it doesn't restart blinking
+                (Some(UserLayer::Bustup(mut previous_layer)), UserLayer::Bustup(new_layer))
+                    if Arc::ptr_eq(
+                        previous_layer.inner_ref().bustup(),
+                        new_layer.inner_ref().bustup(),
+                    ) =>
+                {
+                    let new_layer = new_layer.inner_ref();
+                    previous_layer.inner_mut().set_expression(
+                        new_layer.expression_name(),
+                        new_layer.expression().cloned(),
+                    );
+                    previous_layer.into()
+                }
+                _ => {
+                    let mut layer = layer.render_clone(&mut context.pre_render.render_clone_ctx());
+                    // the bustups loaded together don't blink in unison, and blink the same way when replaying
+                    if let UserLayer::Bustup(layer) = &mut layer {
+                        layer
+                            .inner_mut()
+                            .set_blink_seed(info.operation_target.layer.raw().into());
+                    }
+                    layer
+                }
             };
 
-            if let UserLayer::Bustup(layer) = &mut layer {
-                // TODO: connect the BustupLayer to the lipsync machinery
-            }
-
             let mut properties = match (previous_props, info.keep_old_props) {
                 (Some(previous_props), true) => previous_props,
                 _ => {
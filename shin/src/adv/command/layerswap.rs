@@ -0,0 +1,62 @@
+use shin_core::vm::command::types::{LayerId, PlaneId};
+use tracing::warn;
+
+use super::prelude::*;
+use crate::adv::vm_state::layers::LayerOperationTargetList;
+
+pub struct LayerSwapStateInfo {
+    plane: PlaneId,
+    moved_layers: LayerOperationTargetList,
+}
+
+impl StartableCommand for command::runtime::LAYERSWAP {
+    type StateInfo = Option<LayerSwapStateInfo>;
+    fn apply_state(&self, state: &mut VmState) -> Option<LayerSwapStateInfo> {
+        let layer_id = |id: i32| u16::try_from(id).ok().and_then(LayerId::try_new);
+        let (Some(layer_1), Some(layer_2)) = (layer_id(self.layer_id_1), layer_id(self.layer_id_2))
+        else {
+            warn!(
+                "LAYERSWAP: layer ids {} and {} are not both in range, not swapping",
+                self.layer_id_1, self.layer_id_2
+            );
+            return None;
+        };
+
+        let layers = &mut state.layers;
+        let plane = layers.current_plane;
+
+        let moved_layers = layers.swap_layers(plane, layer_1, layer_2);
+
+        Some(LayerSwapStateInfo {
+            plane,
+            moved_layers,
+        })
+    }
+
+    fn start(
+        self,
+        context: &mut UpdateContext,
+        _scenario: &Arc<Scenario>,
+        _vm_state: &VmState,
+        state_info: Option<LayerSwapStateInfo>,
+        adv_state: &mut AdvState,
+    ) -> CommandStartResult {
+        let Some(state_info) = state_info else {
+            return self.token.finish().into();
+        };
+
+        adv_state.create_back_layer_group_if_needed(&mut context.pre_render.render_clone_ctx());
+
+        // the layers are stored by their layerbanks, so they already moved along with them
+        let layer_group = adv_state.plane_layer_group_mut(state_info.plane);
+        for &target in &state_info.moved_layers {
+            layer_group
+                .get_layer_mut(target.layerbank)
+                .expect("BUG: layerbank not found")
+                .properties_mut()
+                .set_layer_id(target.layer);
+        }
+
+        self.token.finish().into()
+    }
+}
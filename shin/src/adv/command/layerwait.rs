@@ -7,12 +7,17 @@ use shin_core::{
 use smallvec::SmallVec;
 
 use super::prelude::*;
-use crate::{adv::vm_state::layers::LayerOperationTargetList, layer::LayerProperties};
+use crate::{
+    adv::vm_state::layers::{LayerOperationTargetList, LayerSelection},
+    layer::{LayerProperties, user::UserLayer},
+};
 
 pub struct LAYERWAIT {
     plane: PlaneId,
     layer_id: VLayerId,
     affected_layers: LayerOperationTargetList,
+    /// The layers being unloaded with a delay are waited for too, even though they are already gone from the VM state
+    unloading_range: Option<LayerSelection>,
     properties: SmallVec<LayerProperty, { SMALL_LIST_SIZE }>,
     token: Option<command::token::LAYERWAIT>,
 }
@@ -29,13 +34,7 @@ impl StartableCommand for command::runtime::LAYERWAIT {
             | VLayerIdRepr::ScreenLayer
             | VLayerIdRepr::PageLayer
             | VLayerIdRepr::PlaneLayerGroup => {}
-            VLayerIdRepr::Selected => {
-                affected_layers = layers.layerbank_allocator.layers_in_range(
-                    layers.current_plane,
-                    layers.layer_selection.from,
-                    layers.layer_selection.to,
-                )
-            }
+            VLayerIdRepr::Selected => affected_layers = layers.selected_layers(),
             VLayerIdRepr::Layer(layer_id) => {
                 affected_layers = layers.layerbank_allocator.layers_in_range(
                     layers.current_plane,
@@ -56,11 +55,21 @@ impl StartableCommand for command::runtime::LAYERWAIT {
         affected_layers: LayerOperationTargetList,
         _adv_state: &mut AdvState,
     ) -> CommandStartResult {
+        let unloading_range = match self.layer_id.repr() {
+            VLayerIdRepr::Selected => vm_state.layers.layer_selection,
+            VLayerIdRepr::Layer(layer_id) => Some(LayerSelection {
+                from: layer_id,
+                to: layer_id,
+            }),
+            _ => None,
+        };
+
         Yield(
             LAYERWAIT {
                 plane: vm_state.layers.current_plane,
                 layer_id: self.layer_id,
                 affected_layers,
+                unloading_range,
                 properties: self.wait_properties,
                 token: Some(self.token),
             }
@@ -97,15 +106,28 @@ impl UpdatableCommand for LAYERWAIT {
                     .properties(),
             ),
             VLayerIdRepr::Selected | VLayerIdRepr::Layer(_) => {
-                self.affected_layers.iter().any(|target| {
-                    does_need_wait(
-                        adv_state
-                            .plane_layer_group(self.plane)
+                let plane_layer_group = adv_state.plane_layer_group(self.plane);
+
+                let is_unloading = self.unloading_range.is_some_and(|range| {
+                    plane_layer_group.is_unloading_in_range(range.from, range.to)
+                });
+
+                is_unloading
+                    || self.affected_layers.iter().any(|target| {
+                        let layer = plane_layer_group
                             .get_layer(target.layerbank)
-                            .expect("BUG: layerbank not found")
-                            .properties(),
-                    )
-                })
+                            .expect("BUG: layerbank not found");
+
+                        // the one-shot animations are waited for until they finish playing
+                        let is_animation_playing = match layer {
+                            UserLayer::Animation(animation) => {
+                                animation.inner_ref().is_playing_once()
+                            }
+                            _ => false,
+                        };
+
+                        is_animation_playing || does_need_wait(layer.properties())
+                    })
             }
         };
 
@@ -0,0 +1,37 @@
+use shin_core::{
+    format::scenario::info::MaskIdOpt,
+    vm::command::types::{MaskFlags, PlaneId},
+};
+
+use super::prelude::*;
+
+impl StartableCommand for command::runtime::MASKUNLOAD {
+    type StateInfo = PlaneId;
+
+    fn apply_state(&self, state: &mut VmState) -> PlaneId {
+        let layers = &mut state.layers;
+        let plane = layers.current_plane;
+
+        layers.plane_layergroups[plane].mask_id = MaskIdOpt::none();
+        layers.plane_layergroups[plane].mask_flags = MaskFlags::empty();
+
+        plane
+    }
+
+    fn start(
+        self,
+        context: &mut UpdateContext,
+        _scenario: &Arc<Scenario>,
+        _vm_state: &VmState,
+        state_info: PlaneId,
+        adv_state: &mut AdvState,
+    ) -> CommandStartResult {
+        adv_state.create_back_layer_group_if_needed(&mut context.pre_render.render_clone_ctx());
+
+        adv_state
+            .plane_layer_group_mut(state_info)
+            .clear_mask_texture();
+
+        self.token.finish().into()
+    }
+}
@@ -36,9 +36,11 @@ mod layerctrl;
 mod layerinit;
 mod layerload;
 mod layerselect;
+mod layerswap;
 mod layerunload;
 mod layerwait;
 mod maskload;
+mod maskunload;
 mod moviewait;
 mod msgclose;
 mod msginit;
@@ -49,7 +51,9 @@ mod notifyset;
 mod pageback;
 mod planeclear;
 mod planeselect;
+mod quiz;
 mod saveinfo;
+mod select;
 mod sepan;
 mod seplay;
 mod sestop;
@@ -65,6 +69,7 @@ mod unlock;
 mod voiceplay;
 mod wait;
 mod wipe;
+mod wipewait;
 
 use std::sync::Arc;
 
@@ -77,7 +82,8 @@ use shin_core::{
 
 use self::{
     layerload::LAYERLOAD, layerwait::LAYERWAIT, maskload::MASKLOAD, moviewait::MOVIEWAIT,
-    msgset::MSGSET, msgwait::MSGWAIT, sewait::SEWAIT, wait::WAIT, wipe::WIPE,
+    msgset::MSGSET, msgwait::MSGWAIT, quiz::QUIZ, select::SELECT, sewait::SEWAIT, wait::WAIT,
+    wipe::WIPE, wipewait::WIPEWAIT,
 };
 use crate::{
     adv::{AdvState, VmState},
@@ -121,7 +127,13 @@ pub enum ExecutingCommand {
     #[derivative(Debug = "transparent")]
     WIPE,
     #[derivative(Debug = "transparent")]
+    WIPEWAIT,
+    #[derivative(Debug = "transparent")]
     MASKLOAD,
+    #[derivative(Debug = "transparent")]
+    SELECT,
+    #[derivative(Debug = "transparent")]
+    QUIZ,
 }
 
 pub fn apply_command_state(command: RuntimeCommand, state: &mut VmState) {
@@ -145,9 +157,9 @@ pub fn apply_command_state(command: RuntimeCommand, state: &mut VmState) {
         MSGSIGNAL,
         // MSGSYNC,
         MSGCLOSE,
-        // SELECT,
+        SELECT,
         WIPE,
-        // WIPEWAIT,
+        WIPEWAIT,
         BGMPLAY,
         BGMSTOP,
         BGMVOL,
@@ -178,7 +190,7 @@ pub fn apply_command_state(command: RuntimeCommand, state: &mut VmState) {
         LAYERUNLOAD,
         LAYERCTRL,
         LAYERWAIT,
-        // LAYERSWAP,
+        LAYERSWAP,
         LAYERSELECT,
         MOVIEWAIT,
         // TRANSSET,
@@ -186,11 +198,11 @@ pub fn apply_command_state(command: RuntimeCommand, state: &mut VmState) {
         PAGEBACK,
         PLANESELECT,
         PLANECLEAR,
-        // MASKLOAD,
-        // MASKUNLOAD,
+        MASKLOAD,
+        MASKUNLOAD,
         CHARS,
         TIPSGET,
-        // QUIZ,
+        QUIZ,
         SHOWCHARS,
         NOTIFYSET,
         DEBUGOUT
@@ -225,9 +237,9 @@ pub fn apply_command_state_and_start(
         MSGSIGNAL,
         // MSGSYNC,
         MSGCLOSE,
-        // SELECT,
+        SELECT,
         WIPE,
-        // WIPEWAIT,
+        WIPEWAIT,
         BGMPLAY,
         BGMSTOP,
         BGMVOL,
@@ -258,7 +270,7 @@ pub fn apply_command_state_and_start(
         LAYERUNLOAD,
         LAYERCTRL,
         LAYERWAIT,
-        // LAYERSWAP,
+        LAYERSWAP,
         LAYERSELECT,
         MOVIEWAIT,
         // TRANSSET,
@@ -267,10 +279,10 @@ pub fn apply_command_state_and_start(
         PLANESELECT,
         PLANECLEAR,
         MASKLOAD,
-        // MASKUNLOAD,
+        MASKUNLOAD,
         CHARS,
         TIPSGET,
-        // QUIZ,
+        QUIZ,
         SHOWCHARS,
         NOTIFYSET,
         DEBUGOUT
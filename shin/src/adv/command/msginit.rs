@@ -14,12 +14,8 @@ impl StartableCommand for command::runtime::MSGINIT {
         _state_info: (),
         _adv_state: &mut AdvState,
     ) -> CommandStartResult {
+        // the style is not applied to the message layer right away: the next MSGSET passes it from the VM state,
+        // so that a messagebox of the previous type can slide out before the new one is shown
         self.token.finish().into()
-
-        // adv_state
-        //     .root_layer_group
-        //     .message_layer_mut()
-        //     .set_style(self.messagebox_style);
-        // self.token.finish().into()
     }
 }
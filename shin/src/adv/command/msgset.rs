@@ -24,6 +24,9 @@ impl StartableCommand for command::runtime::MSGSET {
         _state_info: (),
         adv_state: &mut AdvState,
     ) -> CommandStartResult {
+        let timestamp = adv_state.session_time;
+        adv_state.backlog.push(&self.text, timestamp);
+
         adv_state.root_layer_group.message_layer_mut().on_msgset(
             context.pre_render,
             scenario,
@@ -2,9 +2,7 @@ use super::prelude::*;
 
 impl StartableCommand for command::runtime::NOTIFYSET {
     type StateInfo = ();
-    fn apply_state(&self, _state: &mut VmState) {
-        warn!("TODO: NOTIFYSET state: {:?}", self);
-    }
+    fn apply_state(&self, _state: &mut VmState) {}
 
     fn start(
         self,
@@ -12,9 +10,9 @@ impl StartableCommand for command::runtime::NOTIFYSET {
         _scenario: &Arc<Scenario>,
         _vm_state: &VmState,
         _state_info: (),
-        _adv_state: &mut AdvState,
+        adv_state: &mut AdvState,
     ) -> CommandStartResult {
-        warn!("TODO: NOTIFYSET: {:?}", self);
+        adv_state.unlocks.set_notification(self.arg);
         self.token.finish().into()
     }
 }
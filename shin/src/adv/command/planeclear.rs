@@ -1,31 +1,12 @@
-use shin_core::{
-    format::scenario::info::MaskIdOpt,
-    vm::command::types::{LAYERS_COUNT, LayerId, PlaneId},
-};
-use shin_render::shaders::types::RenderCloneCtx;
+use shin_core::vm::command::types::PlaneId;
 
 use super::prelude::*;
 
 impl StartableCommand for command::runtime::PLANECLEAR {
     type StateInfo = PlaneId;
     fn apply_state(&self, state: &mut VmState) -> PlaneId {
-        let layers = &mut state.layers;
-        let plane = layers.current_plane;
-
-        let affected_layers = layers.layerbank_allocator.layers_in_range(
-            plane,
-            LayerId::new(0),
-            LayerId::new(LAYERS_COUNT as u16 - 1),
-        );
-
-        for &target in &affected_layers {
-            layers.layerbanks[target.layerbank].layer_type = None;
-            layers
-                .layerbank_allocator
-                .free_layerbank(plane, target.layer);
-        }
-
-        layers.plane_layergroups[plane].mask_id = MaskIdOpt::none();
+        let plane = state.layers.current_plane;
+        state.layers.clear_plane(plane);
 
         plane
     }
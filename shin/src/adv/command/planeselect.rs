@@ -1,11 +1,16 @@
-use shin_core::vm::command::types::{PLANES_COUNT, PlaneId};
+use tracing::error;
 
 use super::prelude::*;
 
 impl StartableCommand for command::runtime::PLANESELECT {
     type StateInfo = ();
     fn apply_state(&self, state: &mut VmState) {
-        state.layers.current_plane = self.plane_id;
+        if state.layers.select_plane(self.plane_id).is_none() {
+            error!(
+                "PLANESELECT: plane id {} is out of range, keeping plane {:?}",
+                self.plane_id, state.layers.current_plane
+            );
+        }
     }
 
     fn start(
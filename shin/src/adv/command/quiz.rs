@@ -0,0 +1,64 @@
+use std::fmt::{Debug, Formatter};
+
+use tracing::info;
+
+use super::prelude::*;
+use crate::adv::quiz::QuizState;
+
+pub struct QUIZ {
+    token: Option<command::token::QUIZ>,
+    quiz_id: i32,
+}
+
+impl StartableCommand for command::runtime::QUIZ {
+    type StateInfo = ();
+    fn apply_state(&self, _state: &mut VmState) {
+        // nothing to do
+    }
+
+    fn start(
+        self,
+        _context: &mut UpdateContext,
+        _scenario: &Arc<Scenario>,
+        _vm_state: &VmState,
+        _state_info: (),
+        adv_state: &mut AdvState,
+    ) -> CommandStartResult {
+        // TODO: there is no QuizLayer yet, the answer is picked with the arrow keys & enter, with only the number shown
+        let quiz = QuizState::new(self.arg);
+        info!("QUIZ: {}", quiz.quiz_id());
+
+        adv_state.quiz = Some(quiz);
+
+        Yield(
+            QUIZ {
+                token: Some(self.token),
+                quiz_id: self.arg,
+            }
+            .into(),
+        )
+    }
+}
+
+impl UpdatableCommand for QUIZ {
+    fn update(
+        &mut self,
+        _context: &mut UpdateContext,
+        _scenario: &Arc<Scenario>,
+        _vm_state: &VmState,
+        adv_state: &mut AdvState,
+        _is_fast_forwarding: bool,
+    ) -> Option<CommandResult> {
+        // NB: fast-forwarding doesn't answer the quiz, it's up to the player
+        let answer = adv_state.quiz.as_ref()?.answer()?;
+        adv_state.quiz = None;
+
+        Some(self.token.take().unwrap().finish(answer))
+    }
+}
+
+impl Debug for QUIZ {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("QUIZ").field(&self.quiz_id).finish()
+    }
+}
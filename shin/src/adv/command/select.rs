@@ -0,0 +1,83 @@
+use std::fmt::{Debug, Formatter};
+
+use tracing::info;
+
+use super::prelude::*;
+use crate::adv::choice_menu::ChoiceMenu;
+
+/// Returned to the scenario when there is no variant to choose from, not matching any variant index
+const NO_CHOICE: i32 = -1;
+
+pub struct SELECT {
+    token: Option<command::token::SELECT>,
+}
+
+impl StartableCommand for command::runtime::SELECT {
+    type StateInfo = ();
+    fn apply_state(&self, _state: &mut VmState) {
+        // nothing to do
+    }
+
+    fn start(
+        self,
+        _context: &mut UpdateContext,
+        _scenario: &Arc<Scenario>,
+        _vm_state: &VmState,
+        _state_info: (),
+        adv_state: &mut AdvState,
+    ) -> CommandStartResult {
+        let menu = ChoiceMenu::new(
+            self.choice_title,
+            self.variants,
+            self.choice_visibility_mask,
+        );
+        if menu.visible_variants().is_empty() {
+            // there would be nothing to choose from, waiting for a choice would get stuck forever
+            warn!(
+                "SELECT {:?} has all the variants hidden, returning {}",
+                menu.title(),
+                NO_CHOICE
+            );
+            return self.token.finish(NO_CHOICE).into();
+        }
+
+        let variants = menu
+            .visible_variants()
+            .iter()
+            .map(|v| v.text.as_str())
+            .collect::<Vec<_>>();
+        info!("SELECT: {:?} {:?}", menu.title(), variants);
+
+        adv_state.choice_menu = Some(menu);
+
+        Yield(
+            SELECT {
+                token: Some(self.token),
+            }
+            .into(),
+        )
+    }
+}
+
+impl UpdatableCommand for SELECT {
+    fn update(
+        &mut self,
+        _context: &mut UpdateContext,
+        _scenario: &Arc<Scenario>,
+        _vm_state: &VmState,
+        adv_state: &mut AdvState,
+        _is_fast_forwarding: bool,
+    ) -> Option<CommandResult> {
+        // NB: fast-forwarding doesn't pick a choice, it's up to the player
+        let index = adv_state.choice_menu.as_ref()?.selected()?;
+        adv_state.choice_menu = None;
+
+        Some(self.token.take().unwrap().finish(index as i32))
+    }
+}
+
+impl Debug for SELECT {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("SELECT").finish()
+    }
+}
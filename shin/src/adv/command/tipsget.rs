@@ -2,9 +2,7 @@ use super::prelude::*;
 
 impl StartableCommand for command::runtime::TIPSGET {
     type StateInfo = ();
-    fn apply_state(&self, _state: &mut VmState) {
-        warn!("TODO: TIPSGET state: {:?}", self);
-    }
+    fn apply_state(&self, _state: &mut VmState) {}
 
     fn start(
         self,
@@ -12,9 +10,9 @@ impl StartableCommand for command::runtime::TIPSGET {
         _scenario: &Arc<Scenario>,
         _vm_state: &VmState,
         _state_info: (),
-        _adv_state: &mut AdvState,
+        adv_state: &mut AdvState,
     ) -> CommandStartResult {
-        warn!("TODO: TIPSGET: {:?}", self);
+        adv_state.unlocks.unlock_tips(&self.tip_ids);
         self.token.finish().into()
     }
 }
@@ -2,9 +2,7 @@ use super::prelude::*;
 
 impl StartableCommand for command::runtime::TROPHY {
     type StateInfo = ();
-    fn apply_state(&self, _state: &mut VmState) {
-        warn!("TODO: TROPHY state: {:?}", self);
-    }
+    fn apply_state(&self, _state: &mut VmState) {}
 
     fn start(
         self,
@@ -12,9 +10,9 @@ impl StartableCommand for command::runtime::TROPHY {
         _scenario: &Arc<Scenario>,
         _vm_state: &VmState,
         _state_info: (),
-        _adv_state: &mut AdvState,
+        adv_state: &mut AdvState,
     ) -> CommandStartResult {
-        warn!("TODO: TROPHY: {:?}", self);
+        adv_state.unlocks.award_trophy(self.trophy_id);
         self.token.finish().into()
     }
 }
@@ -1,10 +1,11 @@
+use shin_core::format::save::UnlockType;
+
 use super::prelude::*;
 
 impl StartableCommand for command::runtime::UNLOCK {
     type StateInfo = ();
-    fn apply_state(&self, _state: &mut VmState) {
-        warn!("TODO: UNLOCK state: {:?}", self);
-    }
+    // the unlocks are global progression, kept in the `AdvState` to survive the quick-loads
+    fn apply_state(&self, _state: &mut VmState) {}
 
     fn start(
         self,
@@ -12,9 +13,13 @@ impl StartableCommand for command::runtime::UNLOCK {
         _scenario: &Arc<Scenario>,
         _vm_state: &VmState,
         _state_info: (),
-        _adv_state: &mut AdvState,
+        adv_state: &mut AdvState,
     ) -> CommandStartResult {
-        warn!("TODO: UNLOCK: {:?}", self);
+        let Some(unlock_type) = UnlockType::from_raw(self.unlock_type) else {
+            warn!("UNLOCK: unknown unlock type {}", self.unlock_type);
+            return self.token.finish().into();
+        };
+        adv_state.unlocks.unlock(unlock_type, &self.unlock_indices);
         self.token.finish().into()
     }
 }
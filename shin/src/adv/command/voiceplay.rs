@@ -1,20 +1,47 @@
 use super::prelude::*;
+use crate::{
+    asset::bustup::CharacterId,
+    audio::{VoicePlayFlags, voice_asset_path},
+};
 
 impl StartableCommand for command::runtime::VOICEPLAY {
     type StateInfo = ();
     fn apply_state(&self, _state: &mut VmState) {
-        warn!("TODO: VOICEPLAY state: {:?}", self);
+        // voices are not persisted
     }
 
     fn start(
         self,
-        _context: &mut UpdateContext,
-        _scenario: &Arc<Scenario>,
+        context: &mut UpdateContext,
+        scenario: &Arc<Scenario>,
         _vm_state: &VmState,
         _state_info: (),
-        _adv_state: &mut AdvState,
+        adv_state: &mut AdvState,
     ) -> CommandStartResult {
-        warn!("TODO: VOICEPLAY: {:?}", self);
+        // TODO: character muting is not supported yet
+        let flags = VoicePlayFlags::from_bits_truncate(self.flags);
+        let lipsync_characters = if flags.contains(VoicePlayFlags::ENABLE_CHARACTER_LIPSYNC) {
+            scenario
+                .info_tables()
+                .lipsync_character_ids(self.name.as_str())
+                .iter()
+                .map(|&id| CharacterId::new(id as i32))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let path = voice_asset_path(self.name.as_str());
+
+        // TODO: sync - bad!!
+        if let Err(e) = context.asset_server.load_sync(&path).and_then(|voice| {
+            adv_state
+                .message_layer_mut()
+                .voice_player_mut()
+                .play_standalone(self.name.as_str(), voice, self.volume, lipsync_characters)
+        }) {
+            warn!("Failed to play voice {}: {:?}", path, e);
+        }
+
         self.token.finish().into()
     }
 }
@@ -0,0 +1,51 @@
+use super::prelude::*;
+
+/// Waits for the transition started by a `WIPE` with [`WipeFlags::DONT_WAIT`](shin_core::vm::command::types::WipeFlags::DONT_WAIT)
+#[derive(Debug)]
+pub struct WIPEWAIT {
+    token: Option<command::token::WIPEWAIT>,
+}
+
+impl StartableCommand for command::runtime::WIPEWAIT {
+    type StateInfo = ();
+    fn apply_state(&self, _state: &mut VmState) {
+        // nothing to do
+    }
+
+    fn start(
+        self,
+        _context: &mut UpdateContext,
+        _scenario: &Arc<Scenario>,
+        _vm_state: &VmState,
+        _state_info: (),
+        adv_state: &mut AdvState,
+    ) -> CommandStartResult {
+        if !adv_state.screen_layer().is_transition_active() {
+            return self.token.finish().into();
+        }
+
+        Yield(
+            WIPEWAIT {
+                token: Some(self.token),
+            }
+            .into(),
+        )
+    }
+}
+
+impl UpdatableCommand for WIPEWAIT {
+    fn update(
+        &mut self,
+        _context: &mut UpdateContext,
+        _scenario: &Arc<Scenario>,
+        _vm_state: &VmState,
+        adv_state: &mut AdvState,
+        _is_fast_forwarding: bool,
+    ) -> Option<CommandResult> {
+        if adv_state.screen_layer().is_transition_active() {
+            None
+        } else {
+            Some(self.token.take().unwrap().finish())
+        }
+    }
+}
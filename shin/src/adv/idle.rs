@@ -0,0 +1,103 @@
+use std::time::Duration;
+
+use shin_core::time::Ticks;
+
+/// Tells when the player has left the game alone for too long, to start an attract sequence
+///
+/// Disabled unless a timeout is set.
+#[derive(Debug, Clone, Default)]
+pub struct IdleTimer {
+    timeout: Option<Duration>,
+    idle_time: Duration,
+    /// The timeout only fires once until the next input, so that the attract sequence is not restarted over and over
+    fired: bool,
+}
+
+impl IdleTimer {
+    pub fn new(timeout: Option<Duration>) -> Self {
+        Self {
+            timeout,
+            ..Self::default()
+        }
+    }
+
+    /// Count the frame as idle unless there was any input, returning `true` when the timeout is reached
+    ///
+    /// The time spent on a choice prompt is not counted, as the player is expected to think there.
+    pub fn update(&mut self, delta_ticks: Ticks, had_input: bool, in_choice: bool) -> bool {
+        let Some(timeout) = self.timeout else {
+            return false;
+        };
+
+        if had_input || in_choice {
+            self.idle_time = Duration::ZERO;
+            self.fired = false;
+            return false;
+        }
+
+        self.idle_time += delta_ticks.as_duration();
+        if self.fired || self.idle_time < timeout {
+            return false;
+        }
+
+        self.fired = true;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use shin_core::time::Ticks;
+
+    use super::IdleTimer;
+
+    /// Runs the timer for the given number of seconds, returning how many times it fired
+    fn idle(timer: &mut IdleTimer, seconds: u32, in_choice: bool) -> usize {
+        (0..seconds * 60)
+            .filter(|_| timer.update(Ticks::from_u32(1), false, in_choice))
+            .count()
+    }
+
+    #[test]
+    fn fires_after_the_timeout() {
+        let mut timer = IdleTimer::new(Some(Duration::from_secs(30)));
+
+        assert_eq!(idle(&mut timer, 29, false), 0);
+        assert_eq!(idle(&mut timer, 2, false), 1);
+        // not again until there is some input
+        assert_eq!(idle(&mut timer, 60, false), 0);
+
+        timer.update(Ticks::from_u32(1), true, false);
+        assert_eq!(idle(&mut timer, 29, false), 0);
+        assert_eq!(idle(&mut timer, 2, false), 1);
+    }
+
+    #[test]
+    fn input_resets_the_timer() {
+        let mut timer = IdleTimer::new(Some(Duration::from_secs(30)));
+
+        assert_eq!(idle(&mut timer, 20, false), 0);
+        timer.update(Ticks::from_u32(1), true, false);
+        assert_eq!(idle(&mut timer, 20, false), 0);
+        assert_eq!(idle(&mut timer, 11, false), 1);
+    }
+
+    #[test]
+    fn choices_do_not_fire() {
+        let mut timer = IdleTimer::new(Some(Duration::from_secs(30)));
+
+        assert_eq!(idle(&mut timer, 20, false), 0);
+        assert_eq!(idle(&mut timer, 120, true), 0);
+        // the time before the choice is not counted either
+        assert_eq!(idle(&mut timer, 20, false), 0);
+    }
+
+    #[test]
+    fn disabled_by_default() {
+        let mut timer = IdleTimer::default();
+
+        assert_eq!(idle(&mut timer, 3600, false), 0);
+    }
+}
@@ -1,24 +1,44 @@
 pub mod assets;
+pub mod backlog;
+pub mod choice_menu;
 mod command;
+pub mod idle;
+pub mod playtime;
+pub mod quiz;
+pub mod quiz_label;
+mod syscall;
+#[cfg(test)]
+pub mod test_utils;
 mod vm_state;
 
-use std::{borrow::Cow, sync::Arc};
+use std::{
+    borrow::Cow,
+    path::PathBuf,
+    sync::{Arc, mpsc},
+    time::Duration,
+};
 
+use anyhow::{Context, Result};
 pub use command::{CommandStartResult, ExecutingCommand, StartableCommand, UpdatableCommand};
 use egui::Window;
 use enum_map::{Enum, EnumMap, enum_map};
-use glam::Mat4;
+use glam::{Mat4, vec2};
 use itertools::Itertools;
+use parking_lot::Mutex;
 use shin_audio::AudioManager;
 use shin_core::{
-    format::scenario::{Scenario, instruction_elements::CodeAddress},
-    primitives::color::UnormColor,
+    format::{
+        save::UnlockState,
+        scenario::{Scenario, instruction_elements::CodeAddress},
+    },
+    primitives::color::{FloatColor4, UnormColor},
+    time::Tween,
     vm::{
-        Scripter,
+        Scripter, ScripterSnapshot,
         breakpoint::BreakpointObserver,
         command::{
             CommandResult,
-            types::{LayerId, PLANES_COUNT, PlaneId, VLayerId, VLayerIdRepr},
+            types::{LayerId, PLANES_COUNT, PlaneId, VLayerId, VLayerIdRepr, Volume},
         },
     },
 };
@@ -28,22 +48,32 @@ use shin_render::{
     shaders::types::{RenderClone as _, RenderCloneCtx},
 };
 use smallvec::{SmallVec, smallvec};
-use tracing::{debug, warn};
+use tracing::{debug, error, info, warn};
 use vm_state::layers::ITER_VLAYER_SMALL_VECTOR_SIZE;
 pub use vm_state::{VmState, layers::LayerSelection};
 use winit::keyboard::KeyCode;
 
 use crate::{
-    adv::assets::AdvAssets,
+    adv::{
+        assets::{AdvAssets, AdvFonts},
+        backlog::{Backlog, TranscriptFormat},
+        choice_menu::{ChoiceMenu, ChoiceMenuLabels},
+        idle::IdleTimer,
+        playtime::Playtime,
+        quiz::QuizState,
+        quiz_label::QuizLabel,
+        syscall::{SyscallRequest, syscall_channel},
+    },
     app::AppAction,
-    audio::{BgmPlayer, SePlayer, VoicePlayer},
+    audio::{BgmPlayer, SePlayer, VoiceCaptionTable, VoicePlayer, voice_asset_path},
     layer::{
-        AnyLayer, AnyLayerMut, Layer as _, LayerGroup, PageLayer, RootLayerGroup, ScreenLayer,
-        message_layer::MessageLayer, render_layer_without_bg, render_params::TransformParams,
-        user::UserLayer,
+        AnyLayer, AnyLayerMut, DrawableLayer as _, FadeOverlay, Layer as _, LayerGroup, PageLayer,
+        RootLayerGroup, ScreenLayer, message_layer::MessageLayer, render_layer_without_bg,
+        render_params::TransformParams, user::UserLayer,
     },
     render::{
-        PreRenderContext,
+        PreRenderContext, VIRTUAL_CANVAS_SIZE_VEC,
+        label::Label,
         overlay::{OverlayCollector, OverlayVisitable},
     },
     update::{AdvUpdatable, AdvUpdateContext, Updatable, UpdateContext},
@@ -89,11 +119,39 @@ pub struct Adv {
     // action_state: ActionState<AdvMessageAction>,
     current_command: Option<ExecutingCommand>,
     fast_forward_to_bp: Option<BreakpointObserver>,
+    quick_save: Option<QuickSave>,
+    /// Length of the backlog before the current command was started
+    current_command_backlog_len: usize,
+    /// Shared with the syscall handler, for [`call_id::PLAYTIME`](syscall::call_id::PLAYTIME)
+    playtime: Arc<Mutex<Playtime>>,
+    idle_timer: IdleTimer,
+    attract_entry_point: CodeAddress,
+    transcript_path: PathBuf,
+    transcript_format: TranscriptFormat,
+    /// The syscalls the scenario issued, waiting to be applied
+    syscall_requests: mpsc::Receiver<SyscallRequest>,
+    /// The VM failed, so the scenario is not run anymore, see [`Adv::halt`]
+    is_halted: bool,
+}
+
+/// A single save slot kept in memory, not tied to any scenario command
+struct QuickSave {
+    scripter: ScripterSnapshot,
+    vm_state: VmState,
+    backlog: Backlog,
+    playtime: Playtime,
 }
 
 impl Adv {
-    pub fn new(audio_manager: Arc<AudioManager>, assets: AdvAssets, scripter: Scripter) -> Self {
+    pub fn new(
+        audio_manager: Arc<AudioManager>,
+        assets: AdvAssets,
+        mut scripter: Scripter,
+    ) -> Self {
         let scenario = assets.scenario.clone();
+        let playtime = Arc::new(Mutex::new(Playtime::new()));
+        let (syscall_handler, syscall_requests) = syscall_channel(playtime.clone());
+        scripter.set_syscall_handler(syscall_handler);
         let vm_state = VmState::new();
         let adv_state = AdvState::new(audio_manager, assets);
 
@@ -104,36 +162,355 @@ impl Adv {
             adv_state,
             current_command: None,
             fast_forward_to_bp: None,
+            quick_save: None,
+            current_command_backlog_len: 0,
+            playtime,
+            idle_timer: IdleTimer::default(),
+            attract_entry_point: CodeAddress(0),
+            transcript_path: PathBuf::from("transcript.md"),
+            transcript_format: TranscriptFormat::Markdown,
+            syscall_requests,
+            is_halted: false,
+        }
+    }
+
+    /// Apply the syscalls issued while the scenario was running, in order
+    fn apply_syscall_requests(&mut self) {
+        for request in self.syscall_requests.try_iter() {
+            debug!("Applying syscall {:?}", request);
+            match request {
+                SyscallRequest::ScreenShake {
+                    amplitude,
+                    frequency,
+                    duration,
+                } => self
+                    .adv_state
+                    .screen_layer_mut()
+                    .start_shake(amplitude, frequency, duration),
+                SyscallRequest::ScreenFade { color, tween } => {
+                    self.adv_state.fade_screen(color, tween)
+                }
+                SyscallRequest::AttachLayer { child, parent } => {
+                    self.attach_layer(child, Some(parent))
+                }
+                SyscallRequest::DetachLayer { layer } => self.attach_layer(layer, None),
+                SyscallRequest::SetRenderOrder {
+                    layer,
+                    render_order,
+                } => self.set_render_order(layer, render_order),
+            }
+        }
+    }
+
+    /// Attach the `child` layer of the current plane to the `parent` one, or detach it if there is no parent
+    fn attach_layer(&mut self, child: LayerId, parent: Option<LayerId>) {
+        let layers = &self.vm_state.layers;
+        let plane = layers.current_plane;
+        let layerbank = |layer| layers.layerbank_allocator.get_layerbank_id(plane, layer);
+
+        let Some(child_bank) = layerbank(child) else {
+            warn!(?child, "Attaching a layer that is not loaded");
+            return;
+        };
+        let group = self.adv_state.plane_layer_group_mut(plane);
+        match parent {
+            None => group.detach_layer(child_bank),
+            Some(parent) => {
+                let Some(parent_bank) = layerbank(parent) else {
+                    warn!(?parent, "Attaching to a layer that is not loaded");
+                    return;
+                };
+                if let Err(error) = group.attach_layer(child_bank, parent_bank) {
+                    warn!(?child, ?parent, "Could not attach the layer: {}", error);
+                }
+            }
+        }
+    }
+
+    /// Override the render position of the `layer` of the current plane, see [`LayerProperties::set_render_order`](crate::layer::LayerProperties::set_render_order)
+    fn set_render_order(&mut self, layer: LayerId, render_order: Option<i32>) {
+        let layers = &self.vm_state.layers;
+        let plane = layers.current_plane;
+        let Some(user_layer) = layers
+            .layerbank_allocator
+            .get_layerbank_id(plane, layer)
+            .and_then(|layerbank| {
+                self.adv_state
+                    .plane_layer_group_mut(plane)
+                    .get_layer_mut(layerbank)
+            })
+        else {
+            warn!(?layer, "Reordering a layer that is not loaded");
+            return;
+        };
+
+        user_layer.properties_mut().set_render_order(render_order);
+    }
+
+    /// Set the table used to look up captions for the voices played
+    pub fn set_voice_captions(&mut self, captions: Arc<VoiceCaptionTable>) {
+        self.adv_state
+            .message_layer_mut()
+            .voice_player_mut()
+            .set_caption_table(captions);
+    }
+
+    /// Dampen the raster & ripple layer effects and disable the ghosting, see [`AdvUpdateContext::reduced_motion`]
+    pub fn set_reduced_motion(&mut self, reduced_motion: bool) {
+        self.adv_state.reduced_motion = reduced_motion;
+    }
+
+    /// Fade the translucent layer groups (like the planes) as a whole, see [`AdvUpdateContext::group_opacity`]
+    pub fn set_group_opacity(&mut self, group_opacity: bool) {
+        self.adv_state.group_opacity = group_opacity;
+    }
+
+    /// Configure where and in which format the transcript is exported to
+    pub fn set_transcript_output(&mut self, path: PathBuf, format: TranscriptFormat) {
+        self.transcript_path = path;
+        self.transcript_format = format;
+    }
+
+    /// Write all the messages shown so far to the transcript file
+    pub fn export_transcript(&self) {
+        match self
+            .adv_state
+            .backlog
+            .write_transcript(&self.transcript_path, self.transcript_format)
+        {
+            Ok(()) => info!(
+                "Exported the transcript to {}",
+                self.transcript_path.display()
+            ),
+            Err(e) => error!(
+                "Failed to export the transcript to {}: {}",
+                self.transcript_path.display(),
+                e
+            ),
         }
     }
 
+    /// Replay the voice of the backlog entry at `index`, without affecting the current message or the scenario execution
+    ///
+    /// Returns `false` for the entries without a voice, which can't be replayed.
+    pub fn replay_backlog_voice(&mut self, context: &UpdateContext, index: usize) -> bool {
+        let Some(voice_name) = self
+            .adv_state
+            .backlog
+            .entries()
+            .get(index)
+            .and_then(|entry| entry.voice.clone())
+        else {
+            return false;
+        };
+
+        let path = voice_asset_path(&voice_name);
+        // TODO: sync - bad!!
+        match context.asset_server.load_sync(&path).and_then(|voice| {
+            self.adv_state
+                .message_layer_mut()
+                .voice_player_mut()
+                .replay(voice, Volume::default())
+        }) {
+            Ok(()) => true,
+            Err(e) => {
+                warn!("Failed to replay voice {}: {:?}", path, e);
+                false
+            }
+        }
+    }
+
+    /// Replay the voice of the latest message that has one, see [`Self::replay_backlog_voice`]
+    pub fn replay_last_voice(&mut self, context: &UpdateContext) -> bool {
+        let Some(index) = self
+            .adv_state
+            .backlog
+            .entries()
+            .iter()
+            .rposition(|entry| entry.voice.is_some())
+        else {
+            return false;
+        };
+
+        self.replay_backlog_voice(context, index)
+    }
+
+    /// Jump to `entry_point` to start an attract sequence when there is no input for `timeout`
+    ///
+    /// Like [`Scripter::unsafe_set_position`], the jump does not clean up after the interrupted scene, so the sequence has to set up the layers it needs.
+    pub fn set_idle_timeout(&mut self, timeout: Duration, entry_point: CodeAddress) {
+        self.idle_timer = IdleTimer::new(Some(timeout));
+        self.attract_entry_point = entry_point;
+    }
+
+    fn start_attract_sequence(&mut self) {
+        info!(
+            "No input for a while, starting the attract sequence at {:?}",
+            self.attract_entry_point
+        );
+
+        self.scripter.unsafe_set_position(self.attract_entry_point);
+        self.current_command = None;
+        self.fast_forward_to_bp = None;
+    }
+
     pub fn fast_forward_to(&mut self, addr: CodeAddress) {
         assert!(self.fast_forward_to_bp.is_none());
         self.fast_forward_to_bp = Some(self.scripter.add_breakpoint(addr).into());
     }
 
+    /// Stop running the scenario after the VM failed, as its state can't be trusted anymore.
+    ///
+    /// The scene stays on the screen as it was, and a quick-load can still bring the game back to a good state.
+    fn halt(&mut self, error: anyhow::Error) {
+        error!(
+            "The scenario failed at {:?}, halting it: {:?}",
+            self.scripter.position(),
+            error
+        );
+        self.is_halted = true;
+        self.current_command = None;
+    }
+
+    /// Save the current state to the quick-save slot, replacing the previous quick-save
+    pub fn quick_save(&mut self) {
+        debug!("Quick-saving at {:?}", self.scripter.position());
+
+        // NB: the snapshot points to the currently executing command, so it will be started anew on quick-load
+        // the state changes it made to the `VmState` are already applied, so this doesn't break anything
+        // the messages it added to the backlog would be added again though, so leave them out
+        let mut backlog = self.adv_state.backlog.clone();
+        if self.current_command.is_some() {
+            backlog.truncate(self.current_command_backlog_len);
+        }
+
+        self.quick_save = Some(QuickSave {
+            scripter: self.scripter.snapshot(),
+            vm_state: self.vm_state.clone(),
+            backlog,
+            playtime: *self.playtime.lock(),
+        });
+    }
+
+    /// Restore the state from the quick-save slot
+    ///
+    /// Returns `false` if nothing has been quick-saved yet. If the scene can't be restored, the game goes on as if nothing was loaded.
+    pub fn quick_load(&mut self, context: &mut UpdateContext) -> Result<bool> {
+        let Some(quick_save) = &self.quick_save else {
+            warn!("Quick-load requested, but nothing was quick-saved");
+            return Ok(false);
+        };
+
+        debug!("Quick-loading at {:?}", quick_save.scripter.position());
+
+        self.adv_state
+            .restore_from_vm_state(context, &self.scenario, &quick_save.vm_state)
+            .context("Failed to restore the scene")?;
+
+        self.scripter.restore(&quick_save.scripter);
+        self.vm_state = quick_save.vm_state.clone();
+        self.adv_state.backlog = quick_save.backlog.clone();
+        *self.playtime.lock() = quick_save.playtime;
+        self.current_command = None;
+        self.fast_forward_to_bp = None;
+        self.is_halted = false;
+
+        Ok(true)
+    }
+
+    /// Go on with a changed version of the scenario, for the hot-reloading
+    ///
+    /// Like with a quick-load, the current command is started again, now from the new scenario, and the scene is restored from the VM state.
+    /// See [`Scripter::set_scenario`] for the changes this makes sense for.
+    pub fn reload_scenario(
+        &mut self,
+        context: &mut UpdateContext,
+        scenario: Arc<Scenario>,
+    ) -> Result<()> {
+        info!("Reloading the scenario at {:?}", self.scripter.position());
+
+        let snapshot = self.scripter.snapshot();
+        // the messages added by the current command will be added again
+        if self.current_command.is_some() {
+            self.adv_state
+                .backlog
+                .truncate(self.current_command_backlog_len);
+        }
+
+        self.scenario = scenario;
+        self.scripter.set_scenario(&self.scenario);
+        self.scripter.restore(&snapshot);
+        self.current_command = None;
+        self.is_halted = false;
+
+        self.adv_state
+            .restore_from_vm_state(context, &self.scenario, &self.vm_state)
+            .context("Failed to restore the scene")
+    }
+
     // TODO: impl Scene for Adv
     #[tracing::instrument(skip_all)]
     pub fn render(&self, pass: &mut RenderPass) {
         self.adv_state.render(pass);
     }
 
+    /// Render everything except for the UI, see [`AdvState::render_scene`]
+    pub fn render_scene(&self, pass: &mut RenderPass) {
+        self.adv_state.render_scene(pass);
+    }
+
+    /// Render the UI over the scene, see [`AdvState::render_ui`]
+    pub fn render_ui(&self, pass: &mut RenderPass) {
+        self.adv_state.render_ui(pass);
+    }
+
     pub fn handle_input(&mut self, state: EnumMap<AppAction, ActionState>, is_focused: bool) {
         if !is_focused {
             return;
         }
 
+        if let Some(choice_menu) = &mut self.adv_state.choice_menu {
+            if state[AppAction::AnyUp].is_clicked {
+                choice_menu.move_cursor(-1);
+            }
+            if state[AppAction::AnyDown].is_clicked {
+                choice_menu.move_cursor(1);
+            }
+            if state[AppAction::Enter].is_clicked {
+                choice_menu.select_highlighted();
+            }
+            return;
+        }
+        if let Some(quiz) = &mut self.adv_state.quiz {
+            // the same way as in the choice menu, down goes to the next answer
+            if state[AppAction::AnyUp].is_clicked {
+                quiz.step_pending_answer(-1);
+            }
+            if state[AppAction::AnyDown].is_clicked {
+                quiz.step_pending_answer(1);
+            }
+            if state[AppAction::Enter].is_clicked {
+                quiz.confirm_pending_answer();
+            }
+            return;
+        }
+
         if state[AppAction::Enter].is_clicked {
-            self.adv_state.message_layer_mut().try_advance();
+            let skip = state[AppAction::HoldSkip].is_held || self.fast_forward_to_bp.is_some();
+            self.adv_state.message_layer_mut().try_advance(skip);
         }
     }
 
     // TODO: impl Scene for Adv
+    /// Run the scenario for a frame, `is_active` being `false` when the game is paused or minimized
     pub fn update(
         &mut self,
         context: &mut UpdateContext,
         input_state: EnumMap<AppAction, ActionState>,
+        is_active: bool,
     ) {
+        self.playtime.lock().update(context.delta_ticks, is_active);
+
         // self.action_state.update(context.raw_input_state);
 
         let fast_forward_button_held = input_state[AppAction::HoldSkip].is_held;
@@ -151,6 +528,31 @@ impl Adv {
         // TODO: tasks from task pool can steal focus
         self.handle_input(input_state, true);
 
+        let had_input = input_state.values().any(|state| state.is_held);
+        let in_choice = matches!(self.current_command, Some(ExecutingCommand::SELECT(_)));
+        if self
+            .idle_timer
+            .update(context.delta_ticks, had_input, in_choice)
+        {
+            self.start_attract_sequence();
+        }
+
+        if input_state[AppAction::QuickSave].is_clicked {
+            self.quick_save();
+        }
+        if input_state[AppAction::QuickLoad].is_clicked {
+            if let Err(e) = self.quick_load(context) {
+                error!("Quick-load failed: {:?}", e);
+            }
+        }
+        if input_state[AppAction::ExportTranscript].is_clicked {
+            self.export_transcript();
+        }
+        // there is no backlog UI yet to pick the voice from
+        if input_state[AppAction::ReplayVoice].is_clicked {
+            self.replay_last_voice(context);
+        }
+
         if fast_forward_button_held || self.fast_forward_to_bp.is_some() {
             self.adv_state.root_layer_group_mut().fast_forward();
             if let Some(back_layer_group) = &mut self.adv_state.back_layer_group {
@@ -159,7 +561,7 @@ impl Adv {
         }
 
         let mut result = CommandResult::None;
-        loop {
+        while !self.is_halted {
             // check the fast-forward breakpoint; delete if hit
             if self
                 .fast_forward_to_bp
@@ -173,7 +575,7 @@ impl Adv {
             let is_fast_forwarding = fast_forward_button_held || self.fast_forward_to_bp.is_some();
 
             // TODO: maybe yield if spent too much time in this loop?
-            let runtime_command = if let Some(command) = &mut self.current_command {
+            let run_result = if let Some(command) = &mut self.current_command {
                 match command.update(
                     context,
                     &self.scenario,
@@ -184,13 +586,23 @@ impl Adv {
                     None => break,
                     Some(result) => {
                         self.current_command = None;
-                        self.scripter.run(result).expect("scripter run failed")
+                        self.scripter.run(result)
                     }
                 }
             } else {
-                self.scripter.run(result).expect("scripter run failed")
+                self.scripter.run(result)
             };
+            self.apply_syscall_requests();
 
+            let runtime_command = match run_result {
+                Ok(runtime_command) => runtime_command,
+                Err(error) => {
+                    self.halt(error);
+                    break;
+                }
+            };
+
+            let backlog_len = self.adv_state.backlog.entries().len();
             match command::apply_command_state_and_start(
                 runtime_command,
                 context,
@@ -201,6 +613,7 @@ impl Adv {
                 CommandStartResult::Continue(r) => result = r,
                 CommandStartResult::Yield(executing_command) => {
                     self.current_command = Some(executing_command);
+                    self.current_command_backlog_len = backlog_len;
                 }
                 CommandStartResult::Exit => {
                     todo!("adv exit");
@@ -292,10 +705,31 @@ impl Adv {
 pub struct AdvState {
     pub root_layer_group: RootLayerGroup,
     pub back_layer_group: Option<RootLayerGroup>,
+    pub fade_overlay: FadeOverlay,
+    /// The choice menu of the currently running `SELECT` command
+    pub choice_menu: Option<ChoiceMenu>,
+    /// The choice menu laid out for display
+    pub choice_menu_labels: Option<ChoiceMenuLabels>,
+    /// The quiz of the currently running `QUIZ` command
+    pub quiz: Option<QuizState>,
+    /// The pending answer of the quiz, laid out for display
+    pub quiz_label: Option<QuizLabel>,
     pub audio_manager: Arc<AudioManager>,
     pub bgm_player: BgmPlayer,
     pub se_player: SePlayer,
     pub allow_running_animations: bool,
+    pub reduced_motion: bool,
+    pub group_opacity: bool,
+    pub backlog: Backlog,
+    /// Time since the start of the session, used for backlog timestamps
+    pub session_time: Duration,
+    pub fonts: AdvFonts,
+    /// The caption of the currently playing voice, laid out for display
+    pub caption_label: Option<Label>,
+    /// The extra content unlocked by the scenario.
+    ///
+    /// It's global progression, so unlike the [`VmState`] it's not rolled back by the quick-loads.
+    pub unlocks: UnlockState,
 }
 
 impl AdvState {
@@ -307,11 +741,71 @@ impl AdvState {
                 VoicePlayer::new(audio_manager.clone()),
             ),
             back_layer_group: None,
+            fade_overlay: FadeOverlay::new(),
+            choice_menu: None,
+            choice_menu_labels: None,
+            quiz: None,
+            quiz_label: None,
             audio_manager: audio_manager.clone(),
             bgm_player: BgmPlayer::new(audio_manager.clone()),
             se_player: SePlayer::new(audio_manager),
             allow_running_animations: true,
+            reduced_motion: false,
+            group_opacity: false,
+            backlog: Backlog::new(),
+            session_time: Duration::ZERO,
+            fonts: assets.fonts,
+            caption_label: None,
+            unlocks: UnlockState::new(),
+        }
+    }
+
+    /// The caption of the currently playing voice, to be shown as a subtitle
+    pub fn voice_caption(&self) -> Option<&str> {
+        self.message_layer().voice_player().caption()
+    }
+
+    /// Lay out the caption again when the voice changes
+    fn update_caption_label(&mut self, context: &PreRenderContext) {
+        let caption = self.voice_caption().map(str::to_string);
+        if caption.as_deref() == self.caption_label.as_ref().map(Label::text) {
+            return;
         }
+
+        self.caption_label =
+            caption.map(|caption| Label::new(context, &self.fonts.medium_font, &caption));
+    }
+
+    /// Lay out the choice menu again when a different one is opened
+    fn update_choice_menu_labels(&mut self, context: &PreRenderContext) {
+        let Some(menu) = &self.choice_menu else {
+            self.choice_menu_labels = None;
+            return;
+        };
+        if self
+            .choice_menu_labels
+            .as_ref()
+            .is_some_and(|labels| labels.is_for(menu))
+        {
+            return;
+        }
+
+        self.choice_menu_labels = Some(ChoiceMenuLabels::new(
+            context,
+            &self.fonts.medium_font,
+            menu,
+        ));
+    }
+
+    /// Lay out the pending answer of the quiz again when it changes
+    fn update_quiz_label(&mut self, context: &PreRenderContext) {
+        let answer = self.quiz.as_ref().map(QuizState::pending_answer);
+        if answer == self.quiz_label.as_ref().map(QuizLabel::answer) {
+            return;
+        }
+
+        self.quiz_label =
+            answer.map(|answer| QuizLabel::new(context, &self.fonts.medium_font, answer));
     }
 
     pub fn root_layer_group(&self) -> &RootLayerGroup {
@@ -369,15 +863,194 @@ impl AdvState {
         // self.back_layer_group = Some(self.root_layer_group.render_clone(ctx));
     }
 
+    /// Rebuild the scene from the [`VmState`], used when loading a save
+    ///
+    /// Running transitions and animations are dropped, the scene jumps straight to the state the VM expects.
+    ///
+    /// All the assets are loaded before the scene is touched, so it is left as-is if any of them fails to load.
+    pub fn restore_from_vm_state(
+        &mut self,
+        context: &mut UpdateContext,
+        scenario: &Arc<Scenario>,
+        vm_state: &VmState,
+    ) -> Result<()> {
+        let layers = &vm_state.layers;
+
+        let mask_textures = layers
+            .plane_layergroups
+            .iter()
+            .map(|(plane, plane_state)| {
+                let mask_texture = plane_state
+                    .mask_id
+                    .repr()
+                    .map(|mask_id| {
+                        let path = scenario.info_tables().mask_info(mask_id).path();
+                        context
+                            .asset_server
+                            // TODO: sync - bad!!
+                            .load_sync(&path)
+                            .with_context(|| format!("Failed to load mask texture {}", path))
+                    })
+                    .transpose()?;
+
+                Ok((plane, plane_state, mask_texture))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let user_layers = layers
+            .layerbanks
+            .iter()
+            .filter_map(|(layerbank, layerbank_state)| {
+                let layer_type = layerbank_state.layer_type?;
+
+                // TODO: sync - bad!!
+                let layer = shin_tasks::block_on(UserLayer::load(
+                    context.pre_render.device,
+                    context.asset_server,
+                    &self.audio_manager,
+                    scenario,
+                    layer_type,
+                    layerbank_state.params,
+                ))
+                .with_context(|| format!("Failed to load layer {:?}", layerbank_state.layer_id));
+
+                Some(layer.map(|layer| (layerbank, layerbank_state, layer)))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let bgm = vm_state
+            .audio
+            .bgm
+            .map(|bgm| {
+                let bgm_info = scenario.info_tables().bgm_info(bgm.bgm_id);
+                let audio = context
+                    .asset_server
+                    // TODO: sync - bad!!
+                    .load_sync(bgm_info.path())
+                    .with_context(|| format!("Failed to load BGM track {}", bgm_info.path()))?;
+
+                anyhow::Ok((bgm, bgm_info, audio))
+            })
+            .transpose()?;
+
+        self.back_layer_group = None;
+        self.allow_running_animations = true;
+
+        self.root_layer_group
+            .properties_mut()
+            .restore_from_state(&layers.root_layer_group);
+
+        let screen_layer = self.screen_layer_mut();
+        screen_layer.reset();
+        screen_layer
+            .properties_mut()
+            .restore_from_state(&layers.screen_layer);
+        self.page_layer_mut()
+            .properties_mut()
+            .restore_from_state(&layers.page_layer);
+
+        for (plane, plane_state, mask_texture) in mask_textures {
+            let layer_group = self.plane_layer_group_mut(plane);
+            layer_group
+                .properties_mut()
+                .restore_from_state(&plane_state.properties);
+            match mask_texture {
+                Some(mask_texture) => {
+                    layer_group.set_mask_texture(mask_texture, plane_state.mask_flags)
+                }
+                None => layer_group.clear_mask_texture(),
+            }
+        }
+
+        for (layerbank, layerbank_state, mut layer) in user_layers {
+            let properties = layer.properties_mut();
+            properties.set_layer_id(layerbank_state.layer_id);
+            properties.restore_from_state(&layerbank_state.properties);
+
+            self.plane_layer_group_mut(layerbank_state.plane)
+                .add_layer(layerbank, layer);
+        }
+
+        // the message will be shown again by the restored command
+        self.message_layer_mut().close(false);
+        self.fade_overlay = FadeOverlay::new();
+        self.choice_menu = None;
+        self.quiz = None;
+
+        self.se_player.stop_all(Tween::MS_15);
+        match bgm {
+            Some((bgm, bgm_info, audio)) => {
+                self.bgm_player.play(
+                    audio,
+                    bgm_info.display_name.as_str(),
+                    true,
+                    bgm.volume,
+                    Tween::MS_15,
+                );
+            }
+            None => self.bgm_player.stop(Tween::MS_15),
+        }
+
+        Ok(())
+    }
+
+    /// Fade the whole screen to the `color`, drawing it over all the layers.
+    pub fn fade_screen(&mut self, color: FloatColor4, tween: Tween) {
+        self.fade_overlay.fade_to(color, tween);
+    }
+
     #[tracing::instrument(skip_all)]
     pub fn render(&self, pass: &mut RenderPass) {
         pass.clear(Some(UnormColor::BLACK), Some(0), Some(1.0));
-        render_layer_without_bg(pass, &TransformParams::default(), &self.root_layer_group, 0)
+        render_layer_without_bg(pass, &TransformParams::default(), &self.root_layer_group, 0);
+        self.fade_overlay.render(pass);
+    }
+
+    /// Render only the screen layer, for when the UI is rendered separately (at a different resolution).
+    ///
+    /// Rendering [`Self::render_ui`] over it makes the same image as [`Self::render`], unless the root layer group has effects applied to it as a whole.
+    #[tracing::instrument(skip_all)]
+    pub fn render_scene(&self, pass: &mut RenderPass) {
+        pass.clear(Some(UnormColor::BLACK), Some(0), Some(1.0));
+        render_layer_without_bg(
+            pass,
+            &TransformParams::default(),
+            self.root_layer_group.screen_layer(),
+            0,
+        );
+    }
+
+    /// Render the message layer and the fade overlay over the scene rendered by [`Self::render_scene`]
+    #[tracing::instrument(skip_all)]
+    pub fn render_ui(&self, pass: &mut RenderPass) {
+        pass.clear(None, Some(0), Some(1.0));
+        render_layer_without_bg(
+            pass,
+            &TransformParams::default(),
+            self.root_layer_group.message_layer(),
+            0,
+        );
+        if let Some(caption) = &self.caption_label {
+            // centered at the top of the screen, out of the way of the messagebox
+            let x = (VIRTUAL_CANVAS_SIZE_VEC.x - caption.size().x) / 2.0;
+            caption.render(pass, vec2(x, 40.0), FloatColor4::WHITE);
+        }
+        if let (Some(menu), Some(labels)) = (&self.choice_menu, &self.choice_menu_labels) {
+            labels.render(pass, menu.cursor());
+        }
+        if let Some(quiz_label) = &self.quiz_label {
+            quiz_label.render(pass);
+        }
+        self.fade_overlay.render(pass);
     }
 }
 
 impl Updatable for AdvState {
     fn update(&mut self, context: &mut UpdateContext) {
+        self.session_time += context.delta_ticks.as_duration();
+
+        // taken before the update, as the voice player lives inside the message layer
+        let lipsync = self.message_layer().voice_player().lipsync();
         let adv_update_context = AdvUpdateContext {
             frame_id: context.frame_id,
             delta_ticks: context.delta_ticks,
@@ -385,6 +1058,9 @@ impl Updatable for AdvState {
             device: context.pre_render.device,
             queue: context.pre_render.queue,
             are_animations_allowed: self.allow_running_animations,
+            reduced_motion: self.reduced_motion,
+            group_opacity: self.group_opacity,
+            lipsync: &lipsync,
         };
 
         // this seems like a pre-PAGEBACK feature to stop incomplete transitions from rendering
@@ -394,6 +1070,10 @@ impl Updatable for AdvState {
         }
 
         self.root_layer_group.update(&adv_update_context);
+        self.fade_overlay.update(&adv_update_context);
+        self.update_caption_label(context.pre_render);
+        self.update_choice_menu_labels(context.pre_render);
+        self.update_quiz_label(context.pre_render);
 
         let transform = TransformParams::default();
 
@@ -401,3 +1081,506 @@ impl Updatable for AdvState {
             .pre_render(context.pre_render, &transform);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use shin_core::{
+        format::{
+            save::UnlockType,
+            scenario::{
+                instruction_elements::{CodeAddress, Register},
+                instructions::Instruction,
+            },
+        },
+        primitives::color::{FloatColor4, UnormColor},
+        vm::{
+            command::types::{LayerId, LayerProperty},
+            syscall::syscall_result_register,
+        },
+    };
+
+    use super::{
+        Adv, ExecutingCommand,
+        quiz_label::QuizLabel,
+        syscall::call_id,
+        test_utils::{
+            AdvTester, CODE_OFFSET, assemble, layerctrl, layerload_animation, layerload_tile,
+            layerswap, layerunload, msgset, quiz, select, syscall, unlock, wait,
+        },
+    };
+    use crate::{
+        app::AppAction,
+        asset::system::LayeredAssetIo,
+        layer::{DrawableLayer as _, user::UserLayer},
+        render::overdraw::{OVERDRAW_RAMP, render_overdraw},
+    };
+
+    /// The layer in the `layer` slot of the current plane, if it's loaded
+    fn user_layer(adv: &Adv, layer: u16) -> Option<&UserLayer> {
+        let layers = &adv.vm_state.layers;
+        let layerbank = layers
+            .layerbank_allocator
+            .get_layerbank_id(layers.current_plane, LayerId::new(layer))?;
+
+        adv.adv_state
+            .plane_layer_group(layers.current_plane)
+            .get_layer(layerbank)
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn quick_load_restores_the_scene() {
+        let mut tester = AdvTester::new(&[layerload_tile(1), wait(30), layerunload(1)]);
+
+        tester.run_until(|adv| user_layer(adv, 1).is_some());
+        tester.update(&[AppAction::QuickSave]);
+
+        tester.run_until(|adv| user_layer(adv, 1).is_none());
+        tester.update(&[AppAction::QuickLoad]);
+        assert!(user_layer(&tester.adv, 1).is_some());
+
+        // the scenario goes on from the WAIT it was saved at
+        tester.run_until(|adv| user_layer(adv, 1).is_none());
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn missing_animation_is_skipped() {
+        // there are no animation files in the tests
+        let mut tester = AdvTester::new(&[layerload_animation(1, 7), layerload_tile(2)]);
+
+        // the scenario goes on without the layer
+        tester.run_until(|adv| user_layer(adv, 2).is_some());
+        assert!(user_layer(&tester.adv, 1).is_none());
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn layerswap_out_of_range_is_ignored() {
+        let mut tester = AdvTester::new(&[
+            layerload_tile(1),
+            layerswap(1, 0x100),
+            layerswap(-1, 1),
+            layerswap(1, 2),
+        ]);
+
+        // the invalid swaps leave the layer in place, the scenario goes on to the valid one
+        tester.run_until(|adv| user_layer(adv, 2).is_some());
+        assert!(user_layer(&tester.adv, 1).is_none());
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn overdraw_view() {
+        // two tiles over each other, covering the bottom right quarter of the screen
+        let mut tester = AdvTester::new(&[layerload_tile(1), layerload_tile(2)]);
+        tester.run_until(|adv| user_layer(adv, 1).is_some() && user_layer(adv, 2).is_some());
+
+        let normal = tester.render(|adv, pass| adv.render(pass));
+        let overdraw = tester.render(|adv, pass| render_overdraw(pass, |pass| adv.render(pass)));
+        let overdraw_at = |x, y| UnormColor(u32::from_le_bytes(overdraw.get_pixel(x, y).0));
+        assert_eq!(overdraw_at(140, 70), OVERDRAW_RAMP[1]);
+        assert_eq!(overdraw_at(40, 20), UnormColor::BLACK);
+
+        // turning the view off brings back the normal rendering
+        assert_eq!(tester.render(|adv, pass| adv.render(pass)), normal);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn vm_error_halts_the_scenario() {
+        // a subroutine endlessly calling itself, overflowing the call stack
+        let mut tester = AdvTester::new(&[Instruction::gosub {
+            target: CodeAddress(CODE_OFFSET),
+        }]);
+
+        tester.run_frames(1);
+        assert!(tester.adv.is_halted);
+        assert!(tester.adv.current_command.is_none());
+
+        // the VM is not run anymore, but the rest of the game still is
+        let position = tester.adv.scripter.position();
+        tester.run_frames(10);
+        assert_eq!(tester.adv.scripter.position(), position);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn quick_load_keeps_the_unlocks() {
+        let mut tester = AdvTester::new(&[wait(10), unlock(0, &[3, 40]), wait(10)]);
+        let is_unlocked = |adv: &Adv| adv.adv_state.unlocks.is_unlocked(UnlockType::Cg, 40);
+
+        tester.update(&[AppAction::QuickSave]);
+        tester.run_until(is_unlocked);
+        assert!(tester.adv.adv_state.unlocks.is_unlocked(UnlockType::Cg, 3));
+
+        // the save was made before the UNLOCK, but the gallery is global progression
+        tester.update(&[AppAction::QuickLoad]);
+        assert!(is_unlocked(&tester.adv));
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn quick_load_mid_tween() {
+        let mut tester = AdvTester::new(&[
+            layerload_tile(1),
+            layerctrl(1, LayerProperty::TranslateX, 600, 60),
+            wait(120),
+        ]);
+        let translate_x = |adv: &Adv| {
+            user_layer(adv, 1)
+                .unwrap()
+                .properties()
+                .get_value(LayerProperty::TranslateX)
+        };
+
+        tester.run_until(|adv| user_layer(adv, 1).is_some());
+        tester.run_frames(10);
+        let value = translate_x(&tester.adv);
+        assert!(0.0 < value && value < 600.0, "not mid-tween: {}", value);
+
+        tester.update(&[AppAction::QuickSave]);
+        tester.update(&[AppAction::QuickLoad]);
+
+        // the tween is not replayed, the layer is put where the VM expects it to end up
+        assert_eq!(translate_x(&tester.adv), 600.0);
+        assert!(tester.adv.adv_state.allow_running_animations);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn layerctrl_queues_after_the_running_tween() {
+        let mut tester = AdvTester::new(&[
+            layerload_tile(1),
+            layerctrl(1, LayerProperty::TranslateX, 600, 60),
+            layerctrl(1, LayerProperty::TranslateX, 0, 60),
+            wait(240),
+        ]);
+        let translate_x = |adv: &Adv| {
+            user_layer(adv, 1)
+                .unwrap()
+                .properties()
+                .get_value(LayerProperty::TranslateX)
+        };
+
+        tester.run_until(|adv| user_layer(adv, 1).is_some());
+        // the second tween only starts after the first one reaches 600
+        tester.run_frames(90);
+        let value = translate_x(&tester.adv);
+        assert!(
+            250.0 < value && value < 350.0,
+            "not on the way back: {}",
+            value
+        );
+
+        tester.run_frames(40);
+        assert_eq!(translate_x(&tester.adv), 0.0);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn select_with_all_variants_hidden() {
+        let dest = Register::from_regular_register(10);
+        let mut tester = AdvTester::new(&[select(dest, 0, &["Left", "Right"]), layerload_tile(1)]);
+
+        // nothing to wait for, the scenario goes on
+        tester.run_until(|adv| user_layer(adv, 1).is_some());
+        assert!(tester.adv.adv_state.choice_menu.is_none());
+        assert_eq!(tester.adv.scripter.read_register(dest), -1);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn select_shows_the_menu() {
+        let dest = Register::from_regular_register(10);
+        let mut tester = AdvTester::new(&[select(dest, 0b110, &["Up", "Left", "Right"])]);
+
+        tester.run_frames(1);
+        let labels_match = |adv: &Adv| {
+            let labels = adv.adv_state.choice_menu_labels.as_ref()?;
+            Some(labels.is_for(adv.adv_state.choice_menu.as_ref()?))
+        };
+        assert_eq!(labels_match(&tester.adv), Some(true));
+
+        tester.update(&[AppAction::AnyDown]);
+        tester.update(&[AppAction::Enter]);
+        assert!(tester.adv.adv_state.choice_menu_labels.is_none());
+        assert_eq!(tester.adv.scripter.read_register(dest), 2);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn quiz_answer() {
+        let dest = Register::from_regular_register(10);
+        let mut tester = AdvTester::new(&[quiz(dest, 5)]);
+        let shown_answer = |adv: &Adv| adv.adv_state.quiz_label.as_ref().map(QuizLabel::answer);
+
+        tester.run_frames(1);
+        assert_eq!(shown_answer(&tester.adv), Some(0));
+
+        // down goes to the next answer, up to the previous one
+        tester.update(&[AppAction::AnyDown]);
+        tester.update(&[AppAction::AnyDown]);
+        tester.update(&[AppAction::AnyDown]);
+        tester.update(&[AppAction::AnyUp]);
+        assert_eq!(shown_answer(&tester.adv), Some(2));
+        assert_eq!(tester.adv.scripter.read_register(dest), 0);
+
+        tester.update(&[AppAction::Enter]);
+        assert_eq!(shown_answer(&tester.adv), None);
+        assert_eq!(tester.adv.scripter.read_register(dest), 2);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn syscall_shakes_the_screen() {
+        // a 16 pixel shake over 30 ticks
+        let mut tester =
+            AdvTester::new(&[wait(10), syscall(call_id::SCREEN_SHAKE, (30 << 16) | 16)]);
+        let is_shaking = |adv: &Adv| adv.adv_state.screen_layer().is_shaking();
+
+        tester.run_frames(5);
+        assert!(!is_shaking(&tester.adv));
+
+        tester.run_until(is_shaking);
+        tester.run_frames(31);
+        assert!(!is_shaking(&tester.adv));
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn syscall_fades_the_screen() {
+        // to opaque white over 60 ticks
+        let mut tester = AdvTester::new(&[syscall(call_id::SCREEN_FADE, (60 << 16) | 0xffff)]);
+        let overlay_alpha = |adv: &Adv| adv.adv_state.fade_overlay.color().a;
+
+        tester.run_frames(30);
+        let alpha = overlay_alpha(&tester.adv);
+        assert!(0.4 < alpha && alpha < 0.6, "not halfway: {}", alpha);
+
+        tester.run_frames(30);
+        assert_eq!(
+            tester.adv.adv_state.fade_overlay.color(),
+            FloatColor4::WHITE
+        );
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn syscall_attaches_the_layers() {
+        // the tile of the layer 2 follows the one of the layer 1 to the left half of the screen
+        let mut tester = AdvTester::new(&[
+            layerload_tile(1),
+            layerload_tile(2),
+            syscall(call_id::ATTACH_LAYER, (1 << 16) | 2),
+            layerctrl(1, LayerProperty::TranslateX, -960, 0),
+            wait(10),
+            syscall(call_id::DETACH_LAYER, 2),
+        ]);
+        // whether the left and the right halves of the bottom of the screen are covered by a tile
+        let covered = |tester: &mut AdvTester| {
+            let image = tester.render(|adv, pass| adv.render(pass));
+            let is_red = |x| image.get_pixel(x, 70).0 == [255, 0, 0, 255];
+            (is_red(40), is_red(140))
+        };
+
+        tester.run_until(|adv| user_layer(adv, 2).is_some());
+        tester.run_frames(2);
+        assert_eq!(covered(&mut tester), (true, false));
+
+        // detached, it goes back to where it was loaded
+        tester.run_frames(12);
+        assert_eq!(covered(&mut tester), (true, true));
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn syscall_reorders_the_layers() {
+        let mut tester = AdvTester::new(&[
+            layerload_tile(1),
+            syscall(call_id::SET_RENDER_ORDER, (1500 << 16) | 1),
+            wait(10),
+            syscall(call_id::SET_RENDER_ORDER, 0xffff_0001_u32 as i32),
+        ]);
+        let render_order = |adv: &Adv| {
+            user_layer(adv, 1)
+                .unwrap()
+                .properties()
+                .get_render_order_position()
+        };
+
+        tester.run_until(|adv| user_layer(adv, 1).is_some());
+        tester.run_frames(2);
+        assert_eq!(render_order(&tester.adv), 1500.0);
+
+        // back to the RenderPosition property
+        tester.run_frames(12);
+        assert_eq!(
+            render_order(&tester.adv),
+            user_layer(&tester.adv, 1)
+                .unwrap()
+                .properties()
+                .get_value(LayerProperty::RenderPosition)
+        );
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn syscall_reads_the_playtime() {
+        let dest = syscall_result_register();
+        let mut tester = AdvTester::new(&[wait(200), syscall(call_id::PLAYTIME, 0), wait(100)]);
+        let playtime = |adv: &Adv| adv.playtime.lock().total().as_secs_f32();
+
+        tester.run_frames(90);
+        tester.update(&[AppAction::QuickSave]);
+
+        // minimized for a second
+        tester.is_active = false;
+        tester.run_frames(60);
+        tester.is_active = true;
+
+        // 140 frames of the 200 were actually played
+        tester.run_until(|adv| adv.scripter.read_register(dest) != 0);
+        assert_eq!(tester.adv.scripter.read_register(dest), 2);
+
+        tester.update(&[AppAction::QuickLoad]);
+        assert!(
+            (playtime(&tester.adv) - 1.5).abs() < 0.05,
+            "not restored: {}",
+            playtime(&tester.adv)
+        );
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn click_while_skipping_advances_at_once() {
+        // long enough to still be revealed when the messagebox is in
+        let text = "The quick brown fox jumps over the lazy dog. ".repeat(4);
+        let mut tester = AdvTester::new(&[msgset(&text), msgset(&text)]);
+        let backlog_len = |adv: &Adv| adv.adv_state.backlog.entries().len();
+        let takes_clicks = |adv: &Adv| adv.adv_state.message_layer().is_interested_in_input();
+
+        // the first click only reveals the rest of the message, the second one advances it
+        tester.run_until(takes_clicks);
+        tester.update(&[AppAction::Enter]);
+        tester.run_frames(10);
+        assert_eq!(backlog_len(&tester.adv), 1);
+        tester.update(&[AppAction::Enter]);
+        tester.run_until(|adv| backlog_len(adv) == 2);
+
+        // while skipping, the same click does both
+        tester.run_until(takes_clicks);
+        assert!(tester.adv.adv_state.message_layer_mut().try_advance(true));
+        tester.run_until(|adv| !matches!(adv.current_command, Some(ExecutingCommand::MSGSET(_))));
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn quick_load_mid_message_keeps_the_backlog() {
+        let mut tester = AdvTester::new(&[msgset("first"), msgset("second")]);
+
+        tester.run_frames(2);
+        assert!(matches!(
+            tester.adv.current_command,
+            Some(ExecutingCommand::MSGSET(_))
+        ));
+
+        tester.update(&[AppAction::QuickSave]);
+        tester.update(&[AppAction::QuickLoad]);
+
+        // the MSGSET is started again, but the message is only in the backlog once
+        let texts = tester
+            .adv
+            .adv_state
+            .backlog
+            .entries()
+            .iter()
+            .map(|entry| entry.text.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(texts, ["first"]);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn reloaded_scenario_goes_on_from_the_current_command() {
+        let mut tester = AdvTester::new(&[msgset("first"), msgset("second")]);
+        tester.run_frames(2);
+
+        // the message fixed in place, keeping the code at the same addresses
+        let edited = Arc::new(assemble(&[msgset("fixed"), msgset("second")]));
+        tester
+            .with_update_context(|adv, context| adv.reload_scenario(context, edited))
+            .unwrap();
+        tester.run_frames(1);
+
+        let texts = tester
+            .adv
+            .adv_state
+            .backlog
+            .entries()
+            .iter()
+            .map(|entry| entry.text.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(texts, ["fixed"]);
+    }
+
+    /// An NXA file without any frames, with the `channel_count` from its header
+    fn empty_voice(channel_count: u16) -> Vec<u8> {
+        let mut file = Vec::new();
+        file.extend(b"NXA1");
+        file.extend(2u32.to_le_bytes());
+        file.extend(0x30u32.to_le_bytes());
+        file.extend(48000u32.to_le_bytes());
+        file.extend(channel_count.to_le_bytes());
+        // frame size, frame samples, pre-skip
+        file.extend([3u16, 960, 0].map(u16::to_le_bytes).concat());
+        // samples, loop start and end
+        file.extend([0u32; 3].map(u32::to_le_bytes).concat());
+        file.resize(0x30, 0);
+        file
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn backlog_voice_is_replayed() {
+        let dir = std::env::temp_dir().join(format!("shin-voice-replay-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("voice/00")).unwrap();
+        std::fs::write(dir.join("voice/00/test0001.nxa"), empty_voice(1)).unwrap();
+        std::fs::write(dir.join("voice/00/test0002.nxa"), empty_voice(3)).unwrap();
+        let mut io = LayeredAssetIo::new();
+        io.try_with_dir(&dir).unwrap();
+
+        let mut tester = AdvTester::with_assets(
+            &[
+                msgset("@v00/test0001.first"),
+                msgset("@v00/test0002.second"),
+                msgset("third"),
+            ],
+            io,
+        );
+        let backlog_len = |adv: &Adv| adv.adv_state.backlog.entries().len();
+
+        tester.run_until(|adv| backlog_len(adv) == 1);
+        tester.update(&[AppAction::Enter]);
+        tester.update(&[AppAction::Enter]);
+        tester.run_until(|adv| backlog_len(adv) == 2);
+        let position = tester.adv.scripter.position();
+
+        assert!(tester.with_update_context(|adv, context| adv.replay_backlog_voice(context, 0)));
+        // the voice that can't be decoded is not replayed, without taking the game down
+        assert!(!tester.with_update_context(|adv, context| adv.replay_backlog_voice(context, 1)));
+        tester.update(&[AppAction::ReplayVoice]);
+
+        // the scenario is not affected
+        assert_eq!(tester.adv.scripter.position(), position);
+        assert_eq!(backlog_len(&tester.adv), 2);
+        assert!(matches!(
+            tester.adv.current_command,
+            Some(ExecutingCommand::MSGSET(_))
+        ));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
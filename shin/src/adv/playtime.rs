@@ -0,0 +1,78 @@
+use std::time::Duration;
+
+use shin_core::time::Ticks;
+
+/// Frames taking longer than this are not counted, as the game was most likely suspended during them
+///
+/// The minimized windows are often not redrawn at all, so the time spent minimized shows up as one long frame after restoring.
+const MAX_COUNTED_FRAME_TIME: Duration = Duration::from_secs(1);
+
+/// Total time spent actively playing, for the playtime stats
+///
+/// Kept as a [`Duration`], as [`Ticks`] lose precision over the hours.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Playtime {
+    total: Duration,
+}
+
+impl Playtime {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count the frame time, unless the game is not `active` (paused or minimized)
+    pub fn update(&mut self, delta_ticks: Ticks, active: bool) {
+        let delta = delta_ticks.as_duration();
+        if active && delta <= MAX_COUNTED_FRAME_TIME {
+            self.total += delta;
+        }
+    }
+
+    pub fn total(&self) -> Duration {
+        self.total
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use shin_core::time::Ticks;
+
+    use super::Playtime;
+
+    fn play(playtime: &mut Playtime, seconds: u32, active: bool) {
+        for _ in 0..seconds * 60 {
+            playtime.update(Ticks::from_u32(1), active);
+        }
+    }
+
+    #[test]
+    fn pauses_are_not_counted() {
+        let mut playtime = Playtime::new();
+
+        play(&mut playtime, 10, true);
+        play(&mut playtime, 5, false);
+        play(&mut playtime, 20, true);
+
+        let total = playtime.total().as_secs_f64();
+        assert!((total - 30.0).abs() < 0.01, "{}", total);
+    }
+
+    #[test]
+    fn suspended_frames_are_not_counted() {
+        let mut playtime = Playtime::new();
+
+        play(&mut playtime, 2, true);
+        // the first frame after the window was restored
+        playtime.update(Ticks::from_seconds(600.0), true);
+        play(&mut playtime, 2, true);
+
+        let total = playtime.total();
+        assert!(
+            total.abs_diff(Duration::from_secs(4)) < Duration::from_millis(10),
+            "{:?}",
+            total
+        );
+    }
+}
@@ -0,0 +1,88 @@
+/// State of the quiz shown by the `QUIZ` command
+///
+/// The quiz itself is presented by a `QuizLayer`, this only tracks the answer to be returned to the scenario.
+#[derive(Debug, Clone)]
+pub struct QuizState {
+    quiz_id: i32,
+    /// The answer the player is currently picking
+    pending_answer: i32,
+    answer: Option<i32>,
+}
+
+impl QuizState {
+    pub fn new(quiz_id: i32) -> Self {
+        Self {
+            quiz_id,
+            pending_answer: 0,
+            answer: None,
+        }
+    }
+
+    pub fn quiz_id(&self) -> i32 {
+        self.quiz_id
+    }
+
+    pub fn pending_answer(&self) -> i32 {
+        self.pending_answer
+    }
+
+    /// Moves the pending answer by `delta`, without going below zero.
+    pub fn step_pending_answer(&mut self, delta: i32) {
+        self.pending_answer = (self.pending_answer + delta).max(0);
+    }
+
+    pub fn confirm_pending_answer(&mut self) {
+        self.submit_answer(self.pending_answer);
+    }
+
+    /// Records the answer of the player. Only the first answer counts.
+    pub fn submit_answer(&mut self, answer: i32) {
+        if self.answer.is_none() {
+            self.answer = Some(answer);
+        }
+    }
+
+    /// The value to be written to the destination register of the `QUIZ` command, once the player has answered
+    pub fn answer(&self) -> Option<i32> {
+        self.answer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::QuizState;
+
+    #[test]
+    fn no_answer_until_submitted() {
+        let mut quiz = QuizState::new(3);
+        assert_eq!(quiz.quiz_id(), 3);
+
+        quiz.step_pending_answer(2);
+        assert_eq!(quiz.answer(), None);
+    }
+
+    #[test]
+    fn synthetic_answer() {
+        let mut quiz = QuizState::new(3);
+        quiz.submit_answer(42);
+        assert_eq!(quiz.answer(), Some(42));
+
+        // the answer is final
+        quiz.submit_answer(7);
+        quiz.confirm_pending_answer();
+        assert_eq!(quiz.answer(), Some(42));
+    }
+
+    #[test]
+    fn pending_answer() {
+        let mut quiz = QuizState::new(0);
+        quiz.step_pending_answer(-1);
+        quiz.step_pending_answer(1);
+        quiz.step_pending_answer(1);
+        quiz.step_pending_answer(-1);
+        quiz.step_pending_answer(1);
+
+        quiz.confirm_pending_answer();
+        assert_eq!(quiz.answer(), Some(2));
+    }
+}
@@ -0,0 +1,40 @@
+use std::sync::Arc;
+
+use shin_core::primitives::color::FloatColor4;
+use shin_render::render_pass::RenderPass;
+
+use crate::{
+    asset::font::GpuFontLazy,
+    render::{PreRenderContext, VIRTUAL_CANVAS_SIZE_VEC, label::Label},
+};
+
+/// Shown around the pending answer, until there is a `QuizLayer` presenting the quiz itself
+const ANSWER_PREFIX: &str = "Answer: < ";
+const ANSWER_SUFFIX: &str = " >";
+
+/// The pending answer of a [`QuizState`](super::quiz::QuizState), laid out for display in the middle of the screen
+pub struct QuizLabel {
+    answer: i32,
+    label: Label,
+}
+
+impl QuizLabel {
+    pub fn new(context: &PreRenderContext, font: &Arc<GpuFontLazy>, answer: i32) -> Self {
+        let text = format!("{ANSWER_PREFIX}{answer}{ANSWER_SUFFIX}");
+
+        Self {
+            answer,
+            label: Label::new(context, font, &text),
+        }
+    }
+
+    /// The answer shown by the label
+    pub fn answer(&self) -> i32 {
+        self.answer
+    }
+
+    pub fn render(&self, pass: &mut RenderPass) {
+        let position = (VIRTUAL_CANVAS_SIZE_VEC - self.label.size()) / 2.0;
+        self.label.render(pass, position, FloatColor4::WHITE);
+    }
+}
@@ -0,0 +1,291 @@
+//! The engine services the scenario requests with `SYSCALL`.
+//!
+//! The handler runs inside the [`Scripter`](shin_core::vm::Scripter), which doesn't have access to the layers,
+//! so it only queues the requests, to be applied by the [`Adv`](super::Adv) after the VM yields.
+
+use std::sync::{Arc, mpsc};
+
+use glam::Vec2;
+use parking_lot::Mutex;
+use shin_core::{
+    primitives::color::FloatColor4,
+    time::{Ticks, Tween},
+    vm::{command::types::LayerId, syscall::SyscallHandler},
+};
+use tracing::warn;
+
+use crate::adv::playtime::Playtime;
+
+/// The call ids of the syscalls implemented by this engine.
+///
+/// This is an extension private to this engine: the original one doesn't have any of these, and the scenario format doesn't depend on them.
+/// The numbers are this engine's own, picked to stay clear of the small ones, so that the original scenarios only ever hit the unknown syscall warning.
+pub mod call_id {
+    /// Shake the screen.
+    ///
+    /// The low 16 bits of the argument are the amplitude in pixels, the high 16 bits are the duration in ticks ([`DEFAULT_SHAKE_DURATION`](super::DEFAULT_SHAKE_DURATION) if 0).
+    pub const SCREEN_SHAKE: i32 = 0x100;
+    /// Fade the whole screen to a color, drawn over all the layers.
+    ///
+    /// The low 16 bits of the argument are the color as `0xRGBA` (4 bits per channel, straight alpha), the high 16 bits are the duration in ticks.
+    pub const SCREEN_FADE: i32 = 0x101;
+    /// Make a layer of the current plane follow the transform of another one.
+    ///
+    /// The low 16 bits of the argument are the id of the attached layer, the high 16 bits are the id of the layer it follows.
+    pub const ATTACH_LAYER: i32 = 0x102;
+    /// Make the layer with the id passed as the argument follow the transform of its plane again, undoing [`ATTACH_LAYER`].
+    pub const DETACH_LAYER: i32 = 0x103;
+    /// Draw a layer of the current plane at an explicit position in its plane, instead of the one from its `RenderPosition` property.
+    ///
+    /// The low 16 bits of the argument are the id of the layer, the high 16 bits are the order (on the same scale as the property), with `0xffff` going back to the property.
+    pub const SET_RENDER_ORDER: i32 = 0x104;
+    /// Get the total time spent playing, restored along with the saves, in whole seconds.
+    ///
+    /// The argument is not used, the result goes to the [`syscall_result_register`](shin_core::vm::syscall::syscall_result_register).
+    pub const PLAYTIME: i32 = 0x105;
+}
+
+pub const DEFAULT_SHAKE_DURATION: Ticks = Ticks::from_u32(30);
+/// Oscillations per second of the shakes started by the scenario
+const SHAKE_FREQUENCY: f32 = 12.0;
+
+/// A syscall to be applied to the [`AdvState`](super::AdvState)
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyscallRequest {
+    ScreenShake {
+        amplitude: Vec2,
+        frequency: f32,
+        duration: Ticks,
+    },
+    ScreenFade {
+        color: FloatColor4,
+        tween: Tween,
+    },
+    AttachLayer {
+        child: LayerId,
+        parent: LayerId,
+    },
+    DetachLayer {
+        layer: LayerId,
+    },
+    SetRenderOrder {
+        layer: LayerId,
+        render_order: Option<i32>,
+    },
+}
+
+/// Queues the syscalls for the [`Adv`](super::Adv) and answers the queries, see [`syscall_channel`]
+pub struct AdvSyscallHandler {
+    requests: mpsc::Sender<SyscallRequest>,
+    playtime: Arc<Mutex<Playtime>>,
+}
+
+/// The handler to be set on the scripter, along with the receiving end of its requests
+///
+/// The queries are answered right away, from the state shared with the [`Adv`](super::Adv), like the `playtime`.
+pub fn syscall_channel(
+    playtime: Arc<Mutex<Playtime>>,
+) -> (AdvSyscallHandler, mpsc::Receiver<SyscallRequest>) {
+    let (sender, receiver) = mpsc::channel();
+    (
+        AdvSyscallHandler {
+            requests: sender,
+            playtime,
+        },
+        receiver,
+    )
+}
+
+impl AdvSyscallHandler {
+    fn request(&mut self, request: SyscallRequest) {
+        // the receiver is only dropped along with the whole Adv
+        let _ = self.requests.send(request);
+    }
+
+    /// [`call_id::SCREEN_SHAKE`]
+    pub fn screen_shake(&mut self, amplitude: f32, duration: Ticks) {
+        self.request(SyscallRequest::ScreenShake {
+            amplitude: Vec2::splat(amplitude),
+            frequency: SHAKE_FREQUENCY,
+            duration,
+        });
+    }
+
+    /// [`call_id::SCREEN_FADE`]
+    pub fn screen_fade(&mut self, color: FloatColor4, duration: Ticks) {
+        self.request(SyscallRequest::ScreenFade {
+            color,
+            tween: Tween::linear(duration),
+        });
+    }
+}
+
+impl SyscallHandler for AdvSyscallHandler {
+    fn dispatch(&mut self, call_id: i32, argument: i32) -> Option<i32> {
+        let low = argument & 0xffff;
+        let high = Ticks::from_u32((argument as u32) >> 16);
+
+        match call_id {
+            call_id::SCREEN_SHAKE => {
+                let duration = if high == Ticks::ZERO {
+                    DEFAULT_SHAKE_DURATION
+                } else {
+                    high
+                };
+                self.screen_shake(low as f32, duration);
+            }
+            call_id::SCREEN_FADE => {
+                let channel = |shift: u32| ((low >> shift) & 0xf) as f32 / 15.0;
+                let color = FloatColor4::from_rgba(channel(12), channel(8), channel(4), channel(0));
+                self.screen_fade(color, high);
+            }
+            call_id::ATTACH_LAYER => {
+                let child = LayerId::try_new(low as u16);
+                let parent = LayerId::try_new(((argument as u32) >> 16) as u16);
+                match (child, parent) {
+                    (Some(child), Some(parent)) => {
+                        self.request(SyscallRequest::AttachLayer { child, parent })
+                    }
+                    _ => warn!(argument, "Invalid layer ids to attach"),
+                }
+            }
+            call_id::DETACH_LAYER => {
+                match u16::try_from(argument).ok().and_then(LayerId::try_new) {
+                    Some(layer) => self.request(SyscallRequest::DetachLayer { layer }),
+                    None => warn!(argument, "Invalid layer id to detach"),
+                }
+            }
+            call_id::SET_RENDER_ORDER => {
+                let order = (argument as u32) >> 16;
+                match LayerId::try_new(low as u16) {
+                    Some(layer) => self.request(SyscallRequest::SetRenderOrder {
+                        layer,
+                        render_order: (order != 0xffff).then_some(order as i32),
+                    }),
+                    None => warn!(argument, "Invalid layer id to reorder"),
+                }
+            }
+            call_id::PLAYTIME => {
+                let seconds = self.playtime.lock().total().as_secs();
+                return Some(i32::try_from(seconds).unwrap_or(i32::MAX));
+            }
+            _ => warn!(call_id, argument, "Unknown syscall"),
+        }
+
+        // the requests don't have a result
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use glam::Vec2;
+    use parking_lot::Mutex;
+    use shin_core::{
+        primitives::color::FloatColor4,
+        time::{Ticks, Tween},
+        vm::{command::types::LayerId, syscall::SyscallHandler as _},
+    };
+
+    use super::{DEFAULT_SHAKE_DURATION, SyscallRequest, call_id, syscall_channel};
+    use crate::adv::playtime::Playtime;
+
+    #[test]
+    fn screen_shake_argument() {
+        let (mut handler, requests) = syscall_channel(Default::default());
+
+        assert_eq!(handler.dispatch(call_id::SCREEN_SHAKE, 16), None);
+        assert_eq!(
+            handler.dispatch(call_id::SCREEN_SHAKE, (90 << 16) | 8),
+            None
+        );
+        // unknown syscalls are not queued
+        assert_eq!(handler.dispatch(1, 16), None);
+
+        let shakes = requests
+            .try_iter()
+            .map(|request| match request {
+                SyscallRequest::ScreenShake {
+                    amplitude,
+                    duration,
+                    ..
+                } => (amplitude, duration),
+                request => panic!("Not a shake: {:?}", request),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(shakes, [
+            (Vec2::splat(16.0), DEFAULT_SHAKE_DURATION),
+            (Vec2::splat(8.0), Ticks::from_u32(90)),
+        ]);
+    }
+
+    #[test]
+    fn screen_fade_argument() {
+        let (mut handler, requests) = syscall_channel(Default::default());
+
+        handler.dispatch(call_id::SCREEN_FADE, (60 << 16) | 0xf808);
+        assert_eq!(requests.try_iter().collect::<Vec<_>>(), [
+            SyscallRequest::ScreenFade {
+                color: FloatColor4::from_rgba(1.0, 8.0 / 15.0, 0.0, 8.0 / 15.0),
+                tween: Tween::linear(Ticks::from_u32(60)),
+            }
+        ]);
+    }
+
+    #[test]
+    fn layer_attachment_arguments() {
+        let (mut handler, requests) = syscall_channel(Default::default());
+
+        handler.dispatch(call_id::ATTACH_LAYER, (3 << 16) | 5);
+        handler.dispatch(call_id::DETACH_LAYER, 5);
+        // out of range ids are not queued
+        handler.dispatch(call_id::ATTACH_LAYER, (3 << 16) | 0xffff);
+        handler.dispatch(call_id::DETACH_LAYER, -1);
+
+        assert_eq!(requests.try_iter().collect::<Vec<_>>(), [
+            SyscallRequest::AttachLayer {
+                child: LayerId::new(5),
+                parent: LayerId::new(3),
+            },
+            SyscallRequest::DetachLayer {
+                layer: LayerId::new(5),
+            },
+        ]);
+    }
+
+    #[test]
+    fn render_order_argument() {
+        let (mut handler, requests) = syscall_channel(Default::default());
+
+        handler.dispatch(call_id::SET_RENDER_ORDER, (1500 << 16) | 5);
+        handler.dispatch(call_id::SET_RENDER_ORDER, 0xffff_0005_u32 as i32);
+
+        let layer = LayerId::new(5);
+        assert_eq!(requests.try_iter().collect::<Vec<_>>(), [
+            SyscallRequest::SetRenderOrder {
+                layer,
+                render_order: Some(1500),
+            },
+            SyscallRequest::SetRenderOrder {
+                layer,
+                render_order: None,
+            },
+        ]);
+    }
+
+    #[test]
+    fn playtime_is_answered() {
+        let playtime = Arc::new(Mutex::new(Playtime::new()));
+        let (mut handler, requests) = syscall_channel(playtime.clone());
+
+        assert_eq!(handler.dispatch(call_id::PLAYTIME, 0), Some(0));
+        for _ in 0..150 {
+            playtime.lock().update(Ticks::from_u32(1), true);
+        }
+        // 2.5 seconds
+        assert_eq!(handler.dispatch(call_id::PLAYTIME, 0), Some(2));
+        assert_eq!(requests.try_iter().count(), 0);
+    }
+}
@@ -0,0 +1,388 @@
+//! Running a whole [`Adv`] on a scenario assembled in the test, without any game assets.
+//!
+//! It renders on a [`TestRenderer`], so the tests using it are `#[ignore]`d like the other GPU tests.
+
+use std::{
+    io::Cursor,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use binrw::BinWrite;
+use enum_map::{EnumMap, enum_map};
+use image::RgbaImage;
+use shin_audio::TestClock;
+use shin_core::{
+    format::{
+        font::read_lazy_font,
+        scenario::{
+            Scenario,
+            instruction_elements::{
+                BitmaskNumberArray, CodeAddress, MessageId, NumberSpec, Register, U8Bool,
+                UntypedNumberSpec,
+            },
+            instructions::Instruction,
+            types::U8SmallNumberList,
+        },
+        text::{StringArray, U16FixupString, U16String},
+    },
+    primitives::update::FrameId,
+    time::Ticks,
+    vm::{
+        Scripter,
+        command::{
+            CompiletimeCommand,
+            compiletime::{
+                LAYERCTRL, LAYERLOAD, LAYERSWAP, LAYERUNLOAD, MSGSET, QUIZ, SELECT, SYSCALL,
+                UNLOCK, WAIT,
+            },
+            types::{LayerProperty, LayerType},
+        },
+    },
+};
+use shin_input::ActionState;
+use shin_render::render_pass::RenderPass;
+use winit::dpi::PhysicalSize;
+
+use crate::{
+    adv::{
+        Adv,
+        assets::{AdvAssets, AdvFonts},
+    },
+    app::AppAction,
+    asset::{
+        font::GpuFontLazy,
+        system::{AssetServer, LayeredAssetIo},
+    },
+    layer::message_layer::MessageboxTextures,
+    render::test_utils::{TestRenderer, create_task_pools},
+    update::UpdateContext,
+};
+
+fn constant<T>(value: i32) -> NumberSpec<T> {
+    NumberSpec::new(UntypedNumberSpec::Constant(value))
+}
+
+pub fn wait(ticks: i32) -> Instruction {
+    Instruction::Command(CompiletimeCommand::WAIT(WAIT {
+        allow_interrupt: U8Bool(false),
+        wait_amount: constant(ticks),
+    }))
+}
+
+/// Load a fullscreen tile layer into the `layer` slot
+pub fn layerload_tile(layer: i32) -> Instruction {
+    let params = [0xf00f, 0, 0, 1920, 1080, 0, 0, 0].map(UntypedNumberSpec::Constant);
+
+    Instruction::Command(CompiletimeCommand::LAYERLOAD(LAYERLOAD {
+        layer_id: constant(layer),
+        layer_type: constant(LayerType::Tile as i32),
+        flags: constant(0),
+        params: BitmaskNumberArray::new(params),
+    }))
+}
+
+/// Load the animation `/animation/<animation_id>.anim` into the `layer` slot, played once at the normal speed
+pub fn layerload_animation(layer: i32, animation_id: i32) -> Instruction {
+    let params = [animation_id, 0, 0, 0, 0, 0, 0, 0].map(UntypedNumberSpec::Constant);
+
+    Instruction::Command(CompiletimeCommand::LAYERLOAD(LAYERLOAD {
+        layer_id: constant(layer),
+        layer_type: constant(LayerType::Animation as i32),
+        flags: constant(0),
+        params: BitmaskNumberArray::new(params),
+    }))
+}
+
+pub fn layerunload(layer: i32) -> Instruction {
+    Instruction::Command(CompiletimeCommand::LAYERUNLOAD(LAYERUNLOAD {
+        layer_id: constant(layer),
+        delay_time: constant(0),
+    }))
+}
+
+/// Linearly tween the `property` of the `layer` to the `target_value` over `ticks`, without waiting for it
+pub fn layerctrl(
+    layer: i32,
+    property: LayerProperty,
+    target_value: i32,
+    ticks: i32,
+) -> Instruction {
+    let params = [target_value, ticks, 0, 0, 0, 0, 0, 0].map(UntypedNumberSpec::Constant);
+
+    Instruction::Command(CompiletimeCommand::LAYERCTRL(LAYERCTRL {
+        layer_id: constant(layer),
+        property_id: constant(property as i32),
+        params: BitmaskNumberArray::new(params),
+    }))
+}
+
+/// Swap the layers in the `layer_1` and `layer_2` slots
+pub fn layerswap(layer_1: i32, layer_2: i32) -> Instruction {
+    Instruction::Command(CompiletimeCommand::LAYERSWAP(LAYERSWAP {
+        layer_id_1: constant(layer_1),
+        layer_id_2: constant(layer_2),
+    }))
+}
+
+/// Request the syscall `call_id`, see [`syscall_result_register`](shin_core::vm::syscall::syscall_result_register) for its result
+pub fn syscall(call_id: i32, argument: i32) -> Instruction {
+    Instruction::Command(CompiletimeCommand::SYSCALL(SYSCALL {
+        call_id: constant(call_id),
+        argument: constant(argument),
+    }))
+}
+
+/// Unlock the gallery entries with the `indices`, see [`UnlockType`](shin_core::format::save::UnlockType) for the `unlock_type`
+pub fn unlock(unlock_type: u8, indices: &[i32]) -> Instruction {
+    Instruction::Command(CompiletimeCommand::UNLOCK(UNLOCK {
+        unlock_type,
+        unlock_indices: U8SmallNumberList::from_contents(indices.iter().map(|&i| constant(i))),
+    }))
+}
+
+/// Let the player choose from the `variants` shown by the `visibility_mask`, writing the chosen index to `dest`
+pub fn select(dest: Register, visibility_mask: i32, variants: &[&str]) -> Instruction {
+    Instruction::Command(CompiletimeCommand::SELECT(SELECT {
+        choice_set_base: 0,
+        choice_index: 0,
+        dest,
+        choice_visibility_mask: constant(visibility_mask),
+        choice_title: U16String::new("Choose"),
+        variants: StringArray::new(variants.iter().copied()),
+    }))
+}
+
+/// Let the player answer the quiz `quiz_id`, writing the answer to `dest`
+pub fn quiz(dest: Register, quiz_id: i32) -> Instruction {
+    Instruction::Command(CompiletimeCommand::QUIZ(QUIZ {
+        dest,
+        arg: constant(quiz_id),
+    }))
+}
+
+/// Show a message, waiting for it to be clicked through
+pub fn msgset(text: &str) -> Instruction {
+    Instruction::Command(CompiletimeCommand::MSGSET(MSGSET {
+        msg_id: MessageId(0),
+        auto_wait: U8Bool(true),
+        text: U16FixupString::new(text),
+    }))
+}
+
+/// Where the code of the [`assemble`]d scenarios starts, right after the header
+pub const CODE_OFFSET: u32 = 88;
+
+/// Builds a scenario without any info tables, running the `code` and then idling forever
+pub fn assemble(code: &[Instruction]) -> Scenario {
+    let mut writer = Cursor::new(Vec::new());
+    for instruction in code {
+        instruction.write(&mut writer).unwrap();
+    }
+    let idle_loop = CodeAddress(CODE_OFFSET + writer.position() as u32);
+    for instruction in [wait(60), Instruction::j { target: idle_loop }] {
+        instruction.write(&mut writer).unwrap();
+    }
+    let code = writer.into_inner();
+
+    let size = CODE_OFFSET + code.len() as u32;
+    let mut data = b"SNR ".to_vec();
+    for value in [size, 0, 6, 19, 0, 0, 0, CODE_OFFSET] {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    // all the info table pointers are null
+    data.resize(CODE_OFFSET as usize, 0);
+    data.extend_from_slice(&code);
+
+    Scenario::new(data.into()).unwrap()
+}
+
+/// Encodes a font with a solid 8x8 glyph for all the characters, except for the space which is blank
+fn encode_font() -> Vec<u8> {
+    const TABLE_END: u32 = 16 + 0x10000 * 4;
+
+    // all the 4 mip levels of the texture, stored as LZ77 literals (preceded by an empty bitmap every 8 bytes)
+    let encode_glyph = |metrics: [u8; 8], coverage: u8| {
+        let mip_levels = [coverage; 8 * 8 + 4 * 4 + 2 * 2 + 1];
+        let compressed = mip_levels
+            .chunks(8)
+            .flat_map(|chunk| [&[0][..], chunk].concat())
+            .collect::<Vec<_>>();
+
+        let mut glyph = metrics.to_vec();
+        glyph.extend_from_slice(&(compressed.len() as u16).to_le_bytes());
+        glyph.extend_from_slice(&compressed);
+        glyph
+    };
+    // bearing x and y, actual width and height, advance width, unused, texture width and height
+    let solid_glyph = encode_glyph([0, 8, 8, 8, 8, 0, 8, 8], 0xff);
+    let space_glyph = encode_glyph([0, 0, 0, 0, 8, 0, 8, 8], 0);
+    let space_offset = TABLE_END + solid_glyph.len() as u32;
+
+    let mut font = b"FNT4".to_vec();
+    // version, size, ascent and descent
+    font.extend_from_slice(&1u32.to_le_bytes());
+    font.extend_from_slice(&(space_offset + space_glyph.len() as u32).to_le_bytes());
+    font.extend_from_slice(&12u16.to_le_bytes());
+    font.extend_from_slice(&4u16.to_le_bytes());
+    for character in 0..0x10000u32 {
+        let offset = if character == ' ' as u32 {
+            space_offset
+        } else {
+            TABLE_END
+        };
+        font.extend_from_slice(&offset.to_le_bytes());
+    }
+    font.extend_from_slice(&solid_glyph);
+    font.extend_from_slice(&space_glyph);
+
+    font
+}
+
+/// A font with the ascent of 12 and the descent of 4, with the glyphs being 8x8 squares sitting on the baseline
+pub fn font() -> Arc<GpuFontLazy> {
+    Arc::new(GpuFontLazy::new(
+        read_lazy_font(&mut Cursor::new(encode_font())).unwrap(),
+    ))
+}
+
+/// The test [`font`] in all the styles
+pub fn adv_fonts() -> AdvFonts {
+    let font = font();
+    AdvFonts {
+        system_font: font.clone(),
+        medium_font: font.clone(),
+        bold_font: font,
+    }
+}
+
+/// Blank messagebox textures, the tests don't look at them
+pub fn messagebox_textures(renderer: &TestRenderer) -> Arc<MessageboxTextures> {
+    let blank = RgbaImage::new(1, 1);
+    Arc::new(MessageboxTextures {
+        keywait: renderer.upload(&blank),
+        select: renderer.upload(&blank),
+        select_cursor: renderer.upload(&blank),
+        message_window_1: renderer.upload(&blank),
+        message_window_2: renderer.upload(&blank),
+        message_window_3: renderer.upload(&blank),
+    })
+}
+
+/// An [`Adv`] running on a test scenario, one tick (and one frame worth of audio) per frame
+pub struct AdvTester {
+    renderer: TestRenderer,
+    asset_server: Arc<AssetServer>,
+    audio_clock: TestClock,
+    frame_id: FrameId,
+    pub adv: Adv,
+    /// Passed to [`Adv::update`], `false` for the frames when the game is paused or minimized
+    pub is_active: bool,
+}
+
+impl AdvTester {
+    pub fn new(code: &[Instruction]) -> Self {
+        Self::with_assets(code, LayeredAssetIo::new())
+    }
+
+    /// Like [`Self::new`], with the scenario loading its assets from `io`
+    pub fn with_assets(code: &[Instruction], io: LayeredAssetIo) -> Self {
+        let renderer = TestRenderer::new(PhysicalSize::new(192, 108));
+        create_task_pools();
+
+        let asset_server = renderer.asset_server(io);
+
+        let messagebox_textures = messagebox_textures(&renderer);
+
+        let scenario = Arc::new(assemble(code));
+        let scripter = Scripter::new(&scenario, 0, 42);
+        let assets = AdvAssets {
+            scenario,
+            fonts: adv_fonts(),
+            messagebox_textures,
+        };
+        let audio_clock = TestClock::new(48000);
+        let adv = Adv::new(audio_clock.audio_manager().clone(), assets, scripter);
+
+        Self {
+            renderer,
+            asset_server,
+            audio_clock,
+            frame_id: FrameId::default(),
+            adv,
+            is_active: true,
+        }
+    }
+
+    /// Run a frame with the `actions` clicked
+    pub fn update(&mut self, actions: &[AppAction]) {
+        let input_state: EnumMap<AppAction, ActionState> = enum_map! {
+            action => ActionState {
+                is_held: actions.contains(&action),
+                is_clicked: actions.contains(&action),
+                ..Default::default()
+            }
+        };
+
+        self.frame_id.advance();
+        let is_active = self.is_active;
+        self.with_update_context(|adv, context| adv.update(context, input_state, is_active));
+        self.audio_clock.run_frames(1).unwrap();
+    }
+
+    /// Run `f` with the context of the current frame, like the app does for the updates
+    pub fn with_update_context<R>(
+        &mut self,
+        f: impl FnOnce(&mut Adv, &mut UpdateContext) -> R,
+    ) -> R {
+        let Self {
+            renderer,
+            asset_server,
+            frame_id,
+            adv,
+            ..
+        } = self;
+        renderer.pre_render(|pre_render| {
+            f(adv, &mut UpdateContext {
+                frame_id: *frame_id,
+                delta_ticks: Ticks::from_u32(1),
+                asset_server,
+                pre_render,
+            })
+        })
+    }
+
+    /// Render the last frame with `render` into a new canvas-sized texture, like the app does into the window
+    pub fn render(&mut self, render: impl FnOnce(&Adv, &mut RenderPass)) -> RgbaImage {
+        let mut target = self.renderer.new_render_texture();
+
+        let adv = &self.adv;
+        self.renderer.pre_render(|context| {
+            let depth_stencil = context.depth_stencil;
+            let mut pass = context.begin_pass(
+                target.as_texture_target(),
+                Some(depth_stencil),
+                "AdvTester/render",
+            );
+            render(adv, &mut pass);
+        });
+
+        self.renderer.read(&target)
+    }
+
+    pub fn run_frames(&mut self, count: u32) {
+        for _ in 0..count {
+            self.update(&[]);
+        }
+    }
+
+    /// Run frames until the `condition` holds, giving the layers loading in the background some time
+    pub fn run_until(&mut self, condition: impl Fn(&Adv) -> bool) {
+        let start = Instant::now();
+        while !condition(&self.adv) {
+            assert!(start.elapsed() < Duration::from_secs(5), "Timed out");
+            self.update(&[]);
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+}
@@ -200,6 +200,7 @@ impl LayerbankAllocator {
             };
             let layer_id = self.layerbank_id_to_layer_id[layerbank_id].unwrap().layer;
             f(layer_id, layerbank_id);
+            return;
         }
 
         if !self.range_cache.is_hit(plane, from, to) {
@@ -398,7 +399,8 @@ pub struct LayersState {
     // NB: missing wiper state set by TRANSSET here
     // probably not a problem with umineko not utilizing this system
     pub layerbank_allocator: LayerbankAllocator,
-    pub layer_selection: LayerSelection,
+    /// Layers targeted by [`VLayerIdRepr::Selected`], `None` if the last selection was invalid
+    pub layer_selection: Option<LayerSelection>,
     pub current_plane: PlaneId,
     pub is_page_back_started: bool,
     pub layer_load_with_init_counter: u32,
@@ -417,7 +419,7 @@ impl LayersState {
             page_layer: LayerPropertiesState::new(),
             plane_layergroups: EnumMap::from_fn(|_| PlaneLayerGroupState::new()),
             layerbank_allocator: LayerbankAllocator::new(),
-            layer_selection: LayerSelection::new(),
+            layer_selection: Some(LayerSelection::new()),
             current_plane: PlaneId::new(0),
             is_page_back_started: false,
             layer_load_with_init_counter: 0,
@@ -425,4 +427,264 @@ impl LayersState {
             layerbanks: EnumMap::from_fn(|_| LayerbankState::new()),
         }
     }
+
+    /// Select the plane the layer commands operate on
+    ///
+    /// Returns `None` and keeps the current plane if the id is out of range
+    pub fn select_plane(&mut self, plane_id: i32) -> Option<PlaneId> {
+        let plane = u8::try_from(plane_id).ok().and_then(PlaneId::try_new)?;
+        self.current_plane = plane;
+        Some(plane)
+    }
+
+    /// Select the layers targeted by [`VLayerIdRepr::Selected`]
+    ///
+    /// An inverted range selects nothing, making the batch operations on the selection no-ops
+    pub fn select_layers(&mut self, from: LayerId, to: LayerId) {
+        if from > to {
+            warn!(
+                "LAYERSELECT: inverted selection range {:?}..={:?}, selecting nothing",
+                from, to
+            );
+            self.layer_selection = None;
+        } else {
+            self.layer_selection = Some(LayerSelection { from, to });
+        }
+    }
+
+    /// Get the loaded layers in the selection on the current plane
+    pub fn selected_layers(&mut self) -> LayerOperationTargetList {
+        match self.layer_selection {
+            Some(LayerSelection { from, to }) => {
+                self.layerbank_allocator
+                    .layers_in_range(self.current_plane, from, to)
+            }
+            None => LayerOperationTargetList::new(),
+        }
+    }
+
+    /// Exchange the layerbanks of two layers on the plane, moving the layers and their properties between the slots
+    ///
+    /// Returns the layerbanks that changed their slot, along with the new layer ids
+    pub fn swap_layers(
+        &mut self,
+        plane: PlaneId,
+        layer_1: LayerId,
+        layer_2: LayerId,
+    ) -> LayerOperationTargetList {
+        let mut moved = LayerOperationTargetList::new();
+        if layer_1 == layer_2 {
+            return moved;
+        }
+
+        let layerbank_1 = self.layerbank_allocator.get_layerbank_id(plane, layer_1);
+        let layerbank_2 = self.layerbank_allocator.get_layerbank_id(plane, layer_2);
+        self.layerbank_allocator
+            .swap_layerbanks(plane, layer_1, layer_2);
+
+        for (layerbank, layer) in [(layerbank_1, layer_2), (layerbank_2, layer_1)] {
+            let Some(layerbank) = layerbank else {
+                continue;
+            };
+            self.layerbanks[layerbank].layer_id = layer;
+            let Ok(()) = moved.push(LayerOperationTarget { layer, layerbank }) else {
+                unreachable!()
+            };
+        }
+
+        moved
+    }
+
+    /// Unload all the layers of the plane and remove its mask
+    pub fn clear_plane(&mut self, plane: PlaneId) {
+        let affected_layers = self.layerbank_allocator.layers_in_range(
+            plane,
+            LayerId::new(0),
+            LayerId::new(LAYERS_COUNT as u16 - 1),
+        );
+
+        for &target in &affected_layers {
+            self.layerbanks[target.layerbank].layer_type = None;
+            self.layerbank_allocator.free_layerbank(plane, target.layer);
+        }
+
+        self.plane_layergroups[plane].mask_id = MaskIdOpt::none();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shin_core::{
+        format::scenario::{info::MaskIdOpt, instruction_elements::FromNumber},
+        vm::command::types::{LayerId, LayerProperty, LayerType, PlaneId},
+    };
+
+    use super::LayersState;
+
+    fn load(layers: &mut LayersState, plane: u8, layer: u16) {
+        let plane = PlaneId::new(plane);
+        let layer = LayerId::new(layer);
+
+        let layerbank = layers
+            .layerbank_allocator
+            .alloc_layerbank(plane, layer)
+            .unwrap();
+        let state = &mut layers.layerbanks[layerbank];
+        state.layer_type = Some(LayerType::Picture);
+        state.plane = plane;
+        state.layer_id = layer;
+    }
+
+    fn is_loaded(layers: &LayersState, plane: u8, layer: u16) -> bool {
+        layers
+            .layerbank_allocator
+            .get_layerbank_id(PlaneId::new(plane), LayerId::new(layer))
+            .is_some_and(|layerbank| layers.layerbanks[layerbank].layer_type.is_some())
+    }
+
+    #[test]
+    fn select_plane() {
+        let mut layers = LayersState::new();
+
+        assert_eq!(layers.select_plane(2), Some(PlaneId::new(2)));
+        assert_eq!(layers.current_plane, PlaneId::new(2));
+
+        assert_eq!(layers.select_plane(4), None);
+        assert_eq!(layers.select_plane(-1), None);
+        assert_eq!(layers.select_plane(256), None);
+        assert_eq!(layers.current_plane, PlaneId::new(2));
+    }
+
+    #[test]
+    fn clear_selected_plane() {
+        let mut layers = LayersState::new();
+        load(&mut layers, 0, 1);
+        load(&mut layers, 0, 5);
+        load(&mut layers, 1, 1);
+        load(&mut layers, 1, 200);
+        for plane in [0, 1] {
+            layers.plane_layergroups[PlaneId::new(plane)].mask_id =
+                MaskIdOpt::from_number(plane as i32);
+        }
+
+        layers.select_plane(1).unwrap();
+        layers.clear_plane(layers.current_plane);
+
+        assert!(is_loaded(&layers, 0, 1));
+        assert!(is_loaded(&layers, 0, 5));
+        assert!(!is_loaded(&layers, 1, 1));
+        assert!(!is_loaded(&layers, 1, 200));
+        let mask_id = |plane| layers.plane_layergroups[PlaneId::new(plane)].mask_id;
+        assert_eq!(mask_id(0), MaskIdOpt::from_number(0));
+        assert_eq!(mask_id(1), MaskIdOpt::none());
+
+        // the freed layerbanks can be reused
+        load(&mut layers, 1, 1);
+        assert!(is_loaded(&layers, 1, 1));
+    }
+
+    #[test]
+    fn batch_operation_on_selection() {
+        let mut layers = LayersState::new();
+        for layer in 1..=6 {
+            load(&mut layers, 0, layer);
+        }
+        load(&mut layers, 1, 3);
+
+        layers.select_layers(LayerId::new(2), LayerId::new(4));
+        for target in layers.selected_layers() {
+            layers.layerbanks[target.layerbank]
+                .properties
+                .set_property(LayerProperty::TranslateX, 100);
+        }
+
+        let translate_x = |plane, layer| {
+            let layerbank = layers
+                .layerbank_allocator
+                .get_layerbank_id(PlaneId::new(plane), LayerId::new(layer))
+                .unwrap();
+            layers.layerbanks[layerbank]
+                .properties
+                .get_property(LayerProperty::TranslateX)
+        };
+        let changed = (1..=6)
+            .filter(|&layer| translate_x(0, layer) == 100)
+            .collect::<Vec<_>>();
+        assert_eq!(changed, vec![2, 3, 4]);
+        assert_eq!(translate_x(1, 3), 0);
+    }
+
+    #[test]
+    fn single_layer_selection() {
+        let mut layers = LayersState::new();
+        load(&mut layers, 0, 1);
+        load(&mut layers, 0, 2);
+
+        layers.select_layers(LayerId::new(2), LayerId::new(2));
+        let selected = layers.selected_layers();
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].layer, LayerId::new(2));
+    }
+
+    #[test]
+    fn inverted_selection_is_empty() {
+        let mut layers = LayersState::new();
+        for layer in 1..=6 {
+            load(&mut layers, 0, layer);
+        }
+
+        layers.select_layers(LayerId::new(4), LayerId::new(2));
+        assert!(layers.layer_selection.is_none());
+        assert!(layers.selected_layers().is_empty());
+    }
+
+    #[test]
+    fn swap_layers() {
+        let mut layers = LayersState::new();
+        load(&mut layers, 0, 1);
+        load(&mut layers, 0, 2);
+        let plane = PlaneId::new(0);
+
+        let set_translate_x = |layers: &mut LayersState, layer, value| {
+            let layerbank = layers
+                .layerbank_allocator
+                .get_layerbank_id(plane, LayerId::new(layer))
+                .unwrap();
+            layers.layerbanks[layerbank]
+                .properties
+                .set_property(LayerProperty::TranslateX, value);
+        };
+        let translate_x = |layers: &LayersState, layer| {
+            let layerbank = layers
+                .layerbank_allocator
+                .get_layerbank_id(plane, LayerId::new(layer))?;
+            assert_eq!(layers.layerbanks[layerbank].layer_id, LayerId::new(layer));
+            Some(
+                layers.layerbanks[layerbank]
+                    .properties
+                    .get_property(LayerProperty::TranslateX),
+            )
+        };
+
+        set_translate_x(&mut layers, 1, 100);
+        set_translate_x(&mut layers, 2, 200);
+
+        let moved = layers.swap_layers(plane, LayerId::new(1), LayerId::new(2));
+        assert_eq!(moved.len(), 2);
+        assert_eq!(translate_x(&layers, 1), Some(200));
+        assert_eq!(translate_x(&layers, 2), Some(100));
+
+        // swapping with itself does nothing
+        let moved = layers.swap_layers(plane, LayerId::new(1), LayerId::new(1));
+        assert!(moved.is_empty());
+        assert_eq!(translate_x(&layers, 1), Some(200));
+
+        // swapping with an empty slot moves the layer
+        let moved = layers.swap_layers(plane, LayerId::new(2), LayerId::new(7));
+        assert_eq!(moved.len(), 1);
+        assert_eq!(moved[0].layer, LayerId::new(7));
+        assert_eq!(translate_x(&layers, 2), None);
+        assert_eq!(translate_x(&layers, 7), Some(100));
+        assert!(is_loaded(&layers, 0, 7));
+    }
 }
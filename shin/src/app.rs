@@ -7,17 +7,27 @@ use shin_core::{
     format::scenario::instruction_elements::CodeAddress, primitives::update::FrameId, time::Ticks,
     vm::Scripter,
 };
-use shin_input::{Action, ActionState, RawInputState, inputs::MouseButton};
+use shin_input::{Action, ActionState, Binding, Bindings, inputs::MouseButton};
 use shin_render::render_pass::RenderPass;
 use shin_window::{AppContext, RenderContext, ShinApp};
-use tracing::debug;
+use tracing::{debug, error};
 use winit::keyboard::KeyCode;
 
 use crate::{
     adv::{Adv, assets::AdvAssets},
-    asset::system::{AssetLoadContext, AssetServer, cache::AssetCache, locate_assets},
+    asset::{
+        asset_paths,
+        system::{AssetLoadContext, AssetServer, cache::AssetCache, locate_assets},
+    },
+    audio::VoiceCaptionTable,
     cli::Cli,
-    render::PreRenderContext,
+    render::{
+        PreRenderContext,
+        anti_aliasing::AntiAliasing,
+        dynamic_resolution::{DynamicResolution, DynamicResolutionParams},
+        overdraw::render_overdraw,
+        post_process::{PostProcess, PostProcessParams, Vignette},
+    },
     update::UpdateContext,
 };
 
@@ -27,26 +37,36 @@ pub enum AppAction {
     Act,
     Enter,
     Cancel,
+    AnyUp,
     AnyDown,
     HoldSkip,
+    QuickSave,
+    QuickLoad,
+    ExportTranscript,
+    ReplayVoice,
+    ToggleOverdrawView,
 }
 
 impl Action for AppAction {
-    fn lower(raw_input_state: &RawInputState) -> EnumMap<Self, bool> {
-        EnumMap::from_fn(|action| match action {
-            AppAction::ToggleFullscreen => raw_input_state.keyboard.contains(&KeyCode::F11),
-            AppAction::Act => {
-                raw_input_state.keyboard.contains(&KeyCode::Space)
-                    || raw_input_state.mouse.buttons[MouseButton::Left]
-            }
-            AppAction::Enter => {
-                raw_input_state.keyboard.contains(&KeyCode::Space)
-                    | raw_input_state.keyboard.contains(&KeyCode::Enter)
-            }
-            AppAction::Cancel => raw_input_state.keyboard.contains(&KeyCode::Backspace),
-            AppAction::AnyDown => raw_input_state.keyboard.contains(&KeyCode::ArrowDown),
-            AppAction::HoldSkip => raw_input_state.keyboard.contains(&KeyCode::ControlLeft),
-        })
+    fn default_bindings() -> Bindings<Self> {
+        use Binding::{Key, Mouse};
+
+        Bindings::new()
+            .with(AppAction::ToggleFullscreen, [Key(KeyCode::F11)])
+            .with(AppAction::Act, [
+                Key(KeyCode::Space),
+                Mouse(MouseButton::Left),
+            ])
+            .with(AppAction::Enter, [Key(KeyCode::Space), Key(KeyCode::Enter)])
+            .with(AppAction::Cancel, [Key(KeyCode::Backspace)])
+            .with(AppAction::AnyUp, [Key(KeyCode::ArrowUp)])
+            .with(AppAction::AnyDown, [Key(KeyCode::ArrowDown)])
+            .with(AppAction::HoldSkip, [Key(KeyCode::ControlLeft)])
+            .with(AppAction::QuickSave, [Key(KeyCode::F5)])
+            .with(AppAction::QuickLoad, [Key(KeyCode::F9)])
+            .with(AppAction::ExportTranscript, [Key(KeyCode::F6)])
+            .with(AppAction::ReplayVoice, [Key(KeyCode::KeyR)])
+            .with(AppAction::ToggleOverdrawView, [Key(KeyCode::F3)])
     }
 }
 
@@ -56,6 +76,36 @@ pub struct App {
     audio_manager: Arc<AudioManager>,
     asset_server: Arc<AssetServer>,
     adv: Adv,
+    dynamic_resolution: DynamicResolution,
+    anti_aliasing: AntiAliasing,
+    post_process: PostProcess,
+    /// Debug view replacing the image with the visualization of the overdraw, see [`render_overdraw`]
+    overdraw_view: bool,
+    /// Time since the asset files were last checked for changes, `None` unless hot-reloading
+    since_hot_reload_check: Option<Duration>,
+}
+
+/// How often the asset files are checked for changes when hot-reloading
+const HOT_RELOAD_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Render the adv scene, going through the scaled scene texture when the dynamic resolution is enabled
+/// and through the anti-aliased one when the anti-aliasing is on
+fn render_adv(
+    adv: &Adv,
+    dynamic_resolution: &DynamicResolution,
+    anti_aliasing: &AntiAliasing,
+    pass: &mut RenderPass,
+) {
+    anti_aliasing.render(
+        pass,
+        |pass| dynamic_resolution.render(pass, |pass| adv.render(pass), |pass| adv.render_ui(pass)),
+        |pass| {
+            // otherwise the UI was anti-aliased along with the scene, there's no way around that
+            if !dynamic_resolution.scene_includes_ui() {
+                adv.render_ui(pass)
+            }
+        },
+    );
 }
 
 impl ShinApp for App {
@@ -70,11 +120,15 @@ impl ShinApp for App {
 
         debug!("Asset IO: {:#?}", asset_io);
 
-        let asset_server = Arc::new(AssetServer::new(asset_io.into(), AssetLoadContext {
+        let mut asset_server = AssetServer::new(asset_io.into(), AssetLoadContext {
             wgpu_device: context.wgpu.device.clone(),
             wgpu_queue: context.wgpu.queue.clone(),
             bustup_cache: AssetCache::new(),
-        }));
+        });
+        if cli.hot_reload {
+            asset_server.enable_hot_reload();
+        }
+        let asset_server = Arc::new(asset_server);
 
         // TODO: do not block the game loop (?)
         let adv_assets = shin_tasks::block_on(AdvAssets::load(&asset_server)).unwrap();
@@ -88,6 +142,24 @@ impl ShinApp for App {
 
         let mut adv = Adv::new(audio_manager.clone(), adv_assets, scripter);
 
+        if let Some(path) = &cli.voice_captions {
+            let captions = std::fs::read_to_string(path).with_context(|| {
+                format!("Failed to read voice captions from {}", path.display())
+            })?;
+            adv.set_voice_captions(Arc::new(VoiceCaptionTable::parse(&captions)));
+        }
+
+        let transcript_path = cli
+            .transcript_path
+            .unwrap_or_else(|| format!("transcript.{}", cli.transcript_format.extension()).into());
+        adv.set_transcript_output(transcript_path, cli.transcript_format);
+        adv.set_reduced_motion(cli.reduced_motion);
+        adv.set_group_opacity(cli.group_opacity);
+
+        if let (Some(timeout), Some(addr)) = (cli.idle_timeout, cli.attract_entry_point) {
+            adv.set_idle_timeout(timeout, CodeAddress(addr));
+        }
+
         if let Some(addr) = cli.fast_forward_to {
             debug!("Fast forwarding to 0x{:x}", addr);
             adv.fast_forward_to(CodeAddress(addr));
@@ -141,14 +213,13 @@ impl ShinApp for App {
         // layer_group.add_layer(LayerbankId::new(2), tile_layer_top.into());
         //
         // {
-        //     let tweener = page_layer
+        //     let half_second = Ticks::from_seconds(0.5);
+        //     page_layer
         //         .properties_mut()
-        //         .property_tweener_mut(LayerProperty::MulColorRed);
-        //
-        //     tweener.enqueue(2000.0, Tween::linear(Ticks::from_seconds(0.5)));
-        //     tweener.enqueue(1000.0, Tween::linear(Ticks::from_seconds(0.5)));
-        //     tweener.enqueue(2000.0, Tween::linear(Ticks::from_seconds(0.5)));
-        //     tweener.enqueue(1000.0, Tween::linear(Ticks::from_seconds(0.5)));
+        //         .animate(LayerProperty::MulColorRed, 2000.0, half_second, Easing::Linear)
+        //         .then(LayerProperty::MulColorRed, 1000.0, half_second, Easing::Linear)
+        //         .then(LayerProperty::MulColorRed, 2000.0, half_second, Easing::Linear)
+        //         .then(LayerProperty::MulColorRed, 1000.0, half_second, Easing::Linear);
         //
         //     // tweener.enqueue(2000.0, Tween::linear(Ticks::from_seconds(1.0)));
         //     // tweener.enqueue(0.0, Tween::linear(Ticks::from_seconds(1.0)));
@@ -157,11 +228,36 @@ impl ShinApp for App {
         //     // tweener.enqueue(1000.0, Tween::linear(Ticks::from_seconds(0.5)));
         // }
 
+        let dynamic_resolution = DynamicResolution::new(DynamicResolutionParams {
+            target_frame_time: cli.dynamic_resolution,
+            min_scale: cli.dynamic_resolution_min_scale,
+            native_ui: cli.dynamic_resolution_native_ui,
+        });
+
+        let anti_aliasing = AntiAliasing::new(cli.anti_aliasing);
+
+        let post_process = PostProcess::new(PostProcessParams {
+            gamma: cli.gamma,
+            brightness: cli.brightness,
+            color_filter: cli.color_filter,
+            vignette: Vignette {
+                strength: cli.vignette,
+                radius: cli.vignette_radius,
+                softness: cli.vignette_softness,
+                color: cli.vignette_color,
+            },
+        });
+
         Ok(Self {
             frame_id: FrameId::default(),
             audio_manager,
             asset_server,
             adv,
+            dynamic_resolution,
+            anti_aliasing,
+            post_process,
+            overdraw_view: false,
+            since_hot_reload_check: cli.hot_reload.then_some(Duration::ZERO),
         })
     }
 
@@ -179,6 +275,19 @@ impl ShinApp for App {
         if input[AppAction::ToggleFullscreen].is_clicked {
             context.winit.toggle_fullscreen();
         }
+        if input[AppAction::ToggleOverdrawView].is_clicked {
+            self.overdraw_view = !self.overdraw_view;
+        }
+        let mut scenario_changed = false;
+        if let Some(since_check) = &mut self.since_hot_reload_check {
+            *since_check += elapsed_time;
+            if *since_check >= HOT_RELOAD_INTERVAL {
+                *since_check = Duration::ZERO;
+                // the scenes pick up the changed assets the next time they load them, but the scenario is only loaded once
+                let changed = self.asset_server.reload_changed();
+                scenario_changed = changed.iter().any(|path| path == asset_paths::SCENARIO);
+            }
+        }
 
         // if input[AppAction::Act].is_clicked {
         //     let screen_layer = self.root_layer_group.screen_layer_mut();
@@ -211,7 +320,42 @@ impl ShinApp for App {
             pre_render: &mut pre_render_context,
         };
 
-        self.adv.update(&mut update_context, input);
+        if scenario_changed {
+            if let Err(e) = self
+                .asset_server
+                .load_sync(asset_paths::SCENARIO)
+                .and_then(|scenario| self.adv.reload_scenario(&mut update_context, scenario))
+            {
+                error!("Could not reload the scenario: {:?}", e);
+            }
+        }
+
+        // there is no pause menu yet, so the game only stops being played when minimized
+        let is_active = context.winit.window.is_minimized() != Some(true);
+        self.adv.update(&mut update_context, input, is_active);
+
+        self.dynamic_resolution.update(elapsed_time);
+        let adv = &self.adv;
+        if self.dynamic_resolution.native_ui() {
+            self.dynamic_resolution
+                .pre_render(&mut pre_render_context, |pass| adv.render_scene(pass));
+        } else {
+            self.dynamic_resolution
+                .pre_render(&mut pre_render_context, |pass| adv.render(pass));
+        }
+
+        let dynamic_resolution = &self.dynamic_resolution;
+        // the UI is left out of the anti-aliased scene, to be drawn over it crisp
+        self.anti_aliasing
+            .pre_render(&mut pre_render_context, |pass| {
+                dynamic_resolution.render(pass, |pass| adv.render_scene(pass), |_| {})
+            });
+
+        let anti_aliasing = &self.anti_aliasing;
+        let render =
+            |pass: &mut RenderPass| render_adv(adv, dynamic_resolution, anti_aliasing, pass);
+        self.post_process
+            .pre_render(&mut pre_render_context, render);
 
         // let update_context = AdvUpdateContext {
         //     delta_time: Ticks::from_duration(elapsed_time),
@@ -251,7 +395,19 @@ impl ShinApp for App {
 
     #[tracing::instrument(skip_all)]
     fn render(&mut self, _context: RenderContext, pass: &mut RenderPass) {
-        self.adv.render(pass);
+        if self.overdraw_view {
+            render_overdraw(pass, |pass| self.adv.render(pass));
+            return;
+        }
+
+        self.post_process.render(pass, |pass| {
+            render_adv(
+                &self.adv,
+                &self.dynamic_resolution,
+                &self.anti_aliasing,
+                pass,
+            )
+        });
 
         // render_layer(pass, &transform, &self.adv, FloatColor4::BLACK, 0);
     }
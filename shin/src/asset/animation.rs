@@ -0,0 +1,57 @@
+use std::sync::Arc;
+
+use shin_core::format::animation::AnimationFrame;
+
+use crate::asset::{
+    sprite_sheet::SpriteSheet,
+    system::{Asset, AssetDataAccessor, AssetLoadContext},
+};
+
+/// A sequence of sprites, see [`shin_core::format::animation`]
+pub struct Animation {
+    sheet: SpriteSheet,
+    frames: Vec<AnimationFrame>,
+}
+
+impl Animation {
+    /// Path of the animation loaded by the animation layer with the given id
+    ///
+    /// There is no info table for the animations, so the id is used as the file name directly.
+    pub fn path(id: i32) -> String {
+        format!("/animation/{}.anim", id)
+    }
+
+    pub fn sheet(&self) -> &SpriteSheet {
+        &self.sheet
+    }
+
+    /// There is always at least one frame, and every frame refers to a sprite in the [sheet](Self::sheet)
+    pub fn frames(&self) -> &[AnimationFrame] {
+        &self.frames
+    }
+}
+
+impl Asset for Animation {
+    type Args = ();
+
+    async fn load(
+        context: &Arc<AssetLoadContext>,
+        _args: Self::Args,
+        name: &str,
+        data: AssetDataAccessor,
+    ) -> anyhow::Result<Self> {
+        let label = format!("Animation[{}]", name);
+        let data = data.read_all().await;
+        let context = context.clone();
+
+        shin_tasks::compute::spawn(move || {
+            let animation = shin_core::format::animation::read_animation(&data)?;
+
+            Ok(Animation {
+                sheet: SpriteSheet::upload(&context, animation.sheet, &label),
+                frames: animation.frames,
+            })
+        })
+        .await
+    }
+}
@@ -3,11 +3,12 @@ use std::{
     sync::{Arc, Weak},
 };
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, anyhow};
 use bevy_utils::HashMap;
 use glam::{Vec2, vec2};
 use indexmap::IndexMap;
 use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
 use shin_core::format::{
     bustup::{
         BustupBlockId, BustupBlockPromise, BustupBlockPromiseToken, BustupBuilder, BustupId,
@@ -45,7 +46,8 @@ struct GpuBustupBuilderArgs<'a> {
     context: GpuTextureBuilderContext<'a>,
     cache: &'a BlockCache,
     label: String,
-    expression: String,
+    /// Only the blocks of this expression are decoded, or only the base blocks if there is none
+    expression: Option<&'a str>,
     character_id: CharacterId,
     disable_animations: bool,
 }
@@ -58,10 +60,19 @@ struct GpuBustupSkeleton<'a> {
     pub bustup_id: BustupId,
 
     pub base_blocks: Vec<GpuBustupBlockPromise<'a>>,
-    pub face1: Option<GpuBustupBlockPromise<'a>>,
-    pub face2: Option<GpuBustupBlockPromise<'a>>,
-    pub mouth_blocks: Vec<GpuBustupBlockPromise<'a>>,
-    pub eye_blocks: Vec<GpuBustupBlockPromise<'a>>,
+    pub expression: Option<BustupExpression<GpuBustupBlockPromise<'a>>>,
+}
+
+/// What a single pass over the bustup file decodes, see [`GpuBustupBuilderArgs::expression`]
+struct GpuBustupParts {
+    origin_x: i16,
+    origin_y: i16,
+    effective_width: u16,
+    effective_height: u16,
+    bustup_id: BustupId,
+
+    base_blocks: Vec<Arc<GpuPictureBlock>>,
+    expression: Option<BustupExpression>,
 }
 
 enum GpuBustupBlockPromise<'a> {
@@ -78,10 +89,88 @@ pub struct Bustup {
     pub bustup_id: BustupId,
 
     pub base_blocks: Vec<Arc<GpuPictureBlock>>,
-    pub face1: Option<Arc<GpuPictureBlock>>,
-    pub face2: Option<Arc<GpuPictureBlock>>,
-    pub mouth_blocks: Vec<Arc<GpuPictureBlock>>,
-    pub eye_blocks: Vec<Arc<GpuPictureBlock>>,
+    /// The expressions decoded so far, see [`Bustup::load_expression`]
+    expressions: Mutex<HashMap<String, Arc<BustupExpression>>>,
+
+    /// The whole file, to decode the other expressions from
+    data: Arc<[u8]>,
+    context: Arc<AssetLoadContext>,
+    label: String,
+    args: BustupArgs,
+}
+
+/// The parts drawn over the base of a bustup to show an expression
+pub struct BustupExpression<Block = Arc<GpuPictureBlock>> {
+    pub face1: Option<Block>,
+    pub face2: Option<Block>,
+    /// Frames of the mouth animation, from the closed mouth to the widest open one
+    pub mouth_blocks: Vec<Block>,
+    pub eye_blocks: Vec<Block>,
+}
+
+impl<Block> BustupExpression<Block> {
+    fn map<NewBlock>(self, mut f: impl FnMut(Block) -> NewBlock) -> BustupExpression<NewBlock> {
+        BustupExpression {
+            face1: self.face1.map(&mut f),
+            face2: self.face2.map(&mut f),
+            mouth_blocks: self.mouth_blocks.into_iter().map(&mut f).collect(),
+            eye_blocks: self.eye_blocks.into_iter().map(&mut f).collect(),
+        }
+    }
+}
+
+impl Bustup {
+    /// Decode the base blocks from the file `data`, or only the blocks of the `expression` if there is one
+    fn decode(
+        context: &AssetLoadContext,
+        data: &[u8],
+        label: &str,
+        args: &BustupArgs,
+        expression: Option<&str>,
+    ) -> Result<GpuBustupParts> {
+        let builder_args = GpuBustupBuilderArgs {
+            context: GpuTextureBuilderContext {
+                wgpu_device: &context.wgpu_device,
+                wgpu_queue: &context.wgpu_queue,
+            },
+            cache: &context.bustup_cache,
+            label: label.to_string(),
+            expression,
+            character_id: args.character_id,
+            disable_animations: args.disable_animations,
+        };
+
+        shin_core::format::bustup::read_bustup::<GpuBustupBuilder>(data, builder_args)
+    }
+
+    /// The parts of the `expression`, decoding them when it is shown for the first time.
+    ///
+    /// A bustup has dozens of expressions, so they are not decoded upfront. The blocks shared between the expressions are only decoded once, thanks to the block cache.
+    pub async fn load_expression(&self, expression: &str) -> Result<Arc<BustupExpression>> {
+        if let Some(loaded) = self.expressions.lock().get(expression) {
+            return Ok(loaded.clone());
+        }
+
+        let context = self.context.clone();
+        let data = self.data.clone();
+        let label = self.label.clone();
+        let args = self.args.clone();
+        let name = expression.to_string();
+        let parts = shin_tasks::compute::spawn(move || {
+            Self::decode(&context, &data, &label, &args, Some(name.as_str()))
+        })
+        .await?;
+
+        let loaded =
+            Arc::new(parts.expression.ok_or_else(|| {
+                anyhow!("Bustup {} has no expression {:?}", self.label, expression)
+            })?);
+        self.expressions
+            .lock()
+            .insert(expression.to_string(), loaded.clone());
+
+        Ok(loaded)
+    }
 }
 
 impl<'a> GpuBustupBlockPromise<'a> {
@@ -117,7 +206,7 @@ impl<'b> BustupBuilder for GpuBustupBuilder<'b> {
     type Args = GpuBustupBuilderArgs<'b>;
     type Skeleton<'a> = GpuBustupSkeleton<'a>;
     type BlockType = Arc<GpuPictureBlock>;
-    type Output = Bustup;
+    type Output = GpuBustupParts;
 
     fn new<'a>(args: &Self::Args, skeleton: BustupSkeleton<'a>) -> Self::Skeleton<'a> {
         let BustupSkeleton {
@@ -127,29 +216,31 @@ impl<'b> BustupBuilder for GpuBustupBuilder<'b> {
             effective_height,
             bustup_id,
             base_blocks,
-            mut expressions,
+            expressions,
         } = skeleton;
 
         let lower_block =
             |block: BustupBlockPromise<'a>| GpuBustupBlockPromise::new(args.cache, block);
 
-        let base_blocks = base_blocks.into_iter().map(lower_block).collect::<Vec<_>>();
-
-        let mut face1 = None;
-        let mut face2 = None;
-        let mut mouth_blocks = Vec::new();
-        let mut eye_blocks = Vec::new();
-
-        if let Some(expression) = expressions.swap_remove(&args.expression) {
-            face1 = expression.face1.map(lower_block);
-            face2 = expression.face2.map(lower_block);
-            mouth_blocks = expression
-                .mouth_blocks
-                .into_iter()
-                .map(lower_block)
-                .collect();
-            eye_blocks = expression.eye_blocks.into_iter().map(lower_block).collect();
-        }
+        // the promises dropped here are not decoded
+        let (base_blocks, expression) =
+            match args.expression {
+                None => (base_blocks.into_iter().map(lower_block).collect(), None),
+                Some(name) => {
+                    let expression = expressions.into_iter().find(|(key, _)| key == name).map(
+                        |(_, expression)| {
+                            BustupExpression {
+                                face1: expression.face1,
+                                face2: expression.face2,
+                                mouth_blocks: expression.mouth_blocks,
+                                eye_blocks: expression.eye_blocks,
+                            }
+                            .map(lower_block)
+                        },
+                    );
+                    (Vec::new(), expression)
+                }
+            };
 
         GpuBustupSkeleton {
             origin_x,
@@ -158,10 +249,7 @@ impl<'b> BustupBuilder for GpuBustupBuilder<'b> {
             effective_height,
             bustup_id,
             base_blocks,
-            face1,
-            face2,
-            mouth_blocks,
-            eye_blocks,
+            expression,
         }
     }
 
@@ -185,41 +273,28 @@ impl<'b> BustupBuilder for GpuBustupBuilder<'b> {
             effective_height,
             bustup_id,
             base_blocks,
-            face1,
-            face2,
-            mouth_blocks,
-            eye_blocks,
+            expression,
         } = skeleton;
 
         let lower_block = |block: GpuBustupBlockPromise| block.materialize(args.cache, &token);
 
         let base_blocks = base_blocks.into_iter().map(lower_block).collect::<Vec<_>>();
-        let face1 = face1.map(|block| lower_block(block));
-        let face2 = face2.map(|block| lower_block(block));
-        let mouth_blocks = mouth_blocks
-            .into_iter()
-            .map(lower_block)
-            .collect::<Vec<_>>();
-        let eye_blocks = eye_blocks.into_iter().map(lower_block).collect::<Vec<_>>();
-
-        Ok(Bustup {
+        let expression = expression.map(|expression| expression.map(lower_block));
+
+        Ok(GpuBustupParts {
             origin_x,
             origin_y,
             effective_width,
             effective_height,
             bustup_id,
             base_blocks,
-            face1,
-            face2,
-            mouth_blocks,
-            eye_blocks,
+            expression,
         })
     }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct BustupArgs {
-    pub expression: String,
     pub character_id: CharacterId,
     pub disable_animations: bool,
 }
@@ -233,24 +308,34 @@ impl Asset for Bustup {
         name: &str,
         data: AssetDataAccessor,
     ) -> Result<Self> {
-        let label = format!("{}[{}]", name, args.expression);
-        let data = data.read_all().await;
+        let label = name.to_string();
+        let data: Arc<[u8]> = data.read_all().await.into();
         let context = context.clone();
 
         shin_tasks::compute::spawn(move || {
-            let args = GpuBustupBuilderArgs {
-                context: GpuTextureBuilderContext {
-                    wgpu_device: &context.wgpu_device,
-                    wgpu_queue: &context.wgpu_queue,
-                },
-                cache: &context.bustup_cache,
+            let GpuBustupParts {
+                origin_x,
+                origin_y,
+                effective_width,
+                effective_height,
+                bustup_id,
+                base_blocks,
+                expression: _,
+            } = Self::decode(&context, &data, &label, &args, None)?;
+
+            Ok(Bustup {
+                origin_x,
+                origin_y,
+                effective_width,
+                effective_height,
+                bustup_id,
+                base_blocks,
+                expressions: Mutex::new(HashMap::default()),
+                data,
+                context,
                 label,
-                expression: args.expression,
-                character_id: args.character_id,
-                disable_animations: args.disable_animations,
-            };
-
-            shin_core::format::bustup::read_bustup::<GpuBustupBuilder>(&data, args)
+                args,
+            })
         })
         .await
     }
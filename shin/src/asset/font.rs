@@ -1,4 +1,8 @@
-use std::{io::Cursor, sync::Arc};
+use std::{
+    collections::HashSet,
+    io::Cursor,
+    sync::{Arc, Mutex},
+};
 
 use anyhow::Context;
 use indexmap::{IndexMap, IndexSet};
@@ -11,6 +15,7 @@ use shin_core::{
     layout::font::FontMetrics,
 };
 use shin_render::{gpu_texture::GpuTexture, shaders::types::texture::TextureSource};
+use tracing::warn;
 
 use crate::asset::system::{
     Asset, AssetDataAccessor, AssetLoadContext,
@@ -20,6 +25,8 @@ use crate::asset::system::{
 pub struct GpuFontLazy {
     font: FontLazy,
     glyph_cache: AssetCache<GlyphId, GpuFontGlyph>,
+    /// The codepoints that were already reported as missing from the font
+    missing_codepoints: Mutex<HashSet<char>>,
 }
 
 impl GpuFontLazy {
@@ -27,9 +34,27 @@ impl GpuFontLazy {
         Self {
             font,
             glyph_cache: AssetCache::new(),
+            missing_codepoints: Mutex::new(HashSet::new()),
         }
     }
 
+    /// Get the glyph id for a character, falling back to the `.notdef` glyph for the ones the font doesn't have
+    ///
+    /// Each missing character is only reported once, as the text using it is usually laid out again every frame.
+    pub fn glyph_id(&self, character: char) -> GlyphId {
+        self.font
+            .get_glyph_id_or_notdef(character as u32)
+            .unwrap_or_else(|notdef| {
+                if self.missing_codepoints.lock().unwrap().insert(character) {
+                    warn!(
+                        "Character {:?} (U+{:04X}) is missing from the font, using the .notdef glyph",
+                        character, character as u32
+                    );
+                }
+                notdef
+            })
+    }
+
     /// Returns handles to an array of glyphs in bulk.
     ///
     /// The actual loading will happen in the compute task pool, with [`GpuFontGlyphHandle`] giving access to the results asynchronously.
@@ -43,12 +68,10 @@ impl GpuFontLazy {
             return Vec::new();
         }
 
-        let glyph_map = self.font.get_character_mapping();
-
         let mut glyphs = Vec::with_capacity(characters.len());
         let mut glyphs_dedup = IndexSet::new();
         for &char in characters {
-            let glyph = glyph_map[char as usize];
+            let glyph = self.glyph_id(char);
             glyphs.push(glyph);
             glyphs_dedup.insert(glyph);
         }
@@ -117,7 +140,8 @@ impl FontMetrics for GpuFontLazy {
     }
 
     fn get_glyph_info(&self, codepoint: char) -> Option<GlyphInfo> {
-        self.font.get_glyph_info(codepoint)
+        let glyph_id = self.glyph_id(codepoint);
+        self.font.get_glyph(glyph_id).map(|glyph| glyph.get_info())
     }
 }
 
@@ -159,6 +183,12 @@ impl GpuFontGlyphHandle {
     pub fn as_texture_source(&self) -> TextureSource {
         self.handle.wait_ref().texture.as_source()
     }
+
+    /// Whether both handles end up with the same glyph texture, no matter who requested them
+    #[cfg(test)]
+    pub fn shares_texture_with(&self, other: &Self) -> bool {
+        std::ptr::eq(self.handle.wait_ref(), other.handle.wait_ref())
+    }
 }
 
 impl Asset for GpuFontLazy {
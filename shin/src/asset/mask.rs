@@ -83,6 +83,30 @@ pub struct MaskTexture {
 }
 
 impl MaskTexture {
+    /// Upload a decoded mask to the GPU
+    pub fn new(
+        context: &AssetLoadContext,
+        mask: &shin_core::format::mask::MaskTexture,
+        label: String,
+    ) -> Self {
+        let (vertex_buffer, index_buffer, offsets) = load_vertices(context, &mask.regions, &label);
+
+        let texture = GpuTexture::new_static_from_gray_image(
+            &context.wgpu_device,
+            &context.wgpu_queue,
+            Some(&format!("{}/texture", label)),
+            &mask.texels,
+        );
+
+        MaskTexture {
+            label,
+            offsets,
+            vertex_buffer,
+            index_buffer,
+            texture,
+        }
+    }
+
     pub fn render(
         &self,
         pass: &mut RenderPass,
@@ -209,23 +233,7 @@ impl Asset for MaskTexture {
         shin_tasks::compute::spawn(move || {
             let mask = shin_core::format::mask::read_mask(&data)?;
 
-            let (vertex_buffer, index_buffer, offsets) =
-                load_vertices(&context, &mask.regions, &label);
-
-            let texture = GpuTexture::new_static_from_gray_image(
-                &context.wgpu_device,
-                &context.wgpu_queue,
-                Some(&format!("{}/texture", label)),
-                &mask.texels,
-            );
-
-            Ok(MaskTexture {
-                label,
-                offsets,
-                vertex_buffer,
-                index_buffer,
-                texture,
-            })
+            Ok(MaskTexture::new(&context, &mask, label))
         })
         .await
     }
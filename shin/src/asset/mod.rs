@@ -1,3 +1,4 @@
+pub mod animation;
 mod audio;
 #[expect(unused)]
 pub mod bustup;
@@ -6,6 +7,7 @@ pub mod mask;
 pub mod movie;
 pub mod picture;
 mod scenario;
+pub mod sprite_sheet;
 pub mod system;
 pub mod texture_archive;
 
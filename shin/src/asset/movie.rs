@@ -40,6 +40,6 @@ impl Movie {
         device: &wgpu::Device,
         audio_manager: &AudioManager,
     ) -> Result<VideoPlayerHandle> {
-        VideoPlayerHandle::new(device, audio_manager, self.mp4.clone())
+        VideoPlayerHandle::new(device, audio_manager, self.mp4.clone(), None)
     }
 }
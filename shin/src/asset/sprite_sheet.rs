@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+
+use glam::{Vec2, Vec4, vec2, vec4};
+use shin_core::format::sprite_sheet::SpriteRect;
+use shin_render::gpu_texture::GpuTexture;
+
+use crate::asset::system::AssetLoadContext;
+
+/// The texture with the frames of an [`Animation`](super::animation::Animation), see [`shin_core::format::sprite_sheet`]
+pub struct SpriteSheet {
+    texture: GpuTexture,
+    sprites: HashMap<String, SpriteRect>,
+}
+
+impl SpriteSheet {
+    /// The sheet texture, with premultiplied alpha
+    pub fn texture(&self) -> &GpuTexture {
+        &self.texture
+    }
+
+    pub fn get_sprite(&self, name: &str) -> Option<SpriteRect> {
+        self.sprites.get(name).copied()
+    }
+
+    /// Upload a decoded sheet to the GPU, premultiplying its texture
+    pub fn upload(
+        context: &AssetLoadContext,
+        mut sheet: shin_core::format::sprite_sheet::SpriteSheet,
+        label: &str,
+    ) -> Self {
+        // `Sprite` expects premultiplied textures
+        sheet.premultiply_alpha();
+
+        let texture = GpuTexture::new_static_from_rgba_image(
+            &context.wgpu_device,
+            &context.wgpu_queue,
+            Some(&format!("{}/texture", label)),
+            &sheet.texture,
+        );
+
+        Self {
+            texture,
+            sprites: sheet.sprites,
+        }
+    }
+}
+
+/// Texture coordinates (left, top, right, bottom) of the `rect` in a texture of `texture_size`
+///
+/// With `half_texel_inset`, the coordinates are moved half a texel inwards, to the centers of the edge texels.
+/// This way the bilinear filtering never picks up the neighbouring sprites when the sprite is scaled, at the cost of losing half of the edge texels.
+pub fn sprite_texture_region(rect: SpriteRect, texture_size: Vec2, half_texel_inset: bool) -> Vec4 {
+    let inset = if half_texel_inset { 0.5 } else { 0.0 };
+
+    let top_left = vec2(rect.x as f32, rect.y as f32);
+    let bottom_right = top_left + vec2(rect.width as f32, rect.height as f32);
+
+    let top_left = (top_left + inset) / texture_size;
+    let bottom_right = (bottom_right - inset) / texture_size;
+
+    vec4(top_left.x, top_left.y, bottom_right.x, bottom_right.y)
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::vec4;
+    use image::{Rgba, RgbaImage};
+    use shin_core::format::sprite_sheet::SpriteRect;
+    use shin_render::{PassKind, RenderRequestBuilder};
+    use winit::dpi::PhysicalSize;
+
+    use super::sprite_texture_region;
+    use crate::render::{sprite::Sprite, test_utils::TestRenderer, top_left_projection_matrix};
+
+    /// The left half of an 8x4 sheet, right next to a green sprite
+    const RED_SPRITE: SpriteRect = SpriteRect {
+        x: 0,
+        y: 0,
+        width: 4,
+        height: 4,
+    };
+
+    /// Draws the red sprite scaled up to 16x16 pixels, returning the most green there is in it
+    fn max_green(half_texel_inset: bool) -> u8 {
+        // a tenth of the virtual canvas
+        let mut renderer = TestRenderer::new(PhysicalSize::new(192, 108));
+        let sheet = renderer.upload(&RgbaImage::from_fn(8, 4, |x, _| {
+            if x < 4 {
+                Rgba([255, 0, 0, 255])
+            } else {
+                Rgba([0, 255, 0, 255])
+            }
+        }));
+        let region = sprite_texture_region(RED_SPRITE, sheet.size_vec(), half_texel_inset);
+        let mut target = renderer.new_render_texture();
+
+        renderer.pre_render(|context| {
+            let mut pass = context.begin_pass(target.as_texture_target(), None, "sprite");
+            Sprite::new(
+                sheet.as_source(),
+                vec4(0.0, 0.0, 160.0, 160.0),
+                top_left_projection_matrix(),
+            )
+            .with_texture_region(region)
+            .render(&mut pass, RenderRequestBuilder::new(), PassKind::Opaque);
+        });
+        let image = renderer.read(&target);
+
+        let sprite_pixels = (0..16).flat_map(|y| (0..16).map(move |x| (x, y)));
+        sprite_pixels
+            .map(|(x, y)| image.get_pixel(x, y).0[1])
+            .max()
+            .unwrap()
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn half_texel_inset_avoids_bleeding() {
+        let bled = max_green(false);
+        let inset = max_green(true);
+
+        // the edge pixels sample between the sprites when scaled up
+        assert!(bled > 0);
+        assert_eq!(inset, 0);
+    }
+}
@@ -1,5 +1,6 @@
 mod accessor;
 mod context;
+mod watch;
 
 use std::{
     fmt::Debug,
@@ -8,7 +9,10 @@ use std::{
     hash::Hash,
     ops::{Deref, DerefMut},
     path::{Path, PathBuf},
+    pin::Pin,
     sync::{Arc, RwLock, Weak},
+    task::Poll,
+    time::SystemTime,
 };
 
 use anyhow::{Context, Result, anyhow, bail};
@@ -18,8 +22,10 @@ use shin_core::{
     format::rom::{RomFileReader, RomReader},
     primitives::stateless_reader::StatelessFile,
 };
-use tracing::debug;
+use shin_tasks::AsyncTask;
+use tracing::{debug, info};
 
+use self::watch::AssetWatcher;
 pub use self::{
     accessor::{AssetDataAccessor, AssetDataCursor},
     context::AssetLoadContext,
@@ -41,10 +47,16 @@ pub trait Asset: Send + Sync + Sized + 'static {
     ) -> impl Future<Output = Result<Self>> + Send;
 }
 
-struct AssetMap<T: Asset>(IndexMap<(String, T::Args), Weak<T>>);
+struct CachedAsset<T> {
+    asset: Weak<T>,
+    /// The generation of the file the asset was loaded from, see [`AssetWatcher`]
+    generation: u64,
+}
+
+struct AssetMap<T: Asset>(IndexMap<(String, T::Args), CachedAsset<T>>);
 
 impl<T: Asset> Deref for AssetMap<T> {
-    type Target = IndexMap<(String, T::Args), Weak<T>>;
+    type Target = IndexMap<(String, T::Args), CachedAsset<T>>;
 
     fn deref(&self) -> &Self::Target {
         &self.0
@@ -56,10 +68,39 @@ impl<T: Asset> DerefMut for AssetMap<T> {
     }
 }
 
+/// A load started with [`AssetServer::load_cancellable`], resolving to the loaded asset
+pub struct AssetLoadHandle<T> {
+    task: AsyncTask<Result<Arc<T>>>,
+}
+
+impl<T> AssetLoadHandle<T> {
+    /// The result of the load, if it has finished (it must not be polled again after that)
+    #[allow(unused)] // TODO: for the loading screens
+    pub fn poll(&mut self) -> Option<Result<Arc<T>>> {
+        self.task.poll_naive()
+    }
+
+    /// Stop the load, dropping whatever was loaded so far
+    ///
+    /// The asset is only cached when the load finishes, so a cancelled load leaves nothing behind. Cancelling a finished load just drops the asset.
+    pub fn cancel(self) {
+        self.task.cancel()
+    }
+}
+
+impl<T> Future for AssetLoadHandle<T> {
+    type Output = Result<Arc<T>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.get_mut().task).poll(cx)
+    }
+}
+
 pub struct AssetServer {
     io: AssetIo,
     context: Arc<AssetLoadContext>,
     loaded_assets: RwLock<anymap3::Map<dyn core::any::Any + Send + Sync>>,
+    watcher: Option<AssetWatcher>,
 }
 
 impl AssetServer {
@@ -68,7 +109,35 @@ impl AssetServer {
             io,
             context: Arc::new(context),
             loaded_assets: RwLock::new(anymap3::Map::new()),
+            watcher: None,
+        }
+    }
+
+    /// Watch the files the assets are loaded from, so that the changed ones are loaded anew, see [`Self::reload_changed`]
+    pub fn enable_hot_reload(&mut self) {
+        self.watcher = Some(AssetWatcher::default());
+    }
+
+    /// Check the asset files for changes, so that the following loads read the changed files again
+    ///
+    /// Returns the paths of the changed assets, for the users to load them again. The assets already handed out are not updated in place.
+    /// Does nothing unless the hot-reloading is enabled, and for the assets in the ROM, which can't change.
+    pub fn reload_changed(&self) -> Vec<String> {
+        let Some(watcher) = &self.watcher else {
+            return Vec::new();
+        };
+
+        let changed = watcher.poll(&self.io);
+        for path in &changed {
+            info!("Asset changed, it will be reloaded: {}", path);
         }
+        changed
+    }
+
+    fn generation(&self, path: &str) -> u64 {
+        self.watcher
+            .as_ref()
+            .map_or(0, |watcher| watcher.generation(path))
     }
 
     pub async fn load<T: Asset, P: AsRef<str>>(&self, path: P) -> Result<Arc<T>>
@@ -88,16 +157,24 @@ impl AssetServer {
         let asset_map_key = (path.to_string(), args.clone());
 
         if let Some(loaded) = self.loaded_assets.read().unwrap().get::<AssetMap<T>>() {
-            if let Some(asset) = loaded.get(&asset_map_key) {
-                if let Some(asset) = asset.upgrade() {
-                    debug!("Loaded asset from cache: {}", path);
-                    return Ok(asset);
+            if let Some(cached) = loaded.get(&asset_map_key) {
+                if let Some(asset) = cached.asset.upgrade() {
+                    if cached.generation == self.generation(path) {
+                        debug!("Loaded asset from cache: {}", path);
+                        return Ok(asset);
+                    }
                 }
             }
         }
 
         debug!("Loading asset: {}", path);
 
+        // taken before reading the file, so a change while the asset is loading is caught by the next poll
+        let generation = match &self.watcher {
+            Some(watcher) => watcher.watch(&self.io, path),
+            None => 0,
+        };
+
         // could not find the asset in the cache, load it
         let data = self
             .io
@@ -112,16 +189,39 @@ impl AssetServer {
             .with_context(|| format!("Loading asset {:?}", path))?;
         let asset = Arc::new(asset);
 
-        self.loaded_assets
-            .write()
-            .unwrap()
+        let mut loaded_assets = self.loaded_assets.write().unwrap();
+        let loaded = loaded_assets
             .entry::<AssetMap<T>>()
-            .or_insert_with(|| AssetMap(IndexMap::default()))
-            .insert(asset_map_key, Arc::downgrade(&asset));
+            .or_insert_with(|| AssetMap(IndexMap::default()));
+
+        // a load that started before a reload might finish after it, don't replace the newer asset with it
+        let has_newer = loaded.get(&asset_map_key).is_some_and(|cached| {
+            cached.generation > generation && cached.asset.strong_count() > 0
+        });
+        if !has_newer {
+            loaded.insert(asset_map_key, CachedAsset {
+                asset: Arc::downgrade(&asset),
+                generation,
+            });
+        }
 
         Ok(asset)
     }
 
+    /// Load an asset on the IO task pool, with a handle allowing to cancel the load (like when the player backs out of a loading screen)
+    #[allow(unused)] // TODO: for the loading screens
+    pub fn load_cancellable<T: Asset>(self: &Arc<Self>, path: impl AsRef<str>) -> AssetLoadHandle<T>
+    where
+        T::Args: Default,
+    {
+        let server = self.clone();
+        let path = path.as_ref().to_string();
+
+        AssetLoadHandle {
+            task: shin_tasks::async_io::spawn(async move { server.load(path).await }),
+        }
+    }
+
     /// Load an asset synchronously. This is useful for assets not requiring much CPU time to load.
     /// Though it might cause lockups if the loading is not blazing fast (tm).
     ///
@@ -181,6 +281,16 @@ impl AssetIo {
             AssetIo::Layered(io) => io.read_file(path),
         }
     }
+
+    /// When the file backing the asset was last modified, `None` if it's not a plain file
+    fn modified_time(&self, path: &str) -> Option<SystemTime> {
+        match self {
+            AssetIo::Dir(io) => io.modified_time(path),
+            // the files in the ROM can't change
+            AssetIo::RomFile(_) => None,
+            AssetIo::Layered(io) => io.modified_time(path),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -193,14 +303,24 @@ impl DirAssetIo {
         Self { root_path }
     }
 
+    fn full_path(&self, path: &str) -> PathBuf {
+        self.root_path.join(path.trim_start_matches('/'))
+    }
+
     fn read_file(&self, path: &str) -> Result<AssetDataAccessor> {
         // 1. check if the file exists
-        let full_path = self.root_path.join(path.trim_start_matches('/'));
+        let full_path = self.full_path(path);
         if !full_path.exists() {
             bail!("Asset {:?} not found", path);
         }
         Ok(AssetDataAccessor::from_file(full_path))
     }
+
+    fn modified_time(&self, path: &str) -> Option<SystemTime> {
+        std::fs::metadata(self.full_path(path))
+            .and_then(|meta| meta.modified())
+            .ok()
+    }
 }
 
 pub struct RomAssetIo {
@@ -279,4 +399,241 @@ impl LayeredAssetIo {
             errors
         ))
     }
+
+    /// The modification time of the file in the layer the asset is read from
+    fn modified_time(&self, path: &str) -> Option<SystemTime> {
+        self.io
+            .iter()
+            .find(|io| io.read_file(path).is_ok())?
+            .modified_time(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs::File,
+        future::poll_fn,
+        path::{Path, PathBuf},
+        sync::{
+            Arc,
+            atomic::{AtomicBool, Ordering},
+        },
+        task::Poll,
+        time::{Duration, Instant, SystemTime},
+    };
+
+    use anyhow::Result;
+
+    use super::{Asset, AssetDataAccessor, AssetIo, AssetLoadContext, AssetMap, AssetServer};
+    use crate::{
+        asset::system::cache::AssetCache,
+        render::test_utils::{create_task_pools, request_device},
+    };
+
+    /// Creates an asset server reading a directory with a single `/asset.bin` file, returning the directory for cleanup
+    fn asset_server(name: &str, hot_reload: bool) -> (Arc<AssetServer>, PathBuf) {
+        let (wgpu_device, wgpu_queue) = request_device();
+        create_task_pools();
+
+        let dir = std::env::temp_dir().join(format!("shin-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("asset.bin"), [1, 2, 3]).unwrap();
+
+        let mut server = AssetServer::new(AssetIo::new_dir(&dir).unwrap(), AssetLoadContext {
+            wgpu_device,
+            wgpu_queue,
+            bustup_cache: AssetCache::new(),
+        });
+        if hot_reload {
+            server.enable_hot_reload();
+        }
+        (Arc::new(server), dir)
+    }
+
+    fn cached<T: Asset<Args = ()>>(server: &AssetServer, path: &str) -> Option<Arc<T>> {
+        let loaded_assets = server.loaded_assets.read().unwrap();
+        loaded_assets
+            .get::<AssetMap<T>>()?
+            .get(&(path.to_string(), ()))?
+            .asset
+            .upgrade()
+    }
+
+    /// Replace the contents of `/asset.bin`, making sure the change is seen even within the same second
+    fn change_asset(dir: &Path, contents: &[u8]) {
+        let path = dir.join("asset.bin");
+        std::fs::write(&path, contents).unwrap();
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(10))
+            .unwrap();
+    }
+
+    fn wait_for(condition: impl Fn() -> bool) {
+        let start = Instant::now();
+        while !condition() {
+            assert!(start.elapsed() < Duration::from_secs(5), "Timed out");
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    static SLOW_LOAD_STARTED: AtomicBool = AtomicBool::new(false);
+    static SLOW_LOAD_DROPPED: AtomicBool = AtomicBool::new(false);
+
+    /// Sets the flag when the load future is dropped
+    struct DropFlag(&'static AtomicBool);
+
+    impl Drop for DropFlag {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    /// An asset that never finishes loading
+    struct SlowAsset;
+
+    impl Asset for SlowAsset {
+        type Args = ();
+
+        async fn load(
+            _context: &Arc<AssetLoadContext>,
+            _args: (),
+            _name: &str,
+            _data: AssetDataAccessor,
+        ) -> Result<Self> {
+            let _flag = DropFlag(&SLOW_LOAD_DROPPED);
+            SLOW_LOAD_STARTED.store(true, Ordering::SeqCst);
+
+            std::future::pending::<()>().await;
+            unreachable!()
+        }
+    }
+
+    struct QuickAsset(Vec<u8>);
+
+    impl Asset for QuickAsset {
+        type Args = ();
+
+        async fn load(
+            _context: &Arc<AssetLoadContext>,
+            _args: (),
+            _name: &str,
+            data: AssetDataAccessor,
+        ) -> Result<Self> {
+            Ok(Self(data.read_all().await))
+        }
+    }
+
+    static STALE_LOAD_STARTED: AtomicBool = AtomicBool::new(false);
+    static STALE_LOAD_RELEASED: AtomicBool = AtomicBool::new(false);
+
+    /// An asset whose load of the original `[1, 2, 3]` contents waits for [`STALE_LOAD_RELEASED`], to finish after a reload
+    struct RacingAsset(Vec<u8>);
+
+    impl Asset for RacingAsset {
+        type Args = ();
+
+        async fn load(
+            _context: &Arc<AssetLoadContext>,
+            _args: (),
+            _name: &str,
+            data: AssetDataAccessor,
+        ) -> Result<Self> {
+            let data = data.read_all().await;
+            if data == [1, 2, 3] {
+                STALE_LOAD_STARTED.store(true, Ordering::SeqCst);
+                poll_fn(|cx| {
+                    if STALE_LOAD_RELEASED.load(Ordering::SeqCst) {
+                        Poll::Ready(())
+                    } else {
+                        cx.waker().wake_by_ref();
+                        Poll::Pending
+                    }
+                })
+                .await;
+            }
+            Ok(Self(data))
+        }
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn changed_assets_are_loaded_anew() {
+        let (server, dir) = asset_server("hot-reload", true);
+
+        let old = server.load_sync::<QuickAsset>("/asset.bin").unwrap();
+        assert!(server.reload_changed().is_empty());
+        // unchanged, so it's taken from the cache
+        let cached = server.load_sync::<QuickAsset>("/asset.bin").unwrap();
+        assert!(Arc::ptr_eq(&old, &cached));
+
+        change_asset(&dir, &[4, 5]);
+        assert_eq!(server.reload_changed(), ["/asset.bin"]);
+
+        // the asset handed out before is left as it was
+        let new = server.load_sync::<QuickAsset>("/asset.bin").unwrap();
+        assert_eq!(new.0, [4, 5]);
+        assert_eq!(old.0, [1, 2, 3]);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn stale_load_does_not_replace_the_reloaded_asset() {
+        let (server, dir) = asset_server("hot-reload-race", true);
+
+        // started before the change, finishing after the asset was loaded again
+        let stale_load = server.load_cancellable::<RacingAsset>("/asset.bin");
+        wait_for(|| STALE_LOAD_STARTED.load(Ordering::SeqCst));
+
+        change_asset(&dir, &[4, 5]);
+        assert_eq!(server.reload_changed(), ["/asset.bin"]);
+        let new = server.load_sync::<RacingAsset>("/asset.bin").unwrap();
+        assert_eq!(new.0, [4, 5]);
+
+        STALE_LOAD_RELEASED.store(true, Ordering::SeqCst);
+        let stale = shin_tasks::block_on(stale_load).unwrap();
+        assert_eq!(stale.0, [1, 2, 3]);
+
+        // the cache still has the newer asset
+        let cached = server.load_sync::<RacingAsset>("/asset.bin").unwrap();
+        assert!(Arc::ptr_eq(&new, &cached));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn cancelled_loads_are_not_cached() {
+        let (server, dir) = asset_server("cancel-load", false);
+
+        let handle = server.load_cancellable::<SlowAsset>("/asset.bin");
+        wait_for(|| SLOW_LOAD_STARTED.load(Ordering::SeqCst));
+
+        handle.cancel();
+        // the load future is dropped on the IO task pool
+        wait_for(|| SLOW_LOAD_DROPPED.load(Ordering::SeqCst));
+        assert!(cached::<SlowAsset>(&server, "/asset.bin").is_none());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn cancelling_a_finished_load_is_harmless() {
+        let (server, dir) = asset_server("cancel-finished-load", false);
+
+        let handle = server.load_cancellable::<QuickAsset>("/asset.bin");
+        wait_for(|| cached::<QuickAsset>(&server, "/asset.bin").is_some());
+
+        handle.cancel();
+        let asset = server.load_sync::<QuickAsset>("/asset.bin").unwrap();
+        assert_eq!(asset.0, [1, 2, 3]);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
 }
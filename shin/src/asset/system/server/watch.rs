@@ -0,0 +1,117 @@
+use std::{collections::HashMap, sync::Mutex, time::SystemTime};
+
+use super::AssetIo;
+
+struct WatchedFile {
+    modified: SystemTime,
+    generation: u64,
+}
+
+/// Polls the files the assets were loaded from for changes, for hot-reloading
+///
+/// Every change bumps the generation of the file. The cached assets remember the generation they were loaded from, so the stale ones are loaded anew.
+#[derive(Default)]
+pub struct AssetWatcher {
+    files: Mutex<HashMap<String, WatchedFile>>,
+}
+
+impl AssetWatcher {
+    /// Start watching the file backing the asset at `path`, returning its current generation
+    ///
+    /// The assets that are not backed by a plain file (like the ones in the ROM) are not watched, as they can't change.
+    pub fn watch(&self, io: &AssetIo, path: &str) -> u64 {
+        let mut files = self.files.lock().unwrap();
+        if let Some(file) = files.get(path) {
+            return file.generation;
+        }
+
+        if let Some(modified) = io.modified_time(path) {
+            files.insert(
+                path.to_string(),
+                WatchedFile {
+                    modified,
+                    generation: 0,
+                },
+            );
+        }
+        0
+    }
+
+    pub fn generation(&self, path: &str) -> u64 {
+        self.files
+            .lock()
+            .unwrap()
+            .get(path)
+            .map_or(0, |file| file.generation)
+    }
+
+    /// Check the watched files for changes, returning the paths of the changed ones
+    pub fn poll(&self, io: &AssetIo) -> Vec<String> {
+        let mut files = self.files.lock().unwrap();
+
+        let mut changed = files
+            .iter_mut()
+            .filter_map(|(path, file)| {
+                // a deleted file keeps the last loaded version
+                let modified = io.modified_time(path)?;
+                if modified == file.modified {
+                    return None;
+                }
+
+                file.modified = modified;
+                file.generation += 1;
+                Some(path.clone())
+            })
+            .collect::<Vec<_>>();
+        changed.sort();
+
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs::File,
+        time::{Duration, SystemTime},
+    };
+
+    use super::AssetWatcher;
+    use crate::asset::system::server::AssetIo;
+
+    fn read(io: &AssetIo, path: &str) -> String {
+        String::from_utf8(io.read_file(path).unwrap().read_all_sync()).unwrap()
+    }
+
+    #[test]
+    fn changed_files_are_reloaded() {
+        let dir = std::env::temp_dir().join(format!("shin-asset-watcher-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file_path = dir.join("script.txt");
+        std::fs::write(&file_path, "old").unwrap();
+
+        let io = AssetIo::new_dir(&dir).unwrap();
+        let watcher = AssetWatcher::default();
+
+        let generation = watcher.watch(&io, "/script.txt");
+        assert_eq!(read(&io, "/script.txt"), "old");
+        assert!(watcher.poll(&io).is_empty());
+
+        std::fs::write(&file_path, "new").unwrap();
+        // the file system might not tell apart the writes made within the same second
+        File::options()
+            .write(true)
+            .open(&file_path)
+            .unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(10))
+            .unwrap();
+
+        assert_eq!(watcher.poll(&io), ["/script.txt"]);
+        // the asset loaded before is stale now, so the next load reads the file again
+        assert_ne!(watcher.generation("/script.txt"), generation);
+        assert_eq!(read(&io, "/script.txt"), "new");
+        assert!(watcher.poll(&io).is_empty());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
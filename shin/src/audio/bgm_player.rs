@@ -17,13 +17,8 @@ pub struct BgmPlayer {
 
 impl BgmPlayer {
     pub fn new(audio_manager: Arc<AudioManager>) -> Self {
-        let mut manager = audio_manager.kira_manager().lock();
-
-        let bgm_track = manager
-            .add_sub_track(TrackBuilder::new().routes(TrackRoutes::parent(TrackId::Main)))
-            .expect("Failed to create bgm track");
-
-        drop(manager);
+        let bgm_track = audio_manager
+            .add_sub_track(TrackBuilder::new().routes(TrackRoutes::parent(TrackId::Main)));
 
         Self {
             audio_manager,
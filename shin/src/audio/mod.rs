@@ -1,7 +1,9 @@
 mod bgm_player;
 mod se_player;
+mod voice_caption;
 mod voice_player;
 
 pub use bgm_player::BgmPlayer;
 pub use se_player::{SePlayer, SE_SLOT_COUNT};
-pub use voice_player::{VoicePlayFlags, VoicePlayer};
+pub use voice_caption::VoiceCaptionTable;
+pub use voice_player::{Lipsync, VoicePlayFlags, VoicePlayer, voice_asset_path};
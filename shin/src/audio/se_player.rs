@@ -18,16 +18,11 @@ pub struct SePlayer {
 
 impl SePlayer {
     pub fn new(audio_manager: Arc<AudioManager>) -> Self {
-        let mut manager = audio_manager.kira_manager().lock();
-
         let se_tracks = [(); SE_SLOT_COUNT].map(|_| {
-            manager
+            audio_manager
                 .add_sub_track(TrackBuilder::new().routes(TrackRoutes::parent(TrackId::Main)))
-                .expect("Failed to create se track")
         });
 
-        drop(manager);
-
         Self {
             audio_manager,
             se_tracks,
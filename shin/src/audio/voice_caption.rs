@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+
+use shin_core::vm::command::types::AudioWaitStatus;
+use tracing::debug;
+
+/// Captions to show while a voice is playing, keyed by the voice name
+#[derive(Debug, Default, Clone)]
+pub struct VoiceCaptionTable {
+    captions: HashMap<String, String>,
+}
+
+impl VoiceCaptionTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses a tab-separated caption table: one `<voice name>\t<caption>` pair per line.
+    ///
+    /// Voice names are the same as in VOICEPLAY and are case-insensitive.
+    /// Empty lines and lines starting with `#` are ignored.
+    pub fn parse(text: &str) -> Self {
+        let captions = text
+            .lines()
+            .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let (voice_name, caption) = line.split_once('\t')?;
+                Some((voice_name.trim().to_ascii_lowercase(), caption.trim().to_string()))
+            })
+            .collect();
+
+        Self { captions }
+    }
+
+    pub fn get(&self, voice_name: &str) -> Option<&str> {
+        self.captions
+            .get(&voice_name.to_ascii_lowercase())
+            .map(String::as_str)
+    }
+}
+
+/// The caption of the voice that is currently playing
+///
+/// There is only one voice channel, so a new voice always replaces the caption of the previous one.
+#[derive(Debug, Default)]
+pub struct VoiceCaption {
+    current: Option<String>,
+}
+
+impl VoiceCaption {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Called when a new voice starts playing, replacing the caption of the previous voice (even if the new one doesn't have a caption)
+    pub fn on_voice_start(&mut self, caption: Option<String>) {
+        if let Some(caption) = &caption {
+            debug!("Showing voice caption: {:?}", caption);
+        }
+        self.current = caption;
+    }
+
+    /// Clears the caption when the voice is stopped
+    pub fn on_voice_stop(&mut self) {
+        self.current = None;
+    }
+
+    /// Clears the caption once the voice has finished playing
+    pub fn update(&mut self, voice_status: AudioWaitStatus) {
+        if !voice_status.contains(AudioWaitStatus::PLAYING) {
+            self.current = None;
+        }
+    }
+
+    pub fn text(&self) -> Option<&str> {
+        self.current.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shin_core::vm::command::types::AudioWaitStatus;
+
+    use super::{VoiceCaption, VoiceCaptionTable};
+
+    const TABLE: &str = "# voice\tcaption\n\
+                         00/awase0001\tWhat a mess.\n\
+                         \n\
+                         10/00100001\tOver here!\n";
+
+    #[test]
+    fn table() {
+        let table = VoiceCaptionTable::parse(TABLE);
+
+        assert_eq!(table.get("00/awase0001"), Some("What a mess."));
+        assert_eq!(table.get("00/AWASE0001"), Some("What a mess."));
+        assert_eq!(table.get("10/00100001"), Some("Over here!"));
+        assert_eq!(table.get("voice"), None);
+        assert_eq!(table.get("10/00100002"), None);
+    }
+
+    #[test]
+    fn caption_shown_while_playing() {
+        let table = VoiceCaptionTable::parse(TABLE);
+        let mut caption = VoiceCaption::new();
+
+        caption.on_voice_start(table.get("00/awase0001").map(str::to_string));
+        caption.update(AudioWaitStatus::PLAYING | AudioWaitStatus::FADING);
+        assert_eq!(caption.text(), Some("What a mess."));
+
+        caption.update(AudioWaitStatus::PLAYING);
+        assert_eq!(caption.text(), Some("What a mess."));
+
+        // the voice has finished
+        caption.update(AudioWaitStatus::empty());
+        assert_eq!(caption.text(), None);
+    }
+
+    #[test]
+    fn overlapping_voices_replace_caption() {
+        let table = VoiceCaptionTable::parse(TABLE);
+        let mut caption = VoiceCaption::new();
+
+        caption.on_voice_start(table.get("00/awase0001").map(str::to_string));
+        caption.update(AudioWaitStatus::PLAYING);
+        caption.on_voice_start(table.get("10/00100001").map(str::to_string));
+        caption.update(AudioWaitStatus::PLAYING);
+        assert_eq!(caption.text(), Some("Over here!"));
+
+        // a voice without a caption hides the previous one
+        caption.on_voice_start(None);
+        caption.update(AudioWaitStatus::PLAYING);
+        assert_eq!(caption.text(), None);
+    }
+}
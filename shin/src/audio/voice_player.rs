@@ -1,10 +1,22 @@
 use std::sync::Arc;
 
+use anyhow::{Context as _, Result};
 use bitflags::bitflags;
-use shin_audio::AudioManager;
+use kira::track::{TrackBuilder, TrackHandle, TrackId, TrackRoutes};
+use shin_audio::{AudioData, AudioFile, AudioHandle, AudioManager, AudioSettings};
 use shin_core::{
-    format::scenario::Scenario,
-    vm::command::types::{AudioWaitStatus, Volume},
+    format::{
+        audio::{AudioDecoder, AudioFrameSource},
+        scenario::Scenario,
+    },
+    time::Tween,
+    vm::command::types::{AudioWaitStatus, Pan, Volume},
+};
+use tracing::warn;
+
+use crate::{
+    asset::bustup::CharacterId,
+    audio::voice_caption::{VoiceCaption, VoiceCaptionTable},
 };
 
 bitflags! {
@@ -15,13 +27,66 @@ bitflags! {
     }
 }
 
+/// The asset path of the voice with the given name (as passed to VOICEPLAY, like `00/awase0001`)
+pub fn voice_asset_path(voice_name: &str) -> String {
+    format!("/voice/{}.nxa", voice_name.to_ascii_lowercase())
+}
+
+/// The stop command can only fail when the sound's command queue is full, in which case the voice just plays to the end
+fn stop_voice(handle: &mut AudioHandle) {
+    if let Err(e) = handle.stop(Tween::MS_15) {
+        warn!("Failed to stop the voice: {:?}", e);
+    }
+}
+
+/// Who is speaking in the current voice and how loud, for the bustups to move their lips
+#[derive(Debug, Clone, Default)]
+pub struct Lipsync {
+    characters: Vec<CharacterId>,
+    amplitude: f32,
+}
+
+impl Lipsync {
+    /// The amplitude of the voice if the `character` is speaking in it, 0 otherwise
+    pub fn amplitude(&self, character: CharacterId) -> f32 {
+        if self.characters.contains(&character) {
+            self.amplitude
+        } else {
+            0.0
+        }
+    }
+}
+
 pub struct VoicePlayer {
     audio_manager: Arc<AudioManager>,
+    voice_track: TrackHandle,
+    current_voice: Option<AudioHandle>,
+    /// The characters whose lips move with the current voice
+    lipsync_characters: Vec<CharacterId>,
+    /// A voice replayed from the backlog, kept apart from the voice of the current message
+    replayed_voice: Option<AudioHandle>,
+    caption_table: Arc<VoiceCaptionTable>,
+    caption: VoiceCaption,
 }
 
 impl VoicePlayer {
     pub fn new(audio_manager: Arc<AudioManager>) -> Self {
-        Self { audio_manager }
+        let voice_track = audio_manager
+            .add_sub_track(TrackBuilder::new().routes(TrackRoutes::parent(TrackId::Main)));
+
+        Self {
+            audio_manager,
+            voice_track,
+            current_voice: None,
+            lipsync_characters: Vec::new(),
+            replayed_voice: None,
+            caption_table: Arc::new(VoiceCaptionTable::new()),
+            caption: VoiceCaption::new(),
+        }
+    }
+
+    pub fn set_caption_table(&mut self, caption_table: Arc<VoiceCaptionTable>) {
+        self.caption_table = caption_table;
     }
 
     pub fn play(
@@ -36,9 +101,223 @@ impl VoicePlayer {
         false
     }
 
-    pub fn stop(&mut self) {}
+    /// Play a voice not attached to any message (as done by VOICEPLAY), moving the lips of the `lipsync_characters`
+    ///
+    /// The voice replaces the one currently playing, along with its caption. If the voice can't be decoded, the current one keeps playing.
+    pub fn play_standalone(
+        &mut self,
+        voice_name: &str,
+        voice: Arc<AudioFile>,
+        volume: Volume,
+        lipsync_characters: Vec<CharacterId>,
+    ) -> Result<()> {
+        let source = AudioDecoder::new(voice).context("Creating the audio decoder")?;
+        self.play_source(voice_name, source, volume, lipsync_characters);
+        Ok(())
+    }
+
+    fn play_source<S: AudioFrameSource + Send + 'static>(
+        &mut self,
+        voice_name: &str,
+        source: S,
+        volume: Volume,
+        lipsync_characters: Vec<CharacterId>,
+    ) {
+        let handle = self.audio_manager.play(AudioData {
+            source,
+            settings: AudioSettings {
+                track: self.voice_track.id(),
+                fade_in: Tween::IMMEDIATE,
+                loop_start: None,
+                volume,
+                pan: Pan::default(),
+            },
+        });
+
+        if let Some(mut old_handle) = self.current_voice.take() {
+            stop_voice(&mut old_handle);
+        }
+
+        self.current_voice = Some(handle);
+        self.lipsync_characters = lipsync_characters;
+        self.caption
+            .on_voice_start(self.caption_table.get(voice_name).map(str::to_string));
+    }
+
+    /// Replay a voice from the backlog
+    ///
+    /// Unlike [`Self::play_standalone`], the voice of the current message keeps playing (along with its caption and wait status), only the previous replay is stopped.
+    pub fn replay(&mut self, voice: Arc<AudioFile>, volume: Volume) -> Result<()> {
+        let source = AudioDecoder::new(voice).context("Creating the audio decoder")?;
+        self.replay_source(source, volume);
+        Ok(())
+    }
+
+    fn replay_source<S: AudioFrameSource + Send + 'static>(&mut self, source: S, volume: Volume) {
+        let handle = self.audio_manager.play(AudioData {
+            source,
+            settings: AudioSettings {
+                track: self.voice_track.id(),
+                fade_in: Tween::IMMEDIATE,
+                loop_start: None,
+                volume,
+                pan: Pan::default(),
+            },
+        });
+
+        if let Some(mut old_handle) = self.replayed_voice.replace(handle) {
+            stop_voice(&mut old_handle);
+        }
+    }
+
+    #[allow(unused)] // TODO: for the backlog UI, once there is one
+    pub fn is_replaying(&self) -> bool {
+        self.replayed_voice
+            .as_ref()
+            .is_some_and(|handle| handle.get_wait_status().contains(AudioWaitStatus::PLAYING))
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(mut handle) = self.current_voice.take() {
+            stop_voice(&mut handle);
+        }
+        self.lipsync_characters.clear();
+        self.caption.on_voice_stop();
+    }
+
+    /// Who is speaking in the current voice and how loud, the replayed voices don't move the lips
+    pub fn lipsync(&self) -> Lipsync {
+        Lipsync {
+            characters: self.lipsync_characters.clone(),
+            amplitude: self
+                .current_voice
+                .as_ref()
+                .map_or(0.0, AudioHandle::get_amplitude),
+        }
+    }
+
+    pub fn update(&mut self) {
+        let status = self.get_wait_status();
+        self.caption.update(status);
+    }
+
+    /// The caption of the currently playing voice, if it has one
+    pub fn caption(&self) -> Option<&str> {
+        self.caption.text()
+    }
 
     pub fn get_wait_status(&self) -> AudioWaitStatus {
-        AudioWaitStatus::empty()
+        if let Some(handle) = self.current_voice.as_ref() {
+            handle.get_wait_status()
+        } else {
+            AudioWaitStatus::empty()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shin_audio::TestClock;
+    use shin_core::{
+        format::audio::{AudioBuffer, AudioFrameSource},
+        vm::command::types::{AudioWaitStatus, Volume},
+    };
+
+    use super::VoicePlayer;
+    use crate::asset::bustup::CharacterId;
+
+    const SAMPLE_RATE: u32 = 44100;
+
+    /// A second of a constant signal at `level`, standing in for a decoded voice
+    struct TestVoice {
+        level: f32,
+        position: u32,
+    }
+
+    impl AudioFrameSource for TestVoice {
+        fn max_frame_size(&self) -> usize {
+            1024
+        }
+
+        fn sample_rate(&self) -> u32 {
+            SAMPLE_RATE
+        }
+
+        fn pre_skip(&self) -> u32 {
+            0
+        }
+
+        fn pre_roll(&self) -> u32 {
+            0
+        }
+
+        fn read_frame(&mut self, destination: &mut AudioBuffer) -> bool {
+            destination.clear();
+            let count = 1024.min(SAMPLE_RATE - self.position);
+            if count == 0 {
+                return false;
+            }
+            destination.extend((0..count).map(|_| (self.level, self.level)));
+            self.position += count;
+            true
+        }
+
+        fn samples_seek(&mut self, sample_position: u32) -> anyhow::Result<u32> {
+            self.position = sample_position / 1024 * 1024;
+            Ok(sample_position - self.position)
+        }
+
+        fn current_sample_position(&self) -> u32 {
+            self.position
+        }
+    }
+
+    #[test]
+    fn replay_keeps_the_current_voice() {
+        let mut clock = TestClock::new(SAMPLE_RATE);
+        let mut player = VoicePlayer::new(clock.audio_manager().clone());
+
+        player.replay_source(
+            TestVoice {
+                level: 0.0,
+                position: 0,
+            },
+            Volume::default(),
+        );
+        clock.run_frames(1).unwrap();
+
+        assert!(player.is_replaying());
+        // the voice of the current message (and the wait for it) is not affected
+        assert_eq!(player.get_wait_status(), AudioWaitStatus::empty());
+        assert_eq!(player.caption(), None);
+
+        player.stop();
+        assert!(player.is_replaying());
+    }
+
+    #[test]
+    fn lipsync_follows_the_voice() {
+        let mut clock = TestClock::new(SAMPLE_RATE);
+        let mut player = VoicePlayer::new(clock.audio_manager().clone());
+        let speaking = CharacterId::new(3);
+        let listening = CharacterId::new(4);
+
+        player.play_source(
+            "00/test0001",
+            TestVoice {
+                level: 0.5,
+                position: 0,
+            },
+            Volume::default(),
+            vec![speaking],
+        );
+        clock.run_frames(10).unwrap();
+
+        let lipsync = player.lipsync();
+        assert!((lipsync.amplitude(speaking) - 0.5).abs() < 0.01);
+        assert_eq!(lipsync.amplitude(listening), 0.0);
+
+        player.stop();
+        assert_eq!(player.lipsync().amplitude(speaking), 0.0);
     }
 }
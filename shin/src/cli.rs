@@ -1,7 +1,16 @@
-use std::path::PathBuf;
+use std::{path::PathBuf, time::Duration};
 
 use clap::Parser;
 use clap_num::maybe_hex;
+use glam::Vec3;
+
+use crate::{
+    adv::backlog::TranscriptFormat,
+    render::{
+        anti_aliasing::AntiAliasingMode,
+        post_process::{ColorFilter, parse_hex_color},
+    },
+};
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
@@ -13,6 +22,11 @@ pub struct Cli {
     /// Consult the README for more information.
     #[clap(short, long)]
     pub assets_dir: Option<PathBuf>,
+    /// Watch the asset directories for changes, so that the edited assets are used the next time they are loaded
+    ///
+    /// The edited scenario is picked up right away, continuing from the current command. Has no effect on the assets in `data.rom`.
+    #[clap(long)]
+    pub hot_reload: bool,
     /// Automatically fast-forward the scenario to the specified address (useful for debugging)
     #[clap(long, value_parser=maybe_hex::<u32>)]
     pub fast_forward_to: Option<u32>,
@@ -22,4 +36,144 @@ pub struct Cli {
     /// But in lieu of proper scene loading this helps a lot with testing later parts of the episodes.
     #[clap(long, value_parser=maybe_hex::<u32>)]
     pub unsafe_entry_point: Option<u32>,
+    /// Jump to the attract sequence at the specified address after this many seconds without input
+    ///
+    /// Meant for the kiosk builds, the choice prompts never time out.
+    #[clap(long, requires = "attract_entry_point", value_parser = parse_seconds)]
+    pub idle_timeout: Option<Duration>,
+    /// Address of the attract sequence started by `--idle-timeout`
+    #[clap(long, value_parser=maybe_hex::<u32>)]
+    pub attract_entry_point: Option<u32>,
+    /// Where to write the message transcript when exporting it (F6)
+    ///
+    /// Defaults to `transcript.md` or `transcript.txt` in the current directory, depending on the format.
+    #[clap(long)]
+    pub transcript_path: Option<PathBuf>,
+    /// Format of the exported message transcript
+    #[clap(long, value_enum, default_value = "markdown")]
+    pub transcript_format: TranscriptFormat,
+    /// Show captions for the voices played, read from a tab-separated file
+    ///
+    /// Each line contains a voice name (as passed to VOICEPLAY, like `00/awase0001`) and the caption, separated by a tab.
+    #[clap(long)]
+    pub voice_captions: Option<PathBuf>,
+    /// Adjust the gamma of the image to the display
+    ///
+    /// Values above 1 brighten the dark parts of the image, values below 1 darken them.
+    #[clap(long, default_value_t = 1.0)]
+    pub gamma: f32,
+    /// Multiply the colors of the image by this value
+    #[clap(long, default_value_t = 1.0)]
+    pub brightness: f32,
+    /// Adjust the colors for a color vision deficiency
+    ///
+    /// The `simulate-` variants show how the game looks with the deficiency instead.
+    #[clap(long, value_enum, default_value = "off")]
+    pub color_filter: ColorFilter,
+    /// Fade the edges of the image to the vignette color, from 0 (no vignette) to 1
+    #[clap(long, default_value_t = 0.0)]
+    pub vignette: f32,
+    /// Distance from the center of the image at which the vignette starts, 1 being the distance to the corners
+    #[clap(long, default_value_t = 0.5)]
+    pub vignette_radius: f32,
+    /// Distance over which the vignette ramps up to the full strength
+    #[clap(long, default_value_t = 0.5)]
+    pub vignette_softness: f32,
+    /// Color of the vignette, in the RRGGBB format
+    #[clap(long, value_parser = parse_hex_color, default_value = "000000")]
+    pub vignette_color: Vec3,
+    /// Tone down the layer effects involving motion (waves, ripples and afterimages)
+    #[clap(long)]
+    pub reduced_motion: bool,
+    /// Fade the translucent layer groups (like the planes) as a whole, so that the layers in them don't show through each other
+    ///
+    /// The original game composites them as if they were opaque.
+    #[clap(long)]
+    pub group_opacity: bool,
+    /// Lower the resolution the scene is rendered at when the frames take longer than this many milliseconds
+    ///
+    /// The resolution is raised back when the frames get faster than that.
+    #[clap(long, value_parser = parse_milliseconds)]
+    pub dynamic_resolution: Option<Duration>,
+    /// The lowest fraction of the full resolution the dynamic resolution goes down to
+    #[clap(long, default_value_t = 0.5)]
+    pub dynamic_resolution_min_scale: f32,
+    /// Keep rendering the messages at the full resolution when the dynamic resolution lowers the scene resolution
+    #[clap(long)]
+    pub dynamic_resolution_native_ui: bool,
+    /// Smooth the jagged edges of the scene
+    ///
+    /// The messages are drawn over the anti-aliased scene, so they stay crisp.
+    #[clap(long, value_enum, default_value = "off")]
+    pub anti_aliasing: AntiAliasingMode,
+}
+
+fn positive_duration(seconds: f32) -> Option<Duration> {
+    Duration::try_from_secs_f32(seconds)
+        .ok()
+        .filter(|duration| !duration.is_zero())
+}
+
+/// A positive duration given in seconds, like `1.5`
+fn parse_seconds(value: &str) -> Result<Duration, String> {
+    let seconds = value.parse::<f32>().map_err(|e| e.to_string())?;
+    positive_duration(seconds)
+        .ok_or_else(|| format!("expected a positive number of seconds, got {}", value))
+}
+
+/// A positive duration given in milliseconds, like `16.6`
+fn parse_milliseconds(value: &str) -> Result<Duration, String> {
+    let milliseconds = value.parse::<f32>().map_err(|e| e.to_string())?;
+    positive_duration(milliseconds / 1000.0)
+        .ok_or_else(|| format!("expected a positive number of milliseconds, got {}", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use clap::Parser as _;
+
+    use super::Cli;
+
+    #[test]
+    fn idle_timeout() {
+        let cli = Cli::try_parse_from([
+            "shin",
+            "--idle-timeout",
+            "90",
+            "--attract-entry-point",
+            "0x1234",
+        ])
+        .unwrap();
+        assert_eq!(cli.idle_timeout, Some(Duration::from_secs(90)));
+        assert_eq!(cli.attract_entry_point, Some(0x1234));
+
+        // there is nothing to jump to without the entry point
+        assert!(Cli::try_parse_from(["shin", "--idle-timeout", "90"]).is_err());
+    }
+
+    #[test]
+    fn durations_must_be_positive() {
+        // with an `=`, so that the negative values are not taken for flags
+        let idle_timeout = |value: &str| {
+            let argument = format!("--idle-timeout={}", value);
+            Cli::try_parse_from(["shin", argument.as_str(), "--attract-entry-point", "0"])
+        };
+        assert!(idle_timeout("-1").is_err());
+        assert!(idle_timeout("0").is_err());
+        assert!(idle_timeout("NaN").is_err());
+        assert!(idle_timeout("1e30").is_err());
+
+        let dynamic_resolution = |value: &str| {
+            let argument = format!("--dynamic-resolution={}", value);
+            Cli::try_parse_from(["shin", argument.as_str()])
+        };
+        let target = dynamic_resolution("12.5")
+            .unwrap()
+            .dynamic_resolution
+            .unwrap();
+        assert!(target.abs_diff(Duration::from_micros(12500)) < Duration::from_micros(1));
+        assert!(dynamic_resolution("-16").is_err());
+    }
 }
@@ -0,0 +1,162 @@
+use shin_core::{
+    time::{Easing, Ticks, Tween},
+    vm::command::types::LayerProperty,
+};
+
+use crate::layer::LayerProperties;
+
+/// Sequences property animations of a layer from code, see [`LayerProperties::animate`].
+///
+/// The animations are enqueued to the property tweeners right away, so the builder doesn't need to be kept around.
+pub struct LayerAnimation<'a> {
+    properties: &'a mut LayerProperties,
+    /// Time from now at which the last animated step ends
+    end: Ticks,
+}
+
+impl LayerAnimation<'_> {
+    fn enqueue(
+        mut self,
+        start: Ticks,
+        property: LayerProperty,
+        to: f32,
+        duration: Ticks,
+        easing: Easing,
+    ) -> Self {
+        let tweener = self.properties.property_tweener_mut(property);
+
+        // the property might still be busy with its own animations, in which case we have to wait for them
+        let busy_for = tweener.remaining_duration();
+        if busy_for < start {
+            // hold the value until the previous step is done
+            tweener.enqueue(tweener.final_value(), Tween {
+                duration: start - busy_for,
+                easing: Easing::Linear,
+            });
+        }
+        tweener.enqueue(to, Tween { duration, easing });
+
+        self.end = start.max(busy_for) + duration;
+        self
+    }
+
+    /// Animate another property after all the previous steps have finished.
+    // TODO: use it for the code-driven scenes (title screen and menus)
+    #[allow(unused)]
+    pub fn then(self, property: LayerProperty, to: f32, duration: Ticks, easing: Easing) -> Self {
+        let start = self.end;
+        self.enqueue(start, property, to, duration, easing)
+    }
+
+    /// Time from now until the whole sequence finishes playing.
+    #[allow(unused)]
+    pub fn duration(&self) -> Ticks {
+        self.end
+    }
+}
+
+impl LayerProperties {
+    /// Starts building a sequence of property animations, starting with animating `property` to `to`.
+    ///
+    /// If the property is already being animated, the new animation plays after the ones already queued.
+    /// Use [`LayerProperties::cancel_animations`] to stop the sequence.
+    ///
+    /// ```ignore
+    /// props
+    ///     .animate(MulColorAlpha, 1000.0, Ticks::from_seconds(0.5), Easing::SineOut)
+    ///     .then(TranslateY, 0.0, Ticks::from_seconds(0.3), Easing::Linear);
+    /// ```
+    pub fn animate(
+        &mut self,
+        property: LayerProperty,
+        to: f32,
+        duration: Ticks,
+        easing: Easing,
+    ) -> LayerAnimation<'_> {
+        LayerAnimation {
+            properties: self,
+            end: Ticks::ZERO,
+        }
+        .enqueue(Ticks::ZERO, property, to, duration, easing)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shin_core::{
+        time::{Easing, Ticks},
+        vm::command::types::LayerProperty::{MulColorAlpha, TranslateX},
+    };
+
+    use crate::layer::LayerProperties;
+
+    fn ticks(value: f32) -> Ticks {
+        Ticks::from_f32(value)
+    }
+
+    #[test]
+    fn sequenced_animations_run_in_order() {
+        let mut props = LayerProperties::new();
+        props
+            .property_tweener_mut(MulColorAlpha)
+            .fast_forward_to(0.0);
+
+        let animation = props
+            .animate(MulColorAlpha, 1000.0, ticks(10.0), Easing::Linear)
+            .then(TranslateX, 200.0, ticks(20.0), Easing::Linear);
+        assert_eq!(animation.duration(), ticks(30.0));
+
+        props.update_tweeners(ticks(5.0));
+        assert_eq!(props.get_value(MulColorAlpha), 500.0);
+        assert_eq!(props.get_value(TranslateX), 0.0);
+
+        props.update_tweeners(ticks(5.0));
+        assert_eq!(props.get_value(MulColorAlpha), 1000.0);
+        assert_eq!(props.get_value(TranslateX), 0.0);
+
+        props.update_tweeners(ticks(10.0));
+        assert_eq!(props.get_value(TranslateX), 100.0);
+
+        props.update_tweeners(ticks(10.0));
+        assert_eq!(props.get_value(TranslateX), 200.0);
+        assert!(props.property_tweener(TranslateX).is_idle());
+    }
+
+    #[test]
+    fn animation_waits_for_queued_tweens() {
+        let mut props = LayerProperties::new();
+
+        props.animate(TranslateX, 100.0, ticks(10.0), Easing::Linear);
+        // TranslateX is busy for 10 more ticks, so the sequence is pushed back
+        let animation = props
+            .animate(TranslateX, 0.0, ticks(10.0), Easing::Linear)
+            .then(MulColorAlpha, 0.0, ticks(10.0), Easing::Linear);
+        assert_eq!(animation.duration(), ticks(30.0));
+
+        props.update_tweeners(ticks(15.0));
+        assert_eq!(props.get_value(TranslateX), 50.0);
+        assert_eq!(props.get_value(MulColorAlpha), 1000.0);
+
+        props.update_tweeners(ticks(10.0));
+        assert_eq!(props.get_value(TranslateX), 0.0);
+        assert_eq!(props.get_value(MulColorAlpha), 500.0);
+    }
+
+    #[test]
+    fn cancelled_sequence_stops() {
+        let mut props = LayerProperties::new();
+
+        props
+            .animate(TranslateX, 100.0, ticks(10.0), Easing::Linear)
+            .then(MulColorAlpha, 0.0, ticks(10.0), Easing::Linear);
+
+        props.update_tweeners(ticks(5.0));
+        props.cancel_animations();
+        assert_eq!(props.get_value(TranslateX), 50.0);
+
+        props.update_tweeners(ticks(20.0));
+        assert_eq!(props.get_value(TranslateX), 50.0);
+        assert_eq!(props.get_value(MulColorAlpha), 1000.0);
+        assert!(props.property_tweener(MulColorAlpha).is_idle());
+    }
+}
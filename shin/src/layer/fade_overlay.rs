@@ -0,0 +1,217 @@
+use shin_core::{
+    primitives::color::FloatColor4,
+    time::{Tween, Tweener},
+};
+use shin_render::{
+    ColorBlendType, DrawPrimitive, RenderProgramWithArguments, RenderRequestBuilder,
+    quad_vertices::build_quad_vertices,
+    render_pass::RenderPass,
+    shaders::types::{buffer::VertexSource, vertices::PosColVertex},
+};
+
+use crate::{
+    render::{VIRTUAL_CANVAS_SIZE_VEC, top_left_projection_matrix},
+    update::{AdvUpdatable, AdvUpdateContext},
+};
+
+/// A full-screen color drawn over all the layers, for flashing or fading the screen to a color.
+///
+/// Not present in the original engine, where this is done with a `TileLayer` on the topmost plane.
+/// Here it is faded with the engine's own `SCREEN_FADE` syscall.
+#[derive(Debug, Clone)]
+pub struct FadeOverlay {
+    r: Tweener,
+    g: Tweener,
+    b: Tweener,
+    a: Tweener,
+}
+
+impl FadeOverlay {
+    pub fn new() -> Self {
+        Self {
+            r: Tweener::new(0.0),
+            g: Tweener::new(0.0),
+            b: Tweener::new(0.0),
+            a: Tweener::new(0.0),
+        }
+    }
+
+    /// Fade to the `color` (with straight alpha), cancelling the fade that might be running already.
+    ///
+    /// Fading from a fully transparent overlay only changes the alpha, so that fading in a white flash doesn't go through gray.
+    pub fn fade_to(&mut self, color: FloatColor4, tween: Tween) {
+        if self.a.value() <= 0.0 {
+            self.r.fast_forward_to(color.r);
+            self.g.fast_forward_to(color.g);
+            self.b.fast_forward_to(color.b);
+        } else {
+            self.r.enqueue_now(color.r, tween);
+            self.g.enqueue_now(color.g, tween);
+            self.b.enqueue_now(color.b, tween);
+        }
+        self.a.enqueue_now(color.a, tween);
+    }
+
+    pub fn is_running(&self) -> bool {
+        !(self.r.is_idle() && self.g.is_idle() && self.b.is_idle() && self.a.is_idle())
+    }
+
+    pub fn fast_forward(&mut self) {
+        self.r.fast_forward();
+        self.g.fast_forward();
+        self.b.fast_forward();
+        self.a.fast_forward();
+    }
+
+    /// The current color of the overlay, with straight alpha
+    pub fn color(&self) -> FloatColor4 {
+        FloatColor4::from_rgba(
+            self.r.value(),
+            self.g.value(),
+            self.b.value(),
+            self.a.value().clamp(0.0, 1.0),
+        )
+    }
+
+    pub fn render(&self, pass: &mut RenderPass) {
+        let color = self.color();
+        if color.a <= 0.0 {
+            return;
+        }
+
+        let color = color.premultiply().into_unorm();
+
+        pass.push_debug("FadeOverlay");
+        pass.run(
+            RenderRequestBuilder::new()
+                .color_blend_type(ColorBlendType::LayerPremultiplied1)
+                .build(
+                    RenderProgramWithArguments::Fill {
+                        vertices: VertexSource::VertexData {
+                            vertices: &build_quad_vertices(|t| PosColVertex {
+                                position: (t * VIRTUAL_CANVAS_SIZE_VEC).extend(0.0),
+                                color,
+                            }),
+                        },
+                        transform: top_left_projection_matrix(),
+                    },
+                    DrawPrimitive::TrianglesStrip,
+                ),
+        );
+        pass.pop_debug();
+    }
+}
+
+impl AdvUpdatable for FadeOverlay {
+    fn update(&mut self, context: &AdvUpdateContext) {
+        if context.are_animations_allowed {
+            self.r.update(context.delta_ticks);
+            self.g.update(context.delta_ticks);
+            self.b.update(context.delta_ticks);
+            self.a.update(context.delta_ticks);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{Vec4, vec4};
+    use shin_core::{
+        primitives::color::FloatColor4,
+        time::{Ticks, Tween},
+    };
+    use winit::dpi::PhysicalSize;
+
+    use super::FadeOverlay;
+    use crate::render::{overdraw::render_overdraw, test_utils::TestRenderer};
+
+    // mirrors `FadeOverlay::render`: the premultiplied overlay color is drawn over the scene with the `LayerPremultiplied1` blend
+    fn blend(overlay: &FadeOverlay, scene: Vec4) -> Vec4 {
+        let src = overlay.color().premultiply().into_vec4();
+        src + scene * (1.0 - src.w)
+    }
+
+    fn to_unorm8(value: f32) -> u8 {
+        (value * 255.0).round() as u8
+    }
+
+    fn advance(overlay: &mut FadeOverlay, delta: Ticks) {
+        overlay.r.update(delta);
+        overlay.g.update(delta);
+        overlay.b.update(delta);
+        overlay.a.update(delta);
+    }
+
+    #[test]
+    fn fade_to_white() {
+        let scene = vec4(0.2, 0.6, 1.0, 1.0);
+
+        let mut overlay = FadeOverlay::new();
+        assert_eq!(blend(&overlay, scene), scene);
+
+        overlay.fade_to(FloatColor4::WHITE, Tween::linear(Ticks::from_u32(60)));
+        advance(&mut overlay, Ticks::from_u32(30));
+        assert!(overlay.is_running());
+
+        // halfway through, half of the scene is showing through the white
+        // (and not through gray, which would be the case with the color tweening from transparent black)
+        let color = blend(&overlay, scene).to_array().map(to_unorm8);
+        assert_eq!(color, [153, 204, 255, 255]);
+
+        advance(&mut overlay, Ticks::from_u32(30));
+        assert!(!overlay.is_running());
+        assert_eq!(blend(&overlay, scene), Vec4::ONE);
+    }
+
+    #[test]
+    fn fade_between_colors() {
+        let mut overlay = FadeOverlay::new();
+        overlay.fade_to(FloatColor4::WHITE, Tween::IMMEDIATE);
+        overlay.fast_forward();
+
+        overlay.fade_to(FloatColor4::BLACK, Tween::linear(Ticks::from_u32(60)));
+        advance(&mut overlay, Ticks::from_u32(30));
+
+        // an opaque overlay switches colors without letting the scene through
+        let color = blend(&overlay, Vec4::ONE);
+        assert!(
+            color.abs_diff_eq(vec4(0.5, 0.5, 0.5, 1.0), 1e-5),
+            "{}",
+            color
+        );
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn transparent_overlay_is_noop() {
+        let mut overlay = FadeOverlay::new();
+        overlay.fade_to(FloatColor4::WHITE, Tween::IMMEDIATE);
+        overlay.fast_forward();
+        assert_eq!(overlay.color().a, 1.0);
+
+        overlay.fade_to(FloatColor4::WHITE.with_alpha(0.0), Tween::IMMEDIATE);
+        overlay.fast_forward();
+        assert_eq!(overlay.color().a, 0.0);
+
+        let mut renderer = TestRenderer::new(PhysicalSize::new(192, 108));
+        let mut overdraw = |overlay: &FadeOverlay| {
+            let mut target = renderer.new_render_texture();
+            renderer.pre_render(|context| {
+                let depth_stencil = context.depth_stencil;
+                let mut pass =
+                    context.begin_pass(target.as_texture_target(), Some(depth_stencil), "overdraw");
+                render_overdraw(&mut pass, |pass| overlay.render(pass));
+            });
+            renderer.read(&target)
+        };
+
+        // not a single pixel is drawn to
+        let black = [0, 0, 0, 255];
+        assert!(overdraw(&overlay).pixels().all(|pixel| pixel.0 == black));
+
+        // unlike with the opaque overlay, covering everything
+        overlay.fade_to(FloatColor4::WHITE, Tween::IMMEDIATE);
+        overlay.fast_forward();
+        assert!(overdraw(&overlay).pixels().all(|pixel| pixel.0 != black));
+    }
+}
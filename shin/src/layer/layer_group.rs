@@ -3,20 +3,20 @@ use std::sync::Arc;
 use glam::{Mat4, Vec3, Vec3Swizzles as _, vec2, vec3};
 use shin_core::{
     primitives::color::{FloatColor4, UnormColor},
-    time::Ticks,
-    vm::command::types::{LayerProperty, LayerbankId, MaskFlags},
+    time::{Easing, Ticks, Tween},
+    vm::command::types::{LayerId, LayerProperty, LayerbankId, MaskFlags},
 };
 use shin_derive::RenderClone;
 use shin_render::{
-    ColorBlendType, DepthStencilState, DrawPrimitive, LayerShaderOutputKind, PassKind,
-    RenderProgramWithArguments, RenderRequestBuilder, StencilFunction, StencilMask,
-    StencilOperation, StencilPipelineState, StencilState,
+    ColorBlendType, DepthStencilState, DrawPrimitive, PassKind, RenderProgramWithArguments,
+    RenderRequestBuilder, StencilFunction, StencilMask, StencilOperation, StencilPipelineState,
+    StencilState,
     quad_vertices::build_quad_vertices,
     render_pass::RenderPass,
     shaders::types::{
         buffer::VertexSource,
         texture::{DepthStencilTarget, TextureTarget},
-        vertices::{MaskVertex, PosTexVertex, PosVertex},
+        vertices::{MaskVertex, PosVertex},
     },
 };
 
@@ -30,19 +30,118 @@ use crate::{
         properties::LayerProperties,
         render_params::{DrawableClipMode, DrawableClipParams, DrawableParams, TransformParams},
     },
-    render::{PreRenderContext, VIRTUAL_CANVAS_SIZE_VEC, top_left_projection_matrix},
+    render::{
+        PreRenderContext, VIRTUAL_CANVAS_SIZE_VEC, sprite::Sprite, top_left_projection_matrix,
+    },
     update::{AdvUpdatable, AdvUpdateContext},
 };
 
 #[derive(Clone, RenderClone)]
 struct LayerItem<T> {
     pub layerbank_id: LayerbankId,
-    // this is probably how delayed removal is implemented?
-    // pub some_countdown: Ticks,
+    /// Time left until the layer is removed, set while it is being unloaded with a delay (and is no longer in its layerbank)
+    pub unload_countdown: Option<Ticks>,
+    /// The layer this one is attached to, following its transform instead of the group's one
+    pub parent: Option<LayerbankId>,
     #[render_clone(needs_render)]
     pub layer: T,
 }
 
+/// Why a layer could not be attached to another one
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AttachLayerError {
+    /// There is no layer in this layerbank
+    MissingLayer(LayerbankId),
+    /// The parent is the child itself, or is (indirectly) attached to it
+    Cycle,
+}
+
+impl std::fmt::Display for AttachLayerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AttachLayerError::MissingLayer(id) => write!(f, "no layer in layerbank {:?}", id),
+            AttachLayerError::Cycle => write!(f, "attaching would create a cycle"),
+        }
+    }
+}
+
+impl std::error::Error for AttachLayerError {}
+
+/// The indices of the layers in the order they are rendered in the transparent pass, from the back to the front
+fn sorted_for_rendering<T: DrawableLayer>(layers: &[&LayerItem<T>]) -> Vec<usize> {
+    let mut sorted = (0..layers.len()).collect::<Vec<_>>();
+
+    sorted.sort_by(|&left, &right| {
+        let get_values = |index: usize| {
+            let layer = &layers[index];
+            let prop = layer.layer.properties();
+
+            let id = layer.layerbank_id;
+            let render_position = prop.get_render_order_position();
+
+            (render_position, id)
+        };
+
+        let (left_position, left_id) = get_values(left);
+        let (right_position, right_id) = get_values(right);
+
+        // if positions are close, compare by id
+        if (left_position - right_position).abs() < f32::EPSILON {
+            left_id.cmp(&right_id).reverse()
+        } else {
+            left_position
+                .partial_cmp(&right_position)
+                .unwrap()
+                .reverse()
+        }
+    });
+
+    sorted
+}
+
+/// The transform each layer inherits: the one of the group, or the composed one of the layer it is attached to
+fn inherited_transforms<T: DrawableLayer>(
+    layers: &[&LayerItem<T>],
+    group_transform: &TransformParams,
+) -> Vec<TransformParams> {
+    fn resolve<T: DrawableLayer>(
+        layers: &[&LayerItem<T>],
+        group_transform: &TransformParams,
+        resolved: &mut [Option<TransformParams>],
+        index: usize,
+    ) -> TransformParams {
+        if let Some(transform) = resolved[index] {
+            return transform;
+        }
+
+        // only the layers still in their layerbanks can be parents
+        let parent_index = layers[index].parent.and_then(|parent| {
+            layers
+                .iter()
+                .position(|item| item.layerbank_id == parent && item.unload_countdown.is_none())
+        });
+        // the cycles are rejected when attaching, so this recursion terminates
+        let transform = match parent_index {
+            Some(parent_index) => {
+                let parent_inherited = resolve(layers, group_transform, resolved, parent_index);
+                layers[parent_index]
+                    .layer
+                    .properties()
+                    .get_composed_transform_params(&parent_inherited)
+            }
+            None => *group_transform,
+        };
+
+        resolved[index] = Some(transform);
+        transform
+    }
+
+    let mut resolved = vec![None; layers.len()];
+    (0..layers.len())
+        .map(|index| resolve(layers, group_transform, &mut resolved, index))
+        .collect()
+}
+
 #[derive(Clone, Copy)]
 struct LayerRenderItem {
     // TODO: maybe we should make the layers actually shareable with `Arc`s, yknow...
@@ -54,6 +153,11 @@ struct LayerRenderItem {
 pub struct LayerGroup<T = UserLayer> {
     #[render_clone(needs_render)]
     layers: Vec<LayerItem<T>>,
+    /// The layers fading out, see [`Self::remove_layer`]
+    ///
+    /// They are kept apart, as their layerbanks can be reused by the layers loaded in the meantime.
+    #[render_clone(needs_render)]
+    unloading_layers: Vec<LayerItem<T>>,
 
     stencil_bump: u8,
     layers_to_render: Vec<LayerRenderItem>,
@@ -64,12 +168,15 @@ pub struct LayerGroup<T = UserLayer> {
     mask_flags: MaskFlags,
     props: LayerProperties,
     label: String,
+    /// Composite the translucent group as a whole, see [`AdvUpdateContext::group_opacity`]
+    group_opacity: bool,
 }
 
 impl<T> LayerGroup<T> {
     pub fn new(label: Option<String>) -> Self {
         Self {
             layers: vec![],
+            unloading_layers: vec![],
             stencil_bump: 0,
             layers_to_render: vec![],
             new_drawable_state: NewDrawableLayerState::new(),
@@ -77,6 +184,7 @@ impl<T> LayerGroup<T> {
             mask_flags: MaskFlags::empty(),
             props: LayerProperties::new(),
             label: label.unwrap_or_else(|| "unnamed".to_string()),
+            group_opacity: false,
         }
     }
 
@@ -93,22 +201,89 @@ impl<T> LayerGroup<T> {
             Err(index) => {
                 self.layers.insert(index, LayerItem {
                     layerbank_id,
+                    unload_countdown: None,
+                    parent: None,
                     layer,
                 });
             }
         }
     }
 
-    pub fn remove_layer(&mut self, layerbank_id: LayerbankId, delay_time: Ticks) -> Option<T> {
-        if delay_time != Ticks::ZERO {
-            // this is never used in umineko
-            unimplemented!("LayerGroup::remove_layer: delay_time is not implemented");
+    /// Take the layer out of its layerbank
+    fn take_layer_item(&mut self, layerbank_id: LayerbankId) -> Option<LayerItem<T>> {
+        let item = self
+            .layers
+            .binary_search_by_key(&layerbank_id, |item| item.layerbank_id)
+            .map(|index| self.layers.remove(index))
+            .ok();
+
+        // the children of the removed layer go back to following the group
+        for item in self.layers.iter_mut().chain(&mut self.unloading_layers) {
+            if item.parent == Some(layerbank_id) {
+                item.parent = None;
+            }
         }
 
+        item
+    }
+
+    /// Run the countdowns of the layers being unloaded, removing the ones that ran out
+    fn update_unloading_layers(&mut self, delta_ticks: Ticks) {
+        self.unloading_layers.retain_mut(|item| {
+            let countdown = item
+                .unload_countdown
+                .as_mut()
+                .expect("Unloading layer without a countdown");
+            *countdown -= delta_ticks;
+            *countdown > Ticks::ZERO
+        });
+    }
+
+    /// The layers in their layerbanks followed by the ones being unloaded, as indexed by [`sorted_for_rendering`]
+    fn rendered_layers(&self) -> Vec<&LayerItem<T>> {
+        self.layers.iter().chain(&self.unloading_layers).collect()
+    }
+
+    fn rendered_layer_mut(&mut self, index: usize) -> &mut LayerItem<T> {
+        match index.checked_sub(self.layers.len()) {
+            Some(unloading_index) => &mut self.unloading_layers[unloading_index],
+            None => &mut self.layers[index],
+        }
+    }
+
+    fn layer_index(&self, layerbank_id: LayerbankId) -> Result<usize, AttachLayerError> {
         self.layers
             .binary_search_by_key(&layerbank_id, |item| item.layerbank_id)
-            .map(|index| self.layers.remove(index).layer)
-            .ok()
+            .map_err(|_| AttachLayerError::MissingLayer(layerbank_id))
+    }
+
+    /// Make the `child` layer follow the transform of the `parent` one, like it follows the one of the group otherwise
+    ///
+    /// The child keeps its own properties, which are now relative to the parent.
+    /// Replacing either of the layers keeps the attachment, removing the parent detaches the child.
+    pub fn attach_layer(
+        &mut self,
+        child: LayerbankId,
+        parent: LayerbankId,
+    ) -> Result<(), AttachLayerError> {
+        let child_index = self.layer_index(child)?;
+        let mut ancestor = Some(parent);
+        while let Some(ancestor_id) = ancestor {
+            if ancestor_id == child {
+                return Err(AttachLayerError::Cycle);
+            }
+            ancestor = self.layers[self.layer_index(ancestor_id)?].parent;
+        }
+
+        self.layers[child_index].parent = Some(parent);
+        Ok(())
+    }
+
+    /// Make the layer follow the transform of the group again, undoing [`Self::attach_layer`]
+    pub fn detach_layer(&mut self, layerbank_id: LayerbankId) {
+        if let Ok(index) = self.layer_index(layerbank_id) {
+            self.layers[index].parent = None;
+        }
     }
 
     pub fn get_layer(&self, layerbank_id: LayerbankId) -> Option<&T> {
@@ -132,6 +307,7 @@ impl<T> LayerGroup<T> {
 
     pub fn clear_layers(&mut self) {
         self.layers.clear();
+        self.unloading_layers.clear();
     }
 
     #[expect(unused)] // for future stuff
@@ -150,7 +326,7 @@ impl<T> LayerGroup<T> {
 
     #[allow(clippy::len_zero)]
     pub fn needs_rendering(&self) -> bool {
-        self.mask_texture.is_some() || self.layers.len() > 0
+        self.mask_texture.is_some() || self.layers.len() > 0 || self.unloading_layers.len() > 0
     }
 
     pub fn is_rendered_opaquely(&self) -> bool {
@@ -163,6 +339,39 @@ impl<T> LayerGroup<T> {
     }
 }
 
+impl<T: DrawableLayer> LayerGroup<T> {
+    /// Remove the layer from its layerbank, returning it if it's removed right away
+    ///
+    /// With a non-zero `delay_time` the layer fades out first, staying in the group until the delay runs out.
+    /// The layerbank is free right away though, so a new layer can be loaded into it while the old one is still fading out.
+    pub fn remove_layer(&mut self, layerbank_id: LayerbankId, delay_time: Ticks) -> Option<T> {
+        let mut item = self.take_layer_item(layerbank_id)?;
+        if delay_time <= Ticks::ZERO {
+            return Some(item.layer);
+        }
+
+        item.unload_countdown = Some(delay_time);
+        item.layer
+            .properties_mut()
+            .property_tweener_mut(LayerProperty::MulColorAlpha)
+            .enqueue_now(0.0, Tween {
+                duration: delay_time,
+                easing: Easing::Linear,
+            });
+        self.unloading_layers.push(item);
+
+        None
+    }
+
+    /// Whether any of the layers in the range is still being unloaded, see [`Self::remove_layer`]
+    pub fn is_unloading_in_range(&self, from: LayerId, to: LayerId) -> bool {
+        self.unloading_layers.iter().any(|item| {
+            let layer_id = item.layer.properties().get_layer_id();
+            from <= layer_id && layer_id <= to
+        })
+    }
+}
+
 fn render_mask(
     pass: &mut RenderPass,
     builder: RenderRequestBuilder,
@@ -382,11 +591,50 @@ impl NewDrawableLayerNeedsSeparatePass for LayerGroupNewDrawableNeedsSeparatePas
     }
 }
 
+/// How the texture with the prerendered children of a group gets composited
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum GroupCompositeMode {
+    /// The children are drawn over black and the texture is composited in the opaque pass, like the planes are.
+    Opaque,
+    /// The texture is transparent where there are no children, and it is composited in the transparent pass,
+    /// applying the group alpha and blend type once to all of them.
+    ///
+    /// This way the overlapping children fade out together, instead of showing through each other.
+    Opacity,
+}
+
+impl GroupCompositeMode {
+    /// The groups are composited like the planes, unless the `group_opacity` is opted into
+    fn new(props: &LayerProperties, has_mask_texture: bool, group_opacity: bool) -> Self {
+        // the offscreen masks are drawn over an opaque black background, see `prepare_mask_for_offscreen`
+        if group_opacity && props.is_blending_nontrivial() && !has_mask_texture {
+            Self::Opacity
+        } else {
+            Self::Opaque
+        }
+    }
+
+    fn clear_color(self) -> UnormColor {
+        match self {
+            Self::Opaque => UnormColor::BLACK,
+            Self::Opacity => UnormColor(0),
+        }
+    }
+
+    fn target_pass(self) -> PassKind {
+        match self {
+            Self::Opaque => PassKind::Opaque,
+            Self::Opacity => PassKind::Transparent,
+        }
+    }
+}
+
 struct LayerGroupNewDrawableDelegate<'a, T> {
-    layers: &'a [LayerItem<T>],
+    layers: Vec<&'a LayerItem<T>>,
     layers_to_render: Vec<LayerRenderItem>,
     mask_texture: &'a Option<Arc<MaskTexture>>,
     mask_flags: MaskFlags,
+    group_opacity: bool,
 }
 
 impl<T> NewDrawableLayerNeedsSeparatePass for LayerGroupNewDrawableDelegate<'_, T> {
@@ -400,7 +648,7 @@ impl<T> NewDrawableLayerNeedsSeparatePass for LayerGroupNewDrawableDelegate<'_,
 
 impl<T> NewDrawableLayer for LayerGroupNewDrawableDelegate<'_, T>
 where
-    T: Layer,
+    T: DrawableLayer,
 {
     #[tracing::instrument(skip_all)]
     fn render_drawable_indirect(
@@ -412,22 +660,26 @@ where
         transform: &TransformParams,
     ) -> PassKind {
         let mut pass = context.begin_pass(target, Some(depth_stencil), "LayerGroup/indirect");
+        let composite_mode =
+            GroupCompositeMode::new(props, self.mask_texture.is_some(), self.group_opacity);
 
         if !props.is_visible() {
-            pass.clear(Some(UnormColor::BLACK), None, None);
+            pass.clear(Some(composite_mode.clear_color()), None, None);
         } else {
             let self_transform = props.get_composed_transform_params(transform);
 
             if let Some(mask) = self.mask_texture {
                 prepare_mask_for_offscreen(&mut pass, mask, self.mask_flags, self_transform);
             } else {
-                pass.clear(Some(UnormColor::BLACK), Some(0), None);
+                pass.clear(Some(composite_mode.clear_color()), Some(0), None);
             }
 
+            let inherited_transforms = inherited_transforms(&self.layers, &self_transform);
+
             for render_item in self.layers_to_render.iter().rev() {
                 self.layers[render_item.layer_index].layer.render(
                     &mut pass,
-                    &self_transform,
+                    &inherited_transforms[render_item.layer_index],
                     render_item.stencil_ref_relative,
                     PassKind::Opaque,
                 );
@@ -435,7 +687,7 @@ where
             for render_item in &self.layers_to_render {
                 self.layers[render_item.layer_index].layer.render(
                     &mut pass,
-                    &self_transform,
+                    &inherited_transforms[render_item.layer_index],
                     render_item.stencil_ref_relative,
                     PassKind::Transparent,
                 );
@@ -444,9 +696,12 @@ where
 
         self.layers_to_render.clear();
 
-        // it's very sus that it does that, considering you can apply alpha and stuff...
-        // but I guess that's just how planes are...
-        PassKind::Opaque
+        composite_mode.target_pass()
+    }
+
+    fn composites_with_layer_shader(&self) -> bool {
+        // the masked groups are composited with the mask shader, see `finish_render_with_mask`
+        self.mask_texture.is_none()
     }
 
     fn render_drawable_direct(
@@ -469,19 +724,14 @@ where
 {
     fn update(&mut self, context: &AdvUpdateContext) {
         self.props.update(context);
-        self.new_drawable_state.update(context);
+        self.new_drawable_state.update(context, &self.props);
+        self.group_opacity = context.group_opacity;
 
-        for layer in &mut self.layers {
+        for layer in self.layers.iter_mut().chain(&mut self.unloading_layers) {
             layer.layer.update(context);
         }
 
-        // this code handles the delayed layer removal
-        // don't need it
-        // for layer in &mut self.layers {
-        //     if layer.layerbank_id < 0 {
-        //         todo!("Run the timer and do something with TransitionLayers")
-        //     }
-        // }
+        self.update_unloading_layers(context.delta_ticks);
     }
 }
 
@@ -492,7 +742,7 @@ where
     fn fast_forward(&mut self) {
         self.props.fast_forward();
 
-        for layer in self.layers.iter_mut() {
+        for layer in self.layers.iter_mut().chain(&mut self.unloading_layers) {
             layer.layer.fast_forward();
         }
     }
@@ -502,43 +752,20 @@ where
     }
 
     fn pre_render(&mut self, context: &mut PreRenderContext, transform: &TransformParams) {
-        let mut layers = (0..self.layers.len()).collect::<Vec<_>>();
-
-        layers.sort_by(|&left, &right| {
-            let get_values = |index: usize| {
-                let layer = &self.layers[index];
-                let prop = layer.layer.properties();
-
-                let id = layer.layerbank_id;
-                let render_position = prop.get_value(LayerProperty::RenderPosition);
-
-                (render_position, id)
-            };
-
-            let (left_position, left_id) = get_values(left);
-            let (right_position, right_id) = get_values(right);
-
-            // if positions are close, compare by id
-            if (left_position - right_position).abs() < f32::EPSILON {
-                left_id.cmp(&right_id).reverse()
-            } else {
-                left_position
-                    .partial_cmp(&right_position)
-                    .unwrap()
-                    .reverse()
-            }
-        });
+        let rendered_layers = self.rendered_layers();
+        let layers = sorted_for_rendering(&rendered_layers);
 
         // The original implementations handles `LayerGroup::TransitionLayer` here according to `Effectable` rules
         // This is not necessary for running umineko (it uses a different system for transition), so this is not implemented
 
         let props = self.properties();
         let self_transform = props.get_composed_transform_params(transform);
+        let inherited_transforms = inherited_transforms(&rendered_layers, &self_transform);
 
         for &index in &layers {
-            self.layers[index]
+            self.rendered_layer_mut(index)
                 .layer
-                .pre_render(context, &self_transform);
+                .pre_render(context, &inherited_transforms[index]);
             // NB: if the current layer is `Effectable` (like `LayerGroup::TransitionLayer`), we need to call a special pre-render function and pass the lower layers to it
             // This is not necessary for umineko
         }
@@ -546,6 +773,13 @@ where
         self.layers_to_render.clear();
         let mut layers_to_render = std::mem::take(&mut self.layers_to_render);
 
+        // borrowing only the layer lists, as the drawable state is updated while the delegate is around
+        let rendered_layers = self
+            .layers
+            .iter()
+            .chain(&self.unloading_layers)
+            .collect::<Vec<_>>();
+
         let mut stencil_value = 1;
         for &index in &layers {
             layers_to_render.push(LayerRenderItem {
@@ -553,16 +787,17 @@ where
                 stencil_ref_relative: stencil_value,
             });
 
-            stencil_value += self.layers[index].layer.get_stencil_bump();
+            stencil_value += rendered_layers[index].layer.get_stencil_bump();
         }
 
         self.stencil_bump = stencil_value;
 
         let mut delegate = LayerGroupNewDrawableDelegate {
-            layers: &self.layers,
+            layers: rendered_layers,
             layers_to_render,
             mask_texture: &self.mask_texture,
             mask_flags: self.mask_flags,
+            group_opacity: self.group_opacity,
         };
 
         // NOTE: we DON'T send self_transform to the pre-drawing
@@ -611,6 +846,8 @@ where
         }
 
         let self_transform = props.get_composed_transform_params(transform);
+        let layers = self.rendered_layers();
+        let inherited_transforms = inherited_transforms(&layers, &self_transform);
 
         pass.push_debug(&format!(
             "LayerGroup[{}]/{}",
@@ -632,9 +869,9 @@ where
                     stencil_ref_relative,
                 } in self.layers_to_render.iter().rev()
                 {
-                    self.layers[layer_index].layer.render(
+                    layers[layer_index].layer.render(
                         pass,
-                        &self_transform,
+                        &inherited_transforms[layer_index],
                         stencil_ref + stencil_ref_relative,
                         pass_kind,
                     )
@@ -685,9 +922,9 @@ where
                     stencil_ref_relative,
                 } in &self.layers_to_render
                 {
-                    self.layers[layer_index].layer.render(
+                    layers[layer_index].layer.render(
                         pass,
-                        &self_transform,
+                        &inherited_transforms[layer_index],
                         stencil_ref + stencil_ref_relative,
                         pass_kind,
                     )
@@ -758,23 +995,10 @@ impl<T> LayerGroup<T> {
 
         match pass_kind {
             PassKind::Opaque => {
-                pass.run(builder.color_blend_type(ColorBlendType::Opaque).build(
-                    RenderProgramWithArguments::Layer {
-                        output_kind: LayerShaderOutputKind::Layer,
-                        fragment_shader,
-                        vertices: VertexSource::VertexData {
-                            vertices: &build_quad_vertices(|t| PosTexVertex {
-                                position: t * VIRTUAL_CANVAS_SIZE_VEC,
-                                texture_position: t,
-                            }),
-                        },
-                        texture: prerendered.render_texture,
-                        transform,
-                        color_multiplier,
-                        fragment_shader_param,
-                    },
-                    DrawPrimitive::TrianglesStrip,
-                ));
+                Sprite::full_canvas(prerendered.render_texture)
+                    .with_color(color_multiplier)
+                    .with_fragment_shader(fragment_shader, fragment_shader_param)
+                    .render(pass, builder, PassKind::Opaque);
             }
             PassKind::Transparent => {
                 let mut min = 0.0;
@@ -852,3 +1076,461 @@ where
         &mut self.props
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use glam::{Vec2, vec2, vec4};
+    use shin_core::{
+        primitives::color::{FloatColor4, UnormColor},
+        time::Ticks,
+        vm::command::types::{LayerId, LayerProperty, LayerbankId},
+    };
+    use shin_render::PassKind;
+    use winit::dpi::PhysicalSize;
+
+    use super::{
+        AttachLayerError, GroupCompositeMode, LayerGroup, inherited_transforms,
+        sorted_for_rendering,
+    };
+    use crate::{
+        layer::{
+            DrawableLayer, Layer as _, LayerProperties, render_layer_without_bg,
+            render_params::TransformParams,
+            user::{NullLayer, TileLayer},
+        },
+        render::test_utils::TestRenderer,
+    };
+
+    fn group_props(alpha: f32) -> LayerProperties {
+        let mut props = LayerProperties::new();
+        props
+            .property_tweener_mut(LayerProperty::MulColorAlpha)
+            .fast_forward_to(alpha * 1000.0);
+        props
+    }
+
+    #[test]
+    fn opaque_group_is_a_plane() {
+        let mode = GroupCompositeMode::new(&group_props(1.0), false, true);
+
+        assert_eq!(mode, GroupCompositeMode::Opaque);
+        assert_eq!(mode.clear_color(), UnormColor::BLACK);
+        assert_eq!(mode.target_pass(), PassKind::Opaque);
+
+        // the translucent groups too, unless opted into
+        assert_eq!(
+            GroupCompositeMode::new(&group_props(0.5), false, false),
+            GroupCompositeMode::Opaque
+        );
+        // the masks rely on the opaque background
+        assert_eq!(
+            GroupCompositeMode::new(&group_props(0.5), true, true),
+            GroupCompositeMode::Opaque
+        );
+    }
+
+    /// A group in the top half of the screen, with a blue tile on the right in front of a red one on the left.
+    ///
+    /// Both the group and the tiles are faded by the given alphas.
+    fn overlapping_children(group_alpha: f32, child_alpha: f32) -> LayerGroup<TileLayer> {
+        let mut group = LayerGroup::new(None);
+        for (id, color, rect) in [
+            (0, FloatColor4::BLUE, vec4(-320.0, -540.0, 1280.0, 540.0)),
+            (1, FloatColor4::RED, vec4(-960.0, -540.0, 1280.0, 540.0)),
+        ] {
+            let mut tile = TileLayer::new(color, rect);
+            tile.properties_mut()
+                .property_tweener_mut(LayerProperty::MulColorAlpha)
+                .fast_forward_to(child_alpha * 1000.0);
+            group.add_layer(LayerbankId::new(id), tile);
+        }
+        group
+            .properties_mut()
+            .property_tweener_mut(LayerProperty::MulColorAlpha)
+            .fast_forward_to(group_alpha * 1000.0);
+        group
+    }
+
+    /// The colors of the `group` rendered over white, where there is only the back tile, both of them,
+    /// only the front one and none of them
+    fn render_over_white(
+        renderer: &mut TestRenderer,
+        group: &mut LayerGroup<TileLayer>,
+    ) -> [[u8; 3]; 4] {
+        let mut target = renderer.new_render_texture();
+
+        renderer.pre_render(|context| {
+            let transform = TransformParams::default();
+            group.pre_render(context, &transform);
+
+            let depth_stencil = context.depth_stencil;
+            let mut pass =
+                context.begin_pass(target.as_texture_target(), Some(depth_stencil), "group");
+            pass.clear(Some(UnormColor::WHITE), Some(0), None);
+            render_layer_without_bg(&mut pass, &transform, &*group, 0);
+        });
+        let image = renderer.read(&target);
+
+        [(10, 27), (96, 27), (180, 27), (96, 80)].map(|(x, y)| {
+            let [r, g, b, _] = image.get_pixel(x, y).0;
+            [r, g, b]
+        })
+    }
+
+    fn assert_color_near(actual: [u8; 3], expected: [u8; 3]) {
+        assert!(
+            actual
+                .iter()
+                .zip(expected)
+                .all(|(&a, e)| a.abs_diff(e) <= 2),
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn fades_overlapping_children_together() {
+        // a tenth of the virtual canvas
+        let mut renderer = TestRenderer::new(PhysicalSize::new(192, 108));
+
+        let mut group = overlapping_children(0.5, 1.0);
+        group.group_opacity = true;
+        let [back_only, overlap, front_only, uncovered] =
+            render_over_white(&mut renderer, &mut group);
+
+        assert_color_near(back_only, [255, 128, 128]);
+        assert_color_near(front_only, [128, 128, 255]);
+        // the back child doesn't show through the front one
+        assert_eq!(overlap, front_only);
+        // the area without the children is left alone
+        assert_eq!(uncovered, [255, 255, 255]);
+
+        // fading the children one by one instead makes the overlap visible
+        let mut group = overlapping_children(1.0, 0.5);
+        let [_, overlap, front_only, _] = render_over_white(&mut renderer, &mut group);
+        assert_color_near(front_only, [128, 128, 255]);
+        assert_color_near(overlap, [128, 64, 191]);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn translucent_group_is_opaque_by_default() {
+        let mut renderer = TestRenderer::new(PhysicalSize::new(192, 108));
+
+        // like the planes in the original game, the group is composited over black
+        let mut group = overlapping_children(0.5, 1.0);
+        let [back_only, overlap, front_only, uncovered] =
+            render_over_white(&mut renderer, &mut group);
+
+        assert_color_near(back_only, [128, 0, 0]);
+        assert_color_near(overlap, [0, 0, 128]);
+        assert_color_near(front_only, [0, 0, 128]);
+        assert_eq!(uncovered, [0, 0, 0]);
+    }
+
+    fn set_property(
+        group: &mut LayerGroup<NullLayer>,
+        id: LayerbankId,
+        property: LayerProperty,
+        value: f32,
+    ) {
+        group
+            .get_layer_mut(id)
+            .unwrap()
+            .properties_mut()
+            .property_tweener_mut(property)
+            .fast_forward_to(value);
+    }
+
+    /// Where the origin of the layer ends up in the group
+    fn layer_position(group: &LayerGroup<NullLayer>, id: LayerbankId) -> Vec2 {
+        let layers = group.rendered_layers();
+        let inherited = inherited_transforms(&layers, &TransformParams::default());
+        let index = group.layer_index(id).unwrap();
+
+        let transform = layers[index]
+            .layer
+            .properties()
+            .get_composed_transform_params(&inherited[index]);
+        transform.transform.w_axis.truncate().truncate()
+    }
+
+    #[test]
+    fn attached_layer_follows_the_parent() {
+        let parent = LayerbankId::new(0);
+        let child = LayerbankId::new(1);
+
+        let mut group = LayerGroup::new(None);
+        group.add_layer(parent, NullLayer::new());
+        group.add_layer(child, NullLayer::new());
+        set_property(&mut group, child, LayerProperty::TranslateX, 10.0);
+
+        group.attach_layer(child, parent).unwrap();
+        set_property(&mut group, parent, LayerProperty::TranslateX, 100.0);
+        set_property(&mut group, parent, LayerProperty::TranslateY, 50.0);
+        set_property(&mut group, parent, LayerProperty::ScaleX, 2000.0);
+
+        assert_eq!(layer_position(&group, parent), vec2(100.0, 50.0));
+        // the offset of the child is scaled along with the parent
+        assert_eq!(layer_position(&group, child), vec2(120.0, 50.0));
+
+        group.detach_layer(child);
+        assert_eq!(layer_position(&group, child), vec2(10.0, 0.0));
+
+        // removing the parent detaches the child as well
+        group.attach_layer(child, parent).unwrap();
+        group.remove_layer(parent, Ticks::ZERO);
+        assert_eq!(layer_position(&group, child), vec2(10.0, 0.0));
+    }
+
+    #[test]
+    fn attachment_cycles_are_rejected() {
+        let [first, second, third, missing] = [0, 1, 2, 3].map(LayerbankId::new);
+
+        let mut group = LayerGroup::new(None);
+        for id in [first, second, third] {
+            group.add_layer(id, NullLayer::new());
+        }
+
+        group.attach_layer(second, first).unwrap();
+        group.attach_layer(third, second).unwrap();
+
+        assert_eq!(
+            group.attach_layer(first, first),
+            Err(AttachLayerError::Cycle)
+        );
+        assert_eq!(
+            group.attach_layer(first, third),
+            Err(AttachLayerError::Cycle)
+        );
+        assert_eq!(
+            group.attach_layer(first, missing),
+            Err(AttachLayerError::MissingLayer(missing))
+        );
+        assert_eq!(
+            group.attach_layer(missing, first),
+            Err(AttachLayerError::MissingLayer(missing))
+        );
+    }
+
+    #[test]
+    fn delayed_unload_removes_the_layer_after_the_delay() {
+        let [unloaded, kept] = [0, 1].map(LayerbankId::new);
+
+        let mut group = LayerGroup::new(None);
+        group.add_layer(unloaded, NullLayer::new());
+        group.add_layer(kept, NullLayer::new());
+        group
+            .get_layer_mut(unloaded)
+            .unwrap()
+            .properties_mut()
+            .set_layer_id(LayerId::new(5));
+
+        assert!(group.remove_layer(unloaded, Ticks::from_u32(10)).is_none());
+        assert!(group.is_unloading_in_range(LayerId::new(0), LayerId::new(5)));
+        assert!(!group.is_unloading_in_range(LayerId::new(6), LayerId::new(10)));
+        // the layer fades out while it's being unloaded, out of its layerbank
+        assert!(group.get_layer(unloaded).is_none());
+        let alpha = group.unloading_layers[0]
+            .layer
+            .properties()
+            .property_tweener(LayerProperty::MulColorAlpha)
+            .target_value();
+        assert_eq!(alpha, 0.0);
+        assert_eq!(rendering_order(&group), [1, 0]);
+
+        for _ in 0..5 {
+            group.update_unloading_layers(Ticks::from_u32(1));
+        }
+        // unloading it again doesn't restart the delay
+        group.remove_layer(unloaded, Ticks::from_u32(10));
+        for _ in 0..4 {
+            group.update_unloading_layers(Ticks::from_u32(1));
+        }
+        assert_eq!(group.unloading_layers.len(), 1);
+
+        group.update_unloading_layers(Ticks::from_u32(1));
+        assert!(group.unloading_layers.is_empty());
+        assert!(group.get_layer(kept).is_some());
+        assert!(!group.is_unloading_in_range(LayerId::new(0), LayerId::new(5)));
+    }
+
+    #[test]
+    fn layer_loaded_while_the_previous_one_fades_out() {
+        let layerbank = LayerbankId::new(0);
+
+        let mut group = LayerGroup::new(None);
+        group.add_layer(layerbank, NullLayer::new());
+        group.remove_layer(layerbank, Ticks::from_u32(10));
+
+        let mut loaded = NullLayer::new();
+        loaded.properties_mut().set_layer_id(LayerId::new(3));
+        group.add_layer(layerbank, loaded);
+
+        // both are rendered, the new layer doesn't cut the fade short
+        assert_eq!(rendering_order(&group), [0, 0]);
+        for _ in 0..5 {
+            group.update_unloading_layers(Ticks::from_u32(1));
+        }
+        // the new layer can be unloaded in turn, fading out on its own
+        group.remove_layer(layerbank, Ticks::from_u32(10));
+        assert_eq!(group.unloading_layers.len(), 2);
+        assert!(group.get_layer(layerbank).is_none());
+
+        for _ in 0..5 {
+            group.update_unloading_layers(Ticks::from_u32(1));
+        }
+        assert_eq!(group.unloading_layers.len(), 1);
+        assert!(group.is_unloading_in_range(LayerId::new(3), LayerId::new(3)));
+    }
+
+    fn rendering_order(group: &LayerGroup<NullLayer>) -> Vec<u8> {
+        let layers = group.rendered_layers();
+        sorted_for_rendering(&layers)
+            .into_iter()
+            .map(|index| layers[index].layerbank_id.raw())
+            .collect()
+    }
+
+    #[test]
+    fn render_order_overrides_the_render_position() {
+        let mut group = LayerGroup::new(None);
+        for (id, render_position) in [(0, 1000.0), (1, 1000.0), (2, 2000.0), (3, 500.0)] {
+            let id = LayerbankId::new(id);
+            group.add_layer(id, NullLayer::new());
+            set_property(
+                &mut group,
+                id,
+                LayerProperty::RenderPosition,
+                render_position,
+            );
+        }
+
+        // the higher render positions are drawn first, the ties go by the layerbank
+        assert_eq!(rendering_order(&group), [2, 1, 0, 3]);
+
+        // the back layer jumps to the front, the other ones stay in order
+        group
+            .get_layer_mut(LayerbankId::new(2))
+            .unwrap()
+            .properties_mut()
+            .set_render_order(Some(0));
+        assert_eq!(rendering_order(&group), [1, 0, 3, 2]);
+
+        // a tie with a render position still goes by the layerbank
+        group
+            .get_layer_mut(LayerbankId::new(2))
+            .unwrap()
+            .properties_mut()
+            .set_render_order(Some(1000));
+        assert_eq!(rendering_order(&group), [2, 1, 0, 3]);
+
+        group
+            .get_layer_mut(LayerbankId::new(2))
+            .unwrap()
+            .properties_mut()
+            .set_render_order(None);
+        assert_eq!(rendering_order(&group), [2, 1, 0, 3]);
+    }
+
+    /// A white tile covering the right half of the screen, in a group blurred by `radius` pixels
+    fn blurred_group(radius: f32) -> LayerGroup<TileLayer> {
+        let mut group = LayerGroup::new(None);
+        group.add_layer(
+            LayerbankId::new(0),
+            TileLayer::new(FloatColor4::WHITE, vec4(0.0, -540.0, 960.0, 1080.0)),
+        );
+        group
+            .properties_mut()
+            .property_tweener_mut(LayerProperty::BlurRadius)
+            .fast_forward_to(radius * 1000.0);
+        group
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn small_blur_has_no_pass_of_its_own() {
+        // the blur radii are in virtual canvas pixels, so the canvas is as wide as the virtual one
+        let mut renderer = TestRenderer::new(PhysicalSize::new(1920, 4));
+        let middle = 960;
+
+        for (radius, has_blur_passes) in [(1.5, false), (8.0, true)] {
+            let mut group = blurred_group(radius);
+            let image = renderer.render_layer(&mut group);
+            let red = |x: u32| image.get_pixel(x, 0).0[0];
+
+            // the edge of the tile is softened, the rest is left alone
+            assert!(
+                red(middle - 1) > 0 && red(middle) < 255,
+                "radius {}: {:?}",
+                radius,
+                (middle - 4..middle + 4).map(red).collect::<Vec<_>>()
+            );
+            assert_eq!((red(middle - 10), red(middle + 10)), (0, 255));
+            assert_eq!(
+                group.new_drawable_state.has_effect_target(),
+                has_blur_passes,
+                "radius {}",
+                radius
+            );
+        }
+    }
+
+    /// Times the frames of an effect-heavy scene with lots of slightly blurred groups,
+    /// the small blur done when compositing against the separable passes just above its radius.
+    ///
+    /// Run with `cargo test --release -p shin blur_benchmark -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn blur_benchmark() {
+        const GROUPS: usize = 100;
+        const FRAMES: u32 = 30;
+
+        let mut renderer = TestRenderer::new(PhysicalSize::new(960, 540));
+        let mut target = renderer.new_render_texture();
+
+        for radius in [2.0, 2.5] {
+            let mut groups = (0..GROUPS)
+                .map(|_| blurred_group(radius))
+                .collect::<Vec<_>>();
+
+            let start = Instant::now();
+            for _ in 0..FRAMES {
+                renderer.pre_render(|context| {
+                    let transform = TransformParams::default();
+                    for group in &mut groups {
+                        group.pre_render(context, &transform);
+                    }
+
+                    let depth_stencil = context.depth_stencil;
+                    let mut pass = context.begin_pass(
+                        target.as_texture_target(),
+                        Some(depth_stencil),
+                        "blur_benchmark",
+                    );
+                    pass.clear(Some(UnormColor::BLACK), Some(0), None);
+                    for group in &groups {
+                        render_layer_without_bg(&mut pass, &transform, group, 0);
+                    }
+                });
+                renderer.device.poll(wgpu::Maintain::Wait);
+            }
+
+            let blur_passes = groups
+                .iter()
+                .filter(|group| group.new_drawable_state.has_effect_target())
+                .count()
+                * 2;
+            eprintln!(
+                "radius {}: {} blur passes, {:?} per frame",
+                radius,
+                blur_passes,
+                start.elapsed() / FRAMES
+            );
+        }
+    }
+}
@@ -154,7 +154,6 @@ pub struct MessageLayer {
     vertex_buffer: Option<OwnedVertexBuffer<TextVertex>>,
     sliding_out_messageboxes: Vec<SlidingOutMessagebox>,
     transform: Mat4,
-    // font_atlas: Arc<FontAtlas>,
     // message: Option<Message>,
     // messagebox: Messagebox,
 }
@@ -358,6 +357,13 @@ impl MessageLayer {
             .set_time_left(if self.is_voice_playing { 0.5 } else { 0.0 });
     }
 
+    pub fn voice_player(&self) -> &VoicePlayer {
+        &self.voice_player
+    }
+    pub fn voice_player_mut(&mut self) -> &mut VoicePlayer {
+        &mut self.voice_player
+    }
+
     pub fn on_msgset(
         &mut self,
         ctx: &PreRenderContext,
@@ -432,19 +438,7 @@ impl MessageLayer {
     }
 
     fn set_message(&mut self, ctx: &PreRenderContext, message: &str) {
-        let layout_params = LayoutParams {
-            layout_width: 1500.0,
-            text_alignment: self.text_layout,
-            line_padding_above: 0.0,
-            line_padding_below: 0.0,
-            line_padding_between: 4.0,
-            rubi_size: 20.0,
-            text_size: 50.0,
-            base_font_horizontal_scale: 0.9697,
-            follow_kinsoku_shori_rules: true,
-            always_leave_space_for_rubi: true, // < I am not sure if this should be true
-            perform_soft_breaks: true,
-        };
+        let layout_params = LayoutParams::message_layer(self.text_layout);
         let defaults = MessageTextLayouterDefaults {
             color: 999,
             draw_speed: if self.messagebox_type == MessageboxType::NoText {
@@ -629,7 +623,10 @@ impl MessageLayer {
         self.reset_message();
     }
 
-    pub fn try_advance(&mut self) -> bool {
+    /// Handle a click on the message: the first one reveals the rest of the current section at once, the next one advances past the wait
+    ///
+    /// When `skip`ping, a single click does both.
+    pub fn try_advance(&mut self, skip: bool) -> bool {
         if !self
             .modal_slide
             .is_fully_at(SlideInterpolatorDirection::Increasing)
@@ -644,24 +641,21 @@ impl MessageLayer {
         }
 
         // try to fast-forward any non-complete chars in this section
-        let any_char_ff = self.chars.iter_mut().fold(false, |acc, char| {
-            if char.block_index > self.current_block_index {
-                return acc;
-            }
-
-            if char.current_progress < 1.0 {
-                char.current_progress = 1.0;
-                return true;
-            }
-
-            acc
-        });
+        let any_char_ff = complete_reveal(
+            self.chars
+                .iter_mut()
+                .map(|char| (char.block_index, &mut char.current_progress)),
+            self.current_block_index,
+        );
 
         if any_char_ff {
             if self.current_block_index < self.blocks.len() {
                 self.current_time = self.blocks[self.current_block_index].time;
             }
-            return true;
+            // when skipping, the same click goes on to advance the message
+            if !skip {
+                return true;
+            }
         }
 
         // no chars needed to be fast-forwarded, try to advance wait
@@ -756,6 +750,7 @@ impl AdvUpdatable for MessageLayer {
         self.autoplay_requested = false;
 
         self.props.update(context);
+        self.voice_player.update();
 
         // if we are (semi)-transparent, don't update anything else
         // this is used for pausing
@@ -948,6 +943,24 @@ impl AdvUpdatable for MessageLayer {
     }
 }
 
+/// Sets the reveal progress of the chars up to `current_block_index` (given with their block index) to complete
+///
+/// Returns `true` if any of them were still being revealed.
+fn complete_reveal<'a>(
+    chars: impl IntoIterator<Item = (usize, &'a mut f32)>,
+    current_block_index: usize,
+) -> bool {
+    let mut any_completed = false;
+    for (block_index, progress) in chars {
+        if block_index <= current_block_index && *progress < 1.0 {
+            *progress = 1.0;
+            any_completed = true;
+        }
+    }
+
+    any_completed
+}
+
 impl Layer for MessageLayer {
     fn fast_forward(&mut self) {
         if self.modal_slide.direction() != SlideInterpolatorDirection::Increasing {
@@ -1192,3 +1205,92 @@ impl DrawableLayer for MessageLayer {
         &mut self.props
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use shin_audio::TestClock;
+    use shin_core::{
+        format::scenario::instruction_elements::MessageId,
+        vm::command::types::{MessageTextLayout, MessageboxType},
+    };
+    use winit::dpi::PhysicalSize;
+
+    use super::{MessageFlags, MessageLayer, MsgsetParams, complete_reveal};
+    use crate::{
+        adv::test_utils::{adv_fonts, assemble, messagebox_textures},
+        audio::VoicePlayer,
+        render::test_utils::{TestRenderer, create_task_pools},
+    };
+
+    /// The reveal progress of the chars, with their block index
+    fn click(chars: &mut [(usize, f32)], current_block_index: usize) -> bool {
+        complete_reveal(
+            chars
+                .iter_mut()
+                .map(|(block_index, progress)| (*block_index, progress)),
+            current_block_index,
+        )
+    }
+
+    #[test]
+    fn first_click_completes_the_reveal() {
+        let mut chars = [(0, 1.0), (0, 0.4), (0, 0.0), (1, 0.0)];
+
+        // mid-reveal, the click completes the current block
+        assert!(click(&mut chars, 0));
+        assert_eq!(chars, [(0, 1.0), (0, 1.0), (0, 1.0), (1, 0.0)]);
+
+        // fully revealed, the click is left to advance
+        assert!(!click(&mut chars, 0));
+        assert_eq!(chars, [(0, 1.0), (0, 1.0), (0, 1.0), (1, 0.0)]);
+
+        // and the two stages start over in the next block
+        assert!(click(&mut chars, 1));
+        assert!(!click(&mut chars, 1));
+        assert_eq!(chars, [(0, 1.0), (0, 1.0), (0, 1.0), (1, 1.0)]);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn layers_with_the_same_font_share_the_glyphs() {
+        let mut renderer = TestRenderer::new(PhysicalSize::new(192, 108));
+        create_task_pools();
+        let fonts = adv_fonts();
+        let messagebox_textures = messagebox_textures(&renderer);
+        let audio_clock = TestClock::new(48000);
+        let scenario = Arc::new(assemble(&[]));
+
+        let mut layers = [(); 2].map(|_| {
+            MessageLayer::new(
+                fonts.clone(),
+                messagebox_textures.clone(),
+                VoicePlayer::new(audio_clock.audio_manager().clone()),
+            )
+        });
+        renderer.pre_render(|context| {
+            for layer in &mut layers {
+                layer.on_msgset(
+                    context,
+                    &scenario,
+                    "A",
+                    MsgsetParams {
+                        flags: MessageFlags::empty(),
+                        messagebox_type: MessageboxType::Neutral,
+                        text_layout: MessageTextLayout::Left,
+                        message_id: MessageId(0),
+                    },
+                    true,
+                );
+            }
+        });
+
+        let [first, second] = &layers;
+        assert!(
+            first.chars[0]
+                .glyph
+                .shares_texture_with(&second.chars[0].glyph)
+        );
+    }
+}
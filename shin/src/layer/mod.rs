@@ -1,4 +1,6 @@
+mod animation;
 mod either;
+mod fade_overlay;
 mod layer_group;
 pub mod message_layer;
 mod new_drawable_layer;
@@ -8,10 +10,12 @@ pub mod render_params;
 #[expect(unused)]
 mod root_layer_group;
 mod screen_layer;
+mod screen_shake;
 pub mod user;
 mod wobbler;
 
 use derive_more::From;
+pub use fade_overlay::FadeOverlay;
 use glam::vec3;
 pub use layer_group::LayerGroup;
 pub use new_drawable_layer::{NewDrawableLayer, NewDrawableLayerWrapper};
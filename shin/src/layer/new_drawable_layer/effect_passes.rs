@@ -1,4 +1,6 @@
-use glam::Vec4;
+use std::f32::consts::TAU;
+
+use glam::{Vec2, Vec3, Vec4, vec2, vec3};
 use shin_core::primitives::color::FloatColor4;
 use shin_render::{
     ColorBlendType, DrawPrimitive, LayerFragmentShader, LayerShaderOutputKind,
@@ -10,9 +12,155 @@ use shin_render::{
 
 use crate::{
     layer::LayerProperties,
-    render::{PreRenderContext, VIRTUAL_CANVAS_SIZE_VEC, centered_projection_matrix},
+    render::{
+        PreRenderContext, VIRTUAL_CANVAS_SIZE_VEC, centered_projection_matrix,
+        normalized_projection_matrix,
+    },
 };
 
+/// A sine wave displacing one texture axis as a function of the other one.
+///
+/// All values are in texture coordinates, so the shader doesn't need to know the canvas size.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct WaveParams {
+    pub amplitude: f32,
+    /// Angular frequency, in radians per texture coordinate unit
+    pub frequency: f32,
+    /// Phase, in radians
+    pub phase: f32,
+}
+
+impl WaveParams {
+    /// Converts the layer property values (in virtual canvas pixels) into texture-space wave parameters.
+    ///
+    /// `displaced_extent` is the canvas size along the axis being displaced, `wave_extent` is the size along the axis the wave travels.
+    pub fn from_pixels(
+        amplitude: f32,
+        period: f32,
+        phase: f32,
+        displaced_extent: f32,
+        wave_extent: f32,
+    ) -> Self {
+        // non-positive period doesn't make sense, stretch a single period over the whole layer instead
+        let period = if period > 0.0 { period } else { wave_extent };
+
+        Self {
+            amplitude: amplitude / displaced_extent,
+            frequency: TAU * wave_extent / period,
+            phase,
+        }
+    }
+
+    fn as_vec3(&self) -> Vec3 {
+        vec3(self.amplitude, self.frequency, self.phase)
+    }
+}
+
+/// Concentric waves displacing the texture radially away from (and towards) a center point.
+///
+/// Unlike [`WaveParams`], the displacement is isotropic, so it is computed in pixels.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RippleParams {
+    /// Ripple center, in texture coordinates
+    pub center: Vec2,
+    /// Amplitude, in pixels
+    pub amplitude: f32,
+    /// Angular frequency, in radians per pixel
+    pub frequency: f32,
+    /// Phase, in radians. Increasing it makes the waves travel outwards
+    pub phase: f32,
+}
+
+impl RippleParams {
+    /// Builds the ripple parameters from the layer property values (in virtual canvas pixels).
+    pub fn from_pixels(center: Vec2, amplitude: f32, period: f32, phase: f32) -> Self {
+        // same as with the raster, stretch a single period over the whole layer if the period is nonsensical
+        let period = if period > 0.0 {
+            period
+        } else {
+            VIRTUAL_CANVAS_SIZE_VEC.y
+        };
+
+        Self {
+            center,
+            amplitude,
+            frequency: TAU / period,
+            phase,
+        }
+    }
+
+    fn wave(&self) -> Vec3 {
+        vec3(self.amplitude, self.frequency, self.phase)
+    }
+}
+
+/// Blur radius (in pixels) up to which the blur can be done by the layer shader while compositing, see [`small_blur_step`].
+pub const SMALL_BLUR_MAX_RADIUS: f32 = 2.0;
+/// Taps on each side of the center in the small blur of `layer.wgsl`, making for a 5x5 kernel
+const SMALL_BLUR_TAPS: u32 = 2;
+/// Upper limit for the taps on each side of the center in a separable blur pass, larger radii space the taps further apart
+const LARGE_BLUR_MAX_TAPS: u32 = 16;
+
+/// A single pass of the separable box blur.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BlurPass {
+    /// Distance between the taps, in texture coordinates
+    pub step: Vec2,
+    /// Number of taps on each side of the center
+    pub taps: u32,
+}
+
+/// Distance between the taps of the blur done by the layer shader, in texture coordinates.
+///
+/// Small radii don't need a pass of their own: the layer shader samples a fixed 5x5 kernel when compositing the layer.
+/// `None` when the radius is too large for it, leaving the blur to [`blur_passes`].
+pub fn small_blur_step(radius: f32) -> Option<Vec2> {
+    let radius = radius.abs();
+    if radius > SMALL_BLUR_MAX_RADIUS {
+        return None;
+    }
+
+    Some(Vec2::splat(radius / SMALL_BLUR_TAPS as f32) / VIRTUAL_CANVAS_SIZE_VEC)
+}
+
+/// Plans the passes of a separable box blur with the given radius, in pixels: horizontal and then vertical.
+pub fn blur_passes(radius: f32) -> [BlurPass; 2] {
+    let radius = radius.abs();
+    let pixel = 1.0 / VIRTUAL_CANVAS_SIZE_VEC;
+
+    let taps = (radius.ceil() as u32).clamp(1, LARGE_BLUR_MAX_TAPS);
+    let step = radius / taps as f32;
+    [
+        BlurPass {
+            step: vec2(step, 0.0) * pixel,
+            taps,
+        },
+        BlurPass {
+            step: vec2(0.0, step) * pixel,
+            taps,
+        },
+    ]
+}
+
+/// Prepares the textures for the next frame of ghosting.
+///
+/// Preserves `render_texture_src` (the previous frame) as `render_texture_prev_frame`,
+/// while re-using the old `render_texture_prev_frame` as `render_texture_src` to avoid allocating a new texture each frame.
+/// On the first frame there is no previous frame, so `render_texture_src` gets allocated anew and [`apply_ghosting`] is skipped.
+///
+/// When ghosting is disabled, the previous frame is freed.
+pub fn rotate_ghosting_textures<T>(
+    render_texture_src: &mut Option<T>,
+    render_texture_prev_frame: &mut Option<T>,
+    ghosting_alpha: f32,
+) {
+    if ghosting_alpha <= 0.0 {
+        *render_texture_prev_frame = None;
+    } else {
+        std::mem::swap(render_texture_prev_frame, render_texture_src);
+    }
+}
+
 pub fn apply_ghosting(
     context: &mut PreRenderContext,
     props: &LayerProperties,
@@ -43,8 +191,502 @@ pub fn apply_ghosting(
                     transform: centered_projection_matrix() * props.get_ghosting_transform(),
                     color_multiplier: FloatColor4::from_rgba(1.0, 1.0, 1.0, alpha).premultiply(),
                     fragment_shader_param: Vec4::ZERO,
+                    blur_step: Vec2::ZERO,
                 },
                 DrawPrimitive::TrianglesStrip,
             ),
     );
 }
+
+pub fn apply_raster(
+    context: &mut PreRenderContext,
+    render_texture_src: &RenderTexture,
+    render_texture_target: &mut RenderTexture,
+    horizontal: WaveParams,
+    vertical: WaveParams,
+) {
+    let mut pass = context.begin_pass(
+        render_texture_target.as_texture_target(),
+        None,
+        "NewDrawableLayer/raster",
+    );
+    let quad = pass.geometry_cache().unit_quad();
+
+    pass.run(RenderRequestBuilder::new().build(
+        RenderProgramWithArguments::Raster {
+            vertices: VertexSource::VertexBuffer {
+                vertices: quad.as_buffer_ref(),
+            },
+            texture: render_texture_src.as_texture_source(),
+            transform: normalized_projection_matrix(),
+            horizontal: horizontal.as_vec3(),
+            vertical: vertical.as_vec3(),
+        },
+        DrawPrimitive::TrianglesStrip,
+    ));
+}
+
+pub fn apply_ripple(
+    context: &mut PreRenderContext,
+    render_texture_src: &RenderTexture,
+    render_texture_target: &mut RenderTexture,
+    ripple: RippleParams,
+) {
+    let mut pass = context.begin_pass(
+        render_texture_target.as_texture_target(),
+        None,
+        "NewDrawableLayer/ripple",
+    );
+    let quad = pass.geometry_cache().unit_quad();
+
+    pass.run(RenderRequestBuilder::new().build(
+        RenderProgramWithArguments::Ripple {
+            vertices: VertexSource::VertexBuffer {
+                vertices: quad.as_buffer_ref(),
+            },
+            texture: render_texture_src.as_texture_source(),
+            transform: normalized_projection_matrix(),
+            center: ripple.center,
+            texture_size: VIRTUAL_CANVAS_SIZE_VEC,
+            wave: ripple.wave(),
+        },
+        DrawPrimitive::TrianglesStrip,
+    ));
+}
+
+pub fn apply_blur(
+    context: &mut PreRenderContext,
+    render_texture_src: &RenderTexture,
+    render_texture_target: &mut RenderTexture,
+    blur: BlurPass,
+) {
+    let mut pass = context.begin_pass(
+        render_texture_target.as_texture_target(),
+        None,
+        "NewDrawableLayer/blur",
+    );
+    let quad = pass.geometry_cache().unit_quad();
+
+    pass.run(RenderRequestBuilder::new().build(
+        RenderProgramWithArguments::Blur {
+            vertices: VertexSource::VertexBuffer {
+                vertices: quad.as_buffer_ref(),
+            },
+            texture: render_texture_src.as_texture_source(),
+            transform: normalized_projection_matrix(),
+            step: blur.step,
+            taps: blur.taps,
+        },
+        DrawPrimitive::TrianglesStrip,
+    ));
+}
+
+pub fn apply_dissolve(
+    context: &mut PreRenderContext,
+    render_texture_src: &RenderTexture,
+    render_texture_target: &mut RenderTexture,
+    intensity: f32,
+) {
+    let mut pass = context.begin_pass(
+        render_texture_target.as_texture_target(),
+        None,
+        "NewDrawableLayer/dissolve",
+    );
+    let quad = pass.geometry_cache().unit_quad();
+
+    pass.run(RenderRequestBuilder::new().build(
+        RenderProgramWithArguments::Dissolve {
+            vertices: VertexSource::VertexBuffer {
+                vertices: quad.as_buffer_ref(),
+            },
+            texture: render_texture_src.as_texture_source(),
+            transform: normalized_projection_matrix(),
+            texture_size: VIRTUAL_CANVAS_SIZE_VEC,
+            threshold: intensity.clamp(0.0, 1.0),
+        },
+        DrawPrimitive::TrianglesStrip,
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::TAU;
+
+    use glam::{Vec2, Vec4, vec2, vec4};
+    use image::{Rgba, RgbaImage};
+    use shin_render::{PassKind, RenderRequestBuilder, render_texture::RenderTexture};
+    use winit::dpi::PhysicalSize;
+
+    use super::{
+        RippleParams, SMALL_BLUR_MAX_RADIUS, WaveParams, apply_blur, apply_dissolve, apply_raster,
+        apply_ripple, blur_passes, rotate_ghosting_textures, small_blur_step,
+    };
+    use crate::render::{
+        PreRenderContext, VIRTUAL_CANVAS_SIZE_VEC, sprite::Sprite, test_utils::TestRenderer,
+    };
+
+    const SIZE: usize = 64;
+
+    const NO_WAVE: WaveParams = WaveParams {
+        amplitude: 0.0,
+        frequency: 0.0,
+        phase: 0.0,
+    };
+
+    /// The raster works in texture space, so the canvas can be much smaller than the virtual one
+    fn square_renderer() -> TestRenderer {
+        TestRenderer::new(PhysicalSize::new(SIZE as u32, SIZE as u32))
+    }
+
+    fn image_from_fn(width: u32, height: u32, f: impl Fn(u32, u32) -> [u8; 4]) -> RgbaImage {
+        RgbaImage::from_fn(width, height, |x, y| Rgba(f(x, y)))
+    }
+
+    /// Runs a single effect pass over `image`, stretched over the whole canvas
+    fn apply_effect(
+        renderer: &mut TestRenderer,
+        image: &RgbaImage,
+        effect: impl FnOnce(&mut PreRenderContext, &RenderTexture, &mut RenderTexture),
+    ) -> RgbaImage {
+        let src = renderer.render_texture_from_image(image);
+        let mut target = renderer.new_render_texture();
+        renderer.pre_render(|context| effect(context, &src, &mut target));
+
+        renderer.read(&target)
+    }
+
+    fn wave() -> WaveParams {
+        WaveParams::from_pixels(8.0, 32.0, 0.3, SIZE as f32, SIZE as f32)
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn flat_field_is_unchanged() {
+        let mut renderer = square_renderer();
+        let gray = image_from_fn(SIZE as u32, SIZE as u32, |_, _| [128, 128, 128, 255]);
+
+        let result = apply_effect(&mut renderer, &gray, |context, src, target| {
+            apply_raster(context, src, target, wave(), wave())
+        });
+
+        assert_eq!(result, gray);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn vertical_edge_becomes_wavy() {
+        let mut renderer = square_renderer();
+        let size = SIZE as u32;
+        let edge = image_from_fn(size, size, |x, _| {
+            if x < size / 2 {
+                [0, 0, 0, 255]
+            } else {
+                [255; 4]
+            }
+        });
+        let edge_positions = |image: &RgbaImage| {
+            (0..size)
+                .map(|y| {
+                    (0..size)
+                        .find(|&x| image.get_pixel(x, y).0[0] > 127)
+                        .unwrap()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let unchanged = apply_effect(&mut renderer, &edge, |context, src, target| {
+            apply_raster(context, src, target, NO_WAVE, NO_WAVE)
+        });
+        let unchanged_edges = edge_positions(&unchanged);
+        assert!(
+            unchanged_edges.iter().all(|&x| x == size / 2),
+            "{:?}",
+            unchanged_edges
+        );
+
+        let wavy = apply_effect(&mut renderer, &edge, |context, src, target| {
+            apply_raster(context, src, target, wave(), NO_WAVE)
+        });
+        let wavy_edges = edge_positions(&wavy);
+        let min = *wavy_edges.iter().min().unwrap();
+        let max = *wavy_edges.iter().max().unwrap();
+        // the amplitude is 8 pixels, so the edge should be moved by roughly that much in both directions
+        assert!(min <= size / 2 - 6, "{:?}", wavy_edges);
+        assert!(max >= size / 2 + 6, "{:?}", wavy_edges);
+    }
+
+    /// The ripple is specified in virtual canvas pixels, so use a tenth of the virtual canvas to keep the numbers round
+    const RIPPLE_CANVAS: PhysicalSize<u32> = PhysicalSize::new(192, 108);
+
+    fn ripple() -> RippleParams {
+        // an amplitude of 8 and a period of 32 pixels on the test canvas
+        // a quarter of a period phase shift, so that the displacement is at its maximum at multiples of the wavelength
+        RippleParams::from_pixels(vec2(0.5, 0.5), 80.0, 320.0, -TAU / 4.0)
+    }
+
+    /// Every pixel holds its own position, so the result tells where each pixel was sampled from
+    fn position_image() -> RgbaImage {
+        image_from_fn(RIPPLE_CANVAS.width, RIPPLE_CANVAS.height, |x, y| {
+            [x as u8, y as u8, 0, 255]
+        })
+    }
+
+    /// Where the pixels were moved from by the ripple, compared to the unaffected source
+    fn ripple_displacements() -> impl Fn(u32, u32) -> Vec2 {
+        let mut renderer = TestRenderer::new(RIPPLE_CANVAS);
+        let image = position_image();
+
+        let src = renderer.render_texture_from_image(&image);
+        let src = renderer.read(&src);
+        let rippled = apply_effect(&mut renderer, &image, |context, src, target| {
+            apply_ripple(context, src, target, ripple())
+        });
+
+        move |x, y| {
+            let position = |image: &RgbaImage| {
+                let [r, g, ..] = image.get_pixel(x, y).0;
+                vec2(r as f32, g as f32)
+            };
+            position(&rippled) - position(&src)
+        }
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn ripple_center_is_stationary() {
+        let displacement = ripple_displacements();
+
+        // the pixels around the canvas center
+        for (x, y) in [(95, 53), (96, 53), (95, 54), (96, 54)] {
+            let displacement = displacement(x, y);
+            assert!(
+                displacement.length() <= 1.0,
+                "{:?} displaced by {:?}",
+                (x, y),
+                displacement
+            );
+        }
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn ripple_displaces_mid_radius_radially() {
+        let displacement = ripple_displacements();
+
+        // one wavelength away from the center in several directions
+        for direction in [vec2(1.0, 0.0), vec2(0.0, 1.0), vec2(-1.0, 0.0), vec2(0.0, -1.0)] {
+            let pixel = vec2(96.0, 54.0) + direction * 32.0;
+            let displacement = displacement(pixel.x as u32, pixel.y as u32);
+
+            assert!(
+                (displacement.length() - 8.0).abs() <= 1.0,
+                "{:?} displaced by {:?}",
+                direction,
+                displacement
+            );
+            // the displacement is along the radius
+            assert!(displacement.perp_dot(direction).abs() <= 1.0);
+        }
+    }
+
+    /// Fraction of the pixels left after the dissolve, checking that they keep their color
+    fn dissolve_coverage(renderer: &mut TestRenderer, intensity: f32) -> f32 {
+        let size = SIZE as u32;
+        let image = image_from_fn(size, size, |x, y| [x as u8 * 4, y as u8 * 4, 128, 255]);
+
+        let dissolved = apply_effect(renderer, &image, |context, src, target| {
+            apply_dissolve(context, src, target, intensity)
+        });
+
+        let mut drawn = 0;
+        for (x, y, pixel) in dissolved.enumerate_pixels() {
+            if pixel.0 == [0; 4] {
+                continue;
+            }
+            assert_eq!(pixel, image.get_pixel(x, y), "{:?}", (x, y));
+            drawn += 1;
+        }
+
+        drawn as f32 / (size * size) as f32
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn dissolve_coverage_follows_intensity() {
+        let mut renderer = square_renderer();
+
+        assert_eq!(dissolve_coverage(&mut renderer, 0.0), 1.0);
+        let half = dissolve_coverage(&mut renderer, 0.5);
+        assert!((half - 0.5).abs() < 0.05, "{}", half);
+        assert_eq!(dissolve_coverage(&mut renderer, 1.0), 0.0);
+    }
+
+    // mirrors `apply_ghosting`: the previous frame is drawn over the current one
+    // with the `LayerPremultiplied1` blend and color multiplier of `alpha` (premultiplied)
+    fn ghosting_blend(current: Vec4, prev_frame: Vec4, alpha: f32) -> Vec4 {
+        let src = prev_frame * alpha;
+        src + current * (1.0 - src.w)
+    }
+
+    // simulates the texture rotation done in `NewDrawableLayerState::pre_render`, with textures being solid colors
+    fn render_frame(
+        render_texture_src: &mut Option<Vec4>,
+        render_texture_prev_frame: &mut Option<Vec4>,
+        layer_color: Vec4,
+        ghosting_alpha: f32,
+    ) -> Vec4 {
+        rotate_ghosting_textures(render_texture_src, render_texture_prev_frame, ghosting_alpha);
+
+        // the layer is rendered into a (possibly re-used) source texture, overwriting its contents
+        let src = render_texture_src.insert(layer_color);
+        if let Some(prev_frame) = render_texture_prev_frame {
+            *src = ghosting_blend(*src, *prev_frame, ghosting_alpha);
+        }
+
+        *src
+    }
+
+    #[test]
+    fn ghosting_blends_with_previous_frame() {
+        let red = vec4(1.0, 0.0, 0.0, 1.0);
+        let blue = vec4(0.0, 0.0, 1.0, 1.0);
+        let alpha = 0.25;
+
+        let mut src = None;
+        let mut prev_frame = None;
+
+        // no previous frame: the current one is shown as-is
+        let first = render_frame(&mut src, &mut prev_frame, red, alpha);
+        assert_eq!(first, red);
+        assert_eq!(prev_frame, None);
+
+        let second = render_frame(&mut src, &mut prev_frame, blue, alpha);
+        assert!(second.abs_diff_eq(red * alpha + blue * (1.0 - alpha), 1e-6));
+        assert_eq!(prev_frame, Some(red));
+
+        // the buffers are rotated, so the blended frame is the one being ghosted next
+        let third = render_frame(&mut src, &mut prev_frame, blue, alpha);
+        assert!(third.abs_diff_eq(second * alpha + blue * (1.0 - alpha), 1e-6));
+    }
+
+    #[test]
+    fn disabling_ghosting_frees_previous_frame() {
+        let mut src = Some(Vec4::ONE);
+        let mut prev_frame = Some(Vec4::ZERO);
+
+        rotate_ghosting_textures(&mut src, &mut prev_frame, 0.0);
+        assert_eq!(src, Some(Vec4::ONE));
+        assert_eq!(prev_frame, None);
+    }
+
+    #[test]
+    fn small_blur_is_done_when_compositing() {
+        for radius in [0.0, 0.5, 1.0, 1.5, SMALL_BLUR_MAX_RADIUS, -1.0] {
+            let step = small_blur_step(radius).unwrap();
+            // the 5x5 kernel spans the radius on each side
+            assert!(
+                (step * 2.0 * VIRTUAL_CANVAS_SIZE_VEC - radius.abs()).length() < 1e-3,
+                "radius {}",
+                radius
+            );
+        }
+
+        assert_eq!(small_blur_step(SMALL_BLUR_MAX_RADIUS + 0.5), None);
+    }
+
+    #[test]
+    fn large_blur_is_separable() {
+        let [horizontal, vertical] = blur_passes(8.0);
+        assert_eq!((horizontal.taps, vertical.taps), (8, 8));
+        assert_eq!(horizontal.step.y, 0.0);
+        assert_eq!(vertical.step.x, 0.0);
+
+        // the number of taps is limited, the taps are spread further apart instead
+        let [horizontal, _] = blur_passes(-100.0);
+        assert_eq!(horizontal.taps, 16);
+        let step = horizontal.step.x * VIRTUAL_CANVAS_SIZE_VEC.x;
+        assert!((step * 16.0 - 100.0).abs() < 1e-3);
+    }
+
+    /// The blur radii are in virtual canvas pixels, so the canvas is as wide as the virtual one.
+    /// The test image only changes along x, so a few rows are enough
+    const BLUR_CANVAS: PhysicalSize<u32> = PhysicalSize::new(1920, 4);
+
+    /// A vertical edge in the middle of the canvas, from transparent to opaque white
+    fn edge_image() -> RgbaImage {
+        image_from_fn(BLUR_CANVAS.width, BLUR_CANVAS.height, |x, _| {
+            if x < BLUR_CANVAS.width / 2 {
+                [0; 4]
+            } else {
+                [255; 4]
+            }
+        })
+    }
+
+    fn first_row(image: &RgbaImage) -> Vec<u8> {
+        (0..image.width())
+            .map(|x| image.get_pixel(x, 0).0[0])
+            .collect()
+    }
+
+    /// Composites `image` with the blur of the layer shader, like `NewDrawableLayerState::try_finish_indirect_render` does
+    fn small_blur(renderer: &mut TestRenderer, image: &RgbaImage, radius: f32) -> RgbaImage {
+        let step = small_blur_step(radius).unwrap();
+
+        apply_effect(renderer, image, |context, src, target| {
+            let mut pass = context.begin_pass(target.as_texture_target(), None, "small_blur");
+            Sprite::full_canvas(src.as_texture_source())
+                .with_blur(step)
+                .render(&mut pass, RenderRequestBuilder::new(), PassKind::Opaque);
+        })
+    }
+
+    fn separable_blur(renderer: &mut TestRenderer, image: &RgbaImage, radius: f32) -> RgbaImage {
+        let src = renderer.render_texture_from_image(image);
+        let mut horizontal_target = renderer.new_render_texture();
+        let mut target = renderer.new_render_texture();
+
+        renderer.pre_render(|context| {
+            let [horizontal, vertical] = blur_passes(radius);
+            apply_blur(context, &src, &mut horizontal_target, horizontal);
+            apply_blur(context, &horizontal_target, &mut target, vertical);
+        });
+
+        renderer.read(&target)
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn small_blur_matches_separable() {
+        let mut renderer = TestRenderer::new(BLUR_CANVAS);
+        let edge = edge_image();
+        let middle = BLUR_CANVAS.width as usize / 2;
+
+        for radius in [0.5, 1.0, 1.5, SMALL_BLUR_MAX_RADIUS] {
+            let small = first_row(&small_blur(&mut renderer, &edge, radius));
+            let separable = first_row(&separable_blur(&mut renderer, &edge, radius));
+
+            // the edge is softened...
+            assert!(
+                small[middle - 1] > 0 && small[middle] < 255,
+                "radius {}: {:?}",
+                radius,
+                &small[middle - 4..middle + 4]
+            );
+            // ...without getting visibly different from the separable blur
+            for (x, (a, b)) in small.iter().zip(&separable).enumerate() {
+                assert!(
+                    a.abs_diff(*b) <= 26,
+                    "radius {}, x {}: {} vs {}",
+                    radius,
+                    x,
+                    a,
+                    b
+                );
+            }
+            // and nothing changes away from the edge
+            assert!(small[..middle - 3].iter().all(|&v| v == 0));
+            assert!(small[middle + 3..].iter().all(|&v| v == 255));
+        }
+    }
+}
@@ -1,18 +1,15 @@
 mod effect_passes;
 
-use shin_core::vm::command::types::LayerProperty;
+use glam::{Vec2, Vec3};
+use shin_core::{time::Ticks, vm::command::types::LayerProperty};
 use shin_render::{
-    ColorBlendType, DepthStencilState, DrawPrimitive, LayerShaderOutputKind, PassKind,
-    RenderProgramWithArguments, RenderRequestBuilder, StencilFunction, StencilOperation,
+    DepthStencilState, PassKind, RenderRequestBuilder, StencilFunction, StencilOperation,
     StencilPipelineState, StencilState,
-    quad_vertices::build_quad_vertices,
     render_pass::RenderPass,
     render_texture::RenderTexture,
     shaders::types::{
         RenderClone,
-        buffer::VertexSource,
         texture::{DepthStencilTarget, TextureSource, TextureTarget},
-        vertices::PosTexVertex,
     },
 };
 
@@ -22,8 +19,8 @@ use crate::{
         render_params::{DrawableClipMode, DrawableClipParams, DrawableParams, TransformParams},
     },
     render::{
-        PreRenderContext, VIRTUAL_CANVAS_SIZE_VEC, render_texture_holder::RenderTextureHolder,
-        top_left_projection_matrix,
+        PreRenderContext, VIRTUAL_CANVAS_SIZE_VEC, centered_projection_matrix,
+        render_texture_holder::RenderTextureHolder, sprite::Sprite,
     },
     update::{AdvUpdatable, AdvUpdateContext},
 };
@@ -48,6 +45,12 @@ pub trait NewDrawableLayer: NewDrawableLayerNeedsSeparatePass {
         // TODO: initiate a generic render pass and delegate to Self::render_drawable_direct
         todo!()
     }
+    /// Whether the prerendered texture is composited with the layer shader, which does the small blurs on its own.
+    ///
+    /// Otherwise all the blurs are done in separate passes.
+    fn composites_with_layer_shader(&self) -> bool {
+        true
+    }
     fn render_drawable_direct(
         &self,
         pass: &mut RenderPass,
@@ -64,11 +67,113 @@ pub trait NewDrawableLayerFastForward {
     fn fast_forward(&mut self);
 }
 
+/// Phase of a periodic effect, measured in periods.
+#[derive(Debug, Copy, Clone, Default)]
+struct EffectPhase {
+    time: f32,
+}
+
+impl EffectPhase {
+    fn update(&mut self, delta_time: Ticks, period: Ticks) {
+        if period <= Ticks::ZERO {
+            return;
+        }
+
+        self.time = (self.time + delta_time / period).rem_euclid(1.0);
+    }
+
+    fn reset(&mut self) {
+        self.time = 0.0;
+    }
+
+    fn radians(&self) -> f32 {
+        self.time * std::f32::consts::TAU
+    }
+}
+
+/// How much the displacement effects are scaled down in the reduced motion mode
+const REDUCED_MOTION_AMPLITUDE_SCALE: f32 = 0.25;
+/// Displacement amplitudes (in pixels, after scaling) below this are dropped altogether in the reduced motion mode
+const REDUCED_MOTION_MIN_AMPLITUDE: f32 = 4.0;
+
+/// Values of the effect properties of a layer, as they are going to be rendered.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct EffectValues {
+    blur_radius: f32,
+    prop70: f32,
+    mosaic_size: i32,
+    raster_horizontal_amplitude: f32,
+    raster_vertical_amplitude: f32,
+    ripple_amplitude: f32,
+    dissolve_intensity: f32,
+    ghosting_alpha: f32,
+}
+
+impl EffectValues {
+    /// Reads the effect values from `props`.
+    ///
+    /// With `reduced_motion`, the raster & ripple displacements are dampened and the ghosting (trailing afterimages) is disabled.
+    /// The dissolve is left as is: the scenario uses it to make the characters vanish, which the player must still see.
+    fn new(props: &LayerProperties, reduced_motion: bool) -> Self {
+        let amplitude = |property: LayerProperty| {
+            let amplitude = props.get_value(property);
+            if !reduced_motion {
+                return amplitude;
+            }
+
+            let amplitude = amplitude * REDUCED_MOTION_AMPLITUDE_SCALE;
+            if amplitude.abs() < REDUCED_MOTION_MIN_AMPLITUDE {
+                0.0
+            } else {
+                amplitude
+            }
+        };
+
+        let ghosting_alpha = if reduced_motion {
+            0.0
+        } else {
+            props.get_value(LayerProperty::GhostingAlpha) * 0.001
+        };
+
+        Self {
+            blur_radius: props.get_value(LayerProperty::BlurRadius) * 0.001,
+            prop70: props.get_value(LayerProperty::Prop70) * 0.001,
+            mosaic_size: props.get_value(LayerProperty::MosaicSize) as i32,
+            raster_horizontal_amplitude: amplitude(LayerProperty::RasterHorizontalAmplitude),
+            raster_vertical_amplitude: amplitude(LayerProperty::RasterVerticalAmplitude),
+            ripple_amplitude: amplitude(LayerProperty::RippleAmplitude),
+            dissolve_intensity: props.get_value(LayerProperty::DissolveIntensity) * 0.001,
+            ghosting_alpha,
+        }
+    }
+
+    fn has_raster(&self) -> bool {
+        self.raster_horizontal_amplitude.abs() >= f32::EPSILON
+            || self.raster_vertical_amplitude.abs() >= f32::EPSILON
+    }
+
+    fn has_ripple(&self) -> bool {
+        self.ripple_amplitude.abs() >= f32::EPSILON
+    }
+
+    /// Whether none of the effects are active, so the layer can be drawn without the intermediate textures
+    fn is_noop(&self) -> bool {
+        self.blur_radius.abs() < f32::EPSILON
+            && self.prop70 < f32::EPSILON
+            && self.mosaic_size <= 0
+            && !self.has_raster()
+            && !self.has_ripple()
+            && self.dissolve_intensity <= 0.0
+            && self.ghosting_alpha <= 0.0
+    }
+}
+
 pub struct PrerenderedDrawable<'a> {
     pub render_texture: TextureSource<'a>,
     pub target_pass: PassKind,
 }
 
+/// Cloning the state (for LAYERSWAP and the like) makes copies of the render textures on the GPU, so the clone can be drawn into without affecting the original
 #[derive(Debug, RenderClone)]
 pub struct NewDrawableLayerState {
     #[render_clone(needs_render)]
@@ -79,6 +184,13 @@ pub struct NewDrawableLayerState {
     #[render_clone(needs_render)]
     render_texture_prev_frame: Option<RenderTexture>,
     target_pass: PassKind,
+    /// Distance between the taps of the blur done when compositing, see [`effect_passes::small_blur_step`]
+    blur_step: Vec2,
+    raster_horizontal_phase: EffectPhase,
+    raster_vertical_phase: EffectPhase,
+    ripple_phase: EffectPhase,
+    reduced_motion: bool,
+    pixel_snap: bool,
 }
 
 impl NewDrawableLayerState {
@@ -88,9 +200,26 @@ impl NewDrawableLayerState {
             render_texture_target: None,
             render_texture_prev_frame: None,
             target_pass: PassKind::Transparent,
+            blur_step: Vec2::ZERO,
+            raster_horizontal_phase: EffectPhase::default(),
+            raster_vertical_phase: EffectPhase::default(),
+            ripple_phase: EffectPhase::default(),
+            reduced_motion: false,
+            pixel_snap: false,
         }
     }
 
+    /// See [`DrawableParams::pixel_snap`]
+    pub fn set_pixel_snap(&mut self, enabled: bool) {
+        self.pixel_snap = enabled;
+    }
+
+    /// The target texture is only allocated by the effect passes, so this tells whether there were any
+    #[cfg(test)]
+    pub fn has_effect_target(&self) -> bool {
+        self.render_texture_target.is_some()
+    }
+
     pub fn get_prerendered_tex(&self) -> Option<PrerenderedDrawable> {
         let tex = self.render_texture_src.get()?;
 
@@ -100,8 +229,32 @@ impl NewDrawableLayerState {
         })
     }
 
-    pub fn update(&mut self, _context: &AdvUpdateContext) {
-        // TODO: there are several float values we need to track and to update for some effects
+    pub fn update(&mut self, context: &AdvUpdateContext, props: &LayerProperties) {
+        let dt = context.delta_ticks;
+        self.reduced_motion = context.reduced_motion;
+
+        macro_rules! phase {
+            ($phase:ident, $amplitude:ident, $period:ident) => {
+                if props.get_value(LayerProperty::$amplitude).abs() < f32::EPSILON {
+                    self.$phase.reset();
+                } else {
+                    self.$phase
+                        .update(dt, Ticks::from_f32(props.get_value(LayerProperty::$period)));
+                }
+            };
+        }
+
+        phase!(
+            raster_horizontal_phase,
+            RasterHorizontalAmplitude,
+            RasterHorizontalTPeriod
+        );
+        phase!(
+            raster_vertical_phase,
+            RasterVerticalAmplitude,
+            RasterVerticalTPeriod
+        );
+        phase!(ripple_phase, RippleAmplitude, RippleTPeriod);
     }
 
     pub fn is_rendered_opaquely<T: NewDrawableLayerNeedsSeparatePass>(
@@ -132,39 +285,20 @@ impl NewDrawableLayerState {
             return;
         }
 
-        let blur_radius = props.get_value(LayerProperty::BlurRadius) * 0.001;
-        let prop70 = props.get_value(LayerProperty::Prop70) * 0.001;
-        let mosaic_size = props.get_value(LayerProperty::MosaicSize) as i32;
-        let raster_horizontal_amplitude = props.get_value(LayerProperty::RasterHorizontalAmplitude);
-        let raster_vertical_amplitude = props.get_value(LayerProperty::RasterVerticalAmplitude);
-        let ripple_amplitude = props.get_value(LayerProperty::RippleAmplitude);
-        let dissolve_intensity = props.get_value(LayerProperty::DissolveIntensity) * 0.001;
-        let ghosting_alpha = props.get_value(LayerProperty::GhostingAlpha) * 0.001;
-
-        if blur_radius.abs() < f32::EPSILON
-            && prop70 < f32::EPSILON
-            && mosaic_size <= 0
-            && raster_horizontal_amplitude.abs() < f32::EPSILON
-            && raster_vertical_amplitude.abs() < f32::EPSILON
-            && ripple_amplitude.abs() < f32::EPSILON
-            && dissolve_intensity <= 0.0
-            && ghosting_alpha <= 0.0
-            && !delegate.needs_separate_pass(props)
-        {
+        let effects = EffectValues::new(props, self.reduced_motion);
+
+        if effects.is_noop() && !delegate.needs_separate_pass(props) {
             self.render_texture_src.clear();
             self.render_texture_target = None;
             self.render_texture_prev_frame = None;
             return;
         }
 
-        if ghosting_alpha <= 0.0 {
-            self.render_texture_prev_frame = None;
-        } else {
-            std::mem::swap(
-                &mut self.render_texture_prev_frame,
-                &mut self.render_texture_src.as_inner_mut(),
-            );
-        }
+        effect_passes::rotate_ghosting_textures(
+            self.render_texture_src.as_inner_mut(),
+            &mut self.render_texture_prev_frame,
+            effects.ghosting_alpha,
+        );
 
         let render_texture_src = self.render_texture_src.get_or_init(context);
         self.target_pass = delegate.render_drawable_indirect(
@@ -175,34 +309,97 @@ impl NewDrawableLayerState {
             transform,
         );
 
-        if blur_radius.abs() >= f32::EPSILON {
-            todo!()
+        // the small blur is done when compositing, after the other effects. It's too subtle for the order to matter
+        let small_blur_step = effect_passes::small_blur_step(effects.blur_radius)
+            .filter(|_| delegate.composites_with_layer_shader());
+        self.blur_step = small_blur_step.unwrap_or(Vec2::ZERO);
+        if effects.blur_radius.abs() >= f32::EPSILON && small_blur_step.is_none() {
+            for blur in effect_passes::blur_passes(effects.blur_radius) {
+                let render_texture_target = self.render_texture_target.get_or_insert_with(|| {
+                    context.new_render_texture("NewDrawableLayerState/render_texture_target".into())
+                });
+                effect_passes::apply_blur(context, render_texture_src, render_texture_target, blur);
+                std::mem::swap(render_texture_src, render_texture_target);
+            }
         }
-        if prop70 >= f32::EPSILON {
+        if effects.prop70 >= f32::EPSILON {
             todo!()
         }
-        if mosaic_size > 0 {
+        if effects.mosaic_size > 0 {
             todo!()
         }
-        if raster_horizontal_amplitude.abs() >= f32::EPSILON
-            || raster_vertical_amplitude.abs() >= f32::EPSILON
-        {
-            todo!()
+        if effects.has_raster() {
+            let horizontal = effect_passes::WaveParams::from_pixels(
+                effects.raster_horizontal_amplitude,
+                props.get_value(LayerProperty::RasterHorizontalLPeriod),
+                self.raster_horizontal_phase.radians(),
+                VIRTUAL_CANVAS_SIZE_VEC.x,
+                VIRTUAL_CANVAS_SIZE_VEC.y,
+            );
+            let vertical = effect_passes::WaveParams::from_pixels(
+                effects.raster_vertical_amplitude,
+                props.get_value(LayerProperty::RasterVerticalLPeriod),
+                self.raster_vertical_phase.radians(),
+                VIRTUAL_CANVAS_SIZE_VEC.y,
+                VIRTUAL_CANVAS_SIZE_VEC.x,
+            );
+
+            let render_texture_target = self.render_texture_target.get_or_insert_with(|| {
+                context.new_render_texture("NewDrawableLayerState/render_texture_target".into())
+            });
+            effect_passes::apply_raster(
+                context,
+                render_texture_src,
+                render_texture_target,
+                horizontal,
+                vertical,
+            );
+            std::mem::swap(render_texture_src, render_texture_target);
         }
-        if ripple_amplitude.abs() >= f32::EPSILON {
-            todo!()
+        if effects.has_ripple() {
+            // ripples spread from the layer origin
+            let origin = props
+                .get_composed_transform_params(transform)
+                .compute_total_translation()
+                .transform_point3(Vec3::ZERO);
+            let center = origin.truncate() / VIRTUAL_CANVAS_SIZE_VEC + 0.5;
+
+            let ripple = effect_passes::RippleParams::from_pixels(
+                center,
+                effects.ripple_amplitude,
+                props.get_value(LayerProperty::RippleLPeriod),
+                self.ripple_phase.radians(),
+            );
+
+            let render_texture_target = self.render_texture_target.get_or_insert_with(|| {
+                context.new_render_texture("NewDrawableLayerState/render_texture_target".into())
+            });
+            effect_passes::apply_ripple(context, render_texture_src, render_texture_target, ripple);
+            std::mem::swap(render_texture_src, render_texture_target);
         }
-        if dissolve_intensity > 0.0 {
-            todo!()
+        if effects.dissolve_intensity > 0.0 {
+            let render_texture_target = self.render_texture_target.get_or_insert_with(|| {
+                context.new_render_texture("NewDrawableLayerState/render_texture_target".into())
+            });
+            effect_passes::apply_dissolve(
+                context,
+                render_texture_src,
+                render_texture_target,
+                effects.dissolve_intensity,
+            );
+            std::mem::swap(render_texture_src, render_texture_target);
+
+            // dissolved fragments are transparent and are dropped by the LayerDiscard output in the transparent pass
+            self.target_pass = PassKind::Transparent;
         }
-        match (ghosting_alpha > 0.0, &mut self.render_texture_prev_frame) {
+        match (effects.ghosting_alpha > 0.0, &mut self.render_texture_prev_frame) {
             (true, Some(render_texture_prev_frame)) => {
                 effect_passes::apply_ghosting(
                     context,
                     props,
                     render_texture_src,
                     render_texture_prev_frame,
-                    ghosting_alpha,
+                    effects.ghosting_alpha,
                 );
             }
             _ => self.render_texture_prev_frame = None,
@@ -225,60 +422,49 @@ impl NewDrawableLayerState {
             return true;
         }
 
-        let color_multiplier = props.get_color_multiplier().premultiply();
-        let blend_type = props.get_blend_type();
-        let fragment_shader = props.get_fragment_shader();
-        let fragment_shader_param = props.get_fragment_shader_param();
-
-        // NOTE: the transform is actually used just for clipping
-        // we still compute it just in case
-        let _self_transform = props.get_composed_transform_params(transform);
+        let texture = tex.as_texture_source();
 
+        // NOTE: the transform has already been applied when rendering to the texture
+        // it is only needed to position the clip area
         let clip_params = props.get_clip_params();
-        assert_eq!(clip_params.mode, DrawableClipMode::None);
-
-        let transform = top_left_projection_matrix();
+        let sprite = match clip_params.mode {
+            DrawableClipMode::None => Sprite::full_canvas(texture),
+            DrawableClipMode::Clip => {
+                let self_transform = props.get_composed_transform_params(transform);
+                Sprite::canvas_region(
+                    texture,
+                    clip_params.area,
+                    self_transform.compute_final_transform(),
+                )
+            }
+            DrawableClipMode::ClipIgnoreTransform => {
+                Sprite::canvas_region(texture, clip_params.area, centered_projection_matrix())
+            }
+        };
 
-        let vertices = &build_quad_vertices(|t| PosTexVertex {
-            position: t * VIRTUAL_CANVAS_SIZE_VEC,
-            texture_position: t,
+        let builder = RenderRequestBuilder::new().depth_stencil(DepthStencilState {
+            depth: Default::default(),
+            stencil: StencilState {
+                pipeline: StencilPipelineState {
+                    function: StencilFunction::Greater,
+                    stencil_fail_operation: StencilOperation::Keep,
+                    depth_fail_operation: StencilOperation::Keep,
+                    pass_operation: StencilOperation::Replace,
+                    ..Default::default()
+                },
+                stencil_reference: stencil_ref,
+            },
         });
 
-        pass.run(
-            RenderRequestBuilder::new()
-                .depth_stencil(DepthStencilState {
-                    depth: Default::default(),
-                    stencil: StencilState {
-                        pipeline: StencilPipelineState {
-                            function: StencilFunction::Greater,
-                            stencil_fail_operation: StencilOperation::Keep,
-                            depth_fail_operation: StencilOperation::Keep,
-                            pass_operation: StencilOperation::Replace,
-                            ..Default::default()
-                        },
-                        stencil_reference: stencil_ref,
-                    },
-                })
-                .color_blend_type(match pass_kind {
-                    PassKind::Opaque => ColorBlendType::Opaque,
-                    PassKind::Transparent => ColorBlendType::from_premultiplied_layer(blend_type),
-                })
-                .build(
-                    RenderProgramWithArguments::Layer {
-                        output_kind: match pass_kind {
-                            PassKind::Opaque => LayerShaderOutputKind::Layer,
-                            PassKind::Transparent => LayerShaderOutputKind::LayerDiscard,
-                        },
-                        fragment_shader,
-                        vertices: VertexSource::VertexData { vertices },
-                        texture: tex.as_texture_source(),
-                        transform,
-                        color_multiplier,
-                        fragment_shader_param,
-                    },
-                    DrawPrimitive::TrianglesStrip,
-                ),
-        );
+        sprite
+            .with_blur(self.blur_step)
+            .with_color(props.get_color_multiplier().premultiply())
+            .with_blend_type(props.get_blend_type())
+            .with_fragment_shader(
+                props.get_fragment_shader(),
+                props.get_fragment_shader_param(),
+            )
+            .render(pass, builder, pass_kind);
 
         true
     }
@@ -293,14 +479,21 @@ impl NewDrawableLayerState {
         stencil_ref: u8,
         pass_kind: PassKind,
     ) {
-        // TODO: implement the indirect drawing stuff
         if !props.is_visible() {
             return;
         }
 
+        // the layer has been rendered to a texture in `pre_render`, only need to composite it
+        if self.try_finish_indirect_render(props, pass, transform, stencil_ref, pass_kind) {
+            return;
+        }
+
         let self_transform = props.get_composed_transform_params(transform);
 
-        let drawable = props.get_drawable_params();
+        let drawable = DrawableParams {
+            pixel_snap: self.pixel_snap,
+            ..props.get_drawable_params()
+        };
         let clip = props.get_clip_params();
 
         delegate.render_drawable_direct(
@@ -333,15 +526,25 @@ impl<T: NewDrawableLayer> NewDrawableLayerWrapper<T> {
         }
     }
 
+    /// Round the vertex positions of the layer to the pixel grid, see [`DrawableParams::pixel_snap`]
+    pub fn with_pixel_snap(mut self, enabled: bool) -> Self {
+        self.state.set_pixel_snap(enabled);
+        self
+    }
+
     pub fn inner_ref(&self) -> &T {
         &self.inner_layer
     }
+
+    pub fn inner_mut(&mut self) -> &mut T {
+        &mut self.inner_layer
+    }
 }
 
 impl<T: AdvUpdatable> AdvUpdatable for NewDrawableLayerWrapper<T> {
     fn update(&mut self, context: &AdvUpdateContext) {
         self.inner_layer.update(context);
-        self.state.update(context);
+        self.state.update(context, &self.props);
         self.props.update(context);
     }
 }
@@ -388,3 +591,61 @@ impl<T: NewDrawableLayer + AdvUpdatable + NewDrawableLayerFastForward> DrawableL
         &mut self.props
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use shin_core::vm::command::types::LayerProperty;
+
+    use super::EffectValues;
+    use crate::layer::LayerProperties;
+
+    fn props_with(values: &[(LayerProperty, f32)]) -> LayerProperties {
+        let mut props = LayerProperties::new();
+        for &(property, value) in values {
+            props.property_tweener_mut(property).fast_forward_to(value);
+        }
+        props
+    }
+
+    #[test]
+    fn reduced_motion_drops_small_ripple() {
+        let props = props_with(&[(LayerProperty::RippleAmplitude, 10.0)]);
+
+        assert!(!EffectValues::new(&props, false).is_noop());
+        // the layer doesn't need the intermediate textures anymore
+        assert!(EffectValues::new(&props, true).is_noop());
+    }
+
+    #[test]
+    fn reduced_motion_dampens_large_displacement() {
+        let props = props_with(&[
+            (LayerProperty::RippleAmplitude, 100.0),
+            (LayerProperty::RasterHorizontalAmplitude, -40.0),
+            (LayerProperty::RasterVerticalAmplitude, 8.0),
+        ]);
+        let effects = EffectValues::new(&props, true);
+
+        assert_eq!(effects.ripple_amplitude, 25.0);
+        assert_eq!(effects.raster_horizontal_amplitude, -10.0);
+        assert_eq!(effects.raster_vertical_amplitude, 0.0);
+        assert!(effects.has_raster());
+    }
+
+    #[test]
+    fn reduced_motion_keeps_dissolve() {
+        let props = props_with(&[
+            (LayerProperty::GhostingAlpha, 500.0),
+            (LayerProperty::DissolveIntensity, 300.0),
+        ]);
+
+        let full = EffectValues::new(&props, false);
+        let reduced = EffectValues::new(&props, true);
+        assert!(full.ghosting_alpha > 0.0);
+        assert_eq!(reduced.ghosting_alpha, 0.0);
+
+        // the scenario relies on the dissolve to make the characters vanish
+        assert!(reduced.dissolve_intensity > 0.0);
+        assert_eq!(reduced.dissolve_intensity, full.dissolve_intensity);
+        assert!(!reduced.is_noop());
+    }
+}
@@ -35,6 +35,10 @@ pub struct LayerProperties {
     wobbler_rotation: Wobbler,
     wobbler_scale_x: Wobbler,
     wobbler_scale_y: Wobbler,
+    /// Takes the place of the [`LayerProperty::RenderPosition`] when sorting the layers, if set
+    ///
+    /// Unlike the properties, it is not part of the VM state, the scenario sets it with [`call_id::SET_RENDER_ORDER`](crate::adv::syscall::call_id::SET_RENDER_ORDER).
+    render_order: Option<i32>,
 }
 
 impl LayerProperties {
@@ -49,6 +53,7 @@ impl LayerProperties {
             wobbler_rotation: Wobbler::new(),
             wobbler_scale_x: Wobbler::new(),
             wobbler_scale_y: Wobbler::new(),
+            render_order: None,
         }
     }
 
@@ -68,6 +73,7 @@ impl LayerProperties {
         for (prop, val) in initial_values() {
             self.properties[prop].fast_forward_to(val as f32);
         }
+        self.render_order = None;
     }
 
     pub fn fast_forward(&mut self) {
@@ -76,6 +82,29 @@ impl LayerProperties {
         }
     }
 
+    /// Stops the animations of all properties, leaving them at their current values.
+    // TODO: use it for the code-driven scenes (title screen and menus)
+    #[allow(unused)]
+    pub fn cancel_animations(&mut self) {
+        for tweener in self.properties.values_mut() {
+            tweener.fast_forward_to(tweener.value());
+        }
+    }
+
+    /// Advances all the property animations (but not the wobblers)
+    pub(super) fn update_tweeners(&mut self, dt: Ticks) {
+        for tweener in self.properties.values_mut() {
+            tweener.update(dt);
+        }
+    }
+
+    /// Jump to the target values stored in the [`LayerPropertiesState`], dropping any running animations.
+    pub fn restore_from_state(&mut self, state: &LayerPropertiesState) {
+        for (prop, &val) in &state.properties {
+            self.properties[prop].fast_forward_to(val as f32);
+        }
+    }
+
     pub fn get_layer_id(&self) -> LayerId {
         self.layer_id
     }
@@ -84,6 +113,21 @@ impl LayerProperties {
         self.layer_id = layer_id;
     }
 
+    /// Draw the layer at an explicit position in its group, instead of the one from [`LayerProperty::RenderPosition`]
+    ///
+    /// The order uses the same scale: the layers with higher values are drawn behind the ones with lower values. `None` goes back to the property.
+    pub fn set_render_order(&mut self, render_order: Option<i32>) {
+        self.render_order = render_order;
+    }
+
+    /// The position used to sort the layers of a group, see [`Self::set_render_order`]
+    pub fn get_render_order_position(&self) -> f32 {
+        self.render_order.map_or_else(
+            || self.get_value(LayerProperty::RenderPosition),
+            |render_order| render_order as f32,
+        )
+    }
+
     pub fn set_layerload_counter1(&mut self, layer_load_counter1: u32) {
         self.layer_load_counter1 = layer_load_counter1;
     }
@@ -219,6 +263,7 @@ impl LayerProperties {
             blend_type,
             fragment_shader,
             shader_param,
+            pixel_snap: false,
         }
     }
 
@@ -425,9 +470,7 @@ impl AdvUpdatable for LayerProperties {
         let dt = context.delta_ticks;
 
         if context.are_animations_allowed {
-            for property in self.properties.values_mut() {
-                property.update(dt);
-            }
+            self.update_tweeners(dt);
         }
 
         macro_rules! get {
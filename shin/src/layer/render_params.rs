@@ -77,6 +77,10 @@ pub struct DrawableParams {
     pub blend_type: LayerBlendType,
     pub fragment_shader: LayerFragmentShader,
     pub shader_param: Vec4,
+    /// Round the vertex positions to the pixel grid, for the UI layers that should stay crisp.
+    ///
+    /// This is a per-layer setting, not a property controlled by the scenario. It is off for the layers that move smoothly.
+    pub pixel_snap: bool,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq)]
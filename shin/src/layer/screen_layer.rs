@@ -1,5 +1,9 @@
+use glam::{Mat4, Vec2};
 use replace_with::replace_with;
-use shin_core::primitives::color::{FloatColor4, UnormColor};
+use shin_core::{
+    primitives::color::{FloatColor4, UnormColor},
+    time::Ticks,
+};
 use shin_render::{
     PassKind, RenderRequestBuilder,
     render_pass::RenderPass,
@@ -19,6 +23,7 @@ use crate::{
         properties::LayerProperties,
         render_layer,
         render_params::{DrawableClipMode, DrawableClipParams, DrawableParams, TransformParams},
+        screen_shake::ScreenShake,
     },
     render::{PreRenderContext, render_texture_holder::RenderTextureHolder},
     update::{AdvUpdatable, AdvUpdateContext},
@@ -244,6 +249,7 @@ pub struct ScreenLayer {
     #[render_clone(needs_render)]
     new_drawable_state: NewDrawableLayerState,
     props: LayerProperties,
+    shake: ScreenShake,
 }
 
 impl ScreenLayer {
@@ -257,6 +263,7 @@ impl ScreenLayer {
             plane_count,
             new_drawable_state: NewDrawableLayerState::new(),
             props: LayerProperties::new(),
+            shake: ScreenShake::new(),
         }
     }
 
@@ -303,6 +310,34 @@ impl ScreenLayer {
     pub fn is_transition_active(&self) -> bool {
         self.active_layer.is_transition_active()
     }
+
+    /// Drop the pending page, any running transition and all the page contents, starting with an empty page.
+    pub fn reset(&mut self) {
+        self.active_layer = TransitionLayer::new(None, PageLayer::new(self.plane_count), None);
+        self.pending_layer = None;
+    }
+
+    /// Shake the whole screen, with the `amplitude` (in pixels) decaying to zero over the `duration`.
+    pub fn start_shake(&mut self, amplitude: Vec2, frequency: f32, duration: Ticks) {
+        self.shake.start(amplitude, frequency, duration);
+    }
+
+    #[cfg(test)]
+    pub fn is_shaking(&self) -> bool {
+        self.shake.is_active()
+    }
+}
+
+/// Composes the layer transform, with the screen shake applied on top of it.
+fn get_shaken_transform_params(
+    props: &LayerProperties,
+    shake: &ScreenShake,
+    transform: &TransformParams,
+) -> TransformParams {
+    let mut self_transform = props.get_composed_transform_params(transform);
+    self_transform.transform =
+        Mat4::from_translation(shake.offset().extend(0.0)) * self_transform.transform;
+    self_transform
 }
 
 struct ScreenLayerNewDrawableDelegate<'a> {
@@ -367,7 +402,8 @@ impl NewDrawableLayer for ScreenLayerNewDrawableDelegate<'_> {
 impl AdvUpdatable for ScreenLayer {
     fn update(&mut self, context: &AdvUpdateContext) {
         self.props.update(context);
-        self.new_drawable_state.update(context);
+        self.new_drawable_state.update(context, &self.props);
+        self.shake.update(context);
 
         self.active_layer.update(context);
         if let Some(pending_layer) = &mut self.pending_layer {
@@ -379,6 +415,7 @@ impl AdvUpdatable for ScreenLayer {
 impl Layer for ScreenLayer {
     fn fast_forward(&mut self) {
         self.props.fast_forward();
+        self.shake.stop();
         self.active_layer.fast_forward();
         if let Some(target_layer) = &mut self.pending_layer {
             target_layer.fast_forward();
@@ -392,7 +429,7 @@ impl Layer for ScreenLayer {
     fn pre_render(&mut self, context: &mut PreRenderContext, transform: &TransformParams) {
         let props = &self.props;
 
-        let self_transform = props.get_composed_transform_params(transform);
+        let self_transform = get_shaken_transform_params(props, &self.shake, transform);
 
         self.active_layer.pre_render(context, &self_transform);
 
@@ -427,7 +464,7 @@ impl Layer for ScreenLayer {
             return;
         }
 
-        let self_transform = props.get_composed_transform_params(transform);
+        let self_transform = get_shaken_transform_params(props, &self.shake, transform);
         self.active_layer
             .render(pass, &self_transform, stencil_ref, pass_kind);
     }
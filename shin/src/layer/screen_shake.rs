@@ -0,0 +1,155 @@
+use std::f32::consts::TAU;
+
+use glam::{Vec2, vec2};
+use shin_core::time::Ticks;
+
+use crate::update::{AdvUpdatable, AdvUpdateContext};
+
+/// A shake of the whole screen, decaying to nothing over its duration.
+///
+/// Applied as an offset on top of the screen layer transform, so it doesn't touch any of the layer properties.
+/// Not present in the original engine, where the scenario has to shake the layers with `LAYERCTRL`.
+/// Here it is started with the engine's own `SCREEN_SHAKE` syscall.
+#[derive(Debug, Clone)]
+pub struct ScreenShake {
+    /// Initial amplitude, in pixels
+    amplitude: Vec2,
+    /// Oscillations per second
+    frequency: f32,
+    duration: Ticks,
+    elapsed: Ticks,
+    reduced_motion: bool,
+}
+
+impl ScreenShake {
+    pub fn new() -> Self {
+        Self {
+            amplitude: Vec2::ZERO,
+            frequency: 0.0,
+            duration: Ticks::ZERO,
+            elapsed: Ticks::ZERO,
+            reduced_motion: false,
+        }
+    }
+
+    /// Start shaking, replacing the shake that might be running already.
+    pub fn start(&mut self, amplitude: Vec2, frequency: f32, duration: Ticks) {
+        self.amplitude = amplitude;
+        self.frequency = frequency;
+        self.duration = duration;
+        self.elapsed = Ticks::ZERO;
+    }
+
+    pub fn stop(&mut self) {
+        self.elapsed = self.duration;
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.elapsed < self.duration
+    }
+
+    fn advance(&mut self, delta_time: Ticks) {
+        if self.is_active() {
+            self.elapsed = (self.elapsed + delta_time).min(self.duration);
+        }
+    }
+
+    /// The current offset of the screen, in pixels.
+    ///
+    /// Exactly zero when the shake is not running, or in the reduced motion mode.
+    pub fn offset(&self) -> Vec2 {
+        if !self.is_active() || self.reduced_motion {
+            return Vec2::ZERO;
+        }
+
+        let decay = 1.0 - self.elapsed / self.duration;
+        let phase = self.elapsed.as_seconds() * self.frequency * TAU;
+
+        // shake the axes out of sync, so that the screen doesn't just move along a diagonal
+        self.amplitude * vec2(phase.sin(), (phase * 1.5).cos()) * decay * decay
+    }
+}
+
+impl AdvUpdatable for ScreenShake {
+    fn update(&mut self, context: &AdvUpdateContext) {
+        self.reduced_motion = context.reduced_motion;
+        if context.are_animations_allowed {
+            self.advance(context.delta_ticks);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{Vec2, vec2};
+    use shin_core::time::Ticks;
+
+    use super::ScreenShake;
+
+    const FRAME: Ticks = Ticks::from_u32(1);
+
+    fn shake() -> ScreenShake {
+        let mut shake = ScreenShake::new();
+        shake.start(vec2(20.0, 10.0), 8.0, Ticks::from_seconds(1.0));
+        shake
+    }
+
+    #[test]
+    fn settles_to_zero() {
+        let mut shake = shake();
+
+        let mut max_offset = Vec2::ZERO;
+        for _ in 0..60 {
+            assert!(shake.is_active());
+            shake.advance(FRAME);
+            max_offset = max_offset.max(shake.offset().abs());
+        }
+        assert!(max_offset.x > 0.0 && max_offset.x <= 20.0);
+        assert!(max_offset.y > 0.0 && max_offset.y <= 10.0);
+
+        // the duration is over
+        assert!(!shake.is_active());
+        assert_eq!(shake.offset(), Vec2::ZERO);
+
+        // and it stays that way
+        shake.advance(FRAME);
+        assert_eq!(shake.offset(), Vec2::ZERO);
+    }
+
+    #[test]
+    fn decays() {
+        let mut shake = shake();
+
+        // the peak amplitude keeps going down over each half of the duration
+        let mut peak = |shake: &mut ScreenShake| {
+            (0..30).fold(0.0f32, |peak, _| {
+                shake.advance(FRAME);
+                peak.max(shake.offset().length())
+            })
+        };
+        let first_half = peak(&mut shake);
+        let second_half = peak(&mut shake);
+        assert!(second_half < first_half);
+    }
+
+    #[test]
+    fn stop() {
+        let mut shake = shake();
+        shake.advance(FRAME);
+        assert_ne!(shake.offset(), Vec2::ZERO);
+
+        shake.stop();
+        assert_eq!(shake.offset(), Vec2::ZERO);
+    }
+
+    #[test]
+    fn reduced_motion() {
+        let mut shake = shake();
+        shake.reduced_motion = true;
+
+        for _ in 0..60 {
+            shake.advance(FRAME);
+            assert_eq!(shake.offset(), Vec2::ZERO);
+        }
+    }
+}
@@ -0,0 +1,364 @@
+use std::{fmt::Debug, sync::Arc};
+
+use glam::{Vec4, vec4};
+use shin_core::{
+    format::{
+        animation::AnimationFrame,
+        scenario::instruction_elements::{
+            TypedNumberArray, UntypedNumberArray, lower_number_array,
+        },
+        sprite_sheet::SpriteRect,
+    },
+    time::Ticks,
+};
+use shin_render::{
+    PassKind, RenderRequestBuilder, render_pass::RenderPass, shaders::types::RenderClone,
+};
+
+use crate::{
+    asset::{animation::Animation, sprite_sheet::sprite_texture_region},
+    layer::{
+        NewDrawableLayer, NewDrawableLayerWrapper,
+        new_drawable_layer::{NewDrawableLayerFastForward, NewDrawableLayerNeedsSeparatePass},
+        render_params::{DrawableClipMode, DrawableClipParams, DrawableParams, TransformParams},
+    },
+    render::sprite::Sprite,
+    update::{AdvUpdatable, AdvUpdateContext},
+};
+
+/// Parameters of the animation playback, passed to `LAYERLOAD` after the animation id.
+///
+/// The parameters of the original animation layer are not known, so the layout is our own: `(animation id, rate, flags)`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AnimationArgs {
+    /// Playback speed multiplier, 1.0 being the speed the animation was authored at
+    pub rate: f32,
+    /// Start over after the last frame, instead of stopping on it
+    pub looping: bool,
+}
+
+impl AnimationArgs {
+    /// Returns the animation id and the playback parameters
+    pub fn from_number_array(params: UntypedNumberArray) -> (i32, Self) {
+        let (animation_id, rate, flags, ..): TypedNumberArray = lower_number_array(params);
+
+        // the rate is in thousandths, like the layer properties. zero means the normal speed
+        let rate = if rate > 0 { rate as f32 / 1000.0 } else { 1.0 };
+
+        (animation_id, Self {
+            rate,
+            looping: flags & 1 != 0,
+        })
+    }
+}
+
+/// Keeps track of the frame being shown
+#[derive(Debug, Clone)]
+struct AnimationPlayback {
+    durations: Vec<Ticks>,
+    args: AnimationArgs,
+    frame: usize,
+    /// Time since the current frame was shown
+    frame_time: Ticks,
+    finished: bool,
+}
+
+impl AnimationPlayback {
+    fn new(frames: &[AnimationFrame], args: AnimationArgs) -> Self {
+        Self {
+            durations: frames
+                .iter()
+                .map(|frame| Ticks::from_u32(frame.duration))
+                .collect(),
+            args,
+            frame: 0,
+            frame_time: Ticks::ZERO,
+            finished: false,
+        }
+    }
+
+    fn advance(&mut self, delta: Ticks) {
+        if self.finished {
+            return;
+        }
+
+        self.frame_time += Ticks::from_f32(delta.as_f32() * self.args.rate);
+
+        // a long frame time can skip several frames at once
+        while self.frame_time >= self.durations[self.frame] {
+            if self.frame + 1 == self.durations.len() && !self.args.looping {
+                self.finished = true;
+                self.frame_time = self.durations[self.frame];
+                return;
+            }
+
+            self.frame_time -= self.durations[self.frame];
+            self.frame = (self.frame + 1) % self.durations.len();
+        }
+    }
+
+    /// Jump to the end of a one-shot animation. The looping animations have no end, so they are left as is.
+    fn fast_forward(&mut self) {
+        if self.args.looping {
+            return;
+        }
+
+        self.frame = self.durations.len() - 1;
+        self.frame_time = self.durations[self.frame];
+        self.finished = true;
+    }
+
+    fn frame_index(&self) -> usize {
+        self.frame
+    }
+
+    /// Whether a one-shot animation has shown its last frame for its whole duration. A looping animation never finishes.
+    fn is_finished(&self) -> bool {
+        self.finished
+    }
+}
+
+/// Plays an [`Animation`], showing its frames one after another
+#[derive(Clone, RenderClone)]
+pub struct AnimationLayerImpl {
+    animation: Arc<Animation>,
+    playback: AnimationPlayback,
+}
+
+impl AnimationLayerImpl {
+    pub fn new(animation: Arc<Animation>, args: AnimationArgs) -> Self {
+        let playback = AnimationPlayback::new(animation.frames(), args);
+
+        Self {
+            animation,
+            playback,
+        }
+    }
+
+    /// Whether a one-shot animation is still playing, which `LAYERWAIT` waits for
+    pub fn is_playing_once(&self) -> bool {
+        !self.playback.args.looping && !self.playback.is_finished()
+    }
+
+    fn current_frame(&self) -> &AnimationFrame {
+        &self.animation.frames()[self.playback.frame_index()]
+    }
+}
+
+pub type AnimationLayer = NewDrawableLayerWrapper<AnimationLayerImpl>;
+
+impl AnimationLayer {
+    pub fn new(animation: Arc<Animation>, args: AnimationArgs) -> Self {
+        Self::from_inner(AnimationLayerImpl::new(animation, args))
+    }
+}
+
+/// The quad (x, y, width, height) covered by a frame, centered on its offset from the layer origin
+fn frame_quad(frame: &AnimationFrame, rect: SpriteRect) -> Vec4 {
+    let width = rect.width as f32;
+    let height = rect.height as f32;
+
+    vec4(
+        frame.offset_x as f32 - width / 2.0,
+        frame.offset_y as f32 - height / 2.0,
+        width,
+        height,
+    )
+}
+
+impl NewDrawableLayerNeedsSeparatePass for AnimationLayerImpl {}
+
+impl NewDrawableLayer for AnimationLayerImpl {
+    #[tracing::instrument(skip_all)]
+    fn render_drawable_direct(
+        &self,
+        pass: &mut RenderPass,
+        transform: &TransformParams,
+        &DrawableParams {
+            color_multiplier,
+            blend_type,
+            fragment_shader,
+            shader_param,
+            pixel_snap,
+        }: &DrawableParams,
+        clip: &DrawableClipParams,
+        stencil_ref: u8,
+        pass_kind: PassKind,
+    ) {
+        // like the sprite layer, the frames are always blended
+        if pass_kind != PassKind::Transparent || color_multiplier.a <= 0.0 {
+            return;
+        }
+
+        assert_eq!(
+            clip.mode,
+            DrawableClipMode::None,
+            "Clipping effect is not implemented"
+        );
+
+        let frame = self.current_frame();
+        let sheet = self.animation.sheet();
+        let rect = sheet
+            .get_sprite(&frame.sprite)
+            .expect("BUG: animation frame is not in the sprite sheet");
+        let texture = sheet.texture();
+
+        pass.push_debug("AnimationLayer");
+
+        let mut sprite = Sprite::new(
+            texture.as_source(),
+            frame_quad(frame, rect),
+            transform.compute_final_transform(),
+        );
+        if pixel_snap {
+            sprite = sprite.with_pixel_snap(pass.pixel_size());
+        }
+
+        sprite
+            .with_texture_region(sprite_texture_region(rect, texture.size_vec(), false))
+            .with_color(color_multiplier.premultiply())
+            .with_blend_type(blend_type)
+            .with_fragment_shader(fragment_shader.simplify(shader_param), shader_param)
+            .render(
+                pass,
+                RenderRequestBuilder::new().depth_stencil_shorthand(stencil_ref, false, false),
+                pass_kind,
+            );
+
+        pass.pop_debug();
+    }
+}
+
+impl NewDrawableLayerFastForward for AnimationLayerImpl {
+    fn fast_forward(&mut self) {
+        self.playback.fast_forward();
+    }
+}
+
+impl AdvUpdatable for AnimationLayerImpl {
+    fn update(&mut self, context: &AdvUpdateContext) {
+        self.playback.advance(context.delta_ticks);
+    }
+}
+
+impl Debug for AnimationLayerImpl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("AnimationLayer")
+            .field(&self.playback.frame_index())
+            .field(&self.playback.args)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shin_core::{format::animation::AnimationFrame, time::Ticks};
+
+    use super::{AnimationArgs, AnimationPlayback};
+
+    /// Frames shown for 4, 2 and 6 ticks
+    fn frames() -> Vec<AnimationFrame> {
+        [("a", 4), ("b", 2), ("c", 6)]
+            .into_iter()
+            .map(|(sprite, duration)| AnimationFrame {
+                sprite: sprite.to_string(),
+                duration,
+                offset_x: 0,
+                offset_y: 0,
+            })
+            .collect()
+    }
+
+    fn playback(rate: f32, looping: bool) -> AnimationPlayback {
+        AnimationPlayback::new(&frames(), AnimationArgs { rate, looping })
+    }
+
+    /// Steps the clock by a tick at a time, returning the frame shown after every step
+    fn step(playback: &mut AnimationPlayback, ticks: u32) -> Vec<usize> {
+        (0..ticks)
+            .map(|_| {
+                playback.advance(Ticks::from_u32(1));
+                playback.frame_index()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn decode_args() {
+        assert_eq!(
+            AnimationArgs::from_number_array((12, 2000, 1, 0, 0, 0, 0, 0)),
+            (12, AnimationArgs {
+                rate: 2.0,
+                looping: true
+            })
+        );
+        assert_eq!(
+            AnimationArgs::from_number_array((3, 0, 0, 0, 0, 0, 0, 0)),
+            (3, AnimationArgs {
+                rate: 1.0,
+                looping: false
+            })
+        );
+    }
+
+    #[test]
+    fn one_shot_stops_on_last_frame() {
+        let mut playback = playback(1.0, false);
+        assert_eq!(playback.frame_index(), 0);
+
+        assert_eq!(step(&mut playback, 12), [
+            0, 0, 0, 1, 1, 2, 2, 2, 2, 2, 2, 2
+        ]);
+        assert!(playback.is_finished());
+
+        // and stays there
+        assert_eq!(step(&mut playback, 5), [2; 5]);
+    }
+
+    #[test]
+    fn looping_starts_over() {
+        let mut playback = playback(1.0, true);
+
+        assert_eq!(step(&mut playback, 14), [
+            0, 0, 0, 1, 1, 2, 2, 2, 2, 2, 2, 0, 0, 0
+        ]);
+        assert!(!playback.is_finished());
+    }
+
+    #[test]
+    fn rate_scales_time() {
+        let mut playback = playback(2.0, false);
+
+        assert_eq!(step(&mut playback, 6), [0, 1, 2, 2, 2, 2]);
+        assert!(playback.is_finished());
+    }
+
+    #[test]
+    fn large_step_skips_frames() {
+        let mut playback = playback(1.0, true);
+
+        // 4 + 2 + 6 + 4 + 1
+        playback.advance(Ticks::from_u32(17));
+        assert_eq!(playback.frame_index(), 1);
+
+        let mut playback = self::playback(1.0, false);
+        playback.advance(Ticks::from_u32(100));
+        assert_eq!(playback.frame_index(), 2);
+        assert!(playback.is_finished());
+    }
+
+    #[test]
+    fn fast_forward() {
+        let mut playback = playback(1.0, false);
+        playback.fast_forward();
+        assert_eq!(playback.frame_index(), 2);
+        assert!(playback.is_finished());
+
+        // a looping animation has no end to skip to
+        let mut playback = self::playback(1.0, true);
+        step(&mut playback, 5);
+        playback.fast_forward();
+        assert_eq!(playback.frame_index(), 1);
+        assert!(!playback.is_finished());
+    }
+}
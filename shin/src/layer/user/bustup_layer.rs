@@ -1,6 +1,7 @@
 use std::{fmt::Debug, sync::Arc};
 
 use glam::{Mat4, Vec3, vec3};
+use shin_core::time::Ticks;
 use shin_render::{
     PassKind, RenderRequestBuilder,
     render_pass::RenderPass,
@@ -11,32 +12,303 @@ use shin_render::{
 };
 
 use crate::{
-    asset::bustup::Bustup,
+    asset::bustup::{Bustup, BustupExpression, CharacterId},
     layer::{
         LayerProperties, NewDrawableLayer, NewDrawableLayerWrapper,
         new_drawable_layer::{NewDrawableLayerFastForward, NewDrawableLayerNeedsSeparatePass},
         render_params::{DrawableClipMode, DrawableClipParams, DrawableParams, TransformParams},
         user::picture_layer::{PictureBlockParams, PictureBlockPassKind},
+        wobbler::prng::prng,
     },
     render::PreRenderContext,
     update::{AdvUpdatable, AdvUpdateContext, Updatable, UpdateContext},
 };
 
+/// Amplitude of the voice at which the mouth is shown wide open
+const LIPSYNC_FULL_AMPLITUDE: f32 = 0.25;
+
+/// Pick the mouth frame for the given openness in `[0; 1]`, the frames going from the closed mouth to the widest open one
+fn mouth_frame_for_openness(openness: f32, frame_count: usize) -> usize {
+    if frame_count == 0 {
+        return 0;
+    }
+
+    (openness.clamp(0.0, 1.0) * (frame_count - 1) as f32).round() as usize
+}
+
+/// Pick the mouth frame matching the loudness of the voice, the frames going from the closed mouth to the widest open one
+fn mouth_frame_for_amplitude(amplitude: f32, frame_count: usize) -> usize {
+    mouth_frame_for_openness(amplitude / LIPSYNC_FULL_AMPLITUDE, frame_count)
+}
+
+/// The shape of the mouth pronouncing a vowel
+///
+/// The bustups drawing the vowels have a mouth frame for each of them, in the order of the variants.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MouthShape {
+    Closed = 0,
+    A = 1,
+    I = 2,
+    U = 3,
+    E = 4,
+    O = 5,
+}
+
+/// Number of the mouth frames of the expressions having a frame for each [`MouthShape`]
+const MOUTH_SHAPE_FRAME_COUNT: usize = 6;
+
+/// The shapes guessed from the amplitude of the voice, as the upper bounds of the relative amplitude bands
+///
+/// The voices don't come with the phonemes, so this is only an approximation of our own (the louder the voice, the wider open the vowel), not something taken from the original engine.
+const MOUTH_SHAPE_BANDS: [(f32, MouthShape); 5] = [
+    (0.1, MouthShape::Closed),
+    (0.3, MouthShape::U),
+    (0.5, MouthShape::I),
+    (0.7, MouthShape::E),
+    (0.9, MouthShape::O),
+];
+
+impl MouthShape {
+    pub fn for_amplitude(amplitude: f32) -> Self {
+        let relative = amplitude / LIPSYNC_FULL_AMPLITUDE;
+
+        MOUTH_SHAPE_BANDS
+            .iter()
+            .find(|&&(upper_bound, _)| relative < upper_bound)
+            .map_or(MouthShape::A, |&(_, shape)| shape)
+    }
+
+    /// How wide open the mouth is, in `[0; 1]`
+    fn openness(self) -> f32 {
+        match self {
+            MouthShape::Closed => 0.0,
+            MouthShape::U => 0.25,
+            MouthShape::I => 0.4,
+            MouthShape::E => 0.6,
+            MouthShape::O => 0.8,
+            MouthShape::A => 1.0,
+        }
+    }
+
+    /// The mouth frame showing the shape, falling back to the frame with the same openness for the expressions without the vowels
+    fn mouth_frame(self, frame_count: usize) -> usize {
+        if frame_count >= MOUTH_SHAPE_FRAME_COUNT {
+            self as usize
+        } else {
+            mouth_frame_for_openness(self.openness(), frame_count)
+        }
+    }
+}
+
+/// Pick the mouth frame for lipsync, using the vowel shapes if the expression has them and the plain mouth openness otherwise
+fn lipsync_mouth_frame(amplitude: f32, frame_count: usize) -> usize {
+    if frame_count >= MOUTH_SHAPE_FRAME_COUNT {
+        MouthShape::for_amplitude(amplitude).mouth_frame(frame_count)
+    } else {
+        mouth_frame_for_amplitude(amplitude, frame_count)
+    }
+}
+
+/// How often and how long the bustup blinks
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BlinkParams {
+    /// Mean time the eyes stay open between two blinks
+    pub interval: Ticks,
+    /// How much the time between the blinks varies, as a fraction of the `interval`
+    pub jitter: f32,
+    /// Time the eyes stay closed for a blink
+    pub duration: Ticks,
+}
+
+impl Default for BlinkParams {
+    fn default() -> Self {
+        Self {
+            interval: Ticks::from_seconds(4.0),
+            jitter: 0.5,
+            duration: Ticks::from_u32(6),
+        }
+    }
+}
+
+/// Closes the eyes for a moment at random intervals
+///
+/// The intervals are drawn from the seeded PRNG by the index of the blink, so the same seed gives the same blinks when replaying.
+#[derive(Debug, Clone)]
+struct Blinker {
+    params: BlinkParams,
+    seed: i32,
+    blink_index: i32,
+    /// Time since the eyes opened after the previous blink
+    elapsed: Ticks,
+    closed: bool,
+}
+
+impl Blinker {
+    fn new(params: BlinkParams, seed: i32) -> Self {
+        Self {
+            params,
+            seed,
+            blink_index: 0,
+            elapsed: Ticks::ZERO,
+            closed: false,
+        }
+    }
+
+    /// Time the eyes stay open before the current blink
+    fn open_time(&self) -> Ticks {
+        let jitter = self.params.jitter * prng(0.0, self.blink_index + 1, self.seed);
+        // never let a cycle be empty, so the update always terminates
+        Ticks::from_f32((self.params.interval.as_f32() * (1.0 + jitter)).max(1.0))
+    }
+
+    /// Advance the time, closing the eyes only if blinking is `enabled`
+    ///
+    /// The blinks coming while disabled are skipped, keeping the schedule of the following ones.
+    fn update(&mut self, delta_time: Ticks, enabled: bool) {
+        self.elapsed += delta_time;
+
+        loop {
+            let open_time = self.open_time();
+            if self.elapsed < open_time {
+                self.closed = false;
+                return;
+            }
+            if self.elapsed < open_time + self.params.duration {
+                self.closed = enabled;
+                return;
+            }
+
+            self.elapsed -= open_time + self.params.duration;
+            self.blink_index += 1;
+        }
+    }
+
+    fn is_closed(&self) -> bool {
+        self.closed
+    }
+}
+
+/// The blocks composited to draw a bustup, in drawing order, along with a label for debugging
+///
+/// A missing expression only leaves the base.
+fn composited_parts<'b, Block>(
+    base_blocks: &'b [Block],
+    expression: Option<&'b BustupExpression<Block>>,
+    mouth_frame: usize,
+    eyes_frame: usize,
+) -> Vec<(&'static str, &'b Block)> {
+    let mut parts = base_blocks
+        .iter()
+        .map(|block| ("Base", block))
+        .collect::<Vec<_>>();
+
+    if let Some(expression) = expression {
+        parts.extend(expression.face1.iter().map(|block| ("Face1", block)));
+        parts.extend(expression.face2.iter().map(|block| ("Face2", block)));
+        parts.extend(
+            expression
+                .mouth_blocks
+                .get(mouth_frame)
+                .map(|block| ("Mouth", block)),
+        );
+        parts.extend(
+            expression
+                .eye_blocks
+                .get(eyes_frame)
+                .map(|block| ("Eyes", block)),
+        );
+    }
+
+    parts
+}
+
 #[derive(Clone, RenderClone)]
 pub struct BustupLayerImpl {
     bustup: Arc<Bustup>,
     label: String,
+    /// The character whose voices move the lips of this bustup
+    character_id: CharacterId,
+    expression_name: String,
+    /// `None` when the expression failed to load, showing only the base
+    expression: Option<Arc<BustupExpression>>,
     mouth_state: u32,
-    eyes_state: u32,
+    blinker: Blinker,
 }
 
 impl BustupLayerImpl {
-    pub fn new(bustup: Arc<Bustup>, bustup_name: Option<String>) -> Self {
+    pub fn new(
+        bustup: Arc<Bustup>,
+        bustup_name: Option<String>,
+        character_id: CharacterId,
+        expression_name: &str,
+        expression: Option<Arc<BustupExpression>>,
+    ) -> Self {
         Self {
             bustup,
             label: bustup_name.unwrap_or_else(|| "unnamed".to_string()),
+            character_id,
+            expression_name: expression_name.to_string(),
+            expression,
             mouth_state: 0,
-            eyes_state: 0,
+            blinker: Blinker::new(BlinkParams::default(), 0),
+        }
+    }
+
+    pub fn bustup(&self) -> &Arc<Bustup> {
+        &self.bustup
+    }
+
+    pub fn expression_name(&self) -> &str {
+        &self.expression_name
+    }
+
+    pub fn expression(&self) -> Option<&Arc<BustupExpression>> {
+        self.expression.as_ref()
+    }
+
+    /// Switch to another expression of the bustup, loaded with [`Bustup::load_expression`], starting with a closed mouth
+    pub fn set_expression(
+        &mut self,
+        expression_name: &str,
+        expression: Option<Arc<BustupExpression>>,
+    ) {
+        self.expression_name = expression_name.to_string();
+        self.expression = expression;
+        self.mouth_state = 0;
+    }
+
+    fn current_expression(&self) -> Option<&BustupExpression> {
+        self.expression.as_deref()
+    }
+
+    fn mouth_frame_count(&self) -> usize {
+        self.current_expression()
+            .map_or(0, |expression| expression.mouth_blocks.len())
+    }
+
+    /// Open the mouth according to the amplitude of the voice being played, for lipsync
+    ///
+    /// The expressions having a frame for each [`MouthShape`] get a vowel guessed from the amplitude, the other ones are just opened wider when the voice is louder.
+    pub fn set_mouth_from_amplitude(&mut self, amplitude: f32) {
+        self.mouth_state = lipsync_mouth_frame(amplitude, self.mouth_frame_count()) as u32;
+    }
+
+    fn eyes_frame_count(&self) -> usize {
+        self.current_expression()
+            .map_or(0, |expression| expression.eye_blocks.len())
+    }
+
+    /// Restart the blinks from the given seed, so that each bustup blinks on its own schedule
+    pub fn set_blink_seed(&mut self, seed: i32) {
+        self.blinker = Blinker::new(self.blinker.params, seed);
+    }
+
+    /// The eyes frame to draw, the last one of the expression being the closed eyes shown when blinking
+    fn displayed_eyes_frame(&self) -> usize {
+        if self.blinker.is_closed() {
+            self.eyes_frame_count().saturating_sub(1)
+        } else {
+            0
         }
     }
 
@@ -68,48 +340,17 @@ impl BustupLayerImpl {
             }
         ));
 
-        pass.push_debug("Base");
-        for block in &self.bustup.base_blocks {
-            super::picture_layer::render_block(block, pass, builder, params, transform);
-        }
-        pass.pop_debug();
-
-        if let Some(block) = &self.bustup.face1 {
-            pass.push_debug("Face1");
+        for (label, block) in composited_parts(
+            &self.bustup.base_blocks,
+            self.current_expression(),
+            self.mouth_state as usize,
+            self.displayed_eyes_frame(),
+        ) {
+            pass.push_debug(label);
             super::picture_layer::render_block(block, pass, builder, params, transform);
             pass.pop_debug();
         }
 
-        if let Some(block) = &self.bustup.face2 {
-            pass.push_debug("Face2");
-            super::picture_layer::render_block(block, pass, builder, params, transform);
-            pass.pop_debug();
-        }
-
-        if !self.bustup.mouth_blocks.is_empty() {
-            pass.push_debug("Mouth");
-            super::picture_layer::render_block(
-                &self.bustup.mouth_blocks[self.mouth_state as usize],
-                pass,
-                builder,
-                params,
-                transform,
-            );
-            pass.pop_debug();
-        }
-
-        if !self.bustup.eye_blocks.is_empty() {
-            pass.push_debug("Eyes");
-            super::picture_layer::render_block(
-                &self.bustup.eye_blocks[self.eyes_state as usize],
-                pass,
-                builder,
-                params,
-                transform,
-            );
-            pass.pop_debug();
-        }
-
         pass.pop_debug();
     }
 }
@@ -117,8 +358,20 @@ impl BustupLayerImpl {
 pub type BustupLayer = NewDrawableLayerWrapper<BustupLayerImpl>;
 
 impl BustupLayer {
-    pub fn new(picture: Arc<Bustup>, picture_name: Option<String>) -> Self {
-        Self::from_inner(BustupLayerImpl::new(picture, picture_name))
+    pub fn new(
+        bustup: Arc<Bustup>,
+        bustup_name: Option<String>,
+        character_id: CharacterId,
+        expression_name: &str,
+        expression: Option<Arc<BustupExpression>>,
+    ) -> Self {
+        Self::from_inner(BustupLayerImpl::new(
+            bustup,
+            bustup_name,
+            character_id,
+            expression_name,
+            expression,
+        ))
     }
 }
 
@@ -170,11 +423,177 @@ impl NewDrawableLayerFastForward for BustupLayerImpl {
 }
 
 impl AdvUpdatable for BustupLayerImpl {
-    fn update(&mut self, _ctx: &AdvUpdateContext) {}
+    fn update(&mut self, ctx: &AdvUpdateContext) {
+        self.set_mouth_from_amplitude(ctx.lipsync.amplitude(self.character_id));
+
+        // an expression with the eyes closed has them drawn in a single frame, so it does not blink
+        let can_blink = self.eyes_frame_count() >= 2;
+        self.blinker.update(ctx.delta_ticks, can_blink);
+    }
 }
 
 impl Debug for BustupLayerImpl {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_tuple("BustupLayer").field(&self.label).finish()
+        f.debug_tuple("BustupLayer")
+            .field(&self.label)
+            .field(&self.expression_name)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indexmap::IndexMap;
+    use shin_core::time::Ticks;
+
+    use super::{
+        BlinkParams, Blinker, MouthShape, composited_parts, lipsync_mouth_frame,
+        mouth_frame_for_amplitude,
+    };
+    use crate::asset::bustup::BustupExpression;
+
+    fn expressions() -> IndexMap<String, BustupExpression<&'static str>> {
+        [
+            ("smile", "smile_face", ["smile_mouth0", "smile_mouth1"]),
+            ("angry", "angry_face", ["angry_mouth0", "angry_mouth1"]),
+        ]
+        .into_iter()
+        .map(|(name, face, mouths)| {
+            let expression = BustupExpression {
+                face1: Some(face),
+                face2: None,
+                mouth_blocks: mouths.to_vec(),
+                eye_blocks: vec!["eyes0", "eyes1"],
+            };
+            (name.to_string(), expression)
+        })
+        .collect()
+    }
+
+    fn parts(expression: &str, mouth_frame: usize) -> Vec<&'static str> {
+        let expressions = expressions();
+        composited_parts(
+            &["body", "hair"],
+            expressions.get(expression),
+            mouth_frame,
+            0,
+        )
+        .into_iter()
+        .map(|(_, &block)| block)
+        .collect()
+    }
+
+    #[test]
+    fn switching_expression_changes_parts() {
+        assert_eq!(
+            parts("smile", 0),
+            ["body", "hair", "smile_face", "smile_mouth0", "eyes0"]
+        );
+        assert_eq!(
+            parts("angry", 0),
+            ["body", "hair", "angry_face", "angry_mouth0", "eyes0"]
+        );
+        // only the base is left without an expression
+        assert_eq!(parts("unknown", 0), ["body", "hair"]);
+    }
+
+    #[test]
+    fn mouth_frame_selects_the_mouth_block() {
+        assert_eq!(parts("smile", 1)[3], "smile_mouth1");
+        // a frame missing in the expression is not drawn
+        assert_eq!(parts("smile", 2).len(), 4);
+    }
+
+    #[test]
+    fn amplitude_opens_the_mouth() {
+        assert_eq!(mouth_frame_for_amplitude(0.0, 3), 0);
+        assert_eq!(mouth_frame_for_amplitude(0.1, 3), 1);
+        assert_eq!(mouth_frame_for_amplitude(0.25, 3), 2);
+        assert_eq!(mouth_frame_for_amplitude(1.0, 3), 2);
+        assert_eq!(mouth_frame_for_amplitude(1.0, 0), 0);
+    }
+
+    #[test]
+    fn amplitude_bands_select_the_mouth_shape() {
+        let shapes = [0.0, 0.05, 0.1, 0.15, 0.2, 0.25].map(MouthShape::for_amplitude);
+        assert_eq!(
+            shapes,
+            [
+                MouthShape::Closed,
+                MouthShape::U,
+                MouthShape::I,
+                MouthShape::E,
+                MouthShape::O,
+                MouthShape::A,
+            ]
+        );
+
+        // an expression with the vowels shows each of them
+        let frames =
+            [0.0, 0.05, 0.1, 0.15, 0.2, 0.25].map(|amplitude| lipsync_mouth_frame(amplitude, 6));
+        assert_eq!(frames, [0, 3, 2, 4, 5, 1]);
+    }
+
+    #[test]
+    fn mouth_shapes_degrade_to_open_and_closed() {
+        // with just the closed and the open mouth, the quiet vowels close the mouth
+        assert_eq!(MouthShape::Closed.mouth_frame(2), 0);
+        assert_eq!(MouthShape::U.mouth_frame(2), 0);
+        assert_eq!(MouthShape::E.mouth_frame(2), 1);
+        assert_eq!(MouthShape::A.mouth_frame(2), 1);
+
+        // and the amplitude opens the mouth like before
+        for amplitude in [0.0, 0.05, 0.1, 0.2, 0.3] {
+            assert_eq!(
+                lipsync_mouth_frame(amplitude, 2),
+                mouth_frame_for_amplitude(amplitude, 2)
+            );
+        }
+    }
+
+    /// Run the blinker for a minute, one tick at a time, returning the ticks at which the eyes closed
+    fn blink_starts(params: BlinkParams, seed: i32, enabled: bool) -> Vec<u32> {
+        let mut blinker = Blinker::new(params, seed);
+        let mut was_closed = false;
+        let mut starts = Vec::new();
+        for tick in 0..3600 {
+            blinker.update(Ticks::from_u32(1), enabled);
+            if blinker.is_closed() && !was_closed {
+                starts.push(tick);
+            }
+            was_closed = blinker.is_closed();
+        }
+        starts
+    }
+
+    #[test]
+    fn blinks_follow_the_interval() {
+        let params = BlinkParams::default();
+        let starts = blink_starts(params, 42, true);
+
+        // a blink every 4 seconds on average
+        assert!((10..=20).contains(&starts.len()), "{starts:?}");
+        for pair in starts.windows(2) {
+            let open_time = (pair[1] - pair[0]) as f32 - params.duration.as_f32();
+            assert!((2.0 * 60.0 - 1.0..=6.0 * 60.0 + 1.0).contains(&open_time));
+        }
+    }
+
+    #[test]
+    fn blinks_are_reproducible() {
+        let params = BlinkParams::default();
+        assert_eq!(
+            blink_starts(params, 42, true),
+            blink_starts(params, 42, true)
+        );
+        assert_ne!(
+            blink_starts(params, 42, true),
+            blink_starts(params, 7, true)
+        );
+    }
+
+    #[test]
+    fn no_blinks_while_disabled() {
+        assert!(blink_starts(BlinkParams::default(), 42, false).is_empty());
     }
 }
@@ -1,3 +1,4 @@
+use anyhow::{Context, Result, bail};
 use derivative::Derivative;
 use from_variants::FromVariants;
 use glam::vec4;
@@ -16,6 +17,7 @@ use tracing::{debug, warn};
 
 use crate::{
     asset::{
+        animation::Animation,
         bustup::{Bustup, BustupArgs, CharacterId},
         movie::Movie,
         picture::Picture,
@@ -29,17 +31,24 @@ use crate::{
     update::{AdvUpdatable, AdvUpdateContext},
 };
 
+mod animation_layer;
 #[expect(unused)]
 mod bustup_layer;
 #[expect(unused)]
 mod movie_layer;
 mod null_layer;
 mod picture_layer;
+mod rain_layer;
 mod tile_layer;
 
 pub use self::{
-    bustup_layer::BustupLayer, movie_layer::MovieLayer, null_layer::NullLayer,
-    picture_layer::PictureLayer, tile_layer::TileLayer,
+    animation_layer::{AnimationArgs, AnimationLayer},
+    bustup_layer::BustupLayer,
+    movie_layer::MovieLayer,
+    null_layer::NullLayer,
+    picture_layer::PictureLayer,
+    rain_layer::{RainLayer, RainParams},
+    tile_layer::TileLayer,
 };
 
 #[derive(Derivative, RenderClone, FromVariants)]
@@ -55,6 +64,10 @@ pub enum UserLayer {
     Tile(#[render_clone(needs_render)] TileLayer),
     #[derivative(Debug = "transparent")]
     Movie(#[render_clone(needs_render)] MovieLayer),
+    #[derivative(Debug = "transparent")]
+    Rain(#[render_clone(needs_render)] RainLayer),
+    #[derivative(Debug = "transparent")]
+    Animation(#[render_clone(needs_render)] AnimationLayer),
 }
 
 impl UserLayer {
@@ -65,10 +78,10 @@ impl UserLayer {
         scenario: &Scenario,
         layer_ty: LayerType,
         params: UntypedNumberArray,
-    ) -> Self {
+    ) -> Result<Self> {
         // TODO: this API is not ideal, as we are blocking the main thread for layer loading
         // ideally we want to mimic the API of LayerLoader in the original game
-        match layer_ty {
+        Ok(match layer_ty {
             LayerType::Null => NullLayer::new().into(),
             LayerType::Tile => {
                 let (color, offset_x, offset_y, width, height, ..): TypedNumberArray =
@@ -91,7 +104,7 @@ impl UserLayer {
                 let pic = asset_server
                     .load::<Picture, _>(pic_info.path())
                     .await
-                    .expect("Failed to load picture");
+                    .context("Failed to load picture")?;
                 PictureLayer::new(pic, Some(name.to_string())).into()
             }
             LayerType::Bustup => {
@@ -105,17 +118,31 @@ impl UserLayer {
                     "Load bustup: {} -> {} {} {}",
                     bup_id, name, emotion, lipsync_character_id
                 );
+                // TODO: do this conversion on info load
+                let character_id = CharacterId::new(*lipsync_character_id as i32);
                 let bup = asset_server
                     .load_with_args::<Bustup, _>(bup_info.path(), BustupArgs {
-                        expression: emotion.to_string(),
-                        // TODO: do this conversion on info load
-                        character_id: CharacterId::new(*lipsync_character_id as i32),
+                        character_id,
                         disable_animations: false,
                     })
                     .await
-                    .expect("Failed to load bustup");
+                    .context("Failed to load bustup")?;
+                let expression = match bup.load_expression(emotion).await {
+                    Ok(expression) => Some(expression),
+                    Err(e) => {
+                        warn!("Failed to load the bustup expression: {:?}", e);
+                        None
+                    }
+                };
 
-                BustupLayer::new(bup, Some(name.to_string())).into()
+                BustupLayer::new(
+                    bup,
+                    Some(name.to_string()),
+                    character_id,
+                    emotion,
+                    expression,
+                )
+                .into()
             }
             LayerType::Movie => {
                 let (movie_id, volume, repeat, ..): TypedNumberArray<MovieId, Volume> =
@@ -137,13 +164,13 @@ impl UserLayer {
                 let movie = asset_server
                     .load::<Movie, _>(movie_info.path())
                     .await
-                    .expect("Failed to load movie");
+                    .context("Failed to load movie")?;
 
                 let still_picture = Some(
                     asset_server
                         .load::<Picture, _>(pic_path)
                         .await
-                        .expect("Failed to load still picture"),
+                        .context("Failed to load still picture")?,
                 );
 
                 let args = MovieArgs {
@@ -155,17 +182,21 @@ impl UserLayer {
 
                 MovieLayer::new(device, audio_manager, movie, args, still_picture).into()
             }
-            LayerType::Rain => {
-                let (_always_zero, _min_distance, _max_distance, ..): TypedNumberArray =
-                    lower_number_array(params);
+            LayerType::Rain => RainLayer::new(RainParams::from_number_array(params)).into(),
+            LayerType::Animation => {
+                let (animation_id, args) = AnimationArgs::from_number_array(params);
+                debug!("Load animation: {} {:?}", animation_id, args);
+                let animation = asset_server
+                    .load::<Animation, _>(Animation::path(animation_id))
+                    .await
+                    .context("Failed to load animation")?;
 
-                warn!("Loading NullLayer instead of RainLayer");
-                NullLayer::new().into()
+                AnimationLayer::new(animation, args).into()
             }
             _ => {
-                todo!("Layer type not implemented: {:?}", layer_ty);
+                bail!("Layer type not implemented: {:?}", layer_ty);
             }
-        }
+        })
     }
 }
 
@@ -177,6 +208,8 @@ impl AdvUpdatable for UserLayer {
             Self::Bustup(layer) => layer.update(context),
             Self::Tile(layer) => layer.update(context),
             Self::Movie(layer) => layer.update(context),
+            Self::Rain(layer) => layer.update(context),
+            Self::Animation(layer) => layer.update(context),
         }
     }
 }
@@ -189,6 +222,8 @@ impl DrawableLayer for UserLayer {
             Self::Bustup(layer) => layer.properties(),
             Self::Tile(layer) => layer.properties(),
             Self::Movie(layer) => layer.properties(),
+            Self::Rain(layer) => layer.properties(),
+            Self::Animation(layer) => layer.properties(),
         }
     }
 
@@ -199,6 +234,8 @@ impl DrawableLayer for UserLayer {
             Self::Bustup(layer) => layer.properties_mut(),
             Self::Tile(layer) => layer.properties_mut(),
             Self::Movie(layer) => layer.properties_mut(),
+            Self::Rain(layer) => layer.properties_mut(),
+            Self::Animation(layer) => layer.properties_mut(),
         }
     }
 }
@@ -211,6 +248,8 @@ impl Layer for UserLayer {
             Self::Bustup(layer) => layer.fast_forward(),
             Self::Tile(layer) => layer.fast_forward(),
             Self::Movie(layer) => layer.fast_forward(),
+            Self::Rain(layer) => layer.fast_forward(),
+            Self::Animation(layer) => layer.fast_forward(),
         }
     }
 
@@ -221,6 +260,8 @@ impl Layer for UserLayer {
             Self::Bustup(layer) => layer.get_stencil_bump(),
             Self::Tile(layer) => layer.get_stencil_bump(),
             Self::Movie(layer) => layer.get_stencil_bump(),
+            Self::Rain(layer) => layer.get_stencil_bump(),
+            Self::Animation(layer) => layer.get_stencil_bump(),
         }
     }
 
@@ -231,6 +272,8 @@ impl Layer for UserLayer {
             Self::Bustup(layer) => layer.pre_render(context, transform),
             Self::Tile(layer) => layer.pre_render(context, transform),
             Self::Movie(layer) => layer.pre_render(context, transform),
+            Self::Rain(layer) => layer.pre_render(context, transform),
+            Self::Animation(layer) => layer.pre_render(context, transform),
         }
     }
 
@@ -247,6 +290,8 @@ impl Layer for UserLayer {
             Self::Bustup(layer) => layer.render(pass, transform, stencil_ref, pass_kind),
             Self::Tile(layer) => layer.render(pass, transform, stencil_ref, pass_kind),
             Self::Movie(layer) => layer.render(pass, transform, stencil_ref, pass_kind),
+            Self::Rain(layer) => layer.render(pass, transform, stencil_ref, pass_kind),
+            Self::Animation(layer) => layer.render(pass, transform, stencil_ref, pass_kind),
         }
     }
 }
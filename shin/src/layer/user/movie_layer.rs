@@ -5,7 +5,10 @@ use parking_lot::Mutex;
 use shin_audio::AudioManager;
 use shin_core::{
     format::scenario::info::{MovieTransparencyMode, MovieVolumeSource},
-    primitives::update::{FrameId, UpdateTracker},
+    primitives::{
+        color::UnormColor,
+        update::{FrameId, UpdateTracker},
+    },
     vm::command::types::Volume,
 };
 use shin_render::{
@@ -64,6 +67,11 @@ impl Shared {
     }
 }
 
+/// Clones of a movie layer (made by LAYERSWAP or when saving the scene state) share the same video player.
+///
+/// This way the clone continues from the current playback position (and a finished movie stays finished)
+/// without re-opening the decoder. The shared [`UpdateTracker`]s make sure the movie is advanced only once per frame,
+/// no matter how many layers reference it.
 #[derive(RenderClone)]
 pub struct MovieLayerImpl {
     movie_label: String,
@@ -130,6 +138,26 @@ impl MovieLayer {
     }
 }
 
+impl MovieLayerImpl {
+    fn render_frame(&self, pass: &mut RenderPass, transform: &TransformParams, stencil_ref: u8) {
+        let Some(frame) = self.video_player.get_frame() else {
+            return;
+        };
+
+        // NB: the original engine uses a generic layer shader here, because it does YUV->RGB conversion in a separate pass
+        // we try to do better, so we do the conversion in the main pass
+
+        let transform =
+            transform.compute_final_transform() * Mat4::from_translation(vec3(-960.0, -540.0, 0.0));
+
+        frame.render(
+            pass,
+            RenderRequestBuilder::new().depth_stencil_shorthand(stencil_ref, false, false),
+            transform,
+        );
+    }
+}
+
 impl NewDrawableLayerNeedsSeparatePass for MovieLayerImpl {
     fn needs_separate_pass(&self, props: &LayerProperties) -> bool {
         // NB: this if is not present in the original implementation
@@ -165,7 +193,17 @@ impl NewDrawableLayer for MovieLayerImpl {
         depth_stencil: DepthStencilTarget,
         transform: &TransformParams,
     ) -> PassKind {
-        todo!()
+        // The movie shader already does the YUV->RGB conversion, so there is no need for a separate colorspace pass.
+        // We only get here when the layer needs effects the movie shader can't do (clipping, fragment shaders, blending),
+        // those are applied when the texture is composited by `NewDrawableLayerState`
+        let mut pass = context.begin_pass(target, Some(depth_stencil), "MovieLayer/indirect");
+        pass.clear(Some(UnormColor(0)), Some(0), None);
+
+        let self_transform = props.get_composed_transform_params(transform);
+        self.render_frame(&mut pass, &self_transform, 1);
+
+        // the movie doesn't necessarily cover the whole texture, the layers below have to show through the rest of it
+        PassKind::Transparent
     }
 
     fn render_drawable_direct(
@@ -192,21 +230,7 @@ impl NewDrawableLayer for MovieLayerImpl {
             return;
         }
 
-        let Some(frame) = self.video_player.get_frame() else {
-            return;
-        };
-
-        // NB: the original engine uses a generic layer shader here, because it does YUV->RGB conversion in a separate pass
-        // we try to do better, so we do the conversion in the main pass
-
-        let transform =
-            transform.compute_final_transform() * Mat4::from_translation(vec3(-960.0, -540.0, 0.0));
-
-        frame.render(
-            pass,
-            RenderRequestBuilder::new().depth_stencil_shorthand(stencil_ref, false, false),
-            transform,
-        );
+        self.render_frame(pass, transform, stencil_ref);
     }
 }
 
@@ -230,3 +254,216 @@ impl Debug for MovieLayerImpl {
             .finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        path::{Path, PathBuf},
+        process::Command,
+        sync::Arc,
+        time::{Duration, Instant},
+    };
+
+    use shin_audio::TestClock;
+    use shin_core::{
+        format::scenario::info::{MovieTransparencyMode, MovieVolumeSource},
+        primitives::update::FrameId,
+        time::Ticks,
+        vm::command::types::{LayerProperty, Volume},
+    };
+    use shin_render::shaders::types::RenderClone as _;
+    use winit::dpi::PhysicalSize;
+
+    use super::{MovieArgs, MovieLayer};
+    use crate::{
+        asset::{
+            movie::Movie,
+            system::{AssetServer, LayeredAssetIo},
+        },
+        audio::Lipsync,
+        layer::DrawableLayer,
+        render::test_utils::{TestRenderer, create_task_pools},
+        update::{AdvUpdatable, AdvUpdateContext},
+    };
+
+    /// A second of solid white 960x540 video, covering the top left quarter of the screen
+    fn encode_clip(path: &Path) -> bool {
+        Command::new("ffmpeg")
+            .args(["-loglevel", "error", "-y", "-f", "lavfi"])
+            .args(["-i", "color=c=white:size=960x540:rate=30:duration=1"])
+            .args(["-c:v", "libx264", "-pix_fmt", "yuv420p"])
+            .arg(path)
+            .status()
+            .is_ok_and(|status| status.success())
+    }
+
+    /// The clip encoded into a directory of its own, returning the directory for cleanup
+    fn load_clip(renderer: &TestRenderer, name: &str) -> (Arc<AssetServer>, Arc<Movie>, PathBuf) {
+        create_task_pools();
+
+        let dir = std::env::temp_dir().join(format!("shin-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        assert!(
+            encode_clip(&dir.join("movie.mp4")),
+            "Could not encode a test clip with ffmpeg"
+        );
+        let mut io = LayeredAssetIo::new();
+        io.try_with_dir(&dir).unwrap();
+        let asset_server = renderer.asset_server(io);
+        let movie = asset_server.load_sync::<Movie>("/movie.mp4").unwrap();
+
+        (asset_server, movie, dir)
+    }
+
+    fn play(renderer: &TestRenderer, audio_clock: &TestClock, movie: Arc<Movie>) -> MovieLayer {
+        MovieLayer::new(
+            &renderer.device,
+            audio_clock.audio_manager(),
+            movie,
+            MovieArgs {
+                volume_source: MovieVolumeSource::Bgm,
+                transparency: MovieTransparencyMode::Opaque,
+                local_volume: Volume::default(),
+                repeat: false,
+            },
+            None,
+        )
+    }
+
+    /// Runs a game frame of a tick
+    fn update(
+        layer: &mut MovieLayer,
+        renderer: &TestRenderer,
+        asset_server: &AssetServer,
+        frame_id: &mut FrameId,
+    ) {
+        frame_id.advance();
+        layer.update(&AdvUpdateContext {
+            frame_id: *frame_id,
+            delta_ticks: Ticks::from_u32(1),
+            asset_server,
+            device: &renderer.device,
+            queue: &renderer.queue,
+            are_animations_allowed: true,
+            reduced_motion: false,
+            group_opacity: false,
+            lipsync: &Lipsync::default(),
+        });
+    }
+
+    /// Runs the updates until the first frame is shown, the frames are decoded in the background
+    fn update_until_shown(
+        layer: &mut MovieLayer,
+        renderer: &TestRenderer,
+        asset_server: &AssetServer,
+        frame_id: &mut FrameId,
+    ) {
+        let start = Instant::now();
+        while layer.inner_ref().video_player.get_frame().is_none() {
+            assert!(start.elapsed() < Duration::from_secs(5), "Timed out");
+            update(layer, renderer, asset_server, frame_id);
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter and ffmpeg"]
+    fn movie_layer_smoke() {
+        // 10 virtual pixels per pixel
+        let mut renderer = TestRenderer::new(PhysicalSize::new(192, 108));
+        let (asset_server, movie, dir) = load_clip(&renderer, "movie-layer");
+        let audio_clock = TestClock::new(48000);
+        let mut layer = play(&renderer, &audio_clock, movie);
+
+        let mut frame_id = FrameId::default();
+        update_until_shown(&mut layer, &renderer, &asset_server, &mut frame_id);
+
+        let image = renderer.render_layer(&mut layer);
+        let [r, g, b, a] = image.get_pixel(20, 20).0;
+        assert!(
+            r > 200 && g > 200 && b > 200 && a == 255,
+            "{:?}",
+            (r, g, b, a)
+        );
+        assert_eq!(image.get_pixel(150, 80).0[3], 0);
+
+        // tinting the movie needs a separate pass, which must not black out the rest of the screen
+        layer
+            .properties_mut()
+            .property_tweener_mut(LayerProperty::MulColorRed)
+            .fast_forward_to(500.0);
+        let image = renderer.render_layer(&mut layer);
+        let [r, g, _, a] = image.get_pixel(20, 20).0;
+        assert!(r < 160 && g > 200 && a == 255, "{:?}", (r, g, a));
+        assert_eq!(image.get_pixel(150, 80).0[3], 0);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter and ffmpeg"]
+    fn updates_advance_the_video() {
+        let renderer = TestRenderer::new(PhysicalSize::new(192, 108));
+        let (asset_server, movie, dir) = load_clip(&renderer, "movie-layer-update");
+        let audio_clock = TestClock::new(48000);
+        let mut layer = play(&renderer, &audio_clock, movie);
+        let displayed_frame = |layer: &MovieLayer| {
+            layer
+                .inner_ref()
+                .video_player
+                .displayed_frame_number()
+                .unwrap()
+        };
+
+        let mut frame_id = FrameId::default();
+        update_until_shown(&mut layer, &renderer, &asset_server, &mut frame_id);
+        let first_frame = displayed_frame(&layer);
+
+        // a 30 fps video moves on by a frame every other tick
+        for _ in 0..10 {
+            update(&mut layer, &renderer, &asset_server, &mut frame_id);
+        }
+        assert!(
+            displayed_frame(&layer) > first_frame,
+            "{} > {}",
+            displayed_frame(&layer),
+            first_frame
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter and ffmpeg"]
+    fn clones_keep_the_playback_position() {
+        let mut renderer = TestRenderer::new(PhysicalSize::new(192, 108));
+        let (asset_server, movie, dir) = load_clip(&renderer, "movie-layer-clone");
+        let audio_clock = TestClock::new(48000);
+        let mut layer = play(&renderer, &audio_clock, movie);
+        let position = |layer: &MovieLayer| layer.inner_ref().video_player.position();
+
+        let mut frame_id = FrameId::default();
+        update_until_shown(&mut layer, &renderer, &asset_server, &mut frame_id);
+        for _ in 0..20 {
+            update(&mut layer, &renderer, &asset_server, &mut frame_id);
+        }
+        assert!(position(&layer) > Ticks::ZERO);
+
+        let clone =
+            renderer.pre_render(|context| layer.render_clone(&mut context.render_clone_ctx()));
+        assert_eq!(position(&clone), position(&layer));
+        assert!(!clone.is_finished());
+
+        // a finished movie stays finished in its clones
+        let start = Instant::now();
+        while !layer.is_finished() {
+            assert!(start.elapsed() < Duration::from_secs(5), "Timed out");
+            update(&mut layer, &renderer, &asset_server, &mut frame_id);
+        }
+        let clone =
+            renderer.pre_render(|context| layer.render_clone(&mut context.render_clone_ctx()));
+        assert!(clone.is_finished());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
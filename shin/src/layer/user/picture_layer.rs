@@ -3,12 +3,10 @@ use std::{fmt::Debug, sync::Arc};
 use glam::{Mat4, Vec3, Vec4};
 use shin_core::primitives::color::FloatColor4;
 use shin_render::{
-    ColorBlendType, DrawPrimitive, LayerBlendType, LayerFragmentShader, LayerShaderOutputKind,
-    PassKind, RenderProgramWithArguments, RenderRequestBuilder,
+    LayerBlendType, LayerFragmentShader, PassKind, RenderRequestBuilder,
     render_pass::RenderPass,
     shaders::types::{
         RenderClone,
-        buffer::VertexSource,
         texture::{DepthStencilTarget, TextureTarget},
     },
 };
@@ -22,7 +20,7 @@ use crate::{
         },
         render_params::{DrawableClipMode, DrawableClipParams, DrawableParams, TransformParams},
     },
-    render::PreRenderContext,
+    render::{PreRenderContext, sprite::Sprite},
     update::{AdvUpdatable, AdvUpdateContext},
 };
 
@@ -126,33 +124,28 @@ pub fn render_block(
         return;
     }
 
-    // NOTE: we don't need to slice the vertex buffer, only the index buffer
-    let vertices = block.vertex_buffer.as_buffer_ref();
-    let indices = block.index_buffer.as_sliced_buffer_ref(
-        offset * GpuPictureBlock::INDICES_PER_RECT,
-        count * GpuPictureBlock::INDICES_PER_RECT,
-    );
-    let vertices = VertexSource::VertexAndIndexBuffer { vertices, indices };
-
-    let color_blend_type = match pass_kind {
-        PictureBlockPassKind::OpaqueOnly => ColorBlendType::Opaque,
+    // the opaque pass only draws the opaque rects, without blending
+    let sprite_pass_kind = match pass_kind {
+        PictureBlockPassKind::OpaqueOnly => PassKind::Opaque,
         PictureBlockPassKind::TransparentOnly | PictureBlockPassKind::OpaqueAndTransparent => {
-            ColorBlendType::from_premultiplied_layer(blend_type)
+            PassKind::Transparent
         }
     };
 
-    pass.run(builder.color_blend_type(color_blend_type).build(
-        RenderProgramWithArguments::Layer {
-            output_kind: LayerShaderOutputKind::LayerPremultiply,
-            fragment_shader,
-            vertices,
-            texture: block.texture.as_source(),
-            transform,
-            color_multiplier,
-            fragment_shader_param,
-        },
-        DrawPrimitive::Triangles,
-    ));
+    Sprite::mesh(
+        block.texture.as_source(),
+        &block.vertex_buffer,
+        &block.index_buffer,
+        (
+            offset * GpuPictureBlock::INDICES_PER_RECT,
+            count * GpuPictureBlock::INDICES_PER_RECT,
+        ),
+        transform,
+    )
+    .with_color(color_multiplier)
+    .with_blend_type(blend_type)
+    .with_fragment_shader(fragment_shader, fragment_shader_param)
+    .render(pass, builder, sprite_pass_kind);
 }
 
 #[derive(Clone, RenderClone)]
@@ -0,0 +1,335 @@
+use std::fmt::Debug;
+
+use glam::{Vec2, vec2};
+use shin_core::{
+    format::scenario::instruction_elements::{
+        TypedNumberArray, UntypedNumberArray, lower_number_array,
+    },
+    primitives::color::{FloatColor4, UnormColor},
+    time::Ticks,
+};
+use shin_render::{
+    ColorBlendType, DrawPrimitive, PassKind, RenderProgramWithArguments, RenderRequestBuilder,
+    render_pass::RenderPass,
+    shaders::types::{RenderClone, buffer::VertexSource, vertices::PosColVertex},
+};
+
+use crate::{
+    layer::{
+        NewDrawableLayer, NewDrawableLayerWrapper,
+        new_drawable_layer::{NewDrawableLayerFastForward, NewDrawableLayerNeedsSeparatePass},
+        render_params::{DrawableClipMode, DrawableClipParams, DrawableParams, TransformParams},
+    },
+    render::VIRTUAL_CANVAS_SIZE_VEC,
+    update::{AdvUpdatable, AdvUpdateContext},
+};
+
+/// More raindrops than this are not distinguishable anyway
+const MAX_DROPS: u32 = 4096;
+const RAIN_COLOR: FloatColor4 = FloatColor4::from_rgba(0.8, 0.85, 0.9, 0.35);
+/// The streaks are as long as the distance a drop travels in this time, like a motion blur
+const STREAK_DURATION: f32 = 1.0 / 30.0;
+const MIN_STREAK_LENGTH: f32 = 8.0;
+const STREAK_WIDTH: f32 = 2.0;
+/// The raindrops far away are smaller and slower, down to this scale
+const MIN_DROP_SCALE: f32 = 0.5;
+
+/// Parameters of the rain, passed to `LAYERLOAD`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RainParams {
+    /// Number of the raindrops on the screen
+    pub density: u32,
+    /// Falling speed of the closest raindrops, in pixels per second
+    pub speed: f32,
+    /// Angle between the falling direction and the vertical, in degrees. Positive angles make the rain go to the right
+    pub angle: f32,
+}
+
+impl RainParams {
+    pub fn from_number_array(params: UntypedNumberArray) -> Self {
+        // NB: we don't know what the distances do in the original engine, so they are ignored
+        let (_always_zero, _min_distance, _max_distance, density, speed, angle, ..): TypedNumberArray =
+            lower_number_array(params);
+
+        Self {
+            density: density.clamp(0, MAX_DROPS as i32) as u32,
+            speed: speed as f32,
+            angle: angle as f32,
+        }
+    }
+
+    /// Unit vector of the falling direction
+    fn direction(&self) -> Vec2 {
+        let (sin, cos) = self.angle.to_radians().sin_cos();
+        // the y axis goes down
+        vec2(sin, cos)
+    }
+}
+
+/// Deterministic pseudo-random value in `[0; 1)`, so that the rain looks the same every time
+fn hash01(index: u32, salt: u32) -> f32 {
+    let mut x = index.wrapping_mul(0x9e3779b9) ^ salt.wrapping_mul(0x85ebca6b);
+    x ^= x >> 16;
+    x = x.wrapping_mul(0x7feb352d);
+    x ^= x >> 15;
+    x = x.wrapping_mul(0x846ca68b);
+    x ^= x >> 16;
+
+    (x >> 8) as f32 / (1 << 24) as f32
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Raindrop {
+    /// Position at the start, relative to the top left corner of the rain area
+    origin: Vec2,
+    /// Smaller for the raindrops farther away
+    scale: f32,
+}
+
+#[derive(Clone, RenderClone)]
+pub struct RainLayerImpl {
+    params: RainParams,
+    drops: Vec<Raindrop>,
+    /// Time since the layer was loaded, in seconds
+    time: f32,
+}
+
+impl RainLayerImpl {
+    pub fn new(params: RainParams) -> Self {
+        let area = Self::area(&params);
+        let drops = (0..params.density)
+            .map(|index| Raindrop {
+                origin: vec2(hash01(index, 0), hash01(index, 1)) * area,
+                scale: MIN_DROP_SCALE + (1.0 - MIN_DROP_SCALE) * hash01(index, 2),
+            })
+            .collect();
+
+        Self {
+            params,
+            drops,
+            time: 0.0,
+        }
+    }
+
+    fn streak_length(params: &RainParams, scale: f32) -> f32 {
+        (params.speed.abs() * scale * STREAK_DURATION).max(MIN_STREAK_LENGTH)
+    }
+
+    /// The canvas, extended by the longest streak, so that the raindrops leave the screen completely before wrapping around
+    fn area(params: &RainParams) -> Vec2 {
+        VIRTUAL_CANVAS_SIZE_VEC + Vec2::splat(Self::streak_length(params, 1.0))
+    }
+
+    fn advance(&mut self, delta: Ticks) {
+        self.time += delta.as_seconds();
+    }
+
+    /// The two triangles of every streak, centered on the layer origin
+    fn streak_vertices(&self, color: UnormColor) -> Vec<PosColVertex> {
+        let area = Self::area(&self.params);
+        let direction = self.params.direction();
+        let normal = direction.perp();
+
+        let mut vertices = Vec::with_capacity(self.drops.len() * 6);
+        for drop in &self.drops {
+            let travelled = direction * self.params.speed * drop.scale * self.time;
+            let head = (drop.origin + travelled).rem_euclid(area) - area / 2.0;
+
+            let tail = head - direction * Self::streak_length(&self.params, drop.scale);
+            let half_width = normal * STREAK_WIDTH * drop.scale / 2.0;
+
+            let corners = [
+                tail - half_width,
+                tail + half_width,
+                head - half_width,
+                head + half_width,
+            ];
+            vertices.extend([0, 1, 2, 2, 1, 3].map(|i| PosColVertex {
+                position: corners[i].extend(0.0),
+                color,
+            }));
+        }
+
+        vertices
+    }
+}
+
+pub type RainLayer = NewDrawableLayerWrapper<RainLayerImpl>;
+
+impl RainLayer {
+    pub fn new(params: RainParams) -> Self {
+        Self::from_inner(RainLayerImpl::new(params))
+    }
+}
+
+impl NewDrawableLayerNeedsSeparatePass for RainLayerImpl {}
+
+impl NewDrawableLayer for RainLayerImpl {
+    #[tracing::instrument(skip_all)]
+    fn render_drawable_direct(
+        &self,
+        pass: &mut RenderPass,
+        transform: &TransformParams,
+        &DrawableParams {
+            color_multiplier,
+            blend_type,
+            fragment_shader,
+            shader_param,
+            ..
+        }: &DrawableParams,
+        clip: &DrawableClipParams,
+        stencil_ref: u8,
+        pass_kind: PassKind,
+    ) {
+        let tinted_color = color_multiplier * RAIN_COLOR;
+
+        if self.drops.is_empty() || tinted_color.a <= 0.0 || pass_kind != PassKind::Transparent {
+            return;
+        }
+
+        assert_eq!(
+            clip.mode,
+            DrawableClipMode::None,
+            "Clipping effect is not implemented"
+        );
+
+        let color = fragment_shader
+            .simplify(shader_param)
+            .evaluate(tinted_color, shader_param)
+            .into_unorm();
+        // there is no instancing, so all the streaks are batched into a single draw
+        let vertices = self.streak_vertices(color);
+
+        pass.push_debug("RainLayer");
+
+        pass.run(
+            RenderRequestBuilder::new()
+                .depth_stencil_shorthand(stencil_ref, false, false)
+                .color_blend_type(ColorBlendType::from_regular_layer(blend_type))
+                .build(
+                    RenderProgramWithArguments::Fill {
+                        vertices: VertexSource::VertexData {
+                            vertices: &vertices,
+                        },
+                        transform: transform.compute_final_transform(),
+                    },
+                    DrawPrimitive::Triangles,
+                ),
+        );
+
+        pass.pop_debug();
+    }
+}
+
+impl NewDrawableLayerFastForward for RainLayerImpl {
+    fn fast_forward(&mut self) {}
+}
+
+impl AdvUpdatable for RainLayerImpl {
+    fn update(&mut self, context: &AdvUpdateContext) {
+        self.advance(context.delta_ticks);
+    }
+}
+
+impl Debug for RainLayerImpl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("RainLayer").field(&self.params).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{Vec2, vec2};
+    use shin_core::{primitives::color::UnormColor, time::Ticks};
+
+    use super::{RainLayerImpl, RainParams};
+
+    fn params(density: u32, speed: f32, angle: f32) -> RainParams {
+        RainParams {
+            density,
+            speed,
+            angle,
+        }
+    }
+
+    /// The head and tail centers of every streak
+    fn streaks(layer: &RainLayerImpl) -> Vec<(Vec2, Vec2)> {
+        layer
+            .streak_vertices(UnormColor::WHITE)
+            .chunks_exact(6)
+            .map(|quad| {
+                let corner = |i: usize| quad[i].position.truncate();
+                let tail = (corner(0) + corner(1)) / 2.0;
+                let head = (corner(5) + corner(2)) / 2.0;
+                (head, tail)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn decode_params() {
+        assert_eq!(
+            RainParams::from_number_array((0, 0, 0, 300, 1200, -15, 0, 0)),
+            params(300, 1200.0, -15.0)
+        );
+        // the nonsensical densities are clamped
+        assert_eq!(
+            RainParams::from_number_array((0, 0, 0, -5, 1200, 0, 0, 0)).density,
+            0
+        );
+        assert_eq!(
+            RainParams::from_number_array((0, 0, 0, 1_000_000, 1200, 0, 0, 0)).density,
+            4096
+        );
+    }
+
+    #[test]
+    fn renders_frame() {
+        let mut layer = RainLayerImpl::new(params(200, 1500.0, 10.0));
+        layer.advance(Ticks::from_seconds(0.5));
+        layer.advance(Ticks::from_seconds(1.0 / 60.0));
+
+        let vertices = layer.streak_vertices(UnormColor::WHITE);
+        assert_eq!(vertices.len(), 200 * 6);
+        assert!(vertices.iter().all(|v| v.position.is_finite()));
+    }
+
+    #[test]
+    fn zero_density_renders_nothing() {
+        let mut layer = RainLayerImpl::new(params(0, 1500.0, 10.0));
+        layer.advance(Ticks::from_seconds(1.0));
+
+        assert!(layer.drops.is_empty());
+        assert!(layer.streak_vertices(UnormColor::WHITE).is_empty());
+    }
+
+    #[test]
+    fn angle_affects_direction_and_orientation() {
+        for angle in [0.0f32, 30.0, -45.0] {
+            let expected = vec2(angle.to_radians().sin(), angle.to_radians().cos());
+
+            let mut layer = RainLayerImpl::new(params(50, 600.0, angle));
+            let before = streaks(&layer);
+            layer.advance(Ticks::from_seconds(0.01));
+            let after = streaks(&layer);
+
+            for ((head_before, tail), (head_after, _)) in before.into_iter().zip(after) {
+                // the streaks are oriented along the falling direction
+                let axis = (head_before - tail).normalize();
+                assert!(axis.abs_diff_eq(expected, 1e-4), "{} != {}", axis, expected);
+
+                // the raindrops moved along it (skipping the ones that wrapped around)
+                let movement = head_after - head_before;
+                if movement.length() < 100.0 {
+                    let movement = movement.normalize();
+                    assert!(
+                        movement.abs_diff_eq(expected, 1e-3),
+                        "{} != {}",
+                        movement,
+                        expected
+                    );
+                }
+            }
+        }
+    }
+}
@@ -1,12 +1,10 @@
 use std::fmt::Debug;
 
-use glam::{Vec4, vec3};
+use glam::{Mat4, Vec2, Vec4};
 use shin_core::primitives::color::FloatColor4;
 use shin_render::{
-    ColorBlendType, DrawPrimitive, LayerBlendType, PassKind, RenderProgramWithArguments,
-    RenderRequestBuilder,
-    render_pass::RenderPass,
-    shaders::types::{RenderClone, buffer::VertexSource, vertices::PosColVertex},
+    ColorBlendType, LayerBlendType, PassKind, RenderRequestBuilder, gradient::Gradient,
+    render_pass::RenderPass, shaders::types::RenderClone,
 };
 
 use crate::{
@@ -15,18 +13,28 @@ use crate::{
         new_drawable_layer::{NewDrawableLayerFastForward, NewDrawableLayerNeedsSeparatePass},
         render_params::{DrawableClipMode, DrawableClipParams, DrawableParams, TransformParams},
     },
+    render::{pixel_snap::snap_to_pixel_grid, sprite::Sprite},
     update::{AdvUpdatable, AdvUpdateContext},
 };
 
+#[derive(Debug, Clone)]
+pub enum TileFill {
+    Solid(FloatColor4),
+    /// The geometry of the gradient is in the same space as the rect.
+    ///
+    /// Not loadable by the scenarios, only by the engine's own UI, see [`TileLayer::with_gradient`].
+    Gradient(Gradient),
+}
+
 #[derive(Clone, RenderClone)]
 pub struct TileLayerImpl {
-    color: FloatColor4,
+    fill: TileFill,
     rect: Vec4,
 }
 
 impl TileLayerImpl {
-    pub fn new(color: FloatColor4, rect: Vec4) -> Self {
-        Self { color, rect }
+    pub fn new(fill: TileFill, rect: Vec4) -> Self {
+        Self { fill, rect }
     }
 }
 
@@ -34,36 +42,40 @@ pub type TileLayer = NewDrawableLayerWrapper<TileLayerImpl>;
 
 impl TileLayer {
     pub fn new(color: FloatColor4, rect: Vec4) -> Self {
-        Self::from_inner(TileLayerImpl::new(color, rect))
+        Self::from_inner(TileLayerImpl::new(TileFill::Solid(color), rect))
     }
-}
 
-impl NewDrawableLayerNeedsSeparatePass for TileLayerImpl {}
+    /// A tile filled with a gradient, for the UI drawn by the engine itself (`LAYERLOAD` only makes solid tiles)
+    // TODO: use it in the menus, once there are some
+    #[allow(unused)]
+    pub fn with_gradient(gradient: Gradient, rect: Vec4) -> Self {
+        Self::from_inner(TileLayerImpl::new(TileFill::Gradient(gradient), rect))
+    }
+}
 
-impl NewDrawableLayer for TileLayerImpl {
-    #[tracing::instrument(skip_all)]
-    fn render_drawable_direct(
+impl TileLayerImpl {
+    fn render_gradient(
         &self,
         pass: &mut RenderPass,
         transform: &TransformParams,
+        gradient: &Gradient,
         &DrawableParams {
             color_multiplier,
             blend_type,
             fragment_shader,
             shader_param,
+            pixel_snap,
         }: &DrawableParams,
-        clip: &DrawableClipParams,
         stencil_ref: u8,
         pass_kind: PassKind,
     ) {
-        let tinted_color = color_multiplier * self.color;
+        // like for the solid tiles, the fragment shader is applied to the colors right away
+        // (to the stops, so the colors in between are interpolated from the shaded ones)
         let fragment_shader = fragment_shader.simplify(shader_param);
+        let gradient = gradient
+            .map_colors(|color| fragment_shader.evaluate(color_multiplier * color, shader_param));
 
-        if tinted_color.a <= 0.0 {
-            return;
-        }
-
-        let target_pass = if blend_type == LayerBlendType::Type1 && tinted_color.a >= 1.0 {
+        let target_pass = if blend_type == LayerBlendType::Type1 && gradient.is_opaque() {
             PassKind::Opaque
         } else {
             PassKind::Transparent
@@ -73,62 +85,95 @@ impl NewDrawableLayer for TileLayerImpl {
             return;
         }
 
+        let color_blend_type = match pass_kind {
+            PassKind::Opaque => ColorBlendType::Opaque,
+            PassKind::Transparent => ColorBlendType::from_premultiplied_layer(blend_type),
+        };
+
+        let mut transform = transform.compute_final_transform();
+        if pixel_snap {
+            let origin = snap_to_pixel_grid(Vec2::ZERO, transform, pass.pixel_size());
+            transform *= Mat4::from_translation(origin.extend(0.0));
+        }
+
+        pass.push_debug("TileLayer/gradient");
+        gradient.fill_rect(
+            pass,
+            RenderRequestBuilder::new()
+                .depth_stencil_shorthand(stencil_ref, false, false)
+                .color_blend_type(color_blend_type),
+            self.rect,
+            transform,
+        );
+        pass.pop_debug();
+    }
+}
+
+impl NewDrawableLayerNeedsSeparatePass for TileLayerImpl {}
+
+impl NewDrawableLayer for TileLayerImpl {
+    #[tracing::instrument(skip_all)]
+    fn render_drawable_direct(
+        &self,
+        pass: &mut RenderPass,
+        transform: &TransformParams,
+        drawable: &DrawableParams,
+        clip: &DrawableClipParams,
+        stencil_ref: u8,
+        pass_kind: PassKind,
+    ) {
         assert_eq!(
             clip.mode,
             DrawableClipMode::None,
             "Clipping effect is not implemented"
         );
 
-        let blend_type = match pass_kind {
-            PassKind::Opaque => ColorBlendType::Opaque,
-            PassKind::Transparent => ColorBlendType::from_regular_layer(blend_type),
+        let color = match &self.fill {
+            TileFill::Solid(color) => *color,
+            TileFill::Gradient(gradient) => {
+                self.render_gradient(pass, transform, gradient, drawable, stencil_ref, pass_kind);
+                return;
+            }
         };
 
-        let color = fragment_shader
-            .evaluate(tinted_color, shader_param)
-            .into_unorm();
+        let &DrawableParams {
+            color_multiplier,
+            blend_type,
+            fragment_shader,
+            shader_param,
+            pixel_snap,
+        } = drawable;
+        let tinted_color = color_multiplier * color;
+        let fragment_shader = fragment_shader.simplify(shader_param);
+
+        if tinted_color.a <= 0.0 {
+            return;
+        }
 
-        let transform = transform.compute_final_transform();
+        let target_pass = if blend_type == LayerBlendType::Type1 && tinted_color.a >= 1.0 {
+            PassKind::Opaque
+        } else {
+            PassKind::Transparent
+        };
 
-        let left = self.rect.x;
-        let right = self.rect.x + self.rect.z;
-        let top = self.rect.y;
-        let bottom = self.rect.y + self.rect.w;
+        if pass_kind != target_pass {
+            return;
+        }
 
-        let vertices = &[
-            PosColVertex {
-                position: vec3(left, top, 0.0),
-                color,
-            },
-            PosColVertex {
-                position: vec3(right, top, 0.0),
-                color,
-            },
-            PosColVertex {
-                position: vec3(left, bottom, 0.0),
-                color,
-            },
-            PosColVertex {
-                position: vec3(right, bottom, 0.0),
-                color,
-            },
-        ];
+        let mut sprite =
+            Sprite::solid(tinted_color, self.rect, transform.compute_final_transform())
+                .with_blend_type(blend_type)
+                .with_fragment_shader(fragment_shader, shader_param);
+        if pixel_snap {
+            sprite = sprite.with_pixel_snap(pass.pixel_size());
+        }
 
         pass.push_debug("TileLayer");
-
-        pass.run(
-            RenderRequestBuilder::new()
-                .depth_stencil_shorthand(stencil_ref, false, false)
-                .color_blend_type(blend_type)
-                .build(
-                    RenderProgramWithArguments::Fill {
-                        vertices: VertexSource::VertexData { vertices },
-                        transform,
-                    },
-                    DrawPrimitive::TrianglesStrip,
-                ),
+        sprite.render(
+            pass,
+            RenderRequestBuilder::new().depth_stencil_shorthand(stencil_ref, false, false),
+            pass_kind,
         );
-
         pass.pop_debug();
     }
 }
@@ -143,12 +188,130 @@ impl AdvUpdatable for TileLayerImpl {
 
 impl Debug for TileLayerImpl {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let color = self.color.into_array().map(|v| (v * 255.0) as u8);
-        let color = format!(
-            "#{:02x}{:02x}{:02x}{:02x}",
-            color[0], color[1], color[2], color[3]
+        let hex = |color: FloatColor4| {
+            let color = color.into_array().map(|v| (v * 255.0) as u8);
+            format!(
+                "#{:02x}{:02x}{:02x}{:02x}",
+                color[0], color[1], color[2], color[3]
+            )
+        };
+
+        match &self.fill {
+            TileFill::Solid(color) => f.debug_tuple("TileLayer").field(&hex(*color)).finish(),
+            TileFill::Gradient(gradient) => {
+                let stops = gradient
+                    .stops()
+                    .iter()
+                    .map(|stop| hex(stop.color))
+                    .collect::<Vec<_>>();
+                f.debug_tuple("TileLayer").field(&stops).finish()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{Vec2, vec2, vec4};
+    use shin_core::{primitives::color::FloatColor4, vm::command::types::LayerProperty};
+    use shin_render::{
+        LayerFragmentShader,
+        gradient::{Gradient, GradientKind, GradientStop},
+    };
+    use winit::dpi::PhysicalSize;
+
+    use super::TileLayer;
+    use crate::{layer::DrawableLayer as _, render::test_utils::TestRenderer};
+
+    const FULL_SCREEN: glam::Vec4 = vec4(-960.0, -540.0, 1920.0, 1080.0);
+
+    fn assert_pixel_near(actual: [u8; 4], expected: [u8; 4]) {
+        let close = actual
+            .iter()
+            .zip(expected)
+            .all(|(&actual, expected)| actual.abs_diff(expected) <= 2);
+        assert!(close, "{:?} != {:?}", actual, expected);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn horizontal_gradient() {
+        let mut renderer = TestRenderer::new(PhysicalSize::new(192, 108));
+
+        let mut layer = TileLayer::with_gradient(
+            Gradient::linear(
+                vec2(-960.0, 0.0),
+                vec2(960.0, 0.0),
+                FloatColor4::RED,
+                FloatColor4::BLUE,
+            ),
+            FULL_SCREEN,
         );
+        let image = renderer.render_layer(&mut layer);
 
-        f.debug_tuple("TileLayer").field(&color).finish()
+        // half of the light intensity is ~188 when encoded, not 128
+        assert_pixel_near(image.get_pixel(96, 20).0, [188, 0, 188, 255]);
+        // the gradient doesn't change vertically
+        assert_pixel_near(image.get_pixel(96, 90).0, [188, 0, 188, 255]);
+        assert_pixel_near(image.get_pixel(0, 54).0, [254, 0, 9, 255]);
+        assert_pixel_near(image.get_pixel(191, 54).0, [9, 0, 254, 255]);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn gradient_with_fragment_shader() {
+        let mut renderer = TestRenderer::new(PhysicalSize::new(192, 108));
+
+        let mut layer = TileLayer::with_gradient(
+            Gradient::linear(
+                vec2(-960.0, 0.0),
+                vec2(960.0, 0.0),
+                FloatColor4::RED,
+                FloatColor4::RED,
+            ),
+            FULL_SCREEN,
+        );
+        layer
+            .properties_mut()
+            .property_tweener_mut(LayerProperty::FragmentShader)
+            .fast_forward_to(LayerFragmentShader::Mono as u32 as f32);
+        let image = renderer.render_layer(&mut layer);
+
+        // the luma of red, like the solid tiles get
+        assert_pixel_near(image.get_pixel(96, 54).0, [76, 76, 76, 255]);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn degenerate_gradients_fill_with_the_last_stop() {
+        let mut renderer = TestRenderer::new(PhysicalSize::new(192, 108));
+
+        let linear = Gradient::linear(Vec2::ZERO, Vec2::ZERO, FloatColor4::RED, FloatColor4::BLUE);
+        let radial = Gradient::new(
+            GradientKind::Radial {
+                center: Vec2::ZERO,
+                radius: 0.0,
+            },
+            [
+                GradientStop {
+                    offset: 0.0,
+                    color: FloatColor4::RED,
+                },
+                GradientStop {
+                    offset: 1.0,
+                    color: FloatColor4::BLUE,
+                },
+            ],
+        )
+        .unwrap();
+
+        for gradient in [linear, radial] {
+            let mut layer = TileLayer::with_gradient(gradient, FULL_SCREEN);
+            let image = renderer.render_layer(&mut layer);
+
+            for (x, y) in [(96, 54), (0, 0), (191, 107)] {
+                assert_pixel_near(image.get_pixel(x, y).0, [0, 0, 255, 255]);
+            }
+        }
     }
 }
@@ -0,0 +1,184 @@
+use shin_render::{
+    DrawPrimitive, RenderProgramWithArguments, RenderRequestBuilder, render_pass::RenderPass,
+    shaders::types::buffer::VertexSource,
+};
+
+use crate::render::{
+    PreRenderContext, normalized_projection_matrix, render_texture_holder::RenderTextureHolder,
+};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum AntiAliasingMode {
+    #[default]
+    Off,
+    /// Fast approximate anti-aliasing, smoothing the edges found in the rendered image
+    Fxaa,
+}
+
+/// Smooths the jagged edges of the scene.
+///
+/// The scene is anti-aliased before the UI is drawn over it, so that the text stays crisp.
+/// When turned off the scene is rendered straight to the target, so the output is exactly the same as without the pass.
+pub struct AntiAliasing {
+    mode: AntiAliasingMode,
+    scene_texture: RenderTextureHolder,
+}
+
+impl AntiAliasing {
+    pub fn new(mode: AntiAliasingMode) -> Self {
+        Self {
+            mode,
+            scene_texture: RenderTextureHolder::new("AntiAliasing"),
+        }
+    }
+
+    /// Renders the scene (without the UI) into an offscreen texture, to be anti-aliased in [`Self::render`].
+    pub fn pre_render(
+        &mut self,
+        context: &mut PreRenderContext,
+        render_scene: impl FnOnce(&mut RenderPass),
+    ) {
+        if self.mode == AntiAliasingMode::Off {
+            self.scene_texture.clear();
+            return;
+        }
+
+        let scene_texture = self.scene_texture.get_or_init(context);
+        let depth_stencil = context.depth_stencil;
+        let mut pass = context.begin_pass(
+            scene_texture.as_texture_target(),
+            Some(depth_stencil),
+            "AntiAliasing/scene",
+        );
+
+        render_scene(&mut pass);
+    }
+
+    /// Draws the anti-aliased scene followed by `render_ui`, or calls `render_direct` if the anti-aliasing is off.
+    pub fn render(
+        &self,
+        pass: &mut RenderPass,
+        render_direct: impl FnOnce(&mut RenderPass),
+        render_ui: impl FnOnce(&mut RenderPass),
+    ) {
+        let Some(scene_texture) = self.scene_texture.get() else {
+            render_direct(pass);
+            return;
+        };
+
+        let quad = pass.geometry_cache().unit_quad();
+        pass.run(RenderRequestBuilder::new().build(
+            RenderProgramWithArguments::Fxaa {
+                vertices: VertexSource::VertexBuffer {
+                    vertices: quad.as_buffer_ref(),
+                },
+                texture: scene_texture.as_texture_source(),
+                transform: normalized_projection_matrix(),
+            },
+            DrawPrimitive::TrianglesStrip,
+        ));
+
+        render_ui(pass);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{Rgba, RgbaImage};
+    use shin_render::{PassKind, RenderRequestBuilder, render_pass::RenderPass};
+    use winit::dpi::PhysicalSize;
+
+    use super::{AntiAliasing, AntiAliasingMode};
+    use crate::render::{sprite::Sprite, test_utils::TestRenderer};
+
+    const WIDTH: u32 = 32;
+    const HEIGHT: u32 = 16;
+
+    const WHITE: Rgba<u8> = Rgba([255; 4]);
+    const BLACK: Rgba<u8> = Rgba([0, 0, 0, 255]);
+
+    /// The scene fills the whole canvas, one texel per pixel
+    fn renderer() -> TestRenderer {
+        TestRenderer::new(PhysicalSize::new(WIDTH, HEIGHT))
+    }
+
+    /// Renders `scene` through the anti-aliasing the way the app does, with nothing drawn over it
+    fn anti_alias(
+        renderer: &mut TestRenderer,
+        mode: AntiAliasingMode,
+        scene: &RgbaImage,
+    ) -> RgbaImage {
+        let scene = renderer.upload(scene);
+        let render_scene = |pass: &mut RenderPass| {
+            Sprite::full_canvas(scene.as_source()).render(
+                pass,
+                RenderRequestBuilder::new(),
+                PassKind::Opaque,
+            )
+        };
+
+        let mut anti_aliasing = AntiAliasing::new(mode);
+        let mut target = renderer.new_render_texture();
+        renderer.pre_render(|context| {
+            anti_aliasing.pre_render(context, render_scene);
+
+            let mut pass = context.begin_pass(target.as_texture_target(), None, "anti_alias");
+            anti_aliasing.render(&mut pass, render_scene, |_| {});
+        });
+
+        renderer.read(&target)
+    }
+
+    /// A white shape with a shallow, aliased diagonal edge over black
+    fn staircase() -> RgbaImage {
+        RgbaImage::from_fn(WIDTH, HEIGHT, |x, y| if x > 2 * y { WHITE } else { BLACK })
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn off_renders_directly() {
+        let mut renderer = renderer();
+
+        let result = anti_alias(&mut renderer, AntiAliasingMode::Off, &staircase());
+        assert_eq!(result, staircase());
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn smooths_high_contrast_edge() {
+        let mut renderer = renderer();
+
+        let image = staircase();
+        let smoothed = anti_alias(&mut renderer, AntiAliasingMode::Fxaa, &image);
+
+        let intermediate = smoothed
+            .pixels()
+            .filter(|pixel| (16..240).contains(&pixel.0[0]))
+            .count();
+        assert!(intermediate > 0, "no pixels were smoothed");
+
+        // the pixels away from the edge are left alone, and the scene stays opaque
+        for (x, y, pixel) in smoothed.enumerate_pixels() {
+            assert_eq!(pixel.0[3], 255, "at ({}, {})", x, y);
+            let far_from_edge = (x as i32 - 2 * y as i32).abs() > 4;
+            if far_from_edge {
+                assert_eq!(pixel, image.get_pixel(x, y), "at ({}, {})", x, y);
+            }
+        }
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn flat_image_unchanged() {
+        let mut renderer = renderer();
+
+        let image = RgbaImage::from_pixel(WIDTH, HEIGHT, Rgba([64, 128, 192, 255]));
+        let smoothed = anti_alias(&mut renderer, AntiAliasingMode::Fxaa, &image);
+
+        for (expected, actual) in image.pixels().zip(smoothed.pixels()) {
+            for (a, b) in expected.0.iter().zip(actual.0) {
+                assert!(a.abs_diff(b) <= 1, "{:?} vs {:?}", expected, actual);
+            }
+        }
+    }
+}
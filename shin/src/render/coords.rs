@@ -0,0 +1,262 @@
+//! The coordinate spaces used for rendering, and the conversions between them.
+//!
+//! - [`VirtualPos`]: the virtual canvas of [`VIRTUAL_CANVAS_SIZE`](super::VIRTUAL_CANVAS_SIZE), with the origin at the top left corner and Y going down.
+//!   The layers use a variant of it centered on the canvas, see [`VirtualPos::from_centered`].
+//! - [`Ndc`]: the normalized device coordinates, as output by the projection matrices, in `[-1; 1]` on both axes.
+//! - [`WindowPos`]: the physical pixels of the window surface, with the origin at the top left corner and Y going down.
+//!   The canvas is letterboxed inside of it, as described by [`ViewportParams`].
+//!
+//! All of the conversions only scale and translate each axis independently.
+
+use glam::{Mat4, Vec2, vec2, vec4};
+use shin_render::resize::ViewportParams;
+
+use crate::render::VIRTUAL_CANVAS_SIZE_VEC;
+
+/// A position on the virtual canvas, in virtual pixels from its top left corner
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct VirtualPos(pub Vec2);
+
+/// A position in the normalized device coordinates
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Ndc(pub Vec2);
+
+/// A position in the window, in physical pixels from its top left corner
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct WindowPos(pub Vec2);
+
+/// A scale followed by a translation, applied to each axis independently
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct AxisTransform {
+    scale: Vec2,
+    offset: Vec2,
+}
+
+impl AxisTransform {
+    /// Maps the rectangle between `top_left` and `bottom_right` onto `[-1; 1]`
+    ///
+    /// The computation is done the same way as in [`Mat4::orthographic_rh_gl`], so that the matrices are exactly the same as before.
+    fn to_ndc(top_left: Vec2, bottom_right: Vec2) -> Self {
+        let size = bottom_right - top_left;
+
+        Self {
+            scale: 2.0 / size,
+            offset: -(bottom_right + top_left) / size,
+        }
+    }
+
+    fn apply(&self, position: Vec2) -> Vec2 {
+        position * self.scale + self.offset
+    }
+
+    fn apply_inverse(&self, position: Vec2) -> Vec2 {
+        (position - self.offset) / self.scale
+    }
+
+    /// The matrix applying the transform, also mapping Z from `[-1; 1]` to `[0; 1]` like [`shin_render::shin_orthographic_projection_matrix`]
+    fn to_matrix(self) -> Mat4 {
+        Mat4::from_cols(
+            vec4(self.scale.x, 0.0, 0.0, 0.0),
+            vec4(0.0, self.scale.y, 0.0, 0.0),
+            vec4(0.0, 0.0, -0.5, 0.0),
+            vec4(self.offset.x, self.offset.y, 0.5, 1.0),
+        )
+    }
+}
+
+fn virtual_to_ndc() -> AxisTransform {
+    AxisTransform::to_ndc(Vec2::ZERO, VIRTUAL_CANVAS_SIZE_VEC)
+}
+
+fn centered_to_ndc() -> AxisTransform {
+    AxisTransform::to_ndc(
+        -VIRTUAL_CANVAS_SIZE_VEC / 2.0,
+        VIRTUAL_CANVAS_SIZE_VEC / 2.0,
+    )
+}
+
+fn normalized_to_ndc() -> AxisTransform {
+    AxisTransform::to_ndc(Vec2::ZERO, Vec2::ONE)
+}
+
+/// The canvas region of the window, as (offset, size) in physical pixels
+fn canvas_region(viewport: &ViewportParams) -> (Vec2, Vec2) {
+    let offset = vec2(
+        viewport.canvas_offset.x as f32,
+        viewport.canvas_offset.y as f32,
+    );
+    let size = vec2(
+        viewport.canvas_size.width as f32,
+        viewport.canvas_size.height as f32,
+    );
+    (offset, size)
+}
+
+impl VirtualPos {
+    /// Convert a position relative to the center of the canvas, as used by the layers
+    pub fn from_centered(position: Vec2) -> Self {
+        Self(position + VIRTUAL_CANVAS_SIZE_VEC / 2.0)
+    }
+
+    pub fn to_centered(self) -> Vec2 {
+        self.0 - VIRTUAL_CANVAS_SIZE_VEC / 2.0
+    }
+
+    /// Convert a position relative to the canvas size, `(0, 0)` being the top left corner and `(1, 1)` the bottom right one
+    pub fn from_normalized(position: Vec2) -> Self {
+        Self(position * VIRTUAL_CANVAS_SIZE_VEC)
+    }
+
+    pub fn to_normalized(self) -> Vec2 {
+        self.0 / VIRTUAL_CANVAS_SIZE_VEC
+    }
+
+    pub fn to_ndc(self) -> Ndc {
+        Ndc(virtual_to_ndc().apply(self.0))
+    }
+
+    pub fn to_window(self, viewport: &ViewportParams) -> WindowPos {
+        let (offset, size) = canvas_region(viewport);
+        WindowPos(offset + self.to_normalized() * size)
+    }
+}
+
+impl Ndc {
+    pub fn to_virtual(self) -> VirtualPos {
+        VirtualPos(virtual_to_ndc().apply_inverse(self.0))
+    }
+
+    pub fn to_window(self, viewport: &ViewportParams) -> WindowPos {
+        self.to_virtual().to_window(viewport)
+    }
+}
+
+impl WindowPos {
+    /// The positions outside of the canvas (in the letterboxing) are converted too, ending up outside of the virtual canvas
+    pub fn to_virtual(self, viewport: &ViewportParams) -> VirtualPos {
+        let (offset, size) = canvas_region(viewport);
+        VirtualPos::from_normalized((self.0 - offset) / size)
+    }
+
+    pub fn to_ndc(self, viewport: &ViewportParams) -> Ndc {
+        self.to_virtual(viewport).to_ndc()
+    }
+}
+
+/// Projects [`VirtualPos`] to [`Ndc`]
+pub fn virtual_projection_matrix() -> Mat4 {
+    virtual_to_ndc().to_matrix()
+}
+
+/// Projects positions relative to the canvas center (see [`VirtualPos::from_centered`]) to [`Ndc`]
+pub fn centered_virtual_projection_matrix() -> Mat4 {
+    centered_to_ndc().to_matrix()
+}
+
+/// Projects positions relative to the canvas size (see [`VirtualPos::from_normalized`]) to [`Ndc`]
+pub fn normalized_virtual_projection_matrix() -> Mat4 {
+    normalized_to_ndc().to_matrix()
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{Mat4, Vec2, vec2};
+    use shin_render::{resize::ViewportParams, shin_orthographic_projection_matrix};
+    use winit::dpi::PhysicalSize;
+
+    use super::{
+        Ndc, VirtualPos, WindowPos, centered_virtual_projection_matrix,
+        normalized_virtual_projection_matrix, virtual_projection_matrix,
+    };
+    use crate::render::VIRTUAL_CANVAS_SIZE_VEC;
+
+    fn assert_pos_eq(actual: Vec2, expected: Vec2) {
+        assert!(
+            actual.abs_diff_eq(expected, 1e-3),
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    /// A 16:10 window, with the canvas letterboxed at the top and the bottom
+    fn viewport() -> ViewportParams {
+        ViewportParams::with_aspect_ratio(PhysicalSize::new(1280, 800), 16.0 / 9.0)
+    }
+
+    #[test]
+    fn matrices_are_unchanged() {
+        let (width, height) = VIRTUAL_CANVAS_SIZE_VEC.into();
+
+        assert_eq!(
+            virtual_projection_matrix(),
+            shin_orthographic_projection_matrix(0.0, width, height, 0.0, -1.0, 1.0)
+        );
+        assert_eq!(
+            centered_virtual_projection_matrix(),
+            shin_orthographic_projection_matrix(
+                -width / 2.0,
+                width / 2.0,
+                height / 2.0,
+                -height / 2.0,
+                -1.0,
+                1.0
+            )
+        );
+        assert_eq!(
+            normalized_virtual_projection_matrix(),
+            shin_orthographic_projection_matrix(0.0, 1.0, 1.0, 0.0, -1.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn conversions_match_the_matrices() {
+        let position = vec2(300.0, 200.0);
+        let project =
+            |matrix: Mat4, position: Vec2| matrix.project_point3(position.extend(0.0)).truncate();
+
+        assert_pos_eq(
+            VirtualPos(position).to_ndc().0,
+            project(virtual_projection_matrix(), position),
+        );
+        assert_pos_eq(
+            VirtualPos::from_centered(position).to_ndc().0,
+            project(centered_virtual_projection_matrix(), position),
+        );
+        assert_pos_eq(
+            VirtualPos::from_normalized(vec2(0.25, 0.5)).to_ndc().0,
+            project(normalized_virtual_projection_matrix(), vec2(0.25, 0.5)),
+        );
+    }
+
+    #[test]
+    fn virtual_to_window_round_trip() {
+        let viewport = viewport();
+        // the canvas is 1280x720, 40 pixels down from the top of the window
+        assert_eq!(viewport.canvas_offset.y, 40);
+
+        let position = VirtualPos(vec2(480.0, 270.0));
+        let window = position.to_window(&viewport);
+        assert_pos_eq(window.0, vec2(320.0, 220.0));
+        assert_pos_eq(window.to_virtual(&viewport).0, position.0);
+
+        // through the NDC too
+        let ndc = window.to_ndc(&viewport);
+        assert_pos_eq(ndc.to_window(&viewport).0, window.0);
+        assert_pos_eq(ndc.to_virtual().0, position.0);
+
+        // the corners of the canvas
+        assert_pos_eq(
+            VirtualPos(Vec2::ZERO).to_window(&viewport).0,
+            vec2(0.0, 40.0),
+        );
+        assert_pos_eq(
+            WindowPos(vec2(1280.0, 760.0)).to_virtual(&viewport).0,
+            VIRTUAL_CANVAS_SIZE_VEC,
+        );
+        assert_pos_eq(
+            Ndc(Vec2::ZERO).to_virtual().0,
+            VIRTUAL_CANVAS_SIZE_VEC / 2.0,
+        );
+    }
+}
@@ -0,0 +1,218 @@
+use std::time::Duration;
+
+use shin_render::{
+    PassKind, RenderRequestBuilder, depth_stencil::DepthStencil, render_pass::RenderPass,
+};
+
+use crate::render::{PreRenderContext, render_texture_holder::RenderTextureHolder, sprite::Sprite};
+
+/// How much the scale changes in a single frame
+const SCALE_STEP: f32 = 0.05;
+/// Weight of the latest frame in the averaged frame time
+const FRAME_TIME_SMOOTHING: f32 = 0.1;
+/// The scale is left alone while the frame time is within this fraction of the target, so that it doesn't flip-flop
+const FRAME_TIME_TOLERANCE: f32 = 0.1;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct DynamicResolutionParams {
+    /// The frame time to keep to, `None` disables the dynamic resolution
+    pub target_frame_time: Option<Duration>,
+    /// The lowest fraction of the full resolution the scene is rendered at
+    pub min_scale: f32,
+    /// Render the UI (messages) at the full resolution over the scaled scene
+    pub native_ui: bool,
+}
+
+impl Default for DynamicResolutionParams {
+    fn default() -> Self {
+        Self {
+            target_frame_time: None,
+            min_scale: 0.5,
+            native_ui: false,
+        }
+    }
+}
+
+/// Renders the scene at a lowered resolution when the frames take too long, upscaling it to the full resolution.
+pub struct DynamicResolution {
+    params: DynamicResolutionParams,
+    /// The fraction of the full resolution the scene is rendered at
+    scale: f32,
+    average_frame_time: Option<f32>,
+    scene_texture: RenderTextureHolder,
+    depth_stencil: Option<DepthStencil>,
+}
+
+impl DynamicResolution {
+    pub fn new(params: DynamicResolutionParams) -> Self {
+        Self {
+            params,
+            scale: 1.0,
+            average_frame_time: None,
+            scene_texture: RenderTextureHolder::new("DynamicResolution"),
+            depth_stencil: None,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.params.target_frame_time.is_some()
+    }
+
+    /// Whether the UI is to be rendered separately from the scene, see [`Self::render`]
+    pub fn native_ui(&self) -> bool {
+        self.is_enabled() && self.params.native_ui
+    }
+
+    /// Whether the UI gets baked into the scaled scene texture, so it can't be drawn separately
+    pub fn scene_includes_ui(&self) -> bool {
+        self.is_enabled() && !self.params.native_ui
+    }
+
+    /// Override the scale, until it gets adjusted by the next [`Self::update`]
+    pub fn set_scale(&mut self, scale: f32) {
+        self.scale = scale.clamp(self.params.min_scale, 1.0);
+    }
+
+    /// Adjust the scale to the time the last frame took
+    pub fn update(&mut self, frame_time: Duration) {
+        let Some(target_frame_time) = self.params.target_frame_time else {
+            return;
+        };
+
+        let frame_time = frame_time.as_secs_f32();
+        let average_frame_time = match self.average_frame_time {
+            Some(average) => average + (frame_time - average) * FRAME_TIME_SMOOTHING,
+            None => frame_time,
+        };
+        self.average_frame_time = Some(average_frame_time);
+
+        let target_frame_time = target_frame_time.as_secs_f32();
+        if average_frame_time > target_frame_time * (1.0 + FRAME_TIME_TOLERANCE) {
+            self.set_scale(self.scale - SCALE_STEP);
+        } else if average_frame_time < target_frame_time * (1.0 - FRAME_TIME_TOLERANCE) {
+            self.set_scale(self.scale + SCALE_STEP);
+        }
+    }
+
+    /// Renders the scene into an offscreen texture of the scaled size, to be upscaled in [`Self::render`].
+    ///
+    /// With [`Self::native_ui`] `render_scene` should leave out the UI.
+    pub fn pre_render(
+        &mut self,
+        context: &mut PreRenderContext,
+        render_scene: impl FnOnce(&mut RenderPass),
+    ) {
+        if !self.is_enabled() {
+            self.scene_texture.clear();
+            self.depth_stencil = None;
+            return;
+        }
+
+        // the scene texture is smaller than the canvas, so it can't use the canvas depth stencil buffer
+        let depth_stencil = self.depth_stencil.get_or_insert_with(|| {
+            DepthStencil::new(
+                context.device.clone(),
+                context.resize_source.canvas_handle(),
+                "DynamicResolution/depth_stencil".to_string(),
+            )
+        });
+        depth_stencil.set_scale(self.scale);
+
+        let scene_texture = self.scene_texture.get_or_init(context);
+        scene_texture.set_scale(self.scale);
+
+        let mut pass = context.begin_pass(
+            scene_texture.as_texture_target(),
+            Some(depth_stencil.get_target_view()),
+            "DynamicResolution/scene",
+        );
+
+        render_scene(&mut pass);
+    }
+
+    /// Draws the upscaled scene followed by `render_ui`, or calls `render_direct` if the dynamic resolution is disabled.
+    pub fn render(
+        &self,
+        pass: &mut RenderPass,
+        render_direct: impl FnOnce(&mut RenderPass),
+        render_ui: impl FnOnce(&mut RenderPass),
+    ) {
+        let Some(scene_texture) = self.scene_texture.get() else {
+            render_direct(pass);
+            return;
+        };
+
+        Sprite::full_canvas(scene_texture.as_texture_source()).render(
+            pass,
+            RenderRequestBuilder::new(),
+            PassKind::Opaque,
+        );
+
+        if self.native_ui() {
+            render_ui(pass);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{DynamicResolution, DynamicResolutionParams};
+
+    const TARGET: Duration = Duration::from_millis(16);
+
+    fn dynamic_resolution() -> DynamicResolution {
+        DynamicResolution::new(DynamicResolutionParams {
+            target_frame_time: Some(TARGET),
+            min_scale: 0.5,
+            native_ui: true,
+        })
+    }
+
+    #[test]
+    fn slow_frames_lower_the_scale() {
+        let mut dynamic_resolution = dynamic_resolution();
+
+        dynamic_resolution.update(TARGET * 2);
+        let scale = dynamic_resolution.scale;
+        assert!(scale < 1.0);
+
+        for _ in 0..100 {
+            dynamic_resolution.update(TARGET * 2);
+        }
+        assert!(dynamic_resolution.scale < scale);
+        assert_eq!(dynamic_resolution.scale, 0.5);
+    }
+
+    #[test]
+    fn fast_frames_restore_the_scale() {
+        let mut dynamic_resolution = dynamic_resolution();
+        dynamic_resolution.set_scale(0.5);
+
+        for _ in 0..100 {
+            dynamic_resolution.update(TARGET / 2);
+        }
+        assert_eq!(dynamic_resolution.scale, 1.0);
+    }
+
+    #[test]
+    fn on_target_frames_keep_the_scale() {
+        let mut dynamic_resolution = dynamic_resolution();
+        dynamic_resolution.set_scale(0.75);
+
+        for _ in 0..100 {
+            dynamic_resolution.update(TARGET);
+        }
+        assert_eq!(dynamic_resolution.scale, 0.75);
+    }
+
+    #[test]
+    fn disabled() {
+        let mut dynamic_resolution = DynamicResolution::new(DynamicResolutionParams::default());
+        assert!(!dynamic_resolution.native_ui());
+
+        dynamic_resolution.update(TARGET * 10);
+        assert_eq!(dynamic_resolution.scale, 1.0);
+    }
+}
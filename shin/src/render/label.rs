@@ -0,0 +1,218 @@
+//! A single line of text for the UI outside of the messagebox, like the voice captions.
+//!
+//! Unlike the messages, it is not revealed char-by-char and doesn't support any of the text commands.
+
+use std::sync::Arc;
+
+use glam::{Mat4, Vec2, vec2, vec4};
+use shin_core::{layout::font::FontMetrics as _, primitives::color::FloatColor4};
+use shin_render::{
+    ColorBlendType, DrawPrimitive, RenderProgramWithArguments, RenderRequestBuilder,
+    render_pass::RenderPass,
+    shaders::types::{
+        buffer::{OwnedVertexBuffer, VertexSource},
+        vertices::TextVertex,
+    },
+};
+
+use crate::{
+    asset::font::{GpuFontGlyphHandle, GpuFontLazy},
+    render::{PreRenderContext, top_left_projection_matrix},
+};
+
+const VERTICES_PER_CHARACTER: usize = 4;
+
+struct LabelChar {
+    glyph: GpuFontGlyphHandle,
+    vertex_buffer_offset: usize,
+    border_distances: [Vec2; 8],
+}
+
+pub struct Label {
+    text: String,
+    chars: Vec<LabelChar>,
+    vertex_buffer: Option<OwnedVertexBuffer<TextVertex>>,
+    size: Vec2,
+}
+
+impl Label {
+    /// Lay out the `text`, with the top left corner of the line at the origin
+    pub fn new(context: &PreRenderContext, font: &Arc<GpuFontLazy>, text: &str) -> Self {
+        let characters = text.chars().collect::<Vec<_>>();
+        let (device, queue) = (context.device.clone(), context.queue.clone());
+        let glyphs = font.clone().load_glyphs(device, queue, &characters);
+
+        let ascent = font.get_ascent() as f32;
+        let line_height = ascent + font.get_descent() as f32;
+
+        let mut pen_x = 0.0;
+        let mut chars = Vec::with_capacity(glyphs.len());
+        let mut vertices = Vec::with_capacity(VERTICES_PER_CHARACTER * glyphs.len());
+        for glyph in glyphs {
+            let info = *glyph.info();
+            let position = vec2(pen_x, ascent);
+            pen_x += info.advance_width_f32();
+
+            // nothing to draw for the whitespace
+            if info.actual_width == 0 || info.actual_height == 0 {
+                continue;
+            }
+
+            // the same 2 pixels of overdraw on all sides as in the messagebox, to fit the border
+            let size = info.actual_size_f32();
+            let top_left = position + info.bearing_screenspace_f32() - 2.0;
+            let bottom_right = top_left + size + 4.0;
+
+            let tex_overdraw_ratio = ((bottom_right - top_left) / size - 1.0) / 2.0;
+            let tex_top_left = -info.actual_size_normalized() * tex_overdraw_ratio;
+            let tex_bottom_right = info.actual_size_normalized() * (tex_overdraw_ratio + 1.0);
+
+            let corner = |screen: Vec2, tex: Vec2, color: f32| TextVertex {
+                position: vec4(screen.x, screen.y, tex.x, tex.y),
+                color,
+            };
+            vertices.extend([
+                corner(top_left, tex_top_left, 0.0),
+                corner(
+                    vec2(bottom_right.x, top_left.y),
+                    vec2(tex_bottom_right.x, tex_top_left.y),
+                    0.0,
+                ),
+                corner(
+                    vec2(top_left.x, bottom_right.y),
+                    vec2(tex_top_left.x, tex_bottom_right.y),
+                    1.0,
+                ),
+                corner(bottom_right, tex_bottom_right, 1.0),
+            ]);
+
+            // the border is 1.5 pixels wide in all the 8 directions, see `MessageLayer::set_message`
+            let distance = 1.5 / size * info.actual_size_normalized();
+            let border_distances = [
+                vec2(-1.0, -1.0),
+                vec2(0.0, -1.0),
+                vec2(1.0, -1.0),
+                vec2(-1.0, 0.0),
+                vec2(1.0, 0.0),
+                vec2(-1.0, 1.0),
+                vec2(0.0, 1.0),
+                vec2(1.0, 1.0),
+            ]
+            .map(|v| v.normalize() * distance);
+
+            chars.push(LabelChar {
+                glyph,
+                vertex_buffer_offset: chars.len() * VERTICES_PER_CHARACTER,
+                border_distances,
+            });
+        }
+
+        let vertex_buffer = (!vertices.is_empty()).then(|| {
+            OwnedVertexBuffer::allocate_vertex(context.device, &vertices, Some("Label/vtxbuf"))
+        });
+
+        Self {
+            text: text.to_string(),
+            chars,
+            vertex_buffer,
+            size: vec2(pen_x, line_height),
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Size of the line, in virtual canvas pixels
+    pub fn size(&self) -> Vec2 {
+        self.size
+    }
+
+    /// Draw the text with a black border, the top left corner of the line being at `position` on the virtual canvas
+    pub fn render(&self, pass: &mut RenderPass, position: Vec2, color: FloatColor4) {
+        let Some(vertex_buffer) = &self.vertex_buffer else {
+            return;
+        };
+
+        let transform = top_left_projection_matrix() * Mat4::from_translation(position.extend(0.0));
+        let builder = RenderRequestBuilder::new().color_blend_type(ColorBlendType::Layer1);
+        let vertices = |char: &LabelChar| VertexSource::VertexBuffer {
+            vertices: vertex_buffer
+                .as_sliced_buffer_ref(char.vertex_buffer_offset, VERTICES_PER_CHARACTER),
+        };
+
+        pass.push_debug("Label");
+        for char in &self.chars {
+            pass.run(builder.build(
+                RenderProgramWithArguments::FontBorder {
+                    vertices: vertices(char),
+                    glyph: char.glyph.as_texture_source(),
+                    transform,
+                    distances: char.border_distances,
+                    color: FloatColor4::from_rgba(0.0, 0.0, 0.0, color.a),
+                },
+                DrawPrimitive::TrianglesStrip,
+            ));
+        }
+        for char in &self.chars {
+            pass.run(builder.build(
+                RenderProgramWithArguments::Font {
+                    vertices: vertices(char),
+                    glyph: char.glyph.as_texture_source(),
+                    transform,
+                    color1: color,
+                    color2: color,
+                },
+                DrawPrimitive::TrianglesStrip,
+            ));
+        }
+        pass.pop_debug();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::vec2;
+    use shin_core::primitives::color::FloatColor4;
+    use winit::dpi::PhysicalSize;
+
+    use super::Label;
+    use crate::{
+        adv::test_utils::font,
+        render::test_utils::{TestRenderer, create_task_pools},
+    };
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn label() {
+        // 2 virtual pixels per pixel
+        let mut renderer = TestRenderer::new(PhysicalSize::new(960, 540));
+        // the glyphs are loaded in the background
+        create_task_pools();
+        let mut target = renderer.new_render_texture();
+
+        renderer.pre_render(|context| {
+            let label = Label::new(context, &font(), "ab c");
+            assert_eq!(label.text(), "ab c");
+            // the glyphs of the test font are 8 pixels wide, with the line being 16 pixels high
+            assert_eq!(label.size(), vec2(32.0, 16.0));
+
+            let mut pass = context.begin_pass(target.as_texture_target(), None, "label");
+            label.render(&mut pass, vec2(100.0, 100.0), FloatColor4::WHITE);
+        });
+        let image = renderer.read(&target);
+
+        let is_white = |x: f32, y: f32| {
+            let pixel = image.get_pixel(x as u32 / 2, y as u32 / 2).0;
+            pixel.iter().all(|&channel| channel > 250)
+        };
+        let is_transparent = |x: f32, y: f32| image.get_pixel(x as u32 / 2, y as u32 / 2).0[3] == 0;
+
+        // the glyphs span from 4 to 12 pixels below the top of the line
+        assert!(is_white(104.0, 108.0));
+        assert!(is_white(112.0, 108.0));
+        assert!(is_transparent(120.0, 108.0));
+        assert!(is_white(128.0, 108.0));
+        assert!(is_transparent(104.0, 130.0));
+    }
+}
@@ -9,13 +9,24 @@ use shin_render::{
         RenderCloneCtx,
         texture::{DepthStencilTarget, TextureSamplerStore, TextureTarget},
     },
-    shin_orthographic_projection_matrix,
 };
 use winit::dpi::PhysicalSize;
 
+pub mod anti_aliasing;
+// TODO: use the window conversions for the mouse input
+#[allow(unused)]
+pub mod coords;
+pub mod dynamic_resolution;
+pub mod label;
+pub mod overdraw;
 #[expect(unused)]
 pub mod overlay;
+pub mod pixel_snap;
+pub mod post_process;
 pub mod render_texture_holder;
+pub mod sprite;
+#[cfg(test)]
+pub mod test_utils;
 
 pub const VIRTUAL_CANVAS_SIZE: PhysicalSize<u32> = PhysicalSize::new(1920, 1080);
 pub const VIRTUAL_CANVAS_SIZE_VEC: glam::Vec2 = glam::vec2(
@@ -23,30 +34,19 @@ pub const VIRTUAL_CANVAS_SIZE_VEC: glam::Vec2 = glam::vec2(
     VIRTUAL_CANVAS_SIZE.height as f32,
 );
 
+/// Projects positions relative to the canvas center, see [`coords`]
 pub fn centered_projection_matrix() -> Mat4 {
-    shin_orthographic_projection_matrix(
-        -VIRTUAL_CANVAS_SIZE_VEC.x / 2.0,
-        VIRTUAL_CANVAS_SIZE_VEC.x / 2.0,
-        VIRTUAL_CANVAS_SIZE_VEC.y / 2.0,
-        -VIRTUAL_CANVAS_SIZE_VEC.y / 2.0,
-        -1.0,
-        1.0,
-    )
+    coords::centered_virtual_projection_matrix()
 }
 
+/// Projects [`coords::VirtualPos`] positions
 pub fn top_left_projection_matrix() -> Mat4 {
-    shin_orthographic_projection_matrix(
-        0.0,
-        VIRTUAL_CANVAS_SIZE_VEC.x,
-        VIRTUAL_CANVAS_SIZE_VEC.y,
-        0.0,
-        -1.0,
-        1.0,
-    )
+    coords::virtual_projection_matrix()
 }
 
+/// Projects positions relative to the canvas size, see [`coords`]
 pub fn normalized_projection_matrix() -> Mat4 {
-    shin_orthographic_projection_matrix(0.0, 1.0, 1.0, 0.0, -1.0, 1.0)
+    coords::normalized_virtual_projection_matrix()
 }
 
 pub struct PreRenderContext<'immutable, 'pipelines, 'dynbuffer, 'encoder> {
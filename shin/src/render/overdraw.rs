@@ -0,0 +1,184 @@
+use shin_core::primitives::color::UnormColor;
+use shin_render::{
+    ColorBlendType, DepthStencilState, DrawPrimitive, RenderProgramWithArguments,
+    RenderRequestBuilder, StencilFunction, StencilPipelineState, StencilState,
+    quad_vertices::build_quad_vertices,
+    render_pass::RenderPass,
+    shaders::types::{buffer::VertexSource, vertices::PosColVertex},
+};
+
+use crate::render::{VIRTUAL_CANVAS_SIZE_VEC, top_left_projection_matrix};
+
+/// Colors for the number of draws covering a pixel, starting from a single draw.
+///
+/// The last one is used for that many draws and more.
+pub const OVERDRAW_RAMP: [UnormColor; 6] = [
+    // dark blue
+    UnormColor(0xff800000),
+    // green
+    UnormColor(0xff00a000),
+    // yellow
+    UnormColor(0xff00e0e0),
+    // orange
+    UnormColor(0xff0080ff),
+    UnormColor::RED,
+    UnormColor::WHITE,
+];
+
+/// The stencil test passing for the pixels drawn to at least `level` times, see [`render_overdraw`]
+fn level_stencil(level: u8) -> DepthStencilState {
+    DepthStencilState {
+        stencil: StencilState {
+            pipeline: StencilPipelineState {
+                // passes when `level <= count`
+                function: StencilFunction::LessOrEqual,
+                ..Default::default()
+            },
+            stencil_reference: level,
+        },
+        ..Default::default()
+    }
+}
+
+/// Debug view showing how many times each pixel gets drawn to by `render`, to find where the fill rate goes.
+///
+/// The draws are counted in the stencil buffer (so the masks don't work) and the counts are then shown with [`OVERDRAW_RAMP`],
+/// black being never drawn to. Only the draws into the pass are counted, not the ones into the intermediate render textures.
+pub fn render_overdraw(pass: &mut RenderPass, render: impl FnOnce(&mut RenderPass)) {
+    pass.push_debug("Overdraw");
+
+    pass.clear(Some(UnormColor::BLACK), Some(0), None);
+    pass.set_overdraw_counting(true);
+    render(pass);
+    pass.set_overdraw_counting(false);
+
+    // every level paints over the previous ones where the count is high enough
+    for (level, &color) in (1..).zip(OVERDRAW_RAMP.iter()) {
+        pass.run(
+            RenderRequestBuilder::new()
+                .depth_stencil(level_stencil(level))
+                .color_blend_type(ColorBlendType::Opaque)
+                .build(
+                    RenderProgramWithArguments::Fill {
+                        vertices: VertexSource::VertexData {
+                            vertices: &build_quad_vertices(|t| PosColVertex {
+                                position: (t * VIRTUAL_CANVAS_SIZE_VEC).extend(0.0),
+                                color,
+                            }),
+                        },
+                        transform: top_left_projection_matrix(),
+                    },
+                    DrawPrimitive::TrianglesStrip,
+                ),
+        );
+    }
+
+    pass.pop_debug();
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{Vec4, vec4};
+    use image::RgbaImage;
+    use shin_core::{
+        primitives::color::{FloatColor4, UnormColor},
+        vm::command::types::LayerbankId,
+    };
+    use winit::dpi::PhysicalSize;
+
+    use super::{OVERDRAW_RAMP, render_overdraw};
+    use crate::{
+        layer::{
+            Layer as _, LayerGroup, render_layer_without_bg, render_params::TransformParams,
+            user::TileLayer,
+        },
+        render::test_utils::TestRenderer,
+    };
+
+    /// A tenth of the virtual canvas
+    fn renderer() -> TestRenderer {
+        TestRenderer::new(PhysicalSize::new(192, 108))
+    }
+
+    /// The overdraw of a group of white tiles with the `rects`, in the order they are drawn
+    fn overdraw_of_tiles(renderer: &mut TestRenderer, rects: &[Vec4]) -> RgbaImage {
+        let mut group = LayerGroup::new(None);
+        for (id, &rect) in (0..).zip(rects) {
+            group.add_layer(
+                LayerbankId::new(id),
+                TileLayer::new(FloatColor4::WHITE, rect),
+            );
+        }
+        let mut target = renderer.new_render_texture();
+
+        renderer.pre_render(|context| {
+            let transform = TransformParams::default();
+            group.pre_render(context, &transform);
+
+            let depth_stencil = context.depth_stencil;
+            let mut pass =
+                context.begin_pass(target.as_texture_target(), Some(depth_stencil), "overdraw");
+            render_overdraw(&mut pass, |pass| {
+                // the scene is cleared first, like in `AdvState::render`
+                pass.clear(Some(UnormColor::WHITE), Some(0), Some(1.0));
+                render_layer_without_bg(pass, &transform, &group, 0);
+            });
+        });
+
+        renderer.read(&target)
+    }
+
+    /// The overdraw color at the point on the virtual canvas, measured from the top left corner
+    fn color_at(image: &RgbaImage, x: u32, y: u32) -> UnormColor {
+        UnormColor(u32::from_le_bytes(image.get_pixel(x / 10, y / 10).0))
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn ramp() {
+        let mut renderer = renderer();
+        // the column `i` (240 virtual pixels wide) is covered by `i + 1` tiles, counting up past the end of the ramp
+        let rects = (0..8)
+            .map(|i| {
+                let left = i as f32 * 240.0;
+                vec4(left - 960.0, -540.0, 1920.0 - left, 1080.0)
+            })
+            .collect::<Vec<_>>();
+        let image = overdraw_of_tiles(&mut renderer, &rects);
+
+        for column in 0..8 {
+            let expected = OVERDRAW_RAMP[column.min(OVERDRAW_RAMP.len() - 1)];
+            let x = column as u32 * 240 + 120;
+            assert_eq!(color_at(&image, x, 540), expected, "column {}", column);
+        }
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn overlapping_layers() {
+        let mut renderer = renderer();
+        // a full-screen background with two overlapping 500x500 tiles over it,
+        // from (100, 100) and (400, 400) measured from the top left corner
+        let image = overdraw_of_tiles(&mut renderer, &[
+            vec4(-960.0, -540.0, 1920.0, 1080.0),
+            vec4(-860.0, -440.0, 500.0, 500.0),
+            vec4(-560.0, -140.0, 500.0, 500.0),
+        ]);
+
+        assert_eq!(color_at(&image, 50, 50), OVERDRAW_RAMP[0]);
+        assert_eq!(color_at(&image, 200, 200), OVERDRAW_RAMP[1]);
+        assert_eq!(color_at(&image, 500, 500), OVERDRAW_RAMP[2]);
+        assert_eq!(color_at(&image, 800, 800), OVERDRAW_RAMP[1]);
+        assert_eq!(color_at(&image, 1500, 900), OVERDRAW_RAMP[0]);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn nothing_drawn() {
+        let mut renderer = renderer();
+        // the clear doesn't count as a draw
+        let image = overdraw_of_tiles(&mut renderer, &[]);
+
+        assert_eq!(color_at(&image, 960, 540), UnormColor::BLACK);
+    }
+}
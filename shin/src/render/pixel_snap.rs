@@ -0,0 +1,124 @@
+use glam::{Mat2, Mat4, Vec2, Vec4Swizzles as _};
+
+/// Move `position` (in the space of `transform`) so that it lands on the closest pixel corner of a target of `pixel_size`.
+///
+/// The pixel grid is that of the actual render target, not of the virtual canvas, so this keeps the edges crisp at any canvas scale.
+/// Only the 2D part of the (affine) `transform` is taken into account. Degenerate transforms (e.g. a layer scaled to zero) are left alone.
+pub fn snap_to_pixel_grid(position: Vec2, transform: Mat4, pixel_size: Vec2) -> Vec2 {
+    let linear = Mat2::from_cols(transform.x_axis.xy(), transform.y_axis.xy());
+    let det = linear.determinant();
+    if !det.is_finite() || det == 0.0 {
+        return position;
+    }
+    let offset = transform.w_axis.xy();
+
+    let clip = linear * position + offset;
+    // the y axis is flipped between the clip space and the pixels, but it doesn't change where the pixel corners are
+    let pixel = (clip + 1.0) / 2.0 * pixel_size;
+    let snapped = pixel.round() / pixel_size * 2.0 - 1.0;
+
+    linear.inverse() * (snapped - offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{Mat4, Vec2, vec2, vec3};
+
+    use super::snap_to_pixel_grid;
+    use crate::render::{VIRTUAL_CANVAS_SIZE_VEC, centered_projection_matrix};
+
+    /// Where `position` ends up on a target of `pixel_size`, in pixels from the top left corner
+    fn to_pixels(position: Vec2, transform: Mat4, pixel_size: Vec2) -> Vec2 {
+        let clip = transform.project_point3(position.extend(0.0)).truncate();
+        (clip * vec2(1.0, -1.0) + 1.0) / 2.0 * pixel_size
+    }
+
+    fn is_on_grid(pixel: Vec2) -> bool {
+        pixel.abs_diff_eq(pixel.round(), 1e-3)
+    }
+
+    /// The corners of a 64x32 sprite centered on a layer placed at a fractional offset
+    fn sprite_corners() -> (Mat4, [Vec2; 4]) {
+        let transform =
+            centered_projection_matrix() * Mat4::from_translation(vec3(100.3, -20.7, 0.0));
+        let corners = [
+            vec2(-32.0, -16.0),
+            vec2(32.0, -16.0),
+            vec2(-32.0, 16.0),
+            vec2(32.0, 16.0),
+        ];
+        (transform, corners)
+    }
+
+    #[test]
+    fn sprite_at_fractional_offset_lands_on_pixels() {
+        let (transform, corners) = sprite_corners();
+
+        for corner in corners {
+            assert!(!is_on_grid(to_pixels(
+                corner,
+                transform,
+                VIRTUAL_CANVAS_SIZE_VEC
+            )));
+
+            let snapped = snap_to_pixel_grid(corner, transform, VIRTUAL_CANVAS_SIZE_VEC);
+            let pixel = to_pixels(snapped, transform, VIRTUAL_CANVAS_SIZE_VEC);
+            assert!(is_on_grid(pixel), "{} is not on the pixel grid", pixel);
+            // and it moved by less than half a pixel
+            assert!((snapped - corner).abs().max_element() <= 0.5 + 1e-3);
+        }
+    }
+
+    #[test]
+    fn snaps_to_the_target_pixels() {
+        let (transform, corners) = sprite_corners();
+
+        // the canvas is rendered at a different resolution than the virtual one, it's the actual pixels that count
+        for pixel_size in [vec2(1280.0, 720.0), vec2(2560.0, 1440.0)] {
+            for corner in corners {
+                let snapped = snap_to_pixel_grid(corner, transform, pixel_size);
+                let pixel = to_pixels(snapped, transform, pixel_size);
+                assert!(is_on_grid(pixel), "{} is not on the pixel grid", pixel);
+            }
+        }
+    }
+
+    #[test]
+    fn size_is_kept() {
+        let (transform, corners) = sprite_corners();
+        let snapped =
+            corners.map(|corner| snap_to_pixel_grid(corner, transform, VIRTUAL_CANVAS_SIZE_VEC));
+
+        // the whole sprite is moved, so it doesn't get stretched by a pixel
+        assert!((snapped[1] - snapped[0]).abs_diff_eq(vec2(64.0, 0.0), 1e-3));
+        assert!((snapped[2] - snapped[0]).abs_diff_eq(vec2(0.0, 32.0), 1e-3));
+    }
+
+    #[test]
+    fn degenerate_transform_is_ignored() {
+        let position = vec2(10.3, 5.5);
+
+        for scale in [vec3(0.0, 1.0, 1.0), vec3(f32::NAN, 1.0, 1.0)] {
+            let transform = centered_projection_matrix() * Mat4::from_scale(scale);
+            assert_eq!(
+                snap_to_pixel_grid(position, transform, VIRTUAL_CANVAS_SIZE_VEC),
+                position
+            );
+        }
+    }
+
+    #[test]
+    fn flat_z_is_not_degenerate() {
+        // only the 2D part matters, a layer flattened along z is still snapped
+        let transform = centered_projection_matrix()
+            * Mat4::from_scale(vec3(1.0, 1.0, 0.0))
+            * Mat4::from_translation(vec3(100.3, -20.7, 0.0));
+
+        let snapped = snap_to_pixel_grid(Vec2::ZERO, transform, VIRTUAL_CANVAS_SIZE_VEC);
+        assert!(is_on_grid(to_pixels(
+            snapped,
+            transform,
+            VIRTUAL_CANVAS_SIZE_VEC
+        )));
+    }
+}
@@ -0,0 +1,541 @@
+use glam::{Mat3, Vec3, Vec4, vec3};
+use shin_render::{
+    DrawPrimitive, RenderProgramWithArguments, RenderRequestBuilder, render_pass::RenderPass,
+    shaders::types::buffer::VertexSource,
+};
+
+use crate::render::{
+    PreRenderContext, normalized_projection_matrix, render_texture_holder::RenderTextureHolder,
+};
+
+/// Simulation matrices for the dichromacies, operating on linear RGB.
+///
+/// From "A Physiologically-based Model for Simulation of Color Vision Deficiency" by Machado et al. (2009), at severity 1.0.
+const PROTANOPIA: [[f32; 3]; 3] = [
+    [0.152286, 1.052583, -0.204868],
+    [0.114503, 0.786281, 0.099216],
+    [-0.003882, -0.048116, 1.051998],
+];
+const DEUTERANOPIA: [[f32; 3]; 3] = [
+    [0.367322, 0.860646, -0.227968],
+    [0.280085, 0.672501, 0.047413],
+    [-0.011820, 0.042940, 0.968881],
+];
+const TRITANOPIA: [[f32; 3]; 3] = [
+    [1.255528, -0.076749, -0.178779],
+    [-0.078411, 0.930809, 0.147602],
+    [0.004733, 0.691367, 0.303900],
+];
+
+/// Redistribute the colors lost by the simulated vision into the channels that are still seen (the daltonization error matrices).
+///
+/// The red-green deficiencies lose the red, which is shifted into green & blue.
+const RED_ERROR_SHIFT: [[f32; 3]; 3] = [[0.0, 0.0, 0.0], [0.7, 1.0, 0.0], [0.7, 0.0, 1.0]];
+/// Tritanopia loses the blue, which is shifted into red & green instead.
+const BLUE_ERROR_SHIFT: [[f32; 3]; 3] = [[1.0, 0.0, 0.7], [0.0, 1.0, 0.7], [0.0, 0.0, 0.0]];
+
+fn from_rows(rows: [[f32; 3]; 3]) -> Mat3 {
+    Mat3::from_cols_array_2d(&rows).transpose()
+}
+
+/// Filters for the color vision deficiencies.
+///
+/// The plain variants correct (daltonize) the image, making the colors distinguishable for people with the respective deficiency.
+/// The `simulate-` ones show how the image is seen with the deficiency.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum ColorFilter {
+    #[default]
+    Off,
+    Protanopia,
+    Deuteranopia,
+    Tritanopia,
+    SimulateProtanopia,
+    SimulateDeuteranopia,
+    SimulateTritanopia,
+}
+
+impl ColorFilter {
+    /// The matrix applied to the linear colors
+    pub fn matrix(&self) -> Mat3 {
+        let daltonize = |simulation: [[f32; 3]; 3], error_shift: [[f32; 3]; 3]| {
+            let lost = Mat3::IDENTITY - from_rows(simulation);
+            Mat3::IDENTITY + from_rows(error_shift) * lost
+        };
+
+        match self {
+            ColorFilter::Off => Mat3::IDENTITY,
+            ColorFilter::Protanopia => daltonize(PROTANOPIA, RED_ERROR_SHIFT),
+            ColorFilter::Deuteranopia => daltonize(DEUTERANOPIA, RED_ERROR_SHIFT),
+            ColorFilter::Tritanopia => daltonize(TRITANOPIA, BLUE_ERROR_SHIFT),
+            ColorFilter::SimulateProtanopia => from_rows(PROTANOPIA),
+            ColorFilter::SimulateDeuteranopia => from_rows(DEUTERANOPIA),
+            ColorFilter::SimulateTritanopia => from_rows(TRITANOPIA),
+        }
+    }
+}
+
+fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Fading of the image edges to a color.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Vignette {
+    /// How far the edges are faded to the color, from 0 (no vignette) to 1
+    pub strength: f32,
+    /// Distance from the center at which the fading starts, 1 being the distance to the corners
+    pub radius: f32,
+    /// Distance over which the fading ramps up to the full strength
+    pub softness: f32,
+    /// The color to fade to, in sRGB
+    pub color: Vec3,
+}
+
+impl Default for Vignette {
+    fn default() -> Self {
+        Self {
+            strength: 0.0,
+            radius: 0.5,
+            softness: 0.5,
+            color: Vec3::ZERO,
+        }
+    }
+}
+
+impl Vignette {
+    pub fn is_noop(&self) -> bool {
+        self.strength <= 0.0
+    }
+
+    fn linear_color(&self) -> Vec3 {
+        self.color.map(srgb_to_linear)
+    }
+}
+
+/// Parses a `RRGGBB` hex color (with an optional `#`) into sRGB components.
+pub fn parse_hex_color(value: &str) -> Result<Vec3, String> {
+    let hex = value.strip_prefix('#').unwrap_or(value);
+    if hex.len() != 6 {
+        return Err(format!(
+            "expected a color in the RRGGBB format, got {:?}",
+            value
+        ));
+    }
+    let rgb = u32::from_str_radix(hex, 16).map_err(|e| e.to_string())?;
+
+    let channel = |shift: u32| ((rgb >> shift) & 0xff) as f32 / 255.0;
+    Ok(vec3(channel(16), channel(8), channel(0)))
+}
+
+/// Display adjustments applied to the final image.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PostProcessParams {
+    /// The colors are raised to the power of `1 / gamma`, values above 1 make the image brighter.
+    ///
+    /// It has to be positive, the smaller values are clamped to 0.01.
+    pub gamma: f32,
+    /// Multiplier applied after the gamma curve
+    pub brightness: f32,
+    pub color_filter: ColorFilter,
+    pub vignette: Vignette,
+}
+
+impl Default for PostProcessParams {
+    fn default() -> Self {
+        Self {
+            gamma: 1.0,
+            brightness: 1.0,
+            color_filter: ColorFilter::Off,
+            vignette: Vignette::default(),
+        }
+    }
+}
+
+impl PostProcessParams {
+    pub fn is_identity(&self) -> bool {
+        let default = Self::default();
+
+        self.gamma == default.gamma
+            && self.brightness == default.brightness
+            && self.color_filter == default.color_filter
+            && self.vignette.is_noop()
+    }
+}
+
+/// The final pass, adjusting the composited image before it gets presented.
+///
+/// With the identity parameters the scene is rendered straight to the screen, so the output is exactly the same as without the pass.
+pub struct PostProcess {
+    params: PostProcessParams,
+    render_texture: RenderTextureHolder,
+}
+
+impl PostProcess {
+    pub fn new(params: PostProcessParams) -> Self {
+        Self {
+            params,
+            render_texture: RenderTextureHolder::new("PostProcess"),
+        }
+    }
+
+    /// Renders the scene into an offscreen texture, to be adjusted in [`Self::render`].
+    pub fn pre_render(
+        &mut self,
+        context: &mut PreRenderContext,
+        render: impl FnOnce(&mut RenderPass),
+    ) {
+        if self.params.is_identity() {
+            self.render_texture.clear();
+            return;
+        }
+
+        let render_texture = self.render_texture.get_or_init(context);
+        let depth_stencil = context.depth_stencil;
+        let mut pass = context.begin_pass(
+            render_texture.as_texture_target(),
+            Some(depth_stencil),
+            "PostProcess/scene",
+        );
+
+        render(&mut pass);
+    }
+
+    /// Draws the adjusted scene, or calls `render_direct` if there is nothing to adjust.
+    pub fn render(&self, pass: &mut RenderPass, render_direct: impl FnOnce(&mut RenderPass)) {
+        let Some(render_texture) = self.render_texture.get() else {
+            render_direct(pass);
+            return;
+        };
+
+        let quad = pass.geometry_cache().unit_quad();
+        pass.run(RenderRequestBuilder::new().build(
+            RenderProgramWithArguments::PostProcess {
+                vertices: VertexSource::VertexBuffer {
+                    vertices: quad.as_buffer_ref(),
+                },
+                texture: render_texture.as_texture_source(),
+                transform: normalized_projection_matrix(),
+                color_matrix: color_matrix_rows(self.params.color_filter.matrix()),
+                gamma: self.params.gamma,
+                brightness: self.params.brightness,
+                vignette_radius: self.params.vignette.radius,
+                vignette_softness: self.params.vignette.softness,
+                vignette_strength: self.params.vignette.strength,
+                vignette_color: self.params.vignette.linear_color(),
+            },
+            DrawPrimitive::TrianglesStrip,
+        ));
+    }
+}
+
+fn color_matrix_rows(matrix: Mat3) -> [Vec4; 3] {
+    [0, 1, 2].map(|i| matrix.row(i).extend(0.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{Mat3, Vec3, vec3};
+    use image::{Rgba, RgbaImage};
+    use shin_render::{PassKind, RenderRequestBuilder, render_pass::RenderPass};
+    use winit::dpi::PhysicalSize;
+
+    use super::{ColorFilter, PostProcess, PostProcessParams, Vignette, parse_hex_color};
+    use crate::render::{sprite::Sprite, test_utils::TestRenderer};
+
+    fn filter(color_filter: ColorFilter) -> PostProcessParams {
+        PostProcessParams {
+            color_filter,
+            ..Default::default()
+        }
+    }
+
+    /// 10 virtual pixels per pixel
+    const CANVAS_SIZE: PhysicalSize<u32> = PhysicalSize::new(192, 108);
+
+    /// Runs the post-processing over `image`, stretched over the whole canvas
+    fn post_process_on_gpu(params: PostProcessParams, image: &RgbaImage) -> RgbaImage {
+        let mut renderer = TestRenderer::new(CANVAS_SIZE);
+        let texture = renderer.upload(image);
+        let mut post_process = PostProcess::new(params);
+        let mut target = renderer.new_render_texture();
+
+        let render_scene = |pass: &mut RenderPass| {
+            Sprite::full_canvas(texture.as_source()).render(
+                pass,
+                RenderRequestBuilder::new(),
+                PassKind::Opaque,
+            )
+        };
+        renderer.pre_render(|context| {
+            post_process.pre_render(context, render_scene);
+
+            let mut pass = context.begin_pass(target.as_texture_target(), None, "post_process");
+            post_process.render(&mut pass, render_scene);
+        });
+
+        renderer.read(&target)
+    }
+
+    /// Post-processes a flat `color`, returning the resulting colors at the `positions` on the canvas (in pixels)
+    fn post_process_color_at<const N: usize>(
+        params: PostProcessParams,
+        color: [u8; 3],
+        positions: [(u32, u32); N],
+    ) -> [[u8; 3]; N] {
+        let [r, g, b] = color;
+        let image = RgbaImage::from_pixel(16, 16, Rgba([r, g, b, 255]));
+        let result = post_process_on_gpu(params, &image);
+
+        positions.map(|(x, y)| {
+            let [r, g, b, _] = result.get_pixel(x, y).0;
+            [r, g, b]
+        })
+    }
+
+    /// The center pixel of a post-processed flat `color`
+    fn post_process_color(params: PostProcessParams, color: [u8; 3]) -> [u8; 3] {
+        let [center] = post_process_color_at(params, color, [CENTER]);
+        center
+    }
+
+    const CENTER: (u32, u32) = (CANVAS_SIZE.width / 2, CANVAS_SIZE.height / 2);
+    const CORNERS: [(u32, u32); 4] = [
+        (0, 0),
+        (CANVAS_SIZE.width - 1, 0),
+        (0, CANVAS_SIZE.height - 1),
+        (CANVAS_SIZE.width - 1, CANVAS_SIZE.height - 1),
+    ];
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn identity() {
+        let params = PostProcessParams::default();
+        assert!(params.is_identity());
+
+        // the pass is skipped altogether, so the image is the same as without it
+        let gradient = RgbaImage::from_fn(256, 1, |x, _| Rgba([x as u8, x as u8, x as u8, 255]));
+        let result = post_process_on_gpu(params, &gradient);
+        let mut renderer = TestRenderer::new(CANVAS_SIZE);
+        let direct = renderer.render_texture_from_image(&gradient);
+        assert_eq!(result, renderer.read(&direct));
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn orientation_is_kept() {
+        let params = PostProcessParams {
+            gamma: 2.2,
+            ..Default::default()
+        };
+        let quadrants = [
+            [255, 0, 0, 255],
+            [0, 255, 0, 255],
+            [0, 0, 255, 255],
+            [255; 4],
+        ];
+        // the pure colors are not changed by the gamma, only moved around if the pass gets flipped
+        let image = RgbaImage::from_fn(2, 2, |x, y| Rgba(quadrants[(y * 2 + x) as usize]));
+        let result = post_process_on_gpu(params, &image);
+
+        let (width, height) = (CANVAS_SIZE.width, CANVAS_SIZE.height);
+        let centers = [
+            (width / 4, height / 4),
+            (width * 3 / 4, height / 4),
+            (width / 4, height * 3 / 4),
+            (width * 3 / 4, height * 3 / 4),
+        ];
+        for (&(x, y), expected) in centers.iter().zip(quadrants) {
+            assert_eq!(result.get_pixel(x, y).0, expected, "at ({}, {})", x, y);
+        }
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn gamma_on_gray() {
+        let params = PostProcessParams {
+            gamma: 2.2,
+            ..Default::default()
+        };
+        assert!(!params.is_identity());
+
+        // 0.5 ^ (1 / 2.2)
+        let result = post_process_color(params, [128; 3]);
+        assert_eq!(result, [186; 3]);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn nonpositive_gamma_is_clamped() {
+        for gamma in [0.0, -1.0] {
+            let params = PostProcessParams {
+                gamma,
+                ..Default::default()
+            };
+
+            // everything below white is crushed to black, instead of turning into NaNs
+            let white = post_process_color(params, [255; 3]);
+            assert_eq!(white, [255; 3]);
+            assert_eq!(post_process_color(params, [128; 3]), Some([0; 3]));
+        }
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn brightness_clamps() {
+        let params = PostProcessParams {
+            brightness: 1.5,
+            ..Default::default()
+        };
+
+        let gray = post_process_color(params, [128; 3]);
+        assert_eq!(gray, [192; 3]);
+        assert_eq!(post_process_color(params, [255; 3]), Some([255; 3]));
+    }
+
+    /// The GPU may round the channels differently
+    fn assert_color_near(actual: [u8; 3], expected: [u8; 3]) {
+        let near = (0..3).all(|i| actual[i].abs_diff(expected[i]) <= 1);
+        assert!(near, "{:?} != {:?}", actual, expected);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn color_filter_off() {
+        assert_eq!(ColorFilter::Off.matrix(), Mat3::IDENTITY);
+        assert!(filter(ColorFilter::Off).is_identity());
+        assert!(!filter(ColorFilter::Protanopia).is_identity());
+
+        // should the pass run for something else, the sRGB round-trip doesn't lose anything either
+        let mut params = vignette(1.0);
+        params.vignette.radius = 2.0;
+        let all_values = RgbaImage::from_fn(16, 16, |x, y| {
+            let value = (y * 16 + x) as u8;
+            Rgba([value, value, value, 255])
+        });
+        let result = post_process_on_gpu(params, &all_values);
+        let mut renderer = TestRenderer::new(CANVAS_SIZE);
+        let direct = renderer.render_texture_from_image(&all_values);
+        assert_eq!(result, renderer.read(&direct));
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn protanopia_on_red_swatch() {
+        // in linear space, pure red becomes (0.152286, 0.114503, -0.003882)
+        let simulated = post_process_color(filter(ColorFilter::SimulateProtanopia), [255, 0, 0]);
+        assert_color_near(simulated, [109, 95, 0]);
+
+        // the lost red is shifted into green & blue
+        let corrected = post_process_color(filter(ColorFilter::Protanopia), [255, 0, 0]).unwrap();
+        assert_color_near(corrected, [255, 184, 203]);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn tritanopia_on_blue_swatch() {
+        // in linear space, pure blue becomes (-0.178779, 0.147602, 0.303900)
+        let simulated = post_process_color(filter(ColorFilter::SimulateTritanopia), [0, 0, 255]);
+        assert_color_near(simulated, [0, 107, 150]);
+
+        // the lost blue is shifted into red & green, the blue channel itself is kept
+        let corrected = post_process_color(filter(ColorFilter::Tritanopia), [0, 0, 255]).unwrap();
+        assert_color_near(corrected, [213, 158, 255]);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn grays_unaffected() {
+        // all the matrices preserve the achromatic colors
+        for color_filter in [
+            ColorFilter::Protanopia,
+            ColorFilter::Deuteranopia,
+            ColorFilter::Tritanopia,
+            ColorFilter::SimulateProtanopia,
+            ColorFilter::SimulateDeuteranopia,
+            ColorFilter::SimulateTritanopia,
+        ] {
+            let white = color_filter.matrix() * vec3(1.0, 1.0, 1.0);
+            assert!(
+                white.abs_diff_eq(Vec3::ONE, 1e-5),
+                "{:?}: {}",
+                color_filter,
+                white
+            );
+
+            let gray = post_process_color(filter(color_filter), [128; 3]);
+            assert_color_near(gray, [128; 3]);
+        }
+    }
+
+    fn vignette(strength: f32) -> PostProcessParams {
+        PostProcessParams {
+            vignette: Vignette {
+                strength,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn zero_vignette() {
+        assert!(vignette(0.0).is_identity());
+        assert!(!vignette(0.5).is_identity());
+
+        let colors = post_process_color_at(vignette(0.0), [200; 3], CORNERS);
+        assert_eq!(colors, [[200; 3]; 4]);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn vignette_darkens_corners() {
+        let params = vignette(1.0);
+        let gray = [200; 3];
+
+        let edge = (0, CANVAS_SIZE.height / 2);
+        let [center, edge, corners @ ..] = post_process_color_at(params, gray, [
+            CENTER, edge, CORNERS[0], CORNERS[1], CORNERS[2], CORNERS[3],
+        ]);
+        assert_color_near(center, gray);
+        for corner in corners {
+            assert_color_near(corner, [0; 3]);
+        }
+
+        // the edge midpoints are in the ramp
+        assert!(edge[0] > 0 && edge[0] < center[0], "{:?}", edge);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn vignette_in_linear_space() {
+        // half-way to black is half of the light, not half of the encoded value
+        let mut params = vignette(0.5);
+        params.vignette.radius = 0.0;
+        params.vignette.softness = 0.0;
+
+        let [corner] = post_process_color_at(params, [255; 3], [CORNERS[0]]);
+        assert_color_near(corner, [188; 3]);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn vignette_color() {
+        assert_eq!(
+            parse_hex_color("#ff8000"),
+            Ok(vec3(1.0, 128.0 / 255.0, 0.0))
+        );
+        assert_eq!(parse_hex_color("FFFFFF"), Ok(Vec3::ONE));
+        assert!(parse_hex_color("fff").is_err());
+        assert!(parse_hex_color("#gg0000").is_err());
+
+        let mut params = vignette(1.0);
+        params.vignette.color = Vec3::ONE;
+        let [corner] = post_process_color_at(params, [0; 3], [CORNERS[3]]);
+        assert_color_near(corner, [255; 3]);
+    }
+}
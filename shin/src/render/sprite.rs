@@ -0,0 +1,554 @@
+use glam::{Mat4, Vec2, Vec4, Vec4Swizzles as _, vec2, vec4};
+use shin_core::primitives::color::FloatColor4;
+use shin_render::{
+    ColorBlendType, DrawPrimitive, LayerBlendType, LayerFragmentShader, LayerShaderOutputKind,
+    PassKind, RenderProgramWithArguments, RenderRequestBuilder,
+    quad_vertices::build_quad_vertices,
+    render_pass::RenderPass,
+    shaders::types::{
+        buffer::{OwnedIndexBuffer, OwnedVertexBuffer, VertexSource},
+        texture::TextureSource,
+        vertices::{PosColVertex, PosTexVertex},
+    },
+};
+
+use crate::render::{
+    VIRTUAL_CANVAS_SIZE_VEC, pixel_snap::snap_to_pixel_grid, top_left_projection_matrix,
+};
+
+#[derive(Debug, Copy, Clone)]
+enum Geometry<'a> {
+    /// A single quad, drawn as a triangle strip
+    Quad([PosTexVertex; 4]),
+    /// Indexed triangles, drawing `count` indices starting at `offset`
+    Mesh {
+        vertices: &'a OwnedVertexBuffer<PosTexVertex>,
+        indices: &'a OwnedIndexBuffer,
+        offset: usize,
+        count: usize,
+    },
+}
+
+#[derive(Debug, Copy, Clone)]
+enum Fill<'a> {
+    /// The texture has premultiplied alpha, like the prerendered layers
+    PremultipliedTexture(TextureSource<'a>),
+    /// The texture has straight alpha, which is premultiplied in the shader, like the pictures
+    StraightTexture(TextureSource<'a>),
+    /// No texture, just the color multiplier (with straight alpha)
+    Solid,
+}
+
+/// A textured quad drawn with the layer shader.
+///
+/// Takes care of the vertex data and the pass-dependent blending, which every layer drawing a texture needs.
+/// The pictures draw their blocks as meshes, and the tile layers use the solid sprites.
+#[derive(Debug, Copy, Clone)]
+pub struct Sprite<'a> {
+    fill: Fill<'a>,
+    /// The vertices, in the space of `transform`
+    geometry: Geometry<'a>,
+    transform: Mat4,
+    /// Color multiplier, premultiplied unless the sprite is solid
+    color: FloatColor4,
+    blend_type: LayerBlendType,
+    fragment_shader: LayerFragmentShader,
+    fragment_shader_param: Vec4,
+    /// Distance between the taps of the small blur, in texture coordinates
+    blur_step: Vec2,
+}
+
+impl<'a> Sprite<'a> {
+    fn from_parts(fill: Fill<'a>, geometry: Geometry<'a>, transform: Mat4) -> Self {
+        Self {
+            fill,
+            geometry,
+            transform,
+            color: FloatColor4::WHITE,
+            blend_type: LayerBlendType::Type1,
+            fragment_shader: LayerFragmentShader::Default,
+            fragment_shader_param: Vec4::ZERO,
+            blur_step: Vec2::ZERO,
+        }
+    }
+
+    /// Stretch the whole `texture` over `rect` (x, y, width, height)
+    pub fn new(texture: TextureSource<'a>, rect: Vec4, transform: Mat4) -> Self {
+        Self::from_parts(
+            Fill::PremultipliedTexture(texture),
+            Geometry::Quad(quad_vertices(rect)),
+            transform,
+        )
+    }
+
+    /// Fill `rect` (x, y, width, height) with the `color`, which has straight alpha
+    pub fn solid(color: FloatColor4, rect: Vec4, transform: Mat4) -> Self {
+        Self::from_parts(Fill::Solid, Geometry::Quad(quad_vertices(rect)), transform)
+            .with_color(color)
+    }
+
+    /// Draw the triangles of the `indices` range from `offset`, `count` long, with the straight alpha `texture`
+    pub fn mesh(
+        texture: TextureSource<'a>,
+        vertices: &'a OwnedVertexBuffer<PosTexVertex>,
+        indices: &'a OwnedIndexBuffer,
+        (offset, count): (usize, usize),
+        transform: Mat4,
+    ) -> Self {
+        Self::from_parts(
+            Fill::StraightTexture(texture),
+            Geometry::Mesh {
+                vertices,
+                indices,
+                offset,
+                count,
+            },
+            transform,
+        )
+    }
+
+    /// Cover the whole virtual canvas with `texture`, used to composite the prerendered layers
+    pub fn full_canvas(texture: TextureSource<'a>) -> Self {
+        Self::new(
+            texture,
+            vec4(
+                0.0,
+                0.0,
+                VIRTUAL_CANVAS_SIZE_VEC.x,
+                VIRTUAL_CANVAS_SIZE_VEC.y,
+            ),
+            top_left_projection_matrix(),
+        )
+    }
+
+    /// Cover `area` (x, y, width, height, in the space of `transform`) with the part of a canvas-sized `texture` that ends up under it.
+    ///
+    /// The prerendered layer textures are already in screen space, so this is how they are composited with clipping.
+    pub fn canvas_region(texture: TextureSource<'a>, area: Vec4, transform: Mat4) -> Self {
+        Self::from_parts(
+            Fill::PremultipliedTexture(texture),
+            Geometry::Quad(canvas_region_vertices(area, transform)),
+            transform,
+        )
+    }
+
+    /// Apply `f` to the vertices of a quad. The meshes are left as they are.
+    fn map_quad(mut self, f: impl FnMut(PosTexVertex) -> PosTexVertex) -> Self {
+        if let Geometry::Quad(vertices) = &mut self.geometry {
+            *vertices = vertices.map(f);
+        }
+        self
+    }
+
+    /// Only show the `region` (left, top, right, bottom, in texture coordinates) of the texture, used for the sprite sheets
+    pub fn with_texture_region(self, region: Vec4) -> Self {
+        self.map_quad(|vertex| texture_region_vertex(vertex, region))
+    }
+
+    /// Set the color multiplier. It has to be premultiplied, except for the solid sprites.
+    pub fn with_color(mut self, color: FloatColor4) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Move the sprite so that its origin lands on the pixel grid of a target of `pixel_size` (see [`RenderPass::pixel_size`]), so that it doesn't shimmer at fractional positions.
+    ///
+    /// The whole sprite is moved by the same amount, so it keeps its size (and the texels keep their spacing).
+    pub fn with_pixel_snap(mut self, pixel_size: Vec2) -> Self {
+        let origin = snap_to_pixel_grid(Vec2::ZERO, self.transform, pixel_size);
+        self.transform *= Mat4::from_translation(origin.extend(0.0));
+        self
+    }
+
+    pub fn with_blend_type(mut self, blend_type: LayerBlendType) -> Self {
+        self.blend_type = blend_type;
+        self
+    }
+
+    pub fn with_fragment_shader(mut self, shader: LayerFragmentShader, param: Vec4) -> Self {
+        self.fragment_shader = shader;
+        self.fragment_shader_param = param;
+        self
+    }
+
+    /// Blur the texture with a fixed 5x5 box kernel while sampling it, the taps being `step` apart (in texture coordinates).
+    ///
+    /// Only meant for small steps, the larger blurs are done in separate passes, see `NewDrawableLayerState::pre_render`.
+    pub fn with_blur(mut self, step: Vec2) -> Self {
+        debug_assert!(
+            matches!(self.fill, Fill::PremultipliedTexture(_)),
+            "Only the premultiplied textures can be blurred"
+        );
+        self.blur_step = step;
+        self
+    }
+
+    /// Draw the sprite in the `pass_kind` pass. The depth & stencil state is taken from `builder`.
+    pub fn render(
+        &self,
+        pass: &mut RenderPass,
+        builder: RenderRequestBuilder,
+        pass_kind: PassKind,
+    ) {
+        let builder =
+            builder.color_blend_type(color_blend_type(self.fill, self.blend_type, pass_kind));
+
+        let texture = match self.fill {
+            Fill::PremultipliedTexture(texture) | Fill::StraightTexture(texture) => texture,
+            Fill::Solid => {
+                self.render_solid(pass, builder);
+                return;
+            }
+        };
+
+        let (vertices, primitive) = match &self.geometry {
+            Geometry::Quad(vertices) => (
+                VertexSource::VertexData { vertices },
+                DrawPrimitive::TrianglesStrip,
+            ),
+            &Geometry::Mesh {
+                vertices,
+                indices,
+                offset,
+                count,
+            } => (
+                VertexSource::VertexAndIndexBuffer {
+                    vertices: vertices.as_buffer_ref(),
+                    indices: indices.as_sliced_buffer_ref(offset, count),
+                },
+                DrawPrimitive::Triangles,
+            ),
+        };
+
+        pass.run(builder.build(
+            RenderProgramWithArguments::Layer {
+                output_kind: output_kind(self.fill, pass_kind),
+                fragment_shader: self.fragment_shader,
+                vertices,
+                texture,
+                transform: self.transform,
+                color_multiplier: self.color,
+                fragment_shader_param: self.fragment_shader_param,
+                blur_step: self.blur_step,
+            },
+            primitive,
+        ));
+    }
+
+    /// There is no texture to sample, so the fragment shader is applied to the color right away
+    fn render_solid(&self, pass: &mut RenderPass, builder: RenderRequestBuilder) {
+        let Geometry::Quad(vertices) = &self.geometry else {
+            unreachable!("The solid sprites are always quads");
+        };
+
+        let color = self
+            .fragment_shader
+            .evaluate(self.color, self.fragment_shader_param)
+            .into_unorm();
+        let vertices = &vertices.map(|vertex| PosColVertex {
+            position: vertex.position.extend(0.0),
+            color,
+        });
+
+        pass.run(builder.build(
+            RenderProgramWithArguments::Fill {
+                vertices: VertexSource::VertexData { vertices },
+                transform: self.transform,
+            },
+            DrawPrimitive::TrianglesStrip,
+        ));
+    }
+}
+
+fn quad_vertices(rect: Vec4) -> [PosTexVertex; 4] {
+    let origin = vec2(rect.x, rect.y);
+    let size = vec2(rect.z, rect.w);
+
+    build_quad_vertices(|t| PosTexVertex {
+        position: origin + t * size,
+        texture_position: t,
+    })
+}
+
+fn canvas_region_vertices(area: Vec4, transform: Mat4) -> [PosTexVertex; 4] {
+    // the texture covers the whole canvas, so its coordinates are the canvas position of the vertex
+    let to_canvas = top_left_projection_matrix().inverse() * transform;
+    let origin = vec2(area.x, area.y);
+    let size = vec2(area.z, area.w);
+
+    build_quad_vertices(|t| {
+        let position = origin + t * size;
+        let canvas_position = to_canvas.transform_point3(position.extend(0.0)).truncate();
+
+        PosTexVertex {
+            position,
+            texture_position: canvas_position / VIRTUAL_CANVAS_SIZE_VEC,
+        }
+    })
+}
+
+/// Maps the texture position of the `vertex` from the whole texture to `region`
+fn texture_region_vertex(vertex: PosTexVertex, region: Vec4) -> PosTexVertex {
+    let (top_left, bottom_right) = (region.xy(), region.zw());
+
+    PosTexVertex {
+        texture_position: top_left + vertex.texture_position * (bottom_right - top_left),
+        ..vertex
+    }
+}
+
+fn color_blend_type(fill: Fill, blend_type: LayerBlendType, pass_kind: PassKind) -> ColorBlendType {
+    match (pass_kind, fill) {
+        (PassKind::Opaque, _) => ColorBlendType::Opaque,
+        (PassKind::Transparent, Fill::Solid) => ColorBlendType::from_regular_layer(blend_type),
+        (PassKind::Transparent, Fill::PremultipliedTexture(_) | Fill::StraightTexture(_)) => {
+            ColorBlendType::from_premultiplied_layer(blend_type)
+        }
+    }
+}
+
+fn output_kind(fill: Fill, pass_kind: PassKind) -> LayerShaderOutputKind {
+    match (fill, pass_kind) {
+        (Fill::StraightTexture(_), _) => LayerShaderOutputKind::LayerPremultiply,
+        (_, PassKind::Opaque) => LayerShaderOutputKind::Layer,
+        // transparent fragments are discarded, so that they don't write to the stencil buffer
+        (_, PassKind::Transparent) => LayerShaderOutputKind::LayerDiscard,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{Mat4, Vec2, Vec4, vec2, vec3, vec4};
+    use image::{Rgba, RgbaImage};
+    use shin_core::primitives::color::{FloatColor4, UnormColor};
+    use shin_render::{
+        PassKind, RenderRequestBuilder,
+        render_pass::RenderPass,
+        shaders::types::{buffer::Buffer, vertices::PosTexVertex},
+    };
+    use winit::dpi::PhysicalSize;
+
+    use super::{Sprite, canvas_region_vertices, quad_vertices, texture_region_vertex};
+    use crate::render::{
+        centered_projection_matrix, test_utils::TestRenderer, top_left_projection_matrix,
+    };
+
+    fn unpack(vertices: [PosTexVertex; 4]) -> [(Vec2, Vec2); 4] {
+        vertices.map(
+            |PosTexVertex {
+                 position,
+                 texture_position,
+             }| (position, texture_position),
+        )
+    }
+
+    #[test]
+    fn offset_rect() {
+        assert_eq!(unpack(quad_vertices(vec4(-100.0, 50.0, 200.0, 20.0))), [
+            (vec2(-100.0, 50.0), vec2(0.0, 0.0)),
+            (vec2(100.0, 50.0), vec2(1.0, 0.0)),
+            (vec2(-100.0, 70.0), vec2(0.0, 1.0)),
+            (vec2(100.0, 70.0), vec2(1.0, 1.0)),
+        ]);
+    }
+
+    fn assert_texture_positions(vertices: [PosTexVertex; 4], expected: [Vec2; 4]) {
+        for ((_, actual), expected) in unpack(vertices).into_iter().zip(expected) {
+            assert!(
+                actual.abs_diff_eq(expected, 1e-6),
+                "{} != {}",
+                actual,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn canvas_region_covering_the_canvas() {
+        let area = vec4(-960.0, -540.0, 1920.0, 1080.0);
+        let vertices = canvas_region_vertices(area, centered_projection_matrix());
+
+        assert_eq!(
+            unpack(vertices).map(|(position, _)| position),
+            unpack(quad_vertices(area)).map(|(position, _)| position)
+        );
+        assert_texture_positions(vertices, [
+            vec2(0.0, 0.0),
+            vec2(1.0, 0.0),
+            vec2(0.0, 1.0),
+            vec2(1.0, 1.0),
+        ]);
+    }
+
+    #[test]
+    fn canvas_region_clips_texture() {
+        // the middle quarter of the screen, shifted by a layer translation
+        let transform = centered_projection_matrix() * Mat4::from_translation(vec3(96.0, 0.0, 0.0));
+        let vertices = canvas_region_vertices(vec4(-480.0, -270.0, 960.0, 540.0), transform);
+
+        // only the pixels under the clip area are sampled (and written), at their own screen position
+        assert_texture_positions(vertices, [
+            vec2(0.3, 0.25),
+            vec2(0.8, 0.25),
+            vec2(0.3, 0.75),
+            vec2(0.8, 0.75),
+        ]);
+    }
+
+    #[test]
+    fn texture_region() {
+        let rect = vec4(-16.0, -8.0, 32.0, 16.0);
+        let region = vec4(0.25, 0.5, 0.75, 0.625);
+        let vertices = quad_vertices(rect).map(|vertex| texture_region_vertex(vertex, region));
+
+        // the positions are kept, only the sampled part of the texture changes
+        assert_eq!(
+            unpack(vertices).map(|(position, _)| position),
+            unpack(quad_vertices(rect)).map(|(position, _)| position)
+        );
+        assert_texture_positions(vertices, [
+            vec2(0.25, 0.5),
+            vec2(0.75, 0.5),
+            vec2(0.25, 0.625),
+            vec2(0.75, 0.625),
+        ]);
+    }
+
+    #[test]
+    fn pixel_snap_moves_the_whole_sprite() {
+        let rect = vec4(-32.0, -16.0, 64.0, 32.0);
+        let transform =
+            centered_projection_matrix() * Mat4::from_translation(vec3(100.3, -20.7, 0.0));
+        let sprite = Sprite::solid(FloatColor4::WHITE, rect, transform)
+            .with_pixel_snap(vec2(1920.0, 1080.0));
+
+        // the vertices are kept, so the sprite doesn't get stretched by a pixel
+        let super::Geometry::Quad(vertices) = sprite.geometry else {
+            panic!("Not a quad");
+        };
+        assert_eq!(unpack(vertices), unpack(quad_vertices(rect)));
+        // while its origin is moved to the closest pixel corner
+        let origin = sprite.transform.w_axis.truncate().truncate();
+        let snapped = (centered_projection_matrix()
+            * Mat4::from_translation(vec3(100.0, -21.0, 0.0)))
+        .w_axis
+        .truncate()
+        .truncate();
+        assert!(
+            origin.abs_diff_eq(snapped, 1e-6),
+            "{} != {}",
+            origin,
+            snapped
+        );
+    }
+
+    /// 10 virtual pixels per pixel
+    fn renderer() -> TestRenderer {
+        TestRenderer::new(PhysicalSize::new(192, 108))
+    }
+
+    /// Draw over a white canvas
+    fn render_over_white(
+        renderer: &mut TestRenderer,
+        draw: impl FnOnce(&mut RenderPass),
+    ) -> RgbaImage {
+        let mut target = renderer.new_render_texture();
+        renderer.pre_render(|context| {
+            let mut pass = context.begin_pass(target.as_texture_target(), None, "sprite");
+            pass.clear(Some(UnormColor::WHITE), None, None);
+            draw(&mut pass);
+        });
+
+        renderer.read(&target)
+    }
+
+    fn assert_rgb(image: &RgbaImage, (x, y): (u32, u32), expected: [u8; 3]) {
+        let actual = image.get_pixel(x, y).0;
+        let close = actual
+            .iter()
+            .zip(expected)
+            .all(|(&actual, expected)| actual.abs_diff(expected) <= 2);
+        assert!(close, "{:?} != {:?} at {:?}", actual, expected, (x, y));
+    }
+
+    /// The left half of the canvas
+    const LEFT_HALF: Vec4 = vec4(0.0, 0.0, 960.0, 1080.0);
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn textured_sprite() {
+        let mut renderer = renderer();
+        // premultiplied half-transparent red, then opaque blue
+        let image = RgbaImage::from_fn(2, 1, |x, _| match x {
+            0 => Rgba([128, 0, 0, 128]),
+            _ => Rgba([0, 0, 255, 255]),
+        });
+        let texture = renderer.upload(&image);
+        let sprite = Sprite::new(texture.as_source(), LEFT_HALF, top_left_projection_matrix());
+
+        let image = render_over_white(&mut renderer, |pass| {
+            sprite.render(pass, RenderRequestBuilder::new(), PassKind::Transparent)
+        });
+        assert_rgb(&image, (24, 54), [255, 127, 127]);
+        assert_rgb(&image, (72, 54), [0, 0, 255]);
+        assert_rgb(&image, (150, 54), [255, 255, 255]);
+
+        // no blending in the opaque pass
+        let image = render_over_white(&mut renderer, |pass| {
+            sprite.render(pass, RenderRequestBuilder::new(), PassKind::Opaque)
+        });
+        assert_rgb(&image, (24, 54), [128, 0, 0]);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn mesh_with_straight_alpha() {
+        let mut renderer = renderer();
+        let texture = renderer.upload(&RgbaImage::from_pixel(1, 1, Rgba([255, 0, 0, 128])));
+        // the left and the right halves of the canvas
+        let vertices = [
+            quad_vertices(LEFT_HALF),
+            quad_vertices(LEFT_HALF + vec4(960.0, 0.0, 0.0, 0.0)),
+        ]
+        .concat();
+        let indices = [0, 1, 2, 3, 2, 1, 4, 5, 6, 7, 6, 5];
+        let vertices = Buffer::allocate_vertex(&renderer.device, &vertices, Some("mesh/vertex"));
+        let indices = Buffer::allocate_index(&renderer.device, &indices, Some("mesh/index"));
+
+        // only the second quad
+        let sprite = Sprite::mesh(
+            texture.as_source(),
+            &vertices,
+            &indices,
+            (6, 6),
+            top_left_projection_matrix(),
+        );
+        let image = render_over_white(&mut renderer, |pass| {
+            sprite.render(pass, RenderRequestBuilder::new(), PassKind::Transparent)
+        });
+
+        assert_rgb(&image, (48, 54), [255, 255, 255]);
+        // premultiplied in the shader before blending
+        assert_rgb(&image, (144, 54), [255, 127, 127]);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn solid_sprite() {
+        let mut renderer = renderer();
+        let blue = FloatColor4::from_rgba(0.0, 0.0, 1.0, 0.5);
+        let sprite = Sprite::solid(blue, LEFT_HALF, top_left_projection_matrix());
+
+        let image = render_over_white(&mut renderer, |pass| {
+            sprite.render(pass, RenderRequestBuilder::new(), PassKind::Transparent)
+        });
+        assert_rgb(&image, (48, 54), [127, 127, 255]);
+        assert_rgb(&image, (144, 54), [255, 255, 255]);
+
+        // no blending in the opaque pass
+        let image = render_over_white(&mut renderer, |pass| {
+            sprite.render(pass, RenderRequestBuilder::new(), PassKind::Opaque)
+        });
+        assert_rgb(&image, (48, 54), [0, 0, 255]);
+    }
+}
@@ -0,0 +1,191 @@
+//! Rendering on a real GPU in the tests, with the results read back to the CPU.
+//!
+//! The tests using it need a GPU adapter, so they are marked `#[ignore = "needs a GPU adapter"]`.
+
+use std::{
+    pin::pin,
+    sync::{Arc, Once},
+    task::{Context, Poll, Waker},
+};
+
+use image::RgbaImage;
+use shin_core::primitives::color::UnormColor;
+use shin_render::{
+    PassKind, RenderRequestBuilder, TEXTURE_FORMAT,
+    depth_stencil::DepthStencil,
+    dynamic_buffer::DynamicBuffer,
+    gpu_texture::GpuTexture,
+    pipelines::PipelineStorage,
+    render_texture::RenderTexture,
+    resize::{SurfaceResizeSource, ViewportParams},
+    shaders::types::{buffer::BytesAddress, texture::TextureSamplerStore},
+};
+use winit::dpi::PhysicalSize;
+
+use crate::{
+    asset::system::{AssetLoadContext, AssetServer, LayeredAssetIo, cache::AssetCache},
+    layer::{Layer, render_layer_without_bg, render_params::TransformParams},
+    render::{PreRenderContext, sprite::Sprite},
+};
+
+/// wgpu resolves its futures on native without any waiting, as long as the device was polled
+pub fn now_or_never<F: Future>(future: F) -> Option<F::Output> {
+    match pin!(future).poll(&mut Context::from_waker(Waker::noop())) {
+        Poll::Ready(output) => Some(output),
+        Poll::Pending => None,
+    }
+}
+
+/// Panics when there is no GPU adapter, which is why the tests rendering anything are `#[ignore]`d
+pub fn request_device() -> (wgpu::Device, wgpu::Queue) {
+    let instance = wgpu::Instance::default();
+    let adapter = now_or_never(instance.request_adapter(&Default::default()))
+        .flatten()
+        .expect("No GPU adapter available");
+
+    now_or_never(adapter.request_device(&Default::default(), None))
+        .expect("Requesting a device is not immediate")
+        .unwrap()
+}
+
+/// The task pools are global, so they are created by whichever test needs them first
+pub fn create_task_pools() {
+    static TASK_POOLS: Once = Once::new();
+    TASK_POOLS.call_once(shin_tasks::create_task_pools);
+}
+
+/// Everything needed to render offscreen, like the window does for the screen.
+///
+/// The canvas has a size of its own, the projection matrices still map the virtual canvas onto it.
+pub struct TestRenderer {
+    pub device: wgpu::Device,
+    pub queue: wgpu::Queue,
+    resize_source: SurfaceResizeSource,
+    sampler_store: TextureSamplerStore,
+    pipeline_storage: PipelineStorage,
+    dynamic_buffer: DynamicBuffer,
+    depth_stencil: DepthStencil,
+}
+
+impl TestRenderer {
+    pub fn new(canvas_size: PhysicalSize<u32>) -> Self {
+        let (device, queue) = request_device();
+        let resize_source = SurfaceResizeSource::new(ViewportParams::both(canvas_size));
+
+        Self {
+            sampler_store: TextureSamplerStore::new(&device),
+            pipeline_storage: PipelineStorage::new(device.clone(), TEXTURE_FORMAT),
+            dynamic_buffer: DynamicBuffer::new(device.clone(), BytesAddress::new(1024 * 1024)),
+            depth_stencil: DepthStencil::new(
+                device.clone(),
+                resize_source.canvas_handle(),
+                "TestRenderer/depth_stencil".to_string(),
+            ),
+            resize_source,
+            device,
+            queue,
+        }
+    }
+
+    /// A canvas-sized texture, starting out transparent black
+    pub fn new_render_texture(&self) -> RenderTexture {
+        RenderTexture::new(
+            self.device.clone(),
+            self.resize_source.canvas_handle(),
+            "TestRenderer/render_texture".to_string(),
+        )
+    }
+
+    /// Run `f` with the same context the layers get in [`Layer::pre_render`](crate::layer::Layer::pre_render), submitting everything it encoded
+    pub fn pre_render<R>(&mut self, f: impl FnOnce(&mut PreRenderContext) -> R) -> R {
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+
+        let result = f(&mut PreRenderContext {
+            device: &self.device,
+            queue: &self.queue,
+            resize_source: &self.resize_source,
+            sampler_store: &self.sampler_store,
+            depth_stencil: self.depth_stencil.get_target_view(),
+            pipeline_storage: &mut self.pipeline_storage,
+            dynamic_buffer: &mut self.dynamic_buffer,
+            encoder: &mut encoder,
+        });
+
+        // the dynamic buffer uploads have to land before the passes using them
+        let mut dynamic_buffer_encoder = self.device.create_command_encoder(&Default::default());
+        self.dynamic_buffer.finish(&mut dynamic_buffer_encoder);
+        self.queue
+            .submit([dynamic_buffer_encoder.finish(), encoder.finish()]);
+        self.dynamic_buffer.recall();
+
+        result
+    }
+
+    /// An asset server loading from `io`, with the GPU assets living on this renderer's device
+    pub fn asset_server(&self, io: LayeredAssetIo) -> Arc<AssetServer> {
+        Arc::new(AssetServer::new(io.into(), AssetLoadContext {
+            wgpu_device: self.device.clone(),
+            wgpu_queue: self.queue.clone(),
+            bustup_cache: AssetCache::new(),
+        }))
+    }
+
+    /// Render the `layer` on its own onto a new canvas-sized texture, the way a layer group renders its layers
+    pub fn render_layer(&mut self, layer: &mut dyn Layer) -> RgbaImage {
+        let mut target = self.new_render_texture();
+
+        self.pre_render(|context| {
+            let transform = TransformParams::default();
+            layer.pre_render(context, &transform);
+
+            let depth_stencil = context.depth_stencil;
+            let mut pass = context.begin_pass(
+                target.as_texture_target(),
+                Some(depth_stencil),
+                "TestRenderer/render_layer",
+            );
+            pass.clear(Some(UnormColor(0)), Some(0), None);
+            render_layer_without_bg(&mut pass, &transform, layer, 0);
+        });
+
+        self.read(&target)
+    }
+
+    pub fn upload(&self, image: &RgbaImage) -> GpuTexture {
+        GpuTexture::new_static_from_rgba_image(
+            &self.device,
+            &self.queue,
+            Some("TestRenderer/texture"),
+            image,
+        )
+    }
+
+    /// Stretch `image` over a new canvas-sized texture, as the source for the effect passes
+    pub fn render_texture_from_image(&mut self, image: &RgbaImage) -> RenderTexture {
+        let texture = self.upload(image);
+        let mut target = self.new_render_texture();
+
+        self.pre_render(|context| {
+            let mut pass = context.begin_pass(
+                target.as_texture_target(),
+                None,
+                "TestRenderer/render_texture_from_image",
+            );
+            Sprite::full_canvas(texture.as_source()).render(
+                &mut pass,
+                RenderRequestBuilder::new(),
+                PassKind::Opaque,
+            );
+        });
+
+        target
+    }
+
+    pub fn read(&self, texture: &RenderTexture) -> RgbaImage {
+        let readback = texture.read_to_cpu(&self.device, &self.queue);
+        self.device.poll(wgpu::Maintain::Wait);
+        now_or_never(readback)
+            .expect("The readback is not done after waiting for the device")
+            .unwrap()
+    }
+}
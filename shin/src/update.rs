@@ -2,7 +2,7 @@ use std::sync::Arc;
 
 use shin_core::{primitives::update::FrameId, time::Ticks};
 
-use crate::{asset::system::AssetServer, render::PreRenderContext};
+use crate::{asset::system::AssetServer, audio::Lipsync, render::PreRenderContext};
 
 pub struct UpdateContext<'immutable, 'pre_render, 'pipelines, 'dynbuffer, 'encoder> {
     pub frame_id: FrameId,
@@ -22,6 +22,12 @@ pub struct AdvUpdateContext<'a> {
     pub queue: &'a wgpu::Queue,
 
     pub are_animations_allowed: bool,
+    /// Dampen the layer effects involving motion, for the players sensitive to it
+    pub reduced_motion: bool,
+    /// Fade the translucent layer groups as a whole, see [`LayerGroup`](crate::layer::LayerGroup)
+    pub group_opacity: bool,
+    /// The voice being played, for the bustups to move their lips
+    pub lipsync: &'a Lipsync,
 }
 
 pub trait Updatable {
@@ -1,9 +1,9 @@
+use glam::Mat4;
 use shin_core::time::Ticks;
 use shin_render::{
     ColorBlendType, DrawPrimitive, RenderProgramWithArguments, RenderRequestBuilder,
-    quad_vertices::build_quad_vertices,
     render_pass::RenderPass,
-    shaders::types::{buffer::VertexSource, texture::TextureSource, vertices::PosTexVertex},
+    shaders::types::{buffer::VertexSource, texture::TextureSource},
 };
 
 use crate::{
@@ -19,6 +19,37 @@ impl AdvUpdatable for DefaultWiperImpl {
     fn update(&mut self, _context: &AdvUpdateContext) {}
 }
 
+/// Draws a blend of the two textures, `alpha` being the amount of `texture_target`
+///
+/// Passing the same texture twice draws just that texture, which is what the wipers moving the pages around use.
+pub(super) fn render_crossfade(
+    pass: &mut RenderPass,
+    render_request_builder: RenderRequestBuilder,
+    texture_target: TextureSource,
+    texture_source: TextureSource,
+    transform: Mat4,
+    alpha: f32,
+) {
+    let quad = pass.geometry_cache().unit_quad();
+
+    pass.run(
+        render_request_builder
+            .color_blend_type(ColorBlendType::Opaque)
+            .build(
+                RenderProgramWithArguments::WiperDefault {
+                    vertices: VertexSource::VertexBuffer {
+                        vertices: quad.as_buffer_ref(),
+                    },
+                    texture_source,
+                    texture_target,
+                    transform,
+                    alpha,
+                },
+                DrawPrimitive::TrianglesStrip,
+            ),
+    );
+}
+
 impl TimedWiper for DefaultWiperImpl {
     fn render(
         &self,
@@ -28,26 +59,13 @@ impl TimedWiper for DefaultWiperImpl {
         texture_source: TextureSource,
         progress: f32,
     ) {
-        let transform = normalized_projection_matrix();
-
-        let vertices = &build_quad_vertices(|t| PosTexVertex {
-            position: t,
-            texture_position: t,
-        });
-
-        pass.run(
-            render_request_builder
-                .color_blend_type(ColorBlendType::Opaque)
-                .build(
-                    RenderProgramWithArguments::WiperDefault {
-                        vertices: VertexSource::VertexData { vertices },
-                        texture_source,
-                        texture_target,
-                        transform,
-                        alpha: progress,
-                    },
-                    DrawPrimitive::TrianglesStrip,
-                ),
+        render_crossfade(
+            pass,
+            render_request_builder,
+            texture_target,
+            texture_source,
+            normalized_projection_matrix(),
+            progress,
         );
     }
 }
@@ -59,3 +77,32 @@ impl DefaultWiper {
         Self::from_inner(DefaultWiperImpl, duration)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use winit::dpi::PhysicalSize;
+
+    use super::DefaultWiperImpl;
+    use crate::{
+        render::test_utils::TestRenderer,
+        wiper::test_utils::{SOURCE, TARGET, render_wipe},
+    };
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn crossfade_midpoint() {
+        let mut renderer = TestRenderer::new(PhysicalSize::new(16, 9));
+        let mut color =
+            |progress| *render_wipe(&mut renderer, &DefaultWiperImpl, progress).get_pixel(8, 4);
+
+        assert_eq!(color(0.0).0, SOURCE);
+        assert_eq!(color(1.0).0, TARGET);
+        // blended in linear space, so the half of both colors is brighter than 128 in sRGB
+        let [r, g, b, a] = color(0.5).0;
+        assert!(
+            r.abs_diff(188) <= 2 && g == 0 && b.abs_diff(188) <= 2 && a == 255,
+            "{:?}",
+            [r, g, b, a]
+        );
+    }
+}
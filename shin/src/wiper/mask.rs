@@ -1,6 +1,6 @@
 use std::{fmt::Debug, sync::Arc};
 
-use glam::vec2;
+use glam::{Vec2, vec2};
 use shin_core::{
     time::Ticks,
     vm::command::types::{MaskFlags, MaskParam},
@@ -18,6 +18,21 @@ use crate::{
     wiper::timed::{TimedWiper, TimedWiperWrapper},
 };
 
+/// The range the mask values are mapped to by the shader, before being clamped to the blend factor
+///
+/// `param2` is the softness of the edge: at 1.0 the whole mask range blends at once, while at smaller values only a narrow band of the mask values is mid-transition.
+fn mask_minmax(progress: f32, param2: MaskParam, flags: MaskFlags) -> Vec2 {
+    let inv_param2 = 1.0 / param2.0;
+    let mut min = 1.0 - progress * (inv_param2 + 1.0);
+    let mut max = min + inv_param2;
+
+    if flags.contains(MaskFlags::INVERT) {
+        std::mem::swap(&mut min, &mut max);
+    }
+
+    vec2(min, max)
+}
+
 #[derive(Clone)]
 pub struct MaskWiperImpl {
     mask: Arc<MaskTexture>,
@@ -48,13 +63,7 @@ impl TimedWiper for MaskWiperImpl {
         texture_source: TextureSource,
         progress: f32,
     ) {
-        let inv_param2 = 1.0 / self.param2.0;
-        let mut min = 1.0 - progress * (inv_param2 + 1.0);
-        let mut max = min + inv_param2;
-
-        if self.flags.contains(MaskFlags::INVERT) {
-            std::mem::swap(&mut min, &mut max);
-        }
+        let minmax = mask_minmax(progress, self.param2, self.flags);
 
         let mask_size = self.mask.texture.size_vec();
 
@@ -108,7 +117,7 @@ impl TimedWiper for MaskWiperImpl {
                         texture_target,
                         texture_mask: self.mask.texture.as_source(),
                         transform,
-                        minmax: vec2(min, max),
+                        minmax,
                     },
                     DrawPrimitive::TrianglesStrip,
                 ),
@@ -135,3 +144,186 @@ impl MaskWiper {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use glam::Vec2;
+    use image::{GrayImage, Luma};
+    use shin_core::{
+        format::{
+            mask::{MaskRect, MaskRegionInfo, RegionData},
+            scenario::instruction_elements::FromNumber,
+        },
+        vm::command::types::{MaskFlags, MaskParam},
+    };
+    use winit::dpi::PhysicalSize;
+
+    use super::{MaskWiperImpl, mask_minmax};
+    use crate::{
+        asset::{
+            mask::MaskTexture,
+            system::{AssetLoadContext, cache::AssetCache},
+        },
+        render::test_utils::TestRenderer,
+        wiper::test_utils::{SOURCE, TARGET, render_wipe},
+    };
+
+    /// Mirrors the shader, computing how much of the target texture is shown for a mask value
+    fn target_amount(mask_value: f32, minmax: Vec2) -> f32 {
+        let mapped = minmax.x + mask_value * (minmax.y - minmax.x);
+        1.0 - mapped.clamp(0.0, 1.0)
+    }
+
+    fn mask_values() -> impl Iterator<Item = f32> {
+        (0..=10).map(|i| i as f32 / 10.0)
+    }
+
+    #[test]
+    fn extremes_hide_and_reveal_fully() {
+        // the softest and the sharpest edges
+        for param2 in [MaskParam::from_number(0), MaskParam::from_number(1)] {
+            for flags in [MaskFlags::empty(), MaskFlags::INVERT] {
+                let start = mask_minmax(0.0, param2, flags);
+                let end = mask_minmax(1.0, param2, flags);
+
+                for mask_value in mask_values() {
+                    // less than what would show up in an 8-bit color
+                    assert!(
+                        target_amount(mask_value, start) < 1e-3,
+                        "{:?} {:?} {}",
+                        param2,
+                        flags,
+                        mask_value
+                    );
+                    assert!(
+                        target_amount(mask_value, end) > 1.0 - 1e-3,
+                        "{:?} {:?} {}",
+                        param2,
+                        flags,
+                        mask_value
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn invert_reverses_the_order() {
+        let param2 = MaskParam::from_number(100);
+        let progress = 0.5;
+
+        let normal = mask_minmax(progress, param2, MaskFlags::empty());
+        let inverted = mask_minmax(progress, param2, MaskFlags::INVERT);
+
+        // the dark parts of the mask are revealed first, unless inverted
+        assert_eq!(target_amount(0.0, normal), 1.0);
+        assert_eq!(target_amount(1.0, normal), 0.0);
+        assert_eq!(target_amount(0.0, inverted), 0.0);
+        assert_eq!(target_amount(1.0, inverted), 1.0);
+    }
+
+    /// A mask black on the left half and white on the right one
+    fn half_mask(renderer: &TestRenderer) -> Arc<MaskTexture> {
+        let context = AssetLoadContext {
+            wgpu_device: renderer.device.clone(),
+            wgpu_queue: renderer.queue.clone(),
+            bustup_cache: AssetCache::new(),
+        };
+        let region = |rect_count| MaskRegionInfo {
+            rect_count,
+            region_area: 0,
+        };
+        let mask = shin_core::format::mask::MaskTexture {
+            id: 0,
+            regions: RegionData {
+                black_regions: region(1),
+                white_regions: region(0),
+                transparent_regions: region(0),
+                rects: vec![MaskRect {
+                    from_x: 0,
+                    from_y: 0,
+                    to_x: 7,
+                    to_y: 0,
+                }],
+            },
+            texels: GrayImage::from_fn(16, 1, |x, _| Luma([if x < 8 { 0 } else { 255 }])),
+        };
+
+        Arc::new(MaskTexture::new(&context, &mask, "half".to_string()))
+    }
+
+    /// Render the wipe at `progress`, returning the colors at the left and at the right of the screen
+    fn render_mask_wipe(
+        renderer: &mut TestRenderer,
+        param2: MaskParam,
+        flags: MaskFlags,
+        progress: f32,
+    ) -> [[u8; 4]; 2] {
+        let wiper = MaskWiperImpl {
+            mask: half_mask(renderer),
+            param2,
+            flags,
+        };
+        let image = render_wipe(renderer, &wiper, progress);
+
+        [image.get_pixel(8, 18).0, image.get_pixel(56, 18).0]
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn sharp_wipe_reveals_the_dark_parts_first() {
+        let mut renderer = TestRenderer::new(PhysicalSize::new(64, 36));
+        let sharp = MaskParam::from_number(1);
+
+        assert_eq!(
+            render_mask_wipe(&mut renderer, sharp, MaskFlags::empty(), 0.0),
+            [SOURCE, SOURCE]
+        );
+        assert_eq!(
+            render_mask_wipe(&mut renderer, sharp, MaskFlags::empty(), 0.5),
+            [TARGET, SOURCE]
+        );
+        assert_eq!(
+            render_mask_wipe(&mut renderer, sharp, MaskFlags::empty(), 1.0),
+            [TARGET, TARGET]
+        );
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn inverted_and_flipped_wipes() {
+        let mut renderer = TestRenderer::new(PhysicalSize::new(64, 36));
+        let sharp = MaskParam::from_number(1);
+
+        assert_eq!(
+            render_mask_wipe(&mut renderer, sharp, MaskFlags::INVERT, 0.5),
+            [SOURCE, TARGET]
+        );
+        assert_eq!(
+            render_mask_wipe(&mut renderer, sharp, MaskFlags::FLIP_X, 0.5),
+            [SOURCE, TARGET]
+        );
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn soft_wipe_blends_the_whole_screen() {
+        let mut renderer = TestRenderer::new(PhysicalSize::new(64, 36));
+
+        // at the softest edge, a quarter of the way in, the black half is halfway through and the white one has not started yet
+        let [left, right] = render_mask_wipe(
+            &mut renderer,
+            MaskParam::from_number(0),
+            MaskFlags::empty(),
+            0.25,
+        );
+        // blended in linear space, so the half of both colors is brighter than 128 in sRGB
+        assert!(
+            left[0].abs_diff(188) <= 2 && left[2].abs_diff(188) <= 2,
+            "{left:?}"
+        );
+        assert_eq!(right, SOURCE);
+    }
+}
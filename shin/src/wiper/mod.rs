@@ -1,6 +1,10 @@
 mod default;
 mod mask;
+mod scroll;
+#[cfg(test)]
+mod test_utils;
 mod timed;
+mod zoom;
 
 use from_variants::FromVariants;
 use shin_core::{
@@ -16,12 +20,17 @@ use shin_derive::RenderClone;
 use shin_render::{
     render_pass::RenderPass, shaders::types::texture::TextureSource, RenderRequestBuilder,
 };
+use tracing::warn;
 
 pub use self::default::DefaultWiper;
 use crate::{
     asset::{mask::MaskTexture, system::AssetServer},
     update::{AdvUpdatable, AdvUpdateContext},
-    wiper::mask::MaskWiper,
+    wiper::{
+        mask::MaskWiper,
+        scroll::{ScrollDirection, ScrollWiper},
+        zoom::ZoomWiper,
+    },
 };
 
 pub trait Wiper: AdvUpdatable {
@@ -40,6 +49,8 @@ pub trait Wiper: AdvUpdatable {
 pub enum AnyWiper {
     Default(DefaultWiper),
     Mask(MaskWiper),
+    Scroll(ScrollWiper),
+    Zoom(ZoomWiper),
 }
 
 impl AnyWiper {
@@ -71,8 +82,22 @@ impl AnyWiper {
 
                 AnyWiper::Mask(MaskWiper::new(duration, mask, param2, flags))
             }
+            WiperType::Scroll => {
+                let (milliturns, _, _, _, _, _, _, _): TypedNumberArray<i32> =
+                    lower_number_array(params);
+
+                AnyWiper::Scroll(ScrollWiper::new(
+                    duration,
+                    ScrollDirection::from_param(milliturns),
+                ))
+            }
+            WiperType::Zoom => AnyWiper::Zoom(ZoomWiper::new(duration)),
             ty => {
-                todo!("unimplemented wiper {:?} {:?} {:?}", ty, duration, params)
+                warn!(
+                    "Unimplemented wiper {:?} (params {:?}), falling back to the default one",
+                    ty, params
+                );
+                AnyWiper::Default(DefaultWiper::new(duration))
             }
         }
     }
@@ -83,6 +108,8 @@ impl AdvUpdatable for AnyWiper {
         match self {
             AnyWiper::Default(wiper) => wiper.update(context),
             AnyWiper::Mask(wiper) => wiper.update(context),
+            AnyWiper::Scroll(wiper) => wiper.update(context),
+            AnyWiper::Zoom(wiper) => wiper.update(context),
         }
     }
 }
@@ -92,6 +119,8 @@ impl Wiper for AnyWiper {
         match self {
             AnyWiper::Default(wiper) => wiper.is_running(),
             AnyWiper::Mask(wiper) => wiper.is_running(),
+            AnyWiper::Scroll(wiper) => wiper.is_running(),
+            AnyWiper::Zoom(wiper) => wiper.is_running(),
         }
     }
 
@@ -99,6 +128,8 @@ impl Wiper for AnyWiper {
         match self {
             AnyWiper::Default(wiper) => wiper.fast_forward(),
             AnyWiper::Mask(wiper) => wiper.fast_forward(),
+            AnyWiper::Scroll(wiper) => wiper.fast_forward(),
+            AnyWiper::Zoom(wiper) => wiper.fast_forward(),
         }
     }
 
@@ -116,6 +147,12 @@ impl Wiper for AnyWiper {
             AnyWiper::Mask(wiper) => {
                 wiper.render(pass, render_request_builder, from_texture, to_texture)
             }
+            AnyWiper::Scroll(wiper) => {
+                wiper.render(pass, render_request_builder, from_texture, to_texture)
+            }
+            AnyWiper::Zoom(wiper) => {
+                wiper.render(pass, render_request_builder, from_texture, to_texture)
+            }
         }
     }
 }
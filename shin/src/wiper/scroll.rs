@@ -0,0 +1,156 @@
+use std::f32::consts::TAU;
+
+use glam::{Mat4, Vec2};
+use shin_core::time::Ticks;
+use shin_render::{
+    RenderRequestBuilder, render_pass::RenderPass, shaders::types::texture::TextureSource,
+};
+
+use crate::{
+    render::normalized_projection_matrix,
+    update::{AdvUpdatable, AdvUpdateContext},
+    wiper::{
+        default::render_crossfade,
+        timed::{TimedWiper, TimedWiperWrapper},
+    },
+};
+
+/// The direction both pages move in, as a unit vector on the canvas
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ScrollDirection(Vec2);
+
+impl ScrollDirection {
+    /// The direction is passed to WIPE as an angle in milliturns, like the layer rotation, going clockwise from the right
+    pub fn from_param(milliturns: i32) -> Self {
+        let angle = milliturns.rem_euclid(1000) as f32 * 0.001 * TAU;
+
+        Self(Vec2::from_angle(angle))
+    }
+
+    fn vector(self) -> Vec2 {
+        self.0
+    }
+}
+
+/// Offsets of the (source, target) pages, relative to the canvas size
+///
+/// The source page leaves the canvas while the target one comes in right behind it.
+fn scroll_offsets(direction: ScrollDirection, progress: f32) -> (Vec2, Vec2) {
+    let vector = direction.vector();
+
+    (vector * progress, vector * (progress - 1.0))
+}
+
+#[derive(Debug, Clone)]
+pub struct ScrollWiperImpl {
+    direction: ScrollDirection,
+}
+
+impl AdvUpdatable for ScrollWiperImpl {
+    fn update(&mut self, _context: &AdvUpdateContext) {}
+}
+
+impl TimedWiper for ScrollWiperImpl {
+    fn render(
+        &self,
+        pass: &mut RenderPass,
+        render_request_builder: RenderRequestBuilder,
+        texture_target: TextureSource,
+        texture_source: TextureSource,
+        progress: f32,
+    ) {
+        let (source_offset, target_offset) = scroll_offsets(self.direction, progress);
+
+        for (texture, offset) in [
+            (texture_source, source_offset),
+            (texture_target, target_offset),
+        ] {
+            render_crossfade(
+                pass,
+                render_request_builder,
+                texture,
+                texture,
+                normalized_projection_matrix() * Mat4::from_translation(offset.extend(0.0)),
+                0.0,
+            );
+        }
+    }
+}
+
+pub type ScrollWiper = TimedWiperWrapper<ScrollWiperImpl>;
+
+impl ScrollWiper {
+    pub fn new(duration: Ticks, direction: ScrollDirection) -> Self {
+        Self::from_inner(ScrollWiperImpl { direction }, duration)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{Vec2, vec2};
+    use winit::dpi::PhysicalSize;
+
+    use super::{ScrollDirection, ScrollWiperImpl, scroll_offsets};
+    use crate::{
+        render::test_utils::TestRenderer,
+        wiper::test_utils::{SOURCE, TARGET, render_wipe},
+    };
+
+    fn assert_near(actual: Vec2, expected: Vec2) {
+        assert!(
+            actual.abs_diff_eq(expected, 1e-6),
+            "{} vs {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn direction_from_milliturns() {
+        let directions = [0, 250, 500, 750, 1000, -250]
+            .map(|milliturns| ScrollDirection::from_param(milliturns).vector());
+        let expected = [
+            vec2(1.0, 0.0),
+            vec2(0.0, 1.0),
+            vec2(-1.0, 0.0),
+            vec2(0.0, -1.0),
+            vec2(1.0, 0.0),
+            vec2(0.0, -1.0),
+        ];
+        for (actual, expected) in directions.into_iter().zip(expected) {
+            assert_near(actual, expected);
+        }
+    }
+
+    #[test]
+    fn pages_move_together() {
+        let left = ScrollDirection::from_param(500);
+        let down = ScrollDirection::from_param(250);
+
+        let (source, target) = scroll_offsets(left, 0.0);
+        assert_near(source, Vec2::ZERO);
+        assert_near(target, vec2(1.0, 0.0));
+
+        let (source, target) = scroll_offsets(left, 0.25);
+        assert_near(source, vec2(-0.25, 0.0));
+        assert_near(target, vec2(0.75, 0.0));
+
+        let (source, target) = scroll_offsets(down, 1.0);
+        assert_near(source, vec2(0.0, 1.0));
+        assert_near(target, Vec2::ZERO);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn scroll_right_halfway() {
+        let mut renderer = TestRenderer::new(PhysicalSize::new(16, 9));
+        let wiper = ScrollWiperImpl {
+            direction: ScrollDirection::from_param(0),
+        };
+
+        // the source page has moved halfway to the right, with the target one coming in from the left
+        let image = render_wipe(&mut renderer, &wiper, 0.5);
+        assert_eq!(image.get_pixel(4, 4).0, TARGET);
+        assert_eq!(image.get_pixel(12, 4).0, SOURCE);
+    }
+}
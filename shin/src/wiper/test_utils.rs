@@ -0,0 +1,35 @@
+//! Rendering the wipers on a real GPU, between two flat pages.
+
+use image::{Rgba, RgbaImage};
+use shin_render::RenderRequestBuilder;
+
+use crate::{render::test_utils::TestRenderer, wiper::timed::TimedWiper};
+
+/// Color of the page the wipe starts from
+pub const SOURCE: [u8; 4] = [255, 0, 0, 255];
+/// Color of the page the wipe ends at
+pub const TARGET: [u8; 4] = [0, 0, 255, 255];
+
+/// Render the wipe from [`SOURCE`] to [`TARGET`] at `progress`
+pub fn render_wipe(
+    renderer: &mut TestRenderer,
+    wiper: &impl TimedWiper,
+    progress: f32,
+) -> RgbaImage {
+    let source = renderer.render_texture_from_image(&RgbaImage::from_pixel(2, 2, Rgba(SOURCE)));
+    let target = renderer.render_texture_from_image(&RgbaImage::from_pixel(2, 2, Rgba(TARGET)));
+    let mut output = renderer.new_render_texture();
+
+    renderer.pre_render(|context| {
+        let mut pass = context.begin_pass(output.as_texture_target(), None, "render_wipe");
+        wiper.render(
+            &mut pass,
+            RenderRequestBuilder::new(),
+            target.as_texture_source(),
+            source.as_texture_source(),
+            progress,
+        );
+    });
+
+    renderer.read(&output)
+}
@@ -103,3 +103,39 @@ impl<T: TimedWiper + AdvUpdatable> Wiper for TimedWiperWrapper<T> {
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use shin_core::time::Ticks;
+
+    use super::TimedWiperState;
+
+    #[test]
+    fn progress_clock() {
+        let mut state = TimedWiperState::new(Ticks::from_u32(60));
+        assert_eq!(state.get_progress(), 0.0);
+        assert!(state.is_running());
+
+        state.update(true, Ticks::from_u32(30));
+        assert_eq!(state.get_progress(), 0.5);
+
+        // the clock is stopped while the animations are blocked
+        state.update(false, Ticks::from_u32(15));
+        assert_eq!(state.get_progress(), 0.5);
+
+        // and doesn't overshoot
+        state.update(true, Ticks::from_u32(45));
+        assert_eq!(state.get_progress(), 1.0);
+        assert!(!state.is_running());
+    }
+
+    #[test]
+    fn fast_forward_finishes() {
+        let mut state = TimedWiperState::new(Ticks::from_u32(60));
+        state.update(true, Ticks::from_u32(10));
+
+        state.fast_forward();
+        assert_eq!(state.get_progress(), 1.0);
+        assert!(!state.is_running());
+    }
+}
@@ -0,0 +1,88 @@
+use glam::{Mat4, vec3};
+use shin_core::time::Ticks;
+use shin_render::{
+    RenderRequestBuilder, render_pass::RenderPass, shaders::types::texture::TextureSource,
+};
+
+use crate::{
+    render::normalized_projection_matrix,
+    update::{AdvUpdatable, AdvUpdateContext},
+    wiper::{
+        default::render_crossfade,
+        timed::{TimedWiper, TimedWiperWrapper},
+    },
+};
+
+/// Scales the unit quad by `progress` around the center of the canvas
+fn zoom_transform(progress: f32) -> Mat4 {
+    let center = vec3(0.5, 0.5, 0.0);
+
+    Mat4::from_translation(center)
+        * Mat4::from_scale(vec3(progress, progress, 1.0))
+        * Mat4::from_translation(-center)
+}
+
+/// Grows the target page from the center of the canvas, over the source one
+#[derive(Debug, Clone)]
+pub struct ZoomWiperImpl;
+
+impl AdvUpdatable for ZoomWiperImpl {
+    fn update(&mut self, _context: &AdvUpdateContext) {}
+}
+
+impl TimedWiper for ZoomWiperImpl {
+    fn render(
+        &self,
+        pass: &mut RenderPass,
+        render_request_builder: RenderRequestBuilder,
+        texture_target: TextureSource,
+        texture_source: TextureSource,
+        progress: f32,
+    ) {
+        render_crossfade(
+            pass,
+            render_request_builder,
+            texture_source,
+            texture_source,
+            normalized_projection_matrix(),
+            0.0,
+        );
+        render_crossfade(
+            pass,
+            render_request_builder,
+            texture_target,
+            texture_target,
+            normalized_projection_matrix() * zoom_transform(progress),
+            0.0,
+        );
+    }
+}
+
+pub type ZoomWiper = TimedWiperWrapper<ZoomWiperImpl>;
+
+impl ZoomWiper {
+    pub fn new(duration: Ticks) -> Self {
+        Self::from_inner(ZoomWiperImpl, duration)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{Vec2, vec2};
+
+    use super::zoom_transform;
+
+    fn zoomed(progress: f32, position: Vec2) -> Vec2 {
+        zoom_transform(progress)
+            .transform_point3(position.extend(0.0))
+            .truncate()
+    }
+
+    #[test]
+    fn target_grows_from_the_center() {
+        assert_eq!(zoomed(0.0, Vec2::ZERO), vec2(0.5, 0.5));
+        assert_eq!(zoomed(0.0, Vec2::ONE), vec2(0.5, 0.5));
+        assert_eq!(zoomed(0.5, Vec2::ZERO), vec2(0.25, 0.25));
+        assert_eq!(zoomed(1.0, Vec2::ONE), Vec2::ONE);
+    }
+}